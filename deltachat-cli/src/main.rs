@@ -0,0 +1,319 @@
+//! Delta Chat command-line client.
+//!
+//! Runs single commands given as arguments,
+//! reads commands from standard input
+//! or runs scripts of commands with `run FILE`.
+//! All commands are implemented on top of the JSON-RPC API,
+//! which is also available directly with `call METHOD [PARAMS]`.
+use std::env;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use deltachat::constants::DC_VERSION_STR;
+use deltachat_jsonrpc::api::{Accounts, CommandApi};
+use deltachat_jsonrpc::yerpc::RpcServer;
+use serde_json::{json, Value};
+use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::RwLock;
+
+const USAGE: &str = "\
+Usage: deltachat-cli [--accounts DIR] [--account ID] [COMMAND [ARGS...]]
+
+Without a command, commands are read from standard input.
+The accounts directory defaults to DC_ACCOUNTS_PATH or `accounts`.
+
+Commands:
+  accounts                    List accounts.
+  add-account                 Add an account and select it.
+  select ID                   Select the account to use.
+  get KEY                     Print a config option.
+  set KEY [VALUE]             Set or reset a config option.
+  configure [ADDR PASSWORD]   Configure the account, optionally setting address and password.
+  start-io                    Start IO of the account.
+  stop-io                     Stop IO of the account.
+  chats [QUERY]               List chats, optionally matching QUERY.
+  chat-with ADDR              Create a chat with ADDR and print its ID.
+  msgs CHAT_ID                List messages of a chat.
+  send CHAT_ID TEXT           Send a text message.
+  export-sanitized-db FILE    Export the database without message texts, keys and addresses
+                              to attach it to bug reports.
+  watch                       Print events as JSON until interrupted.
+  call METHOD [PARAMS]        Call a JSON-RPC method, PARAMS is a JSON array.
+  run FILE                    Run commands from FILE, stopping at the first error.
+  help                        Print this help.
+  quit                        Exit.";
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() {
+    if let Err(err) = main_impl().await {
+        eprintln!("Error: {err:#}");
+        std::process::exit(1);
+    }
+}
+
+async fn main_impl() -> Result<()> {
+    let mut accounts_dir = env::var("DC_ACCOUNTS_PATH").unwrap_or_else(|_| "accounts".to_string());
+    let mut account_id = None;
+    let mut command = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--accounts" => accounts_dir = args.next().context("--accounts requires a value")?,
+            "--account" => {
+                let id = args.next().context("--account requires a value")?;
+                account_id = Some(id.parse().context("invalid account ID")?);
+            }
+            "--version" => {
+                println!("{}", &*DC_VERSION_STR);
+                return Ok(());
+            }
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ => {
+                command.push(arg);
+                command.extend(args.by_ref());
+            }
+        }
+    }
+
+    let accounts = Accounts::new(PathBuf::from(&accounts_dir)).await?;
+    let api = CommandApi::from_arc(Arc::new(RwLock::new(accounts)));
+    let mut cli = Cli { api, account_id };
+
+    if command.is_empty() {
+        cli.run_interactive().await
+    } else {
+        cli.run_line(&command.join(" ")).await.map(|_| ())
+    }
+}
+
+/// Result of running a command.
+enum Flow {
+    Continue,
+    Quit,
+}
+
+struct Cli {
+    api: CommandApi,
+
+    /// Account used by the commands, the selected account if not set.
+    account_id: Option<u32>,
+}
+
+impl Cli {
+    /// Calls a JSON-RPC method.
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        self.api
+            .handle_request(method.to_string(), params)
+            .await
+            .map_err(|err| anyhow!("{}", err.message))
+    }
+
+    async fn account_id(&self) -> Result<u32> {
+        if let Some(id) = self.account_id {
+            return Ok(id);
+        }
+        match self.call("get_selected_account_id", json!([])).await? {
+            Value::Number(id) => id.as_u64().and_then(|id| u32::try_from(id).ok()),
+            _ => None,
+        }
+        .context("no account selected, use `add-account` or `select ID`")
+    }
+
+    async fn run_interactive(&mut self) -> Result<()> {
+        let mut lines = BufReader::new(io::stdin()).lines();
+        loop {
+            let mut stderr = io::stderr();
+            stderr.write_all(b"> ").await?;
+            stderr.flush().await?;
+
+            let line = match lines.next_line().await? {
+                Some(line) => line,
+                None => return Ok(()),
+            };
+            match self.run_line(&line).await {
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Quit) => return Ok(()),
+                Err(err) => eprintln!("Error: {err:#}"),
+            }
+        }
+    }
+
+    /// Runs the commands of a script, stopping at the first error.
+    async fn run_script(&mut self, path: &str) -> Result<Flow> {
+        let script = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("failed to read {path}"))?;
+        for (i, line) in script.lines().enumerate() {
+            // Boxed because scripts can run scripts.
+            let flow: Pin<Box<dyn Future<Output = Result<Flow>>>> = Box::pin(self.run_line(line));
+            let flow = flow.await.with_context(|| format!("{path}:{}", i + 1))?;
+            if let Flow::Quit = flow {
+                return Ok(Flow::Quit);
+            }
+        }
+        Ok(Flow::Continue)
+    }
+
+    /// Runs a single command. Empty lines and lines starting with `#` are ignored.
+    async fn run_line(&mut self, line: &str) -> Result<Flow> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(Flow::Continue);
+        }
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        let args: Vec<&str> = rest.split_whitespace().collect();
+
+        match (command, args.as_slice()) {
+            ("help", []) => println!("{USAGE}"),
+            ("quit" | "exit", []) => return Ok(Flow::Quit),
+            ("accounts", []) => {
+                let accounts = self.call("get_all_accounts", json!([])).await?;
+                for account in accounts.as_array().into_iter().flatten() {
+                    let addr = account["addr"].as_str().unwrap_or("unconfigured");
+                    println!("{} {}", account["id"], addr);
+                }
+            }
+            ("add-account", []) => {
+                let id = self.call("add_account", json!([])).await?;
+                self.call("select_account", json!([id])).await?;
+                self.account_id = None;
+                println!("{id}");
+            }
+            ("select", [id]) => {
+                let id: u32 = id.parse().context("invalid account ID")?;
+                self.call("select_account", json!([id])).await?;
+                self.account_id = None;
+            }
+            ("get", [key]) => {
+                let id = self.account_id().await?;
+                match self.call("get_config", json!([id, key])).await? {
+                    Value::String(value) => println!("{value}"),
+                    _ => println!("<unset>"),
+                }
+            }
+            ("set", [key]) => {
+                let id = self.account_id().await?;
+                self.call("set_config", json!([id, key, null])).await?;
+            }
+            ("set", [key, ..]) => {
+                let id = self.account_id().await?;
+                let value = rest[key.len()..].trim();
+                self.call("set_config", json!([id, key, value])).await?;
+            }
+            ("configure", []) => {
+                let id = self.account_id().await?;
+                self.call("configure", json!([id])).await?;
+            }
+            ("configure", [addr, password]) => {
+                let id = self.account_id().await?;
+                self.call(
+                    "batch_set_config",
+                    json!([id, { "addr": addr, "mail_pw": password }]),
+                )
+                .await?;
+                self.call("configure", json!([id])).await?;
+            }
+            ("start-io", []) => {
+                let id = self.account_id().await?;
+                self.call("start_io", json!([id])).await?;
+            }
+            ("stop-io", []) => {
+                let id = self.account_id().await?;
+                self.call("stop_io", json!([id])).await?;
+            }
+            ("chats", _) => {
+                let id = self.account_id().await?;
+                let query = if rest.is_empty() { None } else { Some(rest) };
+                let entries = self
+                    .call("get_chatlist_entries", json!([id, null, query, null]))
+                    .await?;
+                let items = self
+                    .call("get_chatlist_items_by_entries", json!([id, entries]))
+                    .await?;
+                for entry in entries.as_array().into_iter().flatten() {
+                    let item = &items[entry.to_string()];
+                    match item["type"].as_str() {
+                        Some("ChatListItem") => println!(
+                            "{} {} [{} fresh] {}",
+                            entry,
+                            item["name"].as_str().unwrap_or_default(),
+                            item["freshMessageCounter"],
+                            item["summaryText2"].as_str().unwrap_or_default()
+                        ),
+                        Some("ArchiveLink") => println!("{entry} <archived chats>"),
+                        _ => println!("{entry} <error: {}>", item["error"]),
+                    }
+                }
+            }
+            ("chat-with", [addr]) => {
+                let id = self.account_id().await?;
+                let contact_id = self.call("create_contact", json!([id, addr, null])).await?;
+                let chat_id = self
+                    .call("create_chat_by_contact_id", json!([id, contact_id]))
+                    .await?;
+                println!("{chat_id}");
+            }
+            ("msgs", [chat_id]) => {
+                let id = self.account_id().await?;
+                let chat_id: u32 = chat_id.parse().context("invalid chat ID")?;
+                let msg_ids = self
+                    .call("get_message_ids", json!([id, chat_id, false, false]))
+                    .await?;
+                for msg_id in msg_ids.as_array().into_iter().flatten() {
+                    let msg = self.call("get_message", json!([id, msg_id])).await?;
+                    println!(
+                        "{} [{}] {}: {}",
+                        msg["id"],
+                        msg["timestamp"],
+                        msg["sender"]["displayName"].as_str().unwrap_or_default(),
+                        msg["text"].as_str().unwrap_or_default()
+                    );
+                }
+            }
+            ("send", [chat_id, _, ..]) => {
+                let id = self.account_id().await?;
+                let text = rest[chat_id.len()..].trim();
+                let chat_id: u32 = chat_id.parse().context("invalid chat ID")?;
+                let msg_id = self
+                    .call("misc_send_text_message", json!([id, chat_id, text]))
+                    .await?;
+                println!("{msg_id}");
+            }
+            ("export-sanitized-db", [path]) => {
+                let id = self.account_id().await?;
+                self.call("export_sanitized_database", json!([id, path]))
+                    .await?;
+            }
+            ("watch", []) => loop {
+                tokio::select! {
+                    event = self.call("get_next_event", json!([])) => println!("{}", event?),
+                    res = tokio::signal::ctrl_c() => {
+                        res?;
+                        break;
+                    }
+                }
+            },
+            ("call", [method, ..]) => {
+                let params = rest[method.len()..].trim();
+                let params = if params.is_empty() {
+                    json!([])
+                } else {
+                    serde_json::from_str(params).context("PARAMS is not valid JSON")?
+                };
+                let result = self.call(method, params).await?;
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            }
+            ("run", [path]) => return self.run_script(path).await,
+            _ => bail!("unknown command or wrong arguments: {line}, see `help`"),
+        }
+        Ok(Flow::Continue)
+    }
+}
@@ -368,6 +368,24 @@ dc_event_emitter_t* dc_get_event_emitter(dc_context_t* context);
 char*           dc_get_blobdir               (const dc_context_t* context);
 
 
+/**
+ * Read a file from the blob directory, e.g. the attachment of a message.
+ *
+ * This allows to read attachments on platforms where the app cannot access
+ * the blob directory directly, e.g. from iOS app extensions.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param path The absolute path of the file, as returned e.g. by dc_msg_get_file().
+ *     The file must be directly in the blob directory.
+ * @param ret_bytes A pointer to a size_t. The size of the file will be written here.
+ * @return The content of the file.
+ *     The blob must be released using dc_str_unref() after usage.
+ *     NULL if the file is not in the blob directory or on errors.
+ */
+char*           dc_get_blob_bytes            (dc_context_t* context, const char* path, size_t* ret_bytes);
+
+
 /**
  * Configure the context. The configuration is handled by key=value pairs as:
  *
@@ -383,11 +401,32 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  * - `send_port`    = SMTP-port, guessed if left out
  * - `send_security`= SMTP-socket, one of @ref DC_SOCKET, defaults to #DC_SOCKET_AUTO
  * - `server_flags` = IMAP-/SMTP-flags as a combination of @ref DC_LP flags, guessed if left out
+ * - `oauth2_client_id` = OAuth2 client ID registered by the application,
+ *                    overrides the built-in one.
+ *                    Needed to use OAuth2 with Outlook.com and Microsoft 365,
+ *                    see dc_get_oauth2_url().
  * - `socks5_enabled` = SOCKS5 enabled
  * - `socks5_host` = SOCKS5 proxy server host
  * - `socks5_port` = SOCKS5 proxy server port
  * - `socks5_user` = SOCKS5 proxy username
  * - `socks5_password` = SOCKS5 proxy password
+ * - `http_proxy_enabled` = HTTP CONNECT proxy enabled,
+ *                    used for IMAP, SMTP and HTTP requests if SOCKS5 is disabled
+ * - `http_proxy_host` = HTTP proxy server host
+ * - `http_proxy_port` = HTTP proxy server port
+ * - `http_proxy_tls` = 1=connect to the HTTP proxy using TLS (HTTPS proxy), 0=plain HTTP (default)
+ * - `http_proxy_user` = HTTP proxy username
+ * - `http_proxy_password` = HTTP proxy password
+ * - `tor_enabled` = 1=connect to IMAP and SMTP servers using the built-in Tor client,
+ *                    takes precedence over SOCKS5 and HTTP proxy settings;
+ *                    onion services published in the provider database are preferred.
+ *                    HTTP requests such as online autoconfiguration are not done in this mode.
+ *                    Requires the core to be built with `tor` feature, otherwise connections fail.
+ *                    0=do not use the built-in Tor client (default)
+ * - `learn_certificate_pins` = 1=pin the public key (SPKI) of the TLS certificate
+ *                    on the first successful connection to each server
+ *                    and reject connections presenting another key later,
+ *                    0=only check pins set explicitly (default)
  * - `imap_certificate_checks` = how to check IMAP certificates, one of the @ref DC_CERTCK flags, defaults to #DC_CERTCK_AUTO (0)
  * - `smtp_certificate_checks` = how to check SMTP certificates, one of the @ref DC_CERTCK flags, defaults to #DC_CERTCK_AUTO (0)
  * - `displayname`  = Own name to use when sending messages. MUAs are allowed to spread this way e.g. using CC, defaults to empty
@@ -408,6 +447,12 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  * - `sentbox_watch`= 1=watch `Sent`-folder for changes,
  *                    0=do not watch the `Sent`-folder (default),
  *                    changes require restarting IO by calling dc_stop_io() and then dc_start_io().
+ * - `watch_folders` = Additional folders to watch for new messages, separated by newlines,
+ *                    eg. folders that server-side filters sort chat messages into.
+ *                    Changes require restarting IO by calling dc_stop_io() and then dc_start_io().
+ * - `watch_folders_move` = 1=move chat messages from `watch_folders` to the inbox
+ *                    or, if `mvbox_move` is set, to the `DeltaChat` folder,
+ *                    0=leave messages in these folders (default).
  * - `mvbox_move`   = 1=detect chat messages,
  *                    move them to the `DeltaChat` folder,
  *                    and watch the `DeltaChat` folder for updates (default),
@@ -445,6 +490,28 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  *                    "Saved messages" are deleted from the server as well as
  *                    e-mails matching the `show_emails` settings above, the UI should clearly point that out.
  *                    See also dc_estimate_deletion_cnt().
+ * - `delete_to_trash` = 1=move deleted messages to the Trash folder instead of deleting them on the server,
+ *                    so that they can be restored using other e-mail clients,
+ *                    0=delete messages on the server directly,
+ *                    unset=use the default of the provider.
+ * - `delete_trash_after` = 0=do not delete messages from the Trash folder automatically (default),
+ *                    >=1=seconds, after which messages are deleted from the Trash folder
+ *                    if `delete_to_trash` is in effect.
+ *                    The time is counted from the moment Delta Chat moved the message to the Trash folder
+ *                    and checked once a day; messages moved there by other clients are not deleted.
+ * - `media_cleanup_after` = 0=do not delete media automatically (default),
+ *                    >=1=seconds, after which images, videos, audio and other files of messages
+ *                    are deleted from the device during housekeeping.
+ *                    The messages are kept and can be downloaded again with dc_download_full_msg(),
+ *                    media of messages which are not on the server anymore is not deleted.
+ *                    "Saved messages", the device chat and webxdc apps are not affected.
+ * - `media_cleanup_min_chat_size` = delete media automatically only from chats
+ *                    using more than this number of bytes, 0=all chats (default).
+ * - `media_cleanup_keep_starred` = 1=keep starred messages when deleting media automatically (default),
+ *                    0=delete starred messages as well.
+ * - `housekeeping_interval` = seconds between automatic housekeeping runs while IO is running,
+ *                    86400=one day (default), 0=do not run housekeeping automatically.
+ *                    #DC_EVENT_HOUSEKEEPING_DONE is emitted when housekeeping finished.
  * - `media_quality` = DC_MEDIA_QUALITY_BALANCED (0) =
  *                    good outgoing images/videos/voice quality at reasonable sizes (default)
  *                    DC_MEDIA_QUALITY_WORSE (1)
@@ -484,6 +551,64 @@ char*           dc_get_blobdir               (const dc_context_t* context);
  *                    to not mess up with non-delivery-reports or read-receipts.
  *                    0=no limit (default).
  *                    Changes affect future messages only.
+ * - `download_limit_image`, `download_limit_video`, `download_limit_audio`, `download_limit_webxdc`
+ *                  = Like `download_limit`, but only for messages with an attachment of the given type;
+ *                    images include GIFs and stickers, audio includes voice messages.
+ *                    Unset=use `download_limit` (default), 0=no limit.
+ *                    As the type is not known for encrypted messages before download,
+ *                    `download_limit` is always used for them.
+ * - `download_limit_metered_image`, `download_limit_metered_video`,
+ *   `download_limit_metered_audio`, `download_limit_metered_webxdc`
+ *                  = Used instead of the options above on metered networks, see `network_class`.
+ *                    A small value, eg. 1, effectively disables automatic download of the type.
+ * - `network_class` = class of the network the device is currently connected to,
+ *                    one of DC_NETWORK_CLASS_UNMETERED (0, default), DC_NETWORK_CLASS_METERED (1)
+ *                    or DC_NETWORK_CLASS_ROAMING (2).
+ *                    The UI should update this option when the network changes,
+ *                    see also dc_accounts_set_network_class().
+ *                    DC_NETWORK_CLASS_ROAMING implies the behaviour of DC_NETWORK_CLASS_METERED.
+ * - `send_limit_metered` = Max. size (in bytes) of messages sent out
+ *                    on metered networks or while roaming.
+ *                    Larger messages are kept in the queue until the network is unmetered.
+ *                    0=no limit (default).
+ * - `download_on_roaming` = 1=download messages automatically while roaming
+ *                    as on metered networks,
+ *                    0=download only small messages automatically while roaming (default).
+ * - `smtp_max_attempts` = Max. number of attempts to send a message before giving up,
+ *                    defaults to 6. See also #DC_EVENT_MSG_GIVEN_UP.
+ * - `smtp_retry_delay` = Delay in seconds before sending is retried after a failure,
+ *                    defaults to 30.
+ * - `smtp_retry_backoff` = Factor the retry delay is multiplied with after each further failure,
+ *                    defaults to 3.
+ * - `smtp_fail_fast` = 1=messages fail immediately on transient SMTP errors
+ *                    instead of being retried later, useful e.g. for bots,
+ *                    0=retry messages on transient errors (default).
+ * - `notify_muted_mentions` = 1=dc_get_fresh_msgs() also returns messages in muted groups
+ *                    which mention the user by address or `@` and display name
+ *                    or quote one of the user's messages,
+ *                    0=messages in muted chats are not returned (default).
+ * - `notification_privacy` = How much content notifications reveal,
+ *                    one of DC_NOTIFICATION_PRIVACY_FULL (0, default): chat, sender and text,
+ *                    DC_NOTIFICATION_PRIVACY_SENDER_ONLY (1): chat and sender only
+ *                    or DC_NOTIFICATION_PRIVACY_GENERIC (2): only that there are new messages.
+ *                    Applied by the JSON-RPC notification info methods.
+ * - `quota_warn_threshold` = Quota usage in percent at which the user is warned
+ *                    about a nearly full mailbox, defaults to 80.
+ * - `quota_error_threshold` = Quota usage in percent at which the user is warned again,
+ *                    defaults to 95. See also #DC_EVENT_QUOTA_WARNING.
+ * - `jmap_url`     = URL of the JMAP session resource, e.g. `https://example.org/.well-known/jmap`.
+ *                    If unset, dc_configure() looks for the session resource
+ *                    at `/.well-known/jmap` of the email domain.
+ *                    If a session resource is found, messages are fetched and sent
+ *                    using JMAP instead of IMAP and SMTP, the IMAP login credentials are used.
+ *                    Changes require calling dc_configure() again.
+ * - `scan_folders_connections` = Maximum number of IMAP connections
+ *                    used in parallel to scan folders that are not watched,
+ *                    e.g. folders filled by server-side filter rules.
+ *                    The connections watching the inbox, mvbox, sentbox
+ *                    and `watch_folders` are reused, no additional connections are opened,
+ *                    so the watched folders go back to IDLE sooner.
+ *                    1=scan all folders over the inbox connection (default).
  * - `ui.*`         = All keys prefixed by `ui.` can be used by the user-interfaces for system-specific purposes.
  *                    The prefix should be followed by the system and maybe subsystem,
  *                    e.g. `ui.desktop.foo`, `ui.desktop.linux.bar`, `ui.android.foo`, `ui.dc40.bar`, `ui.bot.simplebot.baz`.
@@ -577,6 +702,31 @@ int             dc_set_config_from_qr   (dc_context_t* context, const char* qr);
 char*           dc_get_info                  (const dc_context_t* context);
 
 
+/**
+ * Get the optional features supported by this build of the library, in JSON format.
+ *
+ * This allows UIs to hide menus of unsupported features at runtime
+ * instead of parsing the version string.
+ * The returned JSON object maps feature names to `true` or `false`;
+ * new features may be added in later versions, treat missing keys as `false`.
+ * The following keys are currently returned:
+ *
+ * - webxdc: Webxdc apps are supported, see dc_send_webxdc_status_update().
+ * - location: Location streaming is supported, see dc_send_locations_to_chat().
+ * - videochat: Videochat invitations are supported, see dc_send_videochat_invitation().
+ * - backup_transfer: Backups can be sent to a second device, see dc_backup_provider_new().
+ * - sqlcipher: The database can be encrypted.
+ * - tor: The library is built with Tor support.
+ * - jmap: JMAP accounts are supported, see the `jmap_url` option of dc_set_config().
+ * - pq_crypto: Post-quantum encryption is supported.
+ * - jsonrpc: The JSON-RPC API is available, see dc_jsonrpc_init().
+ *
+ * @return The JSON object as string, must be released using dc_str_unref() after usage.
+ *     NULL is never returned.
+ */
+char*           dc_get_capabilities          (void);
+
+
 /**
  * Get URL that can be used to initiate an OAuth2 authorization.
  *
@@ -609,6 +759,37 @@ char*           dc_get_info                  (const dc_context_t* context);
 char*           dc_get_oauth2_url            (dc_context_t* context, const char* addr, const char* redirect_uri);
 
 
+/**
+ * Request OAuth2 access tokens from the UI instead of using the built-in authorization flow.
+ *
+ * If enabled, the core emits #DC_EVENT_OAUTH2_TOKEN_REQUESTED
+ * whenever an access token is needed for an account configured
+ * with `server_flags` set to #DC_LP_AUTH_OAUTH2.
+ * The UI may then obtain a token, e.g. using the sign-in SDK of the platform,
+ * and pass it to dc_set_oauth2_access_token().
+ * This also works for providers without built-in OAuth2 support.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param enabled 1=request tokens from the UI, 0=use the built-in authorization flow.
+ */
+void            dc_set_oauth2_token_requests (dc_context_t* context, int enabled);
+
+
+/**
+ * Answer #DC_EVENT_OAUTH2_TOKEN_REQUESTED.
+ *
+ * If no token is set within 60 seconds after the event,
+ * the core gives up and treats it as a login failure.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param token The OAuth2 access token.
+ *     NULL or an empty string if no token could be obtained.
+ */
+void            dc_set_oauth2_access_token   (dc_context_t* context, const char* token);
+
+
 #define DC_CONNECTIVITY_NOT_CONNECTED        1000
 #define DC_CONNECTIVITY_CONNECTING           2000
 #define DC_CONNECTIVITY_WORKING              3000
@@ -768,6 +949,39 @@ void            dc_start_io     (dc_context_t* context);
  */
 void            dc_stop_io(dc_context_t* context);
 
+
+/**
+ * Enable or disable the maintenance mode.
+ *
+ * In maintenance mode, all networking is disabled,
+ * so tools migrating, exporting or repairing the database
+ * can be sure that no IMAP, SMTP or HTTP connection is made while they run.
+ * Enabling the maintenance mode stops IO as dc_stop_io() does.
+ * While the maintenance mode is enabled, dc_start_io() does nothing
+ * and functions needing the network, e.g. dc_configure(), fail.
+ * Local operations, e.g. exporting a backup with dc_imex(), still work.
+ *
+ * To make sure no connection is made at all,
+ * enable the maintenance mode right after dc_context_new()
+ * or dc_accounts_get_account().
+ * The maintenance mode is not persisted.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @param enabled 1=enable maintenance mode, 0=disable maintenance mode.
+ */
+void            dc_set_maintenance_mode(dc_context_t* context, int enabled);
+
+
+/**
+ * Check if the maintenance mode is enabled, see dc_set_maintenance_mode().
+ *
+ * @memberof dc_context_t
+ * @param context The context object as created by dc_context_new().
+ * @return 1=maintenance mode is enabled, 0=maintenance mode is disabled.
+ */
+int             dc_is_maintenance_mode(dc_context_t* context);
+
 /**
  * This function should be called when there is a hint
  * that the network is available again,
@@ -793,6 +1007,107 @@ void            dc_stop_io(dc_context_t* context);
 void            dc_maybe_network             (dc_context_t* context);
 
 
+/**
+ * Set the UnifiedPush endpoint provided by the distributor.
+ *
+ * If the server supports push notifications,
+ * the endpoint is registered on the next connection.
+ * While the endpoint is registered, the IMAP connection is not kept open
+ * and the app should call dc_handle_push_notification()
+ * when the distributor delivers a message for the endpoint.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param endpoint The endpoint URL, must be an HTTPS URL.
+ *     NULL to stop receiving push notifications.
+ * @return 1 on success, 0 on failure.
+ */
+int             dc_set_unifiedpush_endpoint  (dc_context_t* context, const char* endpoint);
+
+
+/**
+ * Fetch new messages after a push notification was received.
+ * The content of the notification is not needed.
+ *
+ * IO must be started using dc_start_io() before,
+ * otherwise this function has no effect.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ */
+void            dc_handle_push_notification  (dc_context_t* context);
+
+
+/**
+ * Set the APNs or FCM device token for push notifications.
+ *
+ * The token is persisted and passed to the notification service of the server unchanged.
+ * It is registered on the next connection if the server supports push notifications
+ * and registered again automatically after the account is reconfigured.
+ * If set, the token takes precedence over an endpoint set with dc_set_unifiedpush_endpoint().
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param token The device token. NULL to stop receiving push notifications.
+ * @return 1 on success, 0 on failure.
+ */
+int             dc_set_push_device_token     (dc_context_t* context, const char* token);
+
+
+/**
+ * Get the state of push notifications, e.g. to show it in the settings.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @return One of the DC_PUSH_* constants:
+ *     - DC_PUSH_NO_TOKEN (0): Neither a device token nor a UnifiedPush endpoint is set.
+ *     - DC_PUSH_NOT_SUPPORTED (1): The server does not support push notifications.
+ *     - DC_PUSH_PENDING (2): The token will be registered on the next connection.
+ *     - DC_PUSH_REGISTERED (3): The token is registered on the server.
+ */
+int             dc_get_push_state            (dc_context_t* context);
+
+
+/**
+ * Set quiet hours during which notifications should be shown silently,
+ * replacing the previous ones.
+ *
+ * Quiet hours are synchronized to other devices if `send_sync_msgs` is enabled,
+ * use dc_is_quiet_time() to check whether a notification should be shown silently.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @param json JSON array of ranges, e.g. `[{"weekday":0,"start":1320,"end":420}]`.
+ *     `weekday` is the day the range starts on, 0 is Monday and 6 is Sunday.
+ *     `start` and `end` are minutes since midnight in local time;
+ *     if `end` is not after `start`, the range ends on the next day.
+ *     An empty array removes all quiet hours.
+ * @return 1 on success, 0 on failure.
+ */
+int             dc_set_quiet_hours           (dc_context_t* context, const char* json);
+
+
+/**
+ * Get the quiet hours set with dc_set_quiet_hours().
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @return JSON array of ranges as described at dc_set_quiet_hours().
+ *     Returned string must be released using dc_str_unref().
+ */
+char*           dc_get_quiet_hours           (dc_context_t* context);
+
+
+/**
+ * Check whether the current local time falls into the quiet hours.
+ *
+ * @memberof dc_context_t
+ * @param context The context as created by dc_context_new().
+ * @return 1 if notifications should be shown silently, 0 otherwise.
+ */
+int             dc_is_quiet_time             (dc_context_t* context);
+
+
 
 /**
  * Save a keypair as the default keys for the user.
@@ -993,6 +1308,30 @@ uint32_t        dc_prepare_msg               (dc_context_t* context, uint32_t ch
  */
 uint32_t        dc_send_msg                  (dc_context_t* context, uint32_t chat_id, dc_msg_t* msg);
 
+
+/**
+ * Send a message defined by a dc_msg_t object to a chat, without blocking.
+ *
+ * This works like dc_send_msg() but returns immediately,
+ * so the UI thread does not have to wait e.g. while an image is recoded.
+ * Once the message is added to the chat, #DC_EVENT_MSGS_CHANGED is emitted
+ * with the chat ID and the ID of the new message.
+ * If the message cannot be sent, #DC_EVENT_ERROR is emitted instead.
+ *
+ * Like dc_configure() and dc_imex(), which also return immediately
+ * and report their progress with #DC_EVENT_CONFIGURE_PROGRESS and #DC_EVENT_IMEX_PROGRESS,
+ * this function does not need a dedicated thread.
+ *
+ * @memberof dc_context_t
+ * @param context The context object as returned from dc_context_new().
+ * @param chat_id The chat ID to send the message to.
+ *     If dc_prepare_msg() was called before, this parameter can be 0.
+ * @param msg The message object to send to the chat defined by the chat ID.
+ *     The object is copied and not updated, in particular its ID is not set up,
+ *     so it can be freed using dc_msg_unref() right after the call.
+ */
+void            dc_send_msg_async            (dc_context_t* context, uint32_t chat_id, dc_msg_t* msg);
+
 /**
  * Send a message defined by a dc_msg_t object to a chat, synchronously.
  * This bypasses the IO scheduler and creates its own SMTP connection. Which means
@@ -1159,6 +1498,60 @@ int dc_send_webxdc_status_update (dc_context_t* context, uint32_t msg_id, const
  */
 char* dc_get_webxdc_status_updates (dc_context_t* context, uint32_t msg_id, uint32_t serial);
 
+
+/**
+ * Join the realtime channel of a webxdc instance.
+ *
+ * Realtime channels are meant for data which is only useful
+ * to the peers running the app at the same time,
+ * e.g. positions of players in games or cursors in editors.
+ * Unlike status updates, realtime data is not stored and may be lost.
+ *
+ * The webxdc has to request the `realtime` capability in its manifest,
+ * see dc_msg_get_webxdc_info().
+ *
+ * After joining, data sent by other peers of the channel
+ * is reported by #DC_EVENT_WEBXDC_REALTIME_DATA.
+ * Joining a channel again does nothing.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_id The ID of the message with the webxdc instance.
+ * @return 1=success, 0=error
+ */
+int dc_join_webxdc_realtime_channel (dc_context_t* context, uint32_t msg_id);
+
+
+/**
+ * Leave the realtime channel of a webxdc instance
+ * joined with dc_join_webxdc_realtime_channel(),
+ * e.g. when the webxdc app is closed.
+ * Data not sent yet is dropped.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_id The ID of the message with the webxdc instance.
+ * @return 1=success, 0=error
+ */
+int dc_leave_webxdc_realtime_channel (dc_context_t* context, uint32_t msg_id);
+
+
+/**
+ * Send data to the peers of the realtime channel of a webxdc instance.
+ * The channel must be joined with dc_join_webxdc_realtime_channel() before.
+ *
+ * Data is sent over a peer-to-peer transport if the embedding application set one.
+ * Otherwise it is sent by email, data sent within 10 seconds is collected into a single message.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_id The ID of the message with the webxdc instance.
+ * @param data The data to send, at most 128 KiB.
+ * @param len The number of bytes of `data`.
+ * @return 1=success, 0=error
+ */
+int dc_send_webxdc_realtime_data (dc_context_t* context, uint32_t msg_id, const uint8_t* data, size_t len);
+
 /**
  * Save a draft for a chat in the database.
  *
@@ -1790,6 +2183,36 @@ int             dc_set_chat_name             (dc_context_t* context, uint32_t ch
  */
 int dc_set_chat_ephemeral_timer (dc_context_t* context, uint32_t chat_id, uint32_t timer);
 
+/**
+ * Get the chat-specific override of the `delete_server_after` option,
+ * see dc_set_chat_delete_server_after().
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The chat ID to get the value for.
+ * @return -1=the global `delete_server_after` option is used for the chat (default),
+ *     otherwise a value with the same meaning as `delete_server_after`.
+ */
+int64_t dc_get_chat_delete_server_after (dc_context_t* context, uint32_t chat_id);
+
+/**
+ * Override the `delete_server_after` option for a chat,
+ * eg. to delete newsletters from the server after a day
+ * while keeping other messages on the server.
+ *
+ * The setting is local to the device and is not synchronized to other chat members.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param chat_id The chat ID to set the value for.
+ * @param delete_server_after -1=use the global `delete_server_after` option,
+ *     0=do not delete messages of this chat from the server,
+ *     1=delete messages directly after receiving from server,
+ *     >1=seconds, after which messages are deleted from the server.
+ * @return 1=success, 0=error
+ */
+int dc_set_chat_delete_server_after (dc_context_t* context, uint32_t chat_id, int64_t delete_server_after);
+
 /**
  * Set group profile image.
  *
@@ -2038,6 +2461,32 @@ void            dc_markseen_msgs             (dc_context_t* context, const uint3
 dc_msg_t*       dc_get_msg                   (dc_context_t* context, uint32_t msg_id);
 
 
+/**
+ * Get the summaries of several messages at once, in JSON format.
+ *
+ * This is faster than creating a dc_msg_t object
+ * and calling dc_msg_get_summary() for each message,
+ * especially if the bindings have a high per-call overhead, as JNI.
+ *
+ * The returned JSON array contains an object for each given message ID, in the same order,
+ * or `null` if the message could not be loaded. Each object has the following key/values:
+ *
+ * - msg_id: The message ID.
+ * - text1, text1_meaning, text2, timestamp, state: The fields of the summary,
+ *   see dc_msg_get_summary() and dc_chatlist_get_summary() for their meaning.
+ *   text1 and text2 may be `null`.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @param msg_ids An array of uint32_t containing the message IDs.
+ * @param msg_cnt The number of message IDs in msg_ids.
+ * @return A UTF8 encoded JSON array.
+ *     Must be freed using dc_str_unref().
+ *     On errors, an empty string is returned, NULL is never returned.
+ */
+char*           dc_get_msg_summaries_json    (dc_context_t* context, const uint32_t* msg_ids, int msg_cnt);
+
+
 // handle contacts
 
 /**
@@ -2742,6 +3191,10 @@ void        dc_delete_all_locations         (dc_context_t* context);
  * however, using this function avoids race conditions
  * if the failing function is called in another thread than dc_get_next_event().
  *
+ * If a function taking a context panics internally,
+ * it does not abort the app but returns 0 or NULL;
+ * the panic message is set as last error and logged via #DC_EVENT_ERROR then.
+ *
  * @memberof dc_context_t
  * @param context The context object.
  * @return Last error or an empty string if there is no last error.
@@ -2751,6 +3204,57 @@ void        dc_delete_all_locations         (dc_context_t* context);
 char* dc_get_last_error (dc_context_t* context);
 
 
+/**
+ * Get the last events emitted by the context.
+ *
+ * Up to 5000 events are kept in memory together with the time they were emitted,
+ * so bug reports can include what happened before a failure.
+ * The history is not saved and starts empty when the context is opened.
+ *
+ * @memberof dc_context_t
+ * @param context The context object.
+ * @return JSON array of objects, oldest first.
+ *     Each object contains `timestamp_ms`, the time in milliseconds since the Unix epoch,
+ *     and `event`, the event type and its data, e.g. `{"Info":"..."}`.
+ *     Empty string on errors, NULL is never returned.
+ *     The returned value must be released using dc_str_unref() after usage.
+ */
+char* dc_get_event_history (dc_context_t* context);
+
+
+/**
+ * Start or stop recording tracing spans for profiling.
+ *
+ * IMAP, SMTP and message receiving functions are instrumented with spans.
+ * While recording, closed spans of all accounts are kept in memory,
+ * up to 10000 spans.
+ * Starting the recording clears previously recorded spans.
+ * Use dc_get_recorded_spans() to export them.
+ *
+ * Recording has a small performance cost and should only be enabled
+ * while investigating performance problems, e.g. slow folder scans.
+ *
+ * @param enabled 1=start recording, 0=stop recording.
+ * @return 1=success, 0=error,
+ *     e.g. if the application installed its own global tracing subscriber.
+ */
+int   dc_set_span_recording (int enabled);
+
+
+/**
+ * Export the spans recorded after calling dc_set_span_recording().
+ *
+ * The spans are returned in the Chrome trace event format,
+ * which can be saved to a file and opened in `chrome://tracing`
+ * or https://ui.perfetto.dev
+ *
+ * @return JSON object with a `traceEvents` array.
+ *     Empty string on errors, NULL is never returned.
+ *     The returned value must be released using dc_str_unref() after usage.
+ */
+char* dc_get_recorded_spans (void);
+
+
 /**
  * Release a string returned by another deltachat-core function.
  * - Strings returned by any deltachat-core-function
@@ -2805,7 +3309,7 @@ dc_backup_provider_t* dc_backup_provider_new (dc_context_t* context);
  *
  * The scanning device should call the scanned text to dc_check_qr().  If
  * dc_check_qr() returns DC_QR_BACKUP, the backup transfer can be started using
- * dc_get_backup().
+ * dc_receive_backup().
  *
  * @memberof dc_backup_provider_t
  * @param backup_provider The backup provider object as created by
@@ -2820,7 +3324,7 @@ char* dc_backup_provider_get_qr (const dc_backup_provider_t* backup_provider);
 /**
  * Returns the QR code SVG image that will offer the backup to other devices.
  *
- * This works like dc_backup_provider_qr() but returns the text of a rendered
+ * This works like dc_backup_provider_get_qr() but returns the text of a rendered
  * SVG image containing the QR code.
  *
  * @memberof dc_backup_provider_t
@@ -2836,6 +3340,10 @@ char* dc_backup_provider_get_qr_svg (const dc_backup_provider_t* backup_provider
  * Waits for the sending to finish.
  *
  * This is a blocking call and should only be called once.
+ * It returns once the backup was received by another device,
+ * the transfer failed or it was aborted using dc_stop_ongoing_process().
+ * The #DC_EVENT_IMEX_PROGRESS event with value 1000 (succeeded) or 0 (failed)
+ * tells which one happened, on failure dc_get_last_error() returns the error.
  *
  * @memberof dc_backup_provider_t
  * @param backup_provider The backup provider object as created by
@@ -2862,7 +3370,7 @@ void dc_backup_provider_unref (dc_backup_provider_t* backup_provider);
  * Gets a backup offered by a dc_backup_provider_t object on another device.
  *
  * This function is called on a device that scanned the QR code offered by
- * dc_backup_sender_qr() or dc_backup_sender_qr_svg().  Typically this is a
+ * dc_backup_provider_get_qr() or dc_backup_provider_get_qr_svg().  Typically this is a
  * different device than that which provides the backup.
  *
  * This call will block while the backup is being transferred and only
@@ -3113,6 +3621,20 @@ void           dc_accounts_stop_io              (dc_accounts_t* accounts);
 void           dc_accounts_maybe_network        (dc_accounts_t* accounts);
 
 
+/**
+ * Set the class of the network the device is currently connected to
+ * for all accounts.
+ * This function should be called whenever the network changes.
+ * This is similar to setting the `network_class` option with dc_set_config() for each account.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The account manager as created by dc_accounts_new().
+ * @param network_class One of DC_NETWORK_CLASS_UNMETERED, DC_NETWORK_CLASS_METERED
+ *     or DC_NETWORK_CLASS_ROAMING.
+ */
+void           dc_accounts_set_network_class    (dc_accounts_t* accounts, int network_class);
+
+
 /**
  * This function can be called when there is a hint that the network is lost.
  * This is similar to dc_accounts_maybe_network(), however,
@@ -3150,6 +3672,45 @@ void           dc_accounts_maybe_network_lost    (dc_accounts_t* accounts);
 dc_event_emitter_t* dc_accounts_get_event_emitter (dc_accounts_t* accounts);
 
 
+/**
+ * Collapse repeated events emitted during bulk operations.
+ *
+ * Bulk operations such as imports or marking many messages as seen
+ * emit many #DC_EVENT_MSGS_CHANGED and #DC_EVENT_CHAT_MODIFIED events for the same chat.
+ * If a time window is set, only the first of these events for a chat is emitted right away.
+ * Following events for the same chat within the window
+ * are collapsed into a single event emitted at the end of the window.
+ * If several messages changed, `data2` of the collapsed #DC_EVENT_MSGS_CHANGED is 0.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The account manager as created by dc_accounts_new().
+ * @param window_ms Time window in milliseconds.
+ *     0, the default, disables collapsing events.
+ */
+void           dc_accounts_set_event_coalescing_window (dc_accounts_t* accounts, int window_ms);
+
+
+/**
+ * Configure how many events are kept if the application does not receive them
+ * and which events are dropped if there are more.
+ *
+ * By default, an event channel keeps up to 1000 events and drops the oldest ones.
+ * If events were dropped, the event emitter returns #DC_EVENT_EVENT_QUEUE_OVERFLOW
+ * before the remaining events.
+ *
+ * @memberof dc_accounts_t
+ * @param accounts The account manager as created by dc_accounts_new().
+ * @param account_id 0 to configure the channel of dc_accounts_get_event_emitter(),
+ *     otherwise the ID of the account whose own event channel is configured.
+ * @param capacity Maximum number of events kept, at least 1.
+ * @param overflow_policy DC_EVENT_QUEUE_DROP_OLDEST to drop the oldest events
+ *     or DC_EVENT_QUEUE_DROP_LOW_PRIORITY to drop informational events
+ *     such as #DC_EVENT_INFO and #DC_EVENT_WARNING first.
+ * @return 1 on success, 0 on errors.
+ */
+int            dc_accounts_set_event_queue_config (dc_accounts_t* accounts, uint32_t account_id, int capacity, int overflow_policy);
+
+
 /**
  * @class dc_array_t
  *
@@ -3452,6 +4013,50 @@ dc_lot_t*        dc_chatlist_get_summary     (const dc_chatlist_t* chatlist, siz
 dc_lot_t*        dc_chatlist_get_summary2    (dc_context_t* context, uint32_t chat_id, uint32_t msg_id);
 
 
+/**
+ * Get the chat IDs and message IDs of a range of chatlist items at once.
+ *
+ * This is faster than calling dc_chatlist_get_chat_id() and dc_chatlist_get_msg_id()
+ * for each item, especially if the bindings have a high per-call overhead, as JNI.
+ *
+ * @memberof dc_chatlist_t
+ * @param chatlist The chatlist object as created e.g. by dc_get_chatlist().
+ * @param start The index of the first item.
+ * @param count The maximum number of items.
+ * @param ret_chat_ids An array of at least `count` uint32_t the chat IDs are written to.
+ *     May be NULL if the chat IDs are not needed.
+ * @param ret_msg_ids An array of at least `count` uint32_t the message IDs are written to,
+ *     0 for items without a message, see dc_chatlist_get_msg_id().
+ *     May be NULL if the message IDs are not needed.
+ * @return The number of items written, less than `count` if the chatlist ends before.
+ */
+size_t           dc_chatlist_get_ids         (const dc_chatlist_t* chatlist, size_t start, size_t count, uint32_t* ret_chat_ids, uint32_t* ret_msg_ids);
+
+
+/**
+ * Get the summaries of a range of chatlist items at once, in JSON format.
+ *
+ * This is faster than calling dc_chatlist_get_summary() for each item,
+ * especially if the bindings have a high per-call overhead, as JNI.
+ *
+ * The returned JSON array contains an object for each item, with the following key/values:
+ *
+ * - chat_id: The chat ID, see dc_chatlist_get_chat_id().
+ * - msg_id: The message ID, see dc_chatlist_get_msg_id().
+ * - text1, text1_meaning, text2, timestamp, state: The fields of the summary,
+ *   see dc_chatlist_get_summary(). text1 and text2 may be `null`.
+ *
+ * @memberof dc_chatlist_t
+ * @param chatlist The chatlist object as created e.g. by dc_get_chatlist().
+ * @param start The index of the first item.
+ * @param count The maximum number of items, the array is shorter if the chatlist ends before.
+ * @return A UTF8 encoded JSON array.
+ *     Must be freed using dc_str_unref().
+ *     On errors, an empty string is returned, NULL is never returned.
+ */
+char*            dc_chatlist_get_summaries_json (const dc_chatlist_t* chatlist, size_t start, size_t count);
+
+
 /**
  * Helper function to get the associated context object.
  *
@@ -3581,6 +4186,24 @@ char*           dc_chat_get_mailinglist_addr (const dc_chat_t* chat);
 char*           dc_chat_get_name             (const dc_chat_t* chat);
 
 
+/**
+ * Copy the name of the chat into a caller-provided buffer.
+ *
+ * This works like dc_chat_get_name() but does not allocate a new string,
+ * which avoids allocator churn when calling it in tight loops, e.g. to render lists.
+ * If the buffer is too small, an empty string is written;
+ * call the function again with a buffer of at least the returned length plus one byte.
+ *
+ * @memberof dc_chat_t
+ * @param chat The object to query.
+ * @param buf The buffer to copy the null-terminated UTF-8 string to.
+ *     May be NULL to only get the length.
+ * @param buf_len The size of the buffer in bytes.
+ * @return The length of the string in bytes, without the terminating null byte.
+ */
+size_t          dc_chat_get_name_buf         (const dc_chat_t* chat, char* buf, size_t buf_len);
+
+
 /**
  * Get the chat's profile image.
  * For groups, this is the image set by any group member
@@ -3958,6 +4581,24 @@ int64_t          dc_msg_get_sort_timestamp     (const dc_msg_t* msg);
 char*           dc_msg_get_text               (const dc_msg_t* msg);
 
 
+/**
+ * Copy the text of the message into a caller-provided buffer.
+ *
+ * This works like dc_msg_get_text() but does not allocate a new string,
+ * which avoids allocator churn when calling it in tight loops, e.g. to render lists.
+ * If the buffer is too small, an empty string is written;
+ * call the function again with a buffer of at least the returned length plus one byte.
+ *
+ * @memberof dc_msg_t
+ * @param msg The object to query.
+ * @param buf The buffer to copy the null-terminated UTF-8 string to.
+ *     May be NULL to only get the length.
+ * @param buf_len The size of the buffer in bytes.
+ * @return The length of the string in bytes, without the terminating null byte.
+ */
+size_t          dc_msg_get_text_buf           (const dc_msg_t* msg, char* buf, size_t buf_len);
+
+
 /**
  * Get the subject of the e-mail.
  * If there is no subject associated with the message, an empty string is returned.
@@ -3997,6 +4638,9 @@ char*           dc_msg_get_file               (const dc_msg_t* msg);
  * Get a base file name without the path. The base file name includes the extension; the path
  * is not returned. To get the full path, use dc_msg_get_file().
  *
+ * This is the original name of the file, e.g. to be offered when saving it;
+ * the file in the blob directory may have a different name.
+ *
  * @memberof dc_msg_t
  * @param msg The message object.
  * @return The base file name plus the extension without part. If there is no file
@@ -4059,6 +4703,14 @@ char*             dc_msg_get_webxdc_blob      (const dc_msg_t* msg, const char*
  *   currently, this is only true for encrypted Webxdc's in the self chat
  *   that have requested internet access in the manifest.
  *   this is useful for development and maybe for internal integrations at some point.
+ * - capabilities:
+ *   array of capabilities requested by `request_capabilities` in the manifest, e.g. `["realtime", "send_to_chat"]`.
+ *   possible capabilities are `realtime` (see dc_join_webxdc_realtime_channel()),
+ *   `send_to_chat` (`window.webxdc.sendToChat()`)
+ *   and `large_storage` (more web storage than allowed by default).
+ *   the UI may show the capabilities before launching the app
+ *   and has to deny capabilities not listed here.
+ *   joining realtime channels is denied by the core already.
  *
  * @memberof dc_msg_t
  * @param msg The webxdc instance.
@@ -4889,6 +5541,24 @@ char*           dc_contact_get_auth_name     (const dc_contact_t* contact);
 char*           dc_contact_get_display_name  (const dc_contact_t* contact);
 
 
+/**
+ * Copy the display name of the contact into a caller-provided buffer.
+ *
+ * This works like dc_contact_get_display_name() but does not allocate a new string,
+ * which avoids allocator churn when calling it in tight loops, e.g. to render lists.
+ * If the buffer is too small, an empty string is written;
+ * call the function again with a buffer of at least the returned length plus one byte.
+ *
+ * @memberof dc_contact_t
+ * @param contact The object to query.
+ * @param buf The buffer to copy the null-terminated UTF-8 string to.
+ *     May be NULL to only get the length.
+ * @param buf_len The size of the buffer in bytes.
+ * @return The length of the string in bytes, without the terminating null byte.
+ */
+size_t          dc_contact_get_display_name_buf (const dc_contact_t* contact, char* buf, size_t buf_len);
+
+
 // dc_contact_get_first_name is removed,
 // the following define is to make upgrading more smoothly.
 #define         dc_contact_get_first_name    dc_contact_get_display_name
@@ -5247,6 +5917,44 @@ char*           dc_lot_get_text1         (const dc_lot_t* lot);
 char*           dc_lot_get_text2         (const dc_lot_t* lot);
 
 
+/**
+ * Copy the first text of the lot into a caller-provided buffer.
+ *
+ * This works like dc_lot_get_text1() but does not allocate a new string,
+ * which avoids allocator churn when calling it in tight loops, e.g. to render lists.
+ * If the buffer is too small, an empty string is written;
+ * call the function again with a buffer of at least the returned length plus one byte.
+ * If there is no such text, an empty string is written and 0 is returned.
+ *
+ * @memberof dc_lot_t
+ * @param lot The object to query.
+ * @param buf The buffer to copy the null-terminated UTF-8 string to.
+ *     May be NULL to only get the length.
+ * @param buf_len The size of the buffer in bytes.
+ * @return The length of the string in bytes, without the terminating null byte.
+ */
+size_t          dc_lot_get_text1_buf     (const dc_lot_t* lot, char* buf, size_t buf_len);
+
+
+/**
+ * Copy the second text of the lot into a caller-provided buffer.
+ *
+ * This works like dc_lot_get_text2() but does not allocate a new string,
+ * which avoids allocator churn when calling it in tight loops, e.g. to render lists.
+ * If the buffer is too small, an empty string is written;
+ * call the function again with a buffer of at least the returned length plus one byte.
+ * If there is no such text, an empty string is written and 0 is returned.
+ *
+ * @memberof dc_lot_t
+ * @param lot The object to query.
+ * @param buf The buffer to copy the null-terminated UTF-8 string to.
+ *     May be NULL to only get the length.
+ * @param buf_len The size of the buffer in bytes.
+ * @return The length of the string in bytes, without the terminating null byte.
+ */
+size_t          dc_lot_get_text2_buf     (const dc_lot_t* lot, char* buf, size_t buf_len);
+
+
 /**
  * Get the meaning of the first string. Possible meanings of the string are defined by the creator of the object and may be returned e.g.
  * as DC_TEXT1_DRAFT, DC_TEXT1_USERNAME or DC_TEXT1_SELF.
@@ -5719,6 +6427,23 @@ void dc_jsonrpc_request(dc_jsonrpc_instance_t* jsonrpc_instance, const char* req
  */
 char* dc_jsonrpc_next_response(dc_jsonrpc_instance_t* jsonrpc_instance);
 
+/**
+ * Makes a synchronous jsonrpc request and returns its response.
+ * This allows bindings to move to the JSON-RPC API one call at a time,
+ * without having to dispatch the responses of dc_jsonrpc_next_response().
+ *
+ * The response is not returned by dc_jsonrpc_next_response().
+ * Do not call this function from the UI thread,
+ * as some methods, e.g. `configure`, take a long time.
+ *
+ * @memberof dc_jsonrpc_instance_t
+ * @param jsonrpc_instance jsonrpc instance as returned from dc_jsonrpc_init().
+ * @param request JSON-RPC request as string.
+ * @return JSON-RPC response as string, must be freed using dc_str_unref() after usage.
+ *     NULL is returned for notifications, which have no response, and on errors.
+ */
+char* dc_jsonrpc_blocking_call(dc_jsonrpc_instance_t* jsonrpc_instance, const char* request);
+
 /**
  * @class dc_event_emitter_t
  *
@@ -5831,6 +6556,93 @@ char* dc_event_get_data2_str(dc_event_t* event);
 uint32_t dc_event_get_account_id(dc_event_t* event);
 
 
+/**
+ * Get the chat ID of an event.
+ * Unlike dc_event_get_data1_int(), this works for all events concerning a chat,
+ * e.g. #DC_EVENT_MSGS_CHANGED, #DC_EVENT_REACTIONS_CHANGED, #DC_EVENT_INCOMING_MSG,
+ * #DC_EVENT_MSGS_NOTICED, #DC_EVENT_MSG_READ, #DC_EVENT_CHAT_MODIFIED
+ * or #DC_EVENT_CHAT_EPHEMERAL_TIMER_MODIFIED.
+ *
+ * @memberof dc_event_t
+ * @param event The event object as returned from dc_get_next_event().
+ * @return The chat ID, 0 if the event does not concern a single chat.
+ */
+uint32_t dc_event_get_chat_id(dc_event_t* event);
+
+
+/**
+ * Get the message ID of an event,
+ * e.g. of #DC_EVENT_MSGS_CHANGED, #DC_EVENT_REACTIONS_CHANGED, #DC_EVENT_INCOMING_MSG,
+ * #DC_EVENT_MSG_READ, #DC_EVENT_WEBXDC_STATUS_UPDATE, #DC_EVENT_WEBXDC_INSTANCE_DELETED
+ * or #DC_EVENT_WEBXDC_REALTIME_DATA.
+ *
+ * @memberof dc_event_t
+ * @param event The event object as returned from dc_get_next_event().
+ * @return The message ID, 0 if the event does not concern a single message.
+ */
+uint32_t dc_event_get_msg_id(dc_event_t* event);
+
+
+/**
+ * Get the message IDs of an event.
+ * For #DC_EVENT_INCOMING_MSG_BUNCH, these are all the received messages,
+ * so there is no need to parse the JSON returned by dc_event_get_data2_str().
+ * For other events, the array contains the ID returned by dc_event_get_msg_id(), if any.
+ *
+ * @memberof dc_event_t
+ * @param event The event object as returned from dc_get_next_event().
+ * @return An array of message IDs, must be dc_array_unref()'d when no longer used.
+ */
+dc_array_t* dc_event_get_msg_ids(dc_event_t* event);
+
+
+/**
+ * Get the contact ID of an event,
+ * e.g. the contact who reacted for #DC_EVENT_REACTIONS_CHANGED
+ * or the contact of #DC_EVENT_CONTACTS_CHANGED, #DC_EVENT_LOCATION_CHANGED,
+ * #DC_EVENT_SECUREJOIN_INVITER_PROGRESS and #DC_EVENT_SECUREJOIN_JOINER_PROGRESS.
+ *
+ * @memberof dc_event_t
+ * @param event The event object as returned from dc_get_next_event().
+ * @return The contact ID, 0 if the event does not concern a single contact.
+ */
+uint32_t dc_event_get_contact_id(dc_event_t* event);
+
+
+/**
+ * Get the progress of #DC_EVENT_CONFIGURE_PROGRESS, #DC_EVENT_IMEX_PROGRESS,
+ * #DC_EVENT_DB_MAINTENANCE_PROGRESS,
+ * #DC_EVENT_SECUREJOIN_INVITER_PROGRESS or #DC_EVENT_SECUREJOIN_JOINER_PROGRESS.
+ *
+ * @memberof dc_event_t
+ * @param event The event object as returned from dc_get_next_event().
+ * @return The progress in permille: 0=error, 1-999=progress, 1000=success.
+ *     0 for other events.
+ */
+int dc_event_get_progress(dc_event_t* event);
+
+
+/**
+ * Get the serial of the status update of #DC_EVENT_WEBXDC_STATUS_UPDATE.
+ * The update can be retrieved using dc_get_webxdc_status_updates().
+ *
+ * @memberof dc_event_t
+ * @param event The event object as returned from dc_get_next_event().
+ * @return The status update serial, 0 for other events.
+ */
+uint32_t dc_event_get_status_update_serial(dc_event_t* event);
+
+
+/**
+ * Get the new timer of #DC_EVENT_CHAT_EPHEMERAL_TIMER_MODIFIED.
+ *
+ * @memberof dc_event_t
+ * @param event The event object as returned from dc_get_next_event().
+ * @return The ephemeral timer in seconds, 0 if the timer is disabled or for other events.
+ */
+uint32_t dc_event_get_ephemeral_timer(dc_event_t* event);
+
+
 /**
  * Free memory used by an event object.
  * If you forget to do this for an event, this will result in memory leakage.
@@ -6056,6 +6868,21 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_MSG_FAILED               2012
 
 
+/**
+ * Sending a message was given up because of a permanent error
+ * or because the number of attempts exceeded the `smtp_max_attempts` option.
+ * The message is removed from the sending queue and will not be retried,
+ * the error can be retrieved using dc_msg_get_error().
+ *
+ * This event is emitted after #DC_EVENT_MSG_FAILED
+ * and may be used to show a "could not deliver" notification.
+ *
+ * @param data1 (int) chat_id
+ * @param data2 (int) msg_id
+ */
+#define DC_EVENT_MSG_GIVEN_UP             2013
+
+
 /**
  * A single message is read by the receiver. State changed from @ref DC_STATE_OUT_DELIVERED to
  * @ref DC_STATE_OUT_MDN_RCVD.
@@ -6138,6 +6965,27 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_IMEX_FILE_WRITTEN        2052
 
 
+/**
+ * Inform about the progress of a database maintenance
+ * started using the JSON-RPC method `run_db_maintenance`.
+ *
+ * @param data1 (int) 0=error, 1-999=progress in permille, 1000=success and done
+ * @param data2 0
+ */
+#define DC_EVENT_DB_MAINTENANCE_PROGRESS  2055
+
+
+/**
+ * Housekeeping finished.
+ * Housekeeping runs automatically once a day while IO is running,
+ * the interval can be changed with the config option `housekeeping_interval`.
+ *
+ * @param data1 (int) Number of unused files removed from the blob directory.
+ * @param data2 (int) Number of messages whose media was deleted because of `media_cleanup_after`.
+ */
+#define DC_EVENT_HOUSEKEEPING_DONE        2056
+
+
 /**
  * Progress information of a secure-join handshake from the view of the inviter
  * (Alice, the person who shows the QR code).
@@ -6189,6 +7037,31 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_SELFAVATAR_CHANGED               2110
 
 
+/**
+ * The mailbox usage crossed one of the thresholds
+ * set by the options `quota_warn_threshold` and `quota_error_threshold`.
+ * The core also adds a device message in this case,
+ * the UI may additionally show a more prominent warning.
+ * Details can be shown using dc_get_connectivity_html().
+ *
+ * @param data1 (int) Usage of the most used quota resource in percent.
+ * @param data2 0
+ */
+#define DC_EVENT_QUOTA_WARNING                    2115
+
+
+/**
+ * An OAuth2 access token is needed, see dc_set_oauth2_token_requests().
+ *
+ * The UI should obtain a token for the given address
+ * and pass it to dc_set_oauth2_access_token().
+ *
+ * @param data1 (int) 1 if the previous token was rejected and a fresh one is needed, 0 otherwise.
+ * @param data2 (char*) E-mail address the token is requested for.
+ */
+#define DC_EVENT_OAUTH2_TOKEN_REQUESTED           2116
+
+
 /**
  * webxdc status update received.
  * To get the received status update, use dc_get_webxdc_status_updates() with
@@ -6214,6 +7087,33 @@ void dc_event_unref(dc_event_t* event);
 #define DC_EVENT_WEBXDC_INSTANCE_DELETED          2121
 
 
+/**
+ * Data received over the realtime channel of a webxdc instance,
+ * see dc_join_webxdc_realtime_channel().
+ *
+ * The data may contain zero bytes,
+ * the pointer returned by dc_event_get_data2_str() is not zero-terminated
+ * and must be released using dc_str_unref() as usual.
+ *
+ * @param data1 (int) msg_id
+ * @param data2 (int) Number of bytes of the data.
+ *     The data itself is returned by dc_event_get_data2_str().
+ */
+#define DC_EVENT_WEBXDC_REALTIME_DATA             2150
+
+
+/**
+ * Events were dropped because the application did not receive events
+ * and the event channel was full.
+ * Returned by the event emitter before the events remaining in the channel.
+ * The channel size can be configured with dc_accounts_set_event_queue_config().
+ *
+ * @param data1 (int) Number of dropped events.
+ * @param data2 0
+ */
+#define DC_EVENT_EVENT_QUEUE_OVERFLOW             2200
+
+
 /**
  * @}
  */
@@ -6238,6 +7138,32 @@ void dc_event_unref(dc_event_t* event);
 #define DC_MEDIA_QUALITY_WORSE    1
 
 
+/*
+ * Values for dc_get|set_config("network_class")
+ * and dc_accounts_set_network_class()
+ */
+#define DC_NETWORK_CLASS_UNMETERED 0
+#define DC_NETWORK_CLASS_METERED   1
+#define DC_NETWORK_CLASS_ROAMING   2
+
+
+/*
+ * Values for dc_get|set_config("notification_privacy")
+ */
+#define DC_NOTIFICATION_PRIVACY_FULL        0
+#define DC_NOTIFICATION_PRIVACY_SENDER_ONLY 1
+#define DC_NOTIFICATION_PRIVACY_GENERIC     2
+
+
+/*
+ * Values returned by dc_get_push_state()
+ */
+#define DC_PUSH_NO_TOKEN      0
+#define DC_PUSH_NOT_SUPPORTED 1
+#define DC_PUSH_PENDING       2
+#define DC_PUSH_REGISTERED    3
+
+
 /*
  * Values for dc_get|set_config("key_gen_type")
  */
@@ -6246,6 +7172,13 @@ void dc_event_unref(dc_event_t* event);
 #define DC_KEY_GEN_ED25519 2
 
 
+/*
+ * Values for dc_accounts_set_event_queue_config()
+ */
+#define DC_EVENT_QUEUE_DROP_OLDEST       0
+#define DC_EVENT_QUEUE_DROP_LOW_PRIORITY 1
+
+
 /**
  * @defgroup DC_PROVIDER_STATUS DC_PROVIDER_STATUS
  *
@@ -7210,6 +8143,32 @@ void dc_event_unref(dc_event_t* event);
 /// Used as a device message after a successful backup transfer.
 #define DC_STR_BACKUP_TRANSFER_MSG_BODY 163
 
+/// "%1$s messages from %2$s"
+///
+/// Used as summary of a notification for several messages from the same sender.
+/// `%1$s` will be replaced by the number of messages.
+/// `%2$s` will be replaced by the name of the sender.
+#define DC_STR_NOTIFICATION_MSGS_FROM 164
+
+/// "%1$s messages in %2$s"
+///
+/// Used as summary of a notification for several messages in a chat.
+/// `%1$s` will be replaced by the number of messages.
+/// `%2$s` will be replaced by the name of the chat.
+#define DC_STR_NOTIFICATION_MSGS_IN 165
+
+/// "New message"
+///
+/// Used in notifications instead of the message content
+/// if `notification_privacy` hides it.
+#define DC_STR_NEW_MSG 166
+
+/// "%1$s new messages"
+///
+/// Used in notifications if `notification_privacy` hides the chat and sender.
+/// `%1$s` will be replaced by the number of messages.
+#define DC_STR_NEW_MSGS 167
+
 /**
  * @}
  */
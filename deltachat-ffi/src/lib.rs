@@ -17,6 +17,7 @@
 use std::fmt::Write;
 use std::future::Future;
 use std::ops::Deref;
+use std::path::Path;
 use std::ptr;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -24,7 +25,7 @@
 
 use anyhow::Context as _;
 use deltachat::chat::{ChatId, ChatVisibility, MessageListOptions, MuteDuration, ProtectionStatus};
-use deltachat::constants::DC_MSG_ID_LAST_SPECIAL;
+use deltachat::constants::{NetworkClass, DC_MSG_ID_LAST_SPECIAL};
 use deltachat::contact::{Contact, ContactId, Origin};
 use deltachat::context::Context;
 use deltachat::ephemeral::Timer as EphemeralTimer;
@@ -32,6 +33,8 @@
 use deltachat::key::DcKey;
 use deltachat::message::MsgId;
 use deltachat::net::read_url_blob;
+use deltachat::notifications::QuietHours;
+use deltachat::push::PushState;
 use deltachat::qr_code_generator::{generate_backup_qr, get_securejoin_qr_svg};
 use deltachat::reaction::{get_msg_reactions, send_reaction, Reactions};
 use deltachat::stock_str::StockMessage;
@@ -50,9 +53,11 @@
 mod lot;
 
 mod string;
+mod unwind;
 use deltachat::chatlist::Chatlist;
 
 use self::string::*;
+use self::unwind::ffi_boundary;
 
 // as C lacks a good and portable error handling,
 // in general, the C Interface is forgiving wrt to bad parameters.
@@ -95,57 +100,61 @@ fn spawn<T>(fut: T) -> JoinHandle<T::Output>
     dbfile: *const libc::c_char,
     blobdir: *const libc::c_char,
 ) -> *mut dc_context_t {
-    setup_panic!();
+    ffi_boundary("dc_context_new", ptr::null(), || {
+        setup_panic!();
 
-    if dbfile.is_null() {
-        eprintln!("ignoring careless call to dc_context_new()");
-        return ptr::null_mut();
-    }
+        if dbfile.is_null() {
+            eprintln!("ignoring careless call to dc_context_new()");
+            return ptr::null_mut();
+        }
 
-    let ctx = if blobdir.is_null() || *blobdir == 0 {
-        // generate random ID as this functionality is not yet available on the C-api.
-        let id = rand::thread_rng().gen();
-        block_on(Context::new(
-            as_path(dbfile),
-            id,
-            Events::new(),
-            StockStrings::new(),
-        ))
-    } else {
-        eprintln!("blobdir can not be defined explicitly anymore");
-        return ptr::null_mut();
-    };
-    match ctx {
-        Ok(ctx) => Box::into_raw(Box::new(ctx)),
-        Err(err) => {
-            eprintln!("failed to create context: {err:#}");
-            ptr::null_mut()
+        let ctx = if blobdir.is_null() || *blobdir == 0 {
+            // generate random ID as this functionality is not yet available on the C-api.
+            let id = rand::thread_rng().gen();
+            block_on(Context::new(
+                as_path(dbfile),
+                id,
+                Events::new(),
+                StockStrings::new(),
+            ))
+        } else {
+            eprintln!("blobdir can not be defined explicitly anymore");
+            return ptr::null_mut();
+        };
+        match ctx {
+            Ok(ctx) => Box::into_raw(Box::new(ctx)),
+            Err(err) => {
+                eprintln!("failed to create context: {err:#}");
+                ptr::null_mut()
+            }
         }
-    }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_context_new_closed(dbfile: *const libc::c_char) -> *mut dc_context_t {
-    setup_panic!();
+    ffi_boundary("dc_context_new_closed", ptr::null(), || {
+        setup_panic!();
 
-    if dbfile.is_null() {
-        eprintln!("ignoring careless call to dc_context_new_closed()");
-        return ptr::null_mut();
-    }
+        if dbfile.is_null() {
+            eprintln!("ignoring careless call to dc_context_new_closed()");
+            return ptr::null_mut();
+        }
 
-    let id = rand::thread_rng().gen();
-    match block_on(Context::new_closed(
-        as_path(dbfile),
-        id,
-        Events::new(),
-        StockStrings::new(),
-    )) {
-        Ok(context) => Box::into_raw(Box::new(context)),
-        Err(err) => {
-            eprintln!("failed to create context: {err:#}");
-            ptr::null_mut()
+        let id = rand::thread_rng().gen();
+        match block_on(Context::new_closed(
+            as_path(dbfile),
+            id,
+            Events::new(),
+            StockStrings::new(),
+        )) {
+            Ok(context) => Box::into_raw(Box::new(context)),
+            Err(err) => {
+                eprintln!("failed to create context: {err:#}");
+                ptr::null_mut()
+            }
         }
-    }
+    })
 }
 
 #[no_mangle]
@@ -153,29 +162,33 @@ fn spawn<T>(fut: T) -> JoinHandle<T::Output>
     context: *mut dc_context_t,
     passphrase: *const libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_context_open()");
-        return 0;
-    }
+    ffi_boundary("dc_context_open", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_context_open()");
+            return 0;
+        }
 
-    let ctx = &*context;
-    let passphrase = to_string_lossy(passphrase);
-    block_on(ctx.open(passphrase))
-        .context("dc_context_open() failed")
-        .log_err(ctx)
-        .map(|b| b as libc::c_int)
-        .unwrap_or(0)
+        let ctx = &*context;
+        let passphrase = to_string_lossy(passphrase);
+        block_on(ctx.open(passphrase))
+            .context("dc_context_open() failed")
+            .log_err(ctx)
+            .map(|b| b as libc::c_int)
+            .unwrap_or(0)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_context_is_open(context: *mut dc_context_t) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_context_is_open()");
-        return 0;
-    }
+    ffi_boundary("dc_context_is_open", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_context_is_open()");
+            return 0;
+        }
 
-    let ctx = &*context;
-    block_on(ctx.is_open()) as libc::c_int
+        let ctx = &*context;
+        block_on(ctx.is_open()) as libc::c_int
+    })
 }
 
 /// Release the context structure.
@@ -183,21 +196,53 @@ fn spawn<T>(fut: T) -> JoinHandle<T::Output>
 /// This function releases the memory of the `dc_context_t` structure.
 #[no_mangle]
 pub unsafe extern "C" fn dc_context_unref(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_context_unref()");
-        return;
-    }
-    drop(Box::from_raw(context));
+    ffi_boundary("dc_context_unref", ptr::null(), || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_context_unref()");
+            return;
+        }
+        drop(Box::from_raw(context));
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_blobdir(context: *mut dc_context_t) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_blobdir()");
-        return "".strdup();
-    }
-    let ctx = &*context;
-    ctx.get_blobdir().to_string_lossy().strdup()
+    ffi_boundary("dc_get_blobdir", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_blobdir()");
+            return "".strdup();
+        }
+        let ctx = &*context;
+        ctx.get_blobdir().to_string_lossy().strdup()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_blob_bytes(
+    context: *mut dc_context_t,
+    path: *const libc::c_char,
+    ret_bytes: *mut libc::size_t,
+) -> *mut libc::c_char {
+    ffi_boundary("dc_get_blob_bytes", context, || {
+        if context.is_null() || path.is_null() || ret_bytes.is_null() {
+            eprintln!("ignoring careless call to dc_get_blob_bytes()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
+        let path = to_string_lossy(path);
+        let blob = block_on(ctx.read_blob_chunk(Path::new(&path), 0, u64::MAX))
+            .context("dc_get_blob_bytes() failed")
+            .log_err(ctx);
+        match blob {
+            Ok(blob) => {
+                *ret_bytes = blob.len();
+                let ptr = libc::malloc(*ret_bytes);
+                libc::memcpy(ptr, blob.as_ptr() as *mut libc::c_void, *ret_bytes);
+                ptr as *mut libc::c_char
+            }
+            Err(_) => ptr::null_mut(),
+        }
+    })
 }
 
 #[no_mangle]
@@ -206,37 +251,39 @@ fn spawn<T>(fut: T) -> JoinHandle<T::Output>
     key: *const libc::c_char,
     value: *const libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() || key.is_null() {
-        eprintln!("ignoring careless call to dc_set_config()");
-        return 0;
-    }
-    let ctx = &*context;
-    let key = to_string_lossy(key);
-    let value = to_opt_string_lossy(value);
+    ffi_boundary("dc_set_config", context, || {
+        if context.is_null() || key.is_null() {
+            eprintln!("ignoring careless call to dc_set_config()");
+            return 0;
+        }
+        let ctx = &*context;
+        let key = to_string_lossy(key);
+        let value = to_opt_string_lossy(value);
 
-    block_on(async move {
-        if key.starts_with("ui.") {
-            ctx.set_ui_config(&key, value.as_deref())
-                .await
-                .with_context(|| format!("dc_set_config failed: Can't set {key} to {value:?}"))
-                .log_err(ctx)
-                .is_ok() as libc::c_int
-        } else {
-            match config::Config::from_str(&key) {
-                Ok(key) => ctx
-                    .set_config(key, value.as_deref())
+        block_on(async move {
+            if key.starts_with("ui.") {
+                ctx.set_ui_config(&key, value.as_deref())
                     .await
-                    .with_context(|| {
-                        format!("dc_set_config() failed: Can't set {key} to {value:?}")
-                    })
+                    .with_context(|| format!("dc_set_config failed: Can't set {key} to {value:?}"))
                     .log_err(ctx)
-                    .is_ok() as libc::c_int,
-                Err(_) => {
-                    warn!(ctx, "dc_set_config(): invalid key");
-                    0
+                    .is_ok() as libc::c_int
+            } else {
+                match config::Config::from_str(&key) {
+                    Ok(key) => ctx
+                        .set_config(key, value.as_deref())
+                        .await
+                        .with_context(|| {
+                            format!("dc_set_config() failed: Can't set {key} to {value:?}")
+                        })
+                        .log_err(ctx)
+                        .is_ok() as libc::c_int,
+                    Err(_) => {
+                        warn!(ctx, "dc_set_config(): invalid key");
+                        0
+                    }
                 }
             }
-        }
+        })
     })
 }
 
@@ -245,39 +292,41 @@ fn spawn<T>(fut: T) -> JoinHandle<T::Output>
     context: *mut dc_context_t,
     key: *const libc::c_char,
 ) -> *mut libc::c_char {
-    if context.is_null() || key.is_null() {
-        eprintln!("ignoring careless call to dc_get_config()");
-        return "".strdup();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_config", context, || {
+        if context.is_null() || key.is_null() {
+            eprintln!("ignoring careless call to dc_get_config()");
+            return "".strdup();
+        }
+        let ctx = &*context;
 
-    let key = to_string_lossy(key);
+        let key = to_string_lossy(key);
 
-    block_on(async move {
-        if key.starts_with("ui.") {
-            ctx.get_ui_config(&key)
-                .await
-                .context("Can't get ui-config")
-                .log_err(ctx)
-                .unwrap_or_default()
-                .unwrap_or_default()
-                .strdup()
-        } else {
-            match config::Config::from_str(&key) {
-                Ok(key) => ctx
-                    .get_config(key)
+        block_on(async move {
+            if key.starts_with("ui.") {
+                ctx.get_ui_config(&key)
                     .await
-                    .context("Can't get config")
+                    .context("Can't get ui-config")
                     .log_err(ctx)
                     .unwrap_or_default()
                     .unwrap_or_default()
-                    .strdup(),
-                Err(_) => {
-                    warn!(ctx, "dc_get_config(): invalid key '{}'", &key);
-                    "".strdup()
+                    .strdup()
+            } else {
+                match config::Config::from_str(&key) {
+                    Ok(key) => ctx
+                        .get_config(key)
+                        .await
+                        .context("Can't get config")
+                        .log_err(ctx)
+                        .unwrap_or_default()
+                        .unwrap_or_default()
+                        .strdup(),
+                    Err(_) => {
+                        warn!(ctx, "dc_get_config(): invalid key '{}'", &key);
+                        "".strdup()
+                    }
                 }
             }
-        }
+        })
     })
 }
 
@@ -287,27 +336,29 @@ fn spawn<T>(fut: T) -> JoinHandle<T::Output>
     stock_id: u32,
     stock_msg: *mut libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() || stock_msg.is_null() {
-        eprintln!("ignoring careless call to dc_set_stock_string");
-        return 0;
-    }
-    let msg = to_string_lossy(stock_msg);
-    let ctx = &*context;
-
-    block_on(async move {
-        match StockMessage::from_u32(stock_id) {
-            Some(id) => match ctx.set_stock_translation(id, msg).await {
-                Ok(()) => 1,
-                Err(err) => {
-                    warn!(ctx, "set_stock_translation failed: {err:#}");
+    ffi_boundary("dc_set_stock_translation", context, || {
+        if context.is_null() || stock_msg.is_null() {
+            eprintln!("ignoring careless call to dc_set_stock_string");
+            return 0;
+        }
+        let msg = to_string_lossy(stock_msg);
+        let ctx = &*context;
+
+        block_on(async move {
+            match StockMessage::from_u32(stock_id) {
+                Some(id) => match ctx.set_stock_translation(id, msg).await {
+                    Ok(()) => 1,
+                    Err(err) => {
+                        warn!(ctx, "set_stock_translation failed: {err:#}");
+                        0
+                    }
+                },
+                None => {
+                    warn!(ctx, "invalid stock message id {stock_id}");
                     0
                 }
-            },
-            None => {
-                warn!(ctx, "invalid stock message id {stock_id}");
-                0
             }
-        }
+        })
     })
 }
 
@@ -316,39 +367,54 @@ fn spawn<T>(fut: T) -> JoinHandle<T::Output>
     context: *mut dc_context_t,
     qr: *mut libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() || qr.is_null() {
-        eprintln!("ignoring careless call to dc_set_config_from_qr");
-        return 0;
-    }
-    let qr = to_string_lossy(qr);
-    let ctx = &*context;
+    ffi_boundary("dc_set_config_from_qr", context, || {
+        if context.is_null() || qr.is_null() {
+            eprintln!("ignoring careless call to dc_set_config_from_qr");
+            return 0;
+        }
+        let qr = to_string_lossy(qr);
+        let ctx = &*context;
 
-    block_on(async move {
-        match qr::set_config_from_qr(ctx, &qr).await {
-            Ok(()) => 1,
-            Err(err) => {
-                error!(ctx, "Failed to create account from QR code: {err:#}");
-                0
+        block_on(async move {
+            match qr::set_config_from_qr(ctx, &qr).await {
+                Ok(()) => 1,
+                Err(err) => {
+                    error!(ctx, "Failed to create account from QR code: {err:#}");
+                    0
+                }
             }
-        }
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_capabilities() -> *mut libc::c_char {
+    ffi_boundary("dc_get_capabilities", ptr::null(), || {
+        let mut capabilities = deltachat::context::get_capabilities();
+        capabilities.insert("jsonrpc", cfg!(feature = "jsonrpc"));
+        serde_json::to_string(&capabilities)
+            .unwrap_or_default()
+            .strdup()
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_info(context: *const dc_context_t) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_info()");
-        return "".strdup();
-    }
-    let ctx = &*context;
-    block_on(async move {
-        match ctx.get_info().await {
-            Ok(info) => render_info(info).unwrap_or_default().strdup(),
-            Err(err) => {
-                warn!(ctx, "failed to get info: {err:#}");
-                "".strdup()
-            }
+    ffi_boundary("dc_get_info", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_info()");
+            return "".strdup();
         }
+        let ctx = &*context;
+        block_on(async move {
+            match ctx.get_info().await {
+                Ok(info) => render_info(info).unwrap_or_default().strdup(),
+                Err(err) => {
+                    warn!(ctx, "failed to get info: {err:#}");
+                    "".strdup()
+                }
+            }
+        })
     })
 }
 
@@ -365,42 +431,48 @@ fn render_info(
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_connectivity(context: *const dc_context_t) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_connectivity()");
-        return 0;
-    }
-    let ctx = &*context;
-    block_on(async move { ctx.get_connectivity().await as u32 as libc::c_int })
+    ffi_boundary("dc_get_connectivity", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_connectivity()");
+            return 0;
+        }
+        let ctx = &*context;
+        block_on(async move { ctx.get_connectivity().await as u32 as libc::c_int })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_connectivity_html(
     context: *const dc_context_t,
 ) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_connectivity_html()");
-        return "".strdup();
-    }
-    let ctx = &*context;
-    block_on(async move {
-        match ctx.get_connectivity_html().await {
-            Ok(html) => html.strdup(),
-            Err(err) => {
-                error!(ctx, "Failed to get connectivity html: {err:#}");
-                "".strdup()
-            }
+    ffi_boundary("dc_get_connectivity_html", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_connectivity_html()");
+            return "".strdup();
         }
+        let ctx = &*context;
+        block_on(async move {
+            match ctx.get_connectivity_html().await {
+                Ok(html) => html.strdup(),
+                Err(err) => {
+                    error!(ctx, "Failed to get connectivity html: {err:#}");
+                    "".strdup()
+                }
+            }
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_all_work_done(context: *mut dc_context_t) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_all_work_done()");
-        return 0;
-    }
-    let ctx = &*context;
-    block_on(async move { ctx.all_work_done().await as libc::c_int })
+    ffi_boundary("dc_all_work_done", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_all_work_done()");
+            return 0;
+        }
+        let ctx = &*context;
+        block_on(async move { ctx.all_work_done().await as libc::c_int })
+    })
 }
 
 #[no_mangle]
@@ -409,23 +481,62 @@ fn render_info(
     addr: *const libc::c_char,
     redirect: *const libc::c_char,
 ) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_oauth2_url()");
-        return ptr::null_mut(); // NULL explicitly defined as "unknown"
-    }
-    let ctx = &*context;
-    let addr = to_string_lossy(addr);
-    let redirect = to_string_lossy(redirect);
+    ffi_boundary("dc_get_oauth2_url", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_oauth2_url()");
+            return ptr::null_mut(); // NULL explicitly defined as "unknown"
+        }
+        let ctx = &*context;
+        let addr = to_string_lossy(addr);
+        let redirect = to_string_lossy(redirect);
 
-    block_on(async move {
-        match oauth2::get_oauth2_url(ctx, &addr, &redirect)
-            .await
-            .context("dc_get_oauth2_url failed")
-            .log_err(ctx)
-        {
-            Ok(Some(res)) => res.strdup(),
-            Ok(None) | Err(_) => ptr::null_mut(),
+        block_on(async move {
+            match oauth2::get_oauth2_url(ctx, &addr, &redirect)
+                .await
+                .context("dc_get_oauth2_url failed")
+                .log_err(ctx)
+            {
+                Ok(Some(res)) => res.strdup(),
+                Ok(None) | Err(_) => ptr::null_mut(),
+            }
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_oauth2_token_requests(
+    context: *mut dc_context_t,
+    enabled: libc::c_int,
+) {
+    ffi_boundary("dc_set_oauth2_token_requests", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_oauth2_token_requests()");
+            return;
+        }
+        let ctx = &*context;
+        ctx.set_oauth2_token_requests(enabled != 0);
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_oauth2_access_token(
+    context: *mut dc_context_t,
+    token: *const libc::c_char,
+) {
+    ffi_boundary("dc_set_oauth2_access_token", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_oauth2_access_token()");
+            return;
         }
+        let ctx = &*context;
+        let token = to_opt_string_lossy(token);
+        block_on(async move {
+            ctx.set_oauth2_access_token(token.as_deref())
+                .await
+                .context("dc_set_oauth2_access_token failed")
+                .log_err(ctx)
+                .ok();
+        })
     })
 }
 
@@ -440,278 +551,478 @@ fn spawn_configure(ctx: Context) {
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_configure(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_configure()");
-        return;
-    }
+    ffi_boundary("dc_configure", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_configure()");
+            return;
+        }
 
-    let ctx = &*context;
-    spawn_configure(ctx.clone());
+        let ctx = &*context;
+        spawn_configure(ctx.clone());
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_is_configured(context: *mut dc_context_t) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_is_configured()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_is_configured", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_is_configured()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ctx.is_configured()
-            .await
-            .context("failed to get configured state")
-            .log_err(ctx)
-            .unwrap_or_default() as libc::c_int
+        block_on(async move {
+            ctx.is_configured()
+                .await
+                .context("failed to get configured state")
+                .log_err(ctx)
+                .unwrap_or_default() as libc::c_int
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_start_io(context: *mut dc_context_t) {
-    if context.is_null() {
-        return;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_start_io", context, || {
+        if context.is_null() {
+            return;
+        }
+        let ctx = &*context;
 
-    block_on(ctx.start_io())
+        block_on(ctx.start_io())
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_id(context: *mut dc_context_t) -> libc::c_int {
-    if context.is_null() {
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_id", context, || {
+        if context.is_null() {
+            return 0;
+        }
+        let ctx = &*context;
 
-    ctx.get_id() as libc::c_int
+        ctx.get_id() as libc::c_int
+    })
 }
 
 pub type dc_event_t = Event;
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_event_unref(a: *mut dc_event_t) {
-    if a.is_null() {
-        eprintln!("ignoring careless call to dc_event_unref()");
-        return;
-    }
+    ffi_boundary("dc_event_unref", ptr::null(), || {
+        if a.is_null() {
+            eprintln!("ignoring careless call to dc_event_unref()");
+            return;
+        }
 
-    drop(Box::from_raw(a));
+        drop(Box::from_raw(a));
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_event_get_id(event: *mut dc_event_t) -> libc::c_int {
-    if event.is_null() {
-        eprintln!("ignoring careless call to dc_event_get_id()");
-        return 0;
-    }
+    ffi_boundary("dc_event_get_id", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_id()");
+            return 0;
+        }
 
-    let event = &*event;
-    match event.typ {
-        EventType::Info(_) => 100,
-        EventType::SmtpConnected(_) => 101,
-        EventType::ImapConnected(_) => 102,
-        EventType::SmtpMessageSent(_) => 103,
-        EventType::ImapMessageDeleted(_) => 104,
-        EventType::ImapMessageMoved(_) => 105,
-        EventType::ImapInboxIdle => 106,
-        EventType::NewBlobFile(_) => 150,
-        EventType::DeletedBlobFile(_) => 151,
-        EventType::Warning(_) => 300,
-        EventType::Error(_) => 400,
-        EventType::ErrorSelfNotInGroup(_) => 410,
-        EventType::MsgsChanged { .. } => 2000,
-        EventType::ReactionsChanged { .. } => 2001,
-        EventType::IncomingMsg { .. } => 2005,
-        EventType::IncomingMsgBunch { .. } => 2006,
-        EventType::MsgsNoticed { .. } => 2008,
-        EventType::MsgDelivered { .. } => 2010,
-        EventType::MsgFailed { .. } => 2012,
-        EventType::MsgRead { .. } => 2015,
-        EventType::ChatModified(_) => 2020,
-        EventType::ChatEphemeralTimerModified { .. } => 2021,
-        EventType::ContactsChanged(_) => 2030,
-        EventType::LocationChanged(_) => 2035,
-        EventType::ConfigureProgress { .. } => 2041,
-        EventType::ImexProgress(_) => 2051,
-        EventType::ImexFileWritten(_) => 2052,
-        EventType::SecurejoinInviterProgress { .. } => 2060,
-        EventType::SecurejoinJoinerProgress { .. } => 2061,
-        EventType::ConnectivityChanged => 2100,
-        EventType::SelfavatarChanged => 2110,
-        EventType::WebxdcStatusUpdate { .. } => 2120,
-        EventType::WebxdcInstanceDeleted { .. } => 2121,
-    }
+        let event = &*event;
+        match event.typ {
+            EventType::Info(_) => 100,
+            EventType::SmtpConnected(_) => 101,
+            EventType::ImapConnected(_) => 102,
+            EventType::SmtpMessageSent(_) => 103,
+            EventType::ImapMessageDeleted(_) => 104,
+            EventType::ImapMessageMoved(_) => 105,
+            EventType::ImapInboxIdle => 106,
+            EventType::NewBlobFile(_) => 150,
+            EventType::DeletedBlobFile(_) => 151,
+            EventType::Warning(_) => 300,
+            EventType::Error(_) => 400,
+            EventType::ErrorSelfNotInGroup(_) => 410,
+            EventType::MsgsChanged { .. } => 2000,
+            EventType::ReactionsChanged { .. } => 2001,
+            EventType::IncomingMsg { .. } => 2005,
+            EventType::IncomingMsgBunch { .. } => 2006,
+            EventType::MsgsNoticed { .. } => 2008,
+            EventType::MsgDelivered { .. } => 2010,
+            EventType::MsgFailed { .. } => 2012,
+            EventType::MsgGivenUp { .. } => 2013,
+            EventType::MsgRead { .. } => 2015,
+            EventType::ChatModified(_) => 2020,
+            EventType::ChatEphemeralTimerModified { .. } => 2021,
+            EventType::ContactsChanged(_) => 2030,
+            EventType::LocationChanged(_) => 2035,
+            EventType::ConfigureProgress { .. } => 2041,
+            EventType::ImexProgress(_) => 2051,
+            EventType::ImexFileWritten(_) => 2052,
+            EventType::DbMaintenanceProgress(_) => 2055,
+            EventType::HousekeepingDone { .. } => 2056,
+            EventType::SecurejoinInviterProgress { .. } => 2060,
+            EventType::SecurejoinJoinerProgress { .. } => 2061,
+            EventType::ConnectivityChanged => 2100,
+            EventType::SelfavatarChanged => 2110,
+            EventType::QuotaWarning { .. } => 2115,
+            EventType::Oauth2TokenRequested { .. } => 2116,
+            EventType::WebxdcStatusUpdate { .. } => 2120,
+            EventType::WebxdcInstanceDeleted { .. } => 2121,
+            EventType::WebxdcRealtimeData { .. } => 2150,
+            EventType::EventQueueOverflow { .. } => 2200,
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_event_get_data1_int(event: *mut dc_event_t) -> libc::c_int {
-    if event.is_null() {
-        eprintln!("ignoring careless call to dc_event_get_data1_int()");
-        return 0;
-    }
+    ffi_boundary("dc_event_get_data1_int", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_data1_int()");
+            return 0;
+        }
 
-    let event = &(*event).typ;
-    match event {
-        EventType::Info(_)
-        | EventType::SmtpConnected(_)
-        | EventType::ImapConnected(_)
-        | EventType::SmtpMessageSent(_)
-        | EventType::ImapMessageDeleted(_)
-        | EventType::ImapMessageMoved(_)
-        | EventType::ImapInboxIdle
-        | EventType::NewBlobFile(_)
-        | EventType::DeletedBlobFile(_)
-        | EventType::Warning(_)
-        | EventType::Error(_)
-        | EventType::ConnectivityChanged
-        | EventType::SelfavatarChanged
-        | EventType::IncomingMsgBunch { .. }
-        | EventType::ErrorSelfNotInGroup(_) => 0,
-        EventType::MsgsChanged { chat_id, .. }
-        | EventType::ReactionsChanged { chat_id, .. }
-        | EventType::IncomingMsg { chat_id, .. }
-        | EventType::MsgsNoticed(chat_id)
-        | EventType::MsgDelivered { chat_id, .. }
-        | EventType::MsgFailed { chat_id, .. }
-        | EventType::MsgRead { chat_id, .. }
-        | EventType::ChatModified(chat_id)
-        | EventType::ChatEphemeralTimerModified { chat_id, .. } => chat_id.to_u32() as libc::c_int,
-        EventType::ContactsChanged(id) | EventType::LocationChanged(id) => {
-            let id = id.unwrap_or_default();
-            id.to_u32() as libc::c_int
-        }
-        EventType::ConfigureProgress { progress, .. } | EventType::ImexProgress(progress) => {
-            *progress as libc::c_int
-        }
-        EventType::ImexFileWritten(_) => 0,
-        EventType::SecurejoinInviterProgress { contact_id, .. }
-        | EventType::SecurejoinJoinerProgress { contact_id, .. } => {
-            contact_id.to_u32() as libc::c_int
-        }
-        EventType::WebxdcStatusUpdate { msg_id, .. } => msg_id.to_u32() as libc::c_int,
-        EventType::WebxdcInstanceDeleted { msg_id, .. } => msg_id.to_u32() as libc::c_int,
-    }
+        let event = &(*event).typ;
+        match event {
+            EventType::Info(_)
+            | EventType::SmtpConnected(_)
+            | EventType::ImapConnected(_)
+            | EventType::SmtpMessageSent(_)
+            | EventType::ImapMessageDeleted(_)
+            | EventType::ImapMessageMoved(_)
+            | EventType::ImapInboxIdle
+            | EventType::NewBlobFile(_)
+            | EventType::DeletedBlobFile(_)
+            | EventType::Warning(_)
+            | EventType::Error(_)
+            | EventType::ConnectivityChanged
+            | EventType::SelfavatarChanged
+            | EventType::IncomingMsgBunch { .. }
+            | EventType::ErrorSelfNotInGroup(_) => 0,
+            EventType::MsgsChanged { chat_id, .. }
+            | EventType::ReactionsChanged { chat_id, .. }
+            | EventType::IncomingMsg { chat_id, .. }
+            | EventType::MsgsNoticed(chat_id)
+            | EventType::MsgDelivered { chat_id, .. }
+            | EventType::MsgFailed { chat_id, .. }
+            | EventType::MsgGivenUp { chat_id, .. }
+            | EventType::MsgRead { chat_id, .. }
+            | EventType::ChatModified(chat_id)
+            | EventType::ChatEphemeralTimerModified { chat_id, .. } => {
+                chat_id.to_u32() as libc::c_int
+            }
+            EventType::ContactsChanged(id) | EventType::LocationChanged(id) => {
+                let id = id.unwrap_or_default();
+                id.to_u32() as libc::c_int
+            }
+            EventType::ConfigureProgress { progress, .. }
+            | EventType::ImexProgress(progress)
+            | EventType::DbMaintenanceProgress(progress) => *progress as libc::c_int,
+            EventType::HousekeepingDone { removed_files, .. } => *removed_files as libc::c_int,
+            EventType::QuotaWarning { usage_percentage } => *usage_percentage as libc::c_int,
+            EventType::Oauth2TokenRequested { regenerate, .. } => *regenerate as libc::c_int,
+            EventType::ImexFileWritten(_) => 0,
+            EventType::SecurejoinInviterProgress { contact_id, .. }
+            | EventType::SecurejoinJoinerProgress { contact_id, .. } => {
+                contact_id.to_u32() as libc::c_int
+            }
+            EventType::WebxdcStatusUpdate { msg_id, .. } => msg_id.to_u32() as libc::c_int,
+            EventType::WebxdcInstanceDeleted { msg_id, .. } => msg_id.to_u32() as libc::c_int,
+            EventType::WebxdcRealtimeData { msg_id, .. } => msg_id.to_u32() as libc::c_int,
+            EventType::EventQueueOverflow { dropped } => *dropped as libc::c_int,
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_event_get_data2_int(event: *mut dc_event_t) -> libc::c_int {
-    if event.is_null() {
-        eprintln!("ignoring careless call to dc_event_get_data2_int()");
-        return 0;
-    }
+    ffi_boundary("dc_event_get_data2_int", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_data2_int()");
+            return 0;
+        }
 
-    let event = &(*event).typ;
-
-    match event {
-        EventType::Info(_)
-        | EventType::SmtpConnected(_)
-        | EventType::ImapConnected(_)
-        | EventType::SmtpMessageSent(_)
-        | EventType::ImapMessageDeleted(_)
-        | EventType::ImapMessageMoved(_)
-        | EventType::ImapInboxIdle
-        | EventType::NewBlobFile(_)
-        | EventType::DeletedBlobFile(_)
-        | EventType::Warning(_)
-        | EventType::Error(_)
-        | EventType::ErrorSelfNotInGroup(_)
-        | EventType::ContactsChanged(_)
-        | EventType::LocationChanged(_)
-        | EventType::ConfigureProgress { .. }
-        | EventType::ImexProgress(_)
-        | EventType::ImexFileWritten(_)
-        | EventType::MsgsNoticed(_)
-        | EventType::ConnectivityChanged
-        | EventType::WebxdcInstanceDeleted { .. }
-        | EventType::IncomingMsgBunch { .. }
-        | EventType::SelfavatarChanged => 0,
-        EventType::ChatModified(_) => 0,
-        EventType::MsgsChanged { msg_id, .. }
-        | EventType::ReactionsChanged { msg_id, .. }
-        | EventType::IncomingMsg { msg_id, .. }
-        | EventType::MsgDelivered { msg_id, .. }
-        | EventType::MsgFailed { msg_id, .. }
-        | EventType::MsgRead { msg_id, .. } => msg_id.to_u32() as libc::c_int,
-        EventType::SecurejoinInviterProgress { progress, .. }
-        | EventType::SecurejoinJoinerProgress { progress, .. } => *progress as libc::c_int,
-        EventType::ChatEphemeralTimerModified { timer, .. } => timer.to_u32() as libc::c_int,
-        EventType::WebxdcStatusUpdate {
-            status_update_serial,
-            ..
-        } => status_update_serial.to_u32() as libc::c_int,
-    }
+        let event = &(*event).typ;
+
+        match event {
+            EventType::Info(_)
+            | EventType::SmtpConnected(_)
+            | EventType::ImapConnected(_)
+            | EventType::SmtpMessageSent(_)
+            | EventType::ImapMessageDeleted(_)
+            | EventType::ImapMessageMoved(_)
+            | EventType::ImapInboxIdle
+            | EventType::NewBlobFile(_)
+            | EventType::DeletedBlobFile(_)
+            | EventType::Warning(_)
+            | EventType::Error(_)
+            | EventType::ErrorSelfNotInGroup(_)
+            | EventType::ContactsChanged(_)
+            | EventType::LocationChanged(_)
+            | EventType::ConfigureProgress { .. }
+            | EventType::ImexProgress(_)
+            | EventType::ImexFileWritten(_)
+            | EventType::DbMaintenanceProgress(_)
+            | EventType::MsgsNoticed(_)
+            | EventType::ConnectivityChanged
+            | EventType::WebxdcInstanceDeleted { .. }
+            | EventType::IncomingMsgBunch { .. }
+            | EventType::SelfavatarChanged
+            | EventType::QuotaWarning { .. }
+            | EventType::Oauth2TokenRequested { .. }
+            | EventType::EventQueueOverflow { .. } => 0,
+            EventType::ChatModified(_) => 0,
+            EventType::MsgsChanged { msg_id, .. }
+            | EventType::ReactionsChanged { msg_id, .. }
+            | EventType::IncomingMsg { msg_id, .. }
+            | EventType::MsgDelivered { msg_id, .. }
+            | EventType::MsgFailed { msg_id, .. }
+            | EventType::MsgGivenUp { msg_id, .. }
+            | EventType::MsgRead { msg_id, .. } => msg_id.to_u32() as libc::c_int,
+            EventType::SecurejoinInviterProgress { progress, .. }
+            | EventType::SecurejoinJoinerProgress { progress, .. } => *progress as libc::c_int,
+            EventType::ChatEphemeralTimerModified { timer, .. } => timer.to_u32() as libc::c_int,
+            EventType::HousekeepingDone { deleted_msgs, .. } => *deleted_msgs as libc::c_int,
+            EventType::WebxdcStatusUpdate {
+                status_update_serial,
+                ..
+            } => status_update_serial.to_u32() as libc::c_int,
+            EventType::WebxdcRealtimeData { data, .. } => data.len() as libc::c_int,
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_event_get_data2_str(event: *mut dc_event_t) -> *mut libc::c_char {
-    if event.is_null() {
-        eprintln!("ignoring careless call to dc_event_get_data2_str()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_event_get_data2_str", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_data2_str()");
+            return ptr::null_mut();
+        }
 
-    let event = &(*event).typ;
-
-    match event {
-        EventType::Info(msg)
-        | EventType::SmtpConnected(msg)
-        | EventType::ImapConnected(msg)
-        | EventType::SmtpMessageSent(msg)
-        | EventType::ImapMessageDeleted(msg)
-        | EventType::ImapMessageMoved(msg)
-        | EventType::NewBlobFile(msg)
-        | EventType::DeletedBlobFile(msg)
-        | EventType::Warning(msg)
-        | EventType::Error(msg)
-        | EventType::ErrorSelfNotInGroup(msg) => {
-            let data2 = msg.to_c_string().unwrap_or_default();
-            data2.into_raw()
-        }
-        EventType::MsgsChanged { .. }
-        | EventType::ReactionsChanged { .. }
-        | EventType::IncomingMsg { .. }
-        | EventType::ImapInboxIdle
-        | EventType::MsgsNoticed(_)
-        | EventType::MsgDelivered { .. }
-        | EventType::MsgFailed { .. }
-        | EventType::MsgRead { .. }
-        | EventType::ChatModified(_)
-        | EventType::ContactsChanged(_)
-        | EventType::LocationChanged(_)
-        | EventType::ImexProgress(_)
-        | EventType::SecurejoinInviterProgress { .. }
-        | EventType::SecurejoinJoinerProgress { .. }
-        | EventType::ConnectivityChanged
-        | EventType::SelfavatarChanged
-        | EventType::WebxdcStatusUpdate { .. }
-        | EventType::WebxdcInstanceDeleted { .. }
-        | EventType::ChatEphemeralTimerModified { .. } => ptr::null_mut(),
-        EventType::ConfigureProgress { comment, .. } => {
-            if let Some(comment) = comment {
-                comment.to_c_string().unwrap_or_default().into_raw()
-            } else {
-                ptr::null_mut()
+        let event = &(*event).typ;
+
+        match event {
+            EventType::Info(msg)
+            | EventType::SmtpConnected(msg)
+            | EventType::ImapConnected(msg)
+            | EventType::SmtpMessageSent(msg)
+            | EventType::ImapMessageDeleted(msg)
+            | EventType::ImapMessageMoved(msg)
+            | EventType::NewBlobFile(msg)
+            | EventType::DeletedBlobFile(msg)
+            | EventType::Warning(msg)
+            | EventType::Error(msg)
+            | EventType::ErrorSelfNotInGroup(msg) => {
+                let data2 = msg.to_c_string().unwrap_or_default();
+                data2.into_raw()
+            }
+            EventType::MsgsChanged { .. }
+            | EventType::ReactionsChanged { .. }
+            | EventType::IncomingMsg { .. }
+            | EventType::ImapInboxIdle
+            | EventType::MsgsNoticed(_)
+            | EventType::MsgDelivered { .. }
+            | EventType::MsgFailed { .. }
+            | EventType::MsgGivenUp { .. }
+            | EventType::MsgRead { .. }
+            | EventType::ChatModified(_)
+            | EventType::ContactsChanged(_)
+            | EventType::LocationChanged(_)
+            | EventType::ImexProgress(_)
+            | EventType::DbMaintenanceProgress(_)
+            | EventType::HousekeepingDone { .. }
+            | EventType::SecurejoinInviterProgress { .. }
+            | EventType::SecurejoinJoinerProgress { .. }
+            | EventType::ConnectivityChanged
+            | EventType::SelfavatarChanged
+            | EventType::QuotaWarning { .. }
+            | EventType::WebxdcStatusUpdate { .. }
+            | EventType::WebxdcInstanceDeleted { .. }
+            | EventType::ChatEphemeralTimerModified { .. }
+            | EventType::EventQueueOverflow { .. } => ptr::null_mut(),
+            EventType::ConfigureProgress { comment, .. } => {
+                if let Some(comment) = comment {
+                    comment.to_c_string().unwrap_or_default().into_raw()
+                } else {
+                    ptr::null_mut()
+                }
+            }
+            EventType::ImexFileWritten(file) => {
+                let data2 = file.to_c_string().unwrap_or_default();
+                data2.into_raw()
+            }
+            EventType::Oauth2TokenRequested { addr, .. } => {
+                addr.to_c_string().unwrap_or_default().into_raw()
+            }
+            EventType::IncomingMsgBunch { msg_ids } => serde_json::to_string(msg_ids)
+                .unwrap_or_default()
+                .to_c_string()
+                .unwrap_or_default()
+                .into_raw(),
+            EventType::WebxdcRealtimeData { data, .. } => {
+                // The data may contain zero bytes, so it is copied as is
+                // and the length is returned by dc_event_get_data2_int().
+                let ptr = libc::malloc(data.len().max(1)) as *mut u8;
+                if !ptr.is_null() {
+                    ptr::copy_nonoverlapping(data.as_ptr(), ptr, data.len());
+                }
+                ptr as *mut libc::c_char
             }
         }
-        EventType::ImexFileWritten(file) => {
-            let data2 = file.to_c_string().unwrap_or_default();
-            data2.into_raw()
-        }
-        EventType::IncomingMsgBunch { msg_ids } => serde_json::to_string(msg_ids)
-            .unwrap_or_default()
-            .to_c_string()
-            .unwrap_or_default()
-            .into_raw(),
-    }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_event_get_account_id(event: *mut dc_event_t) -> u32 {
-    if event.is_null() {
-        eprintln!("ignoring careless call to dc_event_get_account_id()");
-        return 0;
-    }
+    ffi_boundary("dc_event_get_account_id", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_account_id()");
+            return 0;
+        }
 
-    (*event).id
+        (*event).id
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_chat_id(event: *mut dc_event_t) -> u32 {
+    ffi_boundary("dc_event_get_chat_id", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_chat_id()");
+            return 0;
+        }
+
+        match &(*event).typ {
+            EventType::MsgsChanged { chat_id, .. }
+            | EventType::ReactionsChanged { chat_id, .. }
+            | EventType::IncomingMsg { chat_id, .. }
+            | EventType::MsgsNoticed(chat_id)
+            | EventType::MsgDelivered { chat_id, .. }
+            | EventType::MsgFailed { chat_id, .. }
+            | EventType::MsgGivenUp { chat_id, .. }
+            | EventType::MsgRead { chat_id, .. }
+            | EventType::ChatModified(chat_id)
+            | EventType::ChatEphemeralTimerModified { chat_id, .. } => chat_id.to_u32(),
+            _ => 0,
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_msg_id(event: *mut dc_event_t) -> u32 {
+    ffi_boundary("dc_event_get_msg_id", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_msg_id()");
+            return 0;
+        }
+
+        match &(*event).typ {
+            EventType::MsgsChanged { msg_id, .. }
+            | EventType::ReactionsChanged { msg_id, .. }
+            | EventType::IncomingMsg { msg_id, .. }
+            | EventType::MsgDelivered { msg_id, .. }
+            | EventType::MsgFailed { msg_id, .. }
+            | EventType::MsgGivenUp { msg_id, .. }
+            | EventType::MsgRead { msg_id, .. }
+            | EventType::WebxdcStatusUpdate { msg_id, .. }
+            | EventType::WebxdcInstanceDeleted { msg_id }
+            | EventType::WebxdcRealtimeData { msg_id, .. } => msg_id.to_u32(),
+            _ => 0,
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_msg_ids(event: *mut dc_event_t) -> *mut dc_array_t {
+    ffi_boundary("dc_event_get_msg_ids", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_msg_ids()");
+            return ptr::null_mut();
+        }
+
+        let msg_ids = match &(*event).typ {
+            EventType::IncomingMsgBunch { msg_ids } => {
+                msg_ids.iter().map(|msg_id| msg_id.to_u32()).collect()
+            }
+            _ => match dc_event_get_msg_id(event) {
+                0 => Vec::new(),
+                msg_id => vec![msg_id],
+            },
+        };
+        Box::into_raw(Box::new(dc_array_t::from(msg_ids)))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_contact_id(event: *mut dc_event_t) -> u32 {
+    ffi_boundary("dc_event_get_contact_id", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_contact_id()");
+            return 0;
+        }
+
+        match &(*event).typ {
+            EventType::ReactionsChanged { contact_id, .. }
+            | EventType::SecurejoinInviterProgress { contact_id, .. }
+            | EventType::SecurejoinJoinerProgress { contact_id, .. } => contact_id.to_u32(),
+            EventType::ContactsChanged(contact_id) | EventType::LocationChanged(contact_id) => {
+                contact_id.unwrap_or_default().to_u32()
+            }
+            _ => 0,
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_progress(event: *mut dc_event_t) -> libc::c_int {
+    ffi_boundary("dc_event_get_progress", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_progress()");
+            return 0;
+        }
+
+        match &(*event).typ {
+            EventType::ConfigureProgress { progress, .. }
+            | EventType::ImexProgress(progress)
+            | EventType::DbMaintenanceProgress(progress)
+            | EventType::SecurejoinInviterProgress { progress, .. }
+            | EventType::SecurejoinJoinerProgress { progress, .. } => *progress as libc::c_int,
+            _ => 0,
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_status_update_serial(event: *mut dc_event_t) -> u32 {
+    ffi_boundary("dc_event_get_status_update_serial", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_status_update_serial()");
+            return 0;
+        }
+
+        match &(*event).typ {
+            EventType::WebxdcStatusUpdate {
+                status_update_serial,
+                ..
+            } => status_update_serial.to_u32(),
+            _ => 0,
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_event_get_ephemeral_timer(event: *mut dc_event_t) -> u32 {
+    ffi_boundary("dc_event_get_ephemeral_timer", ptr::null(), || {
+        if event.is_null() {
+            eprintln!("ignoring careless call to dc_event_get_ephemeral_timer()");
+            return 0;
+        }
+
+        match &(*event).typ {
+            EventType::ChatEphemeralTimerModified { timer, .. } => timer.to_u32(),
+            _ => 0,
+        }
+    })
 }
 
 pub type dc_event_emitter_t = EventEmitter;
@@ -720,63 +1031,226 @@ fn spawn_configure(ctx: Context) {
 pub unsafe extern "C" fn dc_get_event_emitter(
     context: *mut dc_context_t,
 ) -> *mut dc_event_emitter_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_event_emitter()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
-    Box::into_raw(Box::new(ctx.get_event_emitter()))
+    ffi_boundary("dc_get_event_emitter", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_event_emitter()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
+        Box::into_raw(Box::new(ctx.get_event_emitter()))
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_event_emitter_unref(emitter: *mut dc_event_emitter_t) {
-    if emitter.is_null() {
-        eprintln!("ignoring careless call to dc_event_emitter_unref()");
-        return;
-    }
+    ffi_boundary("dc_event_emitter_unref", ptr::null(), || {
+        if emitter.is_null() {
+            eprintln!("ignoring careless call to dc_event_emitter_unref()");
+            return;
+        }
 
-    drop(Box::from_raw(emitter));
+        drop(Box::from_raw(emitter));
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_next_event(events: *mut dc_event_emitter_t) -> *mut dc_event_t {
-    if events.is_null() {
-        eprintln!("ignoring careless call to dc_get_next_event()");
-        return ptr::null_mut();
-    }
-    let events = &*events;
+    ffi_boundary("dc_get_next_event", ptr::null(), || {
+        if events.is_null() {
+            eprintln!("ignoring careless call to dc_get_next_event()");
+            return ptr::null_mut();
+        }
+        let events = &*events;
 
-    block_on(async move {
-        events
-            .recv()
-            .await
-            .map(|ev| Box::into_raw(Box::new(ev)))
-            .unwrap_or_else(ptr::null_mut)
+        block_on(async move {
+            events
+                .recv()
+                .await
+                .map(|ev| Box::into_raw(Box::new(ev)))
+                .unwrap_or_else(ptr::null_mut)
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_stop_io(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_stop_io()");
-        return;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_stop_io", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_stop_io()");
+            return;
+        }
+        let ctx = &*context;
+
+        block_on(async move {
+            ctx.stop_io().await;
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_maintenance_mode(context: *mut dc_context_t, enabled: libc::c_int) {
+    ffi_boundary("dc_set_maintenance_mode", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_maintenance_mode()");
+            return;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ctx.stop_io().await;
+        block_on(ctx.set_maintenance_mode(enabled != 0))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_is_maintenance_mode(context: *mut dc_context_t) -> libc::c_int {
+    ffi_boundary("dc_is_maintenance_mode", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_is_maintenance_mode()");
+            return 0;
+        }
+        let ctx = &*context;
+        ctx.is_maintenance_mode() as libc::c_int
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_maybe_network(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_maybe_network()");
-        return;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_maybe_network", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_maybe_network()");
+            return;
+        }
+        let ctx = &*context;
 
-    block_on(async move { ctx.maybe_network().await })
+        block_on(async move { ctx.maybe_network().await })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_unifiedpush_endpoint(
+    context: *mut dc_context_t,
+    endpoint: *const libc::c_char,
+) -> libc::c_int {
+    ffi_boundary("dc_set_unifiedpush_endpoint", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_unifiedpush_endpoint()");
+            return 0;
+        }
+        let ctx = &*context;
+        let endpoint = to_opt_string_lossy(endpoint);
+        block_on(async move {
+            ctx.set_unifiedpush_endpoint(endpoint.as_deref())
+                .await
+                .log_err(ctx)
+                .is_ok() as libc::c_int
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_push_device_token(
+    context: *mut dc_context_t,
+    token: *const libc::c_char,
+) -> libc::c_int {
+    ffi_boundary("dc_set_push_device_token", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_push_device_token()");
+            return 0;
+        }
+        let ctx = &*context;
+        let token = to_opt_string_lossy(token);
+        block_on(async move {
+            ctx.set_push_device_token(token.as_deref())
+                .await
+                .log_err(ctx)
+                .is_ok() as libc::c_int
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_push_state(context: *mut dc_context_t) -> libc::c_int {
+    ffi_boundary("dc_get_push_state", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_push_state()");
+            return 0;
+        }
+        let ctx = &*context;
+        block_on(async move {
+            match ctx.get_push_state().await.log_err(ctx) {
+                Ok(PushState::NoToken) | Err(_) => 0,
+                Ok(PushState::NotSupported) => 1,
+                Ok(PushState::Pending) => 2,
+                Ok(PushState::Registered) => 3,
+            }
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_handle_push_notification(context: *mut dc_context_t) {
+    ffi_boundary("dc_handle_push_notification", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_handle_push_notification()");
+            return;
+        }
+        let ctx = &*context;
+
+        block_on(async move { ctx.handle_push_notification().await })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_quiet_hours(
+    context: *mut dc_context_t,
+    json: *const libc::c_char,
+) -> libc::c_int {
+    ffi_boundary("dc_set_quiet_hours", context, || {
+        if context.is_null() || json.is_null() {
+            eprintln!("ignoring careless call to dc_set_quiet_hours()");
+            return 0;
+        }
+        let ctx = &*context;
+        block_on(async move {
+            let quiet_hours: Vec<QuietHours> = match serde_json::from_str(&to_string_lossy(json)) {
+                Ok(quiet_hours) => quiet_hours,
+                Err(err) => {
+                    error!(ctx, "dc_set_quiet_hours(): invalid JSON: {err:#}");
+                    return 0;
+                }
+            };
+            ctx.set_quiet_hours(&quiet_hours).await.log_err(ctx).is_ok() as libc::c_int
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_quiet_hours(context: *mut dc_context_t) -> *mut libc::c_char {
+    ffi_boundary("dc_get_quiet_hours", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_quiet_hours()");
+            return "".strdup();
+        }
+        let ctx = &*context;
+        block_on(async move {
+            let quiet_hours = ctx.get_quiet_hours().await.log_err(ctx).unwrap_or_default();
+            serde_json::to_string(&quiet_hours)
+                .unwrap_or_default()
+                .strdup()
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_is_quiet_time(context: *mut dc_context_t) -> libc::c_int {
+    ffi_boundary("dc_is_quiet_time", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_is_quiet_time()");
+            return 0;
+        }
+        let ctx = &*context;
+        block_on(async move { ctx.is_quiet_time().await.log_err(ctx).unwrap_or_default() })
+            as libc::c_int
+    })
 }
 
 #[no_mangle]
@@ -786,26 +1260,28 @@ fn spawn_configure(ctx: Context) {
     public_data: *const libc::c_char,
     secret_data: *const libc::c_char,
 ) -> i32 {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_preconfigure_keypair()");
-        return 0;
-    }
-    let ctx = &*context;
-    block_on(async move {
-        let addr = tools::EmailAddress::new(&to_string_lossy(addr))?;
-        let public = key::SignedPublicKey::from_asc(&to_string_lossy(public_data))?.0;
-        let secret = key::SignedSecretKey::from_asc(&to_string_lossy(secret_data))?.0;
-        let keypair = key::KeyPair {
-            addr,
-            public,
-            secret,
-        };
-        key::store_self_keypair(ctx, &keypair, key::KeyPairUse::Default).await?;
-        Ok::<_, anyhow::Error>(1)
+    ffi_boundary("dc_preconfigure_keypair", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_preconfigure_keypair()");
+            return 0;
+        }
+        let ctx = &*context;
+        block_on(async move {
+            let addr = tools::EmailAddress::new(&to_string_lossy(addr))?;
+            let public = key::SignedPublicKey::from_asc(&to_string_lossy(public_data))?.0;
+            let secret = key::SignedSecretKey::from_asc(&to_string_lossy(secret_data))?.0;
+            let keypair = key::KeyPair {
+                addr,
+                public,
+                secret,
+            };
+            key::store_self_keypair(ctx, &keypair, key::KeyPairUse::Default).await?;
+            Ok::<_, anyhow::Error>(1)
+        })
+        .context("Failed to save keypair")
+        .log_err(ctx)
+        .unwrap_or(0)
     })
-    .context("Failed to save keypair")
-    .log_err(ctx)
-    .unwrap_or(0)
 }
 
 #[no_mangle]
@@ -815,31 +1291,33 @@ fn spawn_configure(ctx: Context) {
     query_str: *const libc::c_char,
     query_id: u32,
 ) -> *mut dc_chatlist_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_chatlist()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
-    let qs = to_opt_string_lossy(query_str);
+    ffi_boundary("dc_get_chatlist", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_chatlist()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
+        let qs = to_opt_string_lossy(query_str);
 
-    let qi = if query_id == 0 {
-        None
-    } else {
-        Some(ContactId::new(query_id))
-    };
+        let qi = if query_id == 0 {
+            None
+        } else {
+            Some(ContactId::new(query_id))
+        };
 
-    block_on(async move {
-        match chatlist::Chatlist::try_load(ctx, flags as usize, qs.as_deref(), qi)
-            .await
-            .context("Failed to get chatlist")
-            .log_err(ctx)
-        {
-            Ok(list) => {
-                let ffi_list = ChatlistWrapper { context, list };
-                Box::into_raw(Box::new(ffi_list))
+        block_on(async move {
+            match chatlist::Chatlist::try_load(ctx, flags as usize, qs.as_deref(), qi)
+                .await
+                .context("Failed to get chatlist")
+                .log_err(ctx)
+            {
+                Ok(list) => {
+                    let ffi_list = ChatlistWrapper { context, list };
+                    Box::into_raw(Box::new(ffi_list))
+                }
+                Err(_) => ptr::null_mut(),
             }
-            Err(_) => ptr::null_mut(),
-        }
+        })
     })
 }
 
@@ -848,19 +1326,21 @@ fn spawn_configure(ctx: Context) {
     context: *mut dc_context_t,
     contact_id: u32,
 ) -> u32 {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_create_chat_by_contact_id()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_create_chat_by_contact_id", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_create_chat_by_contact_id()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ChatId::create_for_contact(ctx, ContactId::new(contact_id))
-            .await
-            .context("Failed to create chat from contact_id")
-            .log_err(ctx)
-            .map(|id| id.to_u32())
-            .unwrap_or(0)
+        block_on(async move {
+            ChatId::create_for_contact(ctx, ContactId::new(contact_id))
+                .await
+                .context("Failed to create chat from contact_id")
+                .log_err(ctx)
+                .map(|id| id.to_u32())
+                .unwrap_or(0)
+        })
     })
 }
 
@@ -869,20 +1349,22 @@ fn spawn_configure(ctx: Context) {
     context: *mut dc_context_t,
     contact_id: u32,
 ) -> u32 {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_chat_id_by_contact_id()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_chat_id_by_contact_id", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_chat_id_by_contact_id()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ChatId::lookup_by_contact(ctx, ContactId::new(contact_id))
-            .await
-            .context("Failed to get chat for contact_id")
-            .log_err(ctx)
-            .unwrap_or_default() // unwraps the Result
-            .map(|id| id.to_u32())
-            .unwrap_or(0) // unwraps the Option
+        block_on(async move {
+            ChatId::lookup_by_contact(ctx, ContactId::new(contact_id))
+                .await
+                .context("Failed to get chat for contact_id")
+                .log_err(ctx)
+                .unwrap_or_default() // unwraps the Result
+                .map(|id| id.to_u32())
+                .unwrap_or(0) // unwraps the Option
+        })
     })
 }
 
@@ -892,19 +1374,21 @@ fn spawn_configure(ctx: Context) {
     chat_id: u32,
     msg: *mut dc_msg_t,
 ) -> u32 {
-    if context.is_null() || chat_id == 0 || msg.is_null() {
-        eprintln!("ignoring careless call to dc_prepare_msg()");
-        return 0;
-    }
-    let ctx = &mut *context;
-    let ffi_msg: &mut MessageWrapper = &mut *msg;
+    ffi_boundary("dc_prepare_msg", context, || {
+        if context.is_null() || chat_id == 0 || msg.is_null() {
+            eprintln!("ignoring careless call to dc_prepare_msg()");
+            return 0;
+        }
+        let ctx = &mut *context;
+        let ffi_msg: &mut MessageWrapper = &mut *msg;
 
-    block_on(async move {
-        chat::prepare_msg(ctx, ChatId::new(chat_id), &mut ffi_msg.message)
-            .await
-            .unwrap_or_log_default(ctx, "Failed to prepare message")
+        block_on(async move {
+            chat::prepare_msg(ctx, ChatId::new(chat_id), &mut ffi_msg.message)
+                .await
+                .unwrap_or_log_default(ctx, "Failed to prepare message")
+        })
+        .to_u32()
     })
-    .to_u32()
 }
 
 #[no_mangle]
@@ -913,19 +1397,46 @@ fn spawn_configure(ctx: Context) {
     chat_id: u32,
     msg: *mut dc_msg_t,
 ) -> u32 {
-    if context.is_null() || msg.is_null() {
-        eprintln!("ignoring careless call to dc_send_msg()");
-        return 0;
-    }
-    let ctx = &mut *context;
-    let ffi_msg = &mut *msg;
+    ffi_boundary("dc_send_msg", context, || {
+        if context.is_null() || msg.is_null() {
+            eprintln!("ignoring careless call to dc_send_msg()");
+            return 0;
+        }
+        let ctx = &mut *context;
+        let ffi_msg = &mut *msg;
 
-    block_on(async move {
-        chat::send_msg(ctx, ChatId::new(chat_id), &mut ffi_msg.message)
-            .await
-            .unwrap_or_log_default(ctx, "Failed to send message")
+        block_on(async move {
+            chat::send_msg(ctx, ChatId::new(chat_id), &mut ffi_msg.message)
+                .await
+                .unwrap_or_log_default(ctx, "Failed to send message")
+        })
+        .to_u32()
+    })
+}
+
+fn spawn_send_msg(ctx: Context, chat_id: ChatId, mut msg: message::Message) {
+    spawn(async move {
+        if let Err(err) = chat::send_msg(&ctx, chat_id, &mut msg).await {
+            error!(ctx, "Failed to send message: {err:#}");
+        }
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_send_msg_async(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    msg: *mut dc_msg_t,
+) {
+    ffi_boundary("dc_send_msg_async", context, || {
+        if context.is_null() || msg.is_null() {
+            eprintln!("ignoring careless call to dc_send_msg_async()");
+            return;
+        }
+        let ctx = &*context;
+        let ffi_msg = &*msg;
+        spawn_send_msg(ctx.clone(), ChatId::new(chat_id), ffi_msg.message.clone());
     })
-    .to_u32()
 }
 
 #[no_mangle]
@@ -934,19 +1445,21 @@ fn spawn_configure(ctx: Context) {
     chat_id: u32,
     msg: *mut dc_msg_t,
 ) -> u32 {
-    if context.is_null() || msg.is_null() {
-        eprintln!("ignoring careless call to dc_send_msg_sync()");
-        return 0;
-    }
-    let ctx = &mut *context;
-    let ffi_msg = &mut *msg;
+    ffi_boundary("dc_send_msg_sync", context, || {
+        if context.is_null() || msg.is_null() {
+            eprintln!("ignoring careless call to dc_send_msg_sync()");
+            return 0;
+        }
+        let ctx = &mut *context;
+        let ffi_msg = &mut *msg;
 
-    block_on(async move {
-        chat::send_msg_sync(ctx, ChatId::new(chat_id), &mut ffi_msg.message)
-            .await
-            .unwrap_or_log_default(ctx, "Failed to send message")
+        block_on(async move {
+            chat::send_msg_sync(ctx, ChatId::new(chat_id), &mut ffi_msg.message)
+                .await
+                .unwrap_or_log_default(ctx, "Failed to send message")
+        })
+        .to_u32()
     })
-    .to_u32()
 }
 
 #[no_mangle]
@@ -955,18 +1468,20 @@ fn spawn_configure(ctx: Context) {
     chat_id: u32,
     text_to_send: *const libc::c_char,
 ) -> u32 {
-    if context.is_null() || text_to_send.is_null() {
-        eprintln!("ignoring careless call to dc_send_text_msg()");
-        return 0;
-    }
-    let ctx = &*context;
-    let text_to_send = to_string_lossy(text_to_send);
+    ffi_boundary("dc_send_text_msg", context, || {
+        if context.is_null() || text_to_send.is_null() {
+            eprintln!("ignoring careless call to dc_send_text_msg()");
+            return 0;
+        }
+        let ctx = &*context;
+        let text_to_send = to_string_lossy(text_to_send);
 
-    block_on(async move {
-        chat::send_text_msg(ctx, ChatId::new(chat_id), text_to_send)
-            .await
-            .map(|msg_id| msg_id.to_u32())
-            .unwrap_or_log_default(ctx, "Failed to send text message")
+        block_on(async move {
+            chat::send_text_msg(ctx, ChatId::new(chat_id), text_to_send)
+                .await
+                .map(|msg_id| msg_id.to_u32())
+                .unwrap_or_log_default(ctx, "Failed to send text message")
+        })
     })
 }
 
@@ -975,17 +1490,19 @@ fn spawn_configure(ctx: Context) {
     context: *mut dc_context_t,
     chat_id: u32,
 ) -> u32 {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_send_videochat_invitation()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_send_videochat_invitation", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_send_videochat_invitation()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        chat::send_videochat_invitation(ctx, ChatId::new(chat_id))
-            .await
-            .map(|msg_id| msg_id.to_u32())
-            .unwrap_or_log_default(ctx, "Failed to send video chat invitation")
+        block_on(async move {
+            chat::send_videochat_invitation(ctx, ChatId::new(chat_id))
+                .await
+                .map(|msg_id| msg_id.to_u32())
+                .unwrap_or_log_default(ctx, "Failed to send video chat invitation")
+        })
     })
 }
 
@@ -995,17 +1512,19 @@ fn spawn_configure(ctx: Context) {
     msg_id: u32,
     reaction: *const libc::c_char,
 ) -> u32 {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_send_reaction()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_send_reaction", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_send_reaction()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        send_reaction(ctx, MsgId::new(msg_id), &to_string_lossy(reaction))
-            .await
-            .map(|msg_id| msg_id.to_u32())
-            .unwrap_or_log_default(ctx, "Failed to send reaction")
+        block_on(async move {
+            send_reaction(ctx, MsgId::new(msg_id), &to_string_lossy(reaction))
+                .await
+                .map(|msg_id| msg_id.to_u32())
+                .unwrap_or_log_default(ctx, "Failed to send reaction")
+        })
     })
 }
 
@@ -1014,22 +1533,24 @@ fn spawn_configure(ctx: Context) {
     context: *mut dc_context_t,
     msg_id: u32,
 ) -> *mut dc_reactions_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_msg_reactions()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_msg_reactions", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_msg_reactions()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
 
-    let reactions = if let Ok(reactions) = block_on(get_msg_reactions(ctx, MsgId::new(msg_id)))
-        .context("failed dc_get_msg_reactions() call")
-        .log_err(ctx)
-    {
-        reactions
-    } else {
-        return ptr::null_mut();
-    };
+        let reactions = if let Ok(reactions) = block_on(get_msg_reactions(ctx, MsgId::new(msg_id)))
+            .context("failed dc_get_msg_reactions() call")
+            .log_err(ctx)
+        {
+            reactions
+        } else {
+            return ptr::null_mut();
+        };
 
-    Box::into_raw(Box::new(reactions))
+        Box::into_raw(Box::new(reactions))
+    })
 }
 
 #[no_mangle]
@@ -1039,20 +1560,22 @@ fn spawn_configure(ctx: Context) {
     json: *const libc::c_char,
     descr: *const libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_send_webxdc_status_update()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_send_webxdc_status_update", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_send_webxdc_status_update()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(ctx.send_webxdc_status_update(
-        MsgId::new(msg_id),
-        &to_string_lossy(json),
-        &to_string_lossy(descr),
-    ))
-    .context("Failed to send webxdc update")
-    .log_err(ctx)
-    .is_ok() as libc::c_int
+        block_on(ctx.send_webxdc_status_update(
+            MsgId::new(msg_id),
+            &to_string_lossy(json),
+            &to_string_lossy(descr),
+        ))
+        .context("Failed to send webxdc update")
+        .log_err(ctx)
+        .is_ok() as libc::c_int
+    })
 }
 
 #[no_mangle]
@@ -1061,18 +1584,84 @@ fn spawn_configure(ctx: Context) {
     msg_id: u32,
     last_known_serial: u32,
 ) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_webxdc_status_updates()");
-        return "".strdup();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_webxdc_status_updates", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_webxdc_status_updates()");
+            return "".strdup();
+        }
+        let ctx = &*context;
+
+        block_on(ctx.get_webxdc_status_updates(
+            MsgId::new(msg_id),
+            StatusUpdateSerial::new(last_known_serial),
+        ))
+        .unwrap_or_else(|_| "".to_string())
+        .strdup()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_join_webxdc_realtime_channel(
+    context: *mut dc_context_t,
+    msg_id: u32,
+) -> libc::c_int {
+    ffi_boundary("dc_join_webxdc_realtime_channel", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_join_webxdc_realtime_channel()");
+            return 0;
+        }
+        let ctx = &*context;
+
+        block_on(ctx.join_webxdc_realtime_channel(MsgId::new(msg_id)))
+            .context("Failed to join realtime channel")
+            .log_err(ctx)
+            .is_ok() as libc::c_int
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_leave_webxdc_realtime_channel(
+    context: *mut dc_context_t,
+    msg_id: u32,
+) -> libc::c_int {
+    ffi_boundary("dc_leave_webxdc_realtime_channel", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_leave_webxdc_realtime_channel()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(ctx.get_webxdc_status_updates(
-        MsgId::new(msg_id),
-        StatusUpdateSerial::new(last_known_serial),
-    ))
-    .unwrap_or_else(|_| "".to_string())
-    .strdup()
+        block_on(ctx.leave_webxdc_realtime_channel(MsgId::new(msg_id)))
+            .context("Failed to leave realtime channel")
+            .log_err(ctx)
+            .is_ok() as libc::c_int
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_send_webxdc_realtime_data(
+    context: *mut dc_context_t,
+    msg_id: u32,
+    data: *const u8,
+    len: libc::size_t,
+) -> libc::c_int {
+    ffi_boundary("dc_send_webxdc_realtime_data", context, || {
+        if context.is_null() || (data.is_null() && len > 0) {
+            eprintln!("ignoring careless call to dc_send_webxdc_realtime_data()");
+            return 0;
+        }
+        let ctx = &*context;
+        let data = if len > 0 {
+            std::slice::from_raw_parts(data, len).to_vec()
+        } else {
+            Vec::new()
+        };
+
+        block_on(ctx.send_webxdc_realtime_data(MsgId::new(msg_id), data))
+            .context("Failed to send realtime data")
+            .log_err(ctx)
+            .is_ok() as libc::c_int
+    })
 }
 
 #[no_mangle]
@@ -1081,24 +1670,26 @@ fn spawn_configure(ctx: Context) {
     chat_id: u32,
     msg: *mut dc_msg_t,
 ) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_set_draft()");
-        return;
-    }
-    let ctx = &*context;
-    let msg = if msg.is_null() {
-        None
-    } else {
-        let ffi_msg: &mut MessageWrapper = &mut *msg;
-        Some(&mut ffi_msg.message)
-    };
+    ffi_boundary("dc_set_draft", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_draft()");
+            return;
+        }
+        let ctx = &*context;
+        let msg = if msg.is_null() {
+            None
+        } else {
+            let ffi_msg: &mut MessageWrapper = &mut *msg;
+            Some(&mut ffi_msg.message)
+        };
 
-    block_on(async move {
-        ChatId::new(chat_id)
-            .set_draft(ctx, msg)
-            .await
-            .unwrap_or_log_default(ctx, "failed to set draft");
-    });
+        block_on(async move {
+            ChatId::new(chat_id)
+                .set_draft(ctx, msg)
+                .await
+                .unwrap_or_log_default(ctx, "failed to set draft");
+        });
+    })
 }
 
 #[no_mangle]
@@ -1107,24 +1698,26 @@ fn spawn_configure(ctx: Context) {
     label: *const libc::c_char,
     msg: *mut dc_msg_t,
 ) -> u32 {
-    if context.is_null() || (label.is_null() && msg.is_null()) {
-        eprintln!("ignoring careless call to dc_add_device_msg()");
-        return 0;
-    }
-    let ctx = &mut *context;
-    let msg = if msg.is_null() {
-        None
-    } else {
-        let ffi_msg: &mut MessageWrapper = &mut *msg;
-        Some(&mut ffi_msg.message)
-    };
+    ffi_boundary("dc_add_device_msg", context, || {
+        if context.is_null() || (label.is_null() && msg.is_null()) {
+            eprintln!("ignoring careless call to dc_add_device_msg()");
+            return 0;
+        }
+        let ctx = &mut *context;
+        let msg = if msg.is_null() {
+            None
+        } else {
+            let ffi_msg: &mut MessageWrapper = &mut *msg;
+            Some(&mut ffi_msg.message)
+        };
 
-    block_on(async move {
-        chat::add_device_msg(ctx, to_opt_string_lossy(label).as_deref(), msg)
-            .await
-            .unwrap_or_log_default(ctx, "Failed to add device message")
+        block_on(async move {
+            chat::add_device_msg(ctx, to_opt_string_lossy(label).as_deref(), msg)
+                .await
+                .unwrap_or_log_default(ctx, "Failed to add device message")
+        })
+        .to_u32()
     })
-    .to_u32()
 }
 
 #[no_mangle]
@@ -1132,42 +1725,46 @@ fn spawn_configure(ctx: Context) {
     context: *mut dc_context_t,
     label: *const libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() || label.is_null() {
-        eprintln!("ignoring careless call to dc_was_device_msg_ever_added()");
-        return 0;
-    }
-    let ctx = &mut *context;
+    ffi_boundary("dc_was_device_msg_ever_added", context, || {
+        if context.is_null() || label.is_null() {
+            eprintln!("ignoring careless call to dc_was_device_msg_ever_added()");
+            return 0;
+        }
+        let ctx = &mut *context;
 
-    block_on(async move {
-        chat::was_device_msg_ever_added(ctx, &to_string_lossy(label))
-            .await
-            .unwrap_or(false) as libc::c_int
+        block_on(async move {
+            chat::was_device_msg_ever_added(ctx, &to_string_lossy(label))
+                .await
+                .unwrap_or(false) as libc::c_int
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_draft(context: *mut dc_context_t, chat_id: u32) -> *mut dc_msg_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_draft()");
-        return ptr::null_mut(); // NULL explicitly defined as "no draft"
-    }
-    let ctx = &*context;
-
-    block_on(async move {
-        match ChatId::new(chat_id).get_draft(ctx).await {
-            Ok(Some(draft)) => {
-                let ffi_msg = MessageWrapper {
-                    context,
-                    message: draft,
-                };
-                Box::into_raw(Box::new(ffi_msg))
-            }
-            Ok(None) => ptr::null_mut(),
-            Err(err) => {
-                error!(ctx, "Failed to get draft for chat #{chat_id}: {err:#}");
-                ptr::null_mut()
-            }
+    ffi_boundary("dc_get_draft", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_draft()");
+            return ptr::null_mut(); // NULL explicitly defined as "no draft"
         }
+        let ctx = &*context;
+
+        block_on(async move {
+            match ChatId::new(chat_id).get_draft(ctx).await {
+                Ok(Some(draft)) => {
+                    let ffi_msg = MessageWrapper {
+                        context,
+                        message: draft,
+                    };
+                    Box::into_raw(Box::new(ffi_msg))
+                }
+                Ok(None) => ptr::null_mut(),
+                Err(err) => {
+                    error!(ctx, "Failed to get draft for chat #{chat_id}: {err:#}");
+                    ptr::null_mut()
+                }
+            }
+        })
     })
 }
 
@@ -1178,44 +1775,48 @@ fn spawn_configure(ctx: Context) {
     flags: u32,
     _marker1before: u32,
 ) -> *mut dc_array::dc_array_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_chat_msgs()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_chat_msgs", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_chat_msgs()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
 
-    let info_only = (flags & DC_GCM_INFO_ONLY) != 0;
-    let add_daymarker = (flags & DC_GCM_ADDDAYMARKER) != 0;
-    block_on(async move {
-        Box::into_raw(Box::new(
-            chat::get_chat_msgs_ex(
-                ctx,
-                ChatId::new(chat_id),
-                MessageListOptions {
-                    info_only,
-                    add_daymarker,
-                },
-            )
-            .await
-            .unwrap_or_log_default(ctx, "failed to get chat msgs")
-            .into(),
-        ))
+        let info_only = (flags & DC_GCM_INFO_ONLY) != 0;
+        let add_daymarker = (flags & DC_GCM_ADDDAYMARKER) != 0;
+        block_on(async move {
+            Box::into_raw(Box::new(
+                chat::get_chat_msgs_ex(
+                    ctx,
+                    ChatId::new(chat_id),
+                    MessageListOptions {
+                        info_only,
+                        add_daymarker,
+                    },
+                )
+                .await
+                .unwrap_or_log_default(ctx, "failed to get chat msgs")
+                .into(),
+            ))
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_msg_cnt(context: *mut dc_context_t, chat_id: u32) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_msg_cnt()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_msg_cnt", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_msg_cnt()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ChatId::new(chat_id)
-            .get_msg_cnt(ctx)
-            .await
-            .unwrap_or_log_default(ctx, "failed to get msg count") as libc::c_int
+        block_on(async move {
+            ChatId::new(chat_id)
+                .get_msg_cnt(ctx)
+                .await
+                .unwrap_or_log_default(ctx, "failed to get msg count") as libc::c_int
+        })
     })
 }
 
@@ -1224,17 +1825,20 @@ fn spawn_configure(ctx: Context) {
     context: *mut dc_context_t,
     chat_id: u32,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_fresh_msg_cnt()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_fresh_msg_cnt", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_fresh_msg_cnt()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ChatId::new(chat_id)
-            .get_fresh_msg_cnt(ctx)
-            .await
-            .unwrap_or_log_default(ctx, "failed to get fresh msg cnt") as libc::c_int
+        block_on(async move {
+            ChatId::new(chat_id)
+                .get_fresh_msg_cnt(ctx)
+                .await
+                .unwrap_or_log_default(ctx, "failed to get fresh msg cnt")
+                as libc::c_int
+        })
     })
 }
 
@@ -1244,15 +1848,17 @@ fn spawn_configure(ctx: Context) {
     from_server: libc::c_int,
     seconds: i64,
 ) -> libc::c_int {
-    if context.is_null() || seconds < 0 {
-        eprintln!("ignoring careless call to dc_estimate_deletion_cnt()");
-        return 0;
-    }
-    let ctx = &*context;
-    block_on(async move {
-        message::estimate_deletion_cnt(ctx, from_server != 0, seconds)
-            .await
-            .unwrap_or(0) as libc::c_int
+    ffi_boundary("dc_estimate_deletion_cnt", context, || {
+        if context.is_null() || seconds < 0 {
+            eprintln!("ignoring careless call to dc_estimate_deletion_cnt()");
+            return 0;
+        }
+        let ctx = &*context;
+        block_on(async move {
+            message::estimate_deletion_cnt(ctx, from_server != 0, seconds)
+                .await
+                .unwrap_or(0) as libc::c_int
+        })
     })
 }
 
@@ -1260,19 +1866,44 @@ fn spawn_configure(ctx: Context) {
 pub unsafe extern "C" fn dc_get_fresh_msgs(
     context: *mut dc_context_t,
 ) -> *mut dc_array::dc_array_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_fresh_msgs()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_fresh_msgs", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_fresh_msgs()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
+
+        block_on(async move {
+            let arr = dc_array_t::from(
+                ctx.get_fresh_msgs()
+                    .await
+                    .context("Failed to get fresh messages")
+                    .log_err(ctx)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|msg_id| msg_id.to_u32())
+                    .collect::<Vec<u32>>(),
+            );
+            Box::into_raw(Box::new(arr))
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_next_msgs(context: *mut dc_context_t) -> *mut dc_array::dc_array_t {
+    ffi_boundary("dc_get_next_msgs", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_next_msgs()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
 
-    block_on(async move {
+        let msg_ids = block_on(ctx.get_next_msgs())
+            .context("failed to get next messages")
+            .log_err(ctx)
+            .unwrap_or_default();
         let arr = dc_array_t::from(
-            ctx.get_fresh_msgs()
-                .await
-                .context("Failed to get fresh messages")
-                .log_err(ctx)
-                .unwrap_or_default()
+            msg_ids
                 .iter()
                 .map(|msg_id| msg_id.to_u32())
                 .collect::<Vec<u32>>(),
@@ -1281,64 +1912,47 @@ fn spawn_configure(ctx: Context) {
     })
 }
 
-#[no_mangle]
-pub unsafe extern "C" fn dc_get_next_msgs(context: *mut dc_context_t) -> *mut dc_array::dc_array_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_next_msgs()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
-
-    let msg_ids = block_on(ctx.get_next_msgs())
-        .context("failed to get next messages")
-        .log_err(ctx)
-        .unwrap_or_default();
-    let arr = dc_array_t::from(
-        msg_ids
-            .iter()
-            .map(|msg_id| msg_id.to_u32())
-            .collect::<Vec<u32>>(),
-    );
-    Box::into_raw(Box::new(arr))
-}
-
 #[no_mangle]
 pub unsafe extern "C" fn dc_wait_next_msgs(
     context: *mut dc_context_t,
 ) -> *mut dc_array::dc_array_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_wait_next_msgs()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_wait_next_msgs", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_wait_next_msgs()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
 
-    let msg_ids = block_on(ctx.wait_next_msgs())
-        .context("failed to wait for next messages")
-        .log_err(ctx)
-        .unwrap_or_default();
-    let arr = dc_array_t::from(
-        msg_ids
-            .iter()
-            .map(|msg_id| msg_id.to_u32())
-            .collect::<Vec<u32>>(),
-    );
-    Box::into_raw(Box::new(arr))
+        let msg_ids = block_on(ctx.wait_next_msgs())
+            .context("failed to wait for next messages")
+            .log_err(ctx)
+            .unwrap_or_default();
+        let arr = dc_array_t::from(
+            msg_ids
+                .iter()
+                .map(|msg_id| msg_id.to_u32())
+                .collect::<Vec<u32>>(),
+        );
+        Box::into_raw(Box::new(arr))
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_marknoticed_chat(context: *mut dc_context_t, chat_id: u32) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_marknoticed_chat()");
-        return;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_marknoticed_chat", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_marknoticed_chat()");
+            return;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        chat::marknoticed_chat(ctx, ChatId::new(chat_id))
-            .await
-            .context("Failed marknoticed chat")
-            .log_err(ctx)
-            .unwrap_or(())
+        block_on(async move {
+            chat::marknoticed_chat(ctx, ChatId::new(chat_id))
+                .await
+                .context("Failed marknoticed chat")
+                .log_err(ctx)
+                .unwrap_or(())
+        })
     })
 }
 
@@ -1358,29 +1972,31 @@ fn from_prim<S, T>(s: S) -> Option<T>
     or_msg_type2: libc::c_int,
     or_msg_type3: libc::c_int,
 ) -> *mut dc_array::dc_array_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_chat_media()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
-    let chat_id = if chat_id == 0 {
-        None
-    } else {
-        Some(ChatId::new(chat_id))
-    };
-    let msg_type = from_prim(msg_type).expect(&format!("invalid msg_type = {msg_type}"));
-    let or_msg_type2 =
-        from_prim(or_msg_type2).expect(&format!("incorrect or_msg_type2 = {or_msg_type2}"));
-    let or_msg_type3 =
-        from_prim(or_msg_type3).expect(&format!("incorrect or_msg_type3 = {or_msg_type3}"));
-
-    block_on(async move {
-        Box::into_raw(Box::new(
-            chat::get_chat_media(ctx, chat_id, msg_type, or_msg_type2, or_msg_type3)
-                .await
-                .unwrap_or_log_default(ctx, "Failed get_chat_media")
-                .into(),
-        ))
+    ffi_boundary("dc_get_chat_media", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_chat_media()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
+        let chat_id = if chat_id == 0 {
+            None
+        } else {
+            Some(ChatId::new(chat_id))
+        };
+        let msg_type = from_prim(msg_type).expect(&format!("invalid msg_type = {msg_type}"));
+        let or_msg_type2 =
+            from_prim(or_msg_type2).expect(&format!("incorrect or_msg_type2 = {or_msg_type2}"));
+        let or_msg_type3 =
+            from_prim(or_msg_type3).expect(&format!("incorrect or_msg_type3 = {or_msg_type3}"));
+
+        block_on(async move {
+            Box::into_raw(Box::new(
+                chat::get_chat_media(ctx, chat_id, msg_type, or_msg_type2, or_msg_type3)
+                    .await
+                    .unwrap_or_log_default(ctx, "Failed get_chat_media")
+                    .into(),
+            ))
+        })
     })
 }
 
@@ -1393,35 +2009,37 @@ fn from_prim<S, T>(s: S) -> Option<T>
     or_msg_type2: libc::c_int,
     or_msg_type3: libc::c_int,
 ) -> u32 {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_next_media()");
-        return 0;
-    }
-    let direction = if dir < 0 {
-        chat::Direction::Backward
-    } else {
-        chat::Direction::Forward
-    };
+    ffi_boundary("dc_get_next_media", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_next_media()");
+            return 0;
+        }
+        let direction = if dir < 0 {
+            chat::Direction::Backward
+        } else {
+            chat::Direction::Forward
+        };
 
-    let ctx = &*context;
-    let msg_type = from_prim(msg_type).expect(&format!("invalid msg_type = {msg_type}"));
-    let or_msg_type2 =
-        from_prim(or_msg_type2).expect(&format!("incorrect or_msg_type2 = {or_msg_type2}"));
-    let or_msg_type3 =
-        from_prim(or_msg_type3).expect(&format!("incorrect or_msg_type3 = {or_msg_type3}"));
+        let ctx = &*context;
+        let msg_type = from_prim(msg_type).expect(&format!("invalid msg_type = {msg_type}"));
+        let or_msg_type2 =
+            from_prim(or_msg_type2).expect(&format!("incorrect or_msg_type2 = {or_msg_type2}"));
+        let or_msg_type3 =
+            from_prim(or_msg_type3).expect(&format!("incorrect or_msg_type3 = {or_msg_type3}"));
 
-    block_on(async move {
-        chat::get_next_media(
-            ctx,
-            MsgId::new(msg_id),
-            direction,
-            msg_type,
-            or_msg_type2,
-            or_msg_type3,
-        )
-        .await
-        .map(|msg_id| msg_id.map(|id| id.to_u32()).unwrap_or_default())
-        .unwrap_or(0)
+        block_on(async move {
+            chat::get_next_media(
+                ctx,
+                MsgId::new(msg_id),
+                direction,
+                msg_type,
+                or_msg_type2,
+                or_msg_type3,
+            )
+            .await
+            .map(|msg_id| msg_id.map(|id| id.to_u32()).unwrap_or_default())
+            .unwrap_or(0)
+        })
     })
 }
 
@@ -1431,23 +2049,25 @@ fn from_prim<S, T>(s: S) -> Option<T>
     chat_id: u32,
     protect: libc::c_int,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_set_chat_protection()");
-        return 0;
-    }
-    let ctx = &*context;
-    let protect = if let Some(s) = ProtectionStatus::from_i32(protect) {
-        s
-    } else {
-        warn!(ctx, "bad protect-value for dc_set_chat_protection()");
-        return 0;
-    };
-
-    block_on(async move {
-        match ChatId::new(chat_id).set_protection(ctx, protect).await {
-            Ok(()) => 1,
-            Err(_) => 0,
+    ffi_boundary("dc_set_chat_protection", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_chat_protection()");
+            return 0;
         }
+        let ctx = &*context;
+        let protect = if let Some(s) = ProtectionStatus::from_i32(protect) {
+            s
+        } else {
+            warn!(ctx, "bad protect-value for dc_set_chat_protection()");
+            return 0;
+        };
+
+        block_on(async move {
+            match ChatId::new(chat_id).set_protection(ctx, protect).await {
+                Ok(()) => 1,
+                Err(_) => 0,
+            }
+        })
     })
 }
 
@@ -1457,85 +2077,93 @@ fn from_prim<S, T>(s: S) -> Option<T>
     chat_id: u32,
     archive: libc::c_int,
 ) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_set_chat_visibility()");
-        return;
-    }
-    let ctx = &*context;
-    let visibility = match archive {
-        0 => ChatVisibility::Normal,
-        1 => ChatVisibility::Archived,
-        2 => ChatVisibility::Pinned,
-        _ => {
-            warn!(
-                ctx,
-                "ignoring careless call to dc_set_chat_visibility(): unknown archived state",
-            );
+    ffi_boundary("dc_set_chat_visibility", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_chat_visibility()");
             return;
         }
-    };
+        let ctx = &*context;
+        let visibility = match archive {
+            0 => ChatVisibility::Normal,
+            1 => ChatVisibility::Archived,
+            2 => ChatVisibility::Pinned,
+            _ => {
+                warn!(
+                    ctx,
+                    "ignoring careless call to dc_set_chat_visibility(): unknown archived state",
+                );
+                return;
+            }
+        };
 
-    block_on(async move {
-        ChatId::new(chat_id)
-            .set_visibility(ctx, visibility)
-            .await
-            .context("Failed setting chat visibility")
-            .log_err(ctx)
-            .unwrap_or(())
+        block_on(async move {
+            ChatId::new(chat_id)
+                .set_visibility(ctx, visibility)
+                .await
+                .context("Failed setting chat visibility")
+                .log_err(ctx)
+                .unwrap_or(())
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_delete_chat(context: *mut dc_context_t, chat_id: u32) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_delete_chat()");
-        return;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_delete_chat", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_delete_chat()");
+            return;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ChatId::new(chat_id)
-            .delete(ctx)
-            .await
-            .context("Failed chat delete")
-            .log_err(ctx)
-            .ok();
+        block_on(async move {
+            ChatId::new(chat_id)
+                .delete(ctx)
+                .await
+                .context("Failed chat delete")
+                .log_err(ctx)
+                .ok();
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_block_chat(context: *mut dc_context_t, chat_id: u32) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_block_chat()");
-        return;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_block_chat", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_block_chat()");
+            return;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ChatId::new(chat_id)
-            .block(ctx)
-            .await
-            .context("Failed chat block")
-            .log_err(ctx)
-            .ok();
+        block_on(async move {
+            ChatId::new(chat_id)
+                .block(ctx)
+                .await
+                .context("Failed chat block")
+                .log_err(ctx)
+                .ok();
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accept_chat(context: *mut dc_context_t, chat_id: u32) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_accept_chat()");
-        return;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_accept_chat", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_accept_chat()");
+            return;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ChatId::new(chat_id)
-            .accept(ctx)
-            .await
-            .context("Failed chat accept")
-            .log_err(ctx)
-            .ok();
+        block_on(async move {
+            ChatId::new(chat_id)
+                .accept(ctx)
+                .await
+                .context("Failed chat accept")
+                .log_err(ctx)
+                .ok();
+        })
     })
 }
 
@@ -1544,22 +2172,24 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     chat_id: u32,
 ) -> *mut dc_array::dc_array_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_chat_contacts()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_chat_contacts", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_chat_contacts()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        let arr = dc_array_t::from(
-            chat::get_chat_contacts(ctx, ChatId::new(chat_id))
-                .await
-                .unwrap_or_log_default(ctx, "Failed get_chat_contacts")
-                .iter()
-                .map(|id| id.to_u32())
-                .collect::<Vec<u32>>(),
-        );
-        Box::into_raw(Box::new(arr))
+        block_on(async move {
+            let arr = dc_array_t::from(
+                chat::get_chat_contacts(ctx, ChatId::new(chat_id))
+                    .await
+                    .unwrap_or_log_default(ctx, "Failed get_chat_contacts")
+                    .iter()
+                    .map(|id| id.to_u32())
+                    .collect::<Vec<u32>>(),
+            );
+            Box::into_raw(Box::new(arr))
+        })
     })
 }
 
@@ -1569,46 +2199,50 @@ fn from_prim<S, T>(s: S) -> Option<T>
     chat_id: u32,
     query: *const libc::c_char,
 ) -> *mut dc_array::dc_array_t {
-    if context.is_null() || query.is_null() {
-        eprintln!("ignoring careless call to dc_search_msgs()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
-    let chat_id = if chat_id == 0 {
-        None
-    } else {
-        Some(ChatId::new(chat_id))
-    };
+    ffi_boundary("dc_search_msgs", context, || {
+        if context.is_null() || query.is_null() {
+            eprintln!("ignoring careless call to dc_search_msgs()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
+        let chat_id = if chat_id == 0 {
+            None
+        } else {
+            Some(ChatId::new(chat_id))
+        };
 
-    block_on(async move {
-        let arr = dc_array_t::from(
-            ctx.search_msgs(chat_id, &to_string_lossy(query))
-                .await
-                .unwrap_or_log_default(ctx, "Failed search_msgs")
-                .iter()
-                .map(|msg_id| msg_id.to_u32())
-                .collect::<Vec<u32>>(),
-        );
-        Box::into_raw(Box::new(arr))
+        block_on(async move {
+            let arr = dc_array_t::from(
+                ctx.search_msgs(chat_id, &to_string_lossy(query))
+                    .await
+                    .unwrap_or_log_default(ctx, "Failed search_msgs")
+                    .iter()
+                    .map(|msg_id| msg_id.to_u32())
+                    .collect::<Vec<u32>>(),
+            );
+            Box::into_raw(Box::new(arr))
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_chat(context: *mut dc_context_t, chat_id: u32) -> *mut dc_chat_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_chat()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_chat", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_chat()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        match chat::Chat::load_from_db(ctx, ChatId::new(chat_id)).await {
-            Ok(chat) => {
-                let ffi_chat = ChatWrapper { context, chat };
-                Box::into_raw(Box::new(ffi_chat))
+        block_on(async move {
+            match chat::Chat::load_from_db(ctx, ChatId::new(chat_id)).await {
+                Ok(chat) => {
+                    let ffi_chat = ChatWrapper { context, chat };
+                    Box::into_raw(Box::new(ffi_chat))
+                }
+                Err(_) => ptr::null_mut(),
             }
-            Err(_) => ptr::null_mut(),
-        }
+        })
     })
 }
 
@@ -1618,40 +2252,44 @@ fn from_prim<S, T>(s: S) -> Option<T>
     protect: libc::c_int,
     name: *const libc::c_char,
 ) -> u32 {
-    if context.is_null() || name.is_null() {
-        eprintln!("ignoring careless call to dc_create_group_chat()");
-        return 0;
-    }
-    let ctx = &*context;
-    let protect = if let Some(s) = ProtectionStatus::from_i32(protect) {
-        s
-    } else {
-        warn!(ctx, "bad protect-value for dc_create_group_chat()");
-        return 0;
-    };
+    ffi_boundary("dc_create_group_chat", context, || {
+        if context.is_null() || name.is_null() {
+            eprintln!("ignoring careless call to dc_create_group_chat()");
+            return 0;
+        }
+        let ctx = &*context;
+        let protect = if let Some(s) = ProtectionStatus::from_i32(protect) {
+            s
+        } else {
+            warn!(ctx, "bad protect-value for dc_create_group_chat()");
+            return 0;
+        };
 
-    block_on(async move {
-        chat::create_group_chat(ctx, protect, &to_string_lossy(name))
-            .await
-            .context("Failed to create group chat")
-            .log_err(ctx)
-            .map(|id| id.to_u32())
-            .unwrap_or(0)
+        block_on(async move {
+            chat::create_group_chat(ctx, protect, &to_string_lossy(name))
+                .await
+                .context("Failed to create group chat")
+                .log_err(ctx)
+                .map(|id| id.to_u32())
+                .unwrap_or(0)
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_create_broadcast_list(context: *mut dc_context_t) -> u32 {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_create_broadcast_list()");
-        return 0;
-    }
-    let ctx = &*context;
-    block_on(chat::create_broadcast_list(ctx))
-        .context("Failed to create broadcast list")
-        .log_err(ctx)
-        .map(|id| id.to_u32())
-        .unwrap_or(0)
+    ffi_boundary("dc_create_broadcast_list", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_create_broadcast_list()");
+            return 0;
+        }
+        let ctx = &*context;
+        block_on(chat::create_broadcast_list(ctx))
+            .context("Failed to create broadcast list")
+            .log_err(ctx)
+            .map(|id| id.to_u32())
+            .unwrap_or(0)
+    })
 }
 
 #[no_mangle]
@@ -1660,20 +2298,22 @@ fn from_prim<S, T>(s: S) -> Option<T>
     chat_id: u32,
     contact_id: u32,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_is_contact_in_chat()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_is_contact_in_chat", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_is_contact_in_chat()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(chat::is_contact_in_chat(
-        ctx,
-        ChatId::new(chat_id),
-        ContactId::new(contact_id),
-    ))
-    .context("is_contact_in_chat failed")
-    .log_err(ctx)
-    .unwrap_or_default() as libc::c_int
+        block_on(chat::is_contact_in_chat(
+            ctx,
+            ChatId::new(chat_id),
+            ContactId::new(contact_id),
+        ))
+        .context("is_contact_in_chat failed")
+        .log_err(ctx)
+        .unwrap_or_default() as libc::c_int
+    })
 }
 
 #[no_mangle]
@@ -1682,20 +2322,22 @@ fn from_prim<S, T>(s: S) -> Option<T>
     chat_id: u32,
     contact_id: u32,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_add_contact_to_chat()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_add_contact_to_chat", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_add_contact_to_chat()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(chat::add_contact_to_chat(
-        ctx,
-        ChatId::new(chat_id),
-        ContactId::new(contact_id),
-    ))
-    .context("Failed to add contact")
-    .log_err(ctx)
-    .is_ok() as libc::c_int
+        block_on(chat::add_contact_to_chat(
+            ctx,
+            ChatId::new(chat_id),
+            ContactId::new(contact_id),
+        ))
+        .context("Failed to add contact")
+        .log_err(ctx)
+        .is_ok() as libc::c_int
+    })
 }
 
 #[no_mangle]
@@ -1704,20 +2346,22 @@ fn from_prim<S, T>(s: S) -> Option<T>
     chat_id: u32,
     contact_id: u32,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_remove_contact_from_chat()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_remove_contact_from_chat", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_remove_contact_from_chat()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(chat::remove_contact_from_chat(
-        ctx,
-        ChatId::new(chat_id),
-        ContactId::new(contact_id),
-    ))
-    .context("Failed to remove contact")
-    .log_err(ctx)
-    .is_ok() as libc::c_int
+        block_on(chat::remove_contact_from_chat(
+            ctx,
+            ChatId::new(chat_id),
+            ContactId::new(contact_id),
+        ))
+        .context("Failed to remove contact")
+        .log_err(ctx)
+        .is_ok() as libc::c_int
+    })
 }
 
 #[no_mangle]
@@ -1726,18 +2370,22 @@ fn from_prim<S, T>(s: S) -> Option<T>
     chat_id: u32,
     name: *const libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL.to_u32() || name.is_null()
-    {
-        eprintln!("ignoring careless call to dc_set_chat_name()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_set_chat_name", context, || {
+        if context.is_null()
+            || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL.to_u32()
+            || name.is_null()
+        {
+            eprintln!("ignoring careless call to dc_set_chat_name()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        chat::set_chat_name(ctx, ChatId::new(chat_id), &to_string_lossy(name))
-            .await
-            .map(|_| 1)
-            .unwrap_or_log_default(ctx, "Failed to set chat name")
+        block_on(async move {
+            chat::set_chat_name(ctx, ChatId::new(chat_id), &to_string_lossy(name))
+                .await
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to set chat name")
+        })
     })
 }
 
@@ -1747,17 +2395,19 @@ fn from_prim<S, T>(s: S) -> Option<T>
     chat_id: u32,
     image: *const libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL.to_u32() {
-        eprintln!("ignoring careless call to dc_set_chat_profile_image()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_set_chat_profile_image", context, || {
+        if context.is_null() || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL.to_u32() {
+            eprintln!("ignoring careless call to dc_set_chat_profile_image()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        chat::set_chat_profile_image(ctx, ChatId::new(chat_id), &to_string_lossy(image))
-            .await
-            .map(|_| 1)
-            .unwrap_or_log_default(ctx, "Failed to set profile image")
+        block_on(async move {
+            chat::set_chat_profile_image(ctx, ChatId::new(chat_id), &to_string_lossy(image))
+                .await
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to set profile image")
+        })
     })
 }
 
@@ -1767,31 +2417,33 @@ fn from_prim<S, T>(s: S) -> Option<T>
     chat_id: u32,
     duration: i64,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_set_chat_mute_duration()");
-        return 0;
-    }
-    let ctx = &*context;
-    let muteDuration = match duration {
-        0 => MuteDuration::NotMuted,
-        -1 => MuteDuration::Forever,
-        n if n > 0 => SystemTime::now()
-            .checked_add(Duration::from_secs(duration as u64))
-            .map_or(MuteDuration::Forever, MuteDuration::Until),
-        _ => {
-            warn!(
-                ctx,
-                "dc_chat_set_mute_duration(): Can not use negative duration other than -1",
-            );
+    ffi_boundary("dc_set_chat_mute_duration", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_chat_mute_duration()");
             return 0;
         }
-    };
+        let ctx = &*context;
+        let muteDuration = match duration {
+            0 => MuteDuration::NotMuted,
+            -1 => MuteDuration::Forever,
+            n if n > 0 => SystemTime::now()
+                .checked_add(Duration::from_secs(duration as u64))
+                .map_or(MuteDuration::Forever, MuteDuration::Until),
+            _ => {
+                warn!(
+                    ctx,
+                    "dc_chat_set_mute_duration(): Can not use negative duration other than -1",
+                );
+                return 0;
+            }
+        };
 
-    block_on(async move {
-        chat::set_muted(ctx, ChatId::new(chat_id), muteDuration)
-            .await
-            .map(|_| 1)
-            .unwrap_or_log_default(ctx, "Failed to set mute duration")
+        block_on(async move {
+            chat::set_muted(ctx, ChatId::new(chat_id), muteDuration)
+                .await
+                .map(|_| 1)
+                .unwrap_or_log_default(ctx, "Failed to set mute duration")
+        })
     })
 }
 
@@ -1800,21 +2452,23 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     chat_id: u32,
 ) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_chat_encrinfo()");
-        return "".strdup();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_chat_encrinfo", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_chat_encrinfo()");
+            return "".strdup();
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ChatId::new(chat_id)
-            .get_encryption_info(ctx)
-            .await
-            .map(|s| s.strdup())
-            .unwrap_or_else(|e| {
-                error!(ctx, "{e:#}");
-                ptr::null_mut()
-            })
+        block_on(async move {
+            ChatId::new(chat_id)
+                .get_encryption_info(ctx)
+                .await
+                .map(|s| s.strdup())
+                .unwrap_or_else(|e| {
+                    error!(ctx, "{e:#}");
+                    ptr::null_mut()
+                })
+        })
     })
 }
 
@@ -1823,22 +2477,24 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     chat_id: u32,
 ) -> u32 {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_chat_ephemeral_timer()");
-        return 0;
-    }
-    let ctx = &*context;
-
-    // Timer value 0 is returned in the rare case of a database error,
-    // but it is not dangerous since it is only meant to be used as a
-    // default when changing the value. Such errors should not be
-    // ignored when ephemeral timer value is used to construct
-    // message headers.
-    block_on(async move { ChatId::new(chat_id).get_ephemeral_timer(ctx).await })
-        .context("Failed to get ephemeral timer")
-        .log_err(ctx)
-        .unwrap_or_default()
-        .to_u32()
+    ffi_boundary("dc_get_chat_ephemeral_timer", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_chat_ephemeral_timer()");
+            return 0;
+        }
+        let ctx = &*context;
+
+        // Timer value 0 is returned in the rare case of a database error,
+        // but it is not dangerous since it is only meant to be used as a
+        // default when changing the value. Such errors should not be
+        // ignored when ephemeral timer value is used to construct
+        // message headers.
+        block_on(async move { ChatId::new(chat_id).get_ephemeral_timer(ctx).await })
+            .context("Failed to get ephemeral timer")
+            .log_err(ctx)
+            .unwrap_or_default()
+            .to_u32()
+    })
 }
 
 #[no_mangle]
@@ -1847,19 +2503,70 @@ fn from_prim<S, T>(s: S) -> Option<T>
     chat_id: u32,
     timer: u32,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_set_chat_ephemeral_timer()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_set_chat_ephemeral_timer", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_chat_ephemeral_timer()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        ChatId::new(chat_id)
-            .set_ephemeral_timer(ctx, EphemeralTimer::from_u32(timer))
-            .await
-            .context("Failed to set ephemeral timer")
+        block_on(async move {
+            ChatId::new(chat_id)
+                .set_ephemeral_timer(ctx, EphemeralTimer::from_u32(timer))
+                .await
+                .context("Failed to set ephemeral timer")
+                .log_err(ctx)
+                .is_ok() as libc::c_int
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_chat_delete_server_after(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> i64 {
+    ffi_boundary("dc_get_chat_delete_server_after", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_chat_delete_server_after()");
+            return -1;
+        }
+        let ctx = &*context;
+
+        block_on(async move { ChatId::new(chat_id).get_delete_server_after(ctx).await })
+            .context("Failed to get delete_server_after")
             .log_err(ctx)
-            .is_ok() as libc::c_int
+            .unwrap_or_default()
+            .unwrap_or(-1)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_chat_delete_server_after(
+    context: *mut dc_context_t,
+    chat_id: u32,
+    delete_server_after: i64,
+) -> libc::c_int {
+    ffi_boundary("dc_set_chat_delete_server_after", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_chat_delete_server_after()");
+            return 0;
+        }
+        let ctx = &*context;
+        let delete_server_after = if delete_server_after < 0 {
+            None
+        } else {
+            Some(delete_server_after)
+        };
+
+        block_on(async move {
+            ChatId::new(chat_id)
+                .set_delete_server_after(ctx, delete_server_after)
+                .await
+                .context("Failed to set delete_server_after")
+                .log_err(ctx)
+                .is_ok() as libc::c_int
+        })
     })
 }
 
@@ -1868,17 +2575,19 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     msg_id: u32,
 ) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_msg_info()");
-        return "".strdup();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_msg_info", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_msg_info()");
+            return "".strdup();
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        message::get_msg_info(ctx, MsgId::new(msg_id))
-            .await
-            .unwrap_or_log_default(ctx, "failed to get msg id")
-            .strdup()
+        block_on(async move {
+            message::get_msg_info(ctx, MsgId::new(msg_id))
+                .await
+                .unwrap_or_log_default(ctx, "failed to get msg id")
+                .strdup()
+        })
     })
 }
 
@@ -1887,15 +2596,17 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     msg_id: u32,
 ) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_msg_html()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_msg_html", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_msg_html()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
 
-    block_on(MsgId::new(msg_id).get_html(ctx))
-        .unwrap_or_log_default(ctx, "Failed get_msg_html")
-        .strdup()
+        block_on(MsgId::new(msg_id).get_html(ctx))
+            .unwrap_or_log_default(ctx, "Failed get_msg_html")
+            .strdup()
+    })
 }
 
 #[no_mangle]
@@ -1903,20 +2614,22 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     msg_id: u32,
 ) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_mime_headers()");
-        return ptr::null_mut(); // NULL explicitly defined as "no mime headers"
-    }
-    let ctx = &*context;
-
-    block_on(async move {
-        let mime = message::get_mime_headers(ctx, MsgId::new(msg_id))
-            .await
-            .unwrap_or_log_default(ctx, "failed to get mime headers");
-        if mime.is_empty() {
-            return ptr::null_mut();
+    ffi_boundary("dc_get_mime_headers", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_mime_headers()");
+            return ptr::null_mut(); // NULL explicitly defined as "no mime headers"
         }
-        mime.strdup()
+        let ctx = &*context;
+
+        block_on(async move {
+            let mime = message::get_mime_headers(ctx, MsgId::new(msg_id))
+                .await
+                .unwrap_or_log_default(ctx, "failed to get mime headers");
+            if mime.is_empty() {
+                return ptr::null_mut();
+            }
+            mime.strdup()
+        })
     })
 }
 
@@ -1926,17 +2639,19 @@ fn from_prim<S, T>(s: S) -> Option<T>
     msg_ids: *const u32,
     msg_cnt: libc::c_int,
 ) {
-    if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
-        eprintln!("ignoring careless call to dc_delete_msgs()");
-        return;
-    }
-    let ctx = &*context;
-    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
+    ffi_boundary("dc_delete_msgs", context, || {
+        if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
+            eprintln!("ignoring careless call to dc_delete_msgs()");
+            return;
+        }
+        let ctx = &*context;
+        let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
 
-    block_on(message::delete_msgs(ctx, &msg_ids))
-        .context("failed dc_delete_msgs() call")
-        .log_err(ctx)
-        .ok();
+        block_on(message::delete_msgs(ctx, &msg_ids))
+            .context("failed dc_delete_msgs() call")
+            .log_err(ctx)
+            .ok();
+    })
 }
 
 #[no_mangle]
@@ -1946,21 +2661,23 @@ fn from_prim<S, T>(s: S) -> Option<T>
     msg_cnt: libc::c_int,
     chat_id: u32,
 ) {
-    if context.is_null()
-        || msg_ids.is_null()
-        || msg_cnt <= 0
-        || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL.to_u32()
-    {
-        eprintln!("ignoring careless call to dc_forward_msgs()");
-        return;
-    }
-    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
-    let ctx = &*context;
+    ffi_boundary("dc_forward_msgs", context, || {
+        if context.is_null()
+            || msg_ids.is_null()
+            || msg_cnt <= 0
+            || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL.to_u32()
+        {
+            eprintln!("ignoring careless call to dc_forward_msgs()");
+            return;
+        }
+        let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
+        let ctx = &*context;
 
-    block_on(async move {
-        chat::forward_msgs(ctx, &msg_ids[..], ChatId::new(chat_id))
-            .await
-            .unwrap_or_log_default(ctx, "Failed to forward message")
+        block_on(async move {
+            chat::forward_msgs(ctx, &msg_ids[..], ChatId::new(chat_id))
+                .await
+                .unwrap_or_log_default(ctx, "Failed to forward message")
+        })
     })
 }
 
@@ -1970,19 +2687,21 @@ fn from_prim<S, T>(s: S) -> Option<T>
     msg_ids: *const u32,
     msg_cnt: libc::c_int,
 ) -> libc::c_int {
-    if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
-        eprintln!("ignoring careless call to dc_resend_msgs()");
-        return 0;
-    }
-    let ctx = &*context;
-    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
-
-    if let Err(err) = block_on(chat::resend_msgs(ctx, &msg_ids)) {
-        error!(ctx, "Resending failed: {err:#}");
-        0
-    } else {
-        1
-    }
+    ffi_boundary("dc_resend_msgs", context, || {
+        if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
+            eprintln!("ignoring careless call to dc_resend_msgs()");
+            return 0;
+        }
+        let ctx = &*context;
+        let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
+
+        if let Err(err) = block_on(chat::resend_msgs(ctx, &msg_ids)) {
+            error!(ctx, "Resending failed: {err:#}");
+            0
+        } else {
+            1
+        }
+    })
 }
 
 #[no_mangle]
@@ -1991,70 +2710,78 @@ fn from_prim<S, T>(s: S) -> Option<T>
     msg_ids: *const u32,
     msg_cnt: libc::c_int,
 ) {
-    if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
-        eprintln!("ignoring careless call to dc_markseen_msgs()");
-        return;
-    }
-    let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
-    let ctx = &*context;
+    ffi_boundary("dc_markseen_msgs", context, || {
+        if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
+            eprintln!("ignoring careless call to dc_markseen_msgs()");
+            return;
+        }
+        let msg_ids = convert_and_prune_message_ids(msg_ids, msg_cnt);
+        let ctx = &*context;
 
-    block_on(message::markseen_msgs(ctx, msg_ids))
-        .context("failed dc_markseen_msgs() call")
-        .log_err(ctx)
-        .ok();
+        block_on(message::markseen_msgs(ctx, msg_ids))
+            .context("failed dc_markseen_msgs() call")
+            .log_err(ctx)
+            .ok();
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_msg(context: *mut dc_context_t, msg_id: u32) -> *mut dc_msg_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_msg()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
-
-    block_on(async move {
-        let message = match message::Message::load_from_db(ctx, MsgId::new(msg_id)).await {
-            Ok(msg) => msg,
-            Err(e) => {
-                if msg_id <= constants::DC_MSG_ID_LAST_SPECIAL {
-                    // C-core API returns empty messages, do the same
-                    warn!(
-                        ctx,
-                        "dc_get_msg called with special msg_id={msg_id}, returning empty msg"
-                    );
-                    message::Message::default()
-                } else {
-                    error!(ctx, "dc_get_msg could not retrieve msg_id {msg_id}: {e:#}");
-                    return ptr::null_mut();
+    ffi_boundary("dc_get_msg", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_msg()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
+
+        block_on(async move {
+            let message = match message::Message::load_from_db(ctx, MsgId::new(msg_id)).await {
+                Ok(msg) => msg,
+                Err(e) => {
+                    if msg_id <= constants::DC_MSG_ID_LAST_SPECIAL {
+                        // C-core API returns empty messages, do the same
+                        warn!(
+                            ctx,
+                            "dc_get_msg called with special msg_id={msg_id}, returning empty msg"
+                        );
+                        message::Message::default()
+                    } else {
+                        error!(ctx, "dc_get_msg could not retrieve msg_id {msg_id}: {e:#}");
+                        return ptr::null_mut();
+                    }
                 }
-            }
-        };
-        let ffi_msg = MessageWrapper { context, message };
-        Box::into_raw(Box::new(ffi_msg))
+            };
+            let ffi_msg = MessageWrapper { context, message };
+            Box::into_raw(Box::new(ffi_msg))
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_download_full_msg(context: *mut dc_context_t, msg_id: u32) {
+    ffi_boundary("dc_download_full_msg", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_download_full_msg()");
+            return;
+        }
+        let ctx = &*context;
+        block_on(MsgId::new(msg_id).download_full(ctx))
+            .context("Failed to download message fully.")
+            .log_err(ctx)
+            .ok();
     })
 }
 
-#[no_mangle]
-pub unsafe extern "C" fn dc_download_full_msg(context: *mut dc_context_t, msg_id: u32) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_download_full_msg()");
-        return;
-    }
-    let ctx = &*context;
-    block_on(MsgId::new(msg_id).download_full(ctx))
-        .context("Failed to download message fully.")
-        .log_err(ctx)
-        .ok();
-}
-
 #[no_mangle]
 pub unsafe extern "C" fn dc_may_be_valid_addr(addr: *const libc::c_char) -> libc::c_int {
-    if addr.is_null() {
-        eprintln!("ignoring careless call to dc_may_be_valid_addr()");
-        return 0;
-    }
+    ffi_boundary("dc_may_be_valid_addr", ptr::null(), || {
+        if addr.is_null() {
+            eprintln!("ignoring careless call to dc_may_be_valid_addr()");
+            return 0;
+        }
 
-    contact::may_be_valid_addr(&to_string_lossy(addr)) as libc::c_int
+        contact::may_be_valid_addr(&to_string_lossy(addr)) as libc::c_int
+    })
 }
 
 #[no_mangle]
@@ -2062,18 +2789,20 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     addr: *const libc::c_char,
 ) -> u32 {
-    if context.is_null() || addr.is_null() {
-        eprintln!("ignoring careless call to dc_lookup_contact_id_by_addr()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_lookup_contact_id_by_addr", context, || {
+        if context.is_null() || addr.is_null() {
+            eprintln!("ignoring careless call to dc_lookup_contact_id_by_addr()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        Contact::lookup_id_by_addr(ctx, &to_string_lossy(addr), Origin::IncomingReplyTo)
-            .await
-            .unwrap_or_log_default(ctx, "failed to lookup id")
-            .map(|id| id.to_u32())
-            .unwrap_or_default()
+        block_on(async move {
+            Contact::lookup_id_by_addr(ctx, &to_string_lossy(addr), Origin::IncomingReplyTo)
+                .await
+                .unwrap_or_log_default(ctx, "failed to lookup id")
+                .map(|id| id.to_u32())
+                .unwrap_or_default()
+        })
     })
 }
 
@@ -2083,18 +2812,20 @@ fn from_prim<S, T>(s: S) -> Option<T>
     name: *const libc::c_char,
     addr: *const libc::c_char,
 ) -> u32 {
-    if context.is_null() || addr.is_null() {
-        eprintln!("ignoring careless call to dc_create_contact()");
-        return 0;
-    }
-    let ctx = &*context;
-    let name = to_string_lossy(name);
+    ffi_boundary("dc_create_contact", context, || {
+        if context.is_null() || addr.is_null() {
+            eprintln!("ignoring careless call to dc_create_contact()");
+            return 0;
+        }
+        let ctx = &*context;
+        let name = to_string_lossy(name);
 
-    block_on(Contact::create(ctx, &name, &to_string_lossy(addr)))
-        .context("Cannot create contact")
-        .log_err(ctx)
-        .map(|id| id.to_u32())
-        .unwrap_or(0)
+        block_on(Contact::create(ctx, &name, &to_string_lossy(addr)))
+            .context("Cannot create contact")
+            .log_err(ctx)
+            .map(|id| id.to_u32())
+            .unwrap_or(0)
+    })
 }
 
 #[no_mangle]
@@ -2102,17 +2833,19 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     addr_book: *const libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() || addr_book.is_null() {
-        eprintln!("ignoring careless call to dc_add_address_book()");
-        return 0;
-    }
-    let ctx = &*context;
-
-    block_on(async move {
-        match Contact::add_address_book(ctx, &to_string_lossy(addr_book)).await {
-            Ok(cnt) => cnt as libc::c_int,
-            Err(_) => 0,
+    ffi_boundary("dc_add_address_book", context, || {
+        if context.is_null() || addr_book.is_null() {
+            eprintln!("ignoring careless call to dc_add_address_book()");
+            return 0;
         }
+        let ctx = &*context;
+
+        block_on(async move {
+            match Contact::add_address_book(ctx, &to_string_lossy(addr_book)).await {
+                Ok(cnt) => cnt as libc::c_int,
+                Err(_) => 0,
+            }
+        })
     })
 }
 
@@ -2122,36 +2855,40 @@ fn from_prim<S, T>(s: S) -> Option<T>
     flags: u32,
     query: *const libc::c_char,
 ) -> *mut dc_array::dc_array_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_contacts()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
-    let query = to_opt_string_lossy(query);
-
-    block_on(async move {
-        match Contact::get_all(ctx, flags, query.as_deref()).await {
-            Ok(contacts) => Box::into_raw(Box::new(dc_array_t::from(
-                contacts.iter().map(|id| id.to_u32()).collect::<Vec<u32>>(),
-            ))),
-            Err(_) => ptr::null_mut(),
+    ffi_boundary("dc_get_contacts", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_contacts()");
+            return ptr::null_mut();
         }
+        let ctx = &*context;
+        let query = to_opt_string_lossy(query);
+
+        block_on(async move {
+            match Contact::get_all(ctx, flags, query.as_deref()).await {
+                Ok(contacts) => Box::into_raw(Box::new(dc_array_t::from(
+                    contacts.iter().map(|id| id.to_u32()).collect::<Vec<u32>>(),
+                ))),
+                Err(_) => ptr::null_mut(),
+            }
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_blocked_cnt(context: *mut dc_context_t) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_blocked_cnt()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_blocked_cnt", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_blocked_cnt()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        Contact::get_all_blocked(ctx)
-            .await
-            .unwrap_or_log_default(ctx, "failed to get blocked count")
-            .len() as libc::c_int
+        block_on(async move {
+            Contact::get_all_blocked(ctx)
+                .await
+                .unwrap_or_log_default(ctx, "failed to get blocked count")
+                .len() as libc::c_int
+        })
     })
 }
 
@@ -2159,23 +2896,25 @@ fn from_prim<S, T>(s: S) -> Option<T>
 pub unsafe extern "C" fn dc_get_blocked_contacts(
     context: *mut dc_context_t,
 ) -> *mut dc_array::dc_array_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_blocked_contacts()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_blocked_contacts", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_blocked_contacts()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        Box::into_raw(Box::new(dc_array_t::from(
-            Contact::get_all_blocked(ctx)
-                .await
-                .context("Can't get blocked contacts")
-                .log_err(ctx)
-                .unwrap_or_default()
-                .iter()
-                .map(|id| id.to_u32())
-                .collect::<Vec<u32>>(),
-        )))
+        block_on(async move {
+            Box::into_raw(Box::new(dc_array_t::from(
+                Contact::get_all_blocked(ctx)
+                    .await
+                    .context("Can't get blocked contacts")
+                    .log_err(ctx)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|id| id.to_u32())
+                    .collect::<Vec<u32>>(),
+            )))
+        })
     })
 }
 
@@ -2185,27 +2924,29 @@ fn from_prim<S, T>(s: S) -> Option<T>
     contact_id: u32,
     block: libc::c_int,
 ) {
-    let contact_id = ContactId::new(contact_id);
-    if context.is_null() || contact_id.is_special() {
-        eprintln!("ignoring careless call to dc_block_contact()");
-        return;
-    }
-    let ctx = &*context;
-    block_on(async move {
-        if block == 0 {
-            Contact::unblock(ctx, contact_id)
-                .await
-                .context("Can't unblock contact")
-                .log_err(ctx)
-                .ok();
-        } else {
-            Contact::block(ctx, contact_id)
-                .await
-                .context("Can't block contact")
-                .log_err(ctx)
-                .ok();
+    ffi_boundary("dc_block_contact", context, || {
+        let contact_id = ContactId::new(contact_id);
+        if context.is_null() || contact_id.is_special() {
+            eprintln!("ignoring careless call to dc_block_contact()");
+            return;
         }
-    });
+        let ctx = &*context;
+        block_on(async move {
+            if block == 0 {
+                Contact::unblock(ctx, contact_id)
+                    .await
+                    .context("Can't unblock contact")
+                    .log_err(ctx)
+                    .ok();
+            } else {
+                Contact::block(ctx, contact_id)
+                    .await
+                    .context("Can't block contact")
+                    .log_err(ctx)
+                    .ok();
+            }
+        });
+    })
 }
 
 #[no_mangle]
@@ -2213,20 +2954,22 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     contact_id: u32,
 ) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_contact_encrinfo()");
-        return "".strdup();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_contact_encrinfo", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_contact_encrinfo()");
+            return "".strdup();
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        Contact::get_encrinfo(ctx, ContactId::new(contact_id))
-            .await
-            .map(|s| s.strdup())
-            .unwrap_or_else(|e| {
-                error!(ctx, "{e:#}");
-                ptr::null_mut()
-            })
+        block_on(async move {
+            Contact::get_encrinfo(ctx, ContactId::new(contact_id))
+                .await
+                .map(|s| s.strdup())
+                .unwrap_or_else(|e| {
+                    error!(ctx, "{e:#}");
+                    ptr::null_mut()
+                })
+        })
     })
 }
 
@@ -2235,21 +2978,23 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     contact_id: u32,
 ) -> libc::c_int {
-    let contact_id = ContactId::new(contact_id);
-    if context.is_null() || contact_id.is_special() {
-        eprintln!("ignoring careless call to dc_delete_contact()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_delete_contact", context, || {
+        let contact_id = ContactId::new(contact_id);
+        if context.is_null() || contact_id.is_special() {
+            eprintln!("ignoring careless call to dc_delete_contact()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        match Contact::delete(ctx, contact_id).await {
-            Ok(_) => 1,
-            Err(err) => {
-                error!(ctx, "cannot delete contact: {err:#}");
-                0
+        block_on(async move {
+            match Contact::delete(ctx, contact_id).await {
+                Ok(_) => 1,
+                Err(err) => {
+                    error!(ctx, "cannot delete contact: {err:#}");
+                    0
+                }
             }
-        }
+        })
     })
 }
 
@@ -2258,17 +3003,19 @@ fn from_prim<S, T>(s: S) -> Option<T>
     context: *mut dc_context_t,
     contact_id: u32,
 ) -> *mut dc_contact_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_contact()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_get_contact", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_contact()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        Contact::get_by_id(ctx, ContactId::new(contact_id))
-            .await
-            .map(|contact| Box::into_raw(Box::new(ContactWrapper { context, contact })))
-            .unwrap_or_else(|_| ptr::null_mut())
+        block_on(async move {
+            Contact::get_by_id(ctx, ContactId::new(contact_id))
+                .await
+                .map(|contact| Box::into_raw(Box::new(ContactWrapper { context, contact })))
+                .unwrap_or_else(|_| ptr::null_mut())
+        })
     })
 }
 
@@ -2288,26 +3035,28 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     param1: *const libc::c_char,
     param2: *const libc::c_char,
 ) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_imex()");
-        return;
-    }
-    let what = match imex::ImexMode::from_i32(what_raw) {
-        Some(what) => what,
-        None => {
-            eprintln!("ignoring invalid argument {what_raw} to dc_imex");
+    ffi_boundary("dc_imex", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_imex()");
             return;
         }
-    };
-    let passphrase = to_opt_string_lossy(param2);
+        let what = match imex::ImexMode::from_i32(what_raw) {
+            Some(what) => what,
+            None => {
+                eprintln!("ignoring invalid argument {what_raw} to dc_imex");
+                return;
+            }
+        };
+        let passphrase = to_opt_string_lossy(param2);
 
-    let ctx = &*context;
+        let ctx = &*context;
 
-    if let Some(param1) = to_opt_string_lossy(param1) {
-        spawn_imex(ctx.clone(), what, param1, passphrase);
-    } else {
-        eprintln!("dc_imex called without a valid directory");
-    }
+        if let Some(param1) = to_opt_string_lossy(param1) {
+            spawn_imex(ctx.clone(), what, param1, passphrase);
+        } else {
+            eprintln!("dc_imex called without a valid directory");
+        }
+    })
 }
 
 #[no_mangle]
@@ -2315,41 +3064,45 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     context: *mut dc_context_t,
     dir: *const libc::c_char,
 ) -> *mut libc::c_char {
-    if context.is_null() || dir.is_null() {
-        eprintln!("ignoring careless call to dc_imex_has_backup()");
-        return ptr::null_mut(); // NULL explicitly defined as "has no backup"
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_imex_has_backup", context, || {
+        if context.is_null() || dir.is_null() {
+            eprintln!("ignoring careless call to dc_imex_has_backup()");
+            return ptr::null_mut(); // NULL explicitly defined as "has no backup"
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        match imex::has_backup(ctx, to_string_lossy(dir).as_ref()).await {
-            Ok(res) => res.strdup(),
-            Err(err) => {
-                // do not bubble up error to the user,
-                // the ui will expect that the file does not exist or cannot be accessed
-                warn!(ctx, "dc_imex_has_backup: {err:#}");
-                ptr::null_mut()
+        block_on(async move {
+            match imex::has_backup(ctx, to_string_lossy(dir).as_ref()).await {
+                Ok(res) => res.strdup(),
+                Err(err) => {
+                    // do not bubble up error to the user,
+                    // the ui will expect that the file does not exist or cannot be accessed
+                    warn!(ctx, "dc_imex_has_backup: {err:#}");
+                    ptr::null_mut()
+                }
             }
-        }
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_initiate_key_transfer(context: *mut dc_context_t) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_initiate_key_transfer()");
-        return ptr::null_mut(); // NULL explicitly defined as "error"
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_initiate_key_transfer", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_initiate_key_transfer()");
+            return ptr::null_mut(); // NULL explicitly defined as "error"
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        match imex::initiate_key_transfer(ctx).await {
-            Ok(res) => res.strdup(),
-            Err(err) => {
-                error!(ctx, "dc_initiate_key_transfer(): {err:#}");
-                ptr::null_mut()
+        block_on(async move {
+            match imex::initiate_key_transfer(ctx).await {
+                Ok(res) => res.strdup(),
+                Err(err) => {
+                    error!(ctx, "dc_initiate_key_transfer(): {err:#}");
+                    ptr::null_mut()
+                }
             }
-        }
+        })
     })
 }
 
@@ -2359,33 +3112,38 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     msg_id: u32,
     setup_code: *const libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() || msg_id <= constants::DC_MSG_ID_LAST_SPECIAL || setup_code.is_null() {
-        eprintln!("ignoring careless call to dc_continue_key_transfer()");
-        return 0;
-    }
-    let ctx = &*context;
-
-    block_on(async move {
-        match imex::continue_key_transfer(ctx, MsgId::new(msg_id), &to_string_lossy(setup_code))
-            .await
+    ffi_boundary("dc_continue_key_transfer", context, || {
+        if context.is_null() || msg_id <= constants::DC_MSG_ID_LAST_SPECIAL || setup_code.is_null()
         {
-            Ok(()) => 1,
-            Err(err) => {
-                warn!(ctx, "dc_continue_key_transfer: {err:#}");
-                0
-            }
+            eprintln!("ignoring careless call to dc_continue_key_transfer()");
+            return 0;
         }
+        let ctx = &*context;
+
+        block_on(async move {
+            match imex::continue_key_transfer(ctx, MsgId::new(msg_id), &to_string_lossy(setup_code))
+                .await
+            {
+                Ok(()) => 1,
+                Err(err) => {
+                    warn!(ctx, "dc_continue_key_transfer: {err:#}");
+                    0
+                }
+            }
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_stop_ongoing_process(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_stop_ongoing_process()");
-        return;
-    }
-    let ctx = &*context;
-    block_on(ctx.stop_ongoing());
+    ffi_boundary("dc_stop_ongoing_process", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_stop_ongoing_process()");
+            return;
+        }
+        let ctx = &*context;
+        block_on(ctx.stop_ongoing());
+    })
 }
 
 #[no_mangle]
@@ -2393,17 +3151,19 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     context: *mut dc_context_t,
     qr: *const libc::c_char,
 ) -> *mut dc_lot_t {
-    if context.is_null() || qr.is_null() {
-        eprintln!("ignoring careless call to dc_check_qr()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_check_qr", context, || {
+        if context.is_null() || qr.is_null() {
+            eprintln!("ignoring careless call to dc_check_qr()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
 
-    let lot = match block_on(qr::check_qr(ctx, &to_string_lossy(qr))) {
-        Ok(qr) => qr.into(),
-        Err(err) => err.into(),
-    };
-    Box::into_raw(Box::new(lot))
+        let lot = match block_on(qr::check_qr(ctx, &to_string_lossy(qr))) {
+            Ok(qr) => qr.into(),
+            Err(err) => err.into(),
+        };
+        Box::into_raw(Box::new(lot))
+    })
 }
 
 #[no_mangle]
@@ -2411,20 +3171,22 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     context: *mut dc_context_t,
     chat_id: u32,
 ) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_securejoin_qr()");
-        return "".strdup();
-    }
-    let ctx = &*context;
-    let chat_id = if chat_id == 0 {
-        None
-    } else {
-        Some(ChatId::new(chat_id))
-    };
+    ffi_boundary("dc_get_securejoin_qr", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_securejoin_qr()");
+            return "".strdup();
+        }
+        let ctx = &*context;
+        let chat_id = if chat_id == 0 {
+            None
+        } else {
+            Some(ChatId::new(chat_id))
+        };
 
-    block_on(securejoin::get_securejoin_qr(ctx, chat_id))
-        .unwrap_or_else(|_| "".to_string())
-        .strdup()
+        block_on(securejoin::get_securejoin_qr(ctx, chat_id))
+            .unwrap_or_else(|_| "".to_string())
+            .strdup()
+    })
 }
 
 #[no_mangle]
@@ -2432,20 +3194,22 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     context: *mut dc_context_t,
     chat_id: u32,
 ) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to generate_verification_qr()");
-        return "".strdup();
-    }
-    let ctx = &*context;
-    let chat_id = if chat_id == 0 {
-        None
-    } else {
-        Some(ChatId::new(chat_id))
-    };
+    ffi_boundary("dc_get_securejoin_qr_svg", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to generate_verification_qr()");
+            return "".strdup();
+        }
+        let ctx = &*context;
+        let chat_id = if chat_id == 0 {
+            None
+        } else {
+            Some(ChatId::new(chat_id))
+        };
 
-    block_on(get_securejoin_qr_svg(ctx, chat_id))
-        .unwrap_or_else(|_| "".to_string())
-        .strdup()
+        block_on(get_securejoin_qr_svg(ctx, chat_id))
+            .unwrap_or_else(|_| "".to_string())
+            .strdup()
+    })
 }
 
 #[no_mangle]
@@ -2453,19 +3217,21 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     context: *mut dc_context_t,
     qr: *const libc::c_char,
 ) -> u32 {
-    if context.is_null() || qr.is_null() {
-        eprintln!("ignoring careless call to dc_join_securejoin()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_join_securejoin", context, || {
+        if context.is_null() || qr.is_null() {
+            eprintln!("ignoring careless call to dc_join_securejoin()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        securejoin::join_securejoin(ctx, &to_string_lossy(qr))
-            .await
-            .map(|chatid| chatid.to_u32())
-            .context("failed dc_join_securejoin() call")
-            .log_err(ctx)
-            .unwrap_or_default()
+        block_on(async move {
+            securejoin::join_securejoin(ctx, &to_string_lossy(qr))
+                .await
+                .map(|chatid| chatid.to_u32())
+                .context("failed dc_join_securejoin() call")
+                .log_err(ctx)
+                .unwrap_or_default()
+        })
     })
 }
 
@@ -2475,20 +3241,25 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     chat_id: u32,
     seconds: libc::c_int,
 ) {
-    if context.is_null() || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL.to_u32() || seconds < 0 {
-        eprintln!("ignoring careless call to dc_send_locations_to_chat()");
-        return;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_send_locations_to_chat", context, || {
+        if context.is_null()
+            || chat_id <= constants::DC_CHAT_ID_LAST_SPECIAL.to_u32()
+            || seconds < 0
+        {
+            eprintln!("ignoring careless call to dc_send_locations_to_chat()");
+            return;
+        }
+        let ctx = &*context;
 
-    block_on(location::send_locations_to_chat(
-        ctx,
-        ChatId::new(chat_id),
-        seconds as i64,
-    ))
-    .context("Failed dc_send_locations_to_chat()")
-    .log_err(ctx)
-    .ok();
+        block_on(location::send_locations_to_chat(
+            ctx,
+            ChatId::new(chat_id),
+            seconds as i64,
+        ))
+        .context("Failed dc_send_locations_to_chat()")
+        .log_err(ctx)
+        .ok();
+    })
 }
 
 #[no_mangle]
@@ -2496,19 +3267,22 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     context: *mut dc_context_t,
     chat_id: u32,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_is_sending_locations_to_chat()");
-        return 0;
-    }
-    let ctx = &*context;
-    let chat_id = if chat_id == 0 {
-        None
-    } else {
-        Some(ChatId::new(chat_id))
-    };
+    ffi_boundary("dc_is_sending_locations_to_chat", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_is_sending_locations_to_chat()");
+            return 0;
+        }
+        let ctx = &*context;
+        let chat_id = if chat_id == 0 {
+            None
+        } else {
+            Some(ChatId::new(chat_id))
+        };
 
-    block_on(location::is_sending_locations_to_chat(ctx, chat_id))
-        .unwrap_or_log_default(ctx, "Failed dc_is_sending_locations_to_chat()") as libc::c_int
+        block_on(location::is_sending_locations_to_chat(ctx, chat_id))
+            .unwrap_or_log_default(ctx, "Failed dc_is_sending_locations_to_chat()")
+            as libc::c_int
+    })
 }
 
 #[no_mangle]
@@ -2518,13 +3292,15 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     longitude: libc::c_double,
     accuracy: libc::c_double,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_set_location()");
-        return 0;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_set_location", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_set_location()");
+            return 0;
+        }
+        let ctx = &*context;
 
-    block_on(location::set(ctx, latitude, longitude, accuracy)) as _
+        block_on(location::set(ctx, latitude, longitude, accuracy)) as _
+    })
 }
 
 #[no_mangle]
@@ -2535,55 +3311,111 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     timestamp_begin: i64,
     timestamp_end: i64,
 ) -> *mut dc_array::dc_array_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_locations()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
-    let chat_id = if chat_id == 0 {
-        None
-    } else {
-        Some(ChatId::new(chat_id))
-    };
-    let contact_id = if contact_id == 0 {
-        None
-    } else {
-        Some(contact_id)
-    };
+    ffi_boundary("dc_get_locations", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_locations()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
+        let chat_id = if chat_id == 0 {
+            None
+        } else {
+            Some(ChatId::new(chat_id))
+        };
+        let contact_id = if contact_id == 0 {
+            None
+        } else {
+            Some(contact_id)
+        };
 
-    block_on(async move {
-        let res = location::get_range(ctx, chat_id, contact_id, timestamp_begin, timestamp_end)
-            .await
-            .unwrap_or_log_default(ctx, "Failed get_locations");
-        Box::into_raw(Box::new(dc_array_t::from(res)))
+        block_on(async move {
+            let res = location::get_range(ctx, chat_id, contact_id, timestamp_begin, timestamp_end)
+                .await
+                .unwrap_or_log_default(ctx, "Failed get_locations");
+            Box::into_raw(Box::new(dc_array_t::from(res)))
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_delete_all_locations(context: *mut dc_context_t) {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_delete_all_locations()");
-        return;
-    }
-    let ctx = &*context;
+    ffi_boundary("dc_delete_all_locations", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_delete_all_locations()");
+            return;
+        }
+        let ctx = &*context;
 
-    block_on(async move {
-        location::delete_all(ctx)
-            .await
-            .context("Failed to delete locations")
-            .log_err(ctx)
-            .ok()
-    });
+        block_on(async move {
+            location::delete_all(ctx)
+                .await
+                .context("Failed to delete locations")
+                .log_err(ctx)
+                .ok()
+        });
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_get_last_error(context: *mut dc_context_t) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_get_last_error()");
-        return "".strdup();
-    }
-    let ctx = &*context;
-    ctx.get_last_error().strdup()
+    ffi_boundary("dc_get_last_error", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_last_error()");
+            return "".strdup();
+        }
+        let ctx = &*context;
+        ctx.get_last_error().strdup()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_event_history(context: *mut dc_context_t) -> *mut libc::c_char {
+    ffi_boundary("dc_get_event_history", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_get_event_history()");
+            return "".strdup();
+        }
+        let ctx = &*context;
+        match ctx.get_event_history_json() {
+            Ok(json) => json.strdup(),
+            Err(err) => {
+                warn!(ctx, "failed to get event history: {err:#}");
+                "".strdup()
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_set_span_recording(enabled: libc::c_int) -> libc::c_int {
+    ffi_boundary("dc_set_span_recording", ptr::null(), || {
+        if enabled == 0 {
+            profiling::stop_span_recording();
+            return 1;
+        }
+        match profiling::start_span_recording() {
+            Ok(()) => 1,
+            Err(err) => {
+                eprintln!("dc_set_span_recording(): {err:#}");
+                0
+            }
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_recorded_spans() -> *mut libc::c_char {
+    ffi_boundary(
+        "dc_get_recorded_spans",
+        ptr::null(),
+        || match profiling::get_chrome_trace() {
+            Ok(json) => json.strdup(),
+            Err(err) => {
+                eprintln!("dc_get_recorded_spans(): {err:#}");
+                "".strdup()
+            }
+        },
+    )
 }
 
 // dc_array_t
@@ -2592,130 +3424,152 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_unref(a: *mut dc_array::dc_array_t) {
-    if a.is_null() {
-        eprintln!("ignoring careless call to dc_array_unref()");
-        return;
-    }
+    ffi_boundary("dc_array_unref", ptr::null(), || {
+        if a.is_null() {
+            eprintln!("ignoring careless call to dc_array_unref()");
+            return;
+        }
 
-    drop(Box::from_raw(a));
+        drop(Box::from_raw(a));
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_cnt(array: *const dc_array_t) -> libc::size_t {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_get_cnt()");
-        return 0;
-    }
+    ffi_boundary("dc_array_get_cnt", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_get_cnt()");
+            return 0;
+        }
 
-    (*array).len()
+        (*array).len()
+    })
 }
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_id(array: *const dc_array_t, index: libc::size_t) -> u32 {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_get_id()");
-        return 0;
-    }
+    ffi_boundary("dc_array_get_id", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_get_id()");
+            return 0;
+        }
 
-    (*array).get_id(index)
+        (*array).get_id(index)
+    })
 }
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_latitude(
     array: *const dc_array_t,
     index: libc::size_t,
 ) -> libc::c_double {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_get_latitude()");
-        return 0.0;
-    }
+    ffi_boundary("dc_array_get_latitude", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_get_latitude()");
+            return 0.0;
+        }
 
-    (*array).get_location(index).latitude
+        (*array).get_location(index).latitude
+    })
 }
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_longitude(
     array: *const dc_array_t,
     index: libc::size_t,
 ) -> libc::c_double {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_get_longitude()");
-        return 0.0;
-    }
+    ffi_boundary("dc_array_get_longitude", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_get_longitude()");
+            return 0.0;
+        }
 
-    (*array).get_location(index).longitude
+        (*array).get_location(index).longitude
+    })
 }
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_accuracy(
     array: *const dc_array_t,
     index: libc::size_t,
 ) -> libc::c_double {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_get_accuracy()");
-        return 0.0;
-    }
+    ffi_boundary("dc_array_get_accuracy", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_get_accuracy()");
+            return 0.0;
+        }
 
-    (*array).get_location(index).accuracy
+        (*array).get_location(index).accuracy
+    })
 }
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_timestamp(
     array: *const dc_array_t,
     index: libc::size_t,
 ) -> i64 {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_get_timestamp()");
-        return 0;
-    }
+    ffi_boundary("dc_array_get_timestamp", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_get_timestamp()");
+            return 0;
+        }
 
-    (*array).get_timestamp(index).unwrap_or_default()
+        (*array).get_timestamp(index).unwrap_or_default()
+    })
 }
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_chat_id(
     array: *const dc_array_t,
     index: libc::size_t,
 ) -> libc::c_uint {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_get_chat_id()");
-        return 0;
-    }
-    (*array).get_location(index).chat_id.to_u32()
+    ffi_boundary("dc_array_get_chat_id", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_get_chat_id()");
+            return 0;
+        }
+        (*array).get_location(index).chat_id.to_u32()
+    })
 }
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_contact_id(
     array: *const dc_array_t,
     index: libc::size_t,
 ) -> libc::c_uint {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_get_contact_id()");
-        return 0;
-    }
+    ffi_boundary("dc_array_get_contact_id", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_get_contact_id()");
+            return 0;
+        }
 
-    (*array).get_location(index).contact_id.to_u32()
+        (*array).get_location(index).contact_id.to_u32()
+    })
 }
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_msg_id(
     array: *const dc_array_t,
     index: libc::size_t,
 ) -> libc::c_uint {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_get_msg_id()");
-        return 0;
-    }
+    ffi_boundary("dc_array_get_msg_id", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_get_msg_id()");
+            return 0;
+        }
 
-    (*array).get_location(index).msg_id
+        (*array).get_location(index).msg_id
+    })
 }
 #[no_mangle]
 pub unsafe extern "C" fn dc_array_get_marker(
     array: *const dc_array_t,
     index: libc::size_t,
 ) -> *mut libc::c_char {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_get_marker()");
-        return std::ptr::null_mut(); // NULL explicitly defined as "no markers"
-    }
+    ffi_boundary("dc_array_get_marker", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_get_marker()");
+            return std::ptr::null_mut(); // NULL explicitly defined as "no markers"
+        }
 
-    if let Some(s) = (*array).get_marker(index) {
-        s.strdup()
-    } else {
-        std::ptr::null_mut()
-    }
+        if let Some(s) = (*array).get_marker(index) {
+            s.strdup()
+        } else {
+            std::ptr::null_mut()
+        }
+    })
 }
 
 #[no_mangle]
@@ -2724,19 +3578,21 @@ fn spawn_imex(ctx: Context, what: imex::ImexMode, param1: String, passphrase: Op
     needle: libc::c_uint,
     ret_index: *mut libc::size_t,
 ) -> libc::c_int {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_search_id()");
-        return 0;
-    }
+    ffi_boundary("dc_array_search_id", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_search_id()");
+            return 0;
+        }
 
-    if let Some(i) = (*array).search_id(needle) {
-        if !ret_index.is_null() {
-            *ret_index = i
+        if let Some(i) = (*array).search_id(needle) {
+            if !ret_index.is_null() {
+                *ret_index = i
+            }
+            1
+        } else {
+            0
         }
-        1
-    } else {
-        0
-    }
+    })
 }
 
 // Return the independent-state of the location at the given index.
@@ -2748,12 +3604,14 @@ pub unsafe fn dc_array_is_independent(
     array: *const dc_array_t,
     index: libc::size_t,
 ) -> libc::c_int {
-    if array.is_null() {
-        eprintln!("ignoring careless call to dc_array_is_independent()");
-        return 0;
-    }
+    ffi_boundary("dc_array_is_independent", ptr::null(), || {
+        if array.is_null() {
+            eprintln!("ignoring careless call to dc_array_is_independent()");
+            return 0;
+        }
 
-    (*array).get_location(index).independent as libc::c_int
+        (*array).get_location(index).independent as libc::c_int
+    })
 }
 
 // dc_chatlist_t
@@ -2774,21 +3632,25 @@ pub struct ChatlistWrapper {
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chatlist_unref(chatlist: *mut dc_chatlist_t) {
-    if chatlist.is_null() {
-        eprintln!("ignoring careless call to dc_chatlist_unref()");
-        return;
-    }
-    drop(Box::from_raw(chatlist));
+    ffi_boundary("dc_chatlist_unref", ptr::null(), || {
+        if chatlist.is_null() {
+            eprintln!("ignoring careless call to dc_chatlist_unref()");
+            return;
+        }
+        drop(Box::from_raw(chatlist));
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chatlist_get_cnt(chatlist: *mut dc_chatlist_t) -> libc::size_t {
-    if chatlist.is_null() {
-        eprintln!("ignoring careless call to dc_chatlist_get_cnt()");
-        return 0;
-    }
-    let ffi_list = &*chatlist;
-    ffi_list.list.len() as libc::size_t
+    ffi_boundary("dc_chatlist_get_cnt", ptr::null(), || {
+        if chatlist.is_null() {
+            eprintln!("ignoring careless call to dc_chatlist_get_cnt()");
+            return 0;
+        }
+        let ffi_list = &*chatlist;
+        ffi_list.list.len() as libc::size_t
+    })
 }
 
 #[no_mangle]
@@ -2796,19 +3658,21 @@ pub struct ChatlistWrapper {
     chatlist: *mut dc_chatlist_t,
     index: libc::size_t,
 ) -> u32 {
-    if chatlist.is_null() {
-        eprintln!("ignoring careless call to dc_chatlist_get_chat_id()");
-        return 0;
-    }
-    let ffi_list = &*chatlist;
-    let ctx = &*ffi_list.context;
-    match ffi_list.list.get_chat_id(index) {
-        Ok(chat_id) => chat_id.to_u32(),
-        Err(err) => {
-            warn!(ctx, "get_chat_id failed: {err:#}");
-            0
+    ffi_boundary("dc_chatlist_get_chat_id", ptr::null(), || {
+        if chatlist.is_null() {
+            eprintln!("ignoring careless call to dc_chatlist_get_chat_id()");
+            return 0;
         }
-    }
+        let ffi_list = &*chatlist;
+        let ctx = &*ffi_list.context;
+        match ffi_list.list.get_chat_id(index) {
+            Ok(chat_id) => chat_id.to_u32(),
+            Err(err) => {
+                warn!(ctx, "get_chat_id failed: {err:#}");
+                0
+            }
+        }
+    })
 }
 
 #[no_mangle]
@@ -2816,19 +3680,21 @@ pub struct ChatlistWrapper {
     chatlist: *mut dc_chatlist_t,
     index: libc::size_t,
 ) -> u32 {
-    if chatlist.is_null() {
-        eprintln!("ignoring careless call to dc_chatlist_get_msg_id()");
-        return 0;
-    }
-    let ffi_list = &*chatlist;
-    let ctx = &*ffi_list.context;
-    match ffi_list.list.get_msg_id(index) {
-        Ok(msg_id) => msg_id.map_or(0, |msg_id| msg_id.to_u32()),
-        Err(err) => {
-            warn!(ctx, "get_msg_id failed: {err:#}");
-            0
+    ffi_boundary("dc_chatlist_get_msg_id", ptr::null(), || {
+        if chatlist.is_null() {
+            eprintln!("ignoring careless call to dc_chatlist_get_msg_id()");
+            return 0;
         }
-    }
+        let ffi_list = &*chatlist;
+        let ctx = &*ffi_list.context;
+        match ffi_list.list.get_msg_id(index) {
+            Ok(msg_id) => msg_id.map_or(0, |msg_id| msg_id.to_u32()),
+            Err(err) => {
+                warn!(ctx, "get_msg_id failed: {err:#}");
+                0
+            }
+        }
+    })
 }
 
 #[no_mangle]
@@ -2837,28 +3703,30 @@ pub struct ChatlistWrapper {
     index: libc::size_t,
     chat: *mut dc_chat_t,
 ) -> *mut dc_lot_t {
-    if chatlist.is_null() {
-        eprintln!("ignoring careless call to dc_chatlist_get_summary()");
-        return ptr::null_mut();
-    }
-    let maybe_chat = if chat.is_null() {
-        None
-    } else {
-        let ffi_chat = &*chat;
-        Some(&ffi_chat.chat)
-    };
-    let ffi_list = &*chatlist;
-    let ctx = &*ffi_list.context;
+    ffi_boundary("dc_chatlist_get_summary", ptr::null(), || {
+        if chatlist.is_null() {
+            eprintln!("ignoring careless call to dc_chatlist_get_summary()");
+            return ptr::null_mut();
+        }
+        let maybe_chat = if chat.is_null() {
+            None
+        } else {
+            let ffi_chat = &*chat;
+            Some(&ffi_chat.chat)
+        };
+        let ffi_list = &*chatlist;
+        let ctx = &*ffi_list.context;
 
-    block_on(async move {
-        let summary = ffi_list
-            .list
-            .get_summary(ctx, index, maybe_chat)
-            .await
-            .context("get_summary failed")
-            .log_err(ctx)
-            .unwrap_or_default();
-        Box::into_raw(Box::new(summary.into()))
+        block_on(async move {
+            let summary = ffi_list
+                .list
+                .get_summary(ctx, index, maybe_chat)
+                .await
+                .context("get_summary failed")
+                .log_err(ctx)
+                .unwrap_or_default();
+            Box::into_raw(Box::new(summary.into()))
+        })
     })
 }
 
@@ -2868,38 +3736,134 @@ pub struct ChatlistWrapper {
     chat_id: u32,
     msg_id: u32,
 ) -> *mut dc_lot_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_chatlist_get_summary2()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
-    let msg_id = if msg_id == 0 {
-        None
-    } else {
-        Some(MsgId::new(msg_id))
-    };
-    let summary = block_on(Chatlist::get_summary2(
-        ctx,
-        ChatId::new(chat_id),
-        msg_id,
-        None,
-    ))
-    .context("get_summary2 failed")
-    .log_err(ctx)
-    .unwrap_or_default();
-    Box::into_raw(Box::new(summary.into()))
+    ffi_boundary("dc_chatlist_get_summary2", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_chatlist_get_summary2()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
+        let msg_id = if msg_id == 0 {
+            None
+        } else {
+            Some(MsgId::new(msg_id))
+        };
+        let summary = block_on(Chatlist::get_summary2(
+            ctx,
+            ChatId::new(chat_id),
+            msg_id,
+            None,
+        ))
+        .context("get_summary2 failed")
+        .log_err(ctx)
+        .unwrap_or_default();
+        Box::into_raw(Box::new(summary.into()))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_chatlist_get_ids(
+    chatlist: *mut dc_chatlist_t,
+    start: libc::size_t,
+    count: libc::size_t,
+    ret_chat_ids: *mut u32,
+    ret_msg_ids: *mut u32,
+) -> libc::size_t {
+    ffi_boundary("dc_chatlist_get_ids", ptr::null(), || {
+        if chatlist.is_null() {
+            eprintln!("ignoring careless call to dc_chatlist_get_ids()");
+            return 0;
+        }
+        let ffi_list = &*chatlist;
+        let end = start.saturating_add(count).min(ffi_list.list.len());
+        for (i, index) in (start..end).enumerate() {
+            if !ret_chat_ids.is_null() {
+                *ret_chat_ids.add(i) = ffi_list
+                    .list
+                    .get_chat_id(index)
+                    .map_or(0, |chat_id| chat_id.to_u32());
+            }
+            if !ret_msg_ids.is_null() {
+                *ret_msg_ids.add(i) = ffi_list
+                    .list
+                    .get_msg_id(index)
+                    .ok()
+                    .flatten()
+                    .map_or(0, |msg_id| msg_id.to_u32());
+            }
+        }
+        end.saturating_sub(start)
+    })
+}
+
+/// Converts a summary into the JSON object returned by the `*_summaries_json()` functions.
+fn summary_to_json(summary: summary::Summary) -> serde_json::Value {
+    let lot: lot::Lot = summary.into();
+    serde_json::json!({
+        "text1": lot.get_text1(),
+        "text1_meaning": lot.get_text1_meaning() as u8,
+        "text2": lot.get_text2(),
+        "timestamp": lot.get_timestamp(),
+        "state": lot.get_state() as u32,
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_chatlist_get_summaries_json(
+    chatlist: *mut dc_chatlist_t,
+    start: libc::size_t,
+    count: libc::size_t,
+) -> *mut libc::c_char {
+    ffi_boundary("dc_chatlist_get_summaries_json", ptr::null(), || {
+        if chatlist.is_null() {
+            eprintln!("ignoring careless call to dc_chatlist_get_summaries_json()");
+            return "".strdup();
+        }
+        let ffi_list = &*chatlist;
+        let ctx = &*ffi_list.context;
+        let end = start.saturating_add(count).min(ffi_list.list.len());
+
+        block_on(async move {
+            let mut items = Vec::new();
+            for index in start..end {
+                let summary = ffi_list
+                    .list
+                    .get_summary(ctx, index, None)
+                    .await
+                    .context("get_summary failed")
+                    .log_err(ctx)
+                    .unwrap_or_default();
+                let mut item = summary_to_json(summary);
+                item["chat_id"] = ffi_list
+                    .list
+                    .get_chat_id(index)
+                    .map_or(0, |chat_id| chat_id.to_u32())
+                    .into();
+                item["msg_id"] = ffi_list
+                    .list
+                    .get_msg_id(index)
+                    .ok()
+                    .flatten()
+                    .map_or(0, |msg_id| msg_id.to_u32())
+                    .into();
+                items.push(item);
+            }
+            serde_json::to_string(&items).unwrap_or_default().strdup()
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chatlist_get_context(
     chatlist: *mut dc_chatlist_t,
 ) -> *const dc_context_t {
-    if chatlist.is_null() {
-        eprintln!("ignoring careless call to dc_chatlist_get_context()");
-        return ptr::null_mut();
-    }
-    let ffi_list = &*chatlist;
-    ffi_list.context
+    ffi_boundary("dc_chatlist_get_context", ptr::null(), || {
+        if chatlist.is_null() {
+            eprintln!("ignoring careless call to dc_chatlist_get_context()");
+            return ptr::null_mut();
+        }
+        let ffi_list = &*chatlist;
+        ffi_list.context
+    })
 }
 
 // dc_chat_t
@@ -2920,242 +3884,294 @@ pub struct ChatWrapper {
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_unref(chat: *mut dc_chat_t) {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_unref()");
-        return;
-    }
+    ffi_boundary("dc_chat_unref", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_unref()");
+            return;
+        }
 
-    drop(Box::from_raw(chat));
+        drop(Box::from_raw(chat));
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_get_id(chat: *mut dc_chat_t) -> u32 {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_get_id()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    ffi_chat.chat.get_id().to_u32()
+    ffi_boundary("dc_chat_get_id", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_get_id()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        ffi_chat.chat.get_id().to_u32()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_get_type(chat: *mut dc_chat_t) -> libc::c_int {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_get_type()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    ffi_chat.chat.get_type() as libc::c_int
+    ffi_boundary("dc_chat_get_type", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_get_type()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        ffi_chat.chat.get_type() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_get_name(chat: *mut dc_chat_t) -> *mut libc::c_char {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_get_name()");
-        return "".strdup();
-    }
-    let ffi_chat = &*chat;
-    ffi_chat.chat.get_name().strdup()
+    ffi_boundary("dc_chat_get_name", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_get_name()");
+            return "".strdup();
+        }
+        let ffi_chat = &*chat;
+        ffi_chat.chat.get_name().strdup()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_chat_get_name_buf(
+    chat: *mut dc_chat_t,
+    buf: *mut libc::c_char,
+    buf_len: libc::size_t,
+) -> libc::size_t {
+    ffi_boundary("dc_chat_get_name_buf", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_get_name_buf()");
+            return copy_to_buf("", buf, buf_len);
+        }
+        let ffi_chat = &*chat;
+        copy_to_buf(ffi_chat.chat.get_name(), buf, buf_len)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_get_mailinglist_addr(chat: *mut dc_chat_t) -> *mut libc::c_char {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_get_mailinglist_addr()");
-        return "".strdup();
-    }
-    let ffi_chat = &*chat;
-    ffi_chat
-        .chat
-        .get_mailinglist_addr()
-        .unwrap_or_default()
-        .strdup()
+    ffi_boundary("dc_chat_get_mailinglist_addr", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_get_mailinglist_addr()");
+            return "".strdup();
+        }
+        let ffi_chat = &*chat;
+        ffi_chat
+            .chat
+            .get_mailinglist_addr()
+            .unwrap_or_default()
+            .strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_get_profile_image(chat: *mut dc_chat_t) -> *mut libc::c_char {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_get_profile_image()");
-        return ptr::null_mut(); // NULL explicitly defined as "no image"
-    }
-    let ffi_chat = &*chat;
-    let ctx = &*ffi_chat.context;
+    ffi_boundary("dc_chat_get_profile_image", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_get_profile_image()");
+            return ptr::null_mut(); // NULL explicitly defined as "no image"
+        }
+        let ffi_chat = &*chat;
+        let ctx = &*ffi_chat.context;
 
-    block_on(async move {
-        match ffi_chat.chat.get_profile_image(ctx).await {
-            Ok(Some(p)) => p.to_string_lossy().strdup(),
-            Ok(None) => ptr::null_mut(),
-            Err(err) => {
-                error!(ctx, "failed to get profile image: {err:#}");
-                ptr::null_mut()
+        block_on(async move {
+            match ffi_chat.chat.get_profile_image(ctx).await {
+                Ok(Some(p)) => p.to_string_lossy().strdup(),
+                Ok(None) => ptr::null_mut(),
+                Err(err) => {
+                    error!(ctx, "failed to get profile image: {err:#}");
+                    ptr::null_mut()
+                }
             }
-        }
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_get_color(chat: *mut dc_chat_t) -> u32 {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_get_color()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    let ctx = &*ffi_chat.context;
+    ffi_boundary("dc_chat_get_color", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_get_color()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        let ctx = &*ffi_chat.context;
 
-    block_on(ffi_chat.chat.get_color(ctx)).unwrap_or_log_default(ctx, "Failed get_color")
+        block_on(ffi_chat.chat.get_color(ctx)).unwrap_or_log_default(ctx, "Failed get_color")
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_get_visibility(chat: *mut dc_chat_t) -> libc::c_int {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_get_visibility()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    match ffi_chat.chat.visibility {
-        ChatVisibility::Normal => 0,
-        ChatVisibility::Archived => 1,
-        ChatVisibility::Pinned => 2,
-    }
+    ffi_boundary("dc_chat_get_visibility", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_get_visibility()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        match ffi_chat.chat.visibility {
+            ChatVisibility::Normal => 0,
+            ChatVisibility::Archived => 1,
+            ChatVisibility::Pinned => 2,
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_is_contact_request(chat: *mut dc_chat_t) -> libc::c_int {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_is_contact_request()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    ffi_chat.chat.is_contact_request() as libc::c_int
+    ffi_boundary("dc_chat_is_contact_request", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_is_contact_request()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        ffi_chat.chat.is_contact_request() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_is_unpromoted(chat: *mut dc_chat_t) -> libc::c_int {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_is_unpromoted()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    ffi_chat.chat.is_unpromoted() as libc::c_int
+    ffi_boundary("dc_chat_is_unpromoted", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_is_unpromoted()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        ffi_chat.chat.is_unpromoted() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_is_self_talk(chat: *mut dc_chat_t) -> libc::c_int {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_is_self_talk()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    ffi_chat.chat.is_self_talk() as libc::c_int
+    ffi_boundary("dc_chat_is_self_talk", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_is_self_talk()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        ffi_chat.chat.is_self_talk() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_is_device_talk(chat: *mut dc_chat_t) -> libc::c_int {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_is_device_talk()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    ffi_chat.chat.is_device_talk() as libc::c_int
+    ffi_boundary("dc_chat_is_device_talk", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_is_device_talk()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        ffi_chat.chat.is_device_talk() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_can_send(chat: *mut dc_chat_t) -> libc::c_int {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_can_send()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    let ctx = &*ffi_chat.context;
-    block_on(ffi_chat.chat.can_send(ctx))
-        .context("can_send failed")
-        .log_err(ctx)
-        .unwrap_or_default() as libc::c_int
+    ffi_boundary("dc_chat_can_send", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_can_send()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        let ctx = &*ffi_chat.context;
+        block_on(ffi_chat.chat.can_send(ctx))
+            .context("can_send failed")
+            .log_err(ctx)
+            .unwrap_or_default() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_is_protected(chat: *mut dc_chat_t) -> libc::c_int {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_is_protected()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    ffi_chat.chat.is_protected() as libc::c_int
+    ffi_boundary("dc_chat_is_protected", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_is_protected()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        ffi_chat.chat.is_protected() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_is_sending_locations(chat: *mut dc_chat_t) -> libc::c_int {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_is_sending_locations()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    ffi_chat.chat.is_sending_locations() as libc::c_int
+    ffi_boundary("dc_chat_is_sending_locations", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_is_sending_locations()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        ffi_chat.chat.is_sending_locations() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_is_muted(chat: *mut dc_chat_t) -> libc::c_int {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_is_muted()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    ffi_chat.chat.is_muted() as libc::c_int
+    ffi_boundary("dc_chat_is_muted", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_is_muted()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        ffi_chat.chat.is_muted() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_chat_get_remaining_mute_duration(chat: *mut dc_chat_t) -> i64 {
-    if chat.is_null() {
-        eprintln!("ignoring careless call to dc_chat_get_remaining_mute_duration()");
-        return 0;
-    }
-    let ffi_chat = &*chat;
-    if !ffi_chat.chat.is_muted() {
-        return 0;
-    }
-    // If the chat was muted to before the epoch, it is not muted.
-    match ffi_chat.chat.mute_duration {
-        MuteDuration::NotMuted => 0,
-        MuteDuration::Forever => -1,
-        MuteDuration::Until(when) => when
-            .duration_since(SystemTime::now())
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or(0),
-    }
+    ffi_boundary("dc_chat_get_remaining_mute_duration", ptr::null(), || {
+        if chat.is_null() {
+            eprintln!("ignoring careless call to dc_chat_get_remaining_mute_duration()");
+            return 0;
+        }
+        let ffi_chat = &*chat;
+        if !ffi_chat.chat.is_muted() {
+            return 0;
+        }
+        // If the chat was muted to before the epoch, it is not muted.
+        match ffi_chat.chat.mute_duration {
+            MuteDuration::NotMuted => 0,
+            MuteDuration::Forever => -1,
+            MuteDuration::Until(when) => when
+                .duration_since(SystemTime::now())
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+        }
+    })
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn dc_chat_get_info_json(
-    context: *mut dc_context_t,
-    chat_id: u32,
-) -> *mut libc::c_char {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_chat_get_info_json()");
-        return "".strdup();
-    }
-    let ctx = &*context;
-
-    block_on(async move {
-        let chat = match chat::Chat::load_from_db(ctx, ChatId::new(chat_id)).await {
-            Ok(chat) => chat,
-            Err(err) => {
-                error!(ctx, "dc_get_chat_info_json() failed to load chat: {err:#}");
-                return "".strdup();
-            }
-        };
-        let info = match chat.get_info(ctx).await {
-            Ok(info) => info,
-            Err(err) => {
-                error!(
-                    ctx,
-                    "dc_get_chat_info_json() failed to get chat info: {err:#}"
-                );
-                return "".strdup();
-            }
-        };
-        serde_json::to_string(&info)
-            .unwrap_or_log_default(ctx, "dc_get_chat_info_json() failed to serialise to json")
-            .strdup()
+pub unsafe extern "C" fn dc_chat_get_info_json(
+    context: *mut dc_context_t,
+    chat_id: u32,
+) -> *mut libc::c_char {
+    ffi_boundary("dc_chat_get_info_json", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_chat_get_info_json()");
+            return "".strdup();
+        }
+        let ctx = &*context;
+
+        block_on(async move {
+            let chat = match chat::Chat::load_from_db(ctx, ChatId::new(chat_id)).await {
+                Ok(chat) => chat,
+                Err(err) => {
+                    error!(ctx, "dc_get_chat_info_json() failed to load chat: {err:#}");
+                    return "".strdup();
+                }
+            };
+            let info = match chat.get_info(ctx).await {
+                Ok(info) => info,
+                Err(err) => {
+                    error!(
+                        ctx,
+                        "dc_get_chat_info_json() failed to get chat info: {err:#}"
+                    );
+                    return "".strdup();
+                }
+            };
+            serde_json::to_string(&info)
+                .unwrap_or_log_default(ctx, "dc_get_chat_info_json() failed to serialise to json")
+                .strdup()
+        })
     })
 }
 
@@ -3180,166 +4196,216 @@ pub struct MessageWrapper {
     context: *mut dc_context_t,
     viewtype: libc::c_int,
 ) -> *mut dc_msg_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_msg_new()");
-        return ptr::null_mut();
-    }
-    let context = &*context;
-    let viewtype = from_prim(viewtype).expect(&format!("invalid viewtype = {viewtype}"));
-    let msg = MessageWrapper {
-        context,
-        message: message::Message::new(viewtype),
-    };
-    Box::into_raw(Box::new(msg))
+    ffi_boundary("dc_msg_new", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_msg_new()");
+            return ptr::null_mut();
+        }
+        let context = &*context;
+        let viewtype = from_prim(viewtype).expect(&format!("invalid viewtype = {viewtype}"));
+        let msg = MessageWrapper {
+            context,
+            message: message::Message::new(viewtype),
+        };
+        Box::into_raw(Box::new(msg))
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_unref(msg: *mut dc_msg_t) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_unref()");
-        return;
-    }
+    ffi_boundary("dc_msg_unref", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_unref()");
+            return;
+        }
 
-    drop(Box::from_raw(msg));
+        drop(Box::from_raw(msg));
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_id(msg: *mut dc_msg_t) -> u32 {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_id()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_id().to_u32()
+    ffi_boundary("dc_msg_get_id", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_id()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_id().to_u32()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_from_id(msg: *mut dc_msg_t) -> u32 {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_from_id()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_from_id().to_u32()
+    ffi_boundary("dc_msg_get_from_id", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_from_id()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_from_id().to_u32()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_chat_id(msg: *mut dc_msg_t) -> u32 {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_chat_id()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_chat_id().to_u32()
+    ffi_boundary("dc_msg_get_chat_id", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_chat_id()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_chat_id().to_u32()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_viewtype(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_viewtype()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg
-        .message
-        .get_viewtype()
-        .to_i64()
-        .expect("impossible: Viewtype -> i64 conversion failed") as libc::c_int
+    ffi_boundary("dc_msg_get_viewtype", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_viewtype()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg
+            .message
+            .get_viewtype()
+            .to_i64()
+            .expect("impossible: Viewtype -> i64 conversion failed") as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_state(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_state()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_state() as libc::c_int
+    ffi_boundary("dc_msg_get_state", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_state()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_state() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_download_state(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_download_state()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.download_state() as libc::c_int
+    ffi_boundary("dc_msg_get_download_state", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_download_state()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.download_state() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_timestamp(msg: *mut dc_msg_t) -> i64 {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_received_timestamp()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_timestamp()
+    ffi_boundary("dc_msg_get_timestamp", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_received_timestamp()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_timestamp()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_received_timestamp(msg: *mut dc_msg_t) -> i64 {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_received_timestamp()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_received_timestamp()
+    ffi_boundary("dc_msg_get_received_timestamp", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_received_timestamp()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_received_timestamp()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_sort_timestamp(msg: *mut dc_msg_t) -> i64 {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_sort_timestamp()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_sort_timestamp()
+    ffi_boundary("dc_msg_get_sort_timestamp", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_sort_timestamp()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_sort_timestamp()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_text(msg: *mut dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_text()");
-        return "".strdup();
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_text().unwrap_or_default().strdup()
+    ffi_boundary("dc_msg_get_text", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_text()");
+            return "".strdup();
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_text().unwrap_or_default().strdup()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_msg_get_text_buf(
+    msg: *mut dc_msg_t,
+    buf: *mut libc::c_char,
+    buf_len: libc::size_t,
+) -> libc::size_t {
+    ffi_boundary("dc_msg_get_text_buf", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_text_buf()");
+            return copy_to_buf("", buf, buf_len);
+        }
+        let ffi_msg = &*msg;
+        copy_to_buf(
+            &ffi_msg.message.get_text().unwrap_or_default(),
+            buf,
+            buf_len,
+        )
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_subject(msg: *mut dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_subject()");
-        return "".strdup();
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_subject().strdup()
+    ffi_boundary("dc_msg_get_subject", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_subject()");
+            return "".strdup();
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_subject().strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_file(msg: *mut dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_file()");
-        return "".strdup();
-    }
-    let ffi_msg = &*msg;
-    let ctx = &*ffi_msg.context;
-    ffi_msg
-        .message
-        .get_file(ctx)
-        .map(|p| p.to_string_lossy().strdup())
-        .unwrap_or_else(|| "".strdup())
+    ffi_boundary("dc_msg_get_file", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_file()");
+            return "".strdup();
+        }
+        let ffi_msg = &*msg;
+        let ctx = &*ffi_msg.context;
+        ffi_msg
+            .message
+            .get_file(ctx)
+            .map(|p| p.to_string_lossy().strdup())
+            .unwrap_or_else(|| "".strdup())
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_filename(msg: *mut dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_filename()");
-        return "".strdup();
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_filename().unwrap_or_default().strdup()
+    ffi_boundary("dc_msg_get_filename", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_filename()");
+            return "".strdup();
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_filename().unwrap_or_default().strdup()
+    })
 }
 
 #[no_mangle]
@@ -3348,151 +4414,173 @@ pub struct MessageWrapper {
     filename: *const libc::c_char,
     ret_bytes: *mut libc::size_t,
 ) -> *mut libc::c_char {
-    if msg.is_null() || filename.is_null() || ret_bytes.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_webxdc_blob()");
-        return ptr::null_mut();
-    }
-    let ffi_msg = &*msg;
-    let ctx = &*ffi_msg.context;
-    let blob = block_on(async move {
-        ffi_msg
-            .message
-            .get_webxdc_blob(ctx, &to_string_lossy(filename))
-            .await
-    });
-    match blob {
-        Ok(blob) => {
-            *ret_bytes = blob.len();
-            let ptr = libc::malloc(*ret_bytes);
-            libc::memcpy(ptr, blob.as_ptr() as *mut libc::c_void, *ret_bytes);
-            ptr as *mut libc::c_char
-        }
-        Err(err) => {
-            eprintln!("failed read blob from archive: {err}");
-            ptr::null_mut()
+    ffi_boundary("dc_msg_get_webxdc_blob", ptr::null(), || {
+        if msg.is_null() || filename.is_null() || ret_bytes.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_webxdc_blob()");
+            return ptr::null_mut();
         }
-    }
+        let ffi_msg = &*msg;
+        let ctx = &*ffi_msg.context;
+        let blob = block_on(async move {
+            ffi_msg
+                .message
+                .get_webxdc_blob(ctx, &to_string_lossy(filename))
+                .await
+        });
+        match blob {
+            Ok(blob) => {
+                *ret_bytes = blob.len();
+                let ptr = libc::malloc(*ret_bytes);
+                libc::memcpy(ptr, blob.as_ptr() as *mut libc::c_void, *ret_bytes);
+                ptr as *mut libc::c_char
+            }
+            Err(err) => {
+                eprintln!("failed read blob from archive: {err}");
+                ptr::null_mut()
+            }
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_webxdc_info(msg: *mut dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_webxdc_info()");
-        return "".strdup();
-    }
-    let ffi_msg = &*msg;
-    let ctx = &*ffi_msg.context;
+    ffi_boundary("dc_msg_get_webxdc_info", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_webxdc_info()");
+            return "".strdup();
+        }
+        let ffi_msg = &*msg;
+        let ctx = &*ffi_msg.context;
 
-    block_on(async move {
-        let info = match ffi_msg.message.get_webxdc_info(ctx).await {
-            Ok(info) => info,
-            Err(err) => {
-                error!(ctx, "dc_msg_get_webxdc_info() failed to get info: {err:#}");
-                return "".strdup();
-            }
-        };
-        serde_json::to_string(&info)
-            .unwrap_or_log_default(ctx, "dc_msg_get_webxdc_info() failed to serialise to json")
-            .strdup()
+        block_on(async move {
+            let info = match ffi_msg.message.get_webxdc_info(ctx).await {
+                Ok(info) => info,
+                Err(err) => {
+                    error!(ctx, "dc_msg_get_webxdc_info() failed to get info: {err:#}");
+                    return "".strdup();
+                }
+            };
+            serde_json::to_string(&info)
+                .unwrap_or_log_default(ctx, "dc_msg_get_webxdc_info() failed to serialise to json")
+                .strdup()
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_filemime(msg: *mut dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_filemime()");
-        return "".strdup();
-    }
-    let ffi_msg = &*msg;
-    if let Some(x) = ffi_msg.message.get_filemime() {
-        x.strdup()
-    } else {
-        "".strdup()
-    }
+    ffi_boundary("dc_msg_get_filemime", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_filemime()");
+            return "".strdup();
+        }
+        let ffi_msg = &*msg;
+        if let Some(x) = ffi_msg.message.get_filemime() {
+            x.strdup()
+        } else {
+            "".strdup()
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_filebytes(msg: *mut dc_msg_t) -> u64 {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_filebytes()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    let ctx = &*ffi_msg.context;
+    ffi_boundary("dc_msg_get_filebytes", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_filebytes()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        let ctx = &*ffi_msg.context;
 
-    block_on(ffi_msg.message.get_filebytes(ctx))
-        .unwrap_or_log_default(ctx, "Cannot get file size")
-        .unwrap_or_default()
+        block_on(ffi_msg.message.get_filebytes(ctx))
+            .unwrap_or_log_default(ctx, "Cannot get file size")
+            .unwrap_or_default()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_width(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_width()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_width()
+    ffi_boundary("dc_msg_get_width", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_width()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_width()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_height(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_height()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_height()
+    ffi_boundary("dc_msg_get_height", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_height()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_height()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_duration(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_duration()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_duration()
+    ffi_boundary("dc_msg_get_duration", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_duration()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_duration()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_showpadlock(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_showpadlock()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_showpadlock() as libc::c_int
+    ffi_boundary("dc_msg_get_showpadlock", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_showpadlock()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_showpadlock() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_is_bot(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_is_bot()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.is_bot() as libc::c_int
+    ffi_boundary("dc_msg_is_bot", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_is_bot()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.is_bot() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_ephemeral_timer(msg: *mut dc_msg_t) -> u32 {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_ephemeral_timer()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_ephemeral_timer().to_u32()
+    ffi_boundary("dc_msg_get_ephemeral_timer", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_ephemeral_timer()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_ephemeral_timer().to_u32()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_ephemeral_timestamp(msg: *mut dc_msg_t) -> i64 {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_ephemeral_timer()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_ephemeral_timestamp()
+    ffi_boundary("dc_msg_get_ephemeral_timestamp", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_ephemeral_timer()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_ephemeral_timestamp()
+    })
 }
 
 #[no_mangle]
@@ -3500,24 +4588,66 @@ pub struct MessageWrapper {
     msg: *mut dc_msg_t,
     chat: *mut dc_chat_t,
 ) -> *mut dc_lot_t {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_summary()");
-        return ptr::null_mut();
-    }
-    let maybe_chat = if chat.is_null() {
-        None
-    } else {
-        let ffi_chat = &*chat;
-        Some(&ffi_chat.chat)
-    };
-    let ffi_msg = &mut *msg;
-    let ctx = &*ffi_msg.context;
+    ffi_boundary("dc_msg_get_summary", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_summary()");
+            return ptr::null_mut();
+        }
+        let maybe_chat = if chat.is_null() {
+            None
+        } else {
+            let ffi_chat = &*chat;
+            Some(&ffi_chat.chat)
+        };
+        let ffi_msg = &mut *msg;
+        let ctx = &*ffi_msg.context;
 
-    let summary = block_on(ffi_msg.message.get_summary(ctx, maybe_chat))
-        .context("dc_msg_get_summary failed")
-        .log_err(ctx)
-        .unwrap_or_default();
-    Box::into_raw(Box::new(summary.into()))
+        let summary = block_on(ffi_msg.message.get_summary(ctx, maybe_chat))
+            .context("dc_msg_get_summary failed")
+            .log_err(ctx)
+            .unwrap_or_default();
+        Box::into_raw(Box::new(summary.into()))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_get_msg_summaries_json(
+    context: *mut dc_context_t,
+    msg_ids: *const u32,
+    msg_cnt: libc::c_int,
+) -> *mut libc::c_char {
+    ffi_boundary("dc_get_msg_summaries_json", context, || {
+        if context.is_null() || msg_ids.is_null() || msg_cnt <= 0 {
+            eprintln!("ignoring careless call to dc_get_msg_summaries_json()");
+            return "".strdup();
+        }
+        let ctx = &*context;
+        let msg_ids = std::slice::from_raw_parts(msg_ids, msg_cnt as usize);
+
+        block_on(async move {
+            let mut items = Vec::with_capacity(msg_ids.len());
+            for msg_id in msg_ids {
+                let msg_id = MsgId::new(*msg_id);
+                let summary = match message::Message::load_from_db(ctx, msg_id).await {
+                    Ok(msg) => msg.get_summary(ctx, None).await,
+                    Err(err) => Err(err),
+                };
+                let item = match summary
+                    .context("dc_get_msg_summaries_json() failed")
+                    .log_err(ctx)
+                {
+                    Ok(summary) => {
+                        let mut item = summary_to_json(summary);
+                        item["msg_id"] = msg_id.to_u32().into();
+                        item
+                    }
+                    Err(_) => serde_json::Value::Null,
+                };
+                items.push(item);
+            }
+            serde_json::to_string(&items).unwrap_or_default().strdup()
+        })
+    })
 }
 
 #[no_mangle]
@@ -3525,191 +4655,225 @@ pub struct MessageWrapper {
     msg: *mut dc_msg_t,
     approx_characters: libc::c_int,
 ) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_summarytext()");
-        return "".strdup();
-    }
-    let ffi_msg = &mut *msg;
-    let ctx = &*ffi_msg.context;
+    ffi_boundary("dc_msg_get_summarytext", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_summarytext()");
+            return "".strdup();
+        }
+        let ffi_msg = &mut *msg;
+        let ctx = &*ffi_msg.context;
 
-    let summary = block_on(ffi_msg.message.get_summary(ctx, None))
-        .context("dc_msg_get_summarytext failed")
-        .log_err(ctx)
-        .unwrap_or_default();
-    match usize::try_from(approx_characters) {
-        Ok(chars) => summary.truncated_text(chars).strdup(),
-        Err(_) => summary.text.strdup(),
-    }
+        let summary = block_on(ffi_msg.message.get_summary(ctx, None))
+            .context("dc_msg_get_summarytext failed")
+            .log_err(ctx)
+            .unwrap_or_default();
+        match usize::try_from(approx_characters) {
+            Ok(chars) => summary.truncated_text(chars).strdup(),
+            Err(_) => summary.text.strdup(),
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_override_sender_name(msg: *mut dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_override_sender_name()");
-        return "".strdup();
-    }
-    let ffi_msg = &mut *msg;
+    ffi_boundary("dc_msg_get_override_sender_name", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_override_sender_name()");
+            return "".strdup();
+        }
+        let ffi_msg = &mut *msg;
 
-    ffi_msg.message.get_override_sender_name().strdup()
+        ffi_msg.message.get_override_sender_name().strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_has_deviating_timestamp(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_has_deviating_timestamp()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.has_deviating_timestamp().into()
+    ffi_boundary("dc_msg_has_deviating_timestamp", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_has_deviating_timestamp()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.has_deviating_timestamp().into()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_has_location(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_has_location()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.has_location() as libc::c_int
+    ffi_boundary("dc_msg_has_location", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_has_location()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.has_location() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_is_sent(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_is_sent()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.is_sent().into()
+    ffi_boundary("dc_msg_is_sent", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_is_sent()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.is_sent().into()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_is_forwarded(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_is_forwarded()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.is_forwarded().into()
+    ffi_boundary("dc_msg_is_forwarded", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_is_forwarded()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.is_forwarded().into()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_is_info(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_is_info()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.is_info().into()
+    ffi_boundary("dc_msg_is_info", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_is_info()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.is_info().into()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_info_type(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_info_type()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_info_type() as libc::c_int
+    ffi_boundary("dc_msg_get_info_type", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_info_type()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_info_type() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_is_increation(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_is_increation()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.is_increation().into()
+    ffi_boundary("dc_msg_is_increation", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_is_increation()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.is_increation().into()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_is_setupmessage(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_is_setupmessage()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.is_setupmessage().into()
+    ffi_boundary("dc_msg_is_setupmessage", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_is_setupmessage()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.is_setupmessage().into()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_has_html(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_has_html()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.has_html().into()
+    ffi_boundary("dc_msg_has_html", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_has_html()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.has_html().into()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_videochat_url(msg: *mut dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_videochat_url()");
-        return "".strdup();
-    }
-    let ffi_msg = &*msg;
+    ffi_boundary("dc_msg_get_videochat_url", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_videochat_url()");
+            return "".strdup();
+        }
+        let ffi_msg = &*msg;
 
-    ffi_msg
-        .message
-        .get_videochat_url()
-        .unwrap_or_default()
-        .strdup()
+        ffi_msg
+            .message
+            .get_videochat_url()
+            .unwrap_or_default()
+            .strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_videochat_type(msg: *mut dc_msg_t) -> libc::c_int {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_videochat_type()");
-        return 0;
-    }
-    let ffi_msg = &*msg;
-    ffi_msg.message.get_videochat_type().unwrap_or_default() as i32
+    ffi_boundary("dc_msg_get_videochat_type", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_videochat_type()");
+            return 0;
+        }
+        let ffi_msg = &*msg;
+        ffi_msg.message.get_videochat_type().unwrap_or_default() as i32
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_setupcodebegin(msg: *mut dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_setupcodebegin()");
-        return "".strdup();
-    }
-    let ffi_msg = &*msg;
-    let ctx = &*ffi_msg.context;
+    ffi_boundary("dc_msg_get_setupcodebegin", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_setupcodebegin()");
+            return "".strdup();
+        }
+        let ffi_msg = &*msg;
+        let ctx = &*ffi_msg.context;
 
-    block_on(ffi_msg.message.get_setupcodebegin(ctx))
-        .unwrap_or_default()
-        .strdup()
+        block_on(ffi_msg.message.get_setupcodebegin(ctx))
+            .unwrap_or_default()
+            .strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_set_text(msg: *mut dc_msg_t, text: *const libc::c_char) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_set_text()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    ffi_msg.message.set_text(to_opt_string_lossy(text))
+    ffi_boundary("dc_msg_set_text", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_set_text()");
+            return;
+        }
+        let ffi_msg = &mut *msg;
+        ffi_msg.message.set_text(to_opt_string_lossy(text))
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_set_html(msg: *mut dc_msg_t, html: *const libc::c_char) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_set_html()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    ffi_msg.message.set_html(to_opt_string_lossy(html))
+    ffi_boundary("dc_msg_set_html", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_set_html()");
+            return;
+        }
+        let ffi_msg = &mut *msg;
+        ffi_msg.message.set_html(to_opt_string_lossy(html))
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_set_subject(msg: *mut dc_msg_t, subject: *const libc::c_char) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_subject()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    ffi_msg.message.set_subject(to_string_lossy(subject));
+    ffi_boundary("dc_msg_set_subject", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_subject()");
+            return;
+        }
+        let ffi_msg = &mut *msg;
+        ffi_msg.message.set_subject(to_string_lossy(subject));
+    })
 }
 
 #[no_mangle]
@@ -3717,14 +4881,16 @@ pub struct MessageWrapper {
     msg: *mut dc_msg_t,
     name: *const libc::c_char,
 ) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_set_override_sender_name()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    ffi_msg
-        .message
-        .set_override_sender_name(to_opt_string_lossy(name))
+    ffi_boundary("dc_msg_set_override_sender_name", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_set_override_sender_name()");
+            return;
+        }
+        let ffi_msg = &mut *msg;
+        ffi_msg
+            .message
+            .set_override_sender_name(to_opt_string_lossy(name))
+    })
 }
 
 #[no_mangle]
@@ -3733,15 +4899,17 @@ pub struct MessageWrapper {
     file: *const libc::c_char,
     filemime: *const libc::c_char,
 ) {
-    if msg.is_null() || file.is_null() {
-        eprintln!("ignoring careless call to dc_msg_set_file()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    ffi_msg.message.set_file(
-        to_string_lossy(file),
-        to_opt_string_lossy(filemime).as_deref(),
-    )
+    ffi_boundary("dc_msg_set_file", ptr::null(), || {
+        if msg.is_null() || file.is_null() {
+            eprintln!("ignoring careless call to dc_msg_set_file()");
+            return;
+        }
+        let ffi_msg = &mut *msg;
+        ffi_msg.message.set_file(
+            to_string_lossy(file),
+            to_opt_string_lossy(filemime).as_deref(),
+        )
+    })
 }
 
 #[no_mangle]
@@ -3750,22 +4918,26 @@ pub struct MessageWrapper {
     width: libc::c_int,
     height: libc::c_int,
 ) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_set_dimension()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    ffi_msg.message.set_dimension(width, height)
+    ffi_boundary("dc_msg_set_dimension", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_set_dimension()");
+            return;
+        }
+        let ffi_msg = &mut *msg;
+        ffi_msg.message.set_dimension(width, height)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_set_duration(msg: *mut dc_msg_t, duration: libc::c_int) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_set_duration()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    ffi_msg.message.set_duration(duration)
+    ffi_boundary("dc_msg_set_duration", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_set_duration()");
+            return;
+        }
+        let ffi_msg = &mut *msg;
+        ffi_msg.message.set_duration(duration)
+    })
 }
 
 #[no_mangle]
@@ -3774,12 +4946,14 @@ pub struct MessageWrapper {
     latitude: libc::c_double,
     longitude: libc::c_double,
 ) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_set_location()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    ffi_msg.message.set_location(latitude, longitude)
+    ffi_boundary("dc_msg_set_location", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_set_location()");
+            return;
+        }
+        let ffi_msg = &mut *msg;
+        ffi_msg.message.set_location(latitude, longitude)
+    })
 }
 
 #[no_mangle]
@@ -3789,134 +4963,148 @@ pub struct MessageWrapper {
     height: libc::c_int,
     duration: libc::c_int,
 ) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_latefiling_mediasize()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    let ctx = &*ffi_msg.context;
+    ffi_boundary("dc_msg_latefiling_mediasize", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_latefiling_mediasize()");
+            return;
+        }
+        let ffi_msg = &mut *msg;
+        let ctx = &*ffi_msg.context;
 
-    block_on({
-        ffi_msg
-            .message
-            .latefiling_mediasize(ctx, width, height, duration)
+        block_on({
+            ffi_msg
+                .message
+                .latefiling_mediasize(ctx, width, height, duration)
+        })
+        .context("Cannot set media size")
+        .log_err(ctx)
+        .ok();
     })
-    .context("Cannot set media size")
-    .log_err(ctx)
-    .ok();
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_error(msg: *mut dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_error()");
-        return ptr::null_mut();
-    }
-    let ffi_msg = &*msg;
-    match ffi_msg.message.error() {
-        Some(error) => error.strdup(),
-        None => ptr::null_mut(),
-    }
+    ffi_boundary("dc_msg_get_error", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_error()");
+            return ptr::null_mut();
+        }
+        let ffi_msg = &*msg;
+        match ffi_msg.message.error() {
+            Some(error) => error.strdup(),
+            None => ptr::null_mut(),
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_set_quote(msg: *mut dc_msg_t, quote: *const dc_msg_t) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_set_quote()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    let quote_msg = if quote.is_null() {
-        None
-    } else {
-        let ffi_quote = &*quote;
-        if ffi_msg.context != ffi_quote.context {
-            eprintln!("ignoring attempt to quote message from a different context");
+    ffi_boundary("dc_msg_set_quote", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_set_quote()");
             return;
         }
-        Some(&ffi_quote.message)
-    };
+        let ffi_msg = &mut *msg;
+        let quote_msg = if quote.is_null() {
+            None
+        } else {
+            let ffi_quote = &*quote;
+            if ffi_msg.context != ffi_quote.context {
+                eprintln!("ignoring attempt to quote message from a different context");
+                return;
+            }
+            Some(&ffi_quote.message)
+        };
 
-    block_on(async move {
-        ffi_msg
-            .message
-            .set_quote(&*ffi_msg.context, quote_msg)
-            .await
-            .context("failed to set quote")
-            .log_err(&*ffi_msg.context)
-            .ok();
-    });
+        block_on(async move {
+            ffi_msg
+                .message
+                .set_quote(&*ffi_msg.context, quote_msg)
+                .await
+                .context("failed to set quote")
+                .log_err(&*ffi_msg.context)
+                .ok();
+        });
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_quoted_text(msg: *const dc_msg_t) -> *mut libc::c_char {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_quoted_text()");
-        return ptr::null_mut();
-    }
-    let ffi_msg: &MessageWrapper = &*msg;
-    ffi_msg
-        .message
-        .quoted_text()
-        .map_or_else(ptr::null_mut, |s| s.strdup())
+    ffi_boundary("dc_msg_get_quoted_text", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_quoted_text()");
+            return ptr::null_mut();
+        }
+        let ffi_msg: &MessageWrapper = &*msg;
+        ffi_msg
+            .message
+            .quoted_text()
+            .map_or_else(ptr::null_mut, |s| s.strdup())
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_quoted_msg(msg: *const dc_msg_t) -> *mut dc_msg_t {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_get_quoted_msg()");
-        return ptr::null_mut();
-    }
-    let ffi_msg: &MessageWrapper = &*msg;
-    let context = &*ffi_msg.context;
-    let res = block_on(async move {
-        ffi_msg
-            .message
-            .quoted_message(context)
-            .await
-            .context("failed to get quoted message")
-            .log_err(context)
-            .unwrap_or(None)
-    });
+    ffi_boundary("dc_msg_get_quoted_msg", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_get_quoted_msg()");
+            return ptr::null_mut();
+        }
+        let ffi_msg: &MessageWrapper = &*msg;
+        let context = &*ffi_msg.context;
+        let res = block_on(async move {
+            ffi_msg
+                .message
+                .quoted_message(context)
+                .await
+                .context("failed to get quoted message")
+                .log_err(context)
+                .unwrap_or(None)
+        });
 
-    match res {
-        Some(message) => Box::into_raw(Box::new(MessageWrapper { context, message })),
-        None => ptr::null_mut(),
-    }
+        match res {
+            Some(message) => Box::into_raw(Box::new(MessageWrapper { context, message })),
+            None => ptr::null_mut(),
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_get_parent(msg: *const dc_msg_t) -> *mut dc_msg_t {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_get_parent()");
-        return ptr::null_mut();
-    }
-    let ffi_msg: &MessageWrapper = &*msg;
-    let context = &*ffi_msg.context;
-    let res = block_on(async move {
-        ffi_msg
-            .message
-            .parent(context)
-            .await
-            .context("failed to get parent message")
-            .log_err(context)
-            .unwrap_or(None)
-    });
+    ffi_boundary("dc_msg_get_parent", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_get_parent()");
+            return ptr::null_mut();
+        }
+        let ffi_msg: &MessageWrapper = &*msg;
+        let context = &*ffi_msg.context;
+        let res = block_on(async move {
+            ffi_msg
+                .message
+                .parent(context)
+                .await
+                .context("failed to get parent message")
+                .log_err(context)
+                .unwrap_or(None)
+        });
 
-    match res {
-        Some(message) => Box::into_raw(Box::new(MessageWrapper { context, message })),
-        None => ptr::null_mut(),
-    }
+        match res {
+            Some(message) => Box::into_raw(Box::new(MessageWrapper { context, message })),
+            None => ptr::null_mut(),
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_msg_force_plaintext(msg: *mut dc_msg_t) {
-    if msg.is_null() {
-        eprintln!("ignoring careless call to dc_msg_force_plaintext()");
-        return;
-    }
-    let ffi_msg = &mut *msg;
-    ffi_msg.message.force_plaintext();
+    ffi_boundary("dc_msg_force_plaintext", ptr::null(), || {
+        if msg.is_null() {
+            eprintln!("ignoring careless call to dc_msg_force_plaintext()");
+            return;
+        }
+        let ffi_msg = &mut *msg;
+        ffi_msg.message.force_plaintext();
+    })
 }
 
 // dc_contact_t
@@ -3937,196 +5125,244 @@ pub struct ContactWrapper {
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_unref(contact: *mut dc_contact_t) {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_unref()");
-        return;
-    }
-    drop(Box::from_raw(contact));
+    ffi_boundary("dc_contact_unref", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_unref()");
+            return;
+        }
+        drop(Box::from_raw(contact));
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_id(contact: *mut dc_contact_t) -> u32 {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_id()");
-        return 0;
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.get_id().to_u32()
+    ffi_boundary("dc_contact_get_id", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_id()");
+            return 0;
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.get_id().to_u32()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_addr(contact: *mut dc_contact_t) -> *mut libc::c_char {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_addr()");
-        return "".strdup();
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.get_addr().strdup()
+    ffi_boundary("dc_contact_get_addr", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_addr()");
+            return "".strdup();
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.get_addr().strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_name(contact: *mut dc_contact_t) -> *mut libc::c_char {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_name()");
-        return "".strdup();
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.get_name().strdup()
+    ffi_boundary("dc_contact_get_name", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_name()");
+            return "".strdup();
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.get_name().strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_auth_name(contact: *mut dc_contact_t) -> *mut libc::c_char {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_auth_name()");
-        return "".strdup();
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.get_authname().strdup()
+    ffi_boundary("dc_contact_get_auth_name", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_auth_name()");
+            return "".strdup();
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.get_authname().strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_display_name(
     contact: *mut dc_contact_t,
 ) -> *mut libc::c_char {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_display_name()");
-        return "".strdup();
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.get_display_name().strdup()
+    ffi_boundary("dc_contact_get_display_name", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_display_name()");
+            return "".strdup();
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.get_display_name().strdup()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_contact_get_display_name_buf(
+    contact: *mut dc_contact_t,
+    buf: *mut libc::c_char,
+    buf_len: libc::size_t,
+) -> libc::size_t {
+    ffi_boundary("dc_contact_get_display_name_buf", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_display_name_buf()");
+            return copy_to_buf("", buf, buf_len);
+        }
+        let ffi_contact = &*contact;
+        copy_to_buf(ffi_contact.contact.get_display_name(), buf, buf_len)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_name_n_addr(
     contact: *mut dc_contact_t,
 ) -> *mut libc::c_char {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_name_n_addr()");
-        return "".strdup();
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.get_name_n_addr().strdup()
+    ffi_boundary("dc_contact_get_name_n_addr", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_name_n_addr()");
+            return "".strdup();
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.get_name_n_addr().strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_profile_image(
     contact: *mut dc_contact_t,
 ) -> *mut libc::c_char {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_profile_image()");
-        return ptr::null_mut(); // NULL explicitly defined as "no profile image"
-    }
-    let ffi_contact = &*contact;
-    let ctx = &*ffi_contact.context;
+    ffi_boundary("dc_contact_get_profile_image", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_profile_image()");
+            return ptr::null_mut(); // NULL explicitly defined as "no profile image"
+        }
+        let ffi_contact = &*contact;
+        let ctx = &*ffi_contact.context;
 
-    block_on(async move {
-        ffi_contact
-            .contact
-            .get_profile_image(ctx)
-            .await
-            .unwrap_or_log_default(ctx, "failed to get profile image")
-            .map(|p| p.to_string_lossy().strdup())
-            .unwrap_or_else(std::ptr::null_mut)
+        block_on(async move {
+            ffi_contact
+                .contact
+                .get_profile_image(ctx)
+                .await
+                .unwrap_or_log_default(ctx, "failed to get profile image")
+                .map(|p| p.to_string_lossy().strdup())
+                .unwrap_or_else(std::ptr::null_mut)
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_color(contact: *mut dc_contact_t) -> u32 {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_color()");
-        return 0;
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.get_color()
+    ffi_boundary("dc_contact_get_color", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_color()");
+            return 0;
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.get_color()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_status(contact: *mut dc_contact_t) -> *mut libc::c_char {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_status()");
-        return "".strdup();
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.get_status().strdup()
+    ffi_boundary("dc_contact_get_status", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_status()");
+            return "".strdup();
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.get_status().strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_last_seen(contact: *mut dc_contact_t) -> i64 {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_last_seen()");
-        return 0;
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.last_seen()
+    ffi_boundary("dc_contact_get_last_seen", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_last_seen()");
+            return 0;
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.last_seen()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_was_seen_recently(contact: *mut dc_contact_t) -> libc::c_int {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_was_seen_recently()");
-        return 0;
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.was_seen_recently() as libc::c_int
+    ffi_boundary("dc_contact_was_seen_recently", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_was_seen_recently()");
+            return 0;
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.was_seen_recently() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_is_blocked(contact: *mut dc_contact_t) -> libc::c_int {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_is_blocked()");
-        return 0;
-    }
-    let ffi_contact = &*contact;
-    ffi_contact.contact.is_blocked() as libc::c_int
+    ffi_boundary("dc_contact_is_blocked", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_is_blocked()");
+            return 0;
+        }
+        let ffi_contact = &*contact;
+        ffi_contact.contact.is_blocked() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_is_verified(contact: *mut dc_contact_t) -> libc::c_int {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_is_verified()");
-        return 0;
-    }
-    let ffi_contact = &*contact;
-    let ctx = &*ffi_contact.context;
+    ffi_boundary("dc_contact_is_verified", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_is_verified()");
+            return 0;
+        }
+        let ffi_contact = &*contact;
+        let ctx = &*ffi_contact.context;
 
-    block_on(ffi_contact.contact.is_verified(ctx))
-        .context("is_verified failed")
-        .log_err(ctx)
-        .unwrap_or_default() as libc::c_int
+        block_on(ffi_contact.contact.is_verified(ctx))
+            .context("is_verified failed")
+            .log_err(ctx)
+            .unwrap_or_default() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_verifier_addr(
     contact: *mut dc_contact_t,
 ) -> *mut libc::c_char {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_verifier_addr()");
-        return "".strdup();
-    }
-    let ffi_contact = &*contact;
-    let ctx = &*ffi_contact.context;
-    block_on(ffi_contact.contact.get_verifier_addr(ctx))
-        .context("failed to get verifier for contact")
-        .log_err(ctx)
-        .unwrap_or_default()
-        .strdup()
+    ffi_boundary("dc_contact_get_verifier_addr", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_verifier_addr()");
+            return "".strdup();
+        }
+        let ffi_contact = &*contact;
+        let ctx = &*ffi_contact.context;
+        block_on(ffi_contact.contact.get_verifier_addr(ctx))
+            .context("failed to get verifier for contact")
+            .log_err(ctx)
+            .unwrap_or_default()
+            .strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_contact_get_verifier_id(contact: *mut dc_contact_t) -> u32 {
-    if contact.is_null() {
-        eprintln!("ignoring careless call to dc_contact_get_verifier_id()");
-        return 0;
-    }
-    let ffi_contact = &*contact;
-    let ctx = &*ffi_contact.context;
-    let verifier_contact_id = block_on(ffi_contact.contact.get_verifier_id(ctx))
-        .context("failed to get verifier")
-        .log_err(ctx)
-        .unwrap_or_default()
-        .unwrap_or_default();
+    ffi_boundary("dc_contact_get_verifier_id", ptr::null(), || {
+        if contact.is_null() {
+            eprintln!("ignoring careless call to dc_contact_get_verifier_id()");
+            return 0;
+        }
+        let ffi_contact = &*contact;
+        let ctx = &*ffi_contact.context;
+        let verifier_contact_id = block_on(ffi_contact.contact.get_verifier_id(ctx))
+            .context("failed to get verifier")
+            .log_err(ctx)
+            .unwrap_or_default()
+            .unwrap_or_default();
 
-    verifier_contact_id.to_u32()
+        verifier_contact_id.to_u32()
+    })
 }
 // dc_lot_t
 
@@ -4134,93 +5370,143 @@ pub struct ContactWrapper {
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_lot_unref(lot: *mut dc_lot_t) {
-    if lot.is_null() {
-        eprintln!("ignoring careless call to dc_lot_unref()");
-        return;
-    }
+    ffi_boundary("dc_lot_unref", ptr::null(), || {
+        if lot.is_null() {
+            eprintln!("ignoring careless call to dc_lot_unref()");
+            return;
+        }
 
-    drop(Box::from_raw(lot));
+        drop(Box::from_raw(lot));
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_lot_get_text1(lot: *mut dc_lot_t) -> *mut libc::c_char {
-    if lot.is_null() {
-        eprintln!("ignoring careless call to dc_lot_get_text1()");
-        return ptr::null_mut(); // NULL explicitly defined as "there is no such text"
-    }
+    ffi_boundary("dc_lot_get_text1", ptr::null(), || {
+        if lot.is_null() {
+            eprintln!("ignoring careless call to dc_lot_get_text1()");
+            return ptr::null_mut(); // NULL explicitly defined as "there is no such text"
+        }
 
-    let lot = &*lot;
-    lot.get_text1().strdup()
+        let lot = &*lot;
+        lot.get_text1().strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_lot_get_text2(lot: *mut dc_lot_t) -> *mut libc::c_char {
-    if lot.is_null() {
-        eprintln!("ignoring careless call to dc_lot_get_text2()");
-        return ptr::null_mut(); // NULL explicitly defined as "there is no such text"
-    }
+    ffi_boundary("dc_lot_get_text2", ptr::null(), || {
+        if lot.is_null() {
+            eprintln!("ignoring careless call to dc_lot_get_text2()");
+            return ptr::null_mut(); // NULL explicitly defined as "there is no such text"
+        }
+
+        let lot = &*lot;
+        lot.get_text2().strdup()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_lot_get_text1_buf(
+    lot: *mut dc_lot_t,
+    buf: *mut libc::c_char,
+    buf_len: libc::size_t,
+) -> libc::size_t {
+    ffi_boundary("dc_lot_get_text1_buf", ptr::null(), || {
+        if lot.is_null() {
+            eprintln!("ignoring careless call to dc_lot_get_text1_buf()");
+            return copy_to_buf("", buf, buf_len);
+        }
 
-    let lot = &*lot;
-    lot.get_text2().strdup()
+        let lot = &*lot;
+        copy_to_buf(lot.get_text1().unwrap_or_default(), buf, buf_len)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_lot_get_text2_buf(
+    lot: *mut dc_lot_t,
+    buf: *mut libc::c_char,
+    buf_len: libc::size_t,
+) -> libc::size_t {
+    ffi_boundary("dc_lot_get_text2_buf", ptr::null(), || {
+        if lot.is_null() {
+            eprintln!("ignoring careless call to dc_lot_get_text2_buf()");
+            return copy_to_buf("", buf, buf_len);
+        }
+
+        let lot = &*lot;
+        copy_to_buf(&lot.get_text2().unwrap_or_default(), buf, buf_len)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_lot_get_text1_meaning(lot: *mut dc_lot_t) -> libc::c_int {
-    if lot.is_null() {
-        eprintln!("ignoring careless call to dc_lot_get_text1_meaning()");
-        return 0;
-    }
+    ffi_boundary("dc_lot_get_text1_meaning", ptr::null(), || {
+        if lot.is_null() {
+            eprintln!("ignoring careless call to dc_lot_get_text1_meaning()");
+            return 0;
+        }
 
-    let lot = &*lot;
-    lot.get_text1_meaning() as libc::c_int
+        let lot = &*lot;
+        lot.get_text1_meaning() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_lot_get_state(lot: *mut dc_lot_t) -> libc::c_int {
-    if lot.is_null() {
-        eprintln!("ignoring careless call to dc_lot_get_state()");
-        return 0;
-    }
+    ffi_boundary("dc_lot_get_state", ptr::null(), || {
+        if lot.is_null() {
+            eprintln!("ignoring careless call to dc_lot_get_state()");
+            return 0;
+        }
 
-    let lot = &*lot;
-    lot.get_state() as libc::c_int
+        let lot = &*lot;
+        lot.get_state() as libc::c_int
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_lot_get_id(lot: *mut dc_lot_t) -> u32 {
-    if lot.is_null() {
-        eprintln!("ignoring careless call to dc_lot_get_id()");
-        return 0;
-    }
+    ffi_boundary("dc_lot_get_id", ptr::null(), || {
+        if lot.is_null() {
+            eprintln!("ignoring careless call to dc_lot_get_id()");
+            return 0;
+        }
 
-    let lot = &*lot;
-    lot.get_id()
+        let lot = &*lot;
+        lot.get_id()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_lot_get_timestamp(lot: *mut dc_lot_t) -> i64 {
-    if lot.is_null() {
-        eprintln!("ignoring careless call to dc_lot_get_timestamp()");
-        return 0;
-    }
+    ffi_boundary("dc_lot_get_timestamp", ptr::null(), || {
+        if lot.is_null() {
+            eprintln!("ignoring careless call to dc_lot_get_timestamp()");
+            return 0;
+        }
 
-    let lot = &*lot;
-    lot.get_timestamp()
+        let lot = &*lot;
+        lot.get_timestamp()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_reactions_get_contacts(
     reactions: *mut dc_reactions_t,
 ) -> *mut dc_array::dc_array_t {
-    if reactions.is_null() {
-        eprintln!("ignoring careless call to dc_reactions_get_contacts()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_reactions_get_contacts", ptr::null(), || {
+        if reactions.is_null() {
+            eprintln!("ignoring careless call to dc_reactions_get_contacts()");
+            return ptr::null_mut();
+        }
 
-    let reactions = &*reactions;
-    let array: dc_array_t = reactions.contacts().into();
+        let reactions = &*reactions;
+        let array: dc_array_t = reactions.contacts().into();
 
-    Box::into_raw(Box::new(array))
+        Box::into_raw(Box::new(array))
+    })
 }
 
 #[no_mangle]
@@ -4228,28 +5514,32 @@ pub struct ContactWrapper {
     reactions: *mut dc_reactions_t,
     contact_id: u32,
 ) -> *mut libc::c_char {
-    if reactions.is_null() {
-        eprintln!("ignoring careless call to dc_reactions_get_by_contact_id()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_reactions_get_by_contact_id", ptr::null(), || {
+        if reactions.is_null() {
+            eprintln!("ignoring careless call to dc_reactions_get_by_contact_id()");
+            return ptr::null_mut();
+        }
 
-    let reactions = &*reactions;
-    reactions.get(ContactId::new(contact_id)).as_str().strdup()
+        let reactions = &*reactions;
+        reactions.get(ContactId::new(contact_id)).as_str().strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_reactions_unref(reactions: *mut dc_reactions_t) {
-    if reactions.is_null() {
-        eprintln!("ignoring careless call to dc_reactions_unref()");
-        return;
-    }
+    ffi_boundary("dc_reactions_unref", ptr::null(), || {
+        if reactions.is_null() {
+            eprintln!("ignoring careless call to dc_reactions_unref()");
+            return;
+        }
 
-    drop(Box::from_raw(reactions));
+        drop(Box::from_raw(reactions));
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_str_unref(s: *mut libc::c_char) {
-    libc::free(s as *mut _)
+    ffi_boundary("dc_str_unref", ptr::null(), || libc::free(s as *mut _))
 }
 
 pub struct BackupProviderWrapper {
@@ -4263,83 +5553,93 @@ pub struct BackupProviderWrapper {
 pub unsafe extern "C" fn dc_backup_provider_new(
     context: *mut dc_context_t,
 ) -> *mut dc_backup_provider_t {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_backup_provider_new()");
-        return ptr::null_mut();
-    }
-    let ctx = &*context;
-    block_on(BackupProvider::prepare(ctx))
-        .map(|provider| BackupProviderWrapper {
-            context: ctx,
-            provider,
-        })
-        .map(|ffi_provider| Box::into_raw(Box::new(ffi_provider)))
-        .context("BackupProvider failed")
-        .log_err(ctx)
-        .set_last_error(ctx)
-        .unwrap_or(ptr::null_mut())
+    ffi_boundary("dc_backup_provider_new", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_backup_provider_new()");
+            return ptr::null_mut();
+        }
+        let ctx = &*context;
+        block_on(BackupProvider::prepare(ctx))
+            .map(|provider| BackupProviderWrapper {
+                context: ctx,
+                provider,
+            })
+            .map(|ffi_provider| Box::into_raw(Box::new(ffi_provider)))
+            .context("BackupProvider failed")
+            .log_err(ctx)
+            .set_last_error(ctx)
+            .unwrap_or(ptr::null_mut())
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_backup_provider_get_qr(
     provider: *const dc_backup_provider_t,
 ) -> *mut libc::c_char {
-    if provider.is_null() {
-        eprintln!("ignoring careless call to dc_backup_provider_qr");
-        return "".strdup();
-    }
-    let ffi_provider = &*provider;
-    let ctx = &*ffi_provider.context;
-    deltachat::qr::format_backup(&ffi_provider.provider.qr())
-        .context("BackupProvider get_qr failed")
-        .log_err(ctx)
-        .set_last_error(ctx)
-        .unwrap_or_default()
-        .strdup()
+    ffi_boundary("dc_backup_provider_get_qr", ptr::null(), || {
+        if provider.is_null() {
+            eprintln!("ignoring careless call to dc_backup_provider_qr");
+            return "".strdup();
+        }
+        let ffi_provider = &*provider;
+        let ctx = &*ffi_provider.context;
+        deltachat::qr::format_backup(&ffi_provider.provider.qr())
+            .context("BackupProvider get_qr failed")
+            .log_err(ctx)
+            .set_last_error(ctx)
+            .unwrap_or_default()
+            .strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_backup_provider_get_qr_svg(
     provider: *const dc_backup_provider_t,
 ) -> *mut libc::c_char {
-    if provider.is_null() {
-        eprintln!("ignoring careless call to dc_backup_provider_qr_svg()");
-        return "".strdup();
-    }
-    let ffi_provider = &*provider;
-    let ctx = &*ffi_provider.context;
-    let provider = &ffi_provider.provider;
-    block_on(generate_backup_qr(ctx, &provider.qr()))
-        .context("BackupProvider get_qr_svg failed")
-        .log_err(ctx)
-        .set_last_error(ctx)
-        .unwrap_or_default()
-        .strdup()
+    ffi_boundary("dc_backup_provider_get_qr_svg", ptr::null(), || {
+        if provider.is_null() {
+            eprintln!("ignoring careless call to dc_backup_provider_qr_svg()");
+            return "".strdup();
+        }
+        let ffi_provider = &*provider;
+        let ctx = &*ffi_provider.context;
+        let provider = &ffi_provider.provider;
+        block_on(generate_backup_qr(ctx, &provider.qr()))
+            .context("BackupProvider get_qr_svg failed")
+            .log_err(ctx)
+            .set_last_error(ctx)
+            .unwrap_or_default()
+            .strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_backup_provider_wait(provider: *mut dc_backup_provider_t) {
-    if provider.is_null() {
-        eprintln!("ignoring careless call to dc_backup_provider_wait()");
-        return;
-    }
-    let ffi_provider = &mut *provider;
-    let ctx = &*ffi_provider.context;
-    let provider = &mut ffi_provider.provider;
-    block_on(provider)
-        .context("Failed to await BackupProvider")
-        .log_err(ctx)
-        .set_last_error(ctx)
-        .ok();
+    ffi_boundary("dc_backup_provider_wait", ptr::null(), || {
+        if provider.is_null() {
+            eprintln!("ignoring careless call to dc_backup_provider_wait()");
+            return;
+        }
+        let ffi_provider = &mut *provider;
+        let ctx = &*ffi_provider.context;
+        let provider = &mut ffi_provider.provider;
+        block_on(provider)
+            .context("Failed to await BackupProvider")
+            .log_err(ctx)
+            .set_last_error(ctx)
+            .ok();
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_backup_provider_unref(provider: *mut dc_backup_provider_t) {
-    if provider.is_null() {
-        eprintln!("ignoring careless call to dc_backup_provider_unref()");
-        return;
-    }
-    drop(Box::from_raw(provider));
+    ffi_boundary("dc_backup_provider_unref", ptr::null(), || {
+        if provider.is_null() {
+            eprintln!("ignoring careless call to dc_backup_provider_unref()");
+            return;
+        }
+        drop(Box::from_raw(provider));
+    })
 }
 
 #[no_mangle]
@@ -4347,13 +5647,15 @@ pub struct BackupProviderWrapper {
     context: *mut dc_context_t,
     qr: *const libc::c_char,
 ) -> libc::c_int {
-    if context.is_null() {
-        eprintln!("ignoring careless call to dc_receive_backup()");
-        return 0;
-    }
-    let ctx = &*context;
-    let qr_text = to_string_lossy(qr);
-    receive_backup(ctx.clone(), qr_text)
+    ffi_boundary("dc_receive_backup", context, || {
+        if context.is_null() {
+            eprintln!("ignoring careless call to dc_receive_backup()");
+            return 0;
+        }
+        let ctx = &*context;
+        let qr_text = to_string_lossy(qr);
+        receive_backup(ctx.clone(), qr_text)
+    })
 }
 
 // Because this is a long-running operation make sure we own the Context.  This stops a FFI
@@ -4480,18 +5782,20 @@ fn convert_and_prune_message_ids(msg_ids: *const u32, msg_cnt: libc::c_int) -> V
     context: *const dc_context_t,
     addr: *const libc::c_char,
 ) -> *const dc_provider_t {
-    if context.is_null() || addr.is_null() {
-        eprintln!("ignoring careless call to dc_provider_new_from_email()");
-        return ptr::null();
-    }
-    let addr = to_string_lossy(addr);
+    ffi_boundary("dc_provider_new_from_email", context, || {
+        if context.is_null() || addr.is_null() {
+            eprintln!("ignoring careless call to dc_provider_new_from_email()");
+            return ptr::null();
+        }
+        let addr = to_string_lossy(addr);
 
-    let ctx = &*context;
+        let ctx = &*context;
 
-    match block_on(provider::get_provider_info(ctx, addr.as_str(), true)) {
-        Some(provider) => provider,
-        None => ptr::null_mut(),
-    }
+        match block_on(provider::get_provider_info(ctx, addr.as_str(), true)) {
+            Some(provider) => provider,
+            None => ptr::null_mut(),
+        }
+    })
 }
 
 #[no_mangle]
@@ -4499,78 +5803,88 @@ fn convert_and_prune_message_ids(msg_ids: *const u32, msg_cnt: libc::c_int) -> V
     context: *const dc_context_t,
     addr: *const libc::c_char,
 ) -> *const dc_provider_t {
-    if context.is_null() || addr.is_null() {
-        eprintln!("ignoring careless call to dc_provider_new_from_email_with_dns()");
-        return ptr::null();
-    }
-    let addr = to_string_lossy(addr);
+    ffi_boundary("dc_provider_new_from_email_with_dns", context, || {
+        if context.is_null() || addr.is_null() {
+            eprintln!("ignoring careless call to dc_provider_new_from_email_with_dns()");
+            return ptr::null();
+        }
+        let addr = to_string_lossy(addr);
 
-    let ctx = &*context;
-    let socks5_enabled = block_on(async move {
-        ctx.get_config_bool(config::Config::Socks5Enabled)
-            .await
-            .context("Can't get config")
-            .log_err(ctx)
-    });
+        let ctx = &*context;
+        let socks5_enabled = block_on(async move {
+            ctx.get_config_bool(config::Config::Socks5Enabled)
+                .await
+                .context("Can't get config")
+                .log_err(ctx)
+        });
 
-    match socks5_enabled {
-        Ok(socks5_enabled) => {
-            match block_on(provider::get_provider_info(
-                ctx,
-                addr.as_str(),
-                socks5_enabled,
-            )) {
-                Some(provider) => provider,
-                None => ptr::null_mut(),
+        match socks5_enabled {
+            Ok(socks5_enabled) => {
+                match block_on(provider::get_provider_info(
+                    ctx,
+                    addr.as_str(),
+                    socks5_enabled,
+                )) {
+                    Some(provider) => provider,
+                    None => ptr::null_mut(),
+                }
             }
+            Err(_) => ptr::null_mut(),
         }
-        Err(_) => ptr::null_mut(),
-    }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_provider_get_overview_page(
     provider: *const dc_provider_t,
 ) -> *mut libc::c_char {
-    if provider.is_null() {
-        eprintln!("ignoring careless call to dc_provider_get_overview_page()");
-        return "".strdup();
-    }
-    let provider = &*provider;
-    provider.overview_page.strdup()
+    ffi_boundary("dc_provider_get_overview_page", ptr::null(), || {
+        if provider.is_null() {
+            eprintln!("ignoring careless call to dc_provider_get_overview_page()");
+            return "".strdup();
+        }
+        let provider = &*provider;
+        provider.overview_page.strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_provider_get_before_login_hint(
     provider: *const dc_provider_t,
 ) -> *mut libc::c_char {
-    if provider.is_null() {
-        eprintln!("ignoring careless call to dc_provider_get_before_login_hint()");
-        return "".strdup();
-    }
-    let provider = &*provider;
-    provider.before_login_hint.strdup()
+    ffi_boundary("dc_provider_get_before_login_hint", ptr::null(), || {
+        if provider.is_null() {
+            eprintln!("ignoring careless call to dc_provider_get_before_login_hint()");
+            return "".strdup();
+        }
+        let provider = &*provider;
+        provider.before_login_hint.strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_provider_get_status(provider: *const dc_provider_t) -> libc::c_int {
-    if provider.is_null() {
-        eprintln!("ignoring careless call to dc_provider_get_status()");
-        return 0;
-    }
-    let provider = &*provider;
-    provider.status as libc::c_int
+    ffi_boundary("dc_provider_get_status", ptr::null(), || {
+        if provider.is_null() {
+            eprintln!("ignoring careless call to dc_provider_get_status()");
+            return 0;
+        }
+        let provider = &*provider;
+        provider.status as libc::c_int
+    })
 }
 
 #[no_mangle]
 #[allow(clippy::needless_return)]
 pub unsafe extern "C" fn dc_provider_unref(provider: *mut dc_provider_t) {
-    if provider.is_null() {
-        eprintln!("ignoring careless call to dc_provider_unref()");
-        return;
-    }
-    // currently, there is nothing to free, the provider info is a static object.
-    // this may change once we start localizing string.
+    ffi_boundary("dc_provider_unref", ptr::null(), || {
+        if provider.is_null() {
+            eprintln!("ignoring careless call to dc_provider_unref()");
+            return;
+        }
+        // currently, there is nothing to free, the provider info is a static object.
+        // this may change once we start localizing string.
+    })
 }
 
 // dc_http_response_t
@@ -4582,85 +5896,97 @@ fn convert_and_prune_message_ids(msg_ids: *const u32, msg_cnt: libc::c_int) -> V
     context: *const dc_context_t,
     url: *const libc::c_char,
 ) -> *mut dc_http_response_t {
-    if context.is_null() || url.is_null() {
-        eprintln!("ignoring careless call to dc_get_http_response()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_get_http_response", context, || {
+        if context.is_null() || url.is_null() {
+            eprintln!("ignoring careless call to dc_get_http_response()");
+            return ptr::null_mut();
+        }
 
-    let context = &*context;
-    let url = to_string_lossy(url);
-    if let Ok(response) = block_on(read_url_blob(context, &url))
-        .context("read_url_blob")
-        .log_err(context)
-    {
-        Box::into_raw(Box::new(response))
-    } else {
-        ptr::null_mut()
-    }
+        let context = &*context;
+        let url = to_string_lossy(url);
+        if let Ok(response) = block_on(read_url_blob(context, &url))
+            .context("read_url_blob")
+            .log_err(context)
+        {
+            Box::into_raw(Box::new(response))
+        } else {
+            ptr::null_mut()
+        }
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_http_response_get_mimetype(
     response: *const dc_http_response_t,
 ) -> *mut libc::c_char {
-    if response.is_null() {
-        eprintln!("ignoring careless call to dc_http_response_get_mimetype()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_http_response_get_mimetype", ptr::null(), || {
+        if response.is_null() {
+            eprintln!("ignoring careless call to dc_http_response_get_mimetype()");
+            return ptr::null_mut();
+        }
 
-    let response = &*response;
-    response.mimetype.strdup()
+        let response = &*response;
+        response.mimetype.strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_http_response_get_encoding(
     response: *const dc_http_response_t,
 ) -> *mut libc::c_char {
-    if response.is_null() {
-        eprintln!("ignoring careless call to dc_http_response_get_encoding()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_http_response_get_encoding", ptr::null(), || {
+        if response.is_null() {
+            eprintln!("ignoring careless call to dc_http_response_get_encoding()");
+            return ptr::null_mut();
+        }
 
-    let response = &*response;
-    response.encoding.strdup()
+        let response = &*response;
+        response.encoding.strdup()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_http_response_get_blob(
     response: *const dc_http_response_t,
 ) -> *mut libc::c_char {
-    if response.is_null() {
-        eprintln!("ignoring careless call to dc_http_response_get_blob()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_http_response_get_blob", ptr::null(), || {
+        if response.is_null() {
+            eprintln!("ignoring careless call to dc_http_response_get_blob()");
+            return ptr::null_mut();
+        }
 
-    let response = &*response;
-    let blob_len = response.blob.len();
-    let ptr = libc::malloc(blob_len);
-    libc::memcpy(ptr, response.blob.as_ptr() as *mut libc::c_void, blob_len);
-    ptr as *mut libc::c_char
+        let response = &*response;
+        let blob_len = response.blob.len();
+        let ptr = libc::malloc(blob_len);
+        libc::memcpy(ptr, response.blob.as_ptr() as *mut libc::c_void, blob_len);
+        ptr as *mut libc::c_char
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_http_response_get_size(
     response: *const dc_http_response_t,
 ) -> libc::size_t {
-    if response.is_null() {
-        eprintln!("ignoring careless call to dc_http_response_get_size()");
-        return 0;
-    }
+    ffi_boundary("dc_http_response_get_size", ptr::null(), || {
+        if response.is_null() {
+            eprintln!("ignoring careless call to dc_http_response_get_size()");
+            return 0;
+        }
 
-    let response = &*response;
-    response.blob.len()
+        let response = &*response;
+        response.blob.len()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_http_response_unref(response: *mut dc_http_response_t) {
-    if response.is_null() {
-        eprintln!("ignoring careless call to dc_http_response_unref()");
-        return;
-    }
-    drop(Box::from_raw(response));
+    ffi_boundary("dc_http_response_unref", ptr::null(), || {
+        if response.is_null() {
+            eprintln!("ignoring careless call to dc_http_response_unref()");
+            return;
+        }
+        drop(Box::from_raw(response));
+    })
 }
 
 // -- Accounts
@@ -4694,23 +6020,25 @@ fn new(accounts: Accounts) -> Self {
     _os_name: *const libc::c_char,
     dbfile: *const libc::c_char,
 ) -> *mut dc_accounts_t {
-    setup_panic!();
+    ffi_boundary("dc_accounts_new", ptr::null(), || {
+        setup_panic!();
 
-    if dbfile.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_new()");
-        return ptr::null_mut();
-    }
+        if dbfile.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_new()");
+            return ptr::null_mut();
+        }
 
-    let accs = block_on(Accounts::new(as_path(dbfile).into()));
+        let accs = block_on(Accounts::new(as_path(dbfile).into()));
 
-    match accs {
-        Ok(accs) => Box::into_raw(Box::new(AccountsWrapper::new(accs))),
-        Err(err) => {
-            // We are using Anyhow's .context() and to show the inner error, too, we need the {:#}:
-            eprintln!("failed to create accounts: {err:#}");
-            ptr::null_mut()
+        match accs {
+            Ok(accs) => Box::into_raw(Box::new(AccountsWrapper::new(accs))),
+            Err(err) => {
+                // We are using Anyhow's .context() and to show the inner error, too, we need the {:#}:
+                eprintln!("failed to create accounts: {err:#}");
+                ptr::null_mut()
+            }
         }
-    }
+    })
 }
 
 /// Release the accounts structure.
@@ -4718,11 +6046,13 @@ fn new(accounts: Accounts) -> Self {
 /// This function releases the memory of the `dc_accounts_t` structure.
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_unref(accounts: *mut dc_accounts_t) {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_unref()");
-        return;
-    }
-    let _ = Box::from_raw(accounts);
+    ffi_boundary("dc_accounts_unref", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_unref()");
+            return;
+        }
+        let _ = Box::from_raw(accounts);
+    })
 }
 
 #[no_mangle]
@@ -4730,32 +6060,42 @@ fn new(accounts: Accounts) -> Self {
     accounts: *mut dc_accounts_t,
     id: u32,
 ) -> *mut dc_context_t {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_get_account()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_accounts_get_account", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_get_account()");
+            return ptr::null_mut();
+        }
 
-    let accounts = &*accounts;
-    block_on(accounts.read())
-        .get_account(id)
-        .map(|ctx| Box::into_raw(Box::new(ctx)))
-        .unwrap_or_else(std::ptr::null_mut)
+        let accounts = &*accounts;
+        block_on(accounts.read())
+            .get_account(id)
+            .map(|ctx| {
+                block_on(ctx.open_lazily());
+                Box::into_raw(Box::new(ctx))
+            })
+            .unwrap_or_else(std::ptr::null_mut)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_get_selected_account(
     accounts: *mut dc_accounts_t,
 ) -> *mut dc_context_t {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_get_selected_account()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_accounts_get_selected_account", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_get_selected_account()");
+            return ptr::null_mut();
+        }
 
-    let accounts = &*accounts;
-    block_on(accounts.read())
-        .get_selected_account()
-        .map(|ctx| Box::into_raw(Box::new(ctx)))
-        .unwrap_or_else(std::ptr::null_mut)
+        let accounts = &*accounts;
+        block_on(accounts.read())
+            .get_selected_account()
+            .map(|ctx| {
+                block_on(ctx.open_lazily());
+                Box::into_raw(Box::new(ctx))
+            })
+            .unwrap_or_else(std::ptr::null_mut)
+    })
 }
 
 #[no_mangle]
@@ -4763,65 +6103,73 @@ fn new(accounts: Accounts) -> Self {
     accounts: *mut dc_accounts_t,
     id: u32,
 ) -> libc::c_int {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_select_account()");
-        return 0;
-    }
+    ffi_boundary("dc_accounts_select_account", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_select_account()");
+            return 0;
+        }
 
-    let accounts = &*accounts;
-    block_on(async move {
-        let mut accounts = accounts.write().await;
-        match accounts.select_account(id).await {
-            Ok(()) => 1,
-            Err(err) => {
-                accounts.emit_event(EventType::Error(format!(
-                    "Failed to select account: {err:#}"
-                )));
-                0
+        let accounts = &*accounts;
+        block_on(async move {
+            let mut accounts = accounts.write().await;
+            match accounts.select_account(id).await {
+                Ok(()) => 1,
+                Err(err) => {
+                    accounts.emit_event(EventType::Error(format!(
+                        "Failed to select account: {err:#}"
+                    )));
+                    0
+                }
             }
-        }
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_add_account(accounts: *mut dc_accounts_t) -> u32 {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_add_account()");
-        return 0;
-    }
+    ffi_boundary("dc_accounts_add_account", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_add_account()");
+            return 0;
+        }
 
-    let accounts = &mut *accounts;
+        let accounts = &mut *accounts;
 
-    block_on(async move {
-        let mut accounts = accounts.write().await;
-        match accounts.add_account().await {
-            Ok(id) => id,
-            Err(err) => {
-                accounts.emit_event(EventType::Error(format!("Failed to add account: {err:#}")));
-                0
+        block_on(async move {
+            let mut accounts = accounts.write().await;
+            match accounts.add_account().await {
+                Ok(id) => id,
+                Err(err) => {
+                    accounts
+                        .emit_event(EventType::Error(format!("Failed to add account: {err:#}")));
+                    0
+                }
             }
-        }
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_add_closed_account(accounts: *mut dc_accounts_t) -> u32 {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_add_closed_account()");
-        return 0;
-    }
+    ffi_boundary("dc_accounts_add_closed_account", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_add_closed_account()");
+            return 0;
+        }
 
-    let accounts = &mut *accounts;
+        let accounts = &mut *accounts;
 
-    block_on(async move {
-        let mut accounts = accounts.write().await;
-        match accounts.add_closed_account().await {
-            Ok(id) => id,
-            Err(err) => {
-                accounts.emit_event(EventType::Error(format!("Failed to add account: {err:#}")));
-                0
+        block_on(async move {
+            let mut accounts = accounts.write().await;
+            match accounts.add_closed_account().await {
+                Ok(id) => id,
+                Err(err) => {
+                    accounts
+                        .emit_event(EventType::Error(format!("Failed to add account: {err:#}")));
+                    0
+                }
             }
-        }
+        })
     })
 }
 
@@ -4830,24 +6178,26 @@ fn new(accounts: Accounts) -> Self {
     accounts: *mut dc_accounts_t,
     id: u32,
 ) -> libc::c_int {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_remove_account()");
-        return 0;
-    }
+    ffi_boundary("dc_accounts_remove_account", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_remove_account()");
+            return 0;
+        }
 
-    let accounts = &mut *accounts;
+        let accounts = &mut *accounts;
 
-    block_on(async move {
-        let mut accounts = accounts.write().await;
-        match accounts.remove_account(id).await {
-            Ok(()) => 1,
-            Err(err) => {
-                accounts.emit_event(EventType::Error(format!(
-                    "Failed to remove account: {err:#}"
-                )));
-                0
+        block_on(async move {
+            let mut accounts = accounts.write().await;
+            match accounts.remove_account(id).await {
+                Ok(()) => 1,
+                Err(err) => {
+                    accounts.emit_event(EventType::Error(format!(
+                        "Failed to remove account: {err:#}"
+                    )));
+                    0
+                }
             }
-        }
+        })
     })
 }
 
@@ -4856,112 +6206,222 @@ fn new(accounts: Accounts) -> Self {
     accounts: *mut dc_accounts_t,
     dbfile: *const libc::c_char,
 ) -> u32 {
-    if accounts.is_null() || dbfile.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_migrate_account()");
-        return 0;
-    }
+    ffi_boundary("dc_accounts_migrate_account", ptr::null(), || {
+        if accounts.is_null() || dbfile.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_migrate_account()");
+            return 0;
+        }
 
-    let accounts = &mut *accounts;
-    let dbfile = to_string_lossy(dbfile);
+        let accounts = &mut *accounts;
+        let dbfile = to_string_lossy(dbfile);
 
-    block_on(async move {
-        let mut accounts = accounts.write().await;
-        match accounts
-            .migrate_account(std::path::PathBuf::from(dbfile))
-            .await
-        {
-            Ok(id) => id,
-            Err(err) => {
-                accounts.emit_event(EventType::Error(format!(
-                    "Failed to migrate account: {err:#}"
-                )));
-                0
+        block_on(async move {
+            let mut accounts = accounts.write().await;
+            match accounts
+                .migrate_account(std::path::PathBuf::from(dbfile))
+                .await
+            {
+                Ok(id) => id,
+                Err(err) => {
+                    accounts.emit_event(EventType::Error(format!(
+                        "Failed to migrate account: {err:#}"
+                    )));
+                    0
+                }
             }
-        }
+        })
     })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_get_all(accounts: *mut dc_accounts_t) -> *mut dc_array_t {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_get_all()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_accounts_get_all", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_get_all()");
+            return ptr::null_mut();
+        }
 
-    let accounts = &*accounts;
-    let list = block_on(accounts.read()).get_all();
-    let array: dc_array_t = list.into();
+        let accounts = &*accounts;
+        let list = block_on(accounts.read()).get_all();
+        let array: dc_array_t = list.into();
 
-    Box::into_raw(Box::new(array))
+        Box::into_raw(Box::new(array))
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_all_work_done(accounts: *mut dc_accounts_t) -> libc::c_int {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_all_work_done()");
-        return 0;
-    }
-    let accounts = &*accounts;
-    block_on(async move { accounts.read().await.all_work_done().await as libc::c_int })
+    ffi_boundary("dc_accounts_all_work_done", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_all_work_done()");
+            return 0;
+        }
+        let accounts = &*accounts;
+        block_on(async move { accounts.read().await.all_work_done().await as libc::c_int })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_start_io(accounts: *mut dc_accounts_t) {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_start_io()");
-        return;
-    }
+    ffi_boundary("dc_accounts_start_io", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_start_io()");
+            return;
+        }
 
-    let accounts = &*accounts;
-    block_on(async move { accounts.read().await.start_io().await });
+        let accounts = &*accounts;
+        block_on(async move { accounts.read().await.start_io().await });
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_stop_io(accounts: *mut dc_accounts_t) {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_stop_io()");
-        return;
-    }
+    ffi_boundary("dc_accounts_stop_io", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_stop_io()");
+            return;
+        }
 
-    let accounts = &*accounts;
-    block_on(async move { accounts.read().await.stop_io().await });
+        let accounts = &*accounts;
+        block_on(async move { accounts.read().await.stop_io().await });
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_maybe_network(accounts: *mut dc_accounts_t) {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_maybe_network()");
-        return;
-    }
+    ffi_boundary("dc_accounts_maybe_network", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_maybe_network()");
+            return;
+        }
 
-    let accounts = &*accounts;
-    block_on(async move { accounts.read().await.maybe_network().await });
+        let accounts = &*accounts;
+        block_on(async move { accounts.read().await.maybe_network().await });
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_set_network_class(
+    accounts: *mut dc_accounts_t,
+    network_class: libc::c_int,
+) {
+    ffi_boundary("dc_accounts_set_network_class", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_set_network_class()");
+            return;
+        }
+
+        let accounts = &*accounts;
+        let network_class = match NetworkClass::from_i32(network_class) {
+            Some(network_class) => network_class,
+            None => {
+                eprintln!("dc_accounts_set_network_class(): invalid network class {network_class}");
+                return;
+            }
+        };
+        block_on(async move {
+            let accounts = accounts.read().await;
+            if let Err(err) = accounts.set_network_class(network_class).await {
+                accounts.emit_event(EventType::Error(format!(
+                    "Failed to set network class: {err:#}"
+                )));
+            }
+        });
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_maybe_network_lost(accounts: *mut dc_accounts_t) {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_maybe_network_lost()");
-        return;
-    }
+    ffi_boundary("dc_accounts_maybe_network_lost", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_maybe_network_lost()");
+            return;
+        }
 
-    let accounts = &*accounts;
-    block_on(async move { accounts.write().await.maybe_network_lost().await });
+        let accounts = &*accounts;
+        block_on(async move { accounts.write().await.maybe_network_lost().await });
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn dc_accounts_get_event_emitter(
     accounts: *mut dc_accounts_t,
 ) -> *mut dc_event_emitter_t {
-    if accounts.is_null() {
-        eprintln!("ignoring careless call to dc_accounts_get_event_emitter()");
-        return ptr::null_mut();
-    }
+    ffi_boundary("dc_accounts_get_event_emitter", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_get_event_emitter()");
+            return ptr::null_mut();
+        }
+
+        let accounts = &*accounts;
+        let emitter = block_on(accounts.read()).get_event_emitter();
+
+        Box::into_raw(Box::new(emitter))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_set_event_coalescing_window(
+    accounts: *mut dc_accounts_t,
+    window_ms: libc::c_int,
+) {
+    ffi_boundary(
+        "dc_accounts_set_event_coalescing_window",
+        ptr::null(),
+        || {
+            if accounts.is_null() {
+                eprintln!("ignoring careless call to dc_accounts_set_event_coalescing_window()");
+                return;
+            }
+
+            let accounts = &*accounts;
+            let window = Duration::from_millis(window_ms.try_into().unwrap_or_default());
+            block_on(accounts.read()).set_event_coalescing_window(window);
+        },
+    )
+}
 
-    let accounts = &*accounts;
-    let emitter = block_on(accounts.read()).get_event_emitter();
+#[no_mangle]
+pub unsafe extern "C" fn dc_accounts_set_event_queue_config(
+    accounts: *mut dc_accounts_t,
+    account_id: u32,
+    capacity: libc::c_int,
+    overflow_policy: libc::c_int,
+) -> libc::c_int {
+    ffi_boundary("dc_accounts_set_event_queue_config", ptr::null(), || {
+        if accounts.is_null() {
+            eprintln!("ignoring careless call to dc_accounts_set_event_queue_config()");
+            return 0;
+        }
 
-    Box::into_raw(Box::new(emitter))
+        let overflow_policy = match overflow_policy {
+            0 => OverflowPolicy::DropOldest,
+            1 => OverflowPolicy::DropLowPriority,
+            _ => {
+                eprintln!("ignoring unknown overflow policy {overflow_policy}");
+                return 0;
+            }
+        };
+        let config = EventQueueConfig {
+            capacity: capacity.try_into().unwrap_or_default(),
+            overflow_policy,
+        };
+        let accounts = &*accounts;
+        let accounts = block_on(accounts.read());
+        if account_id == 0 {
+            accounts.set_event_queue_config(config);
+            return 1;
+        }
+        match accounts.set_account_event_queue_config(account_id, config) {
+            Ok(()) => 1,
+            Err(err) => {
+                accounts.emit_event(EventType::Error(format!(
+                    "Failed to set event queue config: {err:#}"
+                )));
+                0
+            }
+        }
+    })
 }
 
 #[cfg(feature = "jsonrpc")]
@@ -4974,35 +6434,45 @@ mod jsonrpc {
     pub struct dc_jsonrpc_instance_t {
         receiver: OutReceiver,
         handle: RpcSession<CommandApi>,
+        api: CommandApi,
     }
 
     #[no_mangle]
     pub unsafe extern "C" fn dc_jsonrpc_init(
         account_manager: *mut dc_accounts_t,
     ) -> *mut dc_jsonrpc_instance_t {
-        if account_manager.is_null() {
-            eprintln!("ignoring careless call to dc_jsonrpc_init()");
-            return ptr::null_mut();
-        }
+        ffi_boundary("dc_jsonrpc_init", ptr::null(), || {
+            if account_manager.is_null() {
+                eprintln!("ignoring careless call to dc_jsonrpc_init()");
+                return ptr::null_mut();
+            }
 
-        let account_manager = &*account_manager;
-        let cmd_api = deltachat_jsonrpc::api::CommandApi::from_arc(account_manager.inner.clone());
+            let account_manager = &*account_manager;
+            let cmd_api =
+                deltachat_jsonrpc::api::CommandApi::from_arc(account_manager.inner.clone());
 
-        let (request_handle, receiver) = RpcClient::new();
-        let handle = RpcSession::new(request_handle, cmd_api);
+            let (request_handle, receiver) = RpcClient::new();
+            let handle = RpcSession::new(request_handle, cmd_api.clone());
 
-        let instance = dc_jsonrpc_instance_t { receiver, handle };
+            let instance = dc_jsonrpc_instance_t {
+                receiver,
+                handle,
+                api: cmd_api,
+            };
 
-        Box::into_raw(Box::new(instance))
+            Box::into_raw(Box::new(instance))
+        })
     }
 
     #[no_mangle]
     pub unsafe extern "C" fn dc_jsonrpc_unref(jsonrpc_instance: *mut dc_jsonrpc_instance_t) {
-        if jsonrpc_instance.is_null() {
-            eprintln!("ignoring careless call to dc_jsonrpc_unref()");
-            return;
-        }
-        drop(Box::from_raw(jsonrpc_instance));
+        ffi_boundary("dc_jsonrpc_unref", ptr::null(), || {
+            if jsonrpc_instance.is_null() {
+                eprintln!("ignoring careless call to dc_jsonrpc_unref()");
+                return;
+            }
+            drop(Box::from_raw(jsonrpc_instance));
+        })
     }
 
     fn spawn_handle_jsonrpc_request(handle: RpcSession<CommandApi>, request: String) {
@@ -5016,27 +6486,60 @@ fn spawn_handle_jsonrpc_request(handle: RpcSession<CommandApi>, request: String)
         jsonrpc_instance: *mut dc_jsonrpc_instance_t,
         request: *const libc::c_char,
     ) {
-        if jsonrpc_instance.is_null() || request.is_null() {
-            eprintln!("ignoring careless call to dc_jsonrpc_request()");
-            return;
-        }
+        ffi_boundary("dc_jsonrpc_request", ptr::null(), || {
+            if jsonrpc_instance.is_null() || request.is_null() {
+                eprintln!("ignoring careless call to dc_jsonrpc_request()");
+                return;
+            }
 
-        let handle = &(*jsonrpc_instance).handle;
-        let request = to_string_lossy(request);
-        spawn_handle_jsonrpc_request(handle.clone(), request);
+            let handle = &(*jsonrpc_instance).handle;
+            let request = to_string_lossy(request);
+            spawn_handle_jsonrpc_request(handle.clone(), request);
+        })
     }
 
     #[no_mangle]
     pub unsafe extern "C" fn dc_jsonrpc_next_response(
         jsonrpc_instance: *mut dc_jsonrpc_instance_t,
     ) -> *mut libc::c_char {
-        if jsonrpc_instance.is_null() {
-            eprintln!("ignoring careless call to dc_jsonrpc_next_response()");
-            return ptr::null_mut();
-        }
-        let api = &*jsonrpc_instance;
-        block_on(api.receiver.recv())
-            .map(|result| serde_json::to_string(&result).unwrap_or_default().strdup())
-            .unwrap_or(ptr::null_mut())
+        ffi_boundary("dc_jsonrpc_next_response", ptr::null(), || {
+            if jsonrpc_instance.is_null() {
+                eprintln!("ignoring careless call to dc_jsonrpc_next_response()");
+                return ptr::null_mut();
+            }
+            let api = &*jsonrpc_instance;
+            block_on(api.receiver.recv())
+                .map(|result| serde_json::to_string(&result).unwrap_or_default().strdup())
+                .unwrap_or(ptr::null_mut())
+        })
+    }
+
+    async fn handle_jsonrpc_blocking_call(api: CommandApi, request: String) -> Option<String> {
+        // The response is not returned by dc_jsonrpc_next_response(),
+        // so a separate session is used for each call.
+        let (client, receiver) = RpcClient::new();
+        let session = RpcSession::new(client, api);
+        session.handle_incoming(&request).await;
+        drop(session);
+        let response = receiver.recv().await.ok()?;
+        serde_json::to_string(&response).ok()
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn dc_jsonrpc_blocking_call(
+        jsonrpc_instance: *mut dc_jsonrpc_instance_t,
+        request: *const libc::c_char,
+    ) -> *mut libc::c_char {
+        ffi_boundary("dc_jsonrpc_blocking_call", ptr::null(), || {
+            if jsonrpc_instance.is_null() || request.is_null() {
+                eprintln!("ignoring careless call to dc_jsonrpc_blocking_call()");
+                return ptr::null_mut();
+            }
+            let api = (*jsonrpc_instance).api.clone();
+            let request = to_string_lossy(request);
+            block_on(handle_jsonrpc_blocking_call(api, request))
+                .map(|response| response.strdup())
+                .unwrap_or(ptr::null_mut())
+        })
     }
 }
@@ -242,6 +242,30 @@ pub(crate) fn to_opt_string_lossy(s: *const libc::c_char) -> Option<String> {
     Some(to_string_lossy(s))
 }
 
+/// Copies a string into a caller-provided buffer of `buf_len` bytes.
+///
+/// This is used by the `*_buf()` functions which avoid allocating a new C string.
+/// Returns the length of the string in bytes, without the terminating null byte.
+/// The string is only copied if the buffer can hold it together with the null byte,
+/// otherwise an empty string is written, so the caller can retry with a larger buffer.
+/// Embedded null bytes are stripped as by [CStringExt::new_lossy].
+pub(crate) unsafe fn copy_to_buf(
+    s: &str,
+    buf: *mut libc::c_char,
+    buf_len: libc::size_t,
+) -> libc::size_t {
+    let tmp = CString::new_lossy(s);
+    let bytes = tmp.as_bytes_with_nul();
+    if !buf.is_null() && buf_len > 0 {
+        if bytes.len() <= buf_len {
+            ptr::copy_nonoverlapping(tmp.as_ptr(), buf, bytes.len());
+        } else {
+            *buf = 0;
+        }
+    }
+    bytes.len() - 1
+}
+
 /// Convert a C `*char` pointer to a [std::path::Path] slice.
 ///
 /// This converts a `*libc::c_char` pointer to a [Path] slice.  This
@@ -405,6 +429,23 @@ fn test_strdup_string() {
         }
     }
 
+    #[test]
+    fn test_copy_to_buf() {
+        unsafe {
+            let mut buf: [libc::c_char; 6] = [1; 6];
+            assert_eq!(copy_to_buf("hello", buf.as_mut_ptr(), buf.len()), 5);
+            assert_eq!(to_string_lossy(buf.as_ptr()), "hello");
+
+            assert_eq!(copy_to_buf("hello!", buf.as_mut_ptr(), buf.len()), 6);
+            assert_eq!(to_string_lossy(buf.as_ptr()), "");
+
+            assert_eq!(copy_to_buf("hel\x00lo", buf.as_mut_ptr(), buf.len()), 5);
+            assert_eq!(to_string_lossy(buf.as_ptr()), "hello");
+
+            assert_eq!(copy_to_buf("hello", ptr::null_mut(), 0), 5);
+        }
+    }
+
     #[test]
     fn test_strdup_opt_string() {
         unsafe {
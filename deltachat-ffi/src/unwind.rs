@@ -0,0 +1,117 @@
+//! Catching panics at the FFI boundary.
+//!
+//! Unwinding into C code is undefined behaviour
+//! and aborting takes down the whole app,
+//! so every exported function runs its body inside [`ffi_boundary`].
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+use deltachat::context::Context;
+use deltachat::error;
+
+/// Value returned by an FFI function if it panicked.
+pub(crate) trait PanicDefault {
+    fn panic_default() -> Self;
+}
+
+impl PanicDefault for () {
+    fn panic_default() -> Self {}
+}
+
+impl<T> PanicDefault for *mut T {
+    fn panic_default() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+impl<T> PanicDefault for *const T {
+    fn panic_default() -> Self {
+        std::ptr::null()
+    }
+}
+
+macro_rules! impl_panic_default {
+    ($($t:ty => $v:expr),*) => {
+        $(impl PanicDefault for $t {
+            fn panic_default() -> Self {
+                $v
+            }
+        })*
+    };
+}
+
+impl_panic_default!(i32 => 0, u32 => 0, i64 => 0, u64 => 0, usize => 0, f64 => 0.0);
+
+/// Returns the message a panic was started with.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Runs the body `f` of the FFI function `name`, catching panics.
+///
+/// If `f` panics, the panic message is set as the last error of the context
+/// and emitted as an error event; if `context` is NULL, it is only printed to stderr.
+/// Instead of unwinding into the caller, [`PanicDefault::panic_default`] is returned then,
+/// e.g. NULL or 0.
+///
+/// This requires the library to be built with `panic = "unwind"`,
+/// otherwise a panic still aborts the process.
+pub(crate) unsafe fn ffi_boundary<T: PanicDefault>(
+    name: &str,
+    context: *const Context,
+    f: impl FnOnce() -> T,
+) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => res,
+        Err(payload) => {
+            let msg = panic_message(payload.as_ref());
+            match context.as_ref() {
+                Some(context) => {
+                    error!(context, "{name}() panicked: {msg}");
+                }
+                None => eprintln!("{name}() panicked: {msg}"),
+            }
+            T::panic_default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn test_ffi_boundary() {
+        unsafe {
+            assert_eq!(ffi_boundary("dc_test", ptr::null(), || 42), 42);
+
+            let res: *mut libc::c_char =
+                ffi_boundary("dc_test", ptr::null(), || panic!("something went wrong"));
+            assert!(res.is_null());
+
+            let res: libc::c_int = ffi_boundary("dc_test", ptr::null(), || {
+                let v: Vec<i32> = Vec::new();
+                v[1]
+            });
+            assert_eq!(res, 0);
+        }
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let payload = panic::catch_unwind(|| panic!("static message")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "static message");
+
+        let payload = panic::catch_unwind(|| panic!("formatted {}", 42)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "formatted 42");
+    }
+}
@@ -0,0 +1,129 @@
+//! Tokens for serving message attachments over HTTP.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose, Engine as _};
+use rand::{thread_rng, RngCore};
+
+/// Time after which a token expires if it is not used.
+const TOKEN_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+/// Message of a token.
+#[derive(Debug)]
+struct TokenEntry {
+    /// Account ID and message ID.
+    ids: (u32, u32),
+
+    /// Time the token was created or used last.
+    last_used: Instant,
+}
+
+/// Unguessable URL tokens of message attachments served by a blob server.
+///
+/// Each message gets a single token, which expires if it is not used for an hour.
+#[derive(Debug)]
+pub(crate) struct BlobTokens {
+    /// Base URL of the blob server, e.g. `http://127.0.0.1:20809`.
+    base_url: String,
+
+    /// Message by token.
+    messages: HashMap<String, TokenEntry>,
+
+    /// Token by account ID and message ID.
+    tokens: HashMap<(u32, u32), String>,
+}
+
+impl BlobTokens {
+    pub(crate) fn new(base_url: String) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            messages: HashMap::new(),
+            tokens: HashMap::new(),
+        }
+    }
+
+    /// Returns the URL of the attachment of the message, creating a token if needed.
+    pub(crate) fn get_url(&mut self, account_id: u32, msg_id: u32) -> String {
+        self.remove_expired();
+        let ids = (account_id, msg_id);
+        let token = match self.tokens.get(&ids) {
+            Some(token) => token.clone(),
+            None => {
+                let mut bytes = [0u8; 32];
+                thread_rng().fill_bytes(&mut bytes);
+                let token = general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+                self.tokens.insert(ids, token.clone());
+                token
+            }
+        };
+        let url = format!("{}/{}", self.base_url, token);
+        self.messages.insert(
+            token,
+            TokenEntry {
+                ids,
+                last_used: Instant::now(),
+            },
+        );
+        url
+    }
+
+    /// Returns account ID and message ID of the token,
+    /// `None` if the token is unknown or expired.
+    pub(crate) fn resolve(&mut self, token: &str) -> Option<(u32, u32)> {
+        let entry = self.messages.get_mut(token)?;
+        if entry.last_used.elapsed() >= TOKEN_LIFETIME {
+            return None;
+        }
+        entry.last_used = Instant::now();
+        Some(entry.ids)
+    }
+
+    /// Removes expired tokens.
+    fn remove_expired(&mut self) {
+        let tokens = &mut self.tokens;
+        self.messages.retain(|_, entry| {
+            let valid = entry.last_used.elapsed() < TOKEN_LIFETIME;
+            if !valid {
+                tokens.remove(&entry.ids);
+            }
+            valid
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blob_tokens() {
+        let mut blob_tokens = BlobTokens::new("http://127.0.0.1:20809/".to_string());
+        let url = blob_tokens.get_url(1, 10);
+        assert!(url.starts_with("http://127.0.0.1:20809/"));
+        assert_eq!(blob_tokens.get_url(1, 10), url);
+        assert_ne!(blob_tokens.get_url(2, 10), url);
+
+        let token = url.rsplit('/').next().unwrap();
+        assert_eq!(blob_tokens.resolve(token), Some((1, 10)));
+        assert_eq!(blob_tokens.resolve("foo"), None);
+    }
+
+    #[test]
+    fn test_blob_tokens_expire() {
+        let mut blob_tokens = BlobTokens::new("http://127.0.0.1:20809".to_string());
+        let url = blob_tokens.get_url(1, 10);
+        let token = url.rsplit('/').next().unwrap().to_string();
+
+        let expired = Instant::now() - TOKEN_LIFETIME;
+        blob_tokens.messages.get_mut(&token).unwrap().last_used = expired;
+        assert_eq!(blob_tokens.resolve(&token), None);
+
+        // Expired tokens are removed and a new token is created.
+        blob_tokens.get_url(2, 20);
+        assert_eq!(blob_tokens.messages.len(), 1);
+        assert_ne!(blob_tokens.get_url(1, 10), url);
+        assert_eq!(blob_tokens.messages.len(), 2);
+        assert_eq!(blob_tokens.tokens.len(), 2);
+    }
+}
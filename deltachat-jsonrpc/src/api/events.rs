@@ -1,14 +1,28 @@
-use deltachat::{Event as CoreEvent, EventType as CoreEventType};
-use serde::Serialize;
+use std::collections::BTreeSet;
+
+use anyhow::{ensure, Result};
+use deltachat::{
+    AccountsEvent as CoreAccountsEvent, Event as CoreEvent,
+    EventHistoryEntry as CoreEventHistoryEntry, EventType as CoreEventType,
+    OverflowPolicy as CoreOverflowPolicy,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use typescript_type_def::TypeDef;
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 pub struct Event {
     /// Event payload.
     event: EventType,
 
     /// Account ID.
     context_id: u32,
+
+    /// Sequence number of the event, increasing with each event of the account.
+    ///
+    /// Can be passed to `replay_events` after reconnecting.
+    /// 0 for events not emitted by an account.
+    seq: u64,
 }
 
 impl From<CoreEvent> for Event {
@@ -16,11 +30,192 @@ fn from(event: CoreEvent) -> Self {
         Event {
             event: event.typ.into(),
             context_id: event.id,
+            seq: event.seq,
+        }
+    }
+}
+
+impl Event {
+    pub(crate) fn from_history_entry(account_id: u32, entry: CoreEventHistoryEntry) -> Self {
+        Event {
+            event: entry.event.into(),
+            context_id: account_id,
+            seq: entry.seq,
         }
     }
 }
 
-#[derive(Serialize, TypeDef)]
+/// Event recorded in the event history of an account.
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EventHistoryEntry {
+    /// Sequence number of the event.
+    seq: u64,
+
+    /// Time the event was emitted, in milliseconds since the Unix epoch.
+    timestamp_ms: i64,
+
+    /// Event payload.
+    event: EventType,
+}
+
+impl From<CoreEventHistoryEntry> for EventHistoryEntry {
+    fn from(entry: CoreEventHistoryEntry) -> Self {
+        EventHistoryEntry {
+            seq: entry.seq,
+            timestamp_ms: entry.timestamp_ms,
+            event: entry.event.into(),
+        }
+    }
+}
+
+/// Event emitted on the account manager channel.
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(tag = "type")]
+pub enum AccountsEvent {
+    /// Account was added.
+    #[serde(rename_all = "camelCase")]
+    AccountAdded { account_id: u32 },
+
+    /// Account was removed.
+    #[serde(rename_all = "camelCase")]
+    AccountRemoved { account_id: u32 },
+
+    /// Starting IO of the account started, see `start_io_for_all_accounts()`.
+    #[serde(rename_all = "camelCase")]
+    IoStarting { account_id: u32 },
+
+    /// Starting IO of the account finished, see `start_io_for_all_accounts()`.
+    #[serde(rename_all = "camelCase")]
+    IoStarted { account_id: u32 },
+
+    /// Progress of moving the blob directory, see `move_blobdir()`.
+    ///
+    /// @param progress 0=error, 1-999=progress in permille, 1000=success and done
+    #[serde(rename_all = "camelCase")]
+    BlobdirMoveProgress { account_id: u32, progress: usize },
+}
+
+impl From<CoreAccountsEvent> for AccountsEvent {
+    fn from(event: CoreAccountsEvent) -> Self {
+        match event {
+            CoreAccountsEvent::AccountAdded(account_id) => {
+                AccountsEvent::AccountAdded { account_id }
+            }
+            CoreAccountsEvent::AccountRemoved(account_id) => {
+                AccountsEvent::AccountRemoved { account_id }
+            }
+            CoreAccountsEvent::IoStarting(account_id) => AccountsEvent::IoStarting { account_id },
+            CoreAccountsEvent::IoStarted(account_id) => AccountsEvent::IoStarted { account_id },
+            CoreAccountsEvent::BlobdirMoveProgress {
+                account_id,
+                progress,
+            } => AccountsEvent::BlobdirMoveProgress {
+                account_id,
+                progress,
+            },
+        }
+    }
+}
+
+/// What to drop if the event queue is full.
+#[derive(Clone, Copy, Deserialize, TypeDef, JsonSchema)]
+pub enum OverflowPolicy {
+    /// Drop the oldest events.
+    DropOldest,
+    /// Drop informational events such as `Info` and `Warning` first.
+    DropLowPriority,
+}
+
+impl OverflowPolicy {
+    pub fn into_core_type(self) -> CoreOverflowPolicy {
+        match self {
+            OverflowPolicy::DropOldest => CoreOverflowPolicy::DropOldest,
+            OverflowPolicy::DropLowPriority => CoreOverflowPolicy::DropLowPriority,
+        }
+    }
+}
+
+/// Event types delivered to the client by `get_next_event`.
+///
+/// Events are filtered before they are converted and serialized,
+/// so unsubscribed events are cheap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum EventFilter {
+    /// All events except the listed types are delivered.
+    AllExcept(BTreeSet<String>),
+
+    /// Only the listed event types are delivered.
+    Only(BTreeSet<String>),
+}
+
+impl Default for EventFilter {
+    fn default() -> Self {
+        EventFilter::AllExcept(BTreeSet::new())
+    }
+}
+
+impl EventFilter {
+    /// Creates a filter delivering only the given event types,
+    /// or all events if `event_types` is `None`.
+    pub(crate) fn new(event_types: Option<Vec<String>>) -> Result<Self> {
+        match event_types {
+            Some(event_types) => {
+                check_event_type_names(&event_types)?;
+                Ok(EventFilter::Only(event_types.into_iter().collect()))
+            }
+            None => Ok(EventFilter::default()),
+        }
+    }
+
+    /// Starts delivering the given event types.
+    pub(crate) fn subscribe(&mut self, event_types: Vec<String>) -> Result<()> {
+        check_event_type_names(&event_types)?;
+        match self {
+            EventFilter::AllExcept(excluded) => {
+                for event_type in &event_types {
+                    excluded.remove(event_type);
+                }
+            }
+            EventFilter::Only(included) => included.extend(event_types),
+        }
+        Ok(())
+    }
+
+    /// Stops delivering the given event types.
+    pub(crate) fn unsubscribe(&mut self, event_types: Vec<String>) -> Result<()> {
+        check_event_type_names(&event_types)?;
+        match self {
+            EventFilter::AllExcept(excluded) => excluded.extend(event_types),
+            EventFilter::Only(included) => {
+                for event_type in &event_types {
+                    included.remove(event_type);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the event should be delivered.
+    pub(crate) fn matches(&self, event: &CoreEventType) -> bool {
+        match self {
+            EventFilter::AllExcept(excluded) => !excluded.contains(event.name()),
+            EventFilter::Only(included) => included.contains(event.name()),
+        }
+    }
+}
+
+fn check_event_type_names(event_types: &[String]) -> Result<()> {
+    for event_type in event_types {
+        ensure!(
+            CoreEventType::names().contains(&event_type.as_str()),
+            "unknown event type {event_type:?}"
+        );
+    }
+    Ok(())
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(tag = "type")]
 pub enum EventType {
     /// The library-user may write an informational string to the log.
@@ -165,6 +360,15 @@ pub enum EventType {
         msg_id: u32,
     },
 
+    /// Sending a message was given up because of a permanent error
+    /// or because the number of attempts exceeded `smtp_max_attempts`.
+    /// The message will not be retried, the error is available as `Message.error`.
+    #[serde(rename_all = "camelCase")]
+    MsgGivenUp {
+        chat_id: u32,
+        msg_id: u32,
+    },
+
     /// A single message is read by the receiver. State changed from DC_STATE_OUT_DELIVERED to
     /// DC_STATE_OUT_MDN_RCVD, see `Message.state`.
     #[serde(rename_all = "camelCase")]
@@ -242,6 +446,25 @@ pub enum EventType {
         path: String,
     },
 
+    /// Inform about the database maintenance progress started by run_db_maintenance().
+    ///
+    /// 0=error, 1-999=progress in permille, 1000=success and done
+    #[serde(rename_all = "camelCase")]
+    DbMaintenanceProgress {
+        progress: usize,
+    },
+
+    /// Housekeeping finished, see run_housekeeping().
+    #[serde(rename_all = "camelCase")]
+    HousekeepingDone {
+        /// Number of messages whose media was deleted according to the media cleanup policy.
+        deleted_msgs: usize,
+        /// Number of unused files removed from the blob directory.
+        removed_files: usize,
+        /// Number of bytes of the removed files.
+        freed_bytes: u64,
+    },
+
     /// Progress information of a secure-join handshake from the view of the inviter
     /// (Alice, the person who shows the QR code).
     ///
@@ -282,6 +505,23 @@ pub enum EventType {
 
     SelfavatarChanged,
 
+    /// The mailbox usage crossed one of the quota warning thresholds
+    /// set by the config options `quota_warn_threshold` and `quota_error_threshold`.
+    /// See getQuotaUsage() for details.
+    #[serde(rename_all = "camelCase")]
+    QuotaWarning {
+        usage_percentage: u64,
+    },
+
+    /// An OAuth2 access token is needed, see setOauth2TokenRequests().
+    /// The UI should obtain a token for `addr` and pass it to setOauth2AccessToken().
+    #[serde(rename_all = "camelCase")]
+    Oauth2TokenRequested {
+        addr: String,
+        /// True if the previous token was rejected and a fresh one is needed.
+        regenerate: bool,
+    },
+
     #[serde(rename_all = "camelCase")]
     WebxdcStatusUpdate {
         msg_id: u32,
@@ -293,6 +533,22 @@ pub enum EventType {
     WebxdcInstanceDeleted {
         msg_id: u32,
     },
+
+    /// Data received over the joined realtime channel of a webxdc instance.
+    /// See joinWebxdcRealtimeChannel().
+    #[serde(rename_all = "camelCase")]
+    WebxdcRealtimeData {
+        msg_id: u32,
+        data: Vec<u8>,
+    },
+
+    /// Events were dropped because the client did not receive events
+    /// and the event queue was full.
+    /// Returned before the events remaining in the queue.
+    /// See setEventQueueConfig().
+    EventQueueOverflow {
+        dropped: usize,
+    },
 }
 
 impl From<CoreEventType> for EventType {
@@ -342,6 +598,10 @@ fn from(event: CoreEventType) -> Self {
                 chat_id: chat_id.to_u32(),
                 msg_id: msg_id.to_u32(),
             },
+            CoreEventType::MsgGivenUp { chat_id, msg_id } => MsgGivenUp {
+                chat_id: chat_id.to_u32(),
+                msg_id: msg_id.to_u32(),
+            },
             CoreEventType::MsgRead { chat_id, msg_id } => MsgRead {
                 chat_id: chat_id.to_u32(),
                 msg_id: msg_id.to_u32(),
@@ -368,6 +628,16 @@ fn from(event: CoreEventType) -> Self {
             CoreEventType::ImexFileWritten(path) => ImexFileWritten {
                 path: path.to_str().unwrap_or_default().to_owned(),
             },
+            CoreEventType::DbMaintenanceProgress(progress) => DbMaintenanceProgress { progress },
+            CoreEventType::HousekeepingDone {
+                deleted_msgs,
+                removed_files,
+                freed_bytes,
+            } => HousekeepingDone {
+                deleted_msgs,
+                removed_files,
+                freed_bytes,
+            },
             CoreEventType::SecurejoinInviterProgress {
                 contact_id,
                 progress,
@@ -384,6 +654,10 @@ fn from(event: CoreEventType) -> Self {
             },
             CoreEventType::ConnectivityChanged => ConnectivityChanged,
             CoreEventType::SelfavatarChanged => SelfavatarChanged,
+            CoreEventType::QuotaWarning { usage_percentage } => QuotaWarning { usage_percentage },
+            CoreEventType::Oauth2TokenRequested { addr, regenerate } => {
+                Oauth2TokenRequested { addr, regenerate }
+            }
             CoreEventType::WebxdcStatusUpdate {
                 msg_id,
                 status_update_serial,
@@ -394,6 +668,50 @@ fn from(event: CoreEventType) -> Self {
             CoreEventType::WebxdcInstanceDeleted { msg_id } => WebxdcInstanceDeleted {
                 msg_id: msg_id.to_u32(),
             },
+            CoreEventType::WebxdcRealtimeData { msg_id, data } => WebxdcRealtimeData {
+                msg_id: msg_id.to_u32(),
+                data,
+            },
+            CoreEventType::EventQueueOverflow { dropped } => EventQueueOverflow { dropped },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use deltachat::chat::ChatId;
+
+    use super::*;
+
+    #[test]
+    fn test_event_filter() -> Result<()> {
+        let idle = CoreEventType::ImapInboxIdle;
+        let noticed = CoreEventType::MsgsNoticed(ChatId::new(10));
+
+        let mut filter = EventFilter::default();
+        assert!(filter.matches(&idle));
+        assert!(filter.matches(&noticed));
+
+        filter.unsubscribe(vec!["ImapInboxIdle".to_string()])?;
+        assert!(!filter.matches(&idle));
+        assert!(filter.matches(&noticed));
+        filter.subscribe(vec!["ImapInboxIdle".to_string()])?;
+        assert!(filter.matches(&idle));
+
+        let mut filter = EventFilter::new(Some(vec!["MsgsNoticed".to_string()]))?;
+        assert!(!filter.matches(&idle));
+        assert!(filter.matches(&noticed));
+        filter.subscribe(vec!["ImapInboxIdle".to_string()])?;
+        assert!(filter.matches(&idle));
+        filter.unsubscribe(vec!["MsgsNoticed".to_string()])?;
+        assert!(!filter.matches(&noticed));
+
+        assert!(EventFilter::new(Some(vec!["NoSuchEvent".to_string()])).is_err());
+        assert!(filter.subscribe(vec!["NoSuchEvent".to_string()]).is_err());
+        assert_eq!(
+            filter,
+            EventFilter::Only(["ImapInboxIdle".to_string()].into())
+        );
+        Ok(())
+    }
+}
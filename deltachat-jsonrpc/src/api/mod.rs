@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use std::path::Path;
 use std::sync::Arc;
 use std::{collections::HashMap, str::FromStr};
 
@@ -15,11 +16,12 @@
     config::Config,
     constants::DC_MSG_ID_DAYMARKER,
     contact::{may_be_valid_addr, Contact, ContactId, Origin},
-    context::get_info,
+    context::{get_capabilities, get_info},
     ephemeral::Timer,
     imex, location,
     message::{
-        self, delete_msgs, get_msg_info, markseen_msgs, Message, MessageState, MsgId, Viewtype,
+        self, delete_msgs, get_msg_info, markseen_msgs, star_msgs, Message, MessageState, MsgId,
+        Viewtype,
     },
     provider::get_provider_info,
     qr,
@@ -28,38 +30,74 @@
     securejoin,
     stock_str::StockMessage,
     webxdc::StatusUpdateSerial,
+    EventEmitter,
 };
 use sanitize_filename::is_sanitized;
 use tokio::fs;
 use tokio::sync::{watch, Mutex, RwLock};
 use walkdir::WalkDir;
-use yerpc::rpc;
+use yerpc::{rpc, RpcServer};
 
+mod blob_tokens;
 pub mod events;
+pub mod scope;
 pub mod types;
 
 use num_traits::FromPrimitive;
-use types::account::Account;
+use types::account::{Account, AccountCallResult, JSONRPCFreshMsgCount};
+use types::background_fetch::JSONRPCBackgroundFetchSummary;
+use types::certificate_pin::{JSONRPCCertificatePin, JSONRPCPinKind};
 use types::chat::FullChat;
+use types::connectivity::{JSONRPCConnectionInfo, JSONRPCNetworkClass};
 use types::contact::ContactObject;
+use types::database::{
+    JSONRPCBlobGcReport, JSONRPCColdStoredMessage, JSONRPCDbCheckReport, JSONRPCHousekeepingStats,
+    JSONRPCMigrationPlan,
+};
+use types::diagnostics::JSONRPCDiagnosticsStep;
 use types::http::HttpResponse;
+use types::log::{JSONRPCLogEntry, JSONRPCLogLevel};
 use types::message::MessageData;
 use types::message::MessageObject;
+use types::metrics::JSONRPCMetrics;
 use types::provider_info::ProviderInfo;
+use types::proxy::JSONRPCProxyConfig;
+use types::push::JSONRPCPushState;
+use types::quiet_hours::JSONRPCQuietHours;
+use types::quota::JSONRPCQuotaResourceUsage;
+use types::storage::{JSONRPCMediaCleanupPolicy, JSONRPCMediaCleanupReport, JSONRPCStorageUsage};
+use types::traffic::JSONRPCTrafficStats;
 use types::webxdc::WebxdcMessageInfo;
 
-use self::events::Event;
+use self::blob_tokens::BlobTokens;
+use self::events::{AccountsEvent, Event, EventFilter, EventHistoryEntry, OverflowPolicy};
 use self::types::message::MessageLoadResult;
 use self::types::{
     chat::{BasicChat, JSONRPCChatVisibility, MuteDuration},
     location::JsonrpcLocation,
     message::{
-        JSONRPCMessageListItem, MessageNotificationInfo, MessageSearchResult, MessageViewtype,
+        JSONRPCMessageListItem, MessageNotificationInfo, MessageSearchMatch, MessageSearchResult,
+        MessageViewtype, NotificationGroup,
     },
 };
 use crate::api::types::chat_list::{get_chat_list_item_by_id, ChatListItemFetchResult};
 use crate::api::types::qr::QrObject;
 
+/// Maximum number of bytes returned by [`CommandApi::download_blob`].
+const MAX_BLOB_CHUNK_SIZE: u32 = 1024 * 1024;
+
+/// Methods running as the ongoing process of the account given as first parameter,
+/// see [`CommandApi::cancel_ongoing_request`].
+const ONGOING_PROCESS_METHODS: &[&str] = &[
+    "configure",
+    "export_self_keys",
+    "import_self_keys",
+    "export_backup",
+    "import_backup",
+    "provide_backup",
+    "get_backup",
+];
+
 #[derive(Debug)]
 struct AccountState {
     /// The Qr code for current [`CommandApi::provide_backup`] call.
@@ -83,6 +121,12 @@ pub struct CommandApi {
     pub(crate) accounts: Arc<RwLock<Accounts>>,
 
     states: Arc<Mutex<BTreeMap<u32, AccountState>>>,
+
+    /// Event types returned by [`CommandApi::get_next_event`].
+    event_filter: Arc<RwLock<EventFilter>>,
+
+    /// Tokens of the blob server, `None` if there is no blob server.
+    blob_tokens: Arc<Mutex<Option<BlobTokens>>>,
 }
 
 impl CommandApi {
@@ -90,17 +134,74 @@ pub fn new(accounts: Accounts) -> Self {
         CommandApi {
             accounts: Arc::new(RwLock::new(accounts)),
             states: Arc::new(Mutex::new(BTreeMap::new())),
+            event_filter: Default::default(),
+            blob_tokens: Default::default(),
         }
     }
 
-    #[allow(dead_code)]
     pub fn from_arc(accounts: Arc<RwLock<Accounts>>) -> Self {
         CommandApi {
             accounts,
             states: Arc::new(Mutex::new(BTreeMap::new())),
+            event_filter: Default::default(),
+            blob_tokens: Default::default(),
+        }
+    }
+
+    /// Enables [`CommandApi::get_message_blob_url`]
+    /// for a blob server listening on `base_url`.
+    pub async fn enable_blob_server(&self, base_url: String) {
+        *self.blob_tokens.lock().await = Some(BlobTokens::new(base_url));
+    }
+
+    /// Returns account ID and message ID of a blob server token,
+    /// `None` if the token is unknown or expired.
+    pub async fn resolve_blob_token(&self, token: &str) -> Option<(u32, u32)> {
+        self.blob_tokens.lock().await.as_mut()?.resolve(token)
+    }
+
+    /// Stops the ongoing process started by a request, if the method runs as one.
+    ///
+    /// Returns `false` if the method does not run as an ongoing process,
+    /// such requests have to be aborted by the transport instead.
+    /// Otherwise the request returns an error once the process is stopped.
+    pub async fn cancel_ongoing_request(
+        &self,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> Result<bool> {
+        if !ONGOING_PROCESS_METHODS.contains(&method) {
+            return Ok(false);
+        }
+        let account_id = params
+            .get(0)
+            .and_then(|id| id.as_u64())
+            .and_then(|id| u32::try_from(id).ok())
+            .context("missing account ID")?;
+        self.get_context(account_id).await?.stop_ongoing().await;
+        Ok(true)
+    }
+
+    /// Returns the account manager.
+    pub fn accounts(&self) -> Arc<RwLock<Accounts>> {
+        self.accounts.clone()
+    }
+
+    /// Receives the next event matching the event filter.
+    ///
+    /// Returns `None` if the event channel is closed.
+    /// Used by [`CommandApi::get_next_event`] and by transports pushing events to clients.
+    pub async fn recv_event(&self, event_emitter: &EventEmitter) -> Option<Event> {
+        loop {
+            let event = event_emitter.recv().await?;
+            if self.event_filter.read().await.matches(&event.typ) {
+                return Some(event.into());
+            }
         }
     }
 
+    /// Returns the context of the account,
+    /// opening its database on first access, see [`deltachat::context::Context::open_lazily`].
     async fn get_context(&self, id: u32) -> Result<deltachat::context::Context> {
         let sc = self
             .accounts
@@ -108,6 +209,7 @@ async fn get_context(&self, id: u32) -> Result<deltachat::context::Context> {
             .await
             .get_account(id)
             .ok_or_else(|| anyhow!("account with id {} not found", id))?;
+        sc.open_lazily().await;
         Ok(sc)
     }
 
@@ -144,7 +246,11 @@ async fn inner_get_backup_qr(&self, account_id: u32) -> Result<Qr> {
     }
 }
 
-#[rpc(all_positional, ts_outdir = "typescript/generated")]
+#[rpc(
+    all_positional,
+    ts_outdir = "typescript/generated",
+    openrpc_outdir = "openrpc"
+)]
 impl CommandApi {
     /// Test function.
     async fn sleep(&self, delay: f64) {
@@ -165,14 +271,211 @@ async fn get_system_info(&self) -> BTreeMap<&'static str, String> {
         get_info()
     }
 
+    /// Get the optional features supported by this build of the core,
+    /// e.g. `webxdc` or `tor`, mapped to `true` or `false`.
+    ///
+    /// Missing keys should be treated as `false`.
+    async fn get_capabilities(&self) -> BTreeMap<&'static str, bool> {
+        get_capabilities()
+    }
+
+    /// Get the OpenRPC document describing this API.
+    ///
+    /// Lists all methods with their parameters and results,
+    /// types are described with JSON schemas.
+    /// Can be used to generate clients for languages other than TypeScript.
+    async fn get_openrpc_schema(&self) -> Result<serde_json::Value> {
+        let specification = Self::openrpc_specification()?;
+        Ok(serde_json::from_str(&specification)?)
+    }
+
     /// Get the next event.
+    ///
+    /// Events not matching the filter set with [`CommandApi::set_event_filter`],
+    /// [`CommandApi::subscribe_events`] and [`CommandApi::unsubscribe_events`]
+    /// are skipped.
     async fn get_next_event(&self) -> Result<Event> {
         let event_emitter = self.accounts.read().await.get_event_emitter();
+        self.recv_event(&event_emitter)
+            .await
+            .context("event channel is closed")
+    }
+
+    /// Get the next event of the given account.
+    ///
+    /// Unlike [`CommandApi::get_next_event`], this only returns events of a single account,
+    /// so a client showing each account in its own window can poll events per window.
+    /// Events are still returned by `get_next_event` as well.
+    /// The event filter applies to these events too.
+    async fn get_next_account_event(&self, account_id: u32) -> Result<Event> {
+        let event_emitter = self
+            .accounts
+            .read()
+            .await
+            .get_account_event_emitter(account_id)?;
+        self.recv_event(&event_emitter)
+            .await
+            .context("account event channel is closed")
+    }
+
+    /// Get the next account manager event, i.e. an account addition or removal.
+    async fn get_next_manager_event(&self) -> Result<AccountsEvent> {
+        let event_emitter = self.accounts.read().await.get_manager_emitter();
         event_emitter
             .recv()
             .await
             .map(|event| event.into())
-            .context("event channel is closed")
+            .context("manager event channel is closed")
+    }
+
+    /// Sets the event types returned by `get_next_event`.
+    ///
+    /// Event types are named as the `type` field of events, e.g. `"IncomingMsg"`.
+    /// If `event_types` is `null`, all events are returned, which is the default.
+    async fn set_event_filter(&self, event_types: Option<Vec<String>>) -> Result<()> {
+        *self.event_filter.write().await = EventFilter::new(event_types)?;
+        Ok(())
+    }
+
+    /// Returns the last events emitted by the account, oldest first.
+    ///
+    /// Meant to be attached to bug reports.
+    /// The history is kept in memory only and contains at most 5000 events.
+    async fn get_event_history(&self, account_id: u32) -> Result<Vec<EventHistoryEntry>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .get_event_history()
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Returns the events of the account emitted after the event with the sequence number `seq`,
+    /// oldest first.
+    ///
+    /// Lets a reconnecting client catch up on the events it missed
+    /// instead of reloading its whole state.
+    /// Events are filtered like the ones returned by `get_next_event`.
+    /// Fails if some of the events are not available anymore
+    /// or `seq` is unknown, e.g. because the server was restarted;
+    /// the client has to reload its state then.
+    async fn replay_events(&self, account_id: u32, seq: u64) -> Result<Vec<Event>> {
+        let ctx = self.get_context(account_id).await?;
+        let event_filter = self.event_filter.read().await;
+        Ok(ctx
+            .get_events_since(seq)?
+            .into_iter()
+            .filter(|entry| event_filter.matches(&entry.event))
+            .map(|entry| Event::from_history_entry(account_id, entry))
+            .collect())
+    }
+
+    /// Returns the last captured log entries of the account, oldest first.
+    ///
+    /// Only entries of at least `min_level` are returned.
+    /// If `limit` is set, at most `limit` newest entries are returned.
+    /// The log is kept in memory and contains at most 10000 entries.
+    async fn get_log_entries(
+        &self,
+        account_id: u32,
+        min_level: JSONRPCLogLevel,
+        limit: Option<usize>,
+    ) -> Result<Vec<JSONRPCLogEntry>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .get_log_entries(min_level.into_core_type(), limit)
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Sets the minimum level of log entries captured for the account.
+    ///
+    /// Does not affect emitted events.
+    /// The level is reset to `Info` when the account is opened again.
+    async fn set_log_level(&self, account_id: u32, level: JSONRPCLogLevel) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_log_level(level.into_core_type());
+        Ok(())
+    }
+
+    /// Returns the minimum level of log entries captured for the account.
+    async fn get_log_level(&self, account_id: u32) -> Result<JSONRPCLogLevel> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_log_level().into())
+    }
+
+    /// Starts or stops recording tracing spans for profiling.
+    ///
+    /// IMAP, SMTP and message receiving functions are instrumented with spans.
+    /// Recording is done for all accounts of the process.
+    /// Starting the recording clears previously recorded spans.
+    async fn set_span_recording(&self, enabled: bool) -> Result<()> {
+        if enabled {
+            deltachat::profiling::start_span_recording()
+        } else {
+            deltachat::profiling::stop_span_recording();
+            Ok(())
+        }
+    }
+
+    /// Returns the recorded spans in the Chrome trace event JSON format.
+    ///
+    /// The result can be saved to a file and opened in `chrome://tracing`
+    /// or <https://ui.perfetto.dev>.
+    async fn get_recorded_spans(&self) -> Result<String> {
+        deltachat::profiling::get_chrome_trace()
+    }
+
+    /// Sets the time window in milliseconds in which repeated `MsgsChanged`
+    /// and `ChatModified` events for the same chat are collapsed into one.
+    ///
+    /// The first event is returned right away, the following ones are collapsed
+    /// into a single event returned at the end of the window.
+    /// 0, the default, disables coalescing.
+    async fn set_event_coalescing_window(&self, window_ms: u32) {
+        self.accounts
+            .read()
+            .await
+            .set_event_coalescing_window(std::time::Duration::from_millis(window_ms.into()));
+    }
+
+    /// Sets how many events are kept while the client does not call `get_next_event`
+    /// and which events are dropped if there are more.
+    ///
+    /// Without `account_id`, the queue of `get_next_event` is configured,
+    /// otherwise the queue of `get_next_account_event` for the given account.
+    /// By default, 1000 events are kept and the oldest ones are dropped.
+    /// If events were dropped, an `EventQueueOverflow` event is returned
+    /// before the remaining events.
+    async fn set_event_queue_config(
+        &self,
+        account_id: Option<u32>,
+        capacity: u32,
+        overflow_policy: OverflowPolicy,
+    ) -> Result<()> {
+        let config = deltachat::EventQueueConfig {
+            capacity: capacity.try_into()?,
+            overflow_policy: overflow_policy.into_core_type(),
+        };
+        let accounts = self.accounts.read().await;
+        match account_id {
+            Some(account_id) => accounts.set_account_event_queue_config(account_id, config),
+            None => {
+                accounts.set_event_queue_config(config);
+                Ok(())
+            }
+        }
+    }
+
+    /// Starts returning the given event types from `get_next_event`.
+    async fn subscribe_events(&self, event_types: Vec<String>) -> Result<()> {
+        self.event_filter.write().await.subscribe(event_types)
+    }
+
+    /// Stops returning the given event types from `get_next_event`.
+    async fn unsubscribe_events(&self, event_types: Vec<String>) -> Result<()> {
+        self.event_filter.write().await.unsubscribe(event_types)
     }
 
     // ---------------------------------------------
@@ -193,6 +496,37 @@ async fn remove_account(&self, account_id: u32) -> Result<()> {
         Ok(())
     }
 
+    /// Moves the blob directory of the account to `path`, e.g. to an SD card.
+    ///
+    /// `path` must not exist or be empty and IO of the account must be stopped.
+    /// Progress is reported with `BlobdirMoveProgress` events.
+    /// Encrypted accounts have to be opened again afterwards.
+    async fn move_blobdir(&self, account_id: u32, path: String) -> Result<()> {
+        self.accounts
+            .write()
+            .await
+            .move_blobdir(account_id, path.into())
+            .await
+    }
+
+    /// Returns the schema migrations which run when the database of the account is opened,
+    /// without opening it, e.g. to warn before upgrading across many versions.
+    ///
+    /// `passphrase` is only needed for encrypted accounts.
+    async fn get_migration_plan(
+        &self,
+        account_id: u32,
+        passphrase: Option<String>,
+    ) -> Result<JSONRPCMigrationPlan> {
+        Ok(self
+            .accounts
+            .read()
+            .await
+            .get_migration_plan(account_id, passphrase.unwrap_or_default())
+            .await?
+            .into())
+    }
+
     async fn get_all_account_ids(&self) -> Vec<u32> {
         self.accounts.read().await.get_all()
     }
@@ -209,18 +543,73 @@ async fn get_selected_account_id(&self) -> Option<u32> {
         self.accounts.read().await.get_selected_account_id()
     }
 
+    /// Opens the database of the selected account in the background,
+    /// so the first request for it does not have to wait.
+    async fn prewarm_selected_account(&self) {
+        self.accounts.read().await.prewarm_selected_account()
+    }
+
     /// Get a list of all configured accounts.
     async fn get_all_accounts(&self) -> Result<Vec<Account>> {
         let mut accounts = Vec::new();
         for id in self.accounts.read().await.get_all() {
             let context_option = self.accounts.read().await.get_account(id);
             if let Some(ctx) = context_option {
+                ctx.open_lazily().await;
                 accounts.push(Account::from_context(&ctx, id).await?)
             }
         }
         Ok(accounts)
     }
 
+    /// Returns the number of fresh messages in all open accounts
+    /// and the number for each account, e.g. for the app badge.
+    ///
+    /// Messages in muted chats are not counted, see `get_fresh_msgs()`.
+    async fn get_fresh_msg_count_all_accounts(&self) -> Result<JSONRPCFreshMsgCount> {
+        let accounts = self.accounts.read().await.get_fresh_msg_count().await?;
+        Ok(JSONRPCFreshMsgCount {
+            total: accounts.values().sum(),
+            accounts,
+        })
+    }
+
+    /// Calls a read-only method for each account and returns the results by account ID,
+    /// e.g. to get the fresh messages or the connectivity of all accounts in a single request.
+    ///
+    /// `params` are the parameters of the method without the leading account ID.
+    /// Only methods whose first parameter is the account ID
+    /// and which do not change anything are allowed, e.g. `get_fresh_msgs`.
+    /// The method is called for all accounts concurrently,
+    /// an error of one account does not fail the whole call.
+    async fn call_for_all_accounts(
+        &self,
+        method: String,
+        params: Vec<serde_json::Value>,
+    ) -> Result<BTreeMap<u32, AccountCallResult>> {
+        ensure!(
+            scope::is_account_query(&method),
+            "{method} can not be called for all accounts"
+        );
+        let account_ids = self.accounts.read().await.get_all();
+        let calls = account_ids.into_iter().map(|account_id| {
+            let mut account_params = vec![serde_json::Value::from(account_id)];
+            account_params.extend(params.iter().cloned());
+            let method = method.clone();
+            async move {
+                let result = match self
+                    .handle_request(method, serde_json::Value::Array(account_params))
+                    .await
+                {
+                    Ok(result) => AccountCallResult::Ok { result },
+                    Err(err) => AccountCallResult::Error { error: err.message },
+                };
+                (account_id, result)
+            }
+        });
+        Ok(futures::future::join_all(calls).await.into_iter().collect())
+    }
+
     async fn start_io_for_all_accounts(&self) -> Result<()> {
         self.accounts.read().await.start_io().await;
         Ok(())
@@ -231,6 +620,32 @@ async fn stop_io_for_all_accounts(&self) -> Result<()> {
         Ok(())
     }
 
+    /// Sets the proxy used by the account, `null` to connect directly.
+    ///
+    /// Each account may use a different proxy.
+    async fn set_account_proxy(
+        &self,
+        account_id: u32,
+        proxy: Option<JSONRPCProxyConfig>,
+    ) -> Result<()> {
+        let proxy = proxy.map(|proxy| proxy.into_core_type());
+        self.accounts
+            .read()
+            .await
+            .set_proxy(account_id, proxy.as_ref())
+            .await
+    }
+
+    /// Returns the proxy effectively used by each open account,
+    /// `null` for accounts connecting directly.
+    async fn get_account_proxies(&self) -> Result<BTreeMap<u32, Option<JSONRPCProxyConfig>>> {
+        let proxies = self.accounts.read().await.get_proxies().await?;
+        Ok(proxies
+            .into_iter()
+            .map(|(id, proxy)| (id, proxy.map(Into::into)))
+            .collect())
+    }
+
     // ---------------------------------------------
     // Methods that work on individual accounts
     // ---------------------------------------------
@@ -247,10 +662,30 @@ async fn stop_io(&self, id: u32) -> Result<()> {
         Ok(())
     }
 
+    /// Enables or disables the maintenance mode of the account.
+    ///
+    /// In maintenance mode all networking of the account is disabled,
+    /// e.g. for tools migrating, exporting or repairing the database.
+    /// Enabling it stops IO, `start_io` does nothing while it is enabled
+    /// and methods needing the network, e.g. `configure`, fail.
+    /// The maintenance mode is not persisted.
+    async fn set_maintenance_mode(&self, account_id: u32, enabled: bool) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_maintenance_mode(enabled).await;
+        Ok(())
+    }
+
+    /// Returns true if the maintenance mode of the account is enabled.
+    async fn is_maintenance_mode(&self, account_id: u32) -> Result<bool> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.is_maintenance_mode())
+    }
+
     /// Get top-level info for an account.
     async fn get_account_info(&self, account_id: u32) -> Result<Account> {
         let context_option = self.accounts.read().await.get_account(account_id);
         if let Some(ctx) = context_option {
+            ctx.open_lazily().await;
             Ok(Account::from_context(&ctx, account_id).await?)
         } else {
             Err(anyhow!(
@@ -314,6 +749,21 @@ async fn set_config(&self, account_id: u32, key: String, value: Option<String>)
         set_config(&ctx, &key, value.as_deref()).await
     }
 
+    /// Request OAuth2 access tokens from the UI using `Oauth2TokenRequested` events
+    /// instead of using the built-in authorization flow.
+    async fn set_oauth2_token_requests(&self, account_id: u32, enabled: bool) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_oauth2_token_requests(enabled);
+        Ok(())
+    }
+
+    /// Answer an `Oauth2TokenRequested` event.
+    /// `null` or an empty string means that no token could be obtained.
+    async fn set_oauth2_access_token(&self, account_id: u32, token: Option<String>) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_oauth2_access_token(token.as_deref()).await
+    }
+
     async fn batch_set_config(
         &self,
         account_id: u32,
@@ -398,6 +848,10 @@ async fn stop_ongoing_process(&self, account_id: u32) -> Result<()> {
         Ok(())
     }
 
+    /// Exports public and private keys of the account into the directory `path`.
+    ///
+    /// Progress is reported with `ImexProgress` events, see `export_backup`.
+    /// Each written key file is reported with an `ImexFileWritten` event.
     async fn export_self_keys(
         &self,
         account_id: u32,
@@ -414,6 +868,11 @@ async fn export_self_keys(
         .await
     }
 
+    /// Imports secret keys from the `.asc` files in the directory `path`.
+    ///
+    /// The last imported key becomes the default key
+    /// unless its file name contains "legacy".
+    /// Progress is reported with `ImexProgress` events, see `export_backup`.
     async fn import_self_keys(
         &self,
         account_id: u32,
@@ -505,6 +964,26 @@ async fn wait_next_msgs(&self, account_id: u32) -> Result<Vec<u32>> {
         Ok(msg_ids)
     }
 
+    /// Waits for messages to be processed by the bot and returns the IDs
+    /// of incoming messages which are not info messages.
+    ///
+    /// Like [`wait_next_msgs`], but skips messages sent by the bot itself
+    /// and info messages, so simple bots can reply to all returned messages.
+    /// If all new messages are skipped, `last_msg_id` is updated
+    /// and an empty list is returned.
+    ///
+    /// [`wait_next_msgs`]: Self::wait_next_msgs
+    async fn wait_next_incoming_msgs(&self, account_id: u32) -> Result<Vec<u32>> {
+        let ctx = self.get_context(account_id).await?;
+        let msg_ids = ctx
+            .wait_next_incoming_msgs()
+            .await?
+            .iter()
+            .map(|msg_id| msg_id.to_u32())
+            .collect();
+        Ok(msg_ids)
+    }
+
     /// Estimate the number of messages that will be deleted
     /// by the set_config()-options `delete_device_after` or `delete_server_after`.
     /// This is typically used to show the estimated impact to the user
@@ -855,6 +1334,24 @@ async fn set_chat_profile_image(
             .await
     }
 
+    /// Set group profile image from image data, e.g. PNG or JPEG, encoded as base64.
+    ///
+    /// Like `set_chat_profile_image`, for clients which cannot pass a file path
+    /// on the machine running the core.
+    async fn set_chat_profile_image_from_bytes(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        image_data: String,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        use base64::{engine::general_purpose, Engine as _};
+        let image_data = general_purpose::STANDARD
+            .decode(image_data)
+            .context("image data is not valid base64")?;
+        chat::set_chat_profile_image_from_bytes(&ctx, ChatId::new(chat_id), &image_data).await
+    }
+
     async fn set_chat_visibility(
         &self,
         account_id: u32,
@@ -868,6 +1365,10 @@ async fn set_chat_visibility(
             .await
     }
 
+    /// Sets the ephemeral timer of the chat in seconds, 0 disables ephemeral messages.
+    ///
+    /// Emits `ChatEphemeralTimerModified`.
+    /// If the chat is promoted, the change is sent to the other members as a system message.
     async fn set_chat_ephemeral_timer(
         &self,
         account_id: u32,
@@ -880,6 +1381,9 @@ async fn set_chat_ephemeral_timer(
             .await
     }
 
+    /// Returns the ephemeral timer of the chat in seconds, 0 if ephemeral messages are disabled.
+    ///
+    /// The timer is also available as `ephemeralTimer` of `FullChat`.
     async fn get_chat_ephemeral_timer(&self, account_id: u32, chat_id: u32) -> Result<u32> {
         let ctx = self.get_context(account_id).await?;
         Ok(ChatId::new(chat_id)
@@ -888,6 +1392,31 @@ async fn get_chat_ephemeral_timer(&self, account_id: u32, chat_id: u32) -> Resul
             .to_u32())
     }
 
+    /// Overrides the `delete_server_after` config option for a chat.
+    /// `null` reverts to the global setting.
+    async fn set_chat_delete_server_after(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        delete_server_after: Option<i64>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id)
+            .set_delete_server_after(&ctx, delete_server_after)
+            .await
+    }
+
+    /// Returns the chat-specific override of the `delete_server_after` config option
+    /// or `null` if the global setting is used for the chat.
+    async fn get_chat_delete_server_after(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<Option<i64>> {
+        let ctx = self.get_context(account_id).await?;
+        ChatId::new(chat_id).get_delete_server_after(&ctx).await
+    }
+
     // for now only text messages, because we only used text messages in desktop thusfar
     async fn add_device_message(
         &self,
@@ -1059,6 +1588,10 @@ async fn get_message(&self, account_id: u32, message_id: u32) -> Result<MessageO
         MessageObject::from_message_id(&ctx, message_id).await
     }
 
+    /// Returns the HTML part of the message, if any.
+    ///
+    /// Whether the message has an HTML part is available as `hasHtml` of the message,
+    /// so the UI can offer to show the full HTML message only if there is one.
     async fn get_message_html(&self, account_id: u32, message_id: u32) -> Result<Option<String>> {
         let ctx = self.get_context(account_id).await?;
         MsgId::new(message_id).get_html(&ctx).await
@@ -1100,6 +1633,50 @@ async fn get_message_notification_info(
         MessageNotificationInfo::from_msg_id(&ctx, MsgId::new(message_id)).await
     }
 
+    /// Returns IDs of messages the user was not notified about yet, oldest first,
+    /// e.g. to post notifications missed while the client was not running.
+    ///
+    /// Messages are added when the `IncomingMsg` event is emitted for them
+    /// and are returned until they are removed with `clear_pending_notifications()`
+    /// or are not fresh anymore.
+    async fn get_pending_notifications(&self, account_id: u32) -> Result<Vec<u32>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .get_pending_notifications()
+            .await?
+            .iter()
+            .map(|msg_id| msg_id.to_u32())
+            .collect())
+    }
+
+    /// Removes messages from the queue of pending notifications
+    /// after notifications were posted for them, all messages if `message_ids` is null.
+    async fn clear_pending_notifications(
+        &self,
+        account_id: u32,
+        message_ids: Option<Vec<u32>>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        let msg_ids: Option<Vec<MsgId>> =
+            message_ids.map(|ids| ids.into_iter().map(MsgId::new).collect());
+        ctx.clear_pending_notifications(msg_ids.as_deref()).await
+    }
+
+    /// Groups messages, usually fresh ones, by chat
+    /// for showing one stacked notification per chat.
+    ///
+    /// Groups are sorted by their newest message, newest first.
+    async fn get_notification_groups(
+        &self,
+        account_id: u32,
+        message_ids: Vec<u32>,
+    ) -> Result<Vec<NotificationGroup>> {
+        let ctx = self.get_context(account_id).await?;
+        let msg_ids: Vec<MsgId> = message_ids.into_iter().map(MsgId::new).collect();
+        let groups = deltachat::notifications::get_notification_groups(&ctx, &msg_ids).await?;
+        Ok(groups.into_iter().map(Into::into).collect())
+    }
+
     /// Delete messages. The messages are deleted on the current device and
     /// on the IMAP server.
     async fn delete_messages(&self, account_id: u32, message_ids: Vec<u32>) -> Result<()> {
@@ -1108,6 +1685,19 @@ async fn delete_messages(&self, account_id: u32, message_ids: Vec<u32>) -> Resul
         delete_msgs(&ctx, &msgs).await
     }
 
+    /// Stars or unstars messages.
+    /// Starred messages are kept when media is deleted automatically.
+    async fn star_messages(
+        &self,
+        account_id: u32,
+        message_ids: Vec<u32>,
+        star: bool,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        let msgs: Vec<MsgId> = message_ids.into_iter().map(MsgId::new).collect();
+        star_msgs(&ctx, &msgs, star).await
+    }
+
     /// Get an informational text for a single message. The text is multiline and may
     /// contain e.g. the raw text of the message.
     ///
@@ -1160,6 +1750,21 @@ async fn search_messages(
             .collect::<Vec<u32>>())
     }
 
+    /// Searches messages containing the given query string in a single chat
+    /// and returns them in chat order together with the byte offsets and lengths
+    /// of the matches in the UTF-8 encoded message text,
+    /// so UIs can highlight them and jump between them.
+    async fn search_chat_messages(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        query: String,
+    ) -> Result<Vec<MessageSearchMatch>> {
+        let ctx = self.get_context(account_id).await?;
+        let found = ctx.search_chat_msgs(ChatId::new(chat_id), &query).await?;
+        Ok(found.into_iter().map(Into::into).collect())
+    }
+
     async fn message_ids_to_search_results(
         &self,
         account_id: u32,
@@ -1221,6 +1826,34 @@ async fn create_chat_by_contact_id(&self, account_id: u32, contact_id: u32) -> R
             .map(|id| id.to_u32())
     }
 
+    /// Creates a group chat with the given members and returns its ID.
+    ///
+    /// Like `create_group_chat` followed by `add_contact_to_chat` for each contact.
+    /// As the new group is unpromoted, no messages are sent.
+    /// If `protect` is true, all contacts must be verified.
+    /// If a contact cannot be added, the group is deleted again.
+    async fn create_chat_by_contact_ids(
+        &self,
+        account_id: u32,
+        name: String,
+        protect: bool,
+        contact_ids: Vec<u32>,
+    ) -> Result<u32> {
+        let ctx = self.get_context(account_id).await?;
+        let protect = match protect {
+            true => ProtectionStatus::Protected,
+            false => ProtectionStatus::Unprotected,
+        };
+        let chat_id = chat::create_group_chat(&ctx, protect, &name).await?;
+        for contact_id in contact_ids {
+            if let Err(err) = add_contact_to_chat(&ctx, chat_id, ContactId::new(contact_id)).await {
+                chat_id.delete(&ctx).await?;
+                return Err(err.context(format!("failed to add contact {contact_id} to new group")));
+            }
+        }
+        Ok(chat_id.to_u32())
+    }
+
     async fn block_contact(&self, account_id: u32, contact_id: u32) -> Result<()> {
         let ctx = self.get_context(account_id).await?;
         Contact::block(&ctx, ContactId::new(contact_id)).await
@@ -1432,9 +2065,91 @@ async fn get_neighboring_chat_media(
     }
 
     // ---------------------------------------------
-    //                   backup
+    //                   blobs
     // ---------------------------------------------
 
+    /// Stores base64-encoded file data in the blob directory of the account.
+    ///
+    /// Returns the path of the new file, which can be used as `file` of `MessageData`
+    /// or passed to other methods expecting a file path.
+    /// Allows clients without access to the file system of the core
+    /// to send attachments.
+    async fn upload_blob(
+        &self,
+        account_id: u32,
+        file_name: String,
+        data: String,
+    ) -> Result<String> {
+        let ctx = self.get_context(account_id).await?;
+        use base64::{engine::general_purpose, Engine as _};
+        let data = general_purpose::STANDARD
+            .decode(data)
+            .context("blob data is not valid base64")?;
+        let path = ctx.create_blob(&file_name, &data).await?;
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    /// Returns up to `length` bytes of a file in the blob directory, starting at `offset`,
+    /// encoded as base64.
+    ///
+    /// `path` is a file path as returned in the `file` field of a message.
+    /// At most 1 MiB is returned at once, so large files are downloaded in chunks
+    /// until an empty string is returned at the end of the file.
+    async fn download_blob(
+        &self,
+        account_id: u32,
+        path: String,
+        offset: u64,
+        length: u32,
+    ) -> Result<String> {
+        let ctx = self.get_context(account_id).await?;
+        let data = ctx
+            .read_blob_chunk(
+                Path::new(&path),
+                offset,
+                length.min(MAX_BLOB_CHUNK_SIZE).into(),
+            )
+            .await?;
+        use base64::{engine::general_purpose, Engine as _};
+        Ok(general_purpose::STANDARD.encode(data))
+    }
+
+    /// Returns the URL of the message attachment on the blob server.
+    ///
+    /// The URL contains an unguessable token and supports range requests,
+    /// so frontends can use it in `<img>` and `<video>` elements.
+    /// Fails if the message has no attachment or there is no blob server,
+    /// `deltachat-rpc-server` runs a blob server if `DC_BLOB_ADDR` is set.
+    async fn get_message_blob_url(&self, account_id: u32, message_id: u32) -> Result<String> {
+        let ctx = self.get_context(account_id).await?;
+        let msg = Message::load_from_db(&ctx, MsgId::new(message_id)).await?;
+        ensure!(
+            msg.get_file(&ctx).is_some(),
+            "message {message_id} has no attachment"
+        );
+        self.blob_tokens
+            .lock()
+            .await
+            .as_mut()
+            .context("blob server is not enabled")
+            .map(|blob_tokens| blob_tokens.get_url(account_id, message_id))
+    }
+
+    // ---------------------------------------------
+    //                   backup
+    // ---------------------------------------------
+
+    /// Exports a backup of the account into the directory `destination`.
+    ///
+    /// If `passphrase` is set, the backup is encrypted with it.
+    ///
+    /// Progress is reported with `ImexProgress` events:
+    /// 1 to 999 is the progress in permille, 1000 means done
+    /// and 0 means the export failed or was cancelled.
+    /// The written backup file is reported with an `ImexFileWritten` event.
+    ///
+    /// The export can be cancelled with `stop_ongoing_process`
+    /// or by cancelling the request, the method then returns an error.
     async fn export_backup(
         &self,
         account_id: u32,
@@ -1451,6 +2166,11 @@ async fn export_backup(
         .await
     }
 
+    /// Imports the backup file `path` into the unconfigured account.
+    ///
+    /// `passphrase` is needed if the backup was encrypted.
+    /// Progress is reported with `ImexProgress` events, see `export_backup`.
+    /// The import can be cancelled with `stop_ongoing_process` or by cancelling the request.
     async fn import_backup(
         &self,
         account_id: u32,
@@ -1536,6 +2256,161 @@ async fn get_backup(&self, account_id: u32, qr_text: String) -> Result<()> {
         Ok(())
     }
 
+    // ---------------------------------------------
+    //                  database
+    // ---------------------------------------------
+
+    /// Optimizes the database of the account and returns unused space to the file system.
+    ///
+    /// This may take a while for large databases, other database users are blocked meanwhile,
+    /// so UIs should offer it as an explicit action, e.g. "Optimize storage".
+    /// Progress is reported with `DbMaintenanceProgress` events:
+    /// 1 to 999 is the progress in permille, 1000 means done and 0 means it failed.
+    async fn run_db_maintenance(&self, account_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.run_db_maintenance().await
+    }
+
+    /// Returns the time of the last successful `run_db_maintenance`
+    /// as a unix timestamp, null if it never ran.
+    async fn get_last_db_maintenance(&self, account_id: u32) -> Result<Option<i64>> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.get_last_db_maintenance().await
+    }
+
+    /// Checks the database of the account for corruption and inconsistencies
+    /// and returns a report of the problems found.
+    ///
+    /// If `repair` is true, indices are rebuilt and orphaned rows are removed.
+    /// The report still lists the problems found before repairing,
+    /// call `check_database` again to find out whether problems remain.
+    async fn check_database(&self, account_id: u32, repair: bool) -> Result<JSONRPCDbCheckReport> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.check_database(repair).await?.into())
+    }
+
+    /// Runs housekeeping now and returns what was cleaned up.
+    ///
+    /// Housekeeping also runs automatically while IO is running,
+    /// every `housekeeping_interval` seconds unless deferred with `set_housekeeping_deferred`.
+    /// A `HousekeepingDone` event is emitted whenever housekeeping finished.
+    async fn run_housekeeping(&self, account_id: u32) -> Result<JSONRPCHousekeepingStats> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.run_housekeeping().await?.into())
+    }
+
+    /// Looks for files in the blob directory which are not referenced anywhere
+    /// and for references to files which do not exist,
+    /// e.g. to find out why the storage does not shrink.
+    ///
+    /// If `remove` is true, orphaned files not used during the last hour are removed.
+    async fn collect_blob_garbage(
+        &self,
+        account_id: u32,
+        remove: bool,
+    ) -> Result<JSONRPCBlobGcReport> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.collect_blob_garbage(remove).await?.into())
+    }
+
+    /// Exports the database to the new file `path` with message texts, keys, addresses
+    /// and all other texts and binary data replaced,
+    /// so it can be attached to bug reports about performance or synchronization problems.
+    ///
+    /// Structure, sizes and timestamps are kept, the exported database is not encrypted.
+    async fn export_sanitized_database(&self, account_id: u32, path: String) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.export_sanitized_database(Path::new(&path)).await
+    }
+
+    /// Moves messages older than the timestamp `cutoff` to the cold storage database,
+    /// keeping the main database small and the chatlist fast for huge accounts.
+    ///
+    /// Messages still needed, e.g. the last message of each chat, starred or fresh messages,
+    /// are kept. Returns the number of moved messages.
+    async fn move_messages_to_cold_storage(&self, account_id: u32, cutoff: i64) -> Result<usize> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.move_msgs_to_cold_storage(cutoff).await
+    }
+
+    /// Moves the messages of the chat back from cold storage
+    /// and returns the number of restored messages.
+    async fn restore_messages_from_cold_storage(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<usize> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.restore_msgs_from_cold_storage(ChatId::new(chat_id))
+            .await
+    }
+
+    /// Returns the messages of the chat in cold storage, ordered as in the chat.
+    async fn get_cold_stored_messages(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+    ) -> Result<Vec<JSONRPCColdStoredMessage>> {
+        let ctx = self.get_context(account_id).await?;
+        let msgs = ctx.get_cold_stored_msgs(ChatId::new(chat_id)).await?;
+        Ok(msgs.into_iter().map(Into::into).collect())
+    }
+
+    /// Searches messages in cold storage containing the query string,
+    /// in the given chat or in all chats.
+    ///
+    /// As with `search_messages`, the search in all chats is limited to 1000 messages.
+    async fn search_cold_stored_messages(
+        &self,
+        account_id: u32,
+        query: String,
+        chat_id: Option<u32>,
+    ) -> Result<Vec<JSONRPCColdStoredMessage>> {
+        let ctx = self.get_context(account_id).await?;
+        let msgs = ctx
+            .search_cold_stored_msgs(chat_id.map(ChatId::new), &query)
+            .await?;
+        Ok(msgs.into_iter().map(Into::into).collect())
+    }
+
+    /// Defers automatic housekeeping, e.g. while the device is on battery.
+    /// When not deferred anymore, overdue housekeeping runs.
+    ///
+    /// The setting is not persisted.
+    async fn set_housekeeping_deferred(&self, account_id: u32, deferred: bool) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_housekeeping_deferred(deferred).await;
+        Ok(())
+    }
+
+    /// Returns the storage used by the account and by each chat, broken down by view type,
+    /// so users can find out which chats use the most space.
+    ///
+    /// This looks at all messages and files, so it may take a while for large accounts.
+    async fn get_storage_usage(&self, account_id: u32) -> Result<JSONRPCStorageUsage> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_storage_usage().await?.into())
+    }
+
+    /// Deletes media of messages matching `policy` from the device,
+    /// the messages are kept and can be downloaded again as partially downloaded messages.
+    ///
+    /// With `dry_run`, nothing is deleted and the messages whose media would be deleted are returned,
+    /// so the user can confirm before the policy is stored
+    /// in the `media_cleanup_*` config options and applied by housekeeping.
+    async fn cleanup_media(
+        &self,
+        account_id: u32,
+        policy: JSONRPCMediaCleanupPolicy,
+        dry_run: bool,
+    ) -> Result<JSONRPCMediaCleanupReport> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .cleanup_media(&policy.into_core_type(), dry_run)
+            .await?
+            .into())
+    }
+
     // ---------------------------------------------
     //                connectivity
     // ---------------------------------------------
@@ -1547,6 +2422,47 @@ async fn maybe_network(&self) -> Result<()> {
         Ok(())
     }
 
+    /// Fetches new messages for all accounts in parallel,
+    /// giving up on accounts which did not finish within `timeout_in_seconds`.
+    ///
+    /// Meant for background execution on iOS.
+    /// IO is paused while fetching and resumed afterwards if it was running.
+    /// Returns the number of new messages and the messages to notify about for each account,
+    /// so notifications can be posted without further requests.
+    async fn background_fetch(
+        &self,
+        timeout_in_seconds: f64,
+    ) -> Result<BTreeMap<u32, JSONRPCBackgroundFetchSummary>> {
+        ensure!(
+            timeout_in_seconds.is_finite() && timeout_in_seconds >= 0.0,
+            "invalid timeout {timeout_in_seconds}"
+        );
+        let summaries = self
+            .accounts
+            .read()
+            .await
+            .background_fetch(std::time::Duration::from_secs_f64(timeout_in_seconds))
+            .await;
+        Ok(summaries
+            .into_iter()
+            .map(|(id, summary)| (id, summary.into()))
+            .collect())
+    }
+
+    /// Sets the class of the network the device is currently connected to for all accounts.
+    /// Should be called whenever the network changes.
+    ///
+    /// On metered networks and while roaming,
+    /// the `send_limit_metered` and `download_limit_metered_*` options apply,
+    /// while roaming, automatic download is suspended unless `download_on_roaming` is set.
+    async fn set_network_class(&self, network_class: JSONRPCNetworkClass) -> Result<()> {
+        self.accounts
+            .read()
+            .await
+            .set_network_class(network_class.into_core_type())
+            .await
+    }
+
     /// Get the current connectivity, i.e. whether the device is connected to the IMAP server.
     /// One of:
     /// - DC_CONNECTIVITY_NOT_CONNECTED (1000-1999): Show e.g. the string "Not connected" or a red dot
@@ -1580,10 +2496,238 @@ async fn get_connectivity_html(&self, account_id: u32) -> Result<String> {
         ctx.get_connectivity_html().await
     }
 
+    /// Get structured information about each IMAP and SMTP connection
+    /// such as state, last error, last successful sync and number of pending messages.
+    ///
+    /// Meant for UIs building a native connectivity screen
+    /// instead of showing the result of get_connectivity_html().
+    ///
+    /// If the information changes, a #DC_EVENT_CONNECTIVITY_CHANGED will be emitted.
+    async fn get_connectivity_info(&self, account_id: u32) -> Result<Vec<JSONRPCConnectionInfo>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .get_connectivity_info()
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Runs network diagnostics for the account.
+    ///
+    /// Sequentially checks DNS resolution, TCP connection and TLS handshake
+    /// for the IMAP and SMTP server, IMAP login, access to the inbox and SMTP login,
+    /// and returns the result of each step with its duration and error, if any.
+    /// Steps depending on a failed step are reported as skipped.
+    async fn run_network_diagnostics(
+        &self,
+        account_id: u32,
+    ) -> Result<Vec<JSONRPCDiagnosticsStep>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .run_network_diagnostics()
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Returns the number of bytes sent and received by the account
+    /// over IMAP, SMTP and HTTP since the last reset.
+    async fn get_traffic_stats(&self, account_id: u32) -> Result<JSONRPCTrafficStats> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_traffic_stats().await?.into())
+    }
+
+    /// Resets traffic statistics of the account.
+    async fn reset_traffic_stats(&self, account_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.reset_traffic_stats().await
+    }
+
+    /// Returns counters and gauges of the account for monitoring.
+    async fn get_metrics(&self, account_id: u32) -> Result<JSONRPCMetrics> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_metrics().await?.into())
+    }
+
+    /// Returns the metrics of all open accounts in the Prometheus text exposition format.
+    async fn get_metrics_prometheus(&self) -> Result<String> {
+        self.accounts.read().await.get_metrics_prometheus().await
+    }
+
+    /// Returns the usage of all quota resources reported by the server
+    /// or `null` if the quota is not known yet or not supported by the server.
+    ///
+    /// If the information is outdated, an update is scheduled
+    /// and a `ConnectivityChanged` event is emitted once it is done.
+    async fn get_quota_usage(
+        &self,
+        account_id: u32,
+    ) -> Result<Option<Vec<JSONRPCQuotaResourceUsage>>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .get_quota_usage()
+            .await?
+            .map(|usage| usage.into_iter().map(Into::into).collect()))
+    }
+
+    /// Returns TLS certificate pins of the account.
+    async fn get_certificate_pins(&self, account_id: u32) -> Result<Vec<JSONRPCCertificatePin>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .get_certificate_pins()
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Pins the certificate or SPKI of the server with the given hostname,
+    /// replacing existing pin if any.
+    ///
+    /// `sha256` is the hex-encoded SHA-256 hash of the DER-encoded certificate or SPKI.
+    async fn set_certificate_pin(
+        &self,
+        account_id: u32,
+        hostname: String,
+        kind: JSONRPCPinKind,
+        sha256: String,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_certificate_pin(&hostname, kind.into_core_type(), &sha256)
+            .await
+    }
+
+    /// Removes the certificate pin of the server with the given hostname.
+    async fn remove_certificate_pin(&self, account_id: u32, hostname: String) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.remove_certificate_pin(&hostname).await
+    }
+
+    /// Sets the UnifiedPush endpoint provided by the distributor,
+    /// `null` to stop receiving push notifications.
+    ///
+    /// The endpoint is registered on the next connection
+    /// if the server supports push notifications.
+    async fn set_unifiedpush_endpoint(
+        &self,
+        account_id: u32,
+        endpoint: Option<String>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_unifiedpush_endpoint(endpoint.as_deref()).await
+    }
+
+    /// Sets the APNs or FCM device token, `null` to stop receiving push notifications.
+    ///
+    /// The token is registered on the next connection if the server supports push notifications
+    /// and registered again automatically after the account is reconfigured.
+    async fn set_push_device_token(&self, account_id: u32, token: Option<String>) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.set_push_device_token(token.as_deref()).await
+    }
+
+    /// Returns the state of push notifications for the settings screen.
+    async fn get_push_state(&self, account_id: u32) -> Result<JSONRPCPushState> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx.get_push_state().await?.into())
+    }
+
+    /// Sets the quiet hours during which notifications are suppressed,
+    /// replacing the previous ones.
+    ///
+    /// Quiet hours are synchronized to other devices if `send_sync_msgs` is enabled.
+    async fn set_quiet_hours(
+        &self,
+        account_id: u32,
+        quiet_hours: Vec<JSONRPCQuietHours>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        let quiet_hours: Vec<_> = quiet_hours
+            .into_iter()
+            .map(JSONRPCQuietHours::into_core_type)
+            .collect();
+        ctx.set_quiet_hours(&quiet_hours).await
+    }
+
+    /// Returns the quiet hours during which notifications are suppressed.
+    async fn get_quiet_hours(&self, account_id: u32) -> Result<Vec<JSONRPCQuietHours>> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(ctx
+            .get_quiet_hours()
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Returns true if the current local time falls into the quiet hours.
+    async fn is_quiet_time(&self, account_id: u32) -> Result<bool> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.is_quiet_time().await
+    }
+
+    /// Fetches new messages after a push notification was received for the account.
+    async fn handle_push_notification(&self, account_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.handle_push_notification().await;
+        Ok(())
+    }
+
     // ---------------------------------------------
     //                  locations
     // ---------------------------------------------
 
+    /// Enables location streaming to the chat for `seconds` seconds.
+    ///
+    /// 0 disables location streaming to the chat.
+    /// While location streaming is enabled, locations set with `set_location`
+    /// are sent to the chat.
+    /// Enabling and disabling location streaming adds an info message to the chat.
+    async fn send_locations_to_chat(
+        &self,
+        account_id: u32,
+        chat_id: u32,
+        seconds: i64,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        location::send_locations_to_chat(&ctx, ChatId::new(chat_id), seconds).await
+    }
+
+    /// Returns whether locations are streamed to the chat.
+    ///
+    /// Without `chat_id`, returns whether locations are streamed to any chat.
+    async fn is_sending_locations_to_chat(
+        &self,
+        account_id: u32,
+        chat_id: Option<u32>,
+    ) -> Result<bool> {
+        let ctx = self.get_context(account_id).await?;
+        location::is_sending_locations_to_chat(&ctx, chat_id.map(ChatId::new)).await
+    }
+
+    /// Sets the current location of the device.
+    ///
+    /// The location is sent to all chats with location streaming enabled.
+    /// Returns false if location streaming is not enabled for any chat anymore,
+    /// so the client can stop tracking the location.
+    async fn set_location(
+        &self,
+        account_id: u32,
+        latitude: f64,
+        longitude: f64,
+        accuracy: f64,
+    ) -> Result<bool> {
+        let ctx = self.get_context(account_id).await?;
+        Ok(location::set(&ctx, latitude, longitude, accuracy).await)
+    }
+
+    /// Returns the locations of the chat and the contact in the given time range.
+    ///
+    /// Without `chat_id`, locations of all chats are returned,
+    /// without `contact_id` the locations of all contacts.
+    /// `timestamp_end` 0 means no end of the range.
     async fn get_locations(
         &self,
         account_id: u32,
@@ -1606,6 +2750,12 @@ async fn get_locations(
         Ok(locations.into_iter().map(|l| l.into()).collect())
     }
 
+    /// Deletes all locations from the database.
+    async fn delete_all_locations(&self, account_id: u32) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        location::delete_all(&ctx).await
+    }
+
     // ---------------------------------------------
     //                   webxdc
     // ---------------------------------------------
@@ -1636,6 +2786,46 @@ async fn get_webxdc_status_updates(
         .await
     }
 
+    /// Joins the realtime channel of a webxdc instance.
+    /// The app has to request the `Realtime` capability in its manifest.
+    ///
+    /// Data sent by other peers of the channel is emitted as `WebxdcRealtimeData` event.
+    async fn join_webxdc_realtime_channel(
+        &self,
+        account_id: u32,
+        instance_msg_id: u32,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.join_webxdc_realtime_channel(MsgId::new(instance_msg_id))
+            .await
+    }
+
+    /// Leaves the realtime channel of a webxdc instance.
+    async fn leave_webxdc_realtime_channel(
+        &self,
+        account_id: u32,
+        instance_msg_id: u32,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.leave_webxdc_realtime_channel(MsgId::new(instance_msg_id))
+            .await
+    }
+
+    /// Sends data to the peers of the joined realtime channel of a webxdc instance.
+    ///
+    /// Realtime data is not stored and may be lost,
+    /// use sendWebxdcStatusUpdate() for data which has to reach all members.
+    async fn send_webxdc_realtime_data(
+        &self,
+        account_id: u32,
+        instance_msg_id: u32,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let ctx = self.get_context(account_id).await?;
+        ctx.send_webxdc_realtime_data(MsgId::new(instance_msg_id), data)
+            .await
+    }
+
     /// Get info from a webxdc message
     async fn get_webxdc_info(
         &self,
@@ -1689,6 +2879,28 @@ async fn forward_messages(
         forward_msgs(&ctx, &message_ids, ChatId::new(chat_id)).await
     }
 
+    /// Resend own messages with the same Message-ID.
+    ///
+    /// This is primarily intended to make existing webxdcs available to new chat members.
+    /// Only own messages which are delivered or failed can be resent.
+    ///
+    /// Returns the errors of messages which could not be resent, keyed by message ID.
+    /// The other messages are resent.
+    async fn resend_messages(
+        &self,
+        account_id: u32,
+        message_ids: Vec<u32>,
+    ) -> Result<HashMap<u32, String>> {
+        let ctx = self.get_context(account_id).await?;
+        let mut errors = HashMap::new();
+        for message_id in message_ids {
+            if let Err(err) = chat::resend_msgs(&ctx, &[MsgId::new(message_id)]).await {
+                errors.insert(message_id, format!("{err:#}"));
+            }
+        }
+        Ok(errors)
+    }
+
     async fn send_sticker(
         &self,
         account_id: u32,
@@ -1794,6 +3006,12 @@ async fn get_draft(&self, account_id: u32, chat_id: u32) -> Result<Option<Messag
         }
     }
 
+    /// Sends an invitation to a videochat and returns the ID of the sent message.
+    ///
+    /// Creates a unique room for the chat using the `webrtc_instance` config value.
+    /// The returned message has the `videochatUrl` and `videochatType` fields set,
+    /// so the UI can enter the room right away.
+    /// Recipients using other clients get a text with the URL to join.
     async fn send_videochat_invitation(&self, account_id: u32, chat_id: u32) -> Result<u32> {
         let ctx = self.get_context(account_id).await?;
         chat::send_videochat_invitation(&ctx, ChatId::new(chat_id))
@@ -0,0 +1,442 @@
+//! # Scoped sessions.
+//!
+//! A session can be restricted to a [`Scope`], e.g. for a dashboard
+//! or a notification helper, which should not send messages or change settings.
+//! Methods are checked by name before they are dispatched to [`CommandApi`].
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use yerpc::RpcServer;
+
+use super::CommandApi;
+
+/// Methods which do not change anything, allowed in read-only sessions.
+const READ_ONLY_METHODS: &[&str] = &[
+    "sleep",
+    "check_email_validity",
+    "get_system_info",
+    "get_capabilities",
+    "get_openrpc_schema",
+    "get_next_event",
+    "get_next_account_event",
+    "get_next_manager_event",
+    "get_event_history",
+    "replay_events",
+    "get_log_entries",
+    "get_log_level",
+    "get_recorded_spans",
+    "get_all_account_ids",
+    "get_selected_account_id",
+    "get_all_accounts",
+    "get_fresh_msg_count_all_accounts",
+    "call_for_all_accounts",
+    "get_account_proxies",
+    "get_account_info",
+    "get_account_file_size",
+    "get_provider_info",
+    "is_configured",
+    "is_maintenance_mode",
+    "get_info",
+    "check_qr",
+    "get_config",
+    "batch_get_config",
+    "get_fresh_msgs",
+    "get_fresh_msg_cnt",
+    "get_next_msgs",
+    "estimate_auto_deletion_count",
+    "get_chatlist_entries",
+    "get_chatlist_items_by_entries",
+    "get_full_chat_by_id",
+    "get_basic_chat_info",
+    "get_chat_encryption_info",
+    "get_chat_contacts",
+    "get_chat_ephemeral_timer",
+    "get_chat_delete_server_after",
+    "get_first_unread_message_of_chat",
+    "is_chat_muted",
+    "get_message_ids",
+    "get_message_list_items",
+    "get_message",
+    "get_message_html",
+    "get_messages",
+    "get_message_notification_info",
+    "get_pending_notifications",
+    "get_notification_groups",
+    "get_message_info",
+    "search_messages",
+    "message_ids_to_search_results",
+    "get_contact",
+    "get_blocked_contacts",
+    "get_contact_ids",
+    "get_contacts",
+    "get_contacts_by_ids",
+    "get_contact_encryption_info",
+    "lookup_contact_id_by_addr",
+    "get_chat_media",
+    "get_neighboring_chat_media",
+    "download_blob",
+    "get_message_blob_url",
+    "get_connectivity",
+    "get_connectivity_html",
+    "get_connectivity_info",
+    "get_traffic_stats",
+    "get_metrics",
+    "get_metrics_prometheus",
+    "get_quota_usage",
+    "get_certificate_pins",
+    "get_push_state",
+    "get_quiet_hours",
+    "is_quiet_time",
+    "is_sending_locations_to_chat",
+    "get_locations",
+    "get_webxdc_status_updates",
+    "get_webxdc_info",
+    "get_webxdc_blob",
+    "can_send",
+    "get_draft",
+    "misc_get_sticker_folder",
+    "misc_get_stickers",
+    "get_migration_plan",
+    "get_last_db_maintenance",
+    "get_storage_usage",
+    "get_cold_stored_messages",
+    "search_cold_stored_messages",
+    "search_chat_messages",
+];
+
+/// Methods changing data of accounts, e.g. sending messages,
+/// but not the configuration of accounts or of the core.
+///
+/// Together with [`READ_ONLY_METHODS`], these are the only methods
+/// allowed if config changes are not allowed.
+const NO_CONFIG_METHODS: &[&str] = &[
+    "prewarm_selected_account",
+    "stop_ongoing_process",
+    "wait_next_msgs",
+    "wait_next_incoming_msgs",
+    "maybe_network",
+    "background_fetch",
+    "run_network_diagnostics",
+    "handle_push_notification",
+    "get_http_response",
+    "accept_chat",
+    "block_chat",
+    "delete_chat",
+    "get_chat_securejoin_qr_code_svg",
+    "secure_join",
+    "leave_group",
+    "remove_contact_from_chat",
+    "add_contact_to_chat",
+    "create_group_chat",
+    "create_broadcast_list",
+    "add_device_message",
+    "marknoticed_chat",
+    "markseen_msgs",
+    "clear_pending_notifications",
+    "delete_messages",
+    "star_messages",
+    "download_full_message",
+    "create_contact",
+    "create_chat_by_contact_id",
+    "create_chat_by_contact_ids",
+    "block_contact",
+    "unblock_contact",
+    "delete_contact",
+    "change_contact_name",
+    "upload_blob",
+    "send_locations_to_chat",
+    "set_location",
+    "delete_all_locations",
+    "send_webxdc_status_update",
+    "join_webxdc_realtime_channel",
+    "leave_webxdc_realtime_channel",
+    "send_webxdc_realtime_data",
+    "forward_messages",
+    "resend_messages",
+    "send_sticker",
+    "send_reaction",
+    "send_msg",
+    "remove_draft",
+    "send_videochat_invitation",
+    "misc_save_sticker",
+    "misc_send_text_message",
+    "misc_send_msg",
+    "misc_set_draft",
+];
+
+/// Methods without account ID parameter, allowed in single-account sessions.
+const GLOBAL_METHODS: &[&str] = &[
+    "sleep",
+    "check_email_validity",
+    "get_system_info",
+    "get_capabilities",
+    "get_openrpc_schema",
+];
+
+/// Methods affecting several accounts, not allowed in single-account sessions.
+///
+/// All other methods are allowed only if their first parameter is the ID of the account.
+const MULTI_ACCOUNT_METHODS: &[&str] = &[
+    "get_next_event",
+    "get_next_manager_event",
+    "set_event_filter",
+    "subscribe_events",
+    "unsubscribe_events",
+    "set_span_recording",
+    "get_recorded_spans",
+    "set_event_coalescing_window",
+    "set_event_queue_config",
+    "add_account",
+    "get_all_account_ids",
+    "select_account",
+    "get_selected_account_id",
+    "get_all_accounts",
+    "get_fresh_msg_count_all_accounts",
+    "call_for_all_accounts",
+    "start_io_for_all_accounts",
+    "stop_io_for_all_accounts",
+    "get_account_proxies",
+    "set_stock_strings",
+    "maybe_network",
+    "background_fetch",
+    "set_network_class",
+    "get_metrics_prometheus",
+];
+
+/// Returns true if the method only reads data of the account given as first parameter,
+/// so it can be called for all accounts with `call_for_all_accounts`.
+pub(crate) fn is_account_query(method: &str) -> bool {
+    READ_ONLY_METHODS.contains(&method)
+        && !GLOBAL_METHODS.contains(&method)
+        && !MULTI_ACCOUNT_METHODS.contains(&method)
+}
+
+/// Permissions of a session.
+///
+/// The default scope allows everything.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Scope {
+    /// Only methods which do not change anything are allowed,
+    /// e.g. messages cannot be sent.
+    pub read_only: bool,
+
+    /// Methods changing the configuration of accounts or of the core are not allowed.
+    pub no_config_changes: bool,
+
+    /// Only methods of this account are allowed.
+    pub account_id: Option<u32>,
+}
+
+impl Scope {
+    /// Checks if a method call with the given positional parameters is allowed.
+    pub fn check(&self, method: &str, params: &serde_json::Value) -> Result<()> {
+        if self.read_only && !READ_ONLY_METHODS.contains(&method) {
+            bail!("{method} is not allowed in a read-only session");
+        }
+        if self.no_config_changes
+            && !READ_ONLY_METHODS.contains(&method)
+            && !NO_CONFIG_METHODS.contains(&method)
+        {
+            bail!("{method} is not allowed in a session without config changes");
+        }
+        if let Some(account_id) = self.account_id {
+            if !GLOBAL_METHODS.contains(&method)
+                && (MULTI_ACCOUNT_METHODS.contains(&method)
+                    || params.get(0).and_then(|id| id.as_u64()) != Some(account_id.into()))
+            {
+                bail!("{method} is not allowed in a session of account {account_id}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// [`CommandApi`] restricted to a [`Scope`], for use in a single session.
+#[derive(Clone, Debug)]
+pub struct ScopedCommandApi {
+    api: CommandApi,
+    scope: Scope,
+}
+
+impl CommandApi {
+    /// Returns the API restricted to `scope`.
+    pub fn with_scope(self, scope: Scope) -> ScopedCommandApi {
+        ScopedCommandApi { api: self, scope }
+    }
+}
+
+#[async_trait]
+impl RpcServer for ScopedCommandApi {
+    async fn handle_notification(
+        &self,
+        method: String,
+        params: serde_json::Value,
+    ) -> Result<(), yerpc::Error> {
+        self.scope.check(&method, &params)?;
+        self.api.handle_notification(method, params).await
+    }
+
+    async fn handle_request(
+        &self,
+        method: String,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, yerpc::Error> {
+        self.scope.check(&method, &params)?;
+        self.api.handle_request(method, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Methods changing the configuration of accounts or of the core,
+    /// not allowed in sessions without config changes.
+    ///
+    /// Only used to check that every method is classified.
+    const CONFIG_METHODS: &[&str] = &[
+        "set_event_filter",
+        "subscribe_events",
+        "unsubscribe_events",
+        "set_log_level",
+        "set_span_recording",
+        "set_event_coalescing_window",
+        "set_event_queue_config",
+        "add_account",
+        "remove_account",
+        "select_account",
+        "set_account_proxy",
+        "set_config",
+        "batch_set_config",
+        "set_config_from_qr",
+        "set_stock_strings",
+        "configure",
+        "set_maintenance_mode",
+        "import_self_keys",
+        "import_backup",
+        "get_backup",
+        "set_network_class",
+        "set_certificate_pin",
+        "remove_certificate_pin",
+        "set_unifiedpush_endpoint",
+        "set_push_device_token",
+        "set_quiet_hours",
+        "move_blobdir",
+        "start_io_for_all_accounts",
+        "stop_io_for_all_accounts",
+        "start_io",
+        "stop_io",
+        "export_self_keys",
+        "initiate_autocrypt_key_transfer",
+        "continue_autocrypt_key_transfer",
+        "export_backup",
+        "provide_backup",
+        "get_backup_qr",
+        "get_backup_qr_svg",
+        "set_chat_name",
+        "set_chat_profile_image",
+        "set_chat_profile_image_from_bytes",
+        "set_chat_visibility",
+        "set_chat_ephemeral_timer",
+        "set_chat_delete_server_after",
+        "set_chat_mute_duration",
+        "run_db_maintenance",
+        "check_database",
+        "run_housekeeping",
+        "collect_blob_garbage",
+        "export_sanitized_database",
+        "move_messages_to_cold_storage",
+        "restore_messages_from_cold_storage",
+        "set_housekeeping_deferred",
+        "cleanup_media",
+        "reset_traffic_stats",
+    ];
+
+    #[test]
+    fn test_scope_check() {
+        let scope = Scope::default();
+        assert!(scope.check("send_msg", &json!([1, 10, {}])).is_ok());
+        assert!(scope.check("set_config", &json!([1, "addr", null])).is_ok());
+
+        let scope = Scope {
+            read_only: true,
+            ..Default::default()
+        };
+        assert!(scope.check("get_message", &json!([1, 100])).is_ok());
+        assert!(scope.check("send_msg", &json!([1, 10, {}])).is_err());
+        assert!(scope.check("unknown_method", &json!([])).is_err());
+
+        let scope = Scope {
+            no_config_changes: true,
+            ..Default::default()
+        };
+        assert!(scope.check("send_msg", &json!([1, 10, {}])).is_ok());
+        assert!(scope
+            .check("set_config", &json!([1, "addr", null]))
+            .is_err());
+        assert!(scope.check("remove_account", &json!([1])).is_err());
+        assert!(scope
+            .check("export_backup", &json!([1, "/tmp", null]))
+            .is_err());
+        assert!(scope
+            .check("set_chat_visibility", &json!([1, 10, "Archived"]))
+            .is_err());
+        assert!(scope.check("unknown_method", &json!([])).is_err());
+
+        let scope = Scope {
+            account_id: Some(2),
+            ..Default::default()
+        };
+        assert!(scope.check("get_message", &json!([2, 100])).is_ok());
+        assert!(scope.check("get_message", &json!([1, 100])).is_err());
+        assert!(scope.check("get_system_info", &json!([])).is_ok());
+        assert!(scope.check("get_next_event", &json!([])).is_err());
+        assert!(scope.check("get_all_accounts", &json!([])).is_err());
+    }
+
+    #[test]
+    fn test_is_account_query() {
+        assert!(is_account_query("get_fresh_msgs"));
+        assert!(is_account_query("get_connectivity"));
+        assert!(!is_account_query("send_msg"));
+        assert!(!is_account_query("get_system_info"));
+        assert!(!is_account_query("get_all_accounts"));
+        assert!(!is_account_query("call_for_all_accounts"));
+    }
+
+    #[test]
+    fn test_scope_methods_exist() {
+        let source = include_str!("mod.rs");
+        for method in READ_ONLY_METHODS
+            .iter()
+            .chain(NO_CONFIG_METHODS)
+            .chain(CONFIG_METHODS)
+            .chain(GLOBAL_METHODS)
+            .chain(MULTI_ACCOUNT_METHODS)
+        {
+            assert!(
+                source.contains(&format!("async fn {method}(")),
+                "unknown method {method}"
+            );
+        }
+
+        // Every RPC method has to be classified,
+        // so new methods are not allowed in restricted sessions by accident.
+        let (_, rpc_impl) = source.split_once("#[rpc(").unwrap();
+        let (rpc_impl, _) = rpc_impl.split_once("\n}\n").unwrap();
+        for line in rpc_impl.lines() {
+            if let Some((_, method)) = line.split_once("async fn ") {
+                let method = method.split('(').next().unwrap();
+                let classes = [READ_ONLY_METHODS, NO_CONFIG_METHODS, CONFIG_METHODS]
+                    .iter()
+                    .filter(|methods| methods.contains(&method))
+                    .count();
+                assert_eq!(classes, 1, "{method} is not classified exactly once");
+            }
+        }
+    }
+}
@@ -1,12 +1,15 @@
+use std::collections::BTreeMap;
+
 use anyhow::Result;
 use deltachat::config::Config;
 use deltachat::contact::{Contact, ContactId};
+use schemars::JsonSchema;
 use serde::Serialize;
 use typescript_type_def::TypeDef;
 
 use super::color_int_to_hex_string;
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(tag = "type")]
 pub enum Account {
     #[serde(rename_all = "camelCase")]
@@ -43,3 +46,20 @@ pub async fn from_context(ctx: &deltachat::context::Context, id: u32) -> Result<
         }
     }
 }
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "FreshMsgCount", rename_all = "camelCase")]
+pub struct JSONRPCFreshMsgCount {
+    /// Number of fresh messages in all accounts.
+    pub total: usize,
+    /// Number of fresh messages per account ID.
+    pub accounts: BTreeMap<u32, usize>,
+}
+
+/// Result of a method called for a single account by `call_for_all_accounts`.
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(tag = "kind")]
+pub enum AccountCallResult {
+    Ok { result: serde_json::Value },
+    Error { error: String },
+}
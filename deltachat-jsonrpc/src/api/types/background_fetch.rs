@@ -0,0 +1,39 @@
+use deltachat::accounts::BackgroundFetchSummary;
+use schemars::JsonSchema;
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "FetchNotification", rename_all = "camelCase")]
+pub struct JSONRPCFetchNotification {
+    pub chat_id: u32,
+    pub msg_id: u32,
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "BackgroundFetchSummary", rename_all = "camelCase")]
+pub struct JSONRPCBackgroundFetchSummary {
+    /// Number of new incoming messages, including messages in muted chats and contact requests.
+    pub new_msgs: usize,
+    /// New fresh messages the user should be notified about, newest first.
+    pub notifications: Vec<JSONRPCFetchNotification>,
+    /// Error if fetching failed or did not finish in time.
+    pub error: Option<String>,
+}
+
+impl From<BackgroundFetchSummary> for JSONRPCBackgroundFetchSummary {
+    fn from(summary: BackgroundFetchSummary) -> Self {
+        Self {
+            new_msgs: summary.new_msgs,
+            notifications: summary
+                .notifications
+                .into_iter()
+                .map(|(chat_id, msg_id)| JSONRPCFetchNotification {
+                    chat_id: chat_id.to_u32(),
+                    msg_id: msg_id.to_u32(),
+                })
+                .collect(),
+            error: summary.error,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use deltachat::net::{CertificatePin, PinKind};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+#[derive(Clone, Copy, Serialize, Deserialize, TypeDef, JsonSchema)]
+#[serde(rename = "PinKind")]
+pub enum JSONRPCPinKind {
+    /// SHA-256 hash of the whole DER-encoded certificate.
+    Certificate,
+    /// SHA-256 hash of the DER-encoded SubjectPublicKeyInfo.
+    Spki,
+}
+
+impl From<PinKind> for JSONRPCPinKind {
+    fn from(kind: PinKind) -> Self {
+        match kind {
+            PinKind::Certificate => JSONRPCPinKind::Certificate,
+            PinKind::Spki => JSONRPCPinKind::Spki,
+        }
+    }
+}
+
+impl JSONRPCPinKind {
+    pub fn into_core_type(self) -> PinKind {
+        match self {
+            JSONRPCPinKind::Certificate => PinKind::Certificate,
+            JSONRPCPinKind::Spki => PinKind::Spki,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "CertificatePin", rename_all = "camelCase")]
+pub struct JSONRPCCertificatePin {
+    pub hostname: String,
+    pub kind: JSONRPCPinKind,
+    /// Hex-encoded SHA-256 hash.
+    pub sha256: String,
+    /// True if the pin was learned on the first connection rather than set explicitly.
+    pub learned: bool,
+    pub timestamp: i64,
+}
+
+impl From<CertificatePin> for JSONRPCCertificatePin {
+    fn from(pin: CertificatePin) -> Self {
+        Self {
+            hostname: pin.hostname,
+            kind: pin.kind.into(),
+            sha256: pin.sha256,
+            learned: pin.learned,
+            timestamp: pin.timestamp,
+        }
+    }
+}
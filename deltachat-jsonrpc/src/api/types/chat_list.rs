@@ -8,12 +8,13 @@
     chatlist::Chatlist,
 };
 use num_traits::cast::ToPrimitive;
+use schemars::JsonSchema;
 use serde::Serialize;
 use typescript_type_def::TypeDef;
 
 use super::color_int_to_hex_string;
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(tag = "type")]
 pub enum ChatListItemFetchResult {
     #[serde(rename_all = "camelCase")]
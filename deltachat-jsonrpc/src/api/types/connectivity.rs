@@ -0,0 +1,91 @@
+use deltachat::constants::NetworkClass;
+use deltachat::{ConnectionInfo, ConnectionProtocol, ConnectionState};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "ConnectionProtocol")]
+pub enum JSONRPCConnectionProtocol {
+    Imap,
+    Smtp,
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "ConnectionState")]
+pub enum JSONRPCConnectionState {
+    NotStarted,
+    Connecting,
+    /// Fetching or sending messages.
+    Working,
+    Connected,
+    Error,
+    /// The folder is not configured or configured not to be watched.
+    NotConfigured,
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "ConnectionInfo", rename_all = "camelCase")]
+pub struct JSONRPCConnectionInfo {
+    pub protocol: JSONRPCConnectionProtocol,
+    /// Watched folder for IMAP connections,
+    /// `null` for SMTP and for IMAP connections without configured folder.
+    pub folder: Option<String>,
+    pub state: JSONRPCConnectionState,
+    /// Translated description of the current state.
+    pub state_text: String,
+    /// Last error, even if the connection has recovered since then.
+    pub last_error: Option<String>,
+    pub last_error_timestamp: Option<i64>,
+    /// Timestamp of the last time the connection finished fetching or sending messages.
+    pub last_success_timestamp: Option<i64>,
+    /// Number of messages waiting to be sent for SMTP
+    /// or to be moved or deleted on the server for IMAP folders.
+    pub pending_messages: usize,
+}
+
+impl From<ConnectionInfo> for JSONRPCConnectionInfo {
+    fn from(info: ConnectionInfo) -> Self {
+        Self {
+            protocol: match info.protocol {
+                ConnectionProtocol::Imap => JSONRPCConnectionProtocol::Imap,
+                ConnectionProtocol::Smtp => JSONRPCConnectionProtocol::Smtp,
+            },
+            folder: info.folder,
+            state: match info.state {
+                ConnectionState::NotStarted => JSONRPCConnectionState::NotStarted,
+                ConnectionState::Connecting => JSONRPCConnectionState::Connecting,
+                ConnectionState::Working => JSONRPCConnectionState::Working,
+                ConnectionState::Connected => JSONRPCConnectionState::Connected,
+                ConnectionState::Error => JSONRPCConnectionState::Error,
+                ConnectionState::NotConfigured => JSONRPCConnectionState::NotConfigured,
+            },
+            state_text: info.state_text,
+            last_error: info.last_error,
+            last_error_timestamp: info.last_error_timestamp,
+            last_success_timestamp: info.last_success_timestamp,
+            pending_messages: info.pending_messages,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, TypeDef, JsonSchema)]
+#[serde(rename = "NetworkClass")]
+pub enum JSONRPCNetworkClass {
+    /// Unmetered network, e.g. Wi-Fi.
+    Unmetered,
+    /// Metered network, e.g. mobile data.
+    Metered,
+    /// Metered network while roaming.
+    Roaming,
+}
+
+impl JSONRPCNetworkClass {
+    pub fn into_core_type(self) -> NetworkClass {
+        match self {
+            JSONRPCNetworkClass::Unmetered => NetworkClass::Unmetered,
+            JSONRPCNetworkClass::Metered => NetworkClass::Metered,
+            JSONRPCNetworkClass::Roaming => NetworkClass::Roaming,
+        }
+    }
+}
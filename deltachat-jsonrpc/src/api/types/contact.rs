@@ -1,12 +1,13 @@
 use anyhow::Result;
 use deltachat::contact::VerifiedStatus;
 use deltachat::context::Context;
+use schemars::JsonSchema;
 use serde::Serialize;
 use typescript_type_def::TypeDef;
 
 use super::color_int_to_hex_string;
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(rename = "Contact", rename_all = "camelCase")]
 pub struct ContactObject {
     address: String,
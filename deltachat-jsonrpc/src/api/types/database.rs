@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use deltachat::{BlobGcReport, ColdStoredMsg, DbCheckReport, HousekeepingStats, MigrationPlan};
+use schemars::JsonSchema;
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+use super::message::MessageViewtype;
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "DbCheckReport", rename_all = "camelCase")]
+pub struct JSONRPCDbCheckReport {
+    /// Problems reported by SQLite's integrity check, empty if there are none.
+    pub integrity_errors: Vec<String>,
+    /// Number of rows referencing chats, contacts or messages which do not exist, by table.
+    pub orphaned_rows: BTreeMap<String, usize>,
+    /// IDs of messages whose file is missing.
+    pub missing_files: Vec<u32>,
+    /// Whether the database was repaired.
+    pub repaired: bool,
+}
+
+impl From<DbCheckReport> for JSONRPCDbCheckReport {
+    fn from(report: DbCheckReport) -> Self {
+        Self {
+            integrity_errors: report.integrity_errors,
+            orphaned_rows: report.orphaned_rows,
+            missing_files: report
+                .missing_files
+                .into_iter()
+                .map(|msg_id| msg_id.to_u32())
+                .collect(),
+            repaired: report.repaired,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "HousekeepingStats", rename_all = "camelCase")]
+pub struct JSONRPCHousekeepingStats {
+    /// Number of messages whose media was deleted according to the media cleanup policy.
+    pub deleted_msgs: usize,
+    /// Number of deleted messages removed from the database completely.
+    pub pruned_tombstones: usize,
+    /// Number of unused files removed from the blob directory.
+    pub removed_files: usize,
+    /// Number of bytes of the removed files.
+    pub freed_bytes: u64,
+}
+
+impl From<HousekeepingStats> for JSONRPCHousekeepingStats {
+    fn from(stats: HousekeepingStats) -> Self {
+        Self {
+            deleted_msgs: stats.deleted_msgs,
+            pruned_tombstones: stats.pruned_tombstones,
+            removed_files: stats.removed_files,
+            freed_bytes: stats.freed_bytes,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "OrphanedBlob", rename_all = "camelCase")]
+pub struct JSONRPCOrphanedBlob {
+    /// Name of the file in the blob directory.
+    pub name: String,
+    pub size: u64,
+    /// Whether the file was used during the last hour, such files are not removed.
+    pub recent: bool,
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "BlobGcReport", rename_all = "camelCase")]
+pub struct JSONRPCBlobGcReport {
+    /// Files in the blob directory which are not referenced anywhere.
+    pub orphaned_blobs: Vec<JSONRPCOrphanedBlob>,
+    /// Names of referenced files which do not exist in the blob directory.
+    pub missing_blobs: Vec<String>,
+    /// IDs of messages whose file does not exist.
+    pub msgs_with_missing_blobs: Vec<u32>,
+    /// Number of orphaned files removed.
+    pub removed_blobs: usize,
+    /// Number of bytes of the removed files.
+    pub freed_bytes: u64,
+}
+
+impl From<BlobGcReport> for JSONRPCBlobGcReport {
+    fn from(report: BlobGcReport) -> Self {
+        Self {
+            orphaned_blobs: report
+                .orphaned_blobs
+                .into_iter()
+                .map(|blob| JSONRPCOrphanedBlob {
+                    name: blob.name,
+                    size: blob.size,
+                    recent: blob.recent,
+                })
+                .collect(),
+            missing_blobs: report.missing_blobs,
+            msgs_with_missing_blobs: report
+                .msgs_with_missing_blobs
+                .into_iter()
+                .map(|msg_id| msg_id.to_u32())
+                .collect(),
+            removed_blobs: report.removed_blobs,
+            freed_bytes: report.freed_bytes,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "MigrationPlan", rename_all = "camelCase")]
+pub struct JSONRPCMigrationPlan {
+    /// Schema version of the database, null if the database does not exist yet.
+    pub db_version: Option<i32>,
+    /// Schema version the database is migrated to when it is opened.
+    pub latest_version: i32,
+    /// Whether migrations run when the database is opened.
+    pub is_needed: bool,
+}
+
+impl From<MigrationPlan> for JSONRPCMigrationPlan {
+    fn from(plan: MigrationPlan) -> Self {
+        Self {
+            db_version: plan.db_version,
+            latest_version: plan.latest_version,
+            is_needed: plan.is_needed(),
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "ColdStoredMessage", rename_all = "camelCase")]
+pub struct JSONRPCColdStoredMessage {
+    /// ID of the message, kept when it is restored.
+    pub id: u32,
+    pub chat_id: u32,
+    pub from_id: u32,
+    pub timestamp: i64,
+    pub view_type: MessageViewtype,
+    pub text: String,
+}
+
+impl From<ColdStoredMsg> for JSONRPCColdStoredMessage {
+    fn from(msg: ColdStoredMsg) -> Self {
+        Self {
+            id: msg.id.to_u32(),
+            chat_id: msg.chat_id.to_u32(),
+            from_id: msg.from_id.to_u32(),
+            timestamp: msg.timestamp,
+            view_type: msg.viewtype.into(),
+            text: msg.text,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use deltachat::net::{DiagnosticsCheck, DiagnosticsStatus, DiagnosticsStep};
+use schemars::JsonSchema;
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "DiagnosticsCheck")]
+pub enum JSONRPCDiagnosticsCheck {
+    Dns,
+    Tcp,
+    Tls,
+    ImapLogin,
+    FolderAccess,
+    SmtpLogin,
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "DiagnosticsStatus")]
+pub enum JSONRPCDiagnosticsStatus {
+    Ok,
+    Failed,
+    /// The check was not done because a previous step failed.
+    Skipped,
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "DiagnosticsStep", rename_all = "camelCase")]
+pub struct JSONRPCDiagnosticsStep {
+    pub check: JSONRPCDiagnosticsCheck,
+    /// Checked server as `host:port` or folder name.
+    pub target: String,
+    pub status: JSONRPCDiagnosticsStatus,
+    pub duration_ms: u64,
+    /// Additional information such as resolved addresses.
+    pub details: Option<String>,
+    pub error: Option<String>,
+}
+
+impl From<DiagnosticsStep> for JSONRPCDiagnosticsStep {
+    fn from(step: DiagnosticsStep) -> Self {
+        Self {
+            check: match step.check {
+                DiagnosticsCheck::Dns => JSONRPCDiagnosticsCheck::Dns,
+                DiagnosticsCheck::Tcp => JSONRPCDiagnosticsCheck::Tcp,
+                DiagnosticsCheck::Tls => JSONRPCDiagnosticsCheck::Tls,
+                DiagnosticsCheck::ImapLogin => JSONRPCDiagnosticsCheck::ImapLogin,
+                DiagnosticsCheck::FolderAccess => JSONRPCDiagnosticsCheck::FolderAccess,
+                DiagnosticsCheck::SmtpLogin => JSONRPCDiagnosticsCheck::SmtpLogin,
+            },
+            target: step.target,
+            status: match step.status {
+                DiagnosticsStatus::Ok => JSONRPCDiagnosticsStatus::Ok,
+                DiagnosticsStatus::Failed => JSONRPCDiagnosticsStatus::Failed,
+                DiagnosticsStatus::Skipped => JSONRPCDiagnosticsStatus::Skipped,
+            },
+            duration_ms: step.duration_ms,
+            details: step.details,
+            error: step.error,
+        }
+    }
+}
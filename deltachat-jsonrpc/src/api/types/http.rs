@@ -1,8 +1,9 @@
 use deltachat::net::HttpResponse as CoreHttpResponse;
+use schemars::JsonSchema;
 use serde::Serialize;
 use typescript_type_def::TypeDef;
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 pub struct HttpResponse {
     /// base64-encoded response body.
     blob: String,
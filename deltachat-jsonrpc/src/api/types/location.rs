@@ -1,19 +1,24 @@
 use deltachat::location::Location;
+use schemars::JsonSchema;
 use serde::Serialize;
 use typescript_type_def::TypeDef;
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(rename = "Location", rename_all = "camelCase")]
 pub struct JsonrpcLocation {
     pub location_id: u32,
+    /// Whether the location is independent, i.e. not part of the path.
     pub is_independent: bool,
     pub latitude: f64,
     pub longitude: f64,
+    /// Accuracy in meters, 0 if unknown.
     pub accuracy: f64,
     pub timestamp: i64,
     pub contact_id: u32,
+    /// ID of the message the location was sent with, 0 if there is no such message.
     pub msg_id: u32,
     pub chat_id: u32,
+    /// A marker string, such as an emoji, to be displayed on top of the location.
     pub marker: Option<String>,
 }
 
@@ -0,0 +1,57 @@
+use deltachat::log::{LogEntry, LogLevel};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+#[derive(Clone, Copy, Serialize, Deserialize, TypeDef, JsonSchema)]
+#[serde(rename = "LogLevel")]
+pub enum JSONRPCLogLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl JSONRPCLogLevel {
+    pub fn into_core_type(self) -> LogLevel {
+        match self {
+            JSONRPCLogLevel::Info => LogLevel::Info,
+            JSONRPCLogLevel::Warning => LogLevel::Warning,
+            JSONRPCLogLevel::Error => LogLevel::Error,
+        }
+    }
+}
+
+impl From<LogLevel> for JSONRPCLogLevel {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Info => JSONRPCLogLevel::Info,
+            LogLevel::Warning => JSONRPCLogLevel::Warning,
+            LogLevel::Error => JSONRPCLogLevel::Error,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "LogEntry", rename_all = "camelCase")]
+pub struct JSONRPCLogEntry {
+    /// Time the entry was logged, in milliseconds since the Unix epoch.
+    pub timestamp_ms: i64,
+    pub level: JSONRPCLogLevel,
+    /// Source file which logged the entry, if known.
+    pub file: Option<String>,
+    /// Line in the source file, if known.
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl From<LogEntry> for JSONRPCLogEntry {
+    fn from(entry: LogEntry) -> Self {
+        Self {
+            timestamp_ms: entry.timestamp_ms,
+            level: entry.level.into(),
+            file: entry.file,
+            line: entry.line,
+            message: entry.message,
+        }
+    }
+}
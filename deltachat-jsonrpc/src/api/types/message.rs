@@ -8,8 +8,11 @@
 use deltachat::message::Message;
 use deltachat::message::MsgId;
 use deltachat::message::Viewtype;
+use deltachat::notifications::NotificationContent;
 use deltachat::reaction::get_msg_reactions;
+use deltachat::search::MsgSearchMatch;
 use num_traits::cast::ToPrimitive;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use typescript_type_def::TypeDef;
@@ -19,14 +22,14 @@
 use super::reactions::JSONRPCReactions;
 use super::webxdc::WebxdcMessageInfo;
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(rename_all = "camelCase", tag = "variant")]
 pub enum MessageLoadResult {
     Message(MessageObject),
     LoadingError { error: String },
 }
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(rename = "Message", rename_all = "camelCase")]
 pub struct MessageObject {
     id: u32,
@@ -66,7 +69,10 @@ pub struct MessageObject {
     dimensions_height: i32,
     dimensions_width: i32,
 
+    /// Type of the videochat for videochat invitations:
+    /// 0 for unknown, 1 for basicWebRTC, 2 for Jitsi.
     videochat_type: Option<u32>,
+    /// URL to join the videochat for videochat invitations.
     videochat_url: Option<String>,
 
     override_sender_name: Option<String>,
@@ -86,7 +92,7 @@ pub struct MessageObject {
     reactions: Option<JSONRPCReactions>,
 }
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(tag = "kind")]
 enum MessageQuote {
     JustText {
@@ -230,7 +236,7 @@ pub async fn from_msg_id(context: &Context, msg_id: MsgId) -> Result<Self> {
     }
 }
 
-#[derive(Serialize, Deserialize, TypeDef)]
+#[derive(Serialize, Deserialize, TypeDef, JsonSchema)]
 #[serde(rename = "Viewtype")]
 pub enum MessageViewtype {
     Unknown,
@@ -306,7 +312,7 @@ fn from(viewtype: MessageViewtype) -> Self {
     }
 }
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 pub enum DownloadState {
     Done,
     Available,
@@ -325,7 +331,7 @@ fn from(state: download::DownloadState) -> Self {
     }
 }
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 pub enum SystemMessageType {
     Unknown,
     GroupNameChanged,
@@ -355,6 +361,10 @@ pub enum SystemMessageType {
 
     /// Webxdc info added with `info` set in `send_webxdc_status_update()`.
     WebxdcInfoMessage,
+
+    /// Realtime data or peer address sent to the other peers of a webxdc instance.
+    /// These messages are not shown in the chat.
+    WebxdcRealtimeData,
 }
 
 impl From<deltachat::mimeparser::SystemMessage> for SystemMessageType {
@@ -376,13 +386,16 @@ fn from(system_message_type: deltachat::mimeparser::SystemMessage) -> Self {
             SystemMessage::MultiDeviceSync => SystemMessageType::MultiDeviceSync,
             SystemMessage::WebxdcStatusUpdate => SystemMessageType::WebxdcStatusUpdate,
             SystemMessage::WebxdcInfoMessage => SystemMessageType::WebxdcInfoMessage,
+            SystemMessage::WebxdcRealtimeData => SystemMessageType::WebxdcRealtimeData,
         }
     }
 }
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MessageNotificationInfo {
+    /// Message, chat and account IDs,
+    /// to open the chat or act on the message when the notification is clicked.
     id: u32,
     chat_id: u32,
     account_id: u32,
@@ -397,48 +410,105 @@ pub struct MessageNotificationInfo {
     summary_prefix: Option<String>,
     /// also known as summary_text2
     summary_text: String,
+
+    /// True during quiet hours, the notification should be shown silently.
+    suppress_notification: bool,
+
+    /// Accent color of the chat, null if hidden by `notification_privacy`.
+    chat_color: Option<String>,
+    /// SVG avatar with the first letter of the chat name
+    /// to be used if the chat has no profile image.
+    chat_letter_avatar: Option<String>,
+    /// Actions to offer in the notification.
+    actions: Vec<NotificationAction>,
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+pub enum NotificationAction {
+    Reply,
+    MarkSeen,
+    AcceptRequest,
+}
+
+impl From<deltachat::notifications::NotificationAction> for NotificationAction {
+    fn from(action: deltachat::notifications::NotificationAction) -> Self {
+        use deltachat::notifications::NotificationAction as Action;
+        match action {
+            Action::Reply => NotificationAction::Reply,
+            Action::MarkSeen => NotificationAction::MarkSeen,
+            Action::AcceptRequest => NotificationAction::AcceptRequest,
+        }
+    }
 }
 
 impl MessageNotificationInfo {
+    /// Loads notification info for a message,
+    /// revealing only as much as allowed by the `notification_privacy` option.
     pub async fn from_msg_id(context: &Context, msg_id: MsgId) -> Result<Self> {
         let message = Message::load_from_db(context, msg_id).await?;
         let chat = Chat::load_from_db(context, message.get_chat_id()).await?;
+        let content = NotificationContent::load(context, &message, &chat).await?;
 
-        let image = if matches!(
-            message.get_viewtype(),
-            Viewtype::Image | Viewtype::Gif | Viewtype::Sticker
-        ) {
-            message
-                .get_file(context)
-                .map(|path_buf| path_buf.to_str().map(|s| s.to_owned()))
-                .unwrap_or_default()
+        let image_mime_type = if content.image.is_some() {
+            message.get_filemime()
         } else {
             None
         };
 
-        let chat_profile_image = chat
-            .get_profile_image(context)
-            .await?
-            .map(|path_buf| path_buf.to_str().map(|s| s.to_owned()))
-            .unwrap_or_default();
-
-        let summary = message.get_summary(context, Some(&chat)).await?;
-
         Ok(MessageNotificationInfo {
             id: msg_id.to_u32(),
             chat_id: message.get_chat_id().to_u32(),
             account_id: context.get_id(),
-            image,
-            image_mime_type: message.get_filemime(),
-            chat_name: chat.name,
-            chat_profile_image,
-            summary_prefix: summary.prefix.map(|s| s.to_string()),
-            summary_text: summary.text,
+            image: content
+                .image
+                .and_then(|path_buf| path_buf.to_str().map(|s| s.to_owned())),
+            image_mime_type,
+            chat_name: content.title,
+            chat_profile_image: content
+                .chat_profile_image
+                .and_then(|path_buf| path_buf.to_str().map(|s| s.to_owned())),
+            summary_prefix: content.summary_prefix,
+            summary_text: content.summary_text,
+            suppress_notification: context.is_quiet_time().await?,
+            chat_color: content.chat_color.map(color_int_to_hex_string),
+            chat_letter_avatar: content.chat_letter_avatar,
+            actions: content.actions.into_iter().map(Into::into).collect(),
         })
     }
 }
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationGroup {
+    chat_id: u32,
+    chat_name: String,
+    /// Messages of the group, newest first.
+    message_ids: Vec<u32>,
+    /// Summary line such as "3 messages from Alice",
+    /// same as the preview if the group contains a single message.
+    summary: String,
+    /// Preview of the newest message.
+    preview: String,
+    timestamp: i64,
+    /// True during quiet hours, the notification should be shown silently.
+    suppress_notification: bool,
+}
+
+impl From<deltachat::notifications::NotificationGroup> for NotificationGroup {
+    fn from(group: deltachat::notifications::NotificationGroup) -> Self {
+        NotificationGroup {
+            chat_id: group.chat_id.to_u32(),
+            chat_name: group.chat_name,
+            message_ids: group.msg_ids.into_iter().map(|id| id.to_u32()).collect(),
+            summary: group.summary,
+            preview: group.preview,
+            timestamp: group.timestamp,
+            suppress_notification: group.suppress_notification,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MessageSearchResult {
     id: u32,
@@ -477,7 +547,41 @@ pub async fn from_msg_id(context: &Context, msg_id: MsgId) -> Result<Self> {
     }
 }
 
-#[derive(Serialize, TypeDef)]
+/// Message found by `search_chat_messages` with the positions of the matches.
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSearchMatch {
+    id: u32,
+
+    /// Matches in the message text, in order.
+    matches: Vec<MessageSearchMatchRange>,
+}
+
+/// Position of a match in the UTF-8 encoded message text.
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSearchMatchRange {
+    /// Offset in bytes.
+    offset: usize,
+
+    /// Length in bytes.
+    length: usize,
+}
+
+impl From<MsgSearchMatch> for MessageSearchMatch {
+    fn from(found: MsgSearchMatch) -> Self {
+        MessageSearchMatch {
+            id: found.msg_id.to_u32(),
+            matches: found
+                .ranges
+                .into_iter()
+                .map(|(offset, length)| MessageSearchMatchRange { offset, length })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(rename_all = "camelCase", rename = "MessageListItem", tag = "kind")]
 pub enum JSONRPCMessageListItem {
     Message {
@@ -503,7 +607,7 @@ fn from(item: ChatItem) -> Self {
     }
 }
 
-#[derive(Deserialize, TypeDef)]
+#[derive(Deserialize, TypeDef, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MessageData {
     pub text: Option<String>,
@@ -0,0 +1,34 @@
+use deltachat::metrics::Metrics;
+use schemars::JsonSchema;
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "Metrics", rename_all = "camelCase")]
+pub struct JSONRPCMetrics {
+    /// Messages sent over SMTP since the process started.
+    pub msgs_sent: u64,
+    /// Incoming messages received since the process started.
+    pub msgs_received: u64,
+    /// Messages given up sending since the process started.
+    pub send_failures: u64,
+    /// Same as `get_connectivity`.
+    pub connectivity: u32,
+    /// Number of messages waiting to be sent over SMTP.
+    pub smtp_queue_len: usize,
+    /// Size of the database file in bytes.
+    pub db_size: u64,
+}
+
+impl From<Metrics> for JSONRPCMetrics {
+    fn from(metrics: Metrics) -> Self {
+        Self {
+            msgs_sent: metrics.msgs_sent,
+            msgs_received: metrics.msgs_received,
+            send_failures: metrics.send_failures,
+            connectivity: metrics.connectivity,
+            smtp_queue_len: metrics.smtp_queue_len,
+            db_size: metrics.db_size,
+        }
+    }
+}
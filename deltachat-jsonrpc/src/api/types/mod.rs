@@ -1,13 +1,26 @@
 pub mod account;
+pub mod background_fetch;
+pub mod certificate_pin;
 pub mod chat;
 pub mod chat_list;
+pub mod connectivity;
 pub mod contact;
+pub mod database;
+pub mod diagnostics;
 pub mod http;
 pub mod location;
+pub mod log;
 pub mod message;
+pub mod metrics;
 pub mod provider_info;
+pub mod proxy;
+pub mod push;
 pub mod qr;
+pub mod quiet_hours;
+pub mod quota;
 pub mod reactions;
+pub mod storage;
+pub mod traffic;
 pub mod webxdc;
 
 pub fn color_int_to_hex_string(color: u32) -> String {
@@ -1,9 +1,10 @@
 use deltachat::provider::Provider;
 use num_traits::cast::ToPrimitive;
+use schemars::JsonSchema;
 use serde::Serialize;
 use typescript_type_def::TypeDef;
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProviderInfo {
     pub before_login_hint: String,
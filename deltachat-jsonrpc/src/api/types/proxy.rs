@@ -0,0 +1,62 @@
+use deltachat::socks::{ProxyProtocol, Socks5Config};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+#[derive(Clone, Copy, Serialize, Deserialize, TypeDef, JsonSchema)]
+#[serde(rename = "ProxyProtocol")]
+pub enum JSONRPCProxyProtocol {
+    Socks5,
+    /// HTTP proxy supporting CONNECT method.
+    Http,
+    /// HTTP proxy supporting CONNECT method, connected to over TLS.
+    Https,
+    /// Built-in Tor client, host and port are ignored.
+    Tor,
+}
+
+#[derive(Serialize, Deserialize, TypeDef, JsonSchema)]
+#[serde(rename = "ProxyConfig", rename_all = "camelCase")]
+pub struct JSONRPCProxyConfig {
+    pub protocol: JSONRPCProxyProtocol,
+    pub host: String,
+    pub port: u16,
+    pub user: Option<String>,
+    /// Only used when setting the proxy, never returned.
+    pub password: Option<String>,
+}
+
+impl From<Socks5Config> for JSONRPCProxyConfig {
+    fn from(config: Socks5Config) -> Self {
+        Self {
+            protocol: match config.protocol {
+                ProxyProtocol::Socks5 => JSONRPCProxyProtocol::Socks5,
+                ProxyProtocol::Http => JSONRPCProxyProtocol::Http,
+                ProxyProtocol::Https => JSONRPCProxyProtocol::Https,
+                ProxyProtocol::Tor => JSONRPCProxyProtocol::Tor,
+            },
+            host: config.host,
+            port: config.port,
+            user: config.user_password.map(|(user, _password)| user),
+            password: None,
+        }
+    }
+}
+
+impl JSONRPCProxyConfig {
+    pub fn into_core_type(self) -> Socks5Config {
+        Socks5Config {
+            protocol: match self.protocol {
+                JSONRPCProxyProtocol::Socks5 => ProxyProtocol::Socks5,
+                JSONRPCProxyProtocol::Http => ProxyProtocol::Http,
+                JSONRPCProxyProtocol::Https => ProxyProtocol::Https,
+                JSONRPCProxyProtocol::Tor => ProxyProtocol::Tor,
+            },
+            host: self.host,
+            port: self.port,
+            user_password: self
+                .user
+                .map(|user| (user, self.password.unwrap_or_default())),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use deltachat::push::PushState;
+use schemars::JsonSchema;
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "PushState")]
+pub enum JSONRPCPushState {
+    /// Neither a device token nor a UnifiedPush endpoint is set.
+    NoToken,
+    /// The server does not support push notifications.
+    NotSupported,
+    /// The token will be registered on the next connection.
+    Pending,
+    /// The token is registered on the server.
+    Registered,
+}
+
+impl From<PushState> for JSONRPCPushState {
+    fn from(state: PushState) -> Self {
+        match state {
+            PushState::NoToken => JSONRPCPushState::NoToken,
+            PushState::NotSupported => JSONRPCPushState::NotSupported,
+            PushState::Pending => JSONRPCPushState::Pending,
+            PushState::Registered => JSONRPCPushState::Registered,
+        }
+    }
+}
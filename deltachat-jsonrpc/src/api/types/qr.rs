@@ -1,8 +1,9 @@
 use deltachat::qr::Qr;
+use schemars::JsonSchema;
 use serde::Serialize;
 use typescript_type_def::TypeDef;
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(rename = "Qr", rename_all = "camelCase")]
 #[serde(tag = "type")]
 pub enum QrObject {
@@ -0,0 +1,36 @@
+use deltachat::notifications::QuietHours;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+#[derive(Clone, Copy, Serialize, Deserialize, TypeDef, JsonSchema)]
+#[serde(rename = "QuietHours", rename_all = "camelCase")]
+pub struct JSONRPCQuietHours {
+    /// Day of the week the range starts on, 0 is Monday and 6 is Sunday.
+    pub weekday: u8,
+    /// Start of the range in minutes since midnight, local time.
+    pub start: u16,
+    /// End of the range in minutes since midnight, local time.
+    /// If not after `start`, the range ends on the next day.
+    pub end: u16,
+}
+
+impl From<QuietHours> for JSONRPCQuietHours {
+    fn from(quiet_hours: QuietHours) -> Self {
+        Self {
+            weekday: quiet_hours.weekday,
+            start: quiet_hours.start,
+            end: quiet_hours.end,
+        }
+    }
+}
+
+impl JSONRPCQuietHours {
+    pub fn into_core_type(self) -> QuietHours {
+        QuietHours {
+            weekday: self.weekday,
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
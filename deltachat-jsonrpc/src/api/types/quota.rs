@@ -0,0 +1,30 @@
+use deltachat::quota::QuotaResourceUsage;
+use schemars::JsonSchema;
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "QuotaResourceUsage", rename_all = "camelCase")]
+pub struct JSONRPCQuotaResourceUsage {
+    /// Name of the quota root, empty eg. for Gmail.
+    pub root_name: String,
+    /// Name of the resource as defined in RFC 2087,
+    /// eg. "STORAGE" for the mailbox size in units of 1024 bytes
+    /// or "MESSAGE" for the number of messages.
+    pub resource_name: String,
+    pub usage: u64,
+    pub limit: u64,
+    pub usage_percentage: u64,
+}
+
+impl From<QuotaResourceUsage> for JSONRPCQuotaResourceUsage {
+    fn from(usage: QuotaResourceUsage) -> Self {
+        Self {
+            root_name: usage.root_name,
+            resource_name: usage.resource_name,
+            usage: usage.usage,
+            limit: usage.limit,
+            usage_percentage: usage.usage_percentage,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use deltachat::storage::{
+    ChatStorageUsage, MediaCleanupPolicy, MediaCleanupReport, StorageUsage, ViewtypeStorageUsage,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use typescript_type_def::TypeDef;
+
+use super::message::MessageViewtype;
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "StorageUsage", rename_all = "camelCase")]
+pub struct JSONRPCStorageUsage {
+    /// Size of the database including the write-ahead log in bytes.
+    pub db_size: u64,
+    /// Size of all files in the blob directory in bytes.
+    pub blobdir_size: u64,
+    /// Chats using the most storage first, chats without messages are omitted.
+    pub chats: Vec<JSONRPCChatStorageUsage>,
+}
+
+impl From<StorageUsage> for JSONRPCStorageUsage {
+    fn from(usage: StorageUsage) -> Self {
+        Self {
+            db_size: usage.db_size,
+            blobdir_size: usage.blobdir_size,
+            chats: usage.chats.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "ChatStorageUsage", rename_all = "camelCase")]
+pub struct JSONRPCChatStorageUsage {
+    pub chat_id: u32,
+    pub msg_count: usize,
+    /// Estimated size of the messages in the database in bytes.
+    pub db_bytes: u64,
+    /// Size of the files of the messages in bytes.
+    pub blob_bytes: u64,
+    /// Storage used per view type, view types using the most storage first.
+    pub viewtypes: Vec<JSONRPCViewtypeStorageUsage>,
+}
+
+impl From<ChatStorageUsage> for JSONRPCChatStorageUsage {
+    fn from(usage: ChatStorageUsage) -> Self {
+        Self {
+            chat_id: usage.chat_id.to_u32(),
+            msg_count: usage.msg_count,
+            db_bytes: usage.db_bytes,
+            blob_bytes: usage.blob_bytes,
+            viewtypes: usage.viewtypes.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "ViewtypeStorageUsage", rename_all = "camelCase")]
+pub struct JSONRPCViewtypeStorageUsage {
+    pub viewtype: MessageViewtype,
+    pub msg_count: usize,
+    pub db_bytes: u64,
+    pub blob_bytes: u64,
+}
+
+impl From<ViewtypeStorageUsage> for JSONRPCViewtypeStorageUsage {
+    fn from(usage: ViewtypeStorageUsage) -> Self {
+        Self {
+            viewtype: usage.viewtype.into(),
+            msg_count: usage.msg_count,
+            db_bytes: usage.db_bytes,
+            blob_bytes: usage.blob_bytes,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, TypeDef, JsonSchema)]
+#[serde(rename = "MediaCleanupPolicy", rename_all = "camelCase")]
+pub struct JSONRPCMediaCleanupPolicy {
+    /// Age in seconds after which messages with media are deleted.
+    pub max_age: i64,
+    /// Only chats using more than this number of bytes are cleaned up, 0 for all chats.
+    pub min_chat_size: u64,
+    /// Whether starred messages are kept.
+    pub keep_starred: bool,
+}
+
+impl JSONRPCMediaCleanupPolicy {
+    pub fn into_core_type(self) -> MediaCleanupPolicy {
+        MediaCleanupPolicy {
+            max_age: self.max_age,
+            min_chat_size: self.min_chat_size,
+            keep_starred: self.keep_starred,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "MediaCleanupReport", rename_all = "camelCase")]
+pub struct JSONRPCMediaCleanupReport {
+    /// IDs of the messages whose media was deleted, or would be deleted in dry-run mode.
+    pub msg_ids: Vec<u32>,
+    /// Number of bytes freed by deleting the files,
+    /// files still used by other messages are not counted.
+    pub blob_bytes: u64,
+    pub dry_run: bool,
+}
+
+impl From<MediaCleanupReport> for JSONRPCMediaCleanupReport {
+    fn from(report: MediaCleanupReport) -> Self {
+        Self {
+            msg_ids: report
+                .msg_ids
+                .into_iter()
+                .map(|msg_id| msg_id.to_u32())
+                .collect(),
+            blob_bytes: report.blob_bytes,
+            dry_run: report.dry_run,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use deltachat::net::{ProtocolTraffic, TrafficStats};
+use schemars::JsonSchema;
+use serde::Serialize;
+use typescript_type_def::TypeDef;
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "ProtocolTraffic", rename_all = "camelCase")]
+pub struct JSONRPCProtocolTraffic {
+    pub sent: u64,
+    pub received: u64,
+}
+
+impl From<ProtocolTraffic> for JSONRPCProtocolTraffic {
+    fn from(traffic: ProtocolTraffic) -> Self {
+        Self {
+            sent: traffic.sent,
+            received: traffic.received,
+        }
+    }
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "TrafficStats", rename_all = "camelCase")]
+pub struct JSONRPCTrafficStats {
+    pub imap: JSONRPCProtocolTraffic,
+    pub smtp: JSONRPCProtocolTraffic,
+    /// Only the size of downloaded response bodies is counted for HTTP.
+    pub http: JSONRPCProtocolTraffic,
+    /// Timestamp of the last reset, 0 if the statistics were never reset.
+    pub since: i64,
+}
+
+impl From<TrafficStats> for JSONRPCTrafficStats {
+    fn from(stats: TrafficStats) -> Self {
+        Self {
+            imap: stats.imap.into(),
+            smtp: stats.smtp.into(),
+            http: stats.http.into(),
+            since: stats.since,
+        }
+    }
+}
@@ -1,14 +1,15 @@
 use deltachat::{
     context::Context,
     message::{Message, MsgId},
-    webxdc::WebxdcInfo,
+    webxdc::{WebxdcCapability, WebxdcInfo},
 };
+use schemars::JsonSchema;
 use serde::Serialize;
 use typescript_type_def::TypeDef;
 
 use super::maybe_empty_string_to_option;
 
-#[derive(Serialize, TypeDef)]
+#[derive(Serialize, TypeDef, JsonSchema)]
 #[serde(rename = "WebxdcMessageInfo", rename_all = "camelCase")]
 pub struct WebxdcMessageInfo {
     /// The name of the app.
@@ -35,6 +36,31 @@ pub struct WebxdcMessageInfo {
     source_code_url: Option<String>,
     /// True if full internet access should be granted to the app.
     internet_access: bool,
+    /// Capabilities requested in the manifest.
+    /// Capabilities not listed here must be denied by the implementation,
+    /// which may show the list as a permission summary before launching the app.
+    capabilities: Vec<WebxdcAppCapability>,
+}
+
+#[derive(Serialize, TypeDef, JsonSchema)]
+#[serde(rename = "WebxdcCapability")]
+pub enum WebxdcAppCapability {
+    /// Exchanging data over realtime channels, see joinWebxdcRealtimeChannel().
+    Realtime,
+    /// Passing messages to the implementation to be sent to a chat with `sendToChat()`.
+    SendToChat,
+    /// Storing more data in the web storage than allowed by default.
+    LargeStorage,
+}
+
+impl From<WebxdcCapability> for WebxdcAppCapability {
+    fn from(capability: WebxdcCapability) -> Self {
+        match capability {
+            WebxdcCapability::Realtime => WebxdcAppCapability::Realtime,
+            WebxdcCapability::SendToChat => WebxdcAppCapability::SendToChat,
+            WebxdcCapability::LargeStorage => WebxdcAppCapability::LargeStorage,
+        }
+    }
 }
 
 impl WebxdcMessageInfo {
@@ -50,6 +76,7 @@ pub async fn get_for_message(
             summary,
             source_code_url,
             internet_access,
+            capabilities,
         } = message.get_webxdc_info(context).await?;
 
         Ok(Self {
@@ -59,6 +86,7 @@ pub async fn get_for_message(
             summary: maybe_empty_string_to_option(summary),
             source_code_url: maybe_empty_string_to_option(source_code_url),
             internet_access,
+            capabilities: capabilities.into_iter().map(Into::into).collect(),
         })
     }
 }
@@ -0,0 +1,30 @@
+//! # Token authentication.
+//!
+//! JSON-RPC servers reachable from other hosts require clients
+//! to send a configured token before accepting requests.
+
+/// Returns true if the token `provided` by a client matches the configured `token`.
+///
+/// Takes the same time for all tokens of the same length,
+/// so the token cannot be guessed by measuring response times.
+pub fn check_token(token: &str, provided: &str) -> bool {
+    token.len() == provided.len()
+        && token
+            .bytes()
+            .zip(provided.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_token() {
+        assert!(check_token("secret", "secret"));
+        assert!(!check_token("secret", "secreT"));
+        assert!(!check_token("secret", "secret1"));
+        assert!(!check_token("secret", ""));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod api;
+pub mod auth;
 pub use api::events;
 pub use yerpc;
 
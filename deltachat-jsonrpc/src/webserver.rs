@@ -1,13 +1,23 @@
-use std::net::SocketAddr;
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use axum::{extract::ws::WebSocketUpgrade, response::Response, routing::get, Extension, Router};
+use axum::{
+    extract::{ws::WebSocketUpgrade, State},
+    http::{header, Request, StatusCode},
+    middleware::{self, Next},
+    response::sse::{Event as SseEvent, KeepAlive, Sse},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use deltachat_jsonrpc::api::{Accounts, CommandApi};
+use deltachat_jsonrpc::auth::check_token;
+use futures::{stream, Stream, StreamExt};
 use yerpc::axum::handle_ws_rpc;
 use yerpc::{RpcClient, RpcSession};
 
-mod api;
-use api::{Accounts, CommandApi};
-
 const DEFAULT_PORT: u16 = 20808;
 
 #[tokio::main(flavor = "multi_thread")]
@@ -18,20 +28,34 @@ async fn main() -> Result<(), std::io::Error> {
     let port = std::env::var("DC_PORT")
         .map(|port| port.parse::<u16>().expect("DC_PORT must be a number"))
         .unwrap_or(DEFAULT_PORT);
+    let host = std::env::var("DC_HOST")
+        .map(|host| {
+            host.parse::<IpAddr>()
+                .expect("DC_HOST must be an IP address")
+        })
+        .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    let token = std::env::var("DC_TOKEN").ok().map(Arc::new);
+    assert!(
+        host.is_loopback() || token.is_some(),
+        "DC_HOST {host} is not a loopback address, set DC_TOKEN to accept remote connections"
+    );
     log::info!("Starting with accounts directory `{path}`.");
     let accounts = Accounts::new(PathBuf::from(&path)).await.unwrap();
     let state = CommandApi::new(accounts);
 
     let app = Router::new()
         .route("/ws", get(handler))
+        .route("/rpc", post(http_handler))
+        .route("/events", get(events_handler))
+        .layer(middleware::from_fn_with_state(token, auth_middleware))
         .layer(Extension(state.clone()));
 
     tokio::spawn(async move {
-        state.accounts.read().await.start_io().await;
+        state.accounts().read().await.start_io().await;
     });
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], port));
-    log::info!("JSON-RPC WebSocket server listening on {}", addr);
+    let addr = SocketAddr::new(host, port);
+    log::info!("JSON-RPC server listening on {}", addr);
     axum::Server::bind(&addr)
         .serve(app.into_make_service())
         .await
@@ -42,6 +66,75 @@ async fn main() -> Result<(), std::io::Error> {
 
 async fn handler(ws: WebSocketUpgrade, Extension(api): Extension<CommandApi>) -> Response {
     let (client, out_receiver) = RpcClient::new();
-    let session = RpcSession::new(client.clone(), api.clone());
+    let session = RpcSession::new(client.clone(), api);
     handle_ws_rpc(ws, out_receiver, session).await
 }
+
+/// Handles a single JSON-RPC request sent as the body of a POST request.
+///
+/// Responds with the JSON-RPC response,
+/// or with an empty `204 No Content` response for notifications.
+async fn http_handler(Extension(api): Extension<CommandApi>, body: String) -> Response {
+    let (client, mut out_receiver) = RpcClient::new();
+    let session = RpcSession::new(client, api);
+    session.handle_incoming(&body).await;
+
+    // Once the session is dropped, the channel is closed
+    // as soon as the request is handled.
+    drop(session);
+    match out_receiver.next().await {
+        Some(message) => Json(message).into_response(),
+        None => StatusCode::NO_CONTENT.into_response(),
+    }
+}
+
+/// Streams events of all accounts as server-sent events.
+///
+/// Each event has the same JSON format as the result of `get_next_event`
+/// and the event filter applies.
+/// As with `get_next_event`, each event is delivered to only one client.
+async fn events_handler(
+    Extension(api): Extension<CommandApi>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let event_emitter = api.accounts().read().await.get_event_emitter();
+    let events = stream::unfold((api, event_emitter), |(api, event_emitter)| async move {
+        let event = api.recv_event(&event_emitter).await?;
+        let sse_event = match SseEvent::default().json_data(event) {
+            Ok(sse_event) => sse_event,
+            Err(err) => {
+                log::warn!("Failed to serialize event: {err:#}.");
+                SseEvent::default().comment("event not serialized")
+            }
+        };
+        Some((Ok(sse_event), (api, event_emitter)))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Rejects requests without the token configured with `DC_TOKEN`.
+///
+/// The token is sent in an `Authorization: Bearer <token>` header
+/// or, as browsers cannot set headers for WebSockets, in a `token` query parameter.
+async fn auth_middleware<B>(
+    State(token): State<Option<Arc<String>>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if let Some(token) = token {
+        let header_token = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let query_token = request.uri().query().and_then(|query| {
+            query
+                .split('&')
+                .find_map(|param| param.strip_prefix("token="))
+        });
+        let provided = header_token.or(query_token).unwrap_or_default();
+        if !check_token(&token, provided) {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    }
+    next.run(request).await
+}
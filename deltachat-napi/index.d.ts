@@ -0,0 +1,7 @@
+export * from './binding'
+
+declare module './binding' {
+  interface EventEmitter {
+    [Symbol.asyncIterator](): AsyncIterator<any>
+  }
+}
@@ -0,0 +1,15 @@
+const binding = require('./binding.js')
+
+// Events are only received from the core when the next one is requested,
+// so a slow consumer does not make the events pile up in JavaScript.
+binding.EventEmitter.prototype[Symbol.asyncIterator] = async function* () {
+  for (;;) {
+    const event = await this.recv()
+    if (event === null) {
+      return
+    }
+    yield event
+  }
+}
+
+module.exports = binding
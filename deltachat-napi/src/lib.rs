@@ -0,0 +1,248 @@
+//! Node.js bindings to Delta Chat core using N-API.
+//!
+//! The core runs inside the Node.js process,
+//! so no separate JSON-RPC server process is needed.
+//! Messages and events use the same JSON representation as the JSON-RPC API.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use deltachat::chat::{self, ChatId, ChatItem};
+use deltachat::config::Config;
+use deltachat::context::Context as CoreContext;
+use deltachat::message::{self, MsgId};
+use deltachat_jsonrpc::api::types::message::MessageObject;
+use deltachat_jsonrpc::api::{Accounts as CoreAccounts, CommandApi};
+use deltachat_jsonrpc::events::Event;
+use deltachat_jsonrpc::yerpc::{RpcClient, RpcSession};
+use napi::Result;
+use napi_derive::napi;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+/// Converts an error of the core into a JavaScript error.
+fn to_napi_err(err: anyhow::Error) -> napi::Error {
+    napi::Error::from_reason(format!("{err:#}"))
+}
+
+/// Opens the accounts directory, creating it if it does not exist.
+#[napi]
+pub async fn open_accounts(dir: String) -> Result<Accounts> {
+    let accounts = CoreAccounts::new(PathBuf::from(dir))
+        .await
+        .map_err(to_napi_err)?;
+    let inner = Arc::new(RwLock::new(accounts));
+    let api = CommandApi::from_arc(inner.clone());
+    Ok(Accounts { inner, api })
+}
+
+/// Account manager.
+#[napi]
+pub struct Accounts {
+    inner: Arc<RwLock<CoreAccounts>>,
+    api: CommandApi,
+}
+
+#[napi]
+impl Accounts {
+    /// Returns the IDs of all accounts.
+    #[napi]
+    pub async fn get_all(&self) -> Vec<u32> {
+        self.inner.read().await.get_all()
+    }
+
+    /// Adds a new account and returns its ID.
+    #[napi]
+    pub async fn add_account(&self) -> Result<u32> {
+        self.inner
+            .write()
+            .await
+            .add_account()
+            .await
+            .map_err(to_napi_err)
+    }
+
+    /// Removes the account and all its data.
+    #[napi]
+    pub async fn remove_account(&self, id: u32) -> Result<()> {
+        self.inner
+            .write()
+            .await
+            .remove_account(id)
+            .await
+            .map_err(to_napi_err)
+    }
+
+    /// Returns the account with the given ID or null if it does not exist.
+    #[napi]
+    pub async fn get_account(&self, id: u32) -> Option<Context> {
+        let inner = self.inner.read().await.get_account(id)?;
+        inner.open_lazily().await;
+        Some(Context { inner })
+    }
+
+    /// Starts IO of all accounts.
+    #[napi]
+    pub async fn start_io(&self) {
+        self.inner.read().await.start_io().await;
+    }
+
+    /// Stops IO of all accounts.
+    #[napi]
+    pub async fn stop_io(&self) {
+        self.inner.read().await.stop_io().await;
+    }
+
+    /// Returns an emitter of the events of all accounts.
+    #[napi]
+    pub async fn get_event_emitter(&self) -> EventEmitter {
+        EventEmitter {
+            inner: self.inner.read().await.get_event_emitter(),
+        }
+    }
+
+    /// Handles a JSON-RPC request and returns the response,
+    /// or null if the request is a notification.
+    #[napi]
+    pub async fn jsonrpc_request(&self, request: String) -> Option<String> {
+        let (client, receiver) = RpcClient::new();
+        let session = RpcSession::new(client, self.api.clone());
+        session.handle_incoming(&request).await;
+        drop(session);
+        let response = receiver.recv().await.ok()?;
+        serde_json::to_string(&response).ok()
+    }
+}
+
+/// Single account.
+#[napi]
+pub struct Context {
+    inner: CoreContext,
+}
+
+#[napi]
+impl Context {
+    /// Account ID.
+    #[napi(getter)]
+    pub fn id(&self) -> u32 {
+        self.inner.get_id()
+    }
+
+    /// Returns the value of a configuration option, e.g. `addr`.
+    #[napi]
+    pub async fn get_config(&self, key: String) -> Result<Option<String>> {
+        let key = Config::from_str(&key).map_err(|err| to_napi_err(err.into()))?;
+        self.inner.get_config(key).await.map_err(to_napi_err)
+    }
+
+    /// Sets a configuration option, or resets it to the default if `value` is null.
+    #[napi]
+    pub async fn set_config(&self, key: String, value: Option<String>) -> Result<()> {
+        let key = Config::from_str(&key).map_err(|err| to_napi_err(err.into()))?;
+        self.inner
+            .set_config(key, value.as_deref())
+            .await
+            .map_err(to_napi_err)
+    }
+
+    /// Returns true if the account is configured.
+    #[napi]
+    pub async fn is_configured(&self) -> Result<bool> {
+        self.inner.is_configured().await.map_err(to_napi_err)
+    }
+
+    /// Configures the account with the options set before, e.g. `addr` and `mail_pw`.
+    #[napi]
+    pub async fn configure(&self) -> Result<()> {
+        self.inner.configure().await.map_err(to_napi_err)
+    }
+
+    /// Starts IO of the account.
+    #[napi]
+    pub async fn start_io(&self) {
+        self.inner.start_io().await;
+    }
+
+    /// Stops IO of the account.
+    #[napi]
+    pub async fn stop_io(&self) {
+        self.inner.stop_io().await;
+    }
+
+    /// Returns the IDs of the messages in the chat, oldest first.
+    #[napi]
+    pub async fn get_message_ids(&self, chat_id: u32) -> Result<Vec<u32>> {
+        let items = chat::get_chat_msgs(&self.inner, ChatId::new(chat_id))
+            .await
+            .map_err(to_napi_err)?;
+        Ok(items
+            .into_iter()
+            .filter_map(|item| match item {
+                ChatItem::Message { msg_id } => Some(msg_id.to_u32()),
+                ChatItem::DayMarker { .. } => None,
+            })
+            .collect())
+    }
+
+    /// Returns the message in the same representation as the JSON-RPC `get_message` method.
+    #[napi]
+    pub async fn get_message(&self, msg_id: u32) -> Result<Value> {
+        let message = MessageObject::from_message_id(&self.inner, msg_id)
+            .await
+            .map_err(to_napi_err)?;
+        serde_json::to_value(message).map_err(|err| to_napi_err(err.into()))
+    }
+
+    /// Sends a text message to the chat and returns the ID of the message.
+    #[napi]
+    pub async fn send_text_message(&self, chat_id: u32, text: String) -> Result<u32> {
+        let msg_id = chat::send_text_msg(&self.inner, ChatId::new(chat_id), text)
+            .await
+            .map_err(to_napi_err)?;
+        Ok(msg_id.to_u32())
+    }
+
+    /// Marks the messages as seen.
+    #[napi]
+    pub async fn markseen_messages(&self, msg_ids: Vec<u32>) -> Result<()> {
+        let msg_ids = msg_ids.into_iter().map(MsgId::new).collect();
+        message::markseen_msgs(&self.inner, msg_ids)
+            .await
+            .map_err(to_napi_err)
+    }
+
+    /// Deletes the messages, also from the server.
+    #[napi]
+    pub async fn delete_messages(&self, msg_ids: Vec<u32>) -> Result<()> {
+        let msg_ids: Vec<MsgId> = msg_ids.into_iter().map(MsgId::new).collect();
+        message::delete_msgs(&self.inner, &msg_ids)
+            .await
+            .map_err(to_napi_err)
+    }
+}
+
+/// Emitter of core events.
+///
+/// In JavaScript it is an async iterator,
+/// so events can be consumed with `for await (const event of emitter)`.
+#[napi]
+pub struct EventEmitter {
+    inner: deltachat::EventEmitter,
+}
+
+#[napi]
+impl EventEmitter {
+    /// Waits for the next event, in the same representation as the JSON-RPC `get_next_event` method.
+    ///
+    /// Returns null once no more events can be received.
+    #[napi]
+    pub async fn recv(&self) -> Result<Option<Value>> {
+        match self.inner.recv().await {
+            Some(event) => serde_json::to_value(Event::from(event))
+                .map(Some)
+                .map_err(|err| to_napi_err(err.into())),
+            None => Ok(None),
+        }
+    }
+}
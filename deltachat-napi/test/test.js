@@ -0,0 +1,45 @@
+const assert = require('node:assert')
+const { mkdtempSync } = require('node:fs')
+const { tmpdir } = require('node:os')
+const { join } = require('node:path')
+const { test } = require('node:test')
+
+const { openAccounts } = require('..')
+
+test('accounts and messages', async () => {
+  const accounts = await openAccounts(
+    join(mkdtempSync(join(tmpdir(), 'deltachat-napi-')), 'accounts')
+  )
+  const accountId = await accounts.addAccount()
+  assert.deepStrictEqual(await accounts.getAll(), [accountId])
+
+  const context = await accounts.getAccount(accountId)
+  assert.strictEqual(context.id, accountId)
+  await context.setConfig('displayname', 'Alice')
+  assert.strictEqual(await context.getConfig('displayname'), 'Alice')
+  assert.strictEqual(await context.isConfigured(), false)
+  assert.strictEqual(await accounts.getAccount(accountId + 1), null)
+
+  const response = await accounts.jsonrpcRequest(
+    JSON.stringify({
+      jsonrpc: '2.0',
+      id: 1,
+      method: 'get_config',
+      params: [accountId, 'displayname'],
+    })
+  )
+  assert.strictEqual(JSON.parse(response).result, 'Alice')
+})
+
+test('events are an async iterator', async () => {
+  const accounts = await openAccounts(
+    join(mkdtempSync(join(tmpdir(), 'deltachat-napi-')), 'accounts')
+  )
+  const emitter = await accounts.getEventEmitter()
+  await accounts.addAccount()
+  for await (const event of emitter) {
+    assert.strictEqual(typeof event.context_id, 'number')
+    assert.strictEqual(typeof event.event.type, 'string')
+    break
+  }
+})
@@ -0,0 +1,128 @@
+//! Native Python bindings to the Delta Chat JSON-RPC API.
+//!
+//! Requests are handled inside the Python process on a tokio runtime
+//! and can be awaited from asyncio, so no `deltachat-rpc-server` subprocess is needed.
+//! Use them through `NativeRpc` of `deltachat-rpc-client`.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use deltachat::context::Context;
+use deltachat_jsonrpc::api::{Accounts, CommandApi};
+use deltachat_jsonrpc::yerpc::RpcServer;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use tokio::sync::RwLock;
+
+create_exception!(
+    deltachat_pyo3,
+    JsonRpcError,
+    PyException,
+    "Error response to a JSON-RPC request, the argument is the JSON-encoded error object."
+);
+
+/// Converts an error of the core into a Python exception.
+fn to_py_err(err: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{err:#}"))
+}
+
+/// Returns the context of the account.
+async fn get_context(accounts: &RwLock<Accounts>, account_id: u32) -> PyResult<Context> {
+    let context = accounts
+        .read()
+        .await
+        .get_account(account_id)
+        .ok_or_else(|| PyValueError::new_err(format!("account {account_id} does not exist")))?;
+    context.open_lazily().await;
+    Ok(context)
+}
+
+/// Accounts directory opened in the Python process.
+#[pyclass]
+struct Rpc {
+    accounts: Arc<RwLock<Accounts>>,
+    api: CommandApi,
+}
+
+#[pymethods]
+impl Rpc {
+    /// Opens the accounts directory, creating it if it does not exist.
+    #[staticmethod]
+    fn open(py: Python<'_>, accounts_dir: PathBuf) -> PyResult<&PyAny> {
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let accounts = Accounts::new(accounts_dir).await.map_err(to_py_err)?;
+            let accounts = Arc::new(RwLock::new(accounts));
+            let api = CommandApi::from_arc(accounts.clone());
+            Ok(Rpc { accounts, api })
+        })
+    }
+
+    /// Calls a JSON-RPC method with JSON-encoded parameters.
+    ///
+    /// Returns the JSON-encoded result or raises `JsonRpcError`.
+    fn call<'py>(&self, py: Python<'py>, method: String, params: String) -> PyResult<&'py PyAny> {
+        let api = self.api.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let params = serde_json::from_str(&params)
+                .map_err(|err| PyValueError::new_err(format!("invalid parameters: {err}")))?;
+            match api.handle_request(method, params).await {
+                Ok(result) => Ok(result.to_string()),
+                Err(err) => {
+                    let error = serde_json::json!({
+                        "code": err.code,
+                        "message": err.message,
+                    });
+                    Err(JsonRpcError::new_err(error.to_string()))
+                }
+            }
+        })
+    }
+
+    /// Reads a file from the blob directory of the account, e.g. a message attachment.
+    fn read_blob<'py>(
+        &self,
+        py: Python<'py>,
+        account_id: u32,
+        path: PathBuf,
+    ) -> PyResult<&'py PyAny> {
+        let accounts = self.accounts.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let ctx = get_context(&accounts, account_id).await?;
+            let data = ctx
+                .read_blob_chunk(&path, 0, u64::MAX)
+                .await
+                .map_err(to_py_err)?;
+            Ok(Python::with_gil(|py| PyBytes::new(py, &data).into_py(py)))
+        })
+    }
+
+    /// Creates a file in the blob directory of the account and returns its path.
+    ///
+    /// The file name is derived from `suggested_name`, e.g. to attach it to a message.
+    fn create_blob<'py>(
+        &self,
+        py: Python<'py>,
+        account_id: u32,
+        suggested_name: String,
+        data: Vec<u8>,
+    ) -> PyResult<&'py PyAny> {
+        let accounts = self.accounts.clone();
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let ctx = get_context(&accounts, account_id).await?;
+            let path = ctx
+                .create_blob(&suggested_name, &data)
+                .await
+                .map_err(to_py_err)?;
+            Ok(path.to_string_lossy().into_owned())
+        })
+    }
+}
+
+#[pymodule]
+fn deltachat_pyo3(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Rpc>()?;
+    m.add("JsonRpcError", py.get_type::<JsonRpcError>())?;
+    Ok(())
+}
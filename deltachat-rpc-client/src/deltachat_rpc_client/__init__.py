@@ -7,7 +7,7 @@
 from .contact import Contact
 from .deltachat import DeltaChat
 from .message import Message
-from .rpc import Rpc
+from .rpc import NativeRpc, Rpc
 
 __all__ = [
     "Account",
@@ -19,6 +19,7 @@
     "DeltaChat",
     "EventType",
     "Message",
+    "NativeRpc",
     "SpecialContactId",
     "Rpc",
     "run_bot_cli",
@@ -80,6 +80,29 @@ async def configure(self) -> None:
         """Configure an account."""
         await self._rpc.configure(self.id)
 
+    async def export_backup(self, path: str, passphrase: Optional[str] = None) -> None:
+        """Export a backup of the account into the directory `path`.
+
+        Progress is reported with IMEX_PROGRESS events.
+        """
+        await self._rpc.export_backup(self.id, path, passphrase)
+
+    async def import_backup(self, path: str, passphrase: Optional[str] = None) -> None:
+        """Import the backup file `path` into the unconfigured account."""
+        await self._rpc.import_backup(self.id, path, passphrase)
+
+    async def export_self_keys(self, path: str, passphrase: Optional[str] = None) -> None:
+        """Export public and private keys into the directory `path`."""
+        await self._rpc.export_self_keys(self.id, path, passphrase)
+
+    async def import_self_keys(self, path: str, passphrase: Optional[str] = None) -> None:
+        """Import secret keys from the `.asc` files in the directory `path`."""
+        await self._rpc.import_self_keys(self.id, path, passphrase)
+
+    async def stop_ongoing_process(self) -> None:
+        """Cancel an ongoing backup export or import."""
+        await self._rpc.stop_ongoing_process(self.id)
+
     async def create_contact(self, obj: Union[int, str, Contact], name: Optional[str] = None) -> Contact:
         """Create a new Contact or return an existing one.
 
@@ -250,6 +273,15 @@ async def wait_next_messages(self) -> List[Message]:
         next_msg_ids = await self._rpc.wait_next_msgs(self.id)
         return [Message(self, msg_id) for msg_id in next_msg_ids]
 
+    async def wait_next_incoming_messages(self) -> List[Message]:
+        """Wait for new messages and return the incoming ones which are not info messages.
+
+        Messages sent by this account and info messages are skipped.
+        The returned list may be empty.
+        """
+        next_msg_ids = await self._rpc.wait_next_incoming_msgs(self.id)
+        return [Message(self, msg_id) for msg_id in next_msg_ids]
+
     async def get_fresh_messages_in_arrival_order(self) -> List[Message]:
         """Return fresh messages list sorted in the order of their arrival, with ascending IDs."""
         warn(
@@ -87,6 +87,10 @@ async def set_ephemeral_timer(self, timer: int) -> None:
         """Set ephemeral timer of this chat."""
         await self._rpc.set_chat_ephemeral_timer(self.account.id, self.id, timer)
 
+    async def get_ephemeral_timer(self) -> int:
+        """Return ephemeral timer of this chat in seconds, 0 if disabled."""
+        return await self._rpc.get_chat_ephemeral_timer(self.account.id, self.id)
+
     async def get_encryption_info(self) -> str:
         """Return encryption info for this chat."""
         return await self._rpc.get_chat_encryption_info(self.account.id, self.id)
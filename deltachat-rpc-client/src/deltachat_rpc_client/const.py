@@ -44,6 +44,7 @@ class EventType(str, Enum):
     MSGS_NOTICED = "MsgsNoticed"
     MSG_DELIVERED = "MsgDelivered"
     MSG_FAILED = "MsgFailed"
+    MSG_GIVEN_UP = "MsgGivenUp"
     MSG_READ = "MsgRead"
     CHAT_MODIFIED = "ChatModified"
     CHAT_EPHEMERAL_TIMER_MODIFIED = "ChatEphemeralTimerModified"
@@ -52,12 +53,16 @@ class EventType(str, Enum):
     CONFIGURE_PROGRESS = "ConfigureProgress"
     IMEX_PROGRESS = "ImexProgress"
     IMEX_FILE_WRITTEN = "ImexFileWritten"
+    DB_MAINTENANCE_PROGRESS = "DbMaintenanceProgress"
+    HOUSEKEEPING_DONE = "HousekeepingDone"
     SECUREJOIN_INVITER_PROGRESS = "SecurejoinInviterProgress"
     SECUREJOIN_JOINER_PROGRESS = "SecurejoinJoinerProgress"
     CONNECTIVITY_CHANGED = "ConnectivityChanged"
     SELFAVATAR_CHANGED = "SelfavatarChanged"
     WEBXDC_STATUS_UPDATE = "WebxdcStatusUpdate"
     WEBXDC_INSTANCE_DELETED = "WebxdcInstanceDeleted"
+    WEBXDC_REALTIME_DATA = "WebxdcRealtimeData"
+    EVENT_QUEUE_OVERFLOW = "EventQueueOverflow"
 
 
 class ChatType(IntEnum):
@@ -121,3 +126,4 @@ class SystemMessageType(str, Enum):
     EPHEMERAL_TIMER_CHANGED = "EphemeralTimerChanged"
     MULTI_DEVICE_SYNC = "MultiDeviceSync"
     WEBXDC_INFO_MESSAGE = "WebxdcInfoMessage"
+    WEBXDC_REALTIME_DATA = "WebxdcRealtimeData"
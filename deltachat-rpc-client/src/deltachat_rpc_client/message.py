@@ -1,6 +1,6 @@
 import json
 from dataclasses import dataclass
-from typing import TYPE_CHECKING, Union
+from typing import TYPE_CHECKING, Optional, Union
 
 from ._utils import AttrDict
 from .contact import Contact
@@ -39,6 +39,20 @@ async def mark_seen(self) -> None:
         """Mark the message as seen."""
         await self._rpc.markseen_msgs(self.account.id, [self.id])
 
+    async def get_html(self) -> Optional[str]:
+        """Return the HTML part of the message or None if it has no HTML part.
+
+        Whether the message has an HTML part is available as `has_html` of the message snapshot.
+        """
+        return await self._rpc.get_message_html(self.account.id, self.id)
+
+    async def download_full(self) -> None:
+        """Start downloading a partially downloaded message fully.
+
+        The download state is available as `download_state` of the message snapshot.
+        """
+        await self._rpc.download_full_message(self.account.id, self.id)
+
     async def send_webxdc_status_update(self, update: Union[dict, str], description: str) -> None:
         """Send a webxdc status update. This message must be a webxdc."""
         if not isinstance(update, str):
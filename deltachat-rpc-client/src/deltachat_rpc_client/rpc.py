@@ -63,8 +63,10 @@ async def reader_loop(self) -> None:
                 break
             response = json.loads(line)
             if "id" in response:
-                fut = self.request_events.pop(response["id"])
-                fut.set_result(response)
+                fut = self.request_events.pop(response["id"], None)
+                # The future is missing or done if the request was cancelled.
+                if fut is not None and not fut.done():
+                    fut.set_result(response)
             else:
                 print(response)
 
@@ -88,6 +90,17 @@ async def wait_for_event(self, account_id: int) -> Optional[dict]:
         queue = await self.get_queue(account_id)
         return await queue.get()
 
+    def _cancel_request(self, request_id: int) -> None:
+        """Asks the server to cancel the request, e.g. to stop `configure`."""
+        self.request_events.pop(request_id, None)
+        notification = {
+            "jsonrpc": "2.0",
+            "method": "$/cancelRequest",
+            "params": {"id": request_id},
+        }
+        data = (json.dumps(notification) + "\n").encode()
+        self.process.stdin.write(data)  # noqa
+
     def __getattr__(self, attr: str):
         async def method(*args, **kwargs) -> Any:
             self.id += 1
@@ -106,7 +119,11 @@ async def method(*args, **kwargs) -> Any:
             loop = asyncio.get_running_loop()
             fut = loop.create_future()
             self.request_events[request_id] = fut
-            response = await fut
+            try:
+                response = await fut
+            except asyncio.CancelledError:
+                self._cancel_request(request_id)
+                raise
             if "error" in response:
                 raise JsonRpcError(response["error"])
             if "result" in response:
@@ -114,3 +131,49 @@ async def method(*args, **kwargs) -> Any:
             return None
 
         return method
+
+
+class NativeRpc(Rpc):
+    """Same interface as `Rpc`, but requests are handled inside the Python process
+    by the native `deltachat_pyo3` module instead of a `deltachat-rpc-server` subprocess."""
+
+    def __init__(self, accounts_dir: str):
+        super().__init__()
+        self._accounts_dir = str(accounts_dir)
+        self.native: Any
+        self.native_error: type
+
+    async def start(self) -> None:
+        import deltachat_pyo3
+
+        self.native = await deltachat_pyo3.Rpc.open(self._accounts_dir)
+        self.native_error = deltachat_pyo3.JsonRpcError
+        self.event_queues = {}
+        self.closing = False
+        self.events_task = asyncio.create_task(self.events_loop())
+
+    async def close(self) -> None:
+        """Stop IO of all accounts and wait until the events loop finishes."""
+        self.closing = True
+        await self.stop_io_for_all_accounts()
+        await self.events_task
+
+    async def read_blob(self, account_id: int, path: str) -> bytes:
+        """Read a file from the blob directory of the account, e.g. a message attachment."""
+        return await self.native.read_blob(account_id, path)
+
+    async def create_blob(self, account_id: int, suggested_name: str, data: bytes) -> str:
+        """Create a file in the blob directory of the account and return its path."""
+        return await self.native.create_blob(account_id, suggested_name, data)
+
+    def __getattr__(self, attr: str):
+        async def method(*args, **kwargs) -> Any:
+            assert not (args and kwargs), "Mixing positional and keyword arguments"
+
+            try:
+                result = await self.native.call(attr, json.dumps(kwargs or args))
+            except self.native_error as e:
+                raise JsonRpcError(json.loads(e.args[0])) from None
+            return json.loads(result)
+
+        return method
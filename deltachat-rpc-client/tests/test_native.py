@@ -0,0 +1,22 @@
+import pytest
+from deltachat_rpc_client import DeltaChat, NativeRpc
+from deltachat_rpc_client.rpc import JsonRpcError
+
+pytest.importorskip("deltachat_pyo3")
+
+
+@pytest.mark.asyncio()
+async def test_native_rpc(tmp_path) -> None:
+    async with NativeRpc(tmp_path / "accounts") as rpc:
+        system_info = await rpc.get_system_info()
+        assert "deltachat_core_version" in system_info
+
+        account = await DeltaChat(rpc).add_account()
+        await account.set_config("displayname", "Alice")
+        assert await account.get_config("displayname") == "Alice"
+
+        with pytest.raises(JsonRpcError):
+            await rpc.get_message(account.id, 12345)
+
+        path = await rpc.create_blob(account.id, "hello.txt", b"Hello!")
+        assert await rpc.read_blob(account.id, path) == b"Hello!"
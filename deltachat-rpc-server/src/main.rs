@@ -1,23 +1,44 @@
-use std::env;
 ///! Delta Chat core RPC server.
 ///!
-///! It speaks JSON Lines over stdio.
+///! It speaks JSON Lines over stdio,
+///! or over a Unix domain socket or loopback TCP port shared by multiple clients.
+///! Socket clients can switch to MessagePack encoding.
+use std::collections::HashMap;
+use std::env;
 use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::{anyhow, Context as _, Result};
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
+use axum::body::StreamBody;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
 use deltachat::constants::DC_VERSION_STR;
+use deltachat::message::{Message, MsgId};
+use deltachat::SqlConfig;
+use deltachat_jsonrpc::api::scope::Scope;
 use deltachat_jsonrpc::api::{Accounts, CommandApi};
-use futures_lite::stream::StreamExt;
-use tokio::io::{self, AsyncBufReadExt, BufReader};
+use deltachat_jsonrpc::auth::check_token;
+use futures_lite::stream::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+#[cfg(target_family = "unix")]
+use tokio::net::UnixListener;
+use tokio::net::{TcpListener, TcpStream};
 
 #[cfg(target_family = "unix")]
 use tokio::signal::unix as signal_unix;
 
-use tokio::sync::RwLock;
-use tokio::task::JoinHandle;
+use tokio::sync::{mpsc, RwLock};
+use tokio::task::{AbortHandle, JoinHandle, JoinSet};
+use tokio_util::codec::{FramedRead, LengthDelimitedCodec, LinesCodec};
+use tokio_util::io::ReaderStream;
 use tokio_util::sync::CancellationToken;
-use yerpc::{RpcClient, RpcSession};
+use yerpc::{RpcClient, RpcServer, RpcSession};
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
@@ -39,6 +60,12 @@ async fn main_impl() -> Result<()> {
             }
             eprintln!("{}", &*DC_VERSION_STR);
             return Ok(());
+        } else if first_arg.to_str() == Some("--openrpc") {
+            if let Some(arg) = args.next() {
+                return Err(anyhow!("Unrecognized argument {:?}", arg));
+            }
+            println!("{}", CommandApi::openrpc_specification()?);
+            return Ok(());
         } else {
             return Err(anyhow!("Unrecognized option {:?}", first_arg));
         }
@@ -56,36 +83,103 @@ async fn main_impl() -> Result<()> {
 
     let path = std::env::var("DC_ACCOUNTS_PATH").unwrap_or_else(|_| "accounts".to_string());
     log::info!("Starting with accounts directory `{}`.", path);
-    let accounts = Accounts::new(PathBuf::from(&path)).await?;
+    let mut accounts = Accounts::new(PathBuf::from(&path)).await?;
+    if env::var_os("DC_BACKUP_BEFORE_MIGRATE").is_some() {
+        accounts.set_sql_config(SqlConfig {
+            backup_before_migrate: true,
+            ..Default::default()
+        });
+    }
 
     log::info!("Creating JSON-RPC API.");
     let accounts = Arc::new(RwLock::new(accounts));
+
+    if let Ok(addr) = env::var("DC_METRICS_ADDR") {
+        let listener = TcpListener::bind(&addr)
+            .await
+            .with_context(|| format!("failed to listen for metrics requests on {addr}"))?;
+        log::info!("Serving metrics on {}.", addr);
+        tokio::spawn(serve_metrics(listener, accounts.clone()));
+    }
     let state = CommandApi::from_arc(accounts.clone());
 
-    let (client, mut out_receiver) = RpcClient::new();
-    let session = RpcSession::new(client.clone(), state.clone());
+    if let Ok(addr) = env::var("DC_BLOB_ADDR") {
+        let listener = TcpListener::bind(&addr)
+            .await
+            .with_context(|| format!("failed to listen for blob requests on {addr}"))?;
+        let local_addr = listener.local_addr()?;
+        ensure!(
+            local_addr.ip().is_loopback(),
+            "DC_BLOB_ADDR {addr} is not a loopback address"
+        );
+        log::info!("Serving blobs on {}.", local_addr);
+        state
+            .enable_blob_server(format!("http://{local_addr}"))
+            .await;
+        let blob_state = state.clone();
+        let blob_accounts = accounts.clone();
+        tokio::spawn(async move {
+            if let Err(err) = serve_blobs(listener, blob_state, blob_accounts).await {
+                log::error!("Blob server failed: {:#}", err);
+            }
+        });
+    }
+
     let main_cancel = CancellationToken::new();
+    let mut tasks: Vec<JoinHandle<anyhow::Result<()>>> = Vec::new();
+
+    // Tokens clients of socket connections have to send, required for remote connections.
+    let tokens = Arc::new(Tokens::from_env()?);
+
+    if let Ok(addr) = env::var("DC_RPC_ADDR") {
+        let listener = TcpListener::bind(&addr)
+            .await
+            .with_context(|| format!("failed to listen for RPC connections on {addr}"))?;
+        ensure!(
+            listener.local_addr()?.ip().is_loopback() || !tokens.is_empty(),
+            "DC_RPC_ADDR {addr} is not a loopback address, set DC_RPC_TOKEN to accept remote connections"
+        );
+        log::info!("Serving JSON-RPC on {}.", addr);
+        tasks.push(tokio::spawn(serve_tcp(
+            listener,
+            state.clone(),
+            tokens.clone(),
+            main_cancel.clone(),
+        )));
+    }
+
+    #[cfg(target_family = "unix")]
+    if let Some(socket_path) = env::var_os("DC_RPC_SOCKET") {
+        let socket_path = PathBuf::from(socket_path);
+        let listener = bind_unix_socket(&socket_path).await?;
+        log::info!("Serving JSON-RPC on {}.", socket_path.display());
+        tasks.push(tokio::spawn(serve_unix(
+            listener,
+            socket_path,
+            state.clone(),
+            tokens.clone(),
+            main_cancel.clone(),
+        )));
+    }
+
+    if tasks.is_empty() {
+        tasks.push(serve_stdio(state.clone(), main_cancel.clone()));
+    }
 
-    // Send task prints JSON responses to stdout.
     let cancel = main_cancel.clone();
-    let send_task: JoinHandle<anyhow::Result<()>> = tokio::spawn(async move {
+    tasks.push(tokio::spawn(async move {
         let _cancel_guard = cancel.clone().drop_guard();
-        loop {
-            let message = tokio::select! {
-                _ = cancel.cancelled() => break,
-                message = out_receiver.next() => match message {
-                    None => break,
-                    Some(message) => serde_json::to_string(&message)?,
-                }
-            };
-            log::trace!("RPC send {}", message);
-            println!("{message}");
+        tokio::select! {
+            _ = cancel.cancelled() => (),
+            _ = tokio::signal::ctrl_c() => {
+                log::info!("got ctrl-c event");
+            }
         }
         Ok(())
-    });
+    }));
 
     let cancel = main_cancel.clone();
-    let sigterm_task: JoinHandle<anyhow::Result<()>> = tokio::spawn(async move {
+    tasks.push(tokio::spawn(async move {
         #[cfg(target_family = "unix")]
         {
             let _cancel_guard = cancel.clone().drop_guard();
@@ -98,46 +192,641 @@ async fn main_impl() -> Result<()> {
         }
         let _ = cancel;
         Ok(())
-    });
+    }));
 
-    // Receiver task reads JSON requests from stdin.
-    let cancel = main_cancel.clone();
-    let recv_task: JoinHandle<anyhow::Result<()>> = tokio::spawn(async move {
-        let _cancel_guard = cancel.clone().drop_guard();
-        let stdin = io::stdin();
-        let mut lines = BufReader::new(stdin).lines();
+    main_cancel.cancelled().await;
+    accounts.read().await.stop_io().await;
+    drop(accounts);
+    drop(state);
+    for task in tasks {
+        task.await??;
+    }
 
-        loop {
-            let message = tokio::select! {
-                _ = cancel.cancelled() => break,
-                _ = tokio::signal::ctrl_c() => {
-                    log::info!("got ctrl-c event");
-                    break;
+    Ok(())
+}
+
+/// Serves the metrics of all accounts in the Prometheus text format over HTTP.
+async fn serve_metrics(listener: TcpListener, accounts: Arc<RwLock<Accounts>>) {
+    loop {
+        let mut stream = match listener.accept().await {
+            Ok((stream, _addr)) => stream,
+            Err(err) => {
+                log::warn!("Failed to accept metrics connection: {:#}", err);
+                continue;
+            }
+        };
+        let accounts = accounts.clone();
+        tokio::spawn(async move {
+            if let Err(err) = respond_metrics(&mut stream, &accounts).await {
+                log::warn!("Failed to serve metrics: {:#}", err);
+            }
+        });
+    }
+}
+
+async fn respond_metrics(stream: &mut TcpStream, accounts: &RwLock<Accounts>) -> Result<()> {
+    // The request is not parsed, any request returns the metrics.
+    let mut buf = [0u8; 1024];
+    let len = stream.read(&mut buf).await?;
+    log::trace!(
+        "Metrics request {:?}",
+        String::from_utf8_lossy(buf.get(..len).unwrap_or_default())
+    );
+
+    let (status, body) = match accounts.read().await.get_metrics_prometheus().await {
+        Ok(body) => ("200 OK", body),
+        Err(err) => ("500 Internal Server Error", format!("{err:#}\n")),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n\
+         {body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Serves message attachments over HTTP by the tokens of `CommandApi::get_message_blob_url`.
+async fn serve_blobs(
+    listener: TcpListener,
+    state: CommandApi,
+    accounts: Arc<RwLock<Accounts>>,
+) -> Result<()> {
+    let app = Router::new()
+        .route("/:token", get(blob_handler))
+        .with_state((state, accounts));
+    axum::Server::from_tcp(listener.into_std()?)?
+        .http1_header_read_timeout(BLOB_REQUEST_TIMEOUT)
+        .serve(app.into_make_service())
+        .await?;
+    Ok(())
+}
+
+/// Time a blob client has to send the request head.
+const BLOB_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Responds to `GET` and `HEAD` requests of a blob,
+/// supporting single byte ranges so videos can be seeked.
+async fn blob_handler(
+    State((state, accounts)): State<(CommandApi, Arc<RwLock<Accounts>>)>,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|range| range.to_str().ok());
+    match respond_blob(&state, &accounts, &token, range).await {
+        Ok(response) => response,
+        Err(err) => {
+            log::warn!("Failed to serve blob: {:#}", err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn respond_blob(
+    state: &CommandApi,
+    accounts: &RwLock<Accounts>,
+    token: &str,
+    range: Option<&str>,
+) -> Result<Response> {
+    let (account_id, msg_id) = match state.resolve_blob_token(token).await {
+        Some(ids) => ids,
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    let ctx = accounts
+        .read()
+        .await
+        .get_account(account_id)
+        .context("account of blob not found")?;
+    ctx.open_lazily().await;
+    let msg = Message::load_from_db(&ctx, MsgId::new(msg_id)).await?;
+    let (path, mime) = match msg.get_file(&ctx) {
+        Some(path) => (path, msg.get_filemime().unwrap_or_default()),
+        None => return Ok(StatusCode::NOT_FOUND.into_response()),
+    };
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .with_context(|| format!("failed to open blob {}", path.display()))?;
+    let size = file.metadata().await?.len();
+
+    let (status, start, end) = match range {
+        None => (StatusCode::OK, 0, size),
+        Some(range) => match parse_range(range, size) {
+            Some((first, last)) => (StatusCode::PARTIAL_CONTENT, first, last + 1),
+            None => {
+                let content_range = format!("bytes */{size}");
+                return Ok((
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    [(header::CONTENT_RANGE, content_range)],
+                )
+                    .into_response());
+            }
+        },
+    };
+    let content_type = HeaderValue::from_str(&mime)
+        .ok()
+        .filter(|_| !mime.is_empty())
+        .unwrap_or_else(|| HeaderValue::from_static("application/octet-stream"));
+
+    file.seek(std::io::SeekFrom::Start(start)).await?;
+    let body = StreamBody::new(ReaderStream::new(file.take(end - start)));
+    let mut response = (status, body).into_response();
+    let response_headers = response.headers_mut();
+    response_headers.insert(header::CONTENT_TYPE, content_type);
+    response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(end - start));
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if start > 0 || end < size {
+        let content_range = format!("bytes {start}-{}/{size}", end.max(1) - 1);
+        response_headers.insert(
+            header::CONTENT_RANGE,
+            HeaderValue::from_str(&content_range)?,
+        );
+    }
+    Ok(response)
+}
+
+/// Parses the value of a `Range` header with a single byte range.
+///
+/// Returns the first and the last byte of the range,
+/// `None` if the range is not satisfiable or not supported.
+fn parse_range(range: &str, size: u64) -> Option<(u64, u64)> {
+    let last_byte = size.checked_sub(1)?;
+    let (first, last) = range.strip_prefix("bytes=")?.split_once('-')?;
+    if first.is_empty() {
+        // Suffix range, e.g. `bytes=-500` for the last 500 bytes.
+        let suffix_len: u64 = last.parse().ok()?;
+        if suffix_len == 0 {
+            return None;
+        }
+        return Some((size.saturating_sub(suffix_len), last_byte));
+    }
+    let first: u64 = first.parse().ok()?;
+    let last = if last.is_empty() {
+        last_byte
+    } else {
+        last.parse::<u64>().ok()?.min(last_byte)
+    };
+    if first <= last {
+        Some((first, last))
+    } else {
+        None
+    }
+}
+
+/// Serves JSON-RPC requests from stdin, writing responses to stdout.
+///
+/// Cancels `main_cancel` once stdin is closed.
+fn serve_stdio(
+    state: CommandApi,
+    main_cancel: CancellationToken,
+) -> JoinHandle<anyhow::Result<()>> {
+    tokio::spawn(async move {
+        let _cancel_guard = main_cancel.clone().drop_guard();
+        let tokens = Arc::new(Tokens::default());
+        serve_connection_impl(
+            io::stdin(),
+            io::stdout(),
+            state,
+            tokens,
+            main_cancel.clone(),
+        )
+        .await?;
+        if !main_cancel.is_cancelled() {
+            log::info!("EOF reached on stdin");
+        }
+        Ok(())
+    })
+}
+
+/// Accepts JSON-RPC connections on a TCP port.
+async fn serve_tcp(
+    listener: TcpListener,
+    state: CommandApi,
+    tokens: Arc<Tokens>,
+    cancel: CancellationToken,
+) -> anyhow::Result<()> {
+    loop {
+        let stream = tokio::select! {
+            _ = cancel.cancelled() => break,
+            res = listener.accept() => match res {
+                Ok((stream, _addr)) => stream,
+                Err(err) => {
+                    log::warn!("Failed to accept RPC connection: {:#}", err);
+                    continue;
                 }
-                message = lines.next_line() => match message? {
-                    None => {
-                        log::info!("EOF reached on stdin");
-                        break;
-                    }
-                    Some(message) => message,
+            }
+        };
+        tokio::spawn(serve_connection(
+            stream,
+            state.clone(),
+            tokens.clone(),
+            cancel.clone(),
+        ));
+    }
+    Ok(())
+}
+
+/// Binds the Unix domain socket, removing a socket left over from a previous run.
+#[cfg(target_family = "unix")]
+async fn bind_unix_socket(socket_path: &std::path::Path) -> Result<UnixListener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if let Ok(metadata) = tokio::fs::symlink_metadata(socket_path).await {
+        if metadata.file_type().is_socket() {
+            tokio::fs::remove_file(socket_path).await?;
+        }
+    }
+    UnixListener::bind(socket_path).with_context(|| {
+        format!(
+            "failed to listen for RPC connections on {}",
+            socket_path.display()
+        )
+    })
+}
+
+/// Accepts JSON-RPC connections on a Unix domain socket.
+///
+/// Removes the socket file on shutdown.
+#[cfg(target_family = "unix")]
+async fn serve_unix(
+    listener: UnixListener,
+    socket_path: PathBuf,
+    state: CommandApi,
+    tokens: Arc<Tokens>,
+    cancel: CancellationToken,
+) -> anyhow::Result<()> {
+    loop {
+        let stream = tokio::select! {
+            _ = cancel.cancelled() => break,
+            res = listener.accept() => match res {
+                Ok((stream, _addr)) => stream,
+                Err(err) => {
+                    log::warn!("Failed to accept RPC connection: {:#}", err);
+                    continue;
+                }
+            }
+        };
+        tokio::spawn(serve_connection(
+            stream,
+            state.clone(),
+            tokens.clone(),
+            cancel.clone(),
+        ));
+    }
+    tokio::fs::remove_file(&socket_path).await?;
+    Ok(())
+}
+
+/// Serves JSON Lines requests of a single client connection.
+///
+/// Each connection has its own RPC session, all sessions share the accounts.
+/// Requests still being processed when the client disconnects are aborted.
+async fn serve_connection<S>(
+    stream: S,
+    state: CommandApi,
+    tokens: Arc<Tokens>,
+    cancel: CancellationToken,
+) where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    log::info!("RPC client connected.");
+    let (reader, writer) = io::split(stream);
+    if let Err(err) = serve_connection_impl(reader, writer, state, tokens, cancel).await {
+        log::warn!("RPC connection failed: {:#}", err);
+    }
+    log::info!("RPC client disconnected.");
+}
+
+/// Method of the notification cancelling a request, as in the Language Server Protocol.
+///
+/// The ID of the request is passed as `id` parameter, e.g.
+/// `{"jsonrpc":"2.0","method":"$/cancelRequest","params":{"id":1}}`.
+const CANCEL_REQUEST_METHOD: &str = "$/cancelRequest";
+
+/// Error code of cancelled requests, as in the Language Server Protocol.
+const REQUEST_CANCELLED: i32 = -32800;
+
+/// Request being processed.
+struct InFlightRequest {
+    id: serde_json::Value,
+    method: String,
+    params: serde_json::Value,
+    abort_handle: AbortHandle,
+}
+
+async fn serve_connection_impl<R, W>(
+    reader: R,
+    mut writer: W,
+    state: CommandApi,
+    tokens: Arc<Tokens>,
+    cancel: CancellationToken,
+) -> Result<()>
+where
+    R: AsyncRead + Send + Unpin + 'static,
+    W: AsyncWrite + Send + Unpin + 'static,
+{
+    let mut lines = FramedRead::new(reader, LinesCodec::new());
+
+    // The first line may be a handshake instead of a JSON-RPC request.
+    let first_line = tokio::select! {
+        _ = cancel.cancelled() => return Ok(()),
+        line = lines.next() => match line {
+            None => return Ok(()),
+            Some(line) => line?,
+        }
+    };
+    let handshake = Handshake::parse(&first_line);
+    let scope = if tokens.is_empty() {
+        Scope::default()
+    } else {
+        let provided = handshake
+            .as_ref()
+            .and_then(|handshake| handshake.token.as_deref())
+            .unwrap_or_default();
+        match tokens.scope(provided) {
+            Some(scope) => scope,
+            None => {
+                let response = serde_json::json!({ "error": "invalid token" });
+                write_message(&mut writer, Encoding::Json, &response).await?;
+                bail!("client sent invalid token");
+            }
+        }
+    };
+    let (encoding, first_message) = match handshake {
+        Some(handshake) => {
+            let encoding = Encoding::from_name(handshake.encoding.as_deref());
+            let response = serde_json::json!({ "encoding": encoding.name() });
+            write_message(&mut writer, Encoding::Json, &response).await?;
+            (encoding, None)
+        }
+        None => (Encoding::Json, Some(first_line)),
+    };
+    log::info!("RPC client uses {} encoding.", encoding.name());
+
+    let mut incoming: Pin<Box<dyn Stream<Item = Result<String>> + Send>> = match encoding {
+        Encoding::Json => Box::pin(lines.map(|line| -> Result<String> { Ok(line?) })),
+        Encoding::MessagePack => Box::pin(
+            lines
+                .map_decoder(|_| {
+                    LengthDelimitedCodec::builder()
+                        .max_frame_length(MAX_FRAME_LENGTH)
+                        .new_codec()
+                })
+                .map(|frame| -> Result<String> {
+                    let message: serde_json::Value = rmp_serde::from_slice(&frame?)?;
+                    Ok(message.to_string())
+                }),
+        ),
+    };
+
+    let (client, mut out_receiver) = RpcClient::new();
+    let session = RpcSession::new(client, state.clone().with_scope(scope));
+
+    // Error responses of cancelled requests, which do not send a response themselves.
+    let (cancelled_sender, mut cancelled_receiver) = mpsc::unbounded_channel();
+
+    let send_task: JoinHandle<anyhow::Result<()>> = tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                message = out_receiver.next() => match message {
+                    None => break,
+                    Some(message) => write_message(&mut writer, encoding, &message).await?,
+                },
+                Some(response) = cancelled_receiver.recv() => {
+                    write_message(&mut writer, encoding, &response).await?;
                 }
-            };
-            log::trace!("RPC recv {}", message);
-            let session = session.clone();
-            tokio::spawn(async move {
-                session.handle_incoming(&message).await;
-            });
+            }
         }
         Ok(())
     });
 
-    main_cancel.cancelled().await;
-    accounts.read().await.stop_io().await;
-    drop(accounts);
-    drop(state);
-    send_task.await??;
-    sigterm_task.await??;
-    recv_task.await??;
+    let mut requests = JoinSet::new();
+    let mut in_flight: HashMap<String, InFlightRequest> = HashMap::new();
+    let mut next_message = first_message;
+    loop {
+        let message = match next_message.take() {
+            Some(message) => message,
+            None => tokio::select! {
+                _ = cancel.cancelled() => break,
+                Some(res) = requests.join_next() => {
+                    if let Ok(Some(key)) = res {
+                        in_flight.remove(&key);
+                    }
+                    continue;
+                }
+                message = incoming.next() => match message {
+                    None => break,
+                    Some(message) => message?,
+                }
+            },
+        };
+        log::trace!("RPC recv {}", message);
 
+        let request: serde_json::Value = serde_json::from_str(&message).unwrap_or_default();
+        let method = request
+            .get("method")
+            .and_then(|method| method.as_str())
+            .unwrap_or_default();
+        if method == CANCEL_REQUEST_METHOD {
+            let key = request
+                .get("params")
+                .and_then(|params| params.get("id"))
+                .map(|id| id.to_string());
+            if let Some(request) = key.and_then(|key| in_flight.remove(&key)) {
+                cancel_request(&state, request, &cancelled_sender).await;
+            }
+            continue;
+        }
+
+        let id = request.get("id").cloned();
+        let key = id.as_ref().map(|id| id.to_string());
+        let task_key = key.clone();
+        let session = session.clone();
+        let abort_handle = requests.spawn(async move {
+            session.handle_incoming(&message).await;
+            task_key
+        });
+        if let (Some(id), Some(key)) = (id, key) {
+            in_flight.insert(
+                key,
+                InFlightRequest {
+                    id,
+                    method: method.to_string(),
+                    params: request.get("params").cloned().unwrap_or_default(),
+                    abort_handle,
+                },
+            );
+        }
+    }
+
+    // Abort long-polling requests such as `get_next_event`,
+    // so they do not take events from other clients.
+    requests.shutdown().await;
+    drop(session);
+    drop(cancelled_sender);
+    send_task.await?
+}
+
+/// Cancels a request being processed.
+///
+/// Requests running as the ongoing process of an account, e.g. `configure`,
+/// are stopped and return an error themselves.
+/// Other requests are aborted and answered with a `REQUEST_CANCELLED` error.
+async fn cancel_request(
+    state: &CommandApi,
+    request: InFlightRequest,
+    cancelled_sender: &mpsc::UnboundedSender<serde_json::Value>,
+) {
+    log::info!("Cancelling {} request {}.", request.method, request.id);
+    match state
+        .cancel_ongoing_request(&request.method, &request.params)
+        .await
+    {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(err) => log::warn!("Failed to stop ongoing process: {:#}", err),
+    }
+    request.abort_handle.abort();
+    let response = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": request.id,
+        "error": {
+            "code": REQUEST_CANCELLED,
+            "message": "Request cancelled.",
+        },
+    });
+    cancelled_sender.send(response).ok();
+}
+
+/// Writes a message to the client in the given encoding.
+async fn write_message<W, T>(writer: &mut W, encoding: Encoding, message: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    match encoding {
+        Encoding::Json => {
+            let mut message = serde_json::to_string(message)?;
+            log::trace!("RPC send {}", message);
+            message.push('\n');
+            writer.write_all(message.as_bytes()).await?;
+        }
+        Encoding::MessagePack => {
+            let message = rmp_serde::to_vec_named(message)?;
+            writer.write_u32(message.len().try_into()?).await?;
+            writer.write_all(&message).await?;
+        }
+    }
+    writer.flush().await?;
     Ok(())
 }
+
+/// Maximum size of a MessagePack frame received from a client.
+const MAX_FRAME_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Encoding of the messages on a socket connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    /// JSON Lines, the default.
+    Json,
+
+    /// MessagePack, each message is prefixed with its length as a 32-bit big-endian integer.
+    ///
+    /// Saves CPU time and bandwidth when transferring large batches of messages.
+    MessagePack,
+}
+
+impl Encoding {
+    /// Returns the encoding named `name`, JSON if the encoding is not supported.
+    fn from_name(name: Option<&str>) -> Self {
+        match name {
+            Some("messagepack") => Self::MessagePack,
+            _ => Self::Json,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::MessagePack => "messagepack",
+        }
+    }
+}
+
+/// First line of a socket connection sent instead of a JSON-RPC request,
+/// e.g. `{"token":"secret","encoding":"messagepack"}`.
+///
+/// Answered with the encoding used for the connection, e.g. `{"encoding":"messagepack"}`.
+#[derive(Debug, Deserialize)]
+struct Handshake {
+    /// Token configured with `DC_RPC_TOKEN` or `DC_RPC_SCOPED_TOKENS`.
+    token: Option<String>,
+
+    /// Requested encoding, see [`Encoding`].
+    encoding: Option<String>,
+}
+
+impl Handshake {
+    /// Parses a handshake, returns `None` if the line is not a handshake.
+    fn parse(line: &str) -> Option<Self> {
+        let value: serde_json::Value = serde_json::from_str(line).ok()?;
+        if value.as_object()?.contains_key("jsonrpc") {
+            return None;
+        }
+        serde_json::from_value(value).ok()
+    }
+}
+
+/// Tokens accepted in the handshake of socket connections
+/// and the scopes of the sessions authenticated with them.
+#[derive(Debug, Default)]
+struct Tokens(Vec<(String, Scope)>);
+
+impl Tokens {
+    /// Reads the tokens from the environment.
+    ///
+    /// `DC_RPC_TOKEN` grants access to all methods.
+    /// `DC_RPC_SCOPED_TOKENS` is a JSON object mapping tokens to the scopes of their sessions,
+    /// e.g. `{"<token>":{"read_only":true,"account_id":1}}`.
+    fn from_env() -> Result<Self> {
+        let mut tokens = Vec::new();
+        if let Ok(token) = env::var("DC_RPC_TOKEN") {
+            ensure!(!token.is_empty(), "DC_RPC_TOKEN is empty");
+            tokens.push((token, Scope::default()));
+        }
+        if let Ok(scoped_tokens) = env::var("DC_RPC_SCOPED_TOKENS") {
+            let scoped_tokens: HashMap<String, Scope> = serde_json::from_str(&scoped_tokens)
+                .context("DC_RPC_SCOPED_TOKENS is not a JSON object mapping tokens to scopes")?;
+            for (token, scope) in scoped_tokens {
+                ensure!(
+                    !token.is_empty(),
+                    "DC_RPC_SCOPED_TOKENS contains an empty token"
+                );
+                tokens.push((token, scope));
+            }
+        }
+        Ok(Self(tokens))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the scope of a session authenticated with the `provided` token
+    /// or `None` if the token is invalid.
+    fn scope(&self, provided: &str) -> Option<Scope> {
+        // All tokens are checked, so the response time does not reveal which one matched.
+        let mut res = None;
+        for (token, scope) in &self.0 {
+            if check_token(token, provided) {
+                res = Some(*scope);
+            }
+        }
+        res
+    }
+}
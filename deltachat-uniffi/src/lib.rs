@@ -0,0 +1,260 @@
+//! Kotlin and Swift bindings to Delta Chat core generated with [UniFFI](https://mozilla.github.io/uniffi-rs/).
+//!
+//! The API is blocking, the core runs on its own tokio runtime.
+//! Besides the objects exported here, the whole JSON-RPC API is available
+//! through [`Accounts::jsonrpc_request`], so new core APIs
+//! are available on mobile without changes to the bindings.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use deltachat::chat::{self, ChatId};
+use deltachat::config::Config;
+use deltachat::context::Context as CoreContext;
+use deltachat::message::{Message as CoreMessage, MsgId};
+use deltachat_jsonrpc::api::{Accounts as CoreAccounts, CommandApi};
+use deltachat_jsonrpc::events::Event as JsonrpcEvent;
+use deltachat_jsonrpc::yerpc::{RpcClient, RpcSession};
+use once_cell::sync::Lazy;
+use tokio::runtime::Runtime;
+use tokio::sync::RwLock;
+
+uniffi::setup_scaffolding!();
+
+static RT: Lazy<Runtime> = Lazy::new(|| Runtime::new().expect("unable to create tokio runtime"));
+
+/// Error returned by the core.
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum DcError {
+    #[error("{0}")]
+    Failed(String),
+}
+
+impl From<anyhow::Error> for DcError {
+    fn from(err: anyhow::Error) -> Self {
+        DcError::Failed(format!("{err:#}"))
+    }
+}
+
+type Result<T> = std::result::Result<T, DcError>;
+
+/// Event emitted by an account.
+#[derive(uniffi::Record)]
+pub struct Event {
+    /// Account ID.
+    pub context_id: u32,
+
+    /// Event in the same JSON representation as returned by the JSON-RPC `get_next_event` method.
+    pub json: String,
+}
+
+/// Message loaded from the database.
+#[derive(uniffi::Record)]
+pub struct Message {
+    pub id: u32,
+    pub chat_id: u32,
+    pub from_id: u32,
+    pub text: Option<String>,
+    pub timestamp: i64,
+
+    /// One of the `DC_MSG_*` constants.
+    pub viewtype: u32,
+
+    /// Absolute path of the attachment.
+    pub file: Option<String>,
+}
+
+/// Account manager.
+#[derive(uniffi::Object)]
+pub struct Accounts {
+    inner: Arc<RwLock<CoreAccounts>>,
+    api: CommandApi,
+    events: deltachat::EventEmitter,
+}
+
+#[uniffi::export]
+impl Accounts {
+    /// Opens the accounts directory, creating it if it does not exist.
+    #[uniffi::constructor]
+    pub fn new(dir: String) -> Result<Arc<Self>> {
+        let accounts = RT.block_on(CoreAccounts::new(PathBuf::from(dir)))?;
+        let events = accounts.get_event_emitter();
+        let inner = Arc::new(RwLock::new(accounts));
+        let api = CommandApi::from_arc(inner.clone());
+        Ok(Arc::new(Accounts { inner, api, events }))
+    }
+
+    /// Returns the IDs of all accounts.
+    pub fn get_all(&self) -> Vec<u32> {
+        RT.block_on(async { self.inner.read().await.get_all() })
+    }
+
+    /// Adds a new account and returns its ID.
+    pub fn add_account(&self) -> Result<u32> {
+        let id = RT.block_on(async { self.inner.write().await.add_account().await })?;
+        Ok(id)
+    }
+
+    /// Removes the account and all its data.
+    pub fn remove_account(&self, id: u32) -> Result<()> {
+        RT.block_on(async { self.inner.write().await.remove_account(id).await })?;
+        Ok(())
+    }
+
+    /// Returns the account with the given ID or null if it does not exist.
+    pub fn get_account(&self, id: u32) -> Option<Arc<Context>> {
+        let inner = RT.block_on(async {
+            let inner = self.inner.read().await.get_account(id)?;
+            inner.open_lazily().await;
+            Some(inner)
+        })?;
+        Some(Arc::new(Context { inner }))
+    }
+
+    /// Starts IO of all accounts.
+    pub fn start_io(&self) {
+        RT.block_on(async { self.inner.read().await.start_io().await })
+    }
+
+    /// Stops IO of all accounts.
+    pub fn stop_io(&self) {
+        RT.block_on(async { self.inner.read().await.stop_io().await })
+    }
+
+    /// Waits for the next event of any account.
+    ///
+    /// Returns null once no more events can be received.
+    pub fn get_next_event(&self) -> Option<Event> {
+        let event = RT.block_on(self.events.recv())?;
+        let context_id = event.id;
+        let json = serde_json::to_string(&JsonrpcEvent::from(event)).ok()?;
+        Some(Event { context_id, json })
+    }
+
+    /// Handles a JSON-RPC request and returns the response,
+    /// or null if the request is a notification.
+    pub fn jsonrpc_request(&self, request: String) -> Option<String> {
+        RT.block_on(async {
+            let (client, receiver) = RpcClient::new();
+            let session = RpcSession::new(client, self.api.clone());
+            session.handle_incoming(&request).await;
+            drop(session);
+            let response = receiver.recv().await.ok()?;
+            serde_json::to_string(&response).ok()
+        })
+    }
+}
+
+/// Single account.
+#[derive(uniffi::Object)]
+pub struct Context {
+    inner: CoreContext,
+}
+
+#[uniffi::export]
+impl Context {
+    /// Returns the account ID.
+    pub fn id(&self) -> u32 {
+        self.inner.get_id()
+    }
+
+    /// Returns the value of a configuration option, e.g. `addr`.
+    pub fn get_config(&self, key: String) -> Result<Option<String>> {
+        let key = Config::from_str(&key).map_err(anyhow::Error::from)?;
+        let value = RT.block_on(self.inner.get_config(key))?;
+        Ok(value)
+    }
+
+    /// Sets a configuration option, or resets it to the default if `value` is null.
+    pub fn set_config(&self, key: String, value: Option<String>) -> Result<()> {
+        let key = Config::from_str(&key).map_err(anyhow::Error::from)?;
+        RT.block_on(self.inner.set_config(key, value.as_deref()))?;
+        Ok(())
+    }
+
+    /// Returns true if the account is configured.
+    pub fn is_configured(&self) -> Result<bool> {
+        let configured = RT.block_on(self.inner.is_configured())?;
+        Ok(configured)
+    }
+
+    /// Configures the account with the options set before, e.g. `addr` and `mail_pw`.
+    pub fn configure(&self) -> Result<()> {
+        RT.block_on(self.inner.configure())?;
+        Ok(())
+    }
+
+    /// Starts IO of the account.
+    pub fn start_io(&self) {
+        RT.block_on(self.inner.start_io())
+    }
+
+    /// Stops IO of the account.
+    pub fn stop_io(&self) {
+        RT.block_on(self.inner.stop_io())
+    }
+
+    /// Loads the message from the database.
+    pub fn get_message(&self, msg_id: u32) -> Result<Message> {
+        let msg = RT.block_on(CoreMessage::load_from_db(&self.inner, MsgId::new(msg_id)))?;
+        Ok(Message {
+            id: msg.get_id().to_u32(),
+            chat_id: msg.get_chat_id().to_u32(),
+            from_id: msg.get_from_id().to_u32(),
+            text: msg.get_text(),
+            timestamp: msg.get_timestamp(),
+            viewtype: msg.get_viewtype() as u32,
+            file: msg
+                .get_file(&self.inner)
+                .map(|path| path.to_string_lossy().into_owned()),
+        })
+    }
+
+    /// Sends a text message to the chat and returns the ID of the message.
+    pub fn send_text_message(&self, chat_id: u32, text: String) -> Result<u32> {
+        let msg_id = RT.block_on(chat::send_text_msg(&self.inner, ChatId::new(chat_id), text))?;
+        Ok(msg_id.to_u32())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accounts() {
+        let dir = tempfile::tempdir().unwrap();
+        let accounts_dir = dir.path().join("accounts");
+        let accounts = Accounts::new(accounts_dir.to_string_lossy().into_owned()).unwrap();
+        let id = accounts.add_account().unwrap();
+        assert_eq!(accounts.get_all(), vec![id]);
+        assert!(accounts.get_account(id + 1).is_none());
+
+        let context = accounts.get_account(id).unwrap();
+        assert_eq!(context.id(), id);
+        context
+            .set_config("displayname".to_string(), Some("Alice".to_string()))
+            .unwrap();
+        assert_eq!(
+            context.get_config("displayname".to_string()).unwrap(),
+            Some("Alice".to_string())
+        );
+        assert!(context.get_config("foo".to_string()).is_err());
+        assert!(!context.is_configured().unwrap());
+
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "get_config",
+            "params": [id, "displayname"],
+        });
+        let response = accounts.jsonrpc_request(request.to_string()).unwrap();
+        let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["result"], "Alice");
+
+        accounts.remove_account(id).unwrap();
+        assert!(accounts.get_all().is_empty());
+    }
+}
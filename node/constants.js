@@ -33,9 +33,11 @@ module.exports = {
   DC_EVENT_CONFIGURE_PROGRESS: 2041,
   DC_EVENT_CONNECTIVITY_CHANGED: 2100,
   DC_EVENT_CONTACTS_CHANGED: 2030,
+  DC_EVENT_DB_MAINTENANCE_PROGRESS: 2055,
   DC_EVENT_DELETED_BLOB_FILE: 151,
   DC_EVENT_ERROR: 400,
   DC_EVENT_ERROR_SELF_NOT_IN_GROUP: 410,
+  DC_EVENT_HOUSEKEEPING_DONE: 2056,
   DC_EVENT_IMAP_CONNECTED: 102,
   DC_EVENT_IMAP_INBOX_IDLE: 106,
   DC_EVENT_IMAP_MESSAGE_DELETED: 104,
@@ -50,8 +52,11 @@ module.exports = {
   DC_EVENT_MSGS_NOTICED: 2008,
   DC_EVENT_MSG_DELIVERED: 2010,
   DC_EVENT_MSG_FAILED: 2012,
+  DC_EVENT_MSG_GIVEN_UP: 2013,
   DC_EVENT_MSG_READ: 2015,
   DC_EVENT_NEW_BLOB_FILE: 150,
+  DC_EVENT_OAUTH2_TOKEN_REQUESTED: 2116,
+  DC_EVENT_QUOTA_WARNING: 2115,
   DC_EVENT_REACTIONS_CHANGED: 2001,
   DC_EVENT_SECUREJOIN_INVITER_PROGRESS: 2060,
   DC_EVENT_SECUREJOIN_JOINER_PROGRESS: 2061,
@@ -60,6 +65,7 @@ module.exports = {
   DC_EVENT_SMTP_MESSAGE_SENT: 103,
   DC_EVENT_WARNING: 300,
   DC_EVENT_WEBXDC_INSTANCE_DELETED: 2121,
+  DC_EVENT_WEBXDC_REALTIME_DATA: 2150,
   DC_EVENT_WEBXDC_STATUS_UPDATE: 2120,
   DC_GCL_ADD_ALLDONE_HINT: 4,
   DC_GCL_ADD_SELF: 2,
@@ -106,9 +112,19 @@ module.exports = {
   DC_MSG_VIDEOCHAT_INVITATION: 70,
   DC_MSG_VOICE: 41,
   DC_MSG_WEBXDC: 80,
+  DC_NETWORK_CLASS_METERED: 1,
+  DC_NETWORK_CLASS_ROAMING: 2,
+  DC_NETWORK_CLASS_UNMETERED: 0,
+  DC_NOTIFICATION_PRIVACY_FULL: 0,
+  DC_NOTIFICATION_PRIVACY_GENERIC: 2,
+  DC_NOTIFICATION_PRIVACY_SENDER_ONLY: 1,
   DC_PROVIDER_STATUS_BROKEN: 3,
   DC_PROVIDER_STATUS_OK: 1,
   DC_PROVIDER_STATUS_PREPARATION: 2,
+  DC_PUSH_NOT_SUPPORTED: 1,
+  DC_PUSH_NO_TOKEN: 0,
+  DC_PUSH_PENDING: 2,
+  DC_PUSH_REGISTERED: 3,
   DC_QR_ACCOUNT: 250,
   DC_QR_ADDR: 320,
   DC_QR_ASK_VERIFYCONTACT: 200,
@@ -235,7 +251,11 @@ module.exports = {
   DC_STR_MSGGRPNAME: 15,
   DC_STR_MSGLOCATIONDISABLED: 65,
   DC_STR_MSGLOCATIONENABLED: 64,
+  DC_STR_NEW_MSG: 166,
+  DC_STR_NEW_MSGS: 167,
   DC_STR_NOMESSAGES: 1,
+  DC_STR_NOTIFICATION_MSGS_FROM: 164,
+  DC_STR_NOTIFICATION_MSGS_IN: 165,
   DC_STR_NOT_CONNECTED: 121,
   DC_STR_NOT_SUPPORTED_BY_PROVIDER: 113,
   DC_STR_ONE_MOMENT: 106,
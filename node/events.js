@@ -21,6 +21,7 @@ module.exports = {
   2008: 'DC_EVENT_MSGS_NOTICED',
   2010: 'DC_EVENT_MSG_DELIVERED',
   2012: 'DC_EVENT_MSG_FAILED',
+  2013: 'DC_EVENT_MSG_GIVEN_UP',
   2015: 'DC_EVENT_MSG_READ',
   2020: 'DC_EVENT_CHAT_MODIFIED',
   2021: 'DC_EVENT_CHAT_EPHEMERAL_TIMER_MODIFIED',
@@ -29,10 +30,15 @@ module.exports = {
   2041: 'DC_EVENT_CONFIGURE_PROGRESS',
   2051: 'DC_EVENT_IMEX_PROGRESS',
   2052: 'DC_EVENT_IMEX_FILE_WRITTEN',
+  2055: 'DC_EVENT_DB_MAINTENANCE_PROGRESS',
+  2056: 'DC_EVENT_HOUSEKEEPING_DONE',
   2060: 'DC_EVENT_SECUREJOIN_INVITER_PROGRESS',
   2061: 'DC_EVENT_SECUREJOIN_JOINER_PROGRESS',
   2100: 'DC_EVENT_CONNECTIVITY_CHANGED',
   2110: 'DC_EVENT_SELFAVATAR_CHANGED',
+  2115: 'DC_EVENT_QUOTA_WARNING',
+  2116: 'DC_EVENT_OAUTH2_TOKEN_REQUESTED',
   2120: 'DC_EVENT_WEBXDC_STATUS_UPDATE',
-  2121: 'DC_EVENT_WEBXDC_INSTANCE_DELETED'
+  2121: 'DC_EVENT_WEBXDC_INSTANCE_DELETED',
+  2150: 'DC_EVENT_WEBXDC_REALTIME_DATA'
 }
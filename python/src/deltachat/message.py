@@ -162,6 +162,25 @@ def send_status_update(self, json_data: Union[str, dict], description: str) -> b
             ),
         )
 
+    def get_webxdc_info(self) -> dict:
+        """Get the info of this webxdc message, e.g. the name of the app and its icon."""
+        return json.loads(from_dc_charpointer(lib.dc_msg_get_webxdc_info(self._dc_msg)))
+
+    def get_webxdc_blob(self, filename: str) -> Optional[bytes]:
+        """Get a file from the archive of this webxdc message.
+
+        :param filename: The name of the file inside the archive, e.g. "index.html".
+        :returns: The content of the file, None if the file does not exist.
+        """
+        ret_bytes = ffi.new("size_t*")
+        blob = lib.dc_msg_get_webxdc_blob(self._dc_msg, as_dc_charpointer(filename), ret_bytes)
+        if blob == ffi.NULL:
+            return None
+        try:
+            return bytes(ffi.buffer(blob, ret_bytes[0]))
+        finally:
+            lib.dc_str_unref(blob)
+
     def send_reaction(self, reaction: str):
         """Send a reaction to message and return the resulting Message instance."""
         msg_id = lib.dc_send_reaction(self.account._dc_context, self.id, as_dc_charpointer(reaction))
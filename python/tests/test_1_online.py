@@ -306,6 +306,9 @@ def test_webxdc_message(acfactory, data, lp):
     msg1 = chat.send_msg(msg1)
     assert msg1.is_webxdc()
     assert msg1.filename
+    assert msg1.get_webxdc_info()["name"] == "minimal.xdc"
+    assert b"<html" in msg1.get_webxdc_blob("index.html")
+    assert msg1.get_webxdc_blob("nonexistent.html") is None
 
     assert msg1.send_status_update({"payload": "test1"}, "some test data")
     assert msg1.send_status_update({"payload": "test2"}, "more test data")
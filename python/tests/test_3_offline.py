@@ -1,3 +1,4 @@
+import json
 import os
 import time
 from datetime import datetime, timedelta, timezone
@@ -6,7 +7,7 @@
 
 from deltachat import Account, const
 from deltachat.capi import ffi, lib
-from deltachat.cutil import iter_array
+from deltachat.cutil import as_dc_charpointer, from_dc_charpointer, iter_array
 from deltachat.hookspec import account_hookimpl
 from deltachat.message import Message
 from deltachat.tracker import ImexFailed
@@ -264,6 +265,33 @@ def test_chat_idempotent(self, chat1, ac1):
         else:
             pytest.fail("could not find chat")
 
+    def test_chatlist_batch_getters(self, chat1, ac1):
+        msg = chat1.send_text("hello")
+        dc_chatlist = ffi.gc(lib.dc_get_chatlist(ac1._dc_context, 0, ffi.NULL, 0), lib.dc_chatlist_unref)
+        cnt = lib.dc_chatlist_get_cnt(dc_chatlist)
+        assert cnt > 0
+
+        chat_ids = ffi.new("uint32_t[]", cnt)
+        msg_ids = ffi.new("uint32_t[]", cnt)
+        assert lib.dc_chatlist_get_ids(dc_chatlist, 0, cnt + 10, chat_ids, msg_ids) == cnt
+        for i in range(cnt):
+            assert chat_ids[i] == lib.dc_chatlist_get_chat_id(dc_chatlist, i)
+            assert msg_ids[i] == lib.dc_chatlist_get_msg_id(dc_chatlist, i)
+        assert lib.dc_chatlist_get_ids(dc_chatlist, cnt, 1, chat_ids, ffi.NULL) == 0
+
+        summaries = json.loads(from_dc_charpointer(lib.dc_chatlist_get_summaries_json(dc_chatlist, 0, cnt)))
+        assert len(summaries) == cnt
+        summary = next(summary for summary in summaries if summary["chat_id"] == chat1.id)
+        assert summary["msg_id"] == msg.id
+        assert summary["text2"] == "hello"
+
+        summaries = json.loads(
+            from_dc_charpointer(lib.dc_get_msg_summaries_json(ac1._dc_context, [msg.id, 123123], 2)),
+        )
+        assert summaries[0]["msg_id"] == msg.id
+        assert summaries[0]["text2"] == "hello"
+        assert summaries[1] is None
+
     def test_group_chat_add_second_account(self, acfactory):
         ac1 = acfactory.get_pseudo_configured_account()
         ac2 = acfactory.get_pseudo_configured_account()
@@ -470,6 +498,20 @@ def test_message_file(self, chat1, data, lp, fn, typein, typeout):
         assert msg2 != msg
         assert msg2.filename != msg.filename
 
+    def test_get_blob_bytes(self, chat1, ac1, tmpdir):
+        bin = tmpdir.join("some.bin")
+        bin.write_binary(b"\x00\x01\x02" * 1000)
+        msg = chat1.send_file(bin.strpath)
+
+        ret_bytes = ffi.new("size_t*")
+        blob = lib.dc_get_blob_bytes(ac1._dc_context, as_dc_charpointer(msg.filename), ret_bytes)
+        assert blob != ffi.NULL
+        assert bytes(ffi.buffer(blob, ret_bytes[0])) == b"\x00\x01\x02" * 1000
+        lib.dc_str_unref(blob)
+
+        # Files outside of the blob directory cannot be read.
+        assert lib.dc_get_blob_bytes(ac1._dc_context, as_dc_charpointer(bin.strpath), ret_bytes) == ffi.NULL
+
     def test_create_contact(self, acfactory):
         ac1 = acfactory.get_pseudo_configured_account()
         email = "hello <hello@example.org>"
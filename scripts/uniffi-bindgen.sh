@@ -0,0 +1,20 @@
+#!/bin/sh
+#
+# Generate Kotlin and Swift bindings of deltachat-uniffi
+# into deltachat-uniffi/bindings/kotlin and deltachat-uniffi/bindings/swift.
+
+set -x
+set -e
+
+cargo build --release -p deltachat-uniffi
+
+case "$(uname)" in
+    Darwin) LIBRARY=target/release/libdeltachat_uniffi.dylib ;;
+    *) LIBRARY=target/release/libdeltachat_uniffi.so ;;
+esac
+
+for LANGUAGE in kotlin swift; do
+    cargo run --release -p deltachat-uniffi --features cli --bin uniffi-bindgen -- \
+        generate --library "$LIBRARY" --language "$LANGUAGE" \
+        --out-dir "deltachat-uniffi/bindings/$LANGUAGE"
+done
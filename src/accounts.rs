@@ -2,15 +2,27 @@
 
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use anyhow::{ensure, Context as _, Result};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 use uuid::Uuid;
 
+use crate::chat::ChatId;
+use crate::config::Config as DcConfig;
+use crate::constants::{NetworkClass, DC_CHAT_ID_LAST_SPECIAL};
+use crate::contact::ContactId;
 use crate::context::Context;
-use crate::events::{Event, EventEmitter, EventType, Events};
+use crate::events::{
+    AccountsEvent, AccountsEventEmitter, AccountsEvents, Event, EventEmitter, EventQueueConfig,
+    EventType, Events,
+};
+use crate::message::{Message, MsgId};
+use crate::socks::Socks5Config;
+use crate::sql::{MigrationPlan, SqlConfig};
 use crate::stock_str::StockStrings;
 
 /// Account manager, that can handle multiple accounts in a single place.
@@ -24,6 +36,12 @@ pub struct Accounts {
     /// Event channel to emit account manager errors.
     events: Events,
 
+    /// Event channel to emit account additions and removals.
+    manager_events: AccountsEvents,
+
+    /// Configuration of the database connections of all accounts.
+    sql_config: SqlConfig,
+
     /// Stock string translations shared by all created contexts.
     ///
     /// This way changing a translation for one context automatically
@@ -75,19 +93,62 @@ pub async fn open(dir: PathBuf) -> Result<Self> {
             config,
             accounts,
             events,
+            manager_events: AccountsEvents::new(),
+            sql_config: Default::default(),
             stockstrings,
         })
     }
 
-    /// Returns an account by its `id`:
+    /// Returns an account by its `id`.
+    ///
+    /// The databases of loaded accounts are not opened
+    /// until IO is started for them, see [`Context::open_lazily`].
     pub fn get_account(&self, id: u32) -> Option<Context> {
         self.accounts.get(&id).cloned()
     }
 
+    /// Returns the schema migrations which run when the database of an account is opened,
+    /// without opening it.
+    ///
+    /// `passphrase` is used to read the database if it is encrypted.
+    pub async fn get_migration_plan(&self, id: u32, passphrase: String) -> Result<MigrationPlan> {
+        let context = self
+            .accounts
+            .get(&id)
+            .with_context(|| format!("no account with id {id}"))?;
+        context.get_migration_plan(passphrase).await
+    }
+
     /// Returns the currently selected account.
     pub fn get_selected_account(&self) -> Option<Context> {
-        let id = self.config.get_selected_account();
-        self.accounts.get(&id).cloned()
+        self.get_account(self.config.get_selected_account())
+    }
+
+    /// Opens the database of the selected account in the background,
+    /// so it is ready when the account is accessed.
+    ///
+    /// Meant to be called by UIs right after opening the account manager.
+    pub fn prewarm_selected_account(&self) {
+        if let Some(context) = self.get_selected_account() {
+            tokio::spawn(async move { context.open_lazily().await });
+        }
+    }
+
+    /// Returns all accounts without opening their databases.
+    pub(crate) fn contexts(&self) -> impl Iterator<Item = &Context> {
+        self.accounts.values()
+    }
+
+    /// Sets the configuration of the database connections of all accounts,
+    /// see [`SqlConfig`].
+    ///
+    /// Changes take effect when the database of an account is opened,
+    /// so this should be called right after opening the account manager.
+    pub fn set_sql_config(&mut self, config: SqlConfig) {
+        self.sql_config = config;
+        for account in self.accounts.values() {
+            account.set_sql_config(config);
+        }
     }
 
     /// Returns the currently selected account's id or None if no account is selected.
@@ -112,14 +173,18 @@ pub async fn add_account(&mut self) -> Result<u32> {
         let account_config = self.config.new_account().await?;
         let dbfile = account_config.dbfile(&self.dir);
 
-        let ctx = Context::new(
+        let ctx = Context::new_closed(
             &dbfile,
             account_config.id,
             self.events.clone(),
             self.stockstrings.clone(),
         )
         .await?;
+        ctx.set_sql_config(self.sql_config);
+        ctx.open(String::new()).await?;
         self.accounts.insert(account_config.id, ctx);
+        self.manager_events
+            .emit(AccountsEvent::AccountAdded(account_config.id));
 
         Ok(account_config.id)
     }
@@ -135,7 +200,10 @@ pub async fn add_closed_account(&mut self) -> Result<u32> {
             self.stockstrings.clone(),
         )
         .await?;
+        ctx.set_sql_config(self.sql_config);
         self.accounts.insert(account_config.id, ctx);
+        self.manager_events
+            .emit(AccountsEvent::AccountAdded(account_config.id));
 
         Ok(account_config.id)
     }
@@ -150,6 +218,11 @@ pub async fn remove_account(&mut self, id: u32) -> Result<()> {
         drop(ctx);
 
         if let Some(cfg) = self.config.get_account(id) {
+            if let Some(blobdir) = &cfg.blobdir {
+                fs::remove_dir_all(blobdir)
+                    .await
+                    .context("failed to remove blob directory")?;
+            }
             let account_path = self.dir.join(cfg.dir);
 
             fs::remove_dir_all(&account_path)
@@ -157,6 +230,8 @@ pub async fn remove_account(&mut self, id: u32) -> Result<()> {
                 .context("failed to remove account data")?;
         }
         self.config.remove_account(id).await?;
+        self.events.remove_account_channel(id);
+        self.manager_events.emit(AccountsEvent::AccountRemoved(id));
 
         Ok(())
     }
@@ -204,14 +279,18 @@ pub async fn migrate_account(&mut self, dbfile: PathBuf) -> Result<u32> {
 
         match res {
             Ok(_) => {
-                let ctx = Context::new(
+                let ctx = Context::new_closed(
                     &new_dbfile,
                     account_config.id,
                     self.events.clone(),
                     self.stockstrings.clone(),
                 )
                 .await?;
+                ctx.set_sql_config(self.sql_config);
+                ctx.open(String::new()).await?;
                 self.accounts.insert(account_config.id, ctx);
+                self.manager_events
+                    .emit(AccountsEvent::AccountAdded(account_config.id));
                 Ok(account_config.id)
             }
             Err(err) => {
@@ -229,6 +308,81 @@ pub async fn migrate_account(&mut self, dbfile: PathBuf) -> Result<u32> {
         }
     }
 
+    /// Moves the blob directory of the account to `new_blobdir`, e.g. to an SD card or a bigger disk.
+    ///
+    /// `new_blobdir` must not exist or be empty, IO of the account must be stopped.
+    /// All files are copied and verified, then the account configuration is updated
+    /// and the account switches to a new [`Context`] using the new directory.
+    /// Finally the old directory is removed.
+    /// If copying fails, the account keeps using the old directory.
+    ///
+    /// Contexts of the account returned before must not be used anymore.
+    /// The database of the new context is opened on first access,
+    /// encrypted databases have to be opened again with [`Context::open`].
+    ///
+    /// Progress is reported with [`AccountsEvent::BlobdirMoveProgress`] events.
+    pub async fn move_blobdir(&mut self, id: u32, new_blobdir: PathBuf) -> Result<()> {
+        let ctx = self
+            .get_account(id)
+            .with_context(|| format!("no account with id {id}"))?;
+        let account_config = self
+            .config
+            .get_account(id)
+            .with_context(|| format!("no account with id {id}"))?;
+        ensure!(
+            !ctx.scheduler.is_running().await,
+            "cannot move blob directory, IO is running"
+        );
+        ctx.alloc_ongoing().await?;
+        let old_blobdir = ctx.get_blobdir().to_path_buf();
+        let res = copy_blobdir(&self.manager_events, id, &old_blobdir, &new_blobdir).await;
+        ctx.free_ongoing().await;
+        if let Err(err) = res {
+            self.manager_events
+                .emit(AccountsEvent::BlobdirMoveProgress {
+                    account_id: id,
+                    progress: 0,
+                });
+            return Err(err);
+        }
+
+        let dbfile = account_config.dbfile(&self.dir);
+        let blobdir = if new_blobdir == Context::derive_blobdir(&dbfile) {
+            None
+        } else {
+            Some(new_blobdir.clone())
+        };
+        self.config.set_blobdir(id, blobdir).await?;
+
+        ctx.sql.close().await;
+        let new_ctx = Context::new_closed_with_blobdir(
+            &dbfile,
+            new_blobdir,
+            id,
+            self.events.clone(),
+            self.stockstrings.clone(),
+        )
+        .await?;
+        new_ctx.set_sql_config(self.sql_config);
+        new_ctx.set_lazy_open().await;
+        self.accounts.insert(id, new_ctx.clone());
+        drop(ctx);
+
+        if let Err(err) = fs::remove_dir_all(&old_blobdir).await {
+            warn!(
+                new_ctx,
+                "Failed to remove old blob directory {}: {err:#}.",
+                old_blobdir.display()
+            );
+        }
+        self.manager_events
+            .emit(AccountsEvent::BlobdirMoveProgress {
+                account_id: id,
+                progress: 1000,
+            });
+        Ok(())
+    }
+
     /// Get a list of all account ids.
     pub fn get_all(&self) -> Vec<u32> {
         self.accounts.keys().copied().collect()
@@ -254,10 +408,20 @@ pub async fn all_work_done(&self) -> bool {
     }
 
     /// Starts background tasks such as IMAP and SMTP loops for all accounts.
+    ///
+    /// Databases not opened yet are opened by the scheduler of each account,
+    /// see [`Context::open_lazily`].
+    /// Accounts are started concurrently, at most [`START_IO_CONCURRENCY`] at a time.
+    /// [`AccountsEvent::IoStarting`] and [`AccountsEvent::IoStarted`] are emitted
+    /// on the account manager channel to report the progress for each account.
     pub async fn start_io(&self) {
-        for account in self.accounts.values() {
-            account.start_io().await;
-        }
+        futures::stream::iter(&self.accounts)
+            .for_each_concurrent(START_IO_CONCURRENCY, |(id, account)| async move {
+                self.manager_events.emit(AccountsEvent::IoStarting(*id));
+                account.start_io().await;
+                self.manager_events.emit(AccountsEvent::IoStarted(*id));
+            })
+            .await;
     }
 
     /// Stops background tasks for all accounts.
@@ -270,6 +434,51 @@ pub async fn stop_io(&self) {
         }
     }
 
+    /// Fetches new messages for all open accounts in parallel,
+    /// giving up on accounts which did not finish within `timeout`.
+    ///
+    /// Databases not opened yet are opened as part of the fetch of each account,
+    /// see [`Context::open_lazily`].
+    /// Meant for background execution on iOS, see [`Context::background_fetch`].
+    /// Returns a summary of the messages that arrived for each account.
+    pub async fn background_fetch(
+        &self,
+        timeout: Duration,
+    ) -> BTreeMap<u32, BackgroundFetchSummary> {
+        let futures = self.accounts.iter().map(|(id, account)| async move {
+            account.open_lazily().await;
+            if !account.is_open().await {
+                return None;
+            }
+            let summary = background_fetch_account(account, timeout)
+                .await
+                .unwrap_or_else(|err| BackgroundFetchSummary {
+                    error: Some(format!("{err:#}")),
+                    ..Default::default()
+                });
+            Some((*id, summary))
+        });
+        futures::future::join_all(futures)
+            .await
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Returns the number of fresh messages for each open account,
+    /// see [`Context::get_fresh_msg_count`].
+    ///
+    /// Accounts whose database is not opened yet are skipped like closed accounts.
+    pub async fn get_fresh_msg_count(&self) -> Result<BTreeMap<u32, usize>> {
+        let mut counts = BTreeMap::new();
+        for (id, account) in &self.accounts {
+            if account.is_open().await {
+                counts.insert(*id, account.get_fresh_msg_count().await?);
+            }
+        }
+        Ok(counts)
+    }
+
     /// Notifies all accounts that the network may have become available.
     pub async fn maybe_network(&self) {
         for account in self.accounts.values() {
@@ -284,17 +493,228 @@ pub async fn maybe_network_lost(&self) {
         }
     }
 
+    /// Sets the class of the network the device is currently connected to for all accounts.
+    ///
+    /// Should be called by the UI whenever the network changes.
+    /// Closed accounts and accounts whose database is not opened yet are skipped.
+    pub async fn set_network_class(&self, network_class: NetworkClass) -> Result<()> {
+        for account in self.accounts.values() {
+            if !account.is_open().await {
+                continue;
+            }
+            account
+                .set_config(
+                    DcConfig::NetworkClass,
+                    Some(&(network_class as u8).to_string()),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Sets the proxy used by the account with the given `id`,
+    /// `None` to make the account connect directly.
+    ///
+    /// Each account may use a different proxy.
+    pub async fn set_proxy(&self, id: u32, proxy: Option<&Socks5Config>) -> Result<()> {
+        let account = self
+            .get_account(id)
+            .with_context(|| format!("no account with id {id}"))?;
+        account.open_lazily().await;
+        account.set_proxy(proxy).await
+    }
+
+    /// Returns the proxy effectively used by each open account,
+    /// `None` for accounts connecting directly.
+    ///
+    /// Closed accounts and accounts whose database is not opened yet are not included
+    /// as their configuration cannot be read.
+    pub async fn get_proxies(&self) -> Result<BTreeMap<u32, Option<Socks5Config>>> {
+        let mut proxies = BTreeMap::new();
+        for (id, account) in &self.accounts {
+            if account.is_open().await {
+                proxies.insert(*id, account.get_proxy().await?);
+            }
+        }
+        Ok(proxies)
+    }
+
     /// Emits a single event.
     pub fn emit_event(&self, event: EventType) {
-        self.events.emit(Event { id: 0, typ: event })
+        self.events.emit(Event {
+            id: 0,
+            typ: event,
+            seq: 0,
+        })
     }
 
     /// Returns event emitter.
     pub fn get_event_emitter(&self) -> EventEmitter {
         self.events.get_emitter()
     }
+
+    /// Returns event emitter receiving only the events of the account with the given ID.
+    ///
+    /// Useful if each account is displayed in its own window.
+    /// Events are still delivered to the emitters returned by [`Accounts::get_event_emitter`].
+    /// The emitter returns `None` once the account is removed.
+    pub fn get_account_event_emitter(&self, id: u32) -> Result<EventEmitter> {
+        ensure!(self.accounts.contains_key(&id), "no account with id {id}");
+        Ok(self.events.get_account_emitter(id))
+    }
+
+    /// Sets the time window in which repeated `MsgsChanged` and `ChatModified` events
+    /// for the same chat are collapsed into one.
+    ///
+    /// Useful to avoid event storms during bulk operations such as imports.
+    /// Zero, the default, disables coalescing.
+    pub fn set_event_coalescing_window(&self, window: Duration) {
+        self.events.set_coalescing_window(window)
+    }
+
+    /// Sets the size and overflow policy of the channel of [`Accounts::get_event_emitter`].
+    ///
+    /// Useful to limit memory usage if a client stops receiving events.
+    pub fn set_event_queue_config(&self, config: EventQueueConfig) {
+        self.events.set_queue_config(config)
+    }
+
+    /// Sets the size and overflow policy of the channel of [`Accounts::get_account_event_emitter`]
+    /// for the account with the given ID.
+    pub fn set_account_event_queue_config(&self, id: u32, config: EventQueueConfig) -> Result<()> {
+        ensure!(self.accounts.contains_key(&id), "no account with id {id}");
+        self.events.set_account_queue_config(id, config);
+        Ok(())
+    }
+
+    /// Returns event emitter receiving account additions and removals.
+    pub fn get_manager_emitter(&self) -> AccountsEventEmitter {
+        self.manager_events.get_emitter()
+    }
+}
+
+/// Copies the files of the blob directory `src` into `dst` for [`Accounts::move_blobdir`],
+/// checking the size of each copy.
+///
+/// `dst` is created if it does not exist and must be empty otherwise.
+/// On error, the copied files and the created directory are removed again.
+async fn copy_blobdir(
+    manager_events: &AccountsEvents,
+    account_id: u32,
+    src: &Path,
+    dst: &Path,
+) -> Result<()> {
+    ensure!(src != dst, "blob directory is {} already", dst.display());
+    let created = !dst.exists();
+    if !created {
+        let mut dir = fs::read_dir(dst).await?;
+        ensure!(
+            dir.next_entry().await?.is_none(),
+            "{} is not empty",
+            dst.display()
+        );
+    } else {
+        fs::create_dir_all(dst)
+            .await
+            .with_context(|| format!("failed to create {}", dst.display()))?;
+    }
+
+    let mut files = Vec::new();
+    let mut dir = fs::read_dir(src).await?;
+    while let Some(entry) = dir.next_entry().await? {
+        if entry.file_type().await?.is_file() {
+            files.push(entry.file_name());
+        }
+    }
+
+    let mut copied = Vec::new();
+    let res = async {
+        for (i, name) in files.iter().enumerate() {
+            let dst_file = dst.join(name);
+            let size = fs::copy(src.join(name), &dst_file)
+                .await
+                .with_context(|| format!("failed to copy {name:?}"))?;
+            copied.push(dst_file.clone());
+            let expected = fs::metadata(src.join(name)).await?.len();
+            let written = fs::metadata(&dst_file).await?.len();
+            ensure!(
+                size == expected && written == expected,
+                "copy of {name:?} has {written} bytes instead of {expected}"
+            );
+            manager_events.emit(AccountsEvent::BlobdirMoveProgress {
+                account_id,
+                progress: 1 + 998 * (i + 1) / files.len(),
+            });
+        }
+        Ok(())
+    }
+    .await;
+    if res.is_err() {
+        if created {
+            fs::remove_dir_all(dst).await.ok();
+        } else {
+            for file in copied {
+                fs::remove_file(file).await.ok();
+            }
+        }
+    }
+    res
+}
+
+/// Messages that arrived during [`Accounts::background_fetch`] for a single account.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackgroundFetchSummary {
+    /// Number of new incoming messages, including messages in muted chats and contact requests.
+    pub new_msgs: usize,
+
+    /// Chat and message IDs of new fresh messages the user should be notified about,
+    /// newest first.
+    pub notifications: Vec<(ChatId, MsgId)>,
+
+    /// Error if fetching failed or did not finish in time.
+    pub error: Option<String>,
 }
 
+async fn background_fetch_account(
+    context: &Context,
+    timeout: Duration,
+) -> Result<BackgroundFetchSummary> {
+    let last_msg_id: u32 = context
+        .sql
+        .query_get_value("SELECT IFNULL(MAX(id), 0) FROM msgs", ())
+        .await?
+        .unwrap_or_default();
+
+    let error = match tokio::time::timeout(timeout, context.background_fetch()).await {
+        Ok(Ok(())) => None,
+        Ok(Err(err)) => Some(format!("{err:#}")),
+        Err(_) => Some("Background fetch timed out".to_string()),
+    };
+
+    let new_msgs = context
+        .sql
+        .count(
+            "SELECT COUNT(*) FROM msgs WHERE id>? AND chat_id>? AND hidden=0 AND from_id!=?",
+            (last_msg_id, DC_CHAT_ID_LAST_SPECIAL, ContactId::SELF),
+        )
+        .await?;
+    let mut notifications = Vec::new();
+    for msg_id in context.get_fresh_msgs().await? {
+        if msg_id.to_u32() > last_msg_id {
+            let msg = Message::load_from_db(context, msg_id).await?;
+            notifications.push((msg.chat_id, msg_id));
+        }
+    }
+    Ok(BackgroundFetchSummary {
+        new_msgs,
+        notifications,
+        error,
+    })
+}
+
+/// Maximum number of accounts whose IO is started at the same time by [`Accounts::start_io`].
+pub const START_IO_CONCURRENCY: usize = 5;
+
 /// Configuration file name.
 pub const CONFIG_NAME: &str = "accounts.toml";
 
@@ -384,7 +804,8 @@ pub async fn from_file(file: PathBuf) -> Result<Self> {
     /// Loads all accounts defined in the configuration file.
     ///
     /// Created contexts share the same event channel and stock string
-    /// translations.
+    /// translations. Their databases are not opened yet,
+    /// see [`Context::open_lazily`].
     pub async fn load_accounts(
         &self,
         events: &Events,
@@ -394,8 +815,9 @@ pub async fn load_accounts(
         let mut accounts = BTreeMap::new();
 
         for account_config in &self.inner.accounts {
-            let ctx = Context::new(
+            let ctx = Context::new_closed_with_blobdir(
                 &account_config.dbfile(dir),
+                account_config.blobdir(dir),
                 account_config.id,
                 events.clone(),
                 stockstrings.clone(),
@@ -407,6 +829,7 @@ pub async fn load_accounts(
                     account_config.dbfile(dir)
                 )
             })?;
+            ctx.set_lazy_open().await;
 
             accounts.insert(account_config.id, ctx);
         }
@@ -425,6 +848,7 @@ async fn new_account(&mut self) -> Result<AccountConfig> {
                 id,
                 dir: target_dir,
                 uuid,
+                blobdir: None,
             });
             self.inner.next_id += 1;
             id
@@ -458,6 +882,18 @@ pub async fn remove_account(&mut self, id: u32) -> Result<()> {
         self.sync().await
     }
 
+    /// Sets the blob directory of the account, `None` for the default one.
+    async fn set_blobdir(&mut self, id: u32, blobdir: Option<PathBuf>) -> Result<()> {
+        let account = self
+            .inner
+            .accounts
+            .iter_mut()
+            .find(|e| e.id == id)
+            .with_context(|| format!("invalid account id: {id}"))?;
+        account.blobdir = blobdir;
+        self.sync().await
+    }
+
     /// Returns configuration file section for the given account ID.
     fn get_account(&self, id: u32) -> Option<AccountConfig> {
         self.inner.accounts.iter().find(|e| e.id == id).cloned()
@@ -498,6 +934,11 @@ struct AccountConfig {
 
     /// Universally unique account identifier.
     pub uuid: Uuid,
+
+    /// Absolute path of the blob directory if it was moved with [`Accounts::move_blobdir`],
+    /// `None` if the blobs are stored next to the database file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blobdir: Option<PathBuf>,
 }
 
 impl AccountConfig {
@@ -505,11 +946,19 @@ impl AccountConfig {
     pub fn dbfile(&self, accounts_dir: &Path) -> std::path::PathBuf {
         accounts_dir.join(&self.dir).join(DB_NAME)
     }
+
+    /// Returns the blob directory of the account.
+    pub fn blobdir(&self, accounts_dir: &Path) -> PathBuf {
+        self.blobdir
+            .clone()
+            .unwrap_or_else(|| Context::derive_blobdir(&self.dbfile(accounts_dir)))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::socks::ProxyProtocol;
     use crate::stock_str::{self, StockMessage};
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -679,6 +1128,7 @@ async fn test_accounts_ids_unique_increasing_and_persisted() -> Result<()> {
         let (id0_reopened, id1_reopened, id2_reopened) = {
             let accounts = Accounts::new(p.clone()).await?;
             let ctx = accounts.get_selected_account().unwrap();
+            ctx.open_lazily().await;
             assert_eq!(
                 ctx.get_config(crate::config::Config::Addr).await?,
                 Some("two@example.org".to_string())
@@ -689,6 +1139,7 @@ async fn test_accounts_ids_unique_increasing_and_persisted() -> Result<()> {
 
             let id0 = *ids.first().unwrap();
             let ctx = accounts.get_account(id0).unwrap();
+            ctx.open_lazily().await;
             assert_eq!(
                 ctx.get_config(crate::config::Config::Addr).await?,
                 Some("one@example.org".to_string())
@@ -696,6 +1147,7 @@ async fn test_accounts_ids_unique_increasing_and_persisted() -> Result<()> {
 
             let id1 = *ids.get(1).unwrap();
             let t = accounts.get_account(id1).unwrap();
+            t.open_lazily().await;
             assert_eq!(
                 t.get_config(crate::config::Config::Addr).await?,
                 Some("two@example.org".to_string())
@@ -703,6 +1155,7 @@ async fn test_accounts_ids_unique_increasing_and_persisted() -> Result<()> {
 
             let id2 = *ids.get(2).unwrap();
             let ctx = accounts.get_account(id2).unwrap();
+            ctx.open_lazily().await;
             assert_eq!(
                 ctx.get_config(crate::config::Config::Addr).await?,
                 Some("three@example.org".to_string())
@@ -776,6 +1229,10 @@ async fn test_encrypted_account() -> Result<()> {
             .context("failed to get account")?;
         assert_eq!(account.is_open().await, false);
 
+        // Encrypted databases are not opened lazily.
+        account.open_lazily().await;
+        assert_eq!(account.is_open().await, false);
+
         // Try wrong passphrase.
         assert_eq!(account.open("barfoo".to_string()).await?, false);
         assert_eq!(account.open("".to_string()).await?, false);
@@ -809,4 +1266,306 @@ async fn test_accounts_share_translations() -> Result<()> {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_accounts_proxies() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let p: PathBuf = dir.path().join("accounts");
+
+        let mut accounts = Accounts::new(p.clone()).await?;
+        accounts.add_account().await?;
+        accounts.add_account().await?;
+        accounts.add_closed_account().await?;
+
+        let proxy = Socks5Config {
+            protocol: ProxyProtocol::Socks5,
+            host: "127.0.0.1".to_string(),
+            port: 9050,
+            user_password: None,
+        };
+        accounts.set_proxy(1, Some(&proxy)).await?;
+        accounts.set_proxy(2, None).await?;
+        assert!(accounts.set_proxy(42, None).await.is_err());
+
+        let proxies = accounts.get_proxies().await?;
+        assert_eq!(proxies.len(), 2);
+        assert_eq!(proxies.get(&1), Some(&Some(proxy)));
+        assert_eq!(proxies.get(&2), Some(&None));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_accounts_background_fetch_unconfigured() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let p: PathBuf = dir.path().join("accounts");
+
+        let mut accounts = Accounts::new(p.clone()).await?;
+        accounts.add_account().await?;
+        accounts.add_account().await?;
+        accounts.add_closed_account().await?;
+
+        let summaries = accounts.background_fetch(Duration::from_secs(10)).await;
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries.get(&1), Some(&BackgroundFetchSummary::default()));
+        assert_eq!(summaries.get(&2), Some(&BackgroundFetchSummary::default()));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_accounts_get_fresh_msg_count() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let p: PathBuf = dir.path().join("accounts");
+
+        let mut accounts = Accounts::new(p.clone()).await?;
+        accounts.add_account().await?;
+        accounts.add_closed_account().await?;
+
+        let counts = accounts.get_fresh_msg_count().await?;
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts.get(&1), Some(&0));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_accounts_set_network_class() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let p: PathBuf = dir.path().join("accounts");
+
+        let mut accounts = Accounts::new(p.clone()).await?;
+        accounts.add_account().await?;
+        accounts.add_account().await?;
+        accounts.add_closed_account().await?;
+
+        accounts.set_network_class(NetworkClass::Roaming).await?;
+        for id in [1, 2] {
+            let account = accounts.get_account(id).context("failed to get account")?;
+            assert_eq!(account.get_network_class().await?, NetworkClass::Roaming);
+        }
+
+        accounts.set_network_class(NetworkClass::Unmetered).await?;
+        let account = accounts.get_account(1).context("failed to get account 1")?;
+        assert_eq!(account.get_network_class().await?, NetworkClass::Unmetered);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_accounts_event_channels() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let p: PathBuf = dir.path().join("accounts");
+
+        let mut accounts = Accounts::new(p.clone()).await?;
+        let manager_emitter = accounts.get_manager_emitter();
+        accounts.add_account().await?;
+        accounts.add_closed_account().await?;
+        assert_eq!(
+            manager_emitter.recv().await,
+            Some(AccountsEvent::AccountAdded(1))
+        );
+        assert_eq!(
+            manager_emitter.recv().await,
+            Some(AccountsEvent::AccountAdded(2))
+        );
+        assert!(accounts.get_account_event_emitter(3).is_err());
+
+        let emitter = accounts.get_account_event_emitter(2)?;
+        let account1 = accounts.get_account(1).context("failed to get account 1")?;
+        let account2 = accounts.get_account(2).context("failed to get account 2")?;
+        account1.emit_event(EventType::Info("first".to_string()));
+        account2.emit_event(EventType::Info("second".to_string()));
+        loop {
+            let event = emitter.recv().await.context("account channel closed")?;
+            assert_eq!(event.id, 2);
+            if event.typ == EventType::Info("second".to_string()) {
+                break;
+            }
+        }
+
+        drop(account2);
+        accounts.remove_account(2).await?;
+        assert_eq!(
+            manager_emitter.recv().await,
+            Some(AccountsEvent::AccountRemoved(2))
+        );
+        while let Some(event) = emitter.recv().await {
+            assert_eq!(event.id, 2);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_accounts_lazy_loading() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let p: PathBuf = dir.path().join("accounts");
+
+        let mut accounts = Accounts::new(p.clone()).await?;
+        for _ in 0..3 {
+            let id = accounts.add_account().await?;
+            let account = accounts.get_account(id).context("no account")?;
+            account
+                .set_config(
+                    crate::config::Config::Displayname,
+                    Some(&format!("Account {id}")),
+                )
+                .await?;
+        }
+        accounts.select_account(2).await?;
+        drop(accounts);
+
+        let accounts = Accounts::new(p).await?;
+        assert_eq!(accounts.get_all(), vec![1, 2, 3]);
+        for account in accounts.accounts.values() {
+            assert!(!account.is_open().await);
+        }
+
+        let account = accounts.get_account(1).context("no account 1")?;
+        assert!(!account.is_open().await);
+        account.open_lazily().await;
+        assert!(account.is_open().await);
+        assert_eq!(
+            account
+                .get_config(crate::config::Config::Displayname)
+                .await?
+                .as_deref(),
+            Some("Account 1")
+        );
+        assert!(!accounts.accounts[&2].is_open().await);
+        assert!(accounts.get_account(4).is_none());
+
+        // Accounts not opened yet are skipped.
+        let counts = accounts.get_fresh_msg_count().await?;
+        assert_eq!(counts.keys().copied().collect::<Vec<_>>(), vec![1]);
+        assert!(accounts.get_proxies().await?.get(&3).is_none());
+        assert!(!accounts.accounts[&3].is_open().await);
+
+        accounts.prewarm_selected_account();
+        let account = accounts
+            .get_selected_account()
+            .context("no selected account")?;
+        // Waits for the database opened in the background.
+        account.open_lazily().await;
+        assert!(account.is_open().await);
+        assert_eq!(
+            account
+                .get_config(crate::config::Config::Displayname)
+                .await?
+                .as_deref(),
+            Some("Account 2")
+        );
+        assert!(!accounts.accounts[&3].is_open().await);
+
+        // The scheduler opens the database when IO is started.
+        accounts.start_io().await;
+        assert!(accounts.accounts[&3].is_open().await);
+        accounts.stop_io().await;
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_accounts_start_io_progress() -> Result<()> {
+        let dir = tempfile::tempdir().unwrap();
+        let p: PathBuf = dir.path().join("accounts");
+
+        let mut accounts = Accounts::new(p).await?;
+        let manager_emitter = accounts.get_manager_emitter();
+        for id in 1..=7 {
+            assert_eq!(accounts.add_account().await?, id);
+            assert_eq!(
+                manager_emitter.recv().await,
+                Some(AccountsEvent::AccountAdded(id))
+            );
+        }
+
+        accounts.start_io().await;
+        let mut started = BTreeMap::new();
+        for _ in 0..14 {
+            match manager_emitter.recv().await.context("channel closed")? {
+                AccountsEvent::IoStarting(id) => assert_eq!(started.insert(id, false), None),
+                AccountsEvent::IoStarted(id) => {
+                    assert_eq!(started.insert(id, true), Some(false))
+                }
+                event => panic!("unexpected event {event:?}"),
+            }
+        }
+        assert_eq!(started.len(), 7);
+        assert!(started.values().all(|started| *started));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_move_blobdir() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let p: PathBuf = dir.path().join("accounts");
+        let new_blobdir = dir.path().join("sdcard").join("blobs");
+
+        let mut accounts = Accounts::new(p.clone()).await?;
+        let manager_emitter = accounts.get_manager_emitter();
+        let id = accounts.add_account().await?;
+        manager_emitter.recv().await;
+        let account = accounts.get_account(id).context("no account")?;
+        let old_blobdir = account.get_blobdir().to_path_buf();
+        fs::write(old_blobdir.join("foo.txt"), b"foo").await?;
+        fs::write(old_blobdir.join("bar.txt"), b"bar").await?;
+
+        // The target must be empty.
+        fs::create_dir_all(&new_blobdir).await?;
+        fs::write(new_blobdir.join("other.txt"), b"other").await?;
+        assert!(accounts
+            .move_blobdir(id, new_blobdir.clone())
+            .await
+            .is_err());
+        assert_eq!(
+            manager_emitter.recv().await,
+            Some(AccountsEvent::BlobdirMoveProgress {
+                account_id: id,
+                progress: 0
+            })
+        );
+        assert!(new_blobdir.join("other.txt").exists());
+        assert!(!new_blobdir.join("foo.txt").exists());
+        fs::remove_file(new_blobdir.join("other.txt")).await?;
+
+        drop(account);
+        accounts.move_blobdir(id, new_blobdir.clone()).await?;
+        loop {
+            match manager_emitter.recv().await.context("channel closed")? {
+                AccountsEvent::BlobdirMoveProgress { progress: 1000, .. } => break,
+                AccountsEvent::BlobdirMoveProgress { progress, .. } => {
+                    assert!(progress > 0 && progress < 1000)
+                }
+                event => panic!("unexpected event {event:?}"),
+            }
+        }
+        assert!(!old_blobdir.exists());
+        assert_eq!(fs::read(new_blobdir.join("foo.txt")).await?, b"foo");
+        let account = accounts.get_account(id).context("no account")?;
+        assert!(!account.is_open().await);
+        account.open_lazily().await;
+        assert!(account.is_open().await);
+        assert_eq!(account.get_blobdir(), new_blobdir);
+        drop(account);
+        drop(accounts);
+
+        // The new blob directory is used after reopening the accounts.
+        let mut accounts = Accounts::new(p).await?;
+        let account = accounts.get_account(id).context("no account")?;
+        assert_eq!(account.get_blobdir(), new_blobdir);
+        drop(account);
+
+        // Moving back to the default directory resets the configuration.
+        accounts.move_blobdir(id, old_blobdir.clone()).await?;
+        assert_eq!(accounts.config.get_account(id).unwrap().blobdir, None);
+        assert!(old_blobdir.join("bar.txt").exists());
+
+        accounts.move_blobdir(id, new_blobdir.clone()).await?;
+        accounts.remove_account(id).await?;
+        assert!(!new_blobdir.exists());
+        Ok(())
+    }
 }
@@ -1,17 +1,24 @@
 //! # Blob directory management.
 
 use core::cmp::max;
+use std::collections::HashMap;
 use std::ffi::OsStr;
 use std::fmt;
-use std::io::Cursor;
+use std::io::{Cursor, SeekFrom};
 use std::iter::FusedIterator;
 use std::path::{Path, PathBuf};
 
 use anyhow::{format_err, Context as _, Result};
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine as _;
 use futures::StreamExt;
 use image::{DynamicImage, ImageFormat, ImageOutputFormat};
+use mailparse::body::Body;
 use num_traits::FromPrimitive;
-use tokio::io::AsyncWriteExt;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tokio::{fs, io};
 use tokio_stream::wrappers::ReadDirStream;
 
@@ -20,6 +27,9 @@
 use crate::context::Context;
 use crate::events::EventType;
 use crate::log::LogExt;
+use crate::message::{MessageState, MsgId};
+use crate::param::{Param, Params};
+use crate::tools::{delete_file, time};
 
 /// Represents a file in the blob directory.
 ///
@@ -64,6 +74,139 @@ pub async fn create(
         Ok(blob)
     }
 
+    /// Creates a new blob object named after the hash of `data`,
+    /// so identical files share a single blob.
+    ///
+    /// Names of deduplicated blobs start with [`DEDUPLICATED_PREFIX`].
+    /// If a blob with the same content exists already, it is reused
+    /// and the extension of `suggested_name` is kept.
+    /// Messages referencing the blob are counted in the `blobs` table
+    /// and the blob is removed once the last of them is deleted,
+    /// see [`remove_unreferenced_blobs`].
+    pub async fn create_and_deduplicate(
+        context: &'a Context,
+        suggested_name: &str,
+        data: &[u8],
+    ) -> Result<BlobObject<'a>> {
+        let (_stem, ext) = BlobObject::sanitise_name(suggested_name);
+        let name = deduplicated_name(&Sha256::digest(data), &ext);
+        let blobdir = context.get_blobdir();
+        let _lock = context.blobs_mutex.lock().await;
+        if !blobdir.join(&name).exists() {
+            let (tmp_name, mut file) =
+                BlobObject::create_new_file(context, blobdir, &name, ".tmp").await?;
+            let res = file.write_all(data).await.context("file write failure");
+            let _ = file.flush().await;
+            drop(file);
+            BlobObject::finish_deduplicated(blobdir, &tmp_name, &name, res).await?;
+        }
+        register_deduplicated_blob(context, &name).await?;
+
+        let blob = BlobObject {
+            blobdir,
+            name: format!("$BLOBDIR/{name}"),
+        };
+        context.emit_event(EventType::NewBlobFile(blob.as_name().to_string()));
+        Ok(blob)
+    }
+
+    /// Creates a new blob object with the decoded body of a MIME part,
+    /// deduplicated as with [`BlobObject::create_and_deduplicate`].
+    ///
+    /// Base64 bodies are decoded in chunks of [`STREAMING_CHUNK_SIZE`] encoded bytes
+    /// written to a temporary file one after another,
+    /// so the decoded data is never kept in memory as a whole.
+    ///
+    /// Returns the blob object and the size of the decoded data.
+    pub(crate) async fn create_from_mime_body(
+        context: &'a Context,
+        suggested_name: &str,
+        body: &Body<'_>,
+    ) -> Result<(BlobObject<'a>, usize)> {
+        let blobdir = context.get_blobdir();
+        let (stem, ext) = BlobObject::sanitise_name(suggested_name);
+        let (tmp_name, mut file) =
+            BlobObject::create_new_file(context, blobdir, &stem, &format!("{ext}.tmp")).await?;
+        let mut hasher = Sha256::new();
+        let res = write_mime_body(&mut file, &mut hasher, body).await;
+        let _ = file.flush().await;
+        drop(file);
+        let name = deduplicated_name(&hasher.finalize(), &ext);
+        let _lock = context.blobs_mutex.lock().await;
+        let size = BlobObject::finish_deduplicated(blobdir, &tmp_name, &name, res).await?;
+        register_deduplicated_blob(context, &name).await?;
+
+        let blob = BlobObject {
+            blobdir,
+            name: format!("$BLOBDIR/{name}"),
+        };
+        context.emit_event(EventType::NewBlobFile(blob.as_name().to_string()));
+        Ok((blob, size))
+    }
+
+    /// Creates a deduplicated blob with the content of the file at `src`,
+    /// see [`BlobObject::create_and_deduplicate`].
+    ///
+    /// The file is copied, it is not removed even if it is in the blob directory.
+    pub(crate) async fn create_and_deduplicate_from_path(
+        context: &'a Context,
+        src: &Path,
+    ) -> Result<BlobObject<'a>> {
+        let (_stem, ext) = BlobObject::sanitise_name(&src.to_string_lossy());
+        let name = deduplicated_name(&hash_file(src).await?, &ext);
+        let blobdir = context.get_blobdir();
+        let _lock = context.blobs_mutex.lock().await;
+        if !blobdir.join(&name).exists() {
+            let (tmp_name, file) =
+                BlobObject::create_new_file(context, blobdir, &name, ".tmp").await?;
+            drop(file);
+            let res = fs::copy(src, blobdir.join(&tmp_name))
+                .await
+                .with_context(|| format!("failed to copy file {}", src.display()));
+            BlobObject::finish_deduplicated(blobdir, &tmp_name, &name, res).await?;
+        }
+        register_deduplicated_blob(context, &name).await?;
+
+        let blob = BlobObject {
+            blobdir,
+            name: format!("$BLOBDIR/{name}"),
+        };
+        context.emit_event(EventType::NewBlobFile(blob.as_name().to_string()));
+        Ok(blob)
+    }
+
+    /// Moves the temporary file `tmp_name`, written with the result `res`, to `name`.
+    ///
+    /// The temporary file is removed instead if writing failed
+    /// or a blob with the same content exists already.
+    /// Must be called with [`Context::blobs_mutex`] locked,
+    /// so the blob is not removed by [`remove_unreferenced_blobs`] meanwhile.
+    async fn finish_deduplicated<T>(
+        blobdir: &Path,
+        tmp_name: &str,
+        name: &str,
+        res: Result<T>,
+    ) -> Result<T> {
+        let tmp_path = blobdir.join(tmp_name);
+        let path = blobdir.join(name);
+        if res.is_err() || path.exists() {
+            fs::remove_file(&tmp_path).await.ok();
+        } else {
+            fs::rename(&tmp_path, &path)
+                .await
+                .context("failed to rename deduplicated blob")?;
+        }
+        res
+    }
+
+    /// Returns true if the blob is named after the hash of its content,
+    /// i.e. its name starts with [`DEDUPLICATED_PREFIX`].
+    ///
+    /// Such blobs may be shared by several messages and must not be modified in place.
+    pub(crate) fn is_deduplicated(&self) -> bool {
+        self.as_file_name().starts_with(DEDUPLICATED_PREFIX)
+    }
+
     // Creates a new file, returning a tuple of the name and the handle.
     async fn create_new_file(
         context: &Context,
@@ -242,6 +385,13 @@ pub fn suffix(&self) -> Option<&str> {
     /// ".txt")` while "bar" is returned as `("bar", "")`.
     ///
     /// The extension part will always be lowercased.
+    /// Returns `name` sanitised as with [`BlobObject::sanitise_name`],
+    /// joining the stem and the extension again.
+    pub(crate) fn sanitise_filename(name: &str) -> String {
+        let (stem, ext) = BlobObject::sanitise_name(name);
+        format!("{stem}{ext}")
+    }
+
     fn sanitise_name(name: &str) -> (String, String) {
         let mut name = name.to_string();
         for part in name.rsplit('/') {
@@ -313,8 +463,6 @@ fn is_acceptible_blob_name(name: impl AsRef<OsStr>) -> bool {
     }
 
     pub async fn recode_to_avatar_size(&mut self, context: &Context) -> Result<()> {
-        let blob_abs = self.to_abs_path();
-
         let img_wh =
             match MediaQuality::from_i32(context.get_config_int(Config::MediaQuality).await?)
                 .unwrap_or_default()
@@ -326,16 +474,10 @@ pub async fn recode_to_avatar_size(&mut self, context: &Context) -> Result<()> {
         let strict_limits = true;
         // max_bytes is 20_000 bytes: Outlook servers don't allow headers larger than 32k.
         // 32 / 4 * 3 = 24k if you account for base64 encoding. To be safe, we reduced this to 20k.
-        if let Some(new_name) =
-            self.recode_to_size(context, blob_abs, img_wh, 20_000, strict_limits)?
-        {
-            self.name = new_name;
-        }
-        Ok(())
+        self.recode(context, img_wh, 20_000, strict_limits).await
     }
 
     pub async fn recode_to_image_size(&mut self, context: &Context) -> Result<()> {
-        let blob_abs = self.to_abs_path();
         let (img_wh, max_bytes) =
             match MediaQuality::from_i32(context.get_config_int(Config::MediaQuality).await?)
                 .unwrap_or_default()
@@ -347,10 +489,33 @@ pub async fn recode_to_image_size(&mut self, context: &Context) -> Result<()> {
                 MediaQuality::Worse => (constants::WORSE_IMAGE_SIZE, constants::WORSE_IMAGE_BYTES),
             };
         let strict_limits = false;
+        self.recode(context, img_wh, max_bytes, strict_limits).await
+    }
+
+    /// Recodes the image using [`BlobObject::recode_to_size`] and renames the blob if needed.
+    ///
+    /// A deduplicated blob is recoded into a new deduplicated blob,
+    /// which is registered like the ones created by [`BlobObject::create_and_deduplicate`].
+    async fn recode(
+        &mut self,
+        context: &Context,
+        img_wh: u32,
+        max_bytes: usize,
+        strict_limits: bool,
+    ) -> Result<()> {
+        let _lock = if self.is_deduplicated() {
+            Some(context.blobs_mutex.lock().await)
+        } else {
+            None
+        };
+        let blob_abs = self.to_abs_path();
         if let Some(new_name) =
             self.recode_to_size(context, blob_abs, img_wh, max_bytes, strict_limits)?
         {
             self.name = new_name;
+            if self.is_deduplicated() {
+                register_deduplicated_blob(context, self.as_file_name()).await?;
+            }
         }
         Ok(())
     }
@@ -464,6 +629,21 @@ fn recode_to_size(
                     encode_img(&img, ofmt, &mut encoded)?;
                 }
 
+                if self.is_deduplicated() {
+                    // The blob may be shared with other messages, write a new one.
+                    let ext = blob_abs
+                        .extension()
+                        .and_then(OsStr::to_str)
+                        .map(|ext| format!(".{ext}"))
+                        .unwrap_or_default();
+                    let file_name = deduplicated_name(&Sha256::digest(&encoded), &ext);
+                    blob_abs = self.blobdir.join(&file_name);
+                    changed_name = Some(format!("$BLOBDIR/{file_name}"));
+                    if blob_abs.exists() {
+                        return Ok(changed_name);
+                    }
+                }
+
                 std::fs::write(&blob_abs, &encoded)
                     .context("failed to write recoded blob to file")?;
             }
@@ -583,6 +763,30 @@ fn next(&mut self) -> Option<Self::Item> {
 
 impl FusedIterator for BlobDirIter<'_> {}
 
+impl Context {
+    /// Stores `data` in a new file in the blob directory and returns its absolute path.
+    ///
+    /// The file name is derived from `suggested_name` as for [`BlobObject::create`].
+    pub async fn create_blob(&self, suggested_name: &str, data: &[u8]) -> Result<PathBuf> {
+        let blob = BlobObject::create(self, suggested_name, data).await?;
+        Ok(blob.to_abs_path())
+    }
+
+    /// Reads up to `length` bytes of a file in the blob directory, starting at `offset`.
+    ///
+    /// Fails if `path` is not a file in the blob directory.
+    pub async fn read_blob_chunk(&self, path: &Path, offset: u64, length: u64) -> Result<Vec<u8>> {
+        let blob = BlobObject::from_path(self, path)?;
+        let mut file = fs::File::open(blob.to_abs_path())
+            .await
+            .with_context(|| format!("failed to open blob {}", path.display()))?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        let mut data = Vec::new();
+        file.take(length).read_to_end(&mut data).await?;
+        Ok(data)
+    }
+}
+
 fn encode_img(
     img: &DynamicImage,
     fmt: ImageOutputFormat,
@@ -616,6 +820,285 @@ fn encoded_img_exceeds_bytes(
     Ok(false)
 }
 
+/// Raw config key set by the migration
+/// if blobs of existing messages have to be deduplicated by [`deduplicate_blobs`].
+pub(crate) const DEDUPLICATE_BLOBS_KEY: &str = "deduplicate_blobs";
+
+/// Moves the files of existing messages to deduplicated blobs
+/// named after the hash of their content, see [`BlobObject::create_and_deduplicate`].
+///
+/// Runs once during housekeeping after the migration set [`DEDUPLICATE_BLOBS_KEY`].
+/// The original files are copied, so they are removed only once not referenced anymore,
+/// like all unused files.
+pub(crate) async fn deduplicate_blobs(context: &Context) -> Result<()> {
+    if !context
+        .sql
+        .get_raw_config_bool(DEDUPLICATE_BLOBS_KEY)
+        .await?
+    {
+        return Ok(());
+    }
+
+    // Files of messages being created or edited may still be changed by the UI.
+    let msgs = context
+        .sql
+        .query_map(
+            "SELECT id, param FROM msgs WHERE state NOT IN (?, ?) AND param LIKE '%f=%'",
+            (MessageState::OutPreparing, MessageState::OutDraft),
+            |row| {
+                let id: MsgId = row.get(0)?;
+                let param: String = row.get(1)?;
+                Ok((id, param))
+            },
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await?;
+
+    let mut deduplicated: HashMap<String, String> = HashMap::new();
+    for (msg_id, param) in msgs {
+        let mut param: Params = param.parse().unwrap_or_default();
+        let blob = match param.get_blob(Param::File, context, false).await {
+            Ok(Some(blob)) if !blob.is_deduplicated() => blob,
+            _ => continue,
+        };
+        let name = match deduplicated.get(blob.as_name()) {
+            Some(name) => name.clone(),
+            None => {
+                let name = match BlobObject::create_and_deduplicate_from_path(
+                    context,
+                    &blob.to_abs_path(),
+                )
+                .await
+                {
+                    Ok(deduplicated_blob) => deduplicated_blob.as_name().to_string(),
+                    Err(err) => {
+                        warn!(context, "Cannot deduplicate {}: {err:#}.", blob.as_name());
+                        continue;
+                    }
+                };
+                deduplicated.insert(blob.as_name().to_string(), name.clone());
+                name
+            }
+        };
+        if !param.exists(Param::Filename) {
+            param.set(Param::Filename, blob.as_file_name());
+        }
+        param.set(Param::File, name);
+        context
+            .sql
+            .execute(
+                "UPDATE msgs SET param=? WHERE id=?",
+                (param.to_string(), msg_id),
+            )
+            .await?;
+    }
+    info!(
+        context,
+        "Deduplicated {} blobs of existing messages.",
+        deduplicated.len()
+    );
+
+    context
+        .sql
+        .set_raw_config_bool(DEDUPLICATE_BLOBS_KEY, false)
+        .await?;
+    Ok(())
+}
+
+/// Replaces the file in `param` with a deduplicated blob,
+/// keeping the original file name in [`Param::Filename`].
+///
+/// Used for sent and forwarded files, so they share a single blob
+/// with forwarded copies and identical received files.
+/// Returns false if there is no file or it is deduplicated already.
+pub(crate) async fn deduplicate_file(context: &Context, param: &mut Params) -> Result<bool> {
+    let blob = match param.get_blob(Param::File, context, false).await? {
+        Some(blob) if !blob.is_deduplicated() => blob,
+        _ => return Ok(false),
+    };
+    let deduplicated_blob =
+        BlobObject::create_and_deduplicate_from_path(context, &blob.to_abs_path()).await?;
+    if !param.exists(Param::Filename) {
+        param.set(Param::Filename, blob.as_file_name());
+    }
+    param.set(Param::File, deduplicated_blob.as_name());
+    Ok(true)
+}
+
+/// Minimum number of seconds a deduplicated blob is kept after it was created or reused,
+/// even if no message references it.
+///
+/// The message referencing the blob may not be saved yet.
+const DEDUPLICATED_BLOB_GRACE_PERIOD: i64 = 10 * 60;
+
+/// Adds the deduplicated blob `name` to the `blobs` table if it is not there yet
+/// and protects it from removal for [`DEDUPLICATED_BLOB_GRACE_PERIOD`].
+///
+/// The number of messages referencing the blob is counted by triggers on the `msgs` table.
+async fn register_deduplicated_blob(context: &Context, name: &str) -> Result<()> {
+    context
+        .sql
+        .execute(
+            "INSERT INTO blobs (name, timestamp) VALUES (?, ?)
+             ON CONFLICT (name) DO UPDATE SET timestamp=excluded.timestamp",
+            (name, time()),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Removes deduplicated blobs not referenced by any message anymore.
+///
+/// Called after messages are deleted and during housekeeping.
+/// Blobs which are not deduplicated are removed by housekeeping
+/// once they are not used anymore.
+pub(crate) async fn remove_unreferenced_blobs(context: &Context) -> Result<()> {
+    let _lock = context.blobs_mutex.lock().await;
+    let names = context
+        .sql
+        .query_map(
+            "SELECT name FROM blobs WHERE refcount<=0 AND timestamp<?",
+            (time() - DEDUPLICATED_BLOB_GRACE_PERIOD,),
+            |row| row.get::<_, String>(0),
+            |rows| {
+                rows.collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(Into::into)
+            },
+        )
+        .await?;
+    let blobdir = context.get_blobdir();
+    for name in names {
+        let path = blobdir.join(&name);
+        if let Err(err) = delete_file(context, &path).await {
+            if path.exists() {
+                warn!(context, "Cannot remove unreferenced blob {name}: {err:#}.");
+                continue;
+            }
+        }
+        context
+            .sql
+            .execute("DELETE FROM blobs WHERE name=?", (name,))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Returns an SQL expression evaluating to the name of the deduplicated blob
+/// referenced by the message parameters `param`, NULL if there is none.
+///
+/// Used by the triggers counting the references in the `blobs` table.
+pub(crate) fn deduplicated_blob_sql(param: &str) -> String {
+    let start = format!("instr({param}, 'f=$BLOBDIR/{DEDUPLICATED_PREFIX}')");
+    let rest = format!("substr({param}, {start} + 11)");
+    format!(
+        "CASE WHEN {start}>0 THEN substr({rest}, 1, instr({rest} || char(10), char(10)) - 1) END"
+    )
+}
+
+/// Returns the SHA-256 hash of the file at `path`, reading it in chunks.
+async fn hash_file(path: &Path) -> Result<Vec<u8>> {
+    let mut file = fs::File::open(path)
+        .await
+        .with_context(|| format!("failed to open file {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; STREAMING_CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Prefix of the names of deduplicated blobs, see [`BlobObject::create_and_deduplicate`].
+pub(crate) const DEDUPLICATED_PREFIX: &str = "dedup-";
+
+/// Number of hexadecimal digits of the content hash in the names of deduplicated blobs.
+const DEDUPLICATED_HASH_LEN: usize = 32;
+
+/// Returns the name of a deduplicated blob with the content hash `hash` and extension `ext`.
+fn deduplicated_name(hash: &[u8], ext: &str) -> String {
+    let hash = hex::encode(hash);
+    format!(
+        "{DEDUPLICATED_PREFIX}{}{ext}",
+        &hash[..DEDUPLICATED_HASH_LEN]
+    )
+}
+
+/// Number of base64 characters decoded at once by [`BlobObject::create_from_mime_body`].
+///
+/// Must be a multiple of 4, so chunks can be decoded independently.
+const STREAMING_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Base64 engine accepting missing padding and trailing bits as mail clients do.
+const BASE64_MIME: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// Writes the decoded MIME part `body` to `file` and `hasher`,
+/// returning the number of bytes written.
+async fn write_mime_body(
+    file: &mut fs::File,
+    hasher: &mut Sha256,
+    body: &Body<'_>,
+) -> Result<usize> {
+    match body {
+        Body::Base64(body) => {
+            let mut encoded = body
+                .get_raw()
+                .iter()
+                .filter(|c| !c.is_ascii_whitespace())
+                .copied();
+            let mut chunk = Vec::with_capacity(STREAMING_CHUNK_SIZE);
+            let mut size = 0;
+            loop {
+                chunk.clear();
+                chunk.extend(encoded.by_ref().take(STREAMING_CHUNK_SIZE));
+                if chunk.is_empty() {
+                    break;
+                }
+                let decoded = BASE64_MIME.decode(&chunk).context("invalid base64 body")?;
+                hasher.update(&decoded);
+                file.write_all(&decoded)
+                    .await
+                    .context("file write failure")?;
+                size += decoded.len();
+            }
+            Ok(size)
+        }
+        Body::QuotedPrintable(body) => {
+            let decoded = body.get_decoded()?;
+            hasher.update(&decoded);
+            file.write_all(&decoded)
+                .await
+                .context("file write failure")?;
+            Ok(decoded.len())
+        }
+        Body::SevenBit(body) | Body::EightBit(body) => {
+            hasher.update(body.get_raw());
+            file.write_all(body.get_raw())
+                .await
+                .context("file write failure")?;
+            Ok(body.get_raw().len())
+        }
+        Body::Binary(body) => {
+            hasher.update(body.get_raw());
+            file.write_all(body.get_raw())
+                .await
+                .context("file write failure")?;
+            Ok(body.get_raw().len())
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -624,7 +1107,7 @@ mod tests {
 
     use super::*;
     use crate::chat::{self, create_group_chat, ProtectionStatus};
-    use crate::message::{Message, Viewtype};
+    use crate::message::{delete_msgs, Message, Viewtype};
     use crate::test_utils::{self, TestContext};
 
     fn check_image_size(path: impl AsRef<Path>, width: u32, height: u32) -> image::DynamicImage {
@@ -647,6 +1130,21 @@ async fn test_create() {
         assert_eq!(blob.to_abs_path(), t.get_blobdir().join("foo"));
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_create_blob_read_blob_chunk() -> Result<()> {
+        let t = TestContext::new().await;
+        let path = t.create_blob("foo.txt", b"hello world").await?;
+        assert_eq!(path, t.get_blobdir().join("foo.txt"));
+        assert_eq!(t.read_blob_chunk(&path, 0, 5).await?, b"hello");
+        assert_eq!(t.read_blob_chunk(&path, 6, 100).await?, b"world");
+        assert!(t.read_blob_chunk(&path, 20, 5).await?.is_empty());
+
+        let outside = t.dir.path().join("foo.txt");
+        fs::write(&outside, b"secret").await?;
+        assert!(t.read_blob_chunk(&outside, 0, 5).await.is_err());
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_lowercase_ext() {
         let t = TestContext::new().await;
@@ -1149,4 +1647,180 @@ async fn test_increation_not_blobdir() -> Result<()> {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_create_and_deduplicate() -> Result<()> {
+        let t = TestContext::new().await;
+        let foo = BlobObject::create_and_deduplicate(&t, "foo.txt", b"hello").await?;
+        assert!(foo.is_deduplicated());
+        assert!(foo.as_name().starts_with("$BLOBDIR/dedup-"));
+        assert!(foo.as_name().ends_with(".txt"));
+        assert_eq!(fs::read(foo.to_abs_path()).await?, b"hello");
+
+        let bar = BlobObject::create_and_deduplicate(&t, "dir/bar.TXT", b"hello").await?;
+        assert_eq!(bar, foo);
+        let other = BlobObject::create_and_deduplicate(&t, "foo.txt", b"world").await?;
+        assert_ne!(other, foo);
+        let dat = BlobObject::create_and_deduplicate(&t, "foo.dat", b"hello").await?;
+        assert_ne!(dat, foo);
+        assert!(dat.as_name().ends_with(".dat"));
+
+        // Temporary files are renamed or removed.
+        let mut dir = fs::read_dir(t.get_blobdir()).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            assert!(!entry.file_name().to_string_lossy().ends_with(".tmp"));
+        }
+
+        let blob = BlobObject::create(&t, "foo.txt", b"hello").await?;
+        assert!(!blob.is_deduplicated());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_receive_same_attachment_twice() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let chat = alice.create_chat(&bob).await;
+
+        let mut bob_msgs = Vec::new();
+        for name in ["report.pdf", "copy.pdf"] {
+            let file = alice.get_blobdir().join(name);
+            fs::write(&file, b"%PDF- same content").await?;
+            let mut msg = Message::new(Viewtype::File);
+            msg.set_file(file.to_str().unwrap(), None);
+            let sent = alice.send_msg(chat.id, &mut msg).await;
+            bob_msgs.push(bob.recv_msg(&sent).await);
+        }
+
+        assert_eq!(bob_msgs[0].get_filename().unwrap(), "report.pdf");
+        assert_eq!(bob_msgs[1].get_filename().unwrap(), "copy.pdf");
+        let file = bob_msgs[0].get_file(&bob).unwrap();
+        assert_eq!(bob_msgs[1].get_file(&bob).unwrap(), file);
+        assert_eq!(fs::read(&file).await?, b"%PDF- same content");
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_deduplicate_blobs() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let chat = alice.create_chat(&bob).await;
+
+        let mut msg_ids = Vec::new();
+        for name in ["a.txt", "b.txt"] {
+            let file = alice.get_blobdir().join(name);
+            fs::write(&file, b"same content").await?;
+            let mut msg = Message::new(Viewtype::File);
+            msg.set_file(file.to_str().unwrap(), None);
+            alice.send_msg(chat.id, &mut msg).await;
+            set_legacy_blob(&alice, msg.id, name).await?;
+            msg_ids.push(msg.id);
+        }
+
+        // Nothing happens unless the migration requested it.
+        deduplicate_blobs(&alice).await?;
+        let msg = Message::load_from_db(&alice, msg_ids[0]).await?;
+        assert_eq!(msg.param.get(Param::File), Some("$BLOBDIR/a.txt"));
+
+        alice
+            .sql
+            .set_raw_config_bool(DEDUPLICATE_BLOBS_KEY, true)
+            .await?;
+        deduplicate_blobs(&alice).await?;
+        assert!(!alice.sql.get_raw_config_bool(DEDUPLICATE_BLOBS_KEY).await?);
+
+        let a = Message::load_from_db(&alice, msg_ids[0]).await?;
+        let b = Message::load_from_db(&alice, msg_ids[1]).await?;
+        assert_eq!(a.get_filename().unwrap(), "a.txt");
+        assert_eq!(b.get_filename().unwrap(), "b.txt");
+        let file = a.get_file(&alice).unwrap();
+        assert_eq!(b.get_file(&alice).unwrap(), file);
+        assert!(file.to_str().unwrap().ends_with(".txt"));
+        assert_eq!(fs::read(&file).await?, b"same content");
+
+        // The original files are kept until housekeeping removes unused files.
+        assert!(alice.get_blobdir().join("a.txt").exists());
+        Ok(())
+    }
+
+    /// Makes the message reference the blob `name` which is not deduplicated,
+    /// as messages sent before blobs were deduplicated.
+    async fn set_legacy_blob(t: &TestContext, msg_id: MsgId, name: &str) -> Result<()> {
+        let mut msg = Message::load_from_db(t, msg_id).await?;
+        msg.param.set(Param::File, format!("$BLOBDIR/{name}"));
+        msg.param.remove(Param::Filename);
+        msg.update_param(t).await?;
+        Ok(())
+    }
+
+    async fn get_blob_refcount(t: &TestContext, msg: &Message) -> Result<Option<i64>> {
+        let blob = msg.param.get_blob(Param::File, t, false).await?.unwrap();
+        t.sql
+            .query_get_value(
+                "SELECT refcount FROM blobs WHERE name=?",
+                (blob.as_file_name(),),
+            )
+            .await
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_remove_unreferenced_blobs() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let chat = alice.create_chat(&bob).await;
+
+        let file = alice.get_blobdir().join("report.pdf");
+        fs::write(&file, b"%PDF- content").await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), None);
+        alice.send_msg(chat.id, &mut msg).await;
+        let sent = Message::load_from_db(&alice, msg.id).await?;
+        assert_eq!(sent.get_filename().unwrap(), "report.pdf");
+        assert!(sent
+            .param
+            .get_blob(Param::File, &alice, false)
+            .await?
+            .unwrap()
+            .is_deduplicated());
+        assert_eq!(get_blob_refcount(&alice, &sent).await?, Some(1));
+
+        // Files of forwarded messages are deduplicated as well.
+        set_legacy_blob(&alice, msg.id, "report.pdf").await?;
+        chat::forward_msgs(&alice, &[msg.id], chat.id).await?;
+        let orig = Message::load_from_db(&alice, msg.id).await?;
+        let fwd = alice.get_last_msg_in(chat.id).await;
+        assert_ne!(fwd.id, orig.id);
+        let path = orig.get_file(&alice).unwrap();
+        assert_eq!(fwd.get_file(&alice).unwrap(), path);
+        assert_eq!(orig.get_filename().unwrap(), "report.pdf");
+        assert_eq!(fwd.get_filename().unwrap(), "report.pdf");
+        assert_eq!(get_blob_refcount(&alice, &orig).await?, Some(2));
+
+        // The blob is kept as long as a message references it.
+        alice
+            .sql
+            .execute("UPDATE blobs SET timestamp=0", ())
+            .await?;
+        delete_msgs(&alice, &[orig.id]).await?;
+        assert!(path.exists());
+        assert_eq!(get_blob_refcount(&alice, &fwd).await?, Some(1));
+
+        delete_msgs(&alice, &[fwd.id]).await?;
+        assert!(!path.exists());
+        assert_eq!(get_blob_refcount(&alice, &fwd).await?, None);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_keep_recent_unreferenced_blobs() -> Result<()> {
+        let t = TestContext::new().await;
+        let blob = BlobObject::create_and_deduplicate(&t, "foo.txt", b"hello").await?;
+        remove_unreferenced_blobs(&t).await?;
+        assert!(blob.to_abs_path().exists());
+
+        t.sql.execute("UPDATE blobs SET timestamp=0", ()).await?;
+        remove_unreferenced_blobs(&t).await?;
+        assert!(!blob.to_abs_path().exists());
+        Ok(())
+    }
 }
@@ -12,7 +12,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::aheader::EncryptPreference;
-use crate::blob::BlobObject;
+use crate::blob::{deduplicate_file, remove_unreferenced_blobs, BlobObject};
 use crate::color::str_to_color;
 use crate::config::Config;
 use crate::constants::{
@@ -25,22 +25,27 @@
 use crate::ephemeral::Timer as EphemeralTimer;
 use crate::events::EventType;
 use crate::html::new_html_mimepart;
+#[cfg(feature = "location")]
+use crate::location;
+use crate::log::LogExt;
 use crate::message::{self, Message, MessageState, MsgId, Viewtype};
 use crate::mimefactory::MimeFactory;
 use crate::mimeparser::SystemMessage;
+use crate::notifications::add_pending_notifications;
 use crate::param::{Param, Params};
 use crate::peerstate::{Peerstate, PeerstateVerifiedStatus};
 use crate::receive_imf::ReceivedMsg;
 use crate::scheduler::InterruptInfo;
 use crate::smtp::send_msg_to_smtp;
+use crate::sql;
 use crate::stock_str;
+use crate::sync::{ChatAction, ChatSyncId, SyncData};
 use crate::tools::{
     buf_compress, create_id, create_outgoing_rfc724_mid, create_smeared_timestamp,
     create_smeared_timestamps, get_abs_path, gm2local_offset, improve_single_line_input,
     strip_rtlo_characters, time, IsNoneOrEmpty,
 };
 use crate::webxdc::WEBXDC_SUFFIX;
-use crate::{location, sql};
 
 /// An chat item, such as a message or a marker.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -509,6 +514,18 @@ pub async fn set_protection(self, context: &Context, protect: ProtectionStatus)
 
     /// Archives or unarchives a chat.
     pub async fn set_visibility(self, context: &Context, visibility: ChatVisibility) -> Result<()> {
+        self.set_visibility_ex(context, true, visibility).await
+    }
+
+    /// Archives or unarchives a chat.
+    /// If `sync` is set, the change is recorded with the current timestamp
+    /// and synchronized to other devices.
+    pub(crate) async fn set_visibility_ex(
+        self,
+        context: &Context,
+        sync: bool,
+        visibility: ChatVisibility,
+    ) -> Result<()> {
         ensure!(
             !self.is_special(),
             "bad chat_id, can not be special chat: {}",
@@ -534,6 +551,58 @@ pub async fn set_visibility(self, context: &Context, visibility: ChatVisibility)
 
         context.emit_msgs_changed_without_ids();
 
+        if sync {
+            self.sync(
+                context,
+                ChatAction::SetVisibility(visibility),
+                Param::VisibilityTimestamp,
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds a chat action to the list of items to be synchronized to other devices
+    /// and sends out a sync message.
+    ///
+    /// `scope` is the chat parameter holding the timestamp of the last change of the setting,
+    /// it is updated so that older changes received from other devices are ignored.
+    /// Chats that cannot be identified on other devices, eg. the device chat, are not synced.
+    pub(crate) async fn sync(
+        self,
+        context: &Context,
+        action: ChatAction,
+        scope: Param,
+    ) -> Result<()> {
+        let timestamp = time();
+        self.update_timestamp(context, scope, timestamp).await?;
+
+        let chat = Chat::load_from_db(context, self).await?;
+        let id = match chat.typ {
+            Chattype::Undefined => return Ok(()),
+            Chattype::Single => {
+                if chat.is_device_talk() {
+                    return Ok(());
+                }
+                let contact_id = match get_chat_contacts(context, self).await?.first() {
+                    Some(contact_id) => *contact_id,
+                    None => return Ok(()),
+                };
+                let contact = Contact::get_by_id(context, contact_id).await?;
+                ChatSyncId::ContactAddr(contact.get_addr().to_string())
+            }
+            Chattype::Group | Chattype::Mailinglist | Chattype::Broadcast => {
+                if chat.grpid.is_empty() {
+                    return Ok(());
+                }
+                ChatSyncId::Grpid(chat.grpid)
+            }
+        };
+        context
+            .add_sync_item_with_timestamp(SyncData::AlterChat { id, action }, timestamp)
+            .await?;
+        context.send_sync_msg().await?;
         Ok(())
     }
 
@@ -621,6 +690,7 @@ pub async fn delete(self, context: &Context) -> Result<()> {
             .sql
             .execute("DELETE FROM msgs WHERE chat_id=?;", (self,))
             .await?;
+        context.sql.delete_cold_stored_chat_msgs(self).await?;
 
         context
             .sql
@@ -633,6 +703,10 @@ pub async fn delete(self, context: &Context) -> Result<()> {
             .await?;
 
         context.emit_msgs_changed_without_ids();
+        remove_unreferenced_blobs(context)
+            .await
+            .log_err(context)
+            .ok();
 
         context.set_config(Config::LastHousekeeping, None).await?;
         context
@@ -871,6 +945,57 @@ pub async fn get_fresh_msg_cnt(self, context: &Context) -> Result<usize> {
         Ok(count)
     }
 
+    /// Returns the chat-specific override of `Config::DeleteServerAfter`
+    /// or `None` if the global setting is used for this chat.
+    ///
+    /// The values have the same meaning as the config values:
+    /// 0 means "never", 1 means "at once", other values are seconds.
+    pub async fn get_delete_server_after(self, context: &Context) -> Result<Option<i64>> {
+        let delete_server_after = context
+            .sql
+            .query_get_value("SELECT delete_server_after FROM chats WHERE id=?", (self,))
+            .await?;
+        Ok(delete_server_after.flatten())
+    }
+
+    /// Overrides `Config::DeleteServerAfter` for this chat,
+    /// `None` reverts to the global setting.
+    /// See [`ChatId::get_delete_server_after`] for the meaning of the values.
+    pub async fn set_delete_server_after(
+        self,
+        context: &Context,
+        delete_server_after: Option<i64>,
+    ) -> Result<()> {
+        ensure!(!self.is_special(), "Invalid chat ID");
+        ensure!(
+            delete_server_after.map_or(true, |value| value >= 0),
+            "Invalid delete_server_after value"
+        );
+        context
+            .sql
+            .execute(
+                "UPDATE chats SET delete_server_after=? WHERE id=?",
+                (delete_server_after, self),
+            )
+            .await?;
+        context.emit_event(EventType::ChatModified(self));
+        Ok(())
+    }
+
+    /// Returns the server deletion timeout effective for this chat
+    /// like [`Context::get_config_delete_server_after`] does for the global setting.
+    pub(crate) async fn get_effective_delete_server_after(
+        self,
+        context: &Context,
+    ) -> Result<Option<i64>> {
+        match self.get_delete_server_after(context).await? {
+            None => context.get_config_delete_server_after().await,
+            Some(0) => Ok(None),
+            Some(1) => Ok(Some(0)),
+            Some(x) => Ok(Some(x)),
+        }
+    }
+
     pub(crate) async fn get_param(self, context: &Context) -> Result<Params> {
         let res: Option<String> = context
             .sql
@@ -2040,6 +2165,14 @@ async fn prepare_msg_blob(context: &Context, msg: &mut Message) -> Result<()> {
                 );
             }
         }
+        if !msg.is_increation() && !blob.is_deduplicated() {
+            // Sent files share a single blob with forwarded copies and identical received files.
+            if !msg.param.exists(Param::Filename) {
+                msg.param.set(Param::Filename, blob.as_file_name());
+            }
+            blob =
+                BlobObject::create_and_deduplicate_from_path(context, &blob.to_abs_path()).await?;
+        }
         msg.param.set(Param::File, blob.as_name());
 
         if msg.viewtype == Viewtype::File || msg.viewtype == Viewtype::Image {
@@ -2327,6 +2460,7 @@ async fn create_send_msg_job(context: &Context, msg_id: MsgId) -> Result<Option<
         msg.chat_id.set_gossiped_timestamp(context, time()).await?;
     }
 
+    #[cfg(feature = "location")]
     if 0 != rendered_msg.last_added_location_id {
         if let Err(err) = location::set_kml_sent_timestamp(context, msg.chat_id, time()).await {
             error!(context, "Failed to set kml sent_timestamp: {err:#}.");
@@ -2401,6 +2535,7 @@ pub async fn send_text_msg(
 }
 
 /// Sends invitation to a videochat.
+#[cfg(feature = "videochat")]
 pub async fn send_videochat_invitation(context: &Context, chat_id: ChatId) -> Result<MsgId> {
     ensure!(
         !chat_id.is_special(),
@@ -3148,16 +3283,36 @@ fn column_result(value: rusqlite::types::ValueRef) -> rusqlite::types::FromSqlRe
 
 /// Mutes the chat for a given duration or unmutes it.
 pub async fn set_muted(context: &Context, chat_id: ChatId, duration: MuteDuration) -> Result<()> {
+    set_muted_ex(context, true, chat_id, duration).await
+}
+
+/// Mutes the chat for a given duration or unmutes it.
+/// If `sync` is set, the change is synchronized to other devices.
+pub(crate) async fn set_muted_ex(
+    context: &Context,
+    sync: bool,
+    chat_id: ChatId,
+    duration: MuteDuration,
+) -> Result<()> {
     ensure!(!chat_id.is_special(), "Invalid chat ID");
     context
         .sql
         .execute(
             "UPDATE chats SET muted_until=? WHERE id=?;",
-            (duration, chat_id),
+            (&duration, chat_id),
         )
         .await
         .context(format!("Failed to set mute duration for {chat_id}"))?;
     context.emit_event(EventType::ChatModified(chat_id));
+    if sync {
+        chat_id
+            .sync(
+                context,
+                ChatAction::SetMuted(duration),
+                Param::MuteTimestamp,
+            )
+            .await?;
+    }
     Ok(())
 }
 
@@ -3359,6 +3514,23 @@ pub async fn set_chat_profile_image(
     Ok(())
 }
 
+/// Sets group profile image from encoded image data such as PNG or JPEG.
+///
+/// Like [`set_chat_profile_image`], for callers which have no access to the file system
+/// of the core, e.g. remote JSON-RPC clients.
+pub async fn set_chat_profile_image_from_bytes(
+    context: &Context,
+    chat_id: ChatId,
+    image_data: &[u8],
+) -> Result<()> {
+    let extension = image::guess_format(image_data)
+        .ok()
+        .and_then(|format| format.extensions_str().first())
+        .context("Failed to set profile image; unknown image format")?;
+    let blob = BlobObject::create(context, &format!("group-image.{extension}"), image_data).await?;
+    set_chat_profile_image(context, chat_id, &blob.to_abs_path().to_string_lossy()).await
+}
+
 /// Forwards multiple messages to a chat.
 pub async fn forward_msgs(context: &Context, msg_ids: &[MsgId], chat_id: ChatId) -> Result<()> {
     ensure!(!msg_ids.is_empty(), "empty msgs_ids: nothing to forward");
@@ -3396,6 +3568,16 @@ pub async fn forward_msgs(context: &Context, msg_ids: &[MsgId], chat_id: ChatId)
                 bail!("cannot forward drafts.");
             }
 
+            if msg.state != MessageState::OutPreparing {
+                // The original and the forwarded message share a single blob,
+                // so deleting one of them does not remove the file of the other.
+                match deduplicate_file(context, &mut msg.param).await {
+                    Ok(true) => msg.update_param(context).await?,
+                    Ok(false) => {}
+                    Err(err) => warn!(context, "Cannot deduplicate file of {src_msg_id}: {err:#}."),
+                }
+            }
+
             let original_param = msg.param.clone();
 
             // we tested a sort of broadcast
@@ -3645,6 +3827,9 @@ pub async fn add_device_msg_with_importance(
     }
 
     if !msg_id.is_unset() {
+        if important {
+            add_pending_notifications(context, &[msg_id]).await?;
+        }
         chat_id.emit_msg_event(context, msg_id, important);
     }
 
@@ -3686,6 +3871,7 @@ pub(crate) async fn delete_and_reset_all_device_msgs(context: &Context) -> Resul
         .sql
         .execute("DELETE FROM msgs WHERE from_id=?;", (ContactId::DEVICE,))
         .await?;
+    context.sql.delete_cold_stored_device_msgs().await?;
     context.sql.execute("DELETE FROM devmsglabels;", ()).await?;
 
     // Insert labels for welcome messages to avoid them being readded on reconfiguration.
@@ -4855,6 +5041,27 @@ async fn test_set_chat_name() {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_set_chat_profile_image_from_bytes() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "foo").await?;
+
+        assert!(
+            set_chat_profile_image_from_bytes(&t, chat_id, b"not an image")
+                .await
+                .is_err()
+        );
+        set_chat_profile_image_from_bytes(
+            &t,
+            chat_id,
+            include_bytes!("../test-data/image/avatar64x64.png"),
+        )
+        .await?;
+        let chat = Chat::load_from_db(&t, chat_id).await?;
+        assert!(chat.get_profile_image(&t).await?.is_some());
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_create_same_chat_twice() {
         let context = TestContext::new().await;
@@ -5930,6 +6137,7 @@ async fn test_chat_get_encryption_info() -> Result<()> {
         Ok(())
     }
 
+    #[cfg(feature = "webxdc")]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_get_chat_media() -> Result<()> {
         let t = TestContext::new_alice().await;
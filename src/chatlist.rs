@@ -1,5 +1,7 @@
 //! # Chat list module.
 
+use std::collections::HashMap;
+
 use anyhow::{ensure, Context as _, Result};
 
 use crate::chat::{update_special_chat_names, Chat, ChatId, ChatVisibility};
@@ -9,6 +11,7 @@
 };
 use crate::contact::{Contact, ContactId};
 use crate::context::Context;
+use crate::events::EventType;
 use crate::message::{Message, MessageState, MsgId};
 use crate::stock_str;
 use crate::summary::Summary;
@@ -295,11 +298,31 @@ pub async fn get_summary(
     }
 
     /// Returns a summary for a given chatlist item.
+    ///
+    /// Summaries are cached until an event signals a change of the chat,
+    /// see [`SummaryCache`].
     pub async fn get_summary2(
         context: &Context,
         chat_id: ChatId,
         lastmsg_id: Option<MsgId>,
         chat: Option<&Chat>,
+    ) -> Result<Summary> {
+        let generation = context.summary_cache.generation();
+        if let Some(summary) = context.summary_cache.get(chat_id, lastmsg_id) {
+            return Ok(summary);
+        }
+        let summary = Chatlist::load_summary(context, chat_id, lastmsg_id, chat).await?;
+        context
+            .summary_cache
+            .insert(chat_id, lastmsg_id, generation, &summary);
+        Ok(summary)
+    }
+
+    async fn load_summary(
+        context: &Context,
+        chat_id: ChatId,
+        lastmsg_id: Option<MsgId>,
+        chat: Option<&Chat>,
     ) -> Result<Summary> {
         let chat_loaded: Chat;
         let chat = if let Some(chat) = chat {
@@ -354,6 +377,96 @@ pub fn iter(&self) -> impl Iterator<Item = &(ChatId, Option<MsgId>)> {
     }
 }
 
+/// Maximum number of summaries kept by [`SummaryCache`].
+const SUMMARY_CACHE_SIZE: usize = 1000;
+
+/// Cache of chatlist summaries keyed by chat and last message.
+///
+/// The summary also depends on the state of the last message,
+/// the sender name and the chat, so entries are invalidated
+/// by the events emitted on such changes, see [`SummaryCache::invalidate`].
+#[derive(Debug, Default)]
+pub(crate) struct SummaryCache {
+    inner: std::sync::Mutex<SummaryCacheInner>,
+}
+
+#[derive(Debug, Default)]
+struct SummaryCacheInner {
+    summaries: HashMap<(ChatId, Option<MsgId>), Summary>,
+
+    /// Incremented on each invalidation,
+    /// so summaries loaded before are not inserted afterwards.
+    generation: u64,
+}
+
+impl SummaryCache {
+    fn generation(&self) -> u64 {
+        self.inner.lock().expect("Mutex is poisoned").generation
+    }
+
+    fn get(&self, chat_id: ChatId, lastmsg_id: Option<MsgId>) -> Option<Summary> {
+        self.inner
+            .lock()
+            .expect("Mutex is poisoned")
+            .summaries
+            .get(&(chat_id, lastmsg_id))
+            .cloned()
+    }
+
+    fn insert(
+        &self,
+        chat_id: ChatId,
+        lastmsg_id: Option<MsgId>,
+        generation: u64,
+        summary: &Summary,
+    ) {
+        let mut inner = self.inner.lock().expect("Mutex is poisoned");
+        if inner.generation != generation {
+            return;
+        }
+        if inner.summaries.len() >= SUMMARY_CACHE_SIZE {
+            inner.summaries.clear();
+        }
+        inner
+            .summaries
+            .insert((chat_id, lastmsg_id), summary.clone());
+    }
+
+    /// Removes all summaries.
+    pub(crate) fn clear(&self) {
+        let mut inner = self.inner.lock().expect("Mutex is poisoned");
+        inner.summaries.clear();
+        inner.generation += 1;
+    }
+
+    /// Removes summaries which may be changed according to the emitted `event`.
+    pub(crate) fn invalidate(&self, event: &EventType) {
+        let chat_id = match event {
+            EventType::MsgsChanged { chat_id, .. }
+            | EventType::ReactionsChanged { chat_id, .. }
+            | EventType::IncomingMsg { chat_id, .. }
+            | EventType::MsgDelivered { chat_id, .. }
+            | EventType::MsgFailed { chat_id, .. }
+            | EventType::MsgGivenUp { chat_id, .. }
+            | EventType::MsgRead { chat_id, .. }
+            | EventType::ChatEphemeralTimerModified { chat_id, .. }
+            | EventType::MsgsNoticed(chat_id)
+            | EventType::ChatModified(chat_id) => *chat_id,
+            EventType::ContactsChanged(_)
+            | EventType::WebxdcStatusUpdate { .. }
+            | EventType::WebxdcInstanceDeleted { .. } => ChatId::new(0),
+            _ => return,
+        };
+        let mut inner = self.inner.lock().expect("Mutex is poisoned");
+        if chat_id.is_unset() {
+            inner.summaries.clear();
+        } else {
+            inner.summaries.retain(|(id, _), _| *id != chat_id);
+        }
+        inner.generation += 1;
+    }
+}
+
 /// Returns the number of archived chats
 pub async fn get_archived_cnt(context: &Context) -> Result<usize> {
     let count = context
@@ -388,7 +501,7 @@ pub async fn get_last_message_for_chat(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::chat::{create_group_chat, get_chat_contacts, ProtectionStatus};
+    use crate::chat::{create_group_chat, get_chat_contacts, send_text_msg, ProtectionStatus};
     use crate::message::Viewtype;
     use crate::receive_imf::receive_imf;
     use crate::stock_str::StockMessage;
@@ -643,4 +756,30 @@ async fn test_get_summary_unwrap() {
         let summary = chats.get_summary(&t, 0, None).await.unwrap();
         assert_eq!(summary.text, "foo: bar test"); // the linebreak should be removed from summary
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_summary_cache() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let msg_id = send_text_msg(&t, chat.id, "Hello".to_string()).await?;
+        let chats = Chatlist::try_load(&t, 0, None, None).await?;
+        let index = chats.get_index_for_id(chat.id).unwrap();
+        let summary = chats.get_summary(&t, index, None).await?;
+        assert_eq!(summary.text, "Hello");
+        assert_eq!(summary.state, MessageState::OutPending);
+
+        // Changes without an event are not visible.
+        t.sql
+            .execute("UPDATE msgs SET txt='Bye' WHERE id=?", (msg_id,))
+            .await?;
+        let summary = chats.get_summary(&t, index, None).await?;
+        assert_eq!(summary.text, "Hello");
+
+        // `MsgDelivered` event invalidates the summary.
+        msg_id.set_delivered(&t).await?;
+        let summary = chats.get_summary(&t, index, None).await?;
+        assert_eq!(summary.text, "Bye");
+        assert_eq!(summary.state, MessageState::OutDelivered);
+        Ok(())
+    }
 }
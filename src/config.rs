@@ -14,6 +14,7 @@
 use crate::events::EventType;
 use crate::mimefactory::RECOMMENDED_FILE_SIZE;
 use crate::provider::{get_provider_by_id, Provider};
+use crate::scheduler::InterruptInfo;
 use crate::tools::{get_abs_path, improve_single_line_input, EmailAddress};
 
 /// The available configuration keys.
@@ -54,6 +55,20 @@ pub enum Config {
     /// How to check IMAP server TLS certificates.
     ImapCertificateChecks,
 
+    /// URL of the JMAP session resource.
+    ///
+    /// If set, configuring the account fails if there is no usable session resource at the URL.
+    /// Otherwise the session resource is autodiscovered at `/.well-known/jmap` of the email domain.
+    /// If a session resource is found, messages are fetched and sent using JMAP
+    /// instead of IMAP and SMTP, using the IMAP credentials.
+    JmapUrl,
+
+    /// OAuth 2 client ID to use instead of the built-in one.
+    ///
+    /// Required to use OAuth 2 with Microsoft,
+    /// as the application has to be registered with the Microsoft identity platform.
+    Oauth2ClientId,
+
     /// SMTP server hostname.
     SendServer,
 
@@ -95,6 +110,39 @@ pub enum Config {
     /// SOCKS5 proxy server password.
     Socks5Password,
 
+    /// True if HTTP CONNECT proxy is enabled.
+    ///
+    /// Ignored if SOCKS5 is enabled.
+    HttpProxyEnabled,
+
+    /// HTTP proxy server hostname or address.
+    HttpProxyHost,
+
+    /// HTTP proxy server port.
+    HttpProxyPort,
+
+    /// True if the connection to the HTTP proxy should be protected with TLS.
+    HttpProxyTls,
+
+    /// HTTP proxy server username.
+    HttpProxyUser,
+
+    /// HTTP proxy server password.
+    HttpProxyPassword,
+
+    /// True if connections should be made using the built-in Tor client.
+    ///
+    /// Takes precedence over SOCKS5 and HTTP proxy settings.
+    /// Connections fail if Delta Chat is built without `tor` feature.
+    TorEnabled,
+
+    /// True if the server certificate SPKI should be pinned on the first successful connection
+    /// to a server without a pin.
+    ///
+    /// Explicitly set pins are checked regardless of this setting.
+    #[strum(props(default = "0"))]
+    LearnCertificatePins,
+
     /// Own name to use in the `From:` field when sending messages.
     Displayname,
 
@@ -123,6 +171,17 @@ pub enum Config {
     #[strum(props(default = "0"))]
     SentboxWatch,
 
+    /// Additional folders to watch for new messages, separated by newlines.
+    ///
+    /// Useful if server-side filters sort chat messages into custom folders.
+    /// Changes take effect after restarting I/O.
+    WatchFolders,
+
+    /// True if chat messages should be moved out of `WatchFolders`
+    /// to the inbox or, if `MvboxMove` is set, to the mvbox.
+    #[strum(props(default = "0"))]
+    WatchFoldersMove,
+
     /// True if chat messages should be moved to a separate folder.
     #[strum(props(default = "1"))]
     MvboxMove,
@@ -180,6 +239,32 @@ pub enum Config {
     /// `ProviderOptions::delete_to_trash`.
     DeleteToTrash,
 
+    /// Timeout in seconds after which messages are deleted from the Trash folder
+    /// if `DeleteToTrash` is in effect.
+    /// The timeout is counted from the time Delta Chat moved the message to the Trash folder,
+    /// messages moved there by other clients are not deleted.
+    ///
+    /// Equals to 0 by default, which means messages are never deleted from the Trash folder.
+    #[strum(props(default = "0"))]
+    DeleteTrashAfter,
+
+    /// Age in seconds after which media files of messages are deleted from the device
+    /// by housekeeping, see [`MediaCleanupPolicy`](crate::storage::MediaCleanupPolicy).
+    ///
+    /// Equals to 0 by default, which means media is never deleted automatically.
+    #[strum(props(default = "0"))]
+    MediaCleanupAfter,
+
+    /// Media is deleted automatically only from chats using more than this number of bytes.
+    ///
+    /// Equals to 0 by default, which means media is deleted from all chats.
+    #[strum(props(default = "0"))]
+    MediaCleanupMinChatSize,
+
+    /// Keep starred messages when deleting media automatically if true.
+    #[strum(props(default = "1"))]
+    MediaCleanupKeepStarred,
+
     /// Save raw MIME messages with headers in the database if true.
     SaveMimeHeaders,
 
@@ -243,6 +328,11 @@ pub enum Config {
     /// ID of the configured provider from the provider database.
     ConfiguredProvider,
 
+    /// URL of the JMAP session resource found when configuring.
+    ///
+    /// If set, messages are fetched and sent using JMAP instead of IMAP and SMTP.
+    ConfiguredJmapUrl,
+
     /// True if account is configured.
     Configured,
 
@@ -275,21 +365,132 @@ pub enum Config {
     /// Unset, when quota falls below minimal warning threshold again.
     QuotaExceeding,
 
+    /// Quota usage percentage at which the user is warned about a nearly full mailbox.
+    #[strum(props(default = "80"))]
+    QuotaWarnThreshold,
+
+    /// Quota usage percentage at which the user is warned again,
+    /// should be higher than `QuotaWarnThreshold`.
+    #[strum(props(default = "95"))]
+    QuotaErrorThreshold,
+
     /// address to webrtc instance to use for videochats
     WebrtcInstance,
 
     /// Timestamp of the last time housekeeping was run
     LastHousekeeping,
 
+    /// Interval in seconds in which housekeeping runs while IO is running.
+    ///
+    /// Equals to one day by default,
+    /// 0 means housekeeping only runs if started with `Context::run_housekeeping()`.
+    #[strum(props(default = "86400"))]
+    HousekeepingInterval,
+
+    /// Timestamp of the last database maintenance run by `Context::run_db_maintenance()`.
+    LastDbMaintenance,
+
     /// To how many seconds to debounce scan_all_folders. Used mainly in tests, to disable debouncing completely.
     #[strum(props(default = "60"))]
     ScanAllFoldersDebounceSecs,
 
+    /// Maximum number of IMAP connections used in parallel
+    /// to scan folders that are not watched.
+    /// The connections of the inbox loop and the other watched folders are used,
+    /// no additional connections are opened.
+    /// 1 = scan all folders over the inbox connection only.
+    #[strum(props(default = "1"))]
+    ScanFoldersConnections,
+
     /// Defines the max. size (in bytes) of messages downloaded automatically.
     /// 0 = no limit.
     #[strum(props(default = "0"))]
     DownloadLimit,
 
+    /// Max. size (in bytes) of images, including GIFs and stickers, downloaded automatically.
+    /// Unset = use `DownloadLimit`, 0 = no limit.
+    DownloadLimitImage,
+
+    /// Max. size (in bytes) of videos downloaded automatically.
+    /// Unset = use `DownloadLimit`, 0 = no limit.
+    DownloadLimitVideo,
+
+    /// Max. size (in bytes) of audio and voice messages downloaded automatically.
+    /// Unset = use `DownloadLimit`, 0 = no limit.
+    DownloadLimitAudio,
+
+    /// Max. size (in bytes) of webxdc apps downloaded automatically.
+    /// Unset = use `DownloadLimit`, 0 = no limit.
+    DownloadLimitWebxdc,
+
+    /// Like `DownloadLimitImage`, but used instead of it on metered networks.
+    DownloadLimitMeteredImage,
+
+    /// Like `DownloadLimitVideo`, but used instead of it on metered networks.
+    DownloadLimitMeteredVideo,
+
+    /// Like `DownloadLimitAudio`, but used instead of it on metered networks.
+    DownloadLimitMeteredAudio,
+
+    /// Like `DownloadLimitWebxdc`, but used instead of it on metered networks.
+    DownloadLimitMeteredWebxdc,
+
+    /// Class of the network the device is currently connected to, see [`NetworkClass`].
+    /// Set by the UI when the network changes.
+    ///
+    /// [`NetworkClass`]: crate::constants::NetworkClass
+    #[strum(props(default = "0"))] // also change NetworkClass.default() on changes
+    NetworkClass,
+
+    /// Max. size (in bytes) of messages sent out while the device is on a metered network
+    /// or roaming. Larger messages are kept in the queue until an unmetered network is available.
+    /// 0 = no limit.
+    #[strum(props(default = "0"))]
+    SendLimitMetered,
+
+    /// Whether to download messages automatically while roaming.
+    /// If disabled, only small messages are downloaded automatically while roaming,
+    /// regardless of the configured download limits.
+    #[strum(props(default = "0"))]
+    DownloadOnRoaming,
+
+    /// Max. number of attempts to send a message before giving up.
+    #[strum(props(default = "6"))]
+    SmtpMaxAttempts,
+
+    /// Delay in seconds before sending is retried after a failure.
+    #[strum(props(default = "30"))]
+    SmtpRetryDelay,
+
+    /// Factor the retry delay is multiplied with after each further failure.
+    #[strum(props(default = "3"))]
+    SmtpRetryBackoff,
+
+    /// If set, messages fail immediately on transient SMTP errors
+    /// and on permanent errors which are likely to be transient,
+    /// instead of being retried later.
+    #[strum(props(default = "0"))]
+    SmtpFailFast,
+
+    /// UnifiedPush endpoint URL registered on the server for push notifications,
+    /// see [`Context::set_unifiedpush_endpoint`].
+    UnifiedpushEndpoint,
+
+    /// APNs or FCM device token registered on the server for push notifications,
+    /// see [`Context::set_push_device_token`].
+    PushDeviceToken,
+
+    /// If set, fresh messages in muted groups which mention the user
+    /// or quote one of the user's messages are returned by [`Context::get_fresh_msgs`].
+    #[strum(props(default = "0"))]
+    NotifyMutedMentions,
+
+    /// How much content notifications reveal, see [`NotificationPrivacy`].
+    ///
+    /// [`NotificationPrivacy`]: crate::constants::NotificationPrivacy
+    #[strum(props(default = "0"))] // also change NotificationPrivacy.default() on changes
+    NotificationPrivacy,
+
     /// Send sync messages, requires `BccSelf` to be set as well.
     /// In a future versions, this switch may be removed.
     #[strum(props(default = "0"))]
@@ -448,6 +649,13 @@ pub async fn set_config(&self, key: Config, value: Option<&str>) -> Result<()> {
                 }
                 self.emit_event(EventType::SelfavatarChanged);
             }
+            Config::NetworkClass => {
+                self.sql.set_raw_config(key.as_ref(), value).await?;
+                // Send out messages deferred on metered networks if the network is unmetered now.
+                self.scheduler
+                    .interrupt_smtp(InterruptInfo::new(false))
+                    .await;
+            }
             Config::DeleteDeviceAfter => {
                 let ret = self.sql.set_raw_config(key.as_ref(), value).await;
                 // Interrupt ephemeral loop to delete old messages immediately.
@@ -19,7 +19,7 @@
 use futures::FutureExt;
 use futures_lite::FutureExt as _;
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
-use server_params::{expand_param_vector, ServerParams};
+use server_params::{expand_param_vector, order_onion_servers, ServerParams};
 use tokio::task;
 
 use crate::config::Config;
@@ -37,7 +37,7 @@
 use crate::socks::Socks5Config;
 use crate::stock_str;
 use crate::tools::{time, EmailAddress};
-use crate::{chat, e2ee, provider};
+use crate::{chat, e2ee, jmap, provider, push};
 
 macro_rules! progress {
     ($context:tt, $progress:expr, $comment:expr) => {
@@ -74,6 +74,7 @@ pub async fn configure(&self) -> Result<()> {
             self.sql.is_open().await,
             "cannot configure, database not opened."
         );
+        self.ensure_network_allowed()?;
         let cancel_channel = self.alloc_ongoing().await?;
 
         let res = self
@@ -262,8 +263,11 @@ async fn configure(ctx: &Context, param: &mut LoginParam) -> Result<()> {
                                 strict_tls: Some(provider.opt.strict_tls),
                             })
                             .collect();
+                        let use_onion = socks5_config
+                            .as_ref()
+                            .map_or(false, |socks5_config| socks5_config.is_tor());
 
-                        param_autoconfig = Some(servers)
+                        param_autoconfig = Some(order_onion_servers(servers, use_onion))
                     }
                 }
                 provider::Status::Broken => {
@@ -450,6 +454,11 @@ async fn configure(ctx: &Context, param: &mut LoginParam) -> Result<()> {
 
     progress!(ctx, 900);
 
+    // Decide once whether JMAP is used instead of IMAP and SMTP.
+    let jmap_url = jmap::discover(ctx, param, &param_domain).await?;
+    ctx.set_config(Config::ConfiguredJmapUrl, jmap_url.as_deref())
+        .await?;
+
     let create_mvbox = ctx.should_watch_mvbox().await?;
 
     imap.configure_folders(ctx, create_mvbox).await?;
@@ -479,6 +488,8 @@ async fn configure(ctx: &Context, param: &mut LoginParam) -> Result<()> {
 
     ctx.set_config_bool(Config::FetchedExistingMsgs, false)
         .await?;
+    // The server may have changed, register the push token again.
+    push::reset_push_registration(ctx).await?;
     ctx.scheduler
         .interrupt_inbox(InterruptInfo::new(false))
         .await;
@@ -1,5 +1,6 @@
 //! Variable server parameters lists
 
+use crate::net::tor::is_onion;
 use crate::provider::{Protocol, Socket};
 
 /// Set of variable parameters to try during configuration.
@@ -194,6 +195,32 @@ pub(crate) fn expand_param_vector(
         .collect()
 }
 
+/// Orders servers according to whether Tor onion services can be reached.
+///
+/// If `use_onion` is true, onion services are moved to the front,
+/// so they are tried before clearnet servers.
+/// Otherwise onion services are removed
+/// if there is a clearnet server for the same protocol.
+pub(crate) fn order_onion_servers(v: Vec<ServerParams>, use_onion: bool) -> Vec<ServerParams> {
+    let (onion, clearnet): (Vec<_>, Vec<_>) =
+        v.into_iter().partition(|params| is_onion(&params.hostname));
+    if use_onion {
+        onion.into_iter().chain(clearnet).collect()
+    } else {
+        let mut res: Vec<ServerParams> = Vec::new();
+        for params in onion {
+            if !clearnet
+                .iter()
+                .any(|clearnet_params| clearnet_params.protocol == params.protocol)
+            {
+                res.push(params);
+            }
+        }
+        res.extend(clearnet);
+        res
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -380,4 +407,46 @@ fn test_expand_param_vector() {
             ],
         );
     }
+
+    #[test]
+    fn test_order_onion_servers() {
+        let server = |protocol, hostname: &str| ServerParams {
+            protocol,
+            hostname: hostname.to_string(),
+            port: 0,
+            socket: Socket::Automatic,
+            username: "".to_string(),
+            strict_tls: None,
+        };
+        let v = vec![
+            server(Protocol::Imap, "imap.example.org"),
+            server(Protocol::Imap, "exampleimap.onion"),
+            server(Protocol::Smtp, "smtp.example.org"),
+            server(Protocol::Smtp, "examplesmtp.onion"),
+        ];
+
+        assert_eq!(
+            order_onion_servers(v.clone(), true),
+            vec![
+                server(Protocol::Imap, "exampleimap.onion"),
+                server(Protocol::Smtp, "examplesmtp.onion"),
+                server(Protocol::Imap, "imap.example.org"),
+                server(Protocol::Smtp, "smtp.example.org"),
+            ]
+        );
+        assert_eq!(
+            order_onion_servers(v, false),
+            vec![
+                server(Protocol::Imap, "imap.example.org"),
+                server(Protocol::Smtp, "smtp.example.org"),
+            ]
+        );
+
+        // Onion-only servers are kept even without Tor.
+        let v = vec![
+            server(Protocol::Imap, "exampleimap.onion"),
+            server(Protocol::Smtp, "smtp.example.org"),
+        ];
+        assert_eq!(order_onion_servers(v.clone(), false), v);
+    }
 }
@@ -54,6 +54,40 @@ pub enum MediaQuality {
     Worse = 1,
 }
 
+/// Class of the network the device is connected to.
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, FromSql, ToSql,
+)]
+#[repr(u8)]
+pub enum NetworkClass {
+    /// Unmetered network, e.g. Wi-Fi.
+    #[default] // also change Config.NetworkClass props(default) on changes
+    Unmetered = 0,
+
+    /// Metered network, e.g. mobile data.
+    Metered = 1,
+
+    /// Metered network while roaming.
+    Roaming = 2,
+}
+
+/// How much content notifications reveal.
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, FromSql, ToSql,
+)]
+#[repr(u8)]
+pub enum NotificationPrivacy {
+    /// Show chat, sender and message text.
+    #[default] // also change Config.NotificationPrivacy props(default) on changes
+    Full = 0,
+
+    /// Show chat and sender, but not the message text.
+    SenderOnly = 1,
+
+    /// Show only that there are new messages.
+    Generic = 2,
+}
+
 /// Type of the key to generate.
 #[derive(
     Debug, Default, Display, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, FromSql, ToSql,
@@ -262,6 +296,33 @@ fn test_mediaquality_values() {
         assert_eq!(MediaQuality::Worse, MediaQuality::from_i32(1).unwrap());
     }
 
+    #[test]
+    fn test_network_class_values() {
+        // values may be written to disk and must not change
+        assert_eq!(NetworkClass::Unmetered, NetworkClass::default());
+        assert_eq!(NetworkClass::Unmetered, NetworkClass::from_i32(0).unwrap());
+        assert_eq!(NetworkClass::Metered, NetworkClass::from_i32(1).unwrap());
+        assert_eq!(NetworkClass::Roaming, NetworkClass::from_i32(2).unwrap());
+    }
+
+    #[test]
+    fn test_notification_privacy_values() {
+        // values may be written to disk and must not change
+        assert_eq!(NotificationPrivacy::Full, NotificationPrivacy::default());
+        assert_eq!(
+            NotificationPrivacy::Full,
+            NotificationPrivacy::from_i32(0).unwrap()
+        );
+        assert_eq!(
+            NotificationPrivacy::SenderOnly,
+            NotificationPrivacy::from_i32(1).unwrap()
+        );
+        assert_eq!(
+            NotificationPrivacy::Generic,
+            NotificationPrivacy::from_i32(2).unwrap()
+        );
+    }
+
     #[test]
     fn test_videochattype_values() {
         // values may be written to disk and must not change
@@ -1022,15 +1022,20 @@ pub async fn get_encrinfo(context: &Context, contact_id: ContactId) -> Result<St
     pub async fn delete(context: &Context, contact_id: ContactId) -> Result<()> {
         ensure!(!contact_id.is_special(), "Can not delete special contact");
 
+        // Messages in cold storage can be restored and still need the contact.
+        let in_cold_storage = context
+            .sql
+            .has_cold_stored_msgs_of_contact(contact_id)
+            .await?;
         context
             .sql
             .transaction(move |transaction| {
                 // make sure, the transaction starts with a write command and becomes EXCLUSIVE by that -
                 // upgrading later may be impossible by races.
                 let deleted_contacts = transaction.execute(
-                    "DELETE FROM contacts WHERE id=?
+                    "DELETE FROM contacts WHERE id=? AND ?=0
                      AND (SELECT COUNT(*) FROM chats_contacts WHERE contact_id=?)=0;",
-                    (contact_id, contact_id),
+                    (contact_id, in_cold_storage, contact_id),
                 )?;
                 if deleted_contacts == 0 {
                     transaction.execute(
@@ -4,30 +4,42 @@
 use std::ffi::OsString;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{bail, ensure, Context as _, Result};
 use async_channel::{self as channel, Receiver, Sender};
+use num_traits::FromPrimitive;
 use ratelimit::Ratelimit;
 use tokio::sync::{Mutex, Notify, RwLock};
 
 use crate::chat::{get_chat_cnt, ChatId};
+use crate::chatlist::SummaryCache;
 use crate::config::Config;
-use crate::constants::DC_VERSION_STR;
-use crate::contact::Contact;
+use crate::constants::{Chattype, NetworkClass, DC_VERSION_STR};
+use crate::contact::{Contact, ContactId};
 use crate::debug_logging::DebugLogging;
-use crate::events::{Event, EventEmitter, EventType, Events};
+use crate::events::{Event, EventEmitter, EventHistory, EventType, Events};
+use crate::imap::scan_folders::FolderScanQueue;
+use crate::imap::{FolderMeaning, Imap};
+use crate::jmap::Jmap;
 use crate::key::{DcKey, SignedPublicKey};
+use crate::log::{LogBuffer, LogExt};
 use crate::login_param::LoginParam;
-use crate::message::{self, MessageState, MsgId};
+use crate::message::{self, Message, MessageState, MsgId};
+use crate::metrics::MetricsCounters;
+use crate::net::traffic::TrafficCounters;
+use crate::oauth2::Oauth2TokenProvider;
 use crate::quota::QuotaInfo;
 use crate::scheduler::SchedulerState;
-use crate::sql::Sql;
+use crate::search;
+use crate::sql::{is_sqlcipher, MigrationPlan, Sql, SqlConfig, SqlStorage};
 use crate::stock_str::StockStrings;
 use crate::timesmearing::SmearedTimestamp;
 use crate::tools::{duration_to_str, time};
+use crate::transport::Transport;
+use crate::webxdc::RealtimeChannels;
 
 /// Builder for the [`Context`].
 ///
@@ -78,6 +90,9 @@ pub struct ContextBuilder {
     events: Events,
     stock_strings: StockStrings,
     password: Option<String>,
+    sql_config: SqlConfig,
+    blobdir: Option<PathBuf>,
+    storage: Option<Arc<dyn SqlStorage>>,
 }
 
 impl ContextBuilder {
@@ -93,6 +108,9 @@ pub fn new(dbfile: PathBuf) -> Self {
             events: Events::new(),
             stock_strings: StockStrings::new(),
             password: None,
+            sql_config: Default::default(),
+            blobdir: None,
+            storage: None,
         }
     }
 
@@ -147,10 +165,59 @@ pub fn with_password(mut self, password: String) -> Self {
         self
     }
 
+    /// Sets the configuration of the database connections, see [`SqlConfig`].
+    pub fn with_sql_config(mut self, sql_config: SqlConfig) -> Self {
+        self.sql_config = sql_config;
+        self
+    }
+
+    /// Sets the directory to store blobs in
+    /// instead of the default one next to the database file.
+    ///
+    /// The directory is created if it does not exist.
+    pub fn with_blobdir(mut self, blobdir: PathBuf) -> Self {
+        self.blobdir = Some(blobdir);
+        self
+    }
+
+    /// Sets the storage keeping the database, see [`SqlStorage`].
+    ///
+    /// The database file passed to [`ContextBuilder::new`] is then not opened,
+    /// it is only used to derive the blob directory
+    /// unless it is set with [`ContextBuilder::with_blobdir`].
+    pub fn with_storage(mut self, storage: Arc<dyn SqlStorage>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
     /// Opens the [`Context`].
     pub async fn open(self) -> Result<Context> {
-        let context =
-            Context::new_closed(&self.dbfile, self.id, self.events, self.stock_strings).await?;
+        let blobdir = self
+            .blobdir
+            .unwrap_or_else(|| Context::derive_blobdir(&self.dbfile));
+        let context = match self.storage {
+            Some(storage) => {
+                Context::new_closed_with_storage(
+                    storage,
+                    blobdir,
+                    self.id,
+                    self.events,
+                    self.stock_strings,
+                )
+                .await?
+            }
+            None => {
+                Context::new_closed_with_blobdir(
+                    &self.dbfile,
+                    blobdir,
+                    self.id,
+                    self.events,
+                    self.stock_strings,
+                )
+                .await?
+            }
+        };
+        context.set_sql_config(self.sql_config);
         let password = self.password.unwrap_or_default();
         match context.open(password).await? {
             true => Ok(context),
@@ -217,6 +284,19 @@ pub struct InnerContext {
     /// IMAP UID resync request.
     pub(crate) resync_request: AtomicBool,
 
+    /// If true, all networking is disabled, see [`Context::set_maintenance_mode`].
+    pub(crate) maintenance_mode: AtomicBool,
+
+    /// If true, housekeeping does not run automatically,
+    /// see [`Context::set_housekeeping_deferred`].
+    pub(crate) housekeeping_deferred: AtomicBool,
+
+    /// Mutex to avoid running housekeeping several times in parallel.
+    pub(crate) housekeeping_mutex: Mutex<()>,
+
+    /// Mutex to avoid removing deduplicated blobs while they are created or reused.
+    pub(crate) blobs_mutex: Mutex<()>,
+
     /// Notify about new messages.
     ///
     /// This causes [`Context::wait_next_msgs`] to wake up.
@@ -229,6 +309,9 @@ pub struct InnerContext {
 
     pub(crate) last_full_folder_scan: Mutex<Option<Instant>>,
 
+    /// Folders queued by the last full folder scan.
+    pub(crate) folder_scan_queue: FolderScanQueue,
+
     /// ID for this `Context` in the current process.
     ///
     /// This allows for multiple `Context`s open in a single process where each context can
@@ -247,6 +330,42 @@ pub struct InnerContext {
     /// Standard RwLock instead of [`tokio::sync::RwLock`] is used
     /// because the lock is used from synchronous [`Context::emit_event`].
     pub(crate) debug_logging: std::sync::RwLock<Option<DebugLogging>>,
+
+    /// The last emitted events, see [`Context::get_event_history`].
+    pub(crate) event_history: std::sync::Mutex<EventHistory>,
+
+    /// Captured log, see [`Context::get_log_entries`].
+    pub(crate) log_buffer: std::sync::Mutex<LogBuffer>,
+
+    /// Source of OAuth 2 access tokens set by the embedding application, if any.
+    pub(crate) oauth2_token_provider: std::sync::RwLock<Option<Arc<dyn Oauth2TokenProvider>>>,
+
+    /// Notified when the UI sets an OAuth 2 access token,
+    /// see [`crate::oauth2::Oauth2TokenRequester`].
+    pub(crate) oauth2_token_notify: Notify,
+
+    /// Transport used instead of SMTP, see [`Context::set_transport`].
+    pub(crate) transport: std::sync::RwLock<Option<Arc<dyn Transport>>>,
+
+    /// True if the database is still to be opened by [`Context::open_lazily`].
+    pub(crate) lazy_open: Mutex<bool>,
+
+    /// Bytes sent and received but not yet persisted to the database.
+    pub(crate) traffic: Arc<TrafficCounters>,
+
+    /// Counters returned by [`Context::get_metrics`].
+    pub(crate) metrics: MetricsCounters,
+
+    /// Summaries returned by [`Chatlist::get_summary2`](crate::chatlist::Chatlist::get_summary2).
+    pub(crate) summary_cache: SummaryCache,
+
+    /// Joined realtime channels of webxdc apps.
+    pub(crate) webxdc_realtime: RealtimeChannels,
+
+    /// Built-in Tor client, bootstrapped on first use.
+    #[cfg(feature = "tor")]
+    pub(crate) tor_client:
+        tokio::sync::OnceCell<arti_client::TorClient<tor_rtcompat::PreferredRuntime>>,
 }
 
 /// The state of ongoing process.
@@ -284,6 +403,27 @@ pub fn get_info() -> BTreeMap<&'static str, String> {
     res
 }
 
+/// Returns which optional features are supported by this build of deltachat-core.
+///
+/// Unlike [get_info], the keys are stable, so UIs can use them
+/// to hide unsupported features instead of parsing the version.
+/// Features which are not supported yet, e.g. `pq_crypto`, are reported as `false`
+/// so UIs can rely on the key being present.
+pub fn get_capabilities() -> BTreeMap<&'static str, bool> {
+    let mut res = BTreeMap::new();
+    res.insert("webxdc", cfg!(feature = "webxdc"));
+    res.insert("location", cfg!(feature = "location"));
+    res.insert("videochat", cfg!(feature = "videochat"));
+    res.insert("backup_transfer", true);
+    res.insert("sqlcipher", is_sqlcipher());
+    res.insert("tor", cfg!(feature = "tor"));
+    res.insert("jmap", true);
+    res.insert("provider_db", cfg!(feature = "provider-db"));
+    res.insert("qr_svg", cfg!(feature = "qr-svg"));
+    res.insert("pq_crypto", false);
+    res
+}
+
 impl Context {
     /// Creates new context and opens the database.
     pub async fn new(
@@ -308,10 +448,20 @@ pub async fn new_closed(
         events: Events,
         stockstrings: StockStrings,
     ) -> Result<Context> {
-        let mut blob_fname = OsString::new();
-        blob_fname.push(dbfile.file_name().unwrap_or_default());
-        blob_fname.push("-blobs");
-        let blobdir = dbfile.with_file_name(blob_fname);
+        let blobdir = Context::derive_blobdir(dbfile);
+        Context::new_closed_with_blobdir(dbfile, blobdir, id, events, stockstrings).await
+    }
+
+    /// Creates new context storing blobs in `blobdir` without opening the database.
+    ///
+    /// The blob directory is created if it does not exist.
+    pub(crate) async fn new_closed_with_blobdir(
+        dbfile: &Path,
+        blobdir: PathBuf,
+        id: u32,
+        events: Events,
+        stockstrings: StockStrings,
+    ) -> Result<Context> {
         if !blobdir.exists() {
             tokio::fs::create_dir_all(&blobdir).await?;
         }
@@ -319,6 +469,50 @@ pub async fn new_closed(
         Ok(context)
     }
 
+    /// Creates new context keeping the database in `storage` without opening the database.
+    ///
+    /// The blob directory is created if it does not exist.
+    pub(crate) async fn new_closed_with_storage(
+        storage: Arc<dyn SqlStorage>,
+        blobdir: PathBuf,
+        id: u32,
+        events: Events,
+        stockstrings: StockStrings,
+    ) -> Result<Context> {
+        if !blobdir.exists() {
+            tokio::fs::create_dir_all(&blobdir).await?;
+        }
+        Context::with_sql(
+            Sql::with_storage(storage),
+            blobdir,
+            id,
+            events,
+            stockstrings,
+        )
+    }
+
+    /// Creates new context with the database kept in memory and opens it.
+    ///
+    /// Only blobs are stored on disk, in the existing directory `blobdir`.
+    /// All other data is lost once the context is dropped,
+    /// so this is mostly useful for tests and short-lived deployments.
+    pub async fn new_in_memory(
+        blobdir: &Path,
+        id: u32,
+        events: Events,
+        stockstrings: StockStrings,
+    ) -> Result<Context> {
+        let context = Context::with_sql(
+            Sql::new_in_memory(),
+            blobdir.to_path_buf(),
+            id,
+            events,
+            stockstrings,
+        )?;
+        context.sql.open(&context, "".to_string()).await?;
+        Ok(context)
+    }
+
     /// Opens the database with the given passphrase.
     ///
     /// Returns true if passphrase is correct, false is passphrase is not correct. Fails on other
@@ -337,6 +531,62 @@ pub async fn is_open(&self) -> bool {
         self.sql.is_open().await
     }
 
+    /// Marks the database to be opened on first use by [`Context::open_lazily`].
+    pub(crate) async fn set_lazy_open(&self) {
+        *self.lazy_open.lock().await = true;
+    }
+
+    /// Opens the database without passphrase
+    /// if it was left closed by the account manager to be opened on first use.
+    ///
+    /// The account manager does not open the databases of the accounts it loads.
+    /// This is done by the IO scheduler when IO is started or messages are fetched
+    /// in the background, and by
+    /// [`Accounts::prewarm_selected_account`](crate::accounts::Accounts::prewarm_selected_account).
+    /// Until then the context behaves like a closed account.
+    ///
+    /// Does nothing for other contexts. Encrypted databases are left closed.
+    /// Errors are logged and opening is retried on the next call.
+    pub async fn open_lazily(&self) {
+        let mut lazy_open = self.lazy_open.lock().await;
+        if !*lazy_open {
+            return;
+        }
+        if self.is_open().await {
+            *lazy_open = false;
+            return;
+        }
+        match self.open(String::new()).await {
+            Ok(_) => *lazy_open = false,
+            Err(err) => error!(
+                self,
+                "Failed to open database {:?}: {:#}.",
+                self.get_dbfile(),
+                err
+            ),
+        }
+    }
+
+    /// Sets the configuration of the database connections, see [`SqlConfig`].
+    ///
+    /// Changes take effect the next time the database is opened,
+    /// e.g. with [`Context::open`].
+    pub fn set_sql_config(&self, config: SqlConfig) {
+        self.sql.set_config(config)
+    }
+
+    /// Returns the configuration of the database connections.
+    ///
+    /// If the database is open, the values actually used by the connections are returned,
+    /// otherwise the configuration set with [`Context::set_sql_config`].
+    pub async fn get_sql_config(&self) -> Result<SqlConfig> {
+        if self.sql.is_open().await {
+            self.sql.get_current_config().await
+        } else {
+            Ok(self.sql.get_config())
+        }
+    }
+
     /// Tests the database passphrase.
     ///
     /// Returns true if passphrase is correct.
@@ -346,12 +596,32 @@ pub(crate) async fn check_passphrase(&self, passphrase: String) -> Result<bool>
         self.sql.check_passphrase(passphrase).await
     }
 
+    /// Returns the schema migrations which run when the database is opened,
+    /// without opening it.
+    ///
+    /// `passphrase` is used to read the database if it is not open yet.
+    /// Together with [`SqlConfig::backup_before_migrate`]
+    /// this allows UIs to warn before upgrading across many versions.
+    pub async fn get_migration_plan(&self, passphrase: String) -> Result<MigrationPlan> {
+        self.sql.get_migration_plan(passphrase).await
+    }
+
     pub(crate) fn with_blobdir(
         dbfile: PathBuf,
         blobdir: PathBuf,
         id: u32,
         events: Events,
         stockstrings: StockStrings,
+    ) -> Result<Context> {
+        Self::with_sql(Sql::new(dbfile), blobdir, id, events, stockstrings)
+    }
+
+    fn with_sql(
+        sql: Sql,
+        blobdir: PathBuf,
+        id: u32,
+        events: Events,
+        stockstrings: StockStrings,
     ) -> Result<Context> {
         ensure!(
             blobdir.is_dir(),
@@ -368,7 +638,7 @@ pub(crate) fn with_blobdir(
             id,
             blobdir,
             running_state: RwLock::new(Default::default()),
-            sql: Sql::new(dbfile),
+            sql,
             smeared_timestamp: SmearedTimestamp::new(),
             generating_key_mutex: Mutex::new(()),
             oauth2_mutex: Mutex::new(()),
@@ -380,12 +650,29 @@ pub(crate) fn with_blobdir(
             quota: RwLock::new(None),
             quota_update_request: AtomicBool::new(false),
             resync_request: AtomicBool::new(false),
+            maintenance_mode: AtomicBool::new(false),
+            housekeeping_deferred: AtomicBool::new(false),
+            housekeeping_mutex: Mutex::new(()),
+            blobs_mutex: Mutex::new(()),
             new_msgs_notify,
             server_id: RwLock::new(None),
             creation_time: std::time::SystemTime::now(),
             last_full_folder_scan: Mutex::new(None),
+            folder_scan_queue: FolderScanQueue::default(),
             last_error: std::sync::RwLock::new("".to_string()),
             debug_logging: std::sync::RwLock::new(None),
+            event_history: Default::default(),
+            log_buffer: Default::default(),
+            oauth2_token_provider: std::sync::RwLock::new(None),
+            oauth2_token_notify: Notify::new(),
+            transport: std::sync::RwLock::new(None),
+            lazy_open: Mutex::new(false),
+            traffic: Arc::new(TrafficCounters::default()),
+            metrics: MetricsCounters::default(),
+            summary_cache: SummaryCache::default(),
+            webxdc_realtime: RealtimeChannels::default(),
+            #[cfg(feature = "tor")]
+            tor_client: tokio::sync::OnceCell::new(),
         };
 
         let ctx = Context {
@@ -396,17 +683,58 @@ pub(crate) fn with_blobdir(
     }
 
     /// Starts the IO scheduler.
+    ///
+    /// Opens the database first if it is still to be opened, see [`Context::open_lazily`].
     pub async fn start_io(&self) {
+        self.open_lazily().await;
         if let Ok(false) = self.is_configured().await {
             warn!(self, "can not start io on a context that is not configured");
             return;
         }
+        if self.is_maintenance_mode() {
+            warn!(self, "can not start io in maintenance mode");
+            return;
+        }
         self.scheduler.start(self.clone()).await;
     }
 
+    /// Enables or disables the maintenance mode.
+    ///
+    /// In maintenance mode all networking is disabled,
+    /// so tools migrating, exporting or repairing the database
+    /// can be sure that no IMAP, SMTP or HTTP connection is made meanwhile.
+    /// Enabling the maintenance mode stops the IO scheduler,
+    /// [`Context::start_io`] does nothing until the maintenance mode is disabled
+    /// and operations needing the network, e.g. [`Context::configure`], fail.
+    ///
+    /// The maintenance mode is not persisted and is disabled for new contexts.
+    pub async fn set_maintenance_mode(&self, enabled: bool) {
+        self.maintenance_mode.store(enabled, Ordering::Relaxed);
+        if enabled {
+            self.stop_io().await;
+        }
+    }
+
+    /// Returns true if the maintenance mode is enabled, see [`Context::set_maintenance_mode`].
+    pub fn is_maintenance_mode(&self) -> bool {
+        self.maintenance_mode.load(Ordering::Relaxed)
+    }
+
+    /// Fails if networking is disabled by the maintenance mode.
+    ///
+    /// Called before connecting anywhere.
+    pub(crate) fn ensure_network_allowed(&self) -> Result<()> {
+        ensure!(
+            !self.is_maintenance_mode(),
+            "Networking is disabled in maintenance mode"
+        );
+        Ok(())
+    }
+
     /// Stops the IO scheduler.
     pub async fn stop_io(&self) {
         self.scheduler.stop(self).await;
+        self.flush_traffic_stats().await.log_err(self).ok();
     }
 
     /// Restarts the IO scheduler if it was running before
@@ -420,6 +748,51 @@ pub async fn maybe_network(&self) {
         self.scheduler.maybe_network().await;
     }
 
+    /// Fetches new messages from the inbox and, if watched, the movebox
+    /// without running the IO scheduler.
+    ///
+    /// Meant for fetching in the background, e.g. on iOS,
+    /// where IO cannot run permanently.
+    /// IO is paused while fetching and resumed afterwards if it was running.
+    pub async fn background_fetch(&self) -> Result<()> {
+        self.open_lazily().await;
+        if !self.is_configured().await? {
+            return Ok(());
+        }
+        let _pause_guard = self.scheduler.pause(self.clone()).await?;
+
+        if let Some(mut jmap) = Jmap::new_configured(self).await? {
+            jmap.fetch_new_messages(self).await?;
+            return Ok(());
+        }
+
+        let mut connection = Imap::new_configured(self, channel::bounded(1).1).await?;
+        connection.prepare(self).await?;
+        for (folder_config, folder_meaning) in [
+            (Config::ConfiguredInboxFolder, FolderMeaning::Inbox),
+            (Config::ConfiguredMvboxFolder, FolderMeaning::Mvbox),
+        ] {
+            if folder_meaning == FolderMeaning::Mvbox && !self.should_watch_mvbox().await? {
+                continue;
+            }
+            if let Some(folder) = self.get_config(folder_config).await? {
+                connection
+                    .fetch_move_delete(self, &folder, folder_meaning)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the class of the network the device is currently connected to,
+    /// as set by the UI in the `network_class` option.
+    pub async fn get_network_class(&self) -> Result<NetworkClass> {
+        Ok(
+            NetworkClass::from_i32(self.get_config_int(Config::NetworkClass).await?)
+                .unwrap_or_default(),
+        )
+    }
+
     /// Returns a reference to the underlying SQL instance.
     ///
     /// Warning: this is only here for testing, not part of the public API.
@@ -429,6 +802,8 @@ pub fn sql(&self) -> &Sql {
     }
 
     /// Returns database file path.
+    ///
+    /// This is `:memory:` if the database is not stored in a file, see [`SqlStorage::path`].
     pub fn get_dbfile(&self) -> &Path {
         self.sql.dbfile.as_path()
     }
@@ -440,15 +815,26 @@ pub fn get_blobdir(&self) -> &Path {
 
     /// Emits a single event.
     pub fn emit_event(&self, event: EventType) {
+        self.summary_cache.invalidate(&event);
         {
             let lock = self.debug_logging.read().expect("RwLock is poisoned");
             if let Some(debug_logging) = &*lock {
                 debug_logging.log_event(event.clone());
             }
         }
+        self.log_buffer
+            .lock()
+            .expect("Mutex is poisoned")
+            .capture(&event);
+        let seq = self
+            .event_history
+            .lock()
+            .expect("Mutex is poisoned")
+            .push(event.clone());
         self.events.emit(Event {
             id: self.id,
             typ: event,
+            seq,
         });
     }
 
@@ -559,6 +945,9 @@ pub async fn get_info(&self) -> Result<BTreeMap<&'static str, String>> {
         let contacts = Contact::get_real_cnt(self).await?;
         let is_configured = self.get_config_int(Config::Configured).await?;
         let socks5_enabled = self.get_config_int(Config::Socks5Enabled).await?;
+        let http_proxy_enabled = self.get_config_int(Config::HttpProxyEnabled).await?;
+        let tor_enabled = self.get_config_int(Config::TorEnabled).await?;
+        let learn_certificate_pins = self.get_config_int(Config::LearnCertificatePins).await?;
         let dbversion = self
             .sql
             .get_raw_config_int("dbversion")
@@ -638,7 +1027,26 @@ pub async fn get_info(&self) -> Result<BTreeMap<&'static str, String>> {
                 .unwrap_or_else(|| "<unset>".to_string()),
         );
         res.insert("is_configured", is_configured.to_string());
+        res.insert(
+            "jmap_url",
+            self.get_config(Config::JmapUrl)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
+        res.insert(
+            "configured_jmap_url",
+            self.get_config(Config::ConfiguredJmapUrl)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
         res.insert("socks5_enabled", socks5_enabled.to_string());
+        res.insert("http_proxy_enabled", http_proxy_enabled.to_string());
+        res.insert("tor_enabled", tor_enabled.to_string());
+        res.insert("learn_certificate_pins", learn_certificate_pins.to_string());
+        res.insert(
+            "certificate_pins",
+            self.get_certificate_pins().await?.len().to_string(),
+        );
         res.insert("entered_account_settings", l.to_string());
         res.insert("used_account_settings", l2.to_string());
 
@@ -669,9 +1077,122 @@ pub async fn get_info(&self) -> Result<BTreeMap<&'static str, String>> {
                 .await?
                 .to_string(),
         );
+        res.insert(
+            "download_limit_image",
+            self.get_config(Config::DownloadLimitImage)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
+        res.insert(
+            "download_limit_video",
+            self.get_config(Config::DownloadLimitVideo)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
+        res.insert(
+            "download_limit_audio",
+            self.get_config(Config::DownloadLimitAudio)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
+        res.insert(
+            "download_limit_webxdc",
+            self.get_config(Config::DownloadLimitWebxdc)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
+        res.insert(
+            "download_limit_metered_image",
+            self.get_config(Config::DownloadLimitMeteredImage)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
+        res.insert(
+            "download_limit_metered_video",
+            self.get_config(Config::DownloadLimitMeteredVideo)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
+        res.insert(
+            "download_limit_metered_audio",
+            self.get_config(Config::DownloadLimitMeteredAudio)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
+        res.insert(
+            "download_limit_metered_webxdc",
+            self.get_config(Config::DownloadLimitMeteredWebxdc)
+                .await?
+                .unwrap_or_else(|| "<unset>".to_string()),
+        );
+        res.insert(
+            "network_class",
+            self.get_config_int(Config::NetworkClass).await?.to_string(),
+        );
+        res.insert(
+            "send_limit_metered",
+            self.get_config_int(Config::SendLimitMetered)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "download_on_roaming",
+            self.get_config_bool(Config::DownloadOnRoaming)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "smtp_max_attempts",
+            self.get_config_int(Config::SmtpMaxAttempts)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "smtp_retry_delay",
+            self.get_config_int(Config::SmtpRetryDelay)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "smtp_retry_backoff",
+            self.get_config_int(Config::SmtpRetryBackoff)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "smtp_fail_fast",
+            self.get_config_bool(Config::SmtpFailFast)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "notify_muted_mentions",
+            self.get_config_bool(Config::NotifyMutedMentions)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "notification_privacy",
+            self.get_config_int(Config::NotificationPrivacy)
+                .await?
+                .to_string(),
+        );
         res.insert("sentbox_watch", sentbox_watch.to_string());
         res.insert("mvbox_move", mvbox_move.to_string());
         res.insert("only_fetch_mvbox", only_fetch_mvbox.to_string());
+        res.insert(
+            "watch_folders",
+            self.get_config(Config::WatchFolders)
+                .await?
+                .unwrap_or_default()
+                .replace('\n', ", "),
+        );
+        res.insert(
+            "watch_folders_move",
+            self.get_config_int(Config::WatchFoldersMove)
+                .await?
+                .to_string(),
+        );
         res.insert("folders_configured", folders_configured.to_string());
         res.insert("configured_inbox_folder", configured_inbox_folder);
         res.insert("configured_sentbox_folder", configured_sentbox_folder);
@@ -716,24 +1237,70 @@ pub async fn get_info(&self) -> Result<BTreeMap<&'static str, String>> {
                 .await?
                 .unwrap_or_else(|| "<unset>".to_string()),
         );
+        res.insert(
+            "delete_trash_after",
+            self.get_config_i64(Config::DeleteTrashAfter)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "media_cleanup_after",
+            self.get_config_i64(Config::MediaCleanupAfter)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "housekeeping_interval",
+            self.get_config_i64(Config::HousekeepingInterval)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "housekeeping_deferred",
+            self.is_housekeeping_deferred().to_string(),
+        );
         res.insert(
             "last_housekeeping",
             self.get_config_int(Config::LastHousekeeping)
                 .await?
                 .to_string(),
         );
+        res.insert(
+            "last_db_maintenance",
+            self.get_config_i64(Config::LastDbMaintenance)
+                .await?
+                .to_string(),
+        );
         res.insert(
             "scan_all_folders_debounce_secs",
             self.get_config_int(Config::ScanAllFoldersDebounceSecs)
                 .await?
                 .to_string(),
         );
+        res.insert(
+            "scan_folders_connections",
+            self.get_config_int(Config::ScanFoldersConnections)
+                .await?
+                .to_string(),
+        );
         res.insert(
             "quota_exceeding",
             self.get_config_int(Config::QuotaExceeding)
                 .await?
                 .to_string(),
         );
+        res.insert(
+            "quota_warn_threshold",
+            self.get_config_int(Config::QuotaWarnThreshold)
+                .await?
+                .to_string(),
+        );
+        res.insert(
+            "quota_error_threshold",
+            self.get_config_int(Config::QuotaErrorThreshold)
+                .await?
+                .to_string(),
+        );
         res.insert(
             "authserv_id_candidates",
             self.get_config(Config::AuthservIdCandidates)
@@ -768,27 +1335,37 @@ pub async fn get_info(&self) -> Result<BTreeMap<&'static str, String>> {
     /// and is typically used to show notifications.
     /// Moreover, the number of returned messages
     /// can be used for a badge counter on the app icon.
+    ///
+    /// If `notify_muted_mentions` is set, messages in muted groups
+    /// which mention the user or quote one of the user's messages are included as well.
     pub async fn get_fresh_msgs(&self) -> Result<Vec<MsgId>> {
-        let list = self
+        let muted_mentions = self.get_config_bool(Config::NotifyMutedMentions).await?;
+        let rows = self
             .sql
             .query_map(
                 concat!(
-                    "SELECT m.id",
+                    "SELECT m.id, c.muted_until=-1 OR c.muted_until>?2",
                     " FROM msgs m",
                     " LEFT JOIN contacts ct",
                     "        ON m.from_id=ct.id",
                     " LEFT JOIN chats c",
                     "        ON m.chat_id=c.id",
-                    " WHERE m.state=?",
+                    " WHERE m.state=?1",
                     "   AND m.hidden=0",
                     "   AND m.chat_id>9",
                     "   AND ct.blocked=0",
                     "   AND c.blocked=0",
-                    "   AND NOT(c.muted_until=-1 OR c.muted_until>?)",
+                    "   AND (NOT(c.muted_until=-1 OR c.muted_until>?2)",
+                    "        OR (?3 AND c.type=?4))",
                     " ORDER BY m.timestamp DESC,m.id DESC;"
                 ),
-                (MessageState::InFresh, time()),
-                |row| row.get::<_, MsgId>(0),
+                (
+                    MessageState::InFresh,
+                    time(),
+                    muted_mentions,
+                    Chattype::Group,
+                ),
+                |row| Ok((row.get::<_, MsgId>(0)?, row.get::<_, bool>(1)?)),
                 |rows| {
                     let mut list = Vec::new();
                     for row in rows {
@@ -798,9 +1375,57 @@ pub async fn get_fresh_msgs(&self) -> Result<Vec<MsgId>> {
                 },
             )
             .await?;
+
+        let mut list = Vec::with_capacity(rows.len());
+        let mut mention_check = None;
+        for (msg_id, muted) in rows {
+            if muted {
+                if mention_check.is_none() {
+                    mention_check = Some(MentionCheck::new(self).await?);
+                }
+                if let Some(mention_check) = &mention_check {
+                    if !mention_check.is_mention(self, msg_id).await? {
+                        continue;
+                    }
+                }
+            }
+            list.push(msg_id);
+        }
         Ok(list)
     }
 
+    /// Returns the number of fresh messages in all chats,
+    /// counting the same messages as [`Context::get_fresh_msgs`].
+    ///
+    /// Meant for the app badge.
+    pub async fn get_fresh_msg_count(&self) -> Result<usize> {
+        if self.get_config_bool(Config::NotifyMutedMentions).await? {
+            // Mentions cannot be counted in SQL.
+            return Ok(self.get_fresh_msgs().await?.len());
+        }
+        let count = self
+            .sql
+            .count(
+                concat!(
+                    "SELECT COUNT(*)",
+                    " FROM msgs m",
+                    " LEFT JOIN contacts ct",
+                    "        ON m.from_id=ct.id",
+                    " LEFT JOIN chats c",
+                    "        ON m.chat_id=c.id",
+                    " WHERE m.state=?",
+                    "   AND m.hidden=0",
+                    "   AND m.chat_id>9",
+                    "   AND ct.blocked=0",
+                    "   AND c.blocked=0",
+                    "   AND NOT(c.muted_until=-1 OR c.muted_until>?);"
+                ),
+                (MessageState::InFresh, time()),
+            )
+            .await?;
+        Ok(count)
+    }
+
     /// Returns a list of messages with database ID higher than requested.
     ///
     /// Blocked contacts and chats are excluded,
@@ -861,8 +1486,45 @@ pub async fn wait_next_msgs(&self) -> Result<Vec<MsgId>> {
         Ok(list)
     }
 
+    /// Like [`Self::wait_next_msgs`], but returns only incoming messages
+    /// which are not info messages.
+    ///
+    /// Lets simple bots get only the messages they may need to reply to.
+    /// If all messages returned by [`Self::wait_next_msgs`] are skipped,
+    /// `last_msg_id` is set to the last of them so they are not returned again,
+    /// and an empty list is returned.
+    pub async fn wait_next_incoming_msgs(&self) -> Result<Vec<MsgId>> {
+        let list = self.wait_next_msgs().await?;
+        self.filter_incoming_msgs(list).await
+    }
+
+    /// Returns the incoming non-info messages from `msg_ids`.
+    ///
+    /// If there are none, marks all `msg_ids` as processed.
+    async fn filter_incoming_msgs(&self, msg_ids: Vec<MsgId>) -> Result<Vec<MsgId>> {
+        let mut incoming = Vec::new();
+        for msg_id in &msg_ids {
+            // The message may have been deleted in the meantime.
+            if let Ok(msg) = Message::load_from_db(self, *msg_id).await.log_err(self) {
+                if msg.get_from_id() != ContactId::SELF && !msg.is_info() {
+                    incoming.push(*msg_id);
+                }
+            }
+        }
+        if incoming.is_empty() {
+            if let Some(last_msg_id) = msg_ids.last() {
+                self.set_config_u32(Config::LastMsgId, last_msg_id.to_u32())
+                    .await?;
+            }
+        }
+        Ok(incoming)
+    }
+
     /// Searches for messages containing the query string.
     ///
+    /// Queries of at least 3 characters are looked up in the full-text index
+    /// once all messages are indexed.
+    ///
     /// If `chat_id` is provided this searches only for messages in this chat, if `chat_id`
     /// is `None` this searches messages from all chats.
     pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<Vec<MsgId>> {
@@ -870,21 +1532,30 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
         if real_query.is_empty() {
             return Ok(Vec::new());
         }
-        let str_like_in_text = format!("%{real_query}%");
+        // Use the full-text index if possible, see the `search` module.
+        let (text_condition, text_param) = match search::index_query(self, real_query).await? {
+            Some(index_query) => (
+                "m.id IN (SELECT rowid FROM msgs_search WHERE msgs_search MATCH ?)",
+                index_query,
+            ),
+            None => ("m.txt LIKE ?", format!("%{real_query}%")),
+        };
 
         let list = if let Some(chat_id) = chat_id {
             self.sql
                 .query_map(
-                    "SELECT m.id AS id
+                    &format!(
+                        "SELECT m.id AS id
                  FROM msgs m
                  LEFT JOIN contacts ct
                         ON m.from_id=ct.id
                  WHERE m.chat_id=?
                    AND m.hidden=0
                    AND ct.blocked=0
-                   AND txt LIKE ?
-                 ORDER BY m.timestamp,m.id;",
-                    (chat_id, str_like_in_text),
+                   AND {text_condition}
+                 ORDER BY m.timestamp,m.id;"
+                    ),
+                    (chat_id, text_param),
                     |row| row.get::<_, MsgId>("id"),
                     |rows| {
                         let mut ret = Vec::new();
@@ -908,7 +1579,8 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
             // The limit is documented and UI may add a hint when getting 1000 results.
             self.sql
                 .query_map(
-                    "SELECT m.id AS id
+                    &format!(
+                        "SELECT m.id AS id
                  FROM msgs m
                  LEFT JOIN contacts ct
                         ON m.from_id=ct.id
@@ -918,9 +1590,10 @@ pub async fn search_msgs(&self, chat_id: Option<ChatId>, query: &str) -> Result<
                    AND m.hidden=0
                    AND c.blocked!=1
                    AND ct.blocked=0
-                   AND m.txt LIKE ?
-                 ORDER BY m.id DESC LIMIT 1000",
-                    (str_like_in_text,),
+                   AND {text_condition}
+                 ORDER BY m.id DESC LIMIT 1000"
+                    ),
+                    (text_param,),
                     |row| row.get::<_, MsgId>("id"),
                     |rows| {
                         let mut ret = Vec::new();
@@ -996,6 +1669,51 @@ pub(crate) fn derive_walfile(dbfile: &Path) -> PathBuf {
     }
 }
 
+/// Checks whether messages mention the user.
+struct MentionCheck {
+    /// Lowercase addresses of the user.
+    addrs: Vec<String>,
+
+    /// Lowercase display name of the user prefixed with `@`.
+    displayname: Option<String>,
+}
+
+impl MentionCheck {
+    async fn new(context: &Context) -> Result<Self> {
+        let addrs = context
+            .get_all_self_addrs()
+            .await?
+            .into_iter()
+            .filter(|addr| !addr.is_empty())
+            .map(|addr| addr.to_lowercase())
+            .collect();
+        let displayname = context
+            .get_config(Config::Displayname)
+            .await?
+            .filter(|name| !name.is_empty())
+            .map(|name| format!("@{}", name.to_lowercase()));
+        Ok(Self { addrs, displayname })
+    }
+
+    /// Returns true if the message contains an address of the user
+    /// or `@` followed by the display name of the user,
+    /// or if it quotes a message of the user.
+    async fn is_mention(&self, context: &Context, msg_id: MsgId) -> Result<bool> {
+        let msg = Message::load_from_db(context, msg_id).await?;
+        let text = msg.get_text().unwrap_or_default().to_lowercase();
+        if self.addrs.iter().any(|addr| text.contains(addr.as_str()))
+            || self
+                .displayname
+                .as_ref()
+                .map_or(false, |name| text.contains(name.as_str()))
+        {
+            return Ok(true);
+        }
+        let quote = msg.quoted_message(context).await?;
+        Ok(quote.map_or(false, |quote| quote.from_id == ContactId::SELF))
+    }
+}
+
 /// Returns core version as a string.
 pub fn get_version_str() -> &'static str {
     &DC_VERSION_STR
@@ -1012,13 +1730,14 @@ mod tests {
     use super::*;
     use crate::chat::{
         get_chat_contacts, get_chat_msgs, send_msg, set_muted, Chat, ChatId, MuteDuration,
+        ProtectionStatus,
     };
     use crate::chatlist::Chatlist;
     use crate::constants::Chattype;
     use crate::contact::ContactId;
     use crate::message::{Message, Viewtype};
     use crate::receive_imf::receive_imf;
-    use crate::test_utils::TestContext;
+    use crate::test_utils::{TestContext, TestContextManager};
     use crate::tools::create_outgoing_rfc724_mid;
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -1101,6 +1820,7 @@ async fn test_get_fresh_msgs_and_muted_chats() {
         assert_eq!(get_chat_msgs(&t, claire.id).await.unwrap().len(), 3);
         assert_eq!(claire.id.get_fresh_msg_cnt(&t).await.unwrap(), 3);
         assert_eq!(t.get_fresh_msgs().await.unwrap().len(), 6); // muted claire is not counted
+        assert_eq!(t.get_fresh_msg_count().await.unwrap(), 6);
 
         // unmute claire again
         set_muted(&t, claire.id, MuteDuration::NotMuted)
@@ -1108,6 +1828,7 @@ async fn test_get_fresh_msgs_and_muted_chats() {
             .unwrap();
         assert_eq!(claire.id.get_fresh_msg_cnt(&t).await.unwrap(), 3);
         assert_eq!(t.get_fresh_msgs().await.unwrap().len(), 9); // claire is counted again
+        assert_eq!(t.get_fresh_msg_count().await.unwrap(), 9);
     }
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -1165,6 +1886,51 @@ async fn test_get_fresh_msgs_and_muted_until() {
         assert_eq!(t.get_fresh_msgs().await.unwrap().len(), 1);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_fresh_msgs_muted_mentions() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+        alice.set_config(Config::Displayname, Some("Alice")).await?;
+
+        let bob_chat_id = bob
+            .create_group_with_members(ProtectionStatus::Unprotected, "Group", &[&alice])
+            .await;
+        let sent = bob.send_text(bob_chat_id, "hello").await;
+        let alice_chat_id = alice.recv_msg(&sent).await.chat_id;
+        alice_chat_id.accept(&alice).await?;
+        set_muted(&alice, alice_chat_id, MuteDuration::Forever).await?;
+
+        let sent = alice.send_text(alice_chat_id, "hi all").await;
+        let quoted = bob.recv_msg(&sent).await;
+        let mut reply = Message::new(Viewtype::Text);
+        reply.set_text(Some("I agree".to_string()));
+        reply.set_quote(&bob, Some(&quoted)).await?;
+        let mut mentions = Vec::new();
+        for sent in [
+            bob.send_text(bob_chat_id, "What do you think, alice@example.org?")
+                .await,
+            bob.send_text(bob_chat_id, "Thanks @alice").await,
+            bob.send_msg(bob_chat_id, &mut reply).await,
+        ] {
+            mentions.push(alice.recv_msg(&sent).await.id);
+        }
+        let sent = bob.send_text(bob_chat_id, "Anyone else?").await;
+        alice.recv_msg(&sent).await;
+
+        assert!(alice.get_fresh_msgs().await?.is_empty());
+        assert_eq!(alice.get_fresh_msg_count().await?, 0);
+
+        alice
+            .set_config_bool(Config::NotifyMutedMentions, true)
+            .await?;
+        let mut fresh = alice.get_fresh_msgs().await?;
+        fresh.sort();
+        assert_eq!(fresh, mentions);
+        assert_eq!(alice.get_fresh_msg_count().await?, 3);
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_blobdir_exists() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1208,6 +1974,81 @@ async fn test_with_empty_blobdir() {
         assert!(res.is_err());
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_new_in_memory() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let t = Context::new_in_memory(tmp.path(), 1, Events::new(), StockStrings::new()).await?;
+        assert!(t.is_open().await);
+        assert_eq!(t.get_blobdir(), tmp.path());
+
+        t.set_config(Config::Displayname, Some("Alice")).await?;
+        assert_eq!(
+            t.get_config(Config::Displayname).await?,
+            Some("Alice".to_string())
+        );
+        let contact_id = Contact::create(&t, "Bob", "bob@example.net").await?;
+        let chat_id = ChatId::create_for_contact(&t, contact_id).await?;
+        assert!(!chat_id.is_special());
+
+        // Only blobs are stored on disk.
+        assert_eq!(std::fs::read_dir(tmp.path())?.count(), 0);
+
+        // Each in-memory context has its own database.
+        let t2 = Context::new_in_memory(tmp.path(), 2, Events::new(), StockStrings::new()).await?;
+        assert_eq!(t2.get_config(Config::Displayname).await?, None);
+        Ok(())
+    }
+
+    /// Storage keeping the database in a file and counting opened connections.
+    #[derive(Debug)]
+    struct CountingStorage {
+        inner: crate::sql::SqliteFileStorage,
+        opened: std::sync::atomic::AtomicUsize,
+    }
+
+    impl SqlStorage for CountingStorage {
+        fn open(&self, flags: rusqlite::OpenFlags) -> Result<rusqlite::Connection> {
+            self.opened.fetch_add(1, Ordering::Relaxed);
+            self.inner.open(flags)
+        }
+
+        fn exists(&self) -> bool {
+            self.inner.exists()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_context_with_storage() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let dbfile = tmp.path().join("custom.sqlite");
+        let storage = Arc::new(CountingStorage {
+            inner: crate::sql::SqliteFileStorage::new(dbfile.clone()),
+            opened: Default::default(),
+        });
+        let t = ContextBuilder::new(tmp.path().join("db.sqlite"))
+            .with_storage(storage.clone())
+            .open()
+            .await?;
+        assert!(storage.opened.load(Ordering::Relaxed) > 0);
+        assert!(dbfile.exists());
+        assert!(!tmp.path().join("db.sqlite").exists());
+        // The storage has no path, so the database is reported to be in memory.
+        assert_eq!(t.get_dbfile(), Path::new(":memory:"));
+
+        t.set_config(Config::Displayname, Some("Alice")).await?;
+        drop(t);
+
+        let t = ContextBuilder::new(tmp.path().join("db.sqlite"))
+            .with_storage(storage)
+            .open()
+            .await?;
+        assert_eq!(
+            t.get_config(Config::Displayname).await?,
+            Some("Alice".to_string())
+        );
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_with_blobdir_not_exists() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1223,6 +2064,35 @@ async fn no_crashes_on_context_deref() {
         std::mem::drop(t);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_maintenance_mode() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert!(!t.is_maintenance_mode());
+        assert!(t.ensure_network_allowed().is_ok());
+
+        t.set_maintenance_mode(true).await;
+        assert!(t.is_maintenance_mode());
+        t.start_io().await;
+        assert!(!t.scheduler.is_running().await);
+        assert!(t.configure().await.is_err());
+        assert!(crate::net::http::read_url(&t, "https://example.org")
+            .await
+            .is_err());
+        assert!(
+            crate::net::connect_tcp(&t, "example.org", 993, Duration::from_secs(1), false)
+                .await
+                .is_err()
+        );
+
+        // Local operations still work.
+        let chat = t.create_chat_with_contact("bob", "bob@example.net").await;
+        crate::chat::send_text_msg(&t, chat.id, "hi".to_string()).await?;
+
+        t.set_maintenance_mode(false).await;
+        assert!(t.ensure_network_allowed().is_ok());
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_get_info() {
         let t = TestContext::new().await;
@@ -1239,6 +2109,24 @@ fn test_get_info_no_context() {
         assert_eq!(info.get("level").unwrap(), "awesome");
     }
 
+    #[test]
+    fn test_get_capabilities() {
+        let capabilities = get_capabilities();
+        assert_eq!(capabilities.get("webxdc"), Some(&cfg!(feature = "webxdc")));
+        assert_eq!(
+            capabilities.get("location"),
+            Some(&cfg!(feature = "location"))
+        );
+        assert_eq!(
+            capabilities.get("videochat"),
+            Some(&cfg!(feature = "videochat"))
+        );
+        assert_eq!(capabilities.get("sqlcipher"), Some(&true));
+        assert_eq!(capabilities.get("tor"), Some(&cfg!(feature = "tor")));
+        assert_eq!(capabilities.get("pq_crypto"), Some(&false));
+        assert_eq!(capabilities.get("qr_svg"), Some(&cfg!(feature = "qr-svg")));
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_get_info_completeness() {
         // For easier debugging,
@@ -1256,6 +2144,7 @@ async fn test_get_info_completeness() {
             "mail_port",
             "mail_security",
             "notify_about_wrong_pw",
+            "oauth2_client_id",
             "save_mime_headers",
             "selfstatus",
             "send_server",
@@ -1269,6 +2158,13 @@ async fn test_get_info_completeness() {
             "socks5_port",
             "socks5_user",
             "socks5_password",
+            "http_proxy_host",
+            "http_proxy_port",
+            "http_proxy_tls",
+            "http_proxy_user",
+            "http_proxy_password",
+            "unifiedpush_endpoint",
+            "push_device_token",
         ];
         let t = TestContext::new().await;
         let info = t.get_info().await.unwrap();
@@ -1526,4 +2422,35 @@ async fn test_get_next_msgs() -> Result<()> {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_filter_incoming_msgs() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let alice_chat = alice.create_chat(&bob).await;
+        let bob_chat = bob.create_chat(&alice).await;
+
+        let sent_msg = alice.send_text(alice_chat.id, "Hi Bob").await;
+        let received_msg = bob.recv_msg(&sent_msg).await;
+        let own_msg = bob.send_text(bob_chat.id, "Hi Alice").await;
+
+        // Own messages are skipped, but incoming ones are not marked as processed.
+        let next_msgs = bob.get_next_msgs().await?;
+        assert_eq!(next_msgs.len(), 2);
+        assert_eq!(
+            bob.filter_incoming_msgs(next_msgs).await?,
+            vec![received_msg.id]
+        );
+        assert_eq!(bob.get_next_msgs().await?.len(), 2);
+
+        // If only own messages are left, they are marked as processed.
+        bob.set_config_u32(Config::LastMsgId, received_msg.id.to_u32())
+            .await?;
+        let next_msgs = bob.get_next_msgs().await?;
+        assert_eq!(next_msgs, vec![own_msg.sender_msg_id]);
+        assert!(bob.filter_incoming_msgs(next_msgs).await?.is_empty());
+        assert!(bob.get_next_msgs().await?.is_empty());
+
+        Ok(())
+    }
 }
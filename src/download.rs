@@ -8,6 +8,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::config::Config;
+use crate::constants::NetworkClass;
 use crate::context::Context;
 use crate::imap::{Imap, ImapActionResult};
 use crate::job::{self, Action, Job, Status};
@@ -63,15 +64,124 @@ pub enum DownloadState {
     InProgress = 1000,
 }
 
+/// Automatic download limits, see [`Context::download_limits`].
+///
+/// `None` means "no limit".
+/// Per-viewtype limits are `None` if they are not configured
+/// and `Some(None)` if messages of this viewtype should be downloaded regardless of their size.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct DownloadLimits {
+    default: Option<u32>,
+    image: Option<Option<u32>>,
+    video: Option<Option<u32>>,
+    audio: Option<Option<u32>>,
+    webxdc: Option<Option<u32>>,
+}
+
+impl DownloadLimits {
+    /// Returns the download limit for a message of the given viewtype
+    /// or `None` for "no limit".
+    ///
+    /// If the viewtype is not known, e.g. because the message is encrypted,
+    /// or there is no limit configured for it, the default limit is returned.
+    pub(crate) fn get(&self, viewtype: Option<Viewtype>) -> Option<u32> {
+        let limit = match viewtype {
+            Some(Viewtype::Image | Viewtype::Gif | Viewtype::Sticker) => self.image,
+            Some(Viewtype::Video) => self.video,
+            Some(Viewtype::Audio | Viewtype::Voice) => self.audio,
+            Some(Viewtype::Webxdc) => self.webxdc,
+            _ => None,
+        };
+        limit.unwrap_or(self.default)
+    }
+}
+
+/// Converts a configured download limit into a validated limit or `None` for "no limit".
+fn validate_download_limit(download_limit: i64) -> Option<u32> {
+    if download_limit <= 0 {
+        None
+    } else {
+        Some(max(
+            MIN_DOWNLOAD_LIMIT,
+            download_limit.try_into().unwrap_or(u32::MAX),
+        ))
+    }
+}
+
 impl Context {
     // Returns validated download limit or `None` for "no limit".
     pub(crate) async fn download_limit(&self) -> Result<Option<u32>> {
         let download_limit = self.get_config_int(Config::DownloadLimit).await?;
-        if download_limit <= 0 {
-            Ok(None)
-        } else {
-            Ok(Some(max(MIN_DOWNLOAD_LIMIT, download_limit as u32)))
+        Ok(validate_download_limit(download_limit.into()))
+    }
+
+    /// Returns the download limits for all viewtypes.
+    ///
+    /// On metered networks, the `download_limit_metered_*` options take precedence
+    /// over the corresponding `download_limit_*` options.
+    /// While roaming, only small messages are downloaded unless `download_on_roaming` is set.
+    pub(crate) async fn download_limits(&self) -> Result<DownloadLimits> {
+        let network_class = self.get_network_class().await?;
+        if network_class == NetworkClass::Roaming
+            && !self.get_config_bool(Config::DownloadOnRoaming).await?
+        {
+            return Ok(DownloadLimits {
+                default: Some(MIN_DOWNLOAD_LIMIT),
+                ..Default::default()
+            });
+        }
+        let metered = network_class != NetworkClass::Unmetered;
+        Ok(DownloadLimits {
+            default: self.download_limit().await?,
+            image: self
+                .viewtype_download_limit(
+                    metered,
+                    Config::DownloadLimitImage,
+                    Config::DownloadLimitMeteredImage,
+                )
+                .await?,
+            video: self
+                .viewtype_download_limit(
+                    metered,
+                    Config::DownloadLimitVideo,
+                    Config::DownloadLimitMeteredVideo,
+                )
+                .await?,
+            audio: self
+                .viewtype_download_limit(
+                    metered,
+                    Config::DownloadLimitAudio,
+                    Config::DownloadLimitMeteredAudio,
+                )
+                .await?,
+            webxdc: self
+                .viewtype_download_limit(
+                    metered,
+                    Config::DownloadLimitWebxdc,
+                    Config::DownloadLimitMeteredWebxdc,
+                )
+                .await?,
+        })
+    }
+
+    /// Returns the validated download limit configured for some viewtype
+    /// or `None` if neither `key` nor, on metered networks, `metered_key` is set.
+    async fn viewtype_download_limit(
+        &self,
+        metered: bool,
+        key: Config,
+        metered_key: Config,
+    ) -> Result<Option<Option<u32>>> {
+        let mut value = None;
+        if metered {
+            value = self.get_config(metered_key).await?;
+        }
+        if value.as_deref().unwrap_or_default().is_empty() {
+            value = self.get_config(key).await?;
         }
+        Ok(value
+            .filter(|value| !value.is_empty())
+            .map(|value| validate_download_limit(value.parse().unwrap_or_default())))
     }
 }
 
@@ -302,6 +412,44 @@ async fn test_download_limit() -> Result<()> {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_download_limits() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        t.set_config(Config::DownloadLimit, Some("200000")).await?;
+        t.set_config(Config::DownloadLimitImage, Some("1000000"))
+            .await?;
+        t.set_config(Config::DownloadLimitWebxdc, Some("0")).await?;
+        t.set_config(Config::DownloadLimitMeteredVideo, Some("1"))
+            .await?;
+
+        let limits = t.download_limits().await?;
+        assert_eq!(limits.get(None), Some(200000));
+        assert_eq!(limits.get(Some(Viewtype::File)), Some(200000));
+        assert_eq!(limits.get(Some(Viewtype::Image)), Some(1000000));
+        assert_eq!(limits.get(Some(Viewtype::Gif)), Some(1000000));
+        assert_eq!(limits.get(Some(Viewtype::Video)), Some(200000));
+        assert_eq!(limits.get(Some(Viewtype::Webxdc)), None);
+
+        t.set_config(Config::NetworkClass, Some("1")).await?;
+        let limits = t.download_limits().await?;
+        assert_eq!(limits.get(Some(Viewtype::Image)), Some(1000000));
+        assert_eq!(limits.get(Some(Viewtype::Video)), Some(MIN_DOWNLOAD_LIMIT));
+        assert_eq!(limits.get(Some(Viewtype::Webxdc)), None);
+
+        // Automatic download is suspended while roaming.
+        t.set_config(Config::NetworkClass, Some("2")).await?;
+        let limits = t.download_limits().await?;
+        assert_eq!(limits.get(Some(Viewtype::Image)), Some(MIN_DOWNLOAD_LIMIT));
+        assert_eq!(limits.get(Some(Viewtype::Webxdc)), Some(MIN_DOWNLOAD_LIMIT));
+
+        t.set_config_bool(Config::DownloadOnRoaming, true).await?;
+        let limits = t.download_limits().await?;
+        assert_eq!(limits.get(Some(Viewtype::Image)), Some(1000000));
+        assert_eq!(limits.get(Some(Viewtype::Video)), Some(MIN_DOWNLOAD_LIMIT));
+
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_update_download_state() -> Result<()> {
         let t = TestContext::new_alice().await;
@@ -410,6 +558,7 @@ async fn test_partial_download_and_ephemeral() -> Result<()> {
         Ok(())
     }
 
+    #[cfg(feature = "webxdc")]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_status_update_expands_to_nothing() -> Result<()> {
         let alice = TestContext::new_alice().await;
@@ -73,6 +73,7 @@
 use serde::{Deserialize, Serialize};
 use tokio::time::timeout;
 
+use crate::blob::remove_unreferenced_blobs;
 use crate::chat::{send_msg, ChatId};
 use crate::constants::{DC_CHAT_ID_LAST_SPECIAL, DC_CHAT_ID_TRASH};
 use crate::contact::ContactId;
@@ -462,6 +463,11 @@ pub(crate) async fn delete_expired_messages(context: &Context, now: i64) -> Resu
         for msg_id in webxdc_deleted {
             context.emit_event(EventType::WebxdcInstanceDeleted { msg_id });
         }
+
+        remove_unreferenced_blobs(context)
+            .await
+            .log_err(context)
+            .ok();
     }
 
     Ok(())
@@ -588,15 +594,25 @@ pub(crate) async fn delete_expired_imap_messages(context: &Context) -> Result<()
         };
     let target = context.get_delete_msgs_target().await?;
 
+    // Chats may override the global setting in `chats.delete_server_after`
+    // where 0 means "never" and 1 means "at once".
     context
         .sql
         .execute(
             "UPDATE imap
              SET target=?
              WHERE rfc724_mid IN (
-               SELECT rfc724_mid FROM msgs
-               WHERE ((download_state = 0 AND timestamp < ?) OR
-                      (download_state != 0 AND timestamp < ?) OR
+               SELECT msgs.rfc724_mid FROM msgs LEFT JOIN chats ON msgs.chat_id=chats.id
+               WHERE ((chats.delete_server_after IS NULL AND
+                       ((download_state = 0 AND msgs.timestamp < ?) OR
+                        (download_state != 0 AND msgs.timestamp < ?))) OR
+                      (chats.delete_server_after > 0 AND
+                       ((download_state = 0 AND
+                         msgs.timestamp < ? - (CASE WHEN chats.delete_server_after=1
+                                                    THEN 0
+                                                    ELSE chats.delete_server_after END)) OR
+                        (download_state != 0 AND
+                         msgs.timestamp < ? - MAX(chats.delete_server_after, ?)))) OR
                       (ephemeral_timestamp != 0 AND ephemeral_timestamp <= ?))
              )",
             (
@@ -604,6 +620,9 @@ pub(crate) async fn delete_expired_imap_messages(context: &Context) -> Result<()
                 threshold_timestamp,
                 threshold_timestamp_extended,
                 now,
+                now,
+                MIN_DELETE_SERVER_AFTER,
+                now,
             ),
         )
         .await?;
@@ -1208,6 +1227,74 @@ async fn remove_uid(context: &Context, id: u32) -> Result<()> {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_delete_expired_imap_messages_per_chat() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        const HOUR: i64 = 60 * 60;
+        let now = time();
+        let newsletter = t
+            .create_chat_with_contact("Newsletter", "news@example.net")
+            .await
+            .id;
+        let bob = t
+            .create_chat_with_contact("Bob", "bob@example.net")
+            .await
+            .id;
+        for (id, chat_id) in &[(100, newsletter), (200, bob)] {
+            let message_id = id.to_string();
+            t.sql
+                .execute(
+                    "INSERT INTO msgs (id, rfc724_mid, chat_id, timestamp) VALUES (?,?,?,?);",
+                    (id, &message_id, chat_id, now - 2 * 24 * HOUR),
+                )
+                .await?;
+            t.sql
+                .execute(
+                    "INSERT INTO imap (rfc724_mid, folder, uid, target) VALUES (?,'INBOX',?, 'INBOX');",
+                    (&message_id, id),
+                )
+                .await?;
+        }
+        let marked_for_deletion = || async {
+            t.sql
+                .query_map(
+                    "SELECT rfc724_mid FROM imap WHERE target='' ORDER BY rfc724_mid",
+                    (),
+                    |row| row.get::<_, String>(0),
+                    |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+                )
+                .await
+                .unwrap()
+        };
+
+        assert_eq!(newsletter.get_delete_server_after(&t).await?, None);
+        newsletter
+            .set_delete_server_after(&t, Some(24 * HOUR))
+            .await?;
+        assert_eq!(
+            newsletter.get_delete_server_after(&t).await?,
+            Some(24 * HOUR)
+        );
+        delete_expired_imap_messages(&t).await?;
+        assert_eq!(marked_for_deletion().await, vec!["100".to_string()]);
+
+        // Chat-specific "never" overrides the global setting.
+        bob.set_delete_server_after(&t, Some(0)).await?;
+        t.set_config(Config::DeleteServerAfter, Some(&*HOUR.to_string()))
+            .await?;
+        delete_expired_imap_messages(&t).await?;
+        assert_eq!(marked_for_deletion().await, vec!["100".to_string()]);
+
+        bob.set_delete_server_after(&t, None).await?;
+        delete_expired_imap_messages(&t).await?;
+        assert_eq!(
+            marked_for_deletion().await,
+            vec!["100".to_string(), "200".to_string()]
+        );
+
+        Ok(())
+    }
+
     // Regression test for a bug in the timer rollback protection.
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_ephemeral_timer_references() -> Result<()> {
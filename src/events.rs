@@ -1,16 +1,32 @@
 //! # Events specification.
 
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
 use async_channel::{self as channel, Receiver, Sender, TrySendError};
 
+mod coalescing;
+mod history;
 mod payload;
+mod queue;
 
+use self::coalescing::Coalescing;
+pub(crate) use self::history::EventHistory;
+pub use self::history::{EventHistoryEntry, EVENT_HISTORY_SIZE};
 pub use self::payload::EventType;
+use self::queue::{DroppedEvents, EventQueue};
+pub use self::queue::{EventQueueConfig, OverflowPolicy, DEFAULT_EVENT_QUEUE_SIZE};
 
 /// Event channel.
 #[derive(Debug, Clone)]
 pub struct Events {
-    receiver: Receiver<Event>,
-    sender: Sender<Event>,
+    queue: Arc<EventQueue>,
+
+    /// Queues receiving only the events of a single account,
+    /// created by [`Events::get_account_emitter`].
+    account_channels: Arc<RwLock<BTreeMap<u32, Arc<EventQueue>>>>,
+
+    coalescing: Arc<Coalescing>,
 }
 
 impl Default for Events {
@@ -22,33 +38,113 @@ fn default() -> Self {
 impl Events {
     /// Creates a new event channel.
     pub fn new() -> Self {
-        let (sender, receiver) = channel::bounded(1_000);
-
-        Self { receiver, sender }
+        Self {
+            queue: Arc::new(EventQueue::new(Default::default())),
+            account_channels: Default::default(),
+            coalescing: Default::default(),
+        }
     }
 
     /// Emits an event into event channel.
     ///
-    /// If the channel is full, deletes the oldest event first.
+    /// If the channel is full, events are dropped according to its [`OverflowPolicy`].
+    /// If an account emitter was created for the account which emitted the event,
+    /// the event is additionally sent to the account channel.
+    ///
+    /// Repeated events may be delayed and collapsed,
+    /// see [`Events::set_coalescing_window`].
     pub fn emit(&self, event: Event) {
-        match self.sender.try_send(event) {
-            Ok(()) => {}
-            Err(TrySendError::Full(event)) => {
-                // when we are full, we pop remove the oldest event and push on the new one
-                let _ = self.receiver.try_recv();
-
-                // try again
-                self.emit(event);
-            }
-            Err(TrySendError::Closed(_)) => {
-                unreachable!("unable to emit event, channel disconnected");
-            }
+        if let Some(event) = self.coalesce(event) {
+            self.send(event);
         }
     }
 
+    /// Sends an event into the event channel and the account channel.
+    fn send(&self, event: Event) {
+        let account_channel = self
+            .account_channels
+            .read()
+            .expect("RwLock is poisoned")
+            .get(&event.id)
+            .cloned();
+        if let Some(account_queue) = account_channel {
+            account_queue.push(event.clone());
+        }
+        self.queue.push(event);
+    }
+
     /// Creates an event emitter.
     pub fn get_emitter(&self) -> EventEmitter {
-        EventEmitter(self.receiver.clone())
+        self.queue.get_emitter()
+    }
+
+    /// Sets the size and overflow policy of the event channel.
+    ///
+    /// Account channels are configured with [`Events::set_account_queue_config`].
+    pub fn set_queue_config(&self, config: EventQueueConfig) {
+        self.queue.set_config(config);
+    }
+
+    /// Returns the size and overflow policy of the event channel.
+    pub fn get_queue_config(&self) -> EventQueueConfig {
+        self.queue.get_config()
+    }
+
+    /// Sets the size and overflow policy of the channel of the account with the given ID.
+    ///
+    /// Creates the account channel if it does not exist yet.
+    pub fn set_account_queue_config(&self, id: u32, config: EventQueueConfig) {
+        self.account_queue(id).set_config(config);
+    }
+
+    /// Creates an event emitter receiving only the events of the account with the given ID.
+    ///
+    /// Events are still delivered to the emitters created by [`Events::get_emitter`] as well.
+    /// All emitters created for the same account share one channel.
+    pub fn get_account_emitter(&self, id: u32) -> EventEmitter {
+        self.account_queue(id).get_emitter()
+    }
+
+    /// Returns the channel of the account with the given ID, creating it if needed.
+    fn account_queue(&self, id: u32) -> Arc<EventQueue> {
+        self.account_channels
+            .write()
+            .expect("RwLock is poisoned")
+            .entry(id)
+            .or_insert_with(|| Arc::new(EventQueue::new(Default::default())))
+            .clone()
+    }
+
+    /// Closes the channel of the account with the given ID.
+    ///
+    /// Account emitters return `None` once they have received all remaining events.
+    pub(crate) fn remove_account_channel(&self, id: u32) {
+        if let Some(account_queue) = self
+            .account_channels
+            .write()
+            .expect("RwLock is poisoned")
+            .remove(&id)
+        {
+            account_queue.close();
+        }
+    }
+}
+
+/// Sends `item` into a bounded channel.
+///
+/// If the channel is full, removes the oldest item first.
+/// If the channel is closed, drops the item.
+pub(crate) fn send_dropping_oldest<T>(sender: &Sender<T>, receiver: &Receiver<T>, item: T) {
+    match sender.try_send(item) {
+        Ok(()) => {}
+        Err(TrySendError::Full(item)) => {
+            // when we are full, we pop remove the oldest item and push on the new one
+            let _ = receiver.try_recv();
+
+            // try again
+            send_dropping_oldest(sender, receiver, item);
+        }
+        Err(TrySendError::Closed(_)) => {}
     }
 }
 
@@ -60,16 +156,25 @@ pub fn get_emitter(&self) -> EventEmitter {
 ///
 /// The `EventEmitter` is also a [`Stream`], so a typical usage is in a `while let` loop.
 ///
+/// If events were dropped because the channel was full,
+/// an [`EventType::EventQueueOverflow`] event is returned before the remaining events.
+///
 /// [`Context`]: crate::context::Context
 /// [`Context::get_event_emitter`]: crate::context::Context::get_event_emitter
 /// [`Stream`]: futures::stream::Stream
 #[derive(Debug, Clone)]
-pub struct EventEmitter(Receiver<Event>);
+pub struct EventEmitter {
+    receiver: Receiver<Event>,
+    dropped: Arc<DroppedEvents>,
+}
 
 impl EventEmitter {
     /// Async recv of an event. Return `None` if the `Sender` has been dropped.
     pub async fn recv(&self) -> Option<Event> {
-        self.0.recv().await.ok()
+        if let Some(event) = self.dropped.take_overflow_event() {
+            return Some(event);
+        }
+        self.receiver.recv().await.ok()
     }
 }
 
@@ -80,7 +185,89 @@ fn poll_next(
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        std::pin::Pin::new(&mut self.0).poll_next(cx)
+        if let Some(event) = self.dropped.take_overflow_event() {
+            return std::task::Poll::Ready(Some(event));
+        }
+        std::pin::Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+/// Event emitted on the account manager channel.
+///
+/// See [`Accounts::get_manager_emitter`].
+///
+/// [`Accounts::get_manager_emitter`]: crate::accounts::Accounts::get_manager_emitter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountsEvent {
+    /// Account with the given ID was added.
+    AccountAdded(u32),
+
+    /// Account with the given ID was removed.
+    AccountRemoved(u32),
+
+    /// [`Accounts::start_io`] started to start IO of the account with the given ID.
+    ///
+    /// [`Accounts::start_io`]: crate::accounts::Accounts::start_io
+    IoStarting(u32),
+
+    /// [`Accounts::start_io`] finished starting IO of the account with the given ID.
+    ///
+    /// IO is not started for unconfigured accounts and closed accounts,
+    /// but the event is emitted for them nevertheless.
+    ///
+    /// [`Accounts::start_io`]: crate::accounts::Accounts::start_io
+    IoStarted(u32),
+
+    /// Progress of [`Accounts::move_blobdir`].
+    ///
+    /// [`Accounts::move_blobdir`]: crate::accounts::Accounts::move_blobdir
+    BlobdirMoveProgress {
+        /// ID of the account whose blob directory is moved.
+        account_id: u32,
+
+        /// 0=error, 1-999=progress in permille, 1000=success and done
+        progress: usize,
+    },
+}
+
+/// Account manager event channel.
+#[derive(Debug, Clone)]
+pub(crate) struct AccountsEvents {
+    receiver: Receiver<AccountsEvent>,
+    sender: Sender<AccountsEvent>,
+}
+
+impl AccountsEvents {
+    /// Creates a new account manager event channel.
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = channel::bounded(1_000);
+
+        Self { receiver, sender }
+    }
+
+    /// Emits an event into the channel.
+    ///
+    /// If the channel is full, deletes the oldest event first.
+    pub(crate) fn emit(&self, event: AccountsEvent) {
+        send_dropping_oldest(&self.sender, &self.receiver, event);
+    }
+
+    /// Creates an account manager event emitter.
+    pub(crate) fn get_emitter(&self) -> AccountsEventEmitter {
+        AccountsEventEmitter(self.receiver.clone())
+    }
+}
+
+/// A receiver of [`AccountsEvent`]s.
+///
+/// If multiple instances are created, events are only delivered to one of them.
+#[derive(Debug, Clone)]
+pub struct AccountsEventEmitter(Receiver<AccountsEvent>);
+
+impl AccountsEventEmitter {
+    /// Async recv of an event. Return `None` if the `Sender` has been dropped.
+    pub async fn recv(&self) -> Option<AccountsEvent> {
+        self.0.recv().await.ok()
     }
 }
 
@@ -104,4 +291,13 @@ pub struct Event {
     ///
     /// These are documented in `deltachat.h` as the `DC_EVENT_*` constants.
     pub typ: EventType,
+    /// Sequence number of the event, increasing with each event emitted by the [`Context`].
+    ///
+    /// Events emitted since a given sequence number can be replayed
+    /// with [`Context::get_events_since`].
+    /// 0 for events not emitted by a [`Context`].
+    ///
+    /// [`Context`]: crate::context::Context
+    /// [`Context::get_events_since`]: crate::context::Context::get_events_since
+    pub seq: u64,
 }
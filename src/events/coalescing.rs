@@ -0,0 +1,230 @@
+//! # Coalescing of repeated events.
+//!
+//! Bulk operations such as imports or marking many messages as seen
+//! emit many `MsgsChanged` and `ChatModified` events for the same chat.
+//! If coalescing is enabled, only the first such event in a time window is emitted right away.
+//! Events following it within the window are collapsed into a single event
+//! emitted at the end of the window.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{Event, EventType, Events};
+use crate::chat::ChatId;
+use crate::message::MsgId;
+
+/// Account ID, event type name and chat ID of coalesced events.
+type CoalescingKey = (u32, &'static str, ChatId);
+
+/// Coalescing state shared by all clones of [`Events`].
+#[derive(Debug, Default)]
+pub(super) struct Coalescing {
+    /// Time window in milliseconds, 0 if coalescing is disabled.
+    window_ms: AtomicU64,
+
+    chats: Mutex<BTreeMap<CoalescingKey, ChatState>>,
+}
+
+#[derive(Debug)]
+struct ChatState {
+    /// Time the last event for the chat was emitted.
+    last_emitted: Instant,
+
+    /// Event collapsed from the events received within the window, not emitted yet.
+    pending: Option<Event>,
+}
+
+/// Returns the key if the event can be coalesced.
+fn coalescing_key(event: &Event) -> Option<CoalescingKey> {
+    match event.typ {
+        EventType::MsgsChanged { chat_id, .. } | EventType::ChatModified(chat_id) => {
+            Some((event.id, event.typ.name(), chat_id))
+        }
+        _ => None,
+    }
+}
+
+/// Merges `event` into the `pending` event of the same type and chat.
+///
+/// The merged event gets the sequence number of the newer event.
+fn merge(pending: &mut Event, event: Event) {
+    pending.seq = event.seq;
+    if let (
+        EventType::MsgsChanged { msg_id, .. },
+        EventType::MsgsChanged {
+            msg_id: new_msg_id, ..
+        },
+    ) = (&mut pending.typ, event.typ)
+    {
+        if *msg_id != new_msg_id {
+            // Several messages changed.
+            *msg_id = MsgId::new(0);
+        }
+    }
+}
+
+impl Events {
+    /// Sets the time window in which repeated `MsgsChanged` and `ChatModified` events
+    /// for the same chat are collapsed into one.
+    ///
+    /// Zero, the default, disables coalescing.
+    pub fn set_coalescing_window(&self, window: Duration) {
+        self.coalescing.window_ms.store(
+            window.as_millis().try_into().unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Returns the time window in which repeated events are collapsed.
+    pub fn get_coalescing_window(&self) -> Duration {
+        Duration::from_millis(self.coalescing.window_ms.load(Ordering::Relaxed))
+    }
+
+    /// Returns the event if it should be sent right away.
+    ///
+    /// Otherwise the event is collapsed into a pending event sent at the end of the window.
+    pub(super) fn coalesce(&self, event: Event) -> Option<Event> {
+        let window = self.get_coalescing_window();
+        if window.is_zero() {
+            return Some(event);
+        }
+        let key = match coalescing_key(&event) {
+            Some(key) => key,
+            None => return Some(event),
+        };
+        let runtime = match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => runtime,
+            // Pending events cannot be sent later without a runtime.
+            Err(_) => return Some(event),
+        };
+
+        let now = Instant::now();
+        let mut chats = self.coalescing.chats.lock().expect("Mutex is poisoned");
+        if let Some(state) = chats.get_mut(&key) {
+            let elapsed = now.saturating_duration_since(state.last_emitted);
+            if elapsed < window {
+                match &mut state.pending {
+                    Some(pending) => merge(pending, event),
+                    None => {
+                        state.pending = Some(event);
+                        let events = self.clone();
+                        runtime.spawn(async move {
+                            tokio::time::sleep(window - elapsed).await;
+                            events.flush_coalesced(key);
+                        });
+                    }
+                }
+                return None;
+            }
+        }
+
+        chats.retain(|_, state| {
+            state.pending.is_some() || now.saturating_duration_since(state.last_emitted) < window
+        });
+        chats.insert(
+            key,
+            ChatState {
+                last_emitted: now,
+                pending: None,
+            },
+        );
+        Some(event)
+    }
+
+    /// Sends the pending event collapsed for the given key.
+    fn flush_coalesced(&self, key: CoalescingKey) {
+        let pending = self
+            .coalescing
+            .chats
+            .lock()
+            .expect("Mutex is poisoned")
+            .get_mut(&key)
+            .and_then(|state| {
+                state.last_emitted = Instant::now();
+                state.pending.take()
+            });
+        if let Some(event) = pending {
+            self.send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_coalescing() {
+        let events = Events::new();
+        let emitter = events.get_emitter();
+        events.set_coalescing_window(Duration::from_millis(200));
+        assert_eq!(events.get_coalescing_window(), Duration::from_millis(200));
+
+        let chat_id = ChatId::new(10);
+        for msg_id in [1, 2, 3] {
+            events.emit(Event {
+                id: 1,
+                typ: EventType::MsgsChanged {
+                    chat_id,
+                    msg_id: MsgId::new(msg_id),
+                },
+                seq: msg_id.into(),
+            });
+        }
+        // Events of other chats and other types are not affected.
+        events.emit(Event {
+            id: 1,
+            typ: EventType::ChatModified(chat_id),
+            seq: 4,
+        });
+        events.emit(Event {
+            id: 1,
+            typ: EventType::ImapInboxIdle,
+            seq: 5,
+        });
+
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            let event = emitter.recv().await.unwrap();
+            received.push((event.typ, event.seq));
+        }
+        assert_eq!(
+            received,
+            vec![
+                (
+                    EventType::MsgsChanged {
+                        chat_id,
+                        msg_id: MsgId::new(1),
+                    },
+                    1
+                ),
+                (EventType::ChatModified(chat_id), 4),
+                (EventType::ImapInboxIdle, 5),
+                (
+                    EventType::MsgsChanged {
+                        chat_id,
+                        msg_id: MsgId::new(0),
+                    },
+                    3
+                ),
+            ]
+        );
+
+        events.set_coalescing_window(Duration::ZERO);
+        for _ in 0..2 {
+            events.emit(Event {
+                id: 1,
+                typ: EventType::ChatModified(chat_id),
+                seq: 0,
+            });
+        }
+        for _ in 0..2 {
+            assert_eq!(
+                emitter.recv().await.unwrap().typ,
+                EventType::ChatModified(chat_id)
+            );
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! # Event history.
+//!
+//! The last events emitted by a context are kept in memory together with timestamps,
+//! so bug reports can include what happened before a failure.
+//!
+//! Each event gets a sequence number, so clients which lost events,
+//! e.g. because of a brief disconnect, can replay them.
+
+use std::collections::VecDeque;
+
+use anyhow::{ensure, Result};
+use serde::Serialize;
+
+use super::EventType;
+use crate::context::Context;
+use crate::tools::time_ms;
+
+/// Maximum number of events kept in the event history of a context.
+pub const EVENT_HISTORY_SIZE: usize = 5_000;
+
+/// Event recorded in the event history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EventHistoryEntry {
+    /// Sequence number of the event, see [`Event::seq`](super::Event::seq).
+    pub seq: u64,
+
+    /// Time the event was emitted, in milliseconds since the Unix epoch.
+    pub timestamp_ms: i64,
+
+    /// The event payload.
+    pub event: EventType,
+}
+
+/// Ring buffer of the last emitted events.
+#[derive(Debug, Default)]
+pub(crate) struct EventHistory {
+    entries: VecDeque<EventHistoryEntry>,
+
+    /// Sequence number of the last recorded event, 0 if no event was recorded.
+    last_seq: u64,
+}
+
+impl EventHistory {
+    /// Records an event, removing the oldest event if the history is full.
+    ///
+    /// Returns the sequence number assigned to the event.
+    pub(crate) fn push(&mut self, event: EventType) -> u64 {
+        if self.entries.len() >= EVENT_HISTORY_SIZE {
+            self.entries.pop_front();
+        }
+        self.last_seq += 1;
+        self.entries.push_back(EventHistoryEntry {
+            seq: self.last_seq,
+            timestamp_ms: time_ms(),
+            event,
+        });
+        self.last_seq
+    }
+
+    /// Returns the events with a sequence number greater than `seq`.
+    ///
+    /// Fails if some of these events are not in the history anymore
+    /// or if `seq` was not assigned yet, e.g. because it was assigned before a restart.
+    fn get_since(&self, seq: u64) -> Result<Vec<EventHistoryEntry>> {
+        ensure!(
+            seq <= self.last_seq,
+            "Event {seq} was not emitted, last event is {}.",
+            self.last_seq
+        );
+        let first_seq = self.entries.front().map_or(self.last_seq + 1, |e| e.seq);
+        ensure!(
+            seq + 1 >= first_seq,
+            "Events after {seq} are not available anymore, oldest event is {first_seq}."
+        );
+        Ok(self
+            .entries
+            .iter()
+            .filter(|entry| entry.seq > seq)
+            .cloned()
+            .collect())
+    }
+}
+
+impl Context {
+    /// Returns the last events emitted by this context, oldest first.
+    ///
+    /// At most [`EVENT_HISTORY_SIZE`] events are kept.
+    /// The history is not saved to the database, so it only contains events
+    /// emitted since the context was opened.
+    pub fn get_event_history(&self) -> Vec<EventHistoryEntry> {
+        self.event_history
+            .lock()
+            .expect("Mutex is poisoned")
+            .entries
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the event history as a JSON array.
+    ///
+    /// Entries are objects with `seq`, `timestamp_ms` and `event` fields.
+    pub fn get_event_history_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.get_event_history())?)
+    }
+
+    /// Returns the events emitted after the event with sequence number `seq`, oldest first.
+    ///
+    /// Allows clients to catch up on events missed while disconnected.
+    /// Fails if some of the events are not in the history anymore
+    /// or `seq` is greater than the last assigned sequence number,
+    /// e.g. because the process was restarted.
+    /// In this case the client has to reload its state.
+    pub fn get_events_since(&self, seq: u64) -> Result<Vec<EventHistoryEntry>> {
+        self.event_history
+            .lock()
+            .expect("Mutex is poisoned")
+            .get_since(seq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::indexing_slicing)]
+
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_event_history() -> Result<()> {
+        let t = TestContext::new().await;
+        t.emit_event(EventType::Info("first".to_string()));
+        t.emit_event(EventType::ImapInboxIdle);
+
+        let history = t.get_event_history();
+        let len = history.len();
+        assert!(len >= 2);
+        assert_eq!(history[len - 2].event, EventType::Info("first".to_string()));
+        assert_eq!(history[len - 1].event, EventType::ImapInboxIdle);
+        assert!(history[len - 1].timestamp_ms >= history[len - 2].timestamp_ms);
+        assert!(t.get_event_history_json()?.contains("ImapInboxIdle"));
+
+        let mut history = EventHistory::default();
+        for i in 0..EVENT_HISTORY_SIZE + 10 {
+            history.push(EventType::Info(i.to_string()));
+        }
+        assert_eq!(history.entries.len(), EVENT_HISTORY_SIZE);
+        assert_eq!(
+            history.entries.front().map(|entry| &entry.event),
+            Some(&EventType::Info("10".to_string()))
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_events_since() -> Result<()> {
+        let t = TestContext::new().await;
+        t.emit_event(EventType::Info("first".to_string()));
+        let seq = t.get_event_history().last().unwrap().seq;
+        t.emit_event(EventType::ImapInboxIdle);
+        t.emit_event(EventType::SelfavatarChanged);
+
+        let events = t.get_events_since(seq)?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].seq, seq + 1);
+        assert_eq!(events[0].event, EventType::ImapInboxIdle);
+        assert_eq!(events[1].event, EventType::SelfavatarChanged);
+        assert!(t.get_events_since(seq + 2)?.is_empty());
+        assert!(t.get_events_since(seq + 3).is_err());
+
+        let mut history = EventHistory::default();
+        assert!(history.get_since(0)?.is_empty());
+        for i in 0..EVENT_HISTORY_SIZE + 10 {
+            history.push(EventType::Info(i.to_string()));
+        }
+        assert!(history.get_since(9).is_err());
+        assert_eq!(history.get_since(10)?.len(), EVENT_HISTORY_SIZE);
+        Ok(())
+    }
+}
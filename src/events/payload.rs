@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use strum::VariantNames;
 
 use crate::chat::ChatId;
 use crate::contact::ContactId;
@@ -10,7 +11,7 @@
 use crate::webxdc::StatusUpdateSerial;
 
 /// Event payload.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, EnumVariantNames, IntoStaticStr)]
 pub enum EventType {
     /// The library-user may write an informational string to the log.
     ///
@@ -135,6 +136,19 @@ pub enum EventType {
         msg_id: MsgId,
     },
 
+    /// Sending a message was given up because of a permanent error
+    /// or because the number of attempts exceeded `smtp_max_attempts`.
+    /// The message is removed from the sending queue and will not be retried.
+    ///
+    /// Emitted after `MsgFailed`, the error is available from dc_msg_get_error().
+    MsgGivenUp {
+        /// ID of the chat which the message belongs to.
+        chat_id: ChatId,
+
+        /// ID of the message that could not be sent.
+        msg_id: MsgId,
+    },
+
     /// A single message is read by the receiver. State changed from DC_STATE_OUT_DELIVERED to
     /// DC_STATE_OUT_MDN_RCVD, see dc_msg_get_state().
     MsgRead {
@@ -201,6 +215,27 @@ pub enum EventType {
     /// @param data2 0
     ImexFileWritten(PathBuf),
 
+    /// Inform about the database maintenance progress started by `Context::run_db_maintenance()`.
+    ///
+    /// @param data1 (usize) 0=error, 1-999=progress in permille, 1000=success and done
+    /// @param data2 0
+    DbMaintenanceProgress(usize),
+
+    /// Housekeeping finished, see `Context::run_housekeeping()`.
+    ///
+    /// @param data1 (usize) Number of unused files removed from the blob directory.
+    /// @param data2 (usize) Number of messages whose media was deleted.
+    HousekeepingDone {
+        /// Number of messages whose media was deleted according to the media cleanup policy.
+        deleted_msgs: usize,
+
+        /// Number of unused files removed from the blob directory.
+        removed_files: usize,
+
+        /// Number of bytes of the removed files.
+        freed_bytes: u64,
+    },
+
     /// Progress information of a secure-join handshake from the view of the inviter
     /// (Alice, the person who shows the QR code).
     ///
@@ -241,6 +276,26 @@ pub enum EventType {
     /// The user's avatar changed.
     SelfavatarChanged,
 
+    /// The mailbox usage crossed one of the quota warning thresholds,
+    /// see `Config::QuotaWarnThreshold` and `Config::QuotaErrorThreshold`.
+    QuotaWarning {
+        /// Usage of the most used quota resource in percent.
+        usage_percentage: u64,
+    },
+
+    /// An OAuth 2 access token is needed,
+    /// see [`Context::set_oauth2_token_requests`](crate::context::Context::set_oauth2_token_requests).
+    ///
+    /// The UI should obtain a token, e.g. using the sign-in SDK of the platform,
+    /// and pass it to [`Context::set_oauth2_access_token`](crate::context::Context::set_oauth2_access_token).
+    Oauth2TokenRequested {
+        /// E-mail address the token is requested for.
+        addr: String,
+
+        /// True if the previous token was rejected and a fresh one is needed.
+        regenerate: bool,
+    },
+
     /// Webxdc status update received.
     WebxdcStatusUpdate {
         /// Message ID.
@@ -250,9 +305,74 @@ pub enum EventType {
         status_update_serial: StatusUpdateSerial,
     },
 
+    /// Data received over the joined realtime channel of a webxdc instance,
+    /// see [`Context::join_webxdc_realtime_channel`](crate::context::Context::join_webxdc_realtime_channel).
+    WebxdcRealtimeData {
+        /// ID of the message containing the webxdc instance.
+        msg_id: MsgId,
+
+        /// Received data.
+        data: Vec<u8>,
+    },
+
     /// Inform that a message containing a webxdc instance has been deleted.
     WebxdcInstanceDeleted {
         /// ID of the deleted message.
         msg_id: MsgId,
     },
+
+    /// Events were dropped because the event channel was full,
+    /// see [`EventQueueConfig`](crate::EventQueueConfig).
+    ///
+    /// Emitted by the event emitter before the events remaining in the channel.
+    EventQueueOverflow {
+        /// Number of dropped events.
+        dropped: usize,
+    },
+}
+
+impl EventType {
+    /// Returns the name of the event type, e.g. `"IncomingMsg"`.
+    pub fn name(&self) -> &'static str {
+        self.into()
+    }
+
+    /// Returns the names of all event types.
+    pub fn names() -> &'static [&'static str] {
+        Self::VARIANTS
+    }
+
+    /// Returns true for informational events, which are dropped first if the event channel is full
+    /// and configured with [`OverflowPolicy::DropLowPriority`](crate::OverflowPolicy::DropLowPriority).
+    pub fn is_low_priority(&self) -> bool {
+        matches!(
+            self,
+            EventType::Info(_)
+                | EventType::SmtpConnected(_)
+                | EventType::ImapConnected(_)
+                | EventType::SmtpMessageSent(_)
+                | EventType::ImapMessageDeleted(_)
+                | EventType::ImapMessageMoved(_)
+                | EventType::ImapInboxIdle
+                | EventType::NewBlobFile(_)
+                | EventType::DeletedBlobFile(_)
+                | EventType::Warning(_)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_type_name() {
+        assert_eq!(EventType::ImapInboxIdle.name(), "ImapInboxIdle");
+        assert_eq!(
+            EventType::MsgsNoticed(ChatId::new(10)).name(),
+            "MsgsNoticed"
+        );
+        assert!(EventType::names().contains(&"IncomingMsg"));
+        assert!(!EventType::names().contains(&"Unknown"));
+    }
 }
@@ -0,0 +1,252 @@
+//! # Event queues.
+//!
+//! Emitted events are buffered in a queue until an [`EventEmitter`] receives them.
+//! If the application stops receiving events, e.g. because a JSON-RPC client is slow,
+//! the queue fills up. The size of each queue and which events are dropped
+//! when it is full can be configured with [`EventQueueConfig`].
+//! Emitters report dropped events with an [`EventType::EventQueueOverflow`] event.
+
+use std::sync::{Arc, Mutex};
+
+use async_channel::{self as channel, Receiver, Sender};
+
+use super::{Event, EventEmitter, EventType};
+
+/// Default maximum number of events in an event queue.
+pub const DEFAULT_EVENT_QUEUE_SIZE: usize = 1_000;
+
+/// What to drop if an event is emitted into a full event queue.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest event in the queue.
+    #[default]
+    DropOldest,
+
+    /// Drop all low-priority events in the queue, see [`EventType::is_low_priority`].
+    ///
+    /// If the queue contains only high-priority events,
+    /// a low-priority event being emitted is dropped,
+    /// a high-priority one replaces the oldest event.
+    DropLowPriority,
+}
+
+/// Configuration of an event queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventQueueConfig {
+    /// Maximum number of events in the queue, at least 1.
+    pub capacity: usize,
+
+    /// What to drop if the queue is full.
+    pub overflow_policy: OverflowPolicy,
+}
+
+impl Default for EventQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_EVENT_QUEUE_SIZE,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+/// Number of events dropped from a queue since an emitter last reported it.
+///
+/// Shared by the queue and its emitters.
+#[derive(Debug, Default)]
+pub(super) struct DroppedEvents(Mutex<Option<(u32, usize)>>);
+
+impl DroppedEvents {
+    /// Counts an event dropped from the queue.
+    fn count(&self, event: &Event) {
+        let mut dropped = self.0.lock().expect("Mutex is poisoned");
+        let count = dropped.map_or(0, |(_id, count)| count);
+        *dropped = Some((event.id, count + 1));
+    }
+
+    /// Returns an [`EventType::EventQueueOverflow`] event if events were dropped
+    /// and resets the counter.
+    ///
+    /// The event has the account ID of the last dropped event.
+    pub(super) fn take_overflow_event(&self) -> Option<Event> {
+        let (id, dropped) = self.0.lock().expect("Mutex is poisoned").take()?;
+        Some(Event {
+            id,
+            typ: EventType::EventQueueOverflow { dropped },
+            seq: 0,
+        })
+    }
+}
+
+/// Queue of events not received yet.
+#[derive(Debug)]
+pub(super) struct EventQueue {
+    // The channel is unbounded, the capacity is enforced by `EventQueue::push`
+    // so it can be changed while emitters exist.
+    sender: Sender<Event>,
+    receiver: Receiver<Event>,
+
+    /// Queue configuration, locked while an event is pushed.
+    config: Mutex<EventQueueConfig>,
+
+    dropped: Arc<DroppedEvents>,
+}
+
+impl EventQueue {
+    pub(super) fn new(config: EventQueueConfig) -> Self {
+        let (sender, receiver) = channel::unbounded();
+        Self {
+            sender,
+            receiver,
+            config: Mutex::new(config),
+            dropped: Default::default(),
+        }
+    }
+
+    pub(super) fn set_config(&self, config: EventQueueConfig) {
+        *self.config.lock().expect("Mutex is poisoned") = config;
+    }
+
+    pub(super) fn get_config(&self) -> EventQueueConfig {
+        *self.config.lock().expect("Mutex is poisoned")
+    }
+
+    pub(super) fn get_emitter(&self) -> EventEmitter {
+        EventEmitter {
+            receiver: self.receiver.clone(),
+            dropped: self.dropped.clone(),
+        }
+    }
+
+    /// Closes the queue.
+    ///
+    /// Emitters return `None` once they have received all remaining events.
+    pub(super) fn close(&self) {
+        self.sender.close();
+    }
+
+    /// Pushes an event into the queue, dropping events if the queue is full.
+    ///
+    /// If the queue is closed, drops the event.
+    pub(super) fn push(&self, event: Event) {
+        let config = self.config.lock().expect("Mutex is poisoned");
+        if self.sender.is_closed() {
+            return;
+        }
+
+        let capacity = config.capacity.max(1);
+        if self.sender.len() >= capacity
+            && config.overflow_policy == OverflowPolicy::DropLowPriority
+        {
+            self.drop_low_priority();
+            if self.sender.len() >= capacity && event.typ.is_low_priority() {
+                self.dropped.count(&event);
+                return;
+            }
+        }
+        while self.sender.len() >= capacity {
+            match self.receiver.try_recv() {
+                Ok(dropped_event) => self.dropped.count(&dropped_event),
+                Err(_) => break,
+            }
+        }
+        self.sender.try_send(event).ok();
+    }
+
+    /// Removes all low-priority events from the queue.
+    fn drop_low_priority(&self) {
+        let mut events = Vec::with_capacity(self.receiver.len());
+        while let Ok(event) = self.receiver.try_recv() {
+            events.push(event);
+        }
+        for event in events {
+            if event.typ.is_low_priority() {
+                self.dropped.count(&event);
+            } else {
+                self.sender.try_send(event).ok();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::ChatId;
+
+    fn info(text: &str) -> Event {
+        Event {
+            id: 1,
+            typ: EventType::Info(text.to_string()),
+            seq: 0,
+        }
+    }
+
+    fn chat_modified(chat_id: u32) -> Event {
+        Event {
+            id: 1,
+            typ: EventType::ChatModified(ChatId::new(chat_id)),
+            seq: 0,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_drop_oldest() {
+        let queue = EventQueue::new(EventQueueConfig {
+            capacity: 2,
+            overflow_policy: OverflowPolicy::DropOldest,
+        });
+        let emitter = queue.get_emitter();
+        for event in [info("1"), chat_modified(10), info("3")] {
+            queue.push(event);
+        }
+
+        assert_eq!(
+            emitter.recv().await.unwrap().typ,
+            EventType::EventQueueOverflow { dropped: 1 }
+        );
+        assert_eq!(emitter.recv().await, Some(chat_modified(10)));
+        assert_eq!(emitter.recv().await, Some(info("3")));
+
+        queue.close();
+        assert_eq!(emitter.recv().await, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_drop_low_priority() {
+        let queue = EventQueue::new(EventQueueConfig {
+            capacity: 3,
+            overflow_policy: OverflowPolicy::DropLowPriority,
+        });
+        let emitter = queue.get_emitter();
+        for event in [
+            chat_modified(10),
+            info("1"),
+            info("2"),
+            chat_modified(11),
+            chat_modified(12),
+            info("3"),
+            chat_modified(13),
+        ] {
+            queue.push(event);
+        }
+
+        assert_eq!(
+            emitter.recv().await.unwrap().typ,
+            EventType::EventQueueOverflow { dropped: 4 }
+        );
+        for chat_id in [11, 12, 13] {
+            assert_eq!(emitter.recv().await, Some(chat_modified(chat_id)));
+        }
+
+        // The capacity can be changed while emitters exist.
+        queue.set_config(EventQueueConfig {
+            capacity: 5,
+            overflow_policy: OverflowPolicy::DropLowPriority,
+        });
+        for i in 0..5 {
+            queue.push(info(&i.to_string()));
+        }
+        assert_eq!(queue.receiver.len(), 5);
+        assert!(queue.dropped.take_overflow_event().is_none());
+    }
+}
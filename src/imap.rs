@@ -13,6 +13,7 @@
 
 use anyhow::{bail, format_err, Context as _, Result};
 use async_channel::Receiver;
+use async_imap::imap_proto::BodyStructure;
 use async_imap::types::{Fetch, Flag, Name, NameAttribute, UnsolicitedResponse};
 use futures::{StreamExt, TryStreamExt};
 use num_traits::FromPrimitive;
@@ -40,7 +41,7 @@
 use crate::socks::Socks5Config;
 use crate::sql;
 use crate::stock_str;
-use crate::tools::create_id;
+use crate::tools::{create_id, time};
 
 pub(crate) mod capabilities;
 mod client;
@@ -70,7 +71,9 @@ pub enum ImapActionResult {
 /// - Chat-Version to check if a message is a chat message
 /// - Autocrypt-Setup-Message to check if a message is an autocrypt setup message,
 ///   not necessarily sent by Delta Chat.
-const PREFETCH_FLAGS: &str = "(UID INTERNALDATE RFC822.SIZE BODY.PEEK[HEADER.FIELDS (\
+/// - BODYSTRUCTURE to apply per-viewtype download limits.
+const PREFETCH_FLAGS: &str =
+    "(UID INTERNALDATE RFC822.SIZE BODYSTRUCTURE BODY.PEEK[HEADER.FIELDS (\
                               MESSAGE-ID \
                               X-MICROSOFT-ORIGINAL-MESSAGE-ID \
                               FROM \
@@ -132,6 +135,9 @@ pub enum FolderMeaning {
     /// from the real folder and the result of moving and deleting messages via
     /// virtual folder is unclear.
     Virtual,
+
+    /// Folder watched in addition to the standard folders, see `Config::WatchFolders`.
+    Extra,
 }
 
 impl FolderMeaning {
@@ -145,6 +151,7 @@ pub fn to_config(self) -> Option<Config> {
             FolderMeaning::Trash => Some(Config::ConfiguredTrashFolder),
             FolderMeaning::Drafts => None,
             FolderMeaning::Virtual => None,
+            FolderMeaning::Extra => None,
         }
     }
 }
@@ -293,6 +300,7 @@ pub async fn new_configured(
     /// Calling this function is not enough to perform IMAP operations. Use [`Imap::prepare`]
     /// instead if you are going to actually use connection rather than trying connection
     /// parameters.
+    #[tracing::instrument(skip_all, fields(account_id = context.id))]
     pub async fn connect(&mut self, context: &Context) -> Result<()> {
         if self.config.lp.server.is_empty() {
             bail!("IMAP operation attempted while it is torn down");
@@ -454,6 +462,7 @@ pub fn trigger_reconnect(&mut self, context: &Context) {
     ///
     /// Prefetches headers and downloads new message from the folder, moves messages away from the
     /// folder and deletes messages in the folder.
+    #[tracing::instrument(skip_all, fields(account_id = context.id, folder = watch_folder))]
     pub async fn fetch_move_delete(
         &mut self,
         context: &Context,
@@ -482,6 +491,8 @@ pub async fn fetch_move_delete(
             .session
             .as_mut()
             .context("no IMAP connection established")?;
+        // Do not try to move or delete messages which are already gone from the server.
+        session.delete_vanished(context).await?;
         session
             .move_delete_messages(context, watch_folder)
             .await
@@ -698,6 +709,7 @@ pub(crate) async fn select_with_uidvalidity(
     /// Fetches new messages.
     ///
     /// Returns true if at least one message was fetched.
+    #[tracing::instrument(skip_all, fields(account_id = context.id, folder = folder))]
     pub(crate) async fn fetch_new_messages(
         &mut self,
         context: &Context,
@@ -732,7 +744,7 @@ pub(crate) async fn fetch_new_messages(
         };
         let read_cnt = msgs.len();
 
-        let download_limit = context.download_limit().await?;
+        let download_limits = context.download_limits().await?;
         let mut uids_fetch = Vec::<(_, bool /* partially? */)>::with_capacity(msgs.len() + 1);
         let mut uid_message_ids = BTreeMap::new();
         let mut largest_uid_skipped = None;
@@ -783,7 +795,7 @@ pub(crate) async fn fetch_new_messages(
                 )
                 .await.context("prefetch_should_download")?
             {
-                match download_limit {
+                match download_limits.get(get_fetch_viewtype(fetch_response)) {
                     Some(download_limit) => uids_fetch.push((
                         uid,
                         fetch_response.size.unwrap_or_default() > download_limit,
@@ -965,6 +977,9 @@ async fn move_message_batch(
         if self.can_move() {
             match self.uid_mv(set, &target).await {
                 Ok(()) => {
+                    if context.is_trash(target).await? {
+                        record_moved_to_trash(context, &row_ids).await?;
+                    }
                     // Messages are moved or don't exist, IMAP returns OK response in both cases.
                     context
                         .sql
@@ -1094,6 +1109,96 @@ async fn move_delete_messages(&mut self, context: &Context, folder: &str) -> Res
         Ok(())
     }
 
+    /// Deletes messages from the Trash folder
+    /// which were moved there by Delta Chat more than `DeleteTrashAfter` seconds ago.
+    ///
+    /// Messages moved to the Trash folder by other clients are not deleted.
+    /// Runs at most once a day.
+    pub(crate) async fn cleanup_trash(&mut self, context: &Context) -> Result<()> {
+        let delete_trash_after = context.get_config_i64(Config::DeleteTrashAfter).await?;
+        if delete_trash_after <= 0 || !context.should_delete_to_trash().await? {
+            return Ok(());
+        }
+        let trash = match context.get_config(Config::ConfiguredTrashFolder).await? {
+            Some(trash) => trash,
+            None => return Ok(()),
+        };
+
+        let now = time();
+        let last_cleanup = context
+            .sql
+            .get_raw_config_int64("last_trash_cleanup")
+            .await?
+            .unwrap_or_default();
+        if last_cleanup <= now && now < last_cleanup + 24 * 60 * 60 {
+            return Ok(());
+        }
+
+        let rfc724_mids = get_expired_trash_msgs(context, now - delete_trash_after).await?;
+        if !rfc724_mids.is_empty() {
+            self.delete_trash_msgs(context, &trash, rfc724_mids).await?;
+        }
+
+        context
+            .sql
+            .set_raw_config_int64("last_trash_cleanup", now)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the messages with the Message-IDs `rfc724_mids` from the Trash folder `trash`
+    /// and forgets that they were moved there.
+    async fn delete_trash_msgs(
+        &mut self,
+        context: &Context,
+        trash: &str,
+        rfc724_mids: Vec<String>,
+    ) -> Result<()> {
+        self.select_folder(context, Some(trash)).await?;
+        let mut uids = Vec::new();
+        for rfc724_mid in &rfc724_mids {
+            if rfc724_mid.contains(['"', '\\']) {
+                continue;
+            }
+            uids.extend(
+                self.uid_search(format!("HEADER Message-ID \"<{rfc724_mid}>\""))
+                    .await
+                    .context("failed to search for expired messages in trash")?,
+            );
+        }
+        uids.sort_unstable();
+        uids.dedup();
+        for (_, uid_set) in build_sequence_sets(&uids)? {
+            self.add_flag_finalized_with_set(&uid_set, "\\Deleted")
+                .await?;
+            context.emit_event(EventType::ImapMessageDeleted(format!(
+                "IMAP messages {uid_set} in {trash} marked as deleted"
+            )));
+        }
+        if !uids.is_empty() {
+            info!(
+                context,
+                "Deleting {} old messages from \"{}\".",
+                uids.len(),
+                trash
+            );
+            self.maybe_close_folder(context).await?;
+        }
+
+        // Messages which are not found were restored or deleted by the user meanwhile.
+        context
+            .sql
+            .execute(
+                &format!(
+                    "DELETE FROM imap_trash WHERE rfc724_mid IN ({})",
+                    sql::repeat_vars(rfc724_mids.len())
+                ),
+                rusqlite::params_from_iter(rfc724_mids),
+            )
+            .await?;
+        Ok(())
+    }
+
     /// Stores pending `\Seen` flags for messages in `imap_markseen` table.
     pub(crate) async fn store_seen_flags_on_imap(&mut self, context: &Context) -> Result<()> {
         let rows = context
@@ -1192,8 +1297,16 @@ pub(crate) async fn sync_seen_flags(&mut self, context: &Context, folder: &str)
         let mut highest_modseq = get_modseq(context, folder)
             .await
             .with_context(|| format!("failed to get MODSEQ for folder {folder}"))?;
+        // With QRESYNC, the server additionally reports messages expunged since `highest_modseq`
+        // with `VANISHED (EARLIER)` responses, so we do not need to rescan UIDs to notice them.
+        let qresync = session.can_qresync() && highest_modseq > 0;
+        let query = if qresync {
+            format!("(FLAGS) (CHANGEDSINCE {highest_modseq} VANISHED)")
+        } else {
+            format!("(FLAGS) (CHANGEDSINCE {highest_modseq})")
+        };
         let mut list = session
-            .uid_fetch("1:*", format!("(FLAGS) (CHANGEDSINCE {highest_modseq})"))
+            .uid_fetch("1:*", query)
             .await
             .context("failed to fetch flags")?;
 
@@ -1228,6 +1341,14 @@ pub(crate) async fn sync_seen_flags(&mut self, context: &Context, folder: &str)
                 warn!(context, "FETCH result contains no MODSEQ");
             }
         }
+        drop(list);
+
+        if qresync {
+            session
+                .delete_vanished(context)
+                .await
+                .with_context(|| format!("failed to remove vanished UIDs in {folder}"))?;
+        }
 
         set_modseq(context, folder, highest_modseq)
             .await
@@ -1346,6 +1467,10 @@ async fn prefetch_existing_msgs(&mut self) -> Result<Vec<(u32, async_imap::types
     /// Returns the last UID fetched successfully and the info about each downloaded message.
     /// If the message is incorrect or there is a failure to write a message to the database,
     /// it is skipped and the error is logged.
+    #[tracing::instrument(
+        skip_all,
+        fields(account_id = context.id, folder = folder, count = request_uids.len())
+    )]
     pub(crate) async fn fetch_many_msgs(
         &mut self,
         context: &Context,
@@ -1739,20 +1864,72 @@ impl Session {
     /// Drains all responses from `session.unsolicited_responses` in the process.
     /// If this returns `true`, this means that new emails arrived and you should
     /// fetch again, even if you just fetched.
-    fn server_sent_unsolicited_exists(&self, context: &Context) -> Result<bool> {
+    ///
+    /// Messages reported as expunged in the process are removed from the database.
+    async fn server_sent_unsolicited_exists(&mut self, context: &Context) -> Result<bool> {
+        self.delete_vanished(context).await?;
+        Ok(std::mem::take(&mut self.new_mail))
+    }
+
+    /// Removes messages which the server reported as expunged from the selected folder
+    /// from the database.
+    ///
+    /// Has to be called before another folder is selected,
+    /// because `VANISHED` responses do not contain the folder name.
+    async fn delete_vanished(&mut self, context: &Context) -> Result<()> {
+        self.drain_unsolicited_responses(context);
+        let uids = std::mem::take(&mut self.vanished_uids);
+        if uids.is_empty() {
+            return Ok(());
+        }
+        let folder = if let Some(folder) = &self.selected_folder {
+            folder
+        } else {
+            warn!(
+                context,
+                "Ignoring {} vanished UID(s) without selected folder.",
+                uids.len()
+            );
+            return Ok(());
+        };
+        info!(
+            context,
+            "{} message(s) vanished from folder {}.",
+            uids.len(),
+            folder
+        );
+        let uid_validity = get_uidvalidity(context, folder).await?;
+        remove_vanished_uids(context, folder, uid_validity, uids).await
+    }
+
+    /// Drains all responses from `session.unsolicited_responses`.
+    ///
+    /// Unsolicited EXISTS responses are remembered
+    /// to be reported by `server_sent_unsolicited_exists()` later.
+    /// UIDs reported as expunged by `VANISHED` responses,
+    /// which are sent instead of `EXPUNGE` responses if QRESYNC is enabled,
+    /// are remembered to be removed by `delete_vanished()`.
+    fn drain_unsolicited_responses(&mut self, context: &Context) {
         use async_imap::imap_proto::Response;
         use async_imap::imap_proto::ResponseCode;
         use UnsolicitedResponse::*;
 
-        let mut unsolicited_exists = false;
         while let Ok(response) = self.unsolicited_responses.try_recv() {
+            if let Other(response_data) = &response {
+                if let Response::Vanished { uids, .. } = response_data.parsed() {
+                    for range in uids {
+                        self.vanished_uids.extend(range.clone());
+                    }
+                    continue;
+                }
+            }
             match response {
                 Exists(_) => {
                     info!(
                         context,
                         "Need to fetch again, got unsolicited EXISTS {:?}", response
                     );
-                    unsolicited_exists = true;
+                    self.new_mail = true;
                 }
 
                 // We are not interested in the following responses and they are are
@@ -1773,7 +1950,6 @@ fn server_sent_unsolicited_exists(&self, context: &Context) -> Result<bool> {
                 }
             }
         }
-        Ok(unsolicited_exists)
     }
 }
 
@@ -1861,6 +2037,31 @@ async fn spam_target_folder_cfg(
     }
 }
 
+/// Returns target folder for a message found in one of the `WatchFolders`.
+/// Chat messages are moved out only if `WatchFoldersMove` is set,
+/// other messages always stay where server-side filters put them.
+async fn extra_target_folder_cfg(
+    context: &Context,
+    headers: &[mailparse::MailHeader<'_>],
+) -> Result<Option<Config>> {
+    if !context.get_config_bool(Config::WatchFoldersMove).await?
+        || headers
+            .get_header_value(HeaderDef::AutocryptSetupMessage)
+            .is_some()
+        || !prefetch_is_chat_message(context, headers).await?
+    {
+        return Ok(None);
+    }
+
+    if context.get_config_bool(Config::MvboxMove).await?
+        || context.get_config_bool(Config::OnlyFetchMvbox).await?
+    {
+        Ok(Some(Config::ConfiguredMvboxFolder))
+    } else {
+        Ok(Some(Config::ConfiguredInboxFolder))
+    }
+}
+
 /// Returns `ConfiguredInboxFolder`, `ConfiguredMvboxFolder` or `ConfiguredSentboxFolder` if
 /// the message needs to be moved from `folder`. Otherwise returns `None`.
 pub async fn target_folder_cfg(
@@ -1875,6 +2076,8 @@ pub async fn target_folder_cfg(
 
     if folder_meaning == FolderMeaning::Spam {
         spam_target_folder_cfg(context, headers).await
+    } else if folder_meaning == FolderMeaning::Extra {
+        extra_target_folder_cfg(context, headers).await
     } else if needs_move_to_mvbox(context, headers).await? {
         Ok(Some(Config::ConfiguredMvboxFolder))
     } else {
@@ -1914,6 +2117,14 @@ async fn needs_move_to_mvbox(
         return Ok(false);
     }
 
+    prefetch_is_chat_message(context, headers).await
+}
+
+/// Returns true if the prefetched message is a chat message or a reply to one.
+async fn prefetch_is_chat_message(
+    context: &Context,
+    headers: &[mailparse::MailHeader<'_>],
+) -> Result<bool> {
     if headers.get_header_value(HeaderDef::ChatVersion).is_some() {
         Ok(true)
     } else if let Some(parent) = get_prefetch_parent_message(context, headers).await? {
@@ -2060,6 +2271,70 @@ fn get_fetch_headers(prefetch_msg: &Fetch) -> Result<Vec<mailparse::MailHeader>>
     }
 }
 
+/// Returns the viewtype of the first attachment according to the prefetched BODYSTRUCTURE.
+///
+/// Returns `None` if there is no BODYSTRUCTURE, no attachment
+/// or the message is encrypted and the attachment is not visible yet.
+fn get_fetch_viewtype(prefetch_msg: &Fetch) -> Option<Viewtype> {
+    bodystructure_viewtype(prefetch_msg.bodystructure()?)
+}
+
+fn bodystructure_viewtype(bodystructure: &BodyStructure) -> Option<Viewtype> {
+    let common = match bodystructure {
+        BodyStructure::Multipart { common, bodies, .. } => {
+            if common.ty.subtype.eq_ignore_ascii_case("encrypted") {
+                return None;
+            }
+            return bodies.iter().find_map(bodystructure_viewtype);
+        }
+        BodyStructure::Message { .. } => return None,
+        BodyStructure::Basic { common, .. } | BodyStructure::Text { common, .. } => common,
+    };
+
+    let ty = &common.ty.ty;
+    let subtype = &common.ty.subtype;
+    let is_attachment = common.disposition.as_ref().map_or(false, |disposition| {
+        disposition.ty.eq_ignore_ascii_case("attachment")
+    });
+    let has_xdc_suffix = common
+        .ty
+        .params
+        .iter()
+        .chain(
+            common
+                .disposition
+                .iter()
+                .filter_map(|disposition| disposition.params.as_ref()),
+        )
+        .flatten()
+        .any(|(key, value)| {
+            (key.eq_ignore_ascii_case("name") || key.eq_ignore_ascii_case("filename"))
+                && value.to_lowercase().ends_with(".xdc")
+        });
+
+    if has_xdc_suffix
+        || (ty.eq_ignore_ascii_case("application") && subtype.eq_ignore_ascii_case("webxdc+zip"))
+    {
+        Some(Viewtype::Webxdc)
+    } else if ty.eq_ignore_ascii_case("image") {
+        if subtype.eq_ignore_ascii_case("gif") {
+            Some(Viewtype::Gif)
+        } else if subtype.eq_ignore_ascii_case("svg+xml") {
+            Some(Viewtype::File)
+        } else {
+            Some(Viewtype::Image)
+        }
+    } else if ty.eq_ignore_ascii_case("video") {
+        Some(Viewtype::Video)
+    } else if ty.eq_ignore_ascii_case("audio") {
+        Some(Viewtype::Audio)
+    } else if ty.eq_ignore_ascii_case("application") || is_attachment {
+        Some(Viewtype::File)
+    } else {
+        None
+    }
+}
+
 fn prefetch_get_message_id(headers: &[mailparse::MailHeader]) -> Option<String> {
     headers
         .get_header_value(HeaderDef::XMicrosoftOriginalMessageId)
@@ -2097,6 +2372,10 @@ pub(crate) async fn prefetch_should_download(
     if message::rfc724_mid_exists(context, message_id)
         .await?
         .is_some()
+        || context
+            .sql
+            .cold_stored_rfc724_mid_exists(message_id)
+            .await?
     {
         markseen_on_imap_table(context, message_id).await?;
         return Ok(false);
@@ -2250,6 +2529,37 @@ pub(crate) async fn markseen_on_imap_table(context: &Context, message_id: &str)
     Ok(())
 }
 
+/// Remembers that the messages with the given `imap` table row IDs were moved to the Trash folder
+/// by Delta Chat just now, see [`Session::cleanup_trash`].
+async fn record_moved_to_trash(context: &Context, row_ids: &[i64]) -> Result<()> {
+    context
+        .sql
+        .execute(
+            &format!(
+                "INSERT OR REPLACE INTO imap_trash (rfc724_mid, timestamp)
+                 SELECT rfc724_mid, ? FROM imap WHERE id IN ({}) AND rfc724_mid!=''",
+                sql::repeat_vars(row_ids.len())
+            ),
+            rusqlite::params_from_iter(std::iter::once(time()).chain(row_ids.iter().copied())),
+        )
+        .await
+        .context("cannot record messages moved to trash")?;
+    Ok(())
+}
+
+/// Returns Message-IDs of messages moved to the Trash folder by Delta Chat before `before`.
+async fn get_expired_trash_msgs(context: &Context, before: i64) -> Result<Vec<String>> {
+    context
+        .sql
+        .query_map(
+            "SELECT rfc724_mid FROM imap_trash WHERE timestamp<? ORDER BY timestamp LIMIT 500",
+            (before,),
+            |row| row.get::<_, String>(0),
+            |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+        )
+        .await
+}
+
 /// uid_next is the next unique identifier value from the last time we fetched a folder
 /// See <https://tools.ietf.org/html/rfc3501#section-2.3.1.1>
 /// This function is used to update our uid_next after fetching messages.
@@ -2317,6 +2627,28 @@ pub(crate) async fn set_modseq(context: &Context, folder: &str, modseq: u64) ->
     Ok(())
 }
 
+/// Removes messages which were expunged on the server from the `imap` table
+/// so that they are neither moved nor deleted later.
+async fn remove_vanished_uids(
+    context: &Context,
+    folder: &str,
+    uid_validity: u32,
+    uids: Vec<u32>,
+) -> Result<()> {
+    let folder = folder.to_string();
+    context
+        .sql
+        .transaction(move |transaction| {
+            let mut stmt = transaction
+                .prepare("DELETE FROM imap WHERE folder=? AND uidvalidity=? AND uid=?")?;
+            for uid in uids {
+                stmt.execute((&folder, uid_validity, uid))?;
+            }
+            Ok(())
+        })
+        .await
+}
+
 async fn get_modseq(context: &Context, folder: &str) -> Result<u64> {
     Ok(context
         .sql
@@ -2450,7 +2782,14 @@ async fn add_all_recipients_as_contacts(
         .get_all_recipients(context)
         .await
         .context("could not get recipients")?;
+    add_recipients_as_contacts(context, recipients).await
+}
 
+/// Adds recipients of sent messages as contacts.
+pub(crate) async fn add_recipients_as_contacts(
+    context: &Context,
+    recipients: Vec<SingleInfo>,
+) -> Result<()> {
     let mut any_modified = false;
     for recipient in recipients {
         let display_name_normalized = recipient
@@ -2531,6 +2870,42 @@ async fn test_set_uid_next_validity() {
         assert_eq!(get_uidvalidity(&t.ctx, "Inbox").await.unwrap(), 6);
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_remove_vanished_uids() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        for uid in 1..=4 {
+            t.sql
+                .execute(
+                    "INSERT INTO imap (rfc724_mid, folder, uid, uidvalidity, target)
+                     VALUES (?, 'INBOX', ?, 7, 'INBOX')",
+                    (format!("{uid}@example.org"), uid),
+                )
+                .await?;
+        }
+
+        remove_vanished_uids(&t, "INBOX", 7, vec![2, 3, 10]).await?;
+        let remaining = t
+            .sql
+            .query_map(
+                "SELECT uid FROM imap WHERE folder='INBOX' ORDER BY uid",
+                (),
+                |row| row.get::<_, u32>(0),
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        assert_eq!(remaining, vec![1, 4]);
+
+        // UIDs of another UIDVALIDITY are not affected.
+        remove_vanished_uids(&t, "INBOX", 8, vec![1, 4]).await?;
+        assert_eq!(
+            t.sql
+                .count("SELECT COUNT(*) FROM imap WHERE folder='INBOX'", ())
+                .await?,
+            2
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_build_sequence_sets() {
         assert_eq!(build_sequence_sets(&[]).unwrap(), vec![]);
@@ -2771,6 +3146,59 @@ async fn test_target_folder_setupmsg() -> Result<()> {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_target_folder_extra() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        t.set_config(Config::ConfiguredInboxFolder, Some("INBOX"))
+            .await?;
+        t.set_config(Config::ConfiguredMvboxFolder, Some("DeltaChat"))
+            .await?;
+        t.set_config(Config::WatchFolders, Some("Chats\nINBOX\n\nChats"))
+            .await?;
+        assert_eq!(
+            scan_folders::get_extra_watched_folders(&t).await?,
+            vec!["Chats".to_string()]
+        );
+
+        let chat_msg = b"From: bob@example.net\n\
+            To: alice@example.org\n\
+            Message-ID: <abc@example.com>\n\
+            Chat-Version: 1.0\n\
+            \n\
+            hello\n";
+        let (chat_headers, _) = mailparse::parse_headers(chat_msg)?;
+        let email = b"From: bob@example.net\n\
+            To: alice@example.org\n\
+            Message-ID: <def@example.com>\n\
+            \n\
+            hello\n";
+        let (email_headers, _) = mailparse::parse_headers(email)?;
+
+        // Messages are not moved out of extra folders by default.
+        assert_eq!(
+            target_folder(&t, "Chats", FolderMeaning::Extra, &chat_headers).await?,
+            "Chats"
+        );
+
+        t.set_config_bool(Config::WatchFoldersMove, true).await?;
+        t.set_config_bool(Config::MvboxMove, false).await?;
+        assert_eq!(
+            target_folder(&t, "Chats", FolderMeaning::Extra, &chat_headers).await?,
+            "INBOX"
+        );
+        assert_eq!(
+            target_folder(&t, "Chats", FolderMeaning::Extra, &email_headers).await?,
+            "Chats"
+        );
+
+        t.set_config_bool(Config::MvboxMove, true).await?;
+        assert_eq!(
+            target_folder(&t, "Chats", FolderMeaning::Extra, &chat_headers).await?,
+            "DeltaChat"
+        );
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_get_imap_search_command() -> Result<()> {
         let t = TestContext::new_alice().await;
@@ -2793,4 +3221,50 @@ async fn test_get_imap_search_command() -> Result<()> {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_expired_trash_msgs() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        for (rfc724_mid, uid) in [("old@example.org", 1), ("new@example.org", 2), ("", 3)] {
+            t.sql
+                .execute(
+                    "INSERT INTO imap (rfc724_mid, folder, uid, target) VALUES (?,'INBOX',?,'Trash')",
+                    (rfc724_mid, uid),
+                )
+                .await?;
+        }
+        let row_ids: Vec<i64> = t
+            .sql
+            .query_map(
+                "SELECT id FROM imap",
+                (),
+                |row| row.get(0),
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        record_moved_to_trash(&t, &row_ids).await?;
+        assert_eq!(t.sql.count("SELECT COUNT(*) FROM imap_trash", ()).await?, 2);
+
+        // Messages which were not moved to Trash by Delta Chat are never expired.
+        t.sql
+            .execute(
+                "INSERT INTO imap (rfc724_mid, folder, uid, target) VALUES ('other@example.org','Trash',1,'Trash')",
+                (),
+            )
+            .await?;
+
+        let now = time();
+        t.sql
+            .execute(
+                "UPDATE imap_trash SET timestamp=? WHERE rfc724_mid='old@example.org'",
+                (now - 31 * 24 * 60 * 60,),
+            )
+            .await?;
+        assert_eq!(
+            get_expired_trash_msgs(&t, now - 30 * 24 * 60 * 60).await?,
+            vec!["old@example.org".to_string()]
+        );
+        assert_eq!(get_expired_trash_msgs(&t, now + 1).await?.len(), 2);
+        Ok(())
+    }
 }
@@ -21,6 +21,18 @@ pub(crate) struct Capabilities {
     /// <https://tools.ietf.org/html/rfc7162>
     pub can_condstore: bool,
 
+    /// True if the server has QRESYNC capability as defined in
+    /// <https://tools.ietf.org/html/rfc7162>
+    pub can_qresync: bool,
+
+    /// True if the server has METADATA capability as defined in
+    /// <https://tools.ietf.org/html/rfc5464>
+    pub can_metadata: bool,
+
+    /// True if the server has XDELTAPUSH capability,
+    /// i.e. sends push notifications to the device token stored in the metadata.
+    pub can_push: bool,
+
     /// Server ID if the server supports ID capability.
     pub server_id: Option<HashMap<String, String>>,
 }
@@ -12,8 +12,10 @@
 use super::session::Session;
 use crate::context::Context;
 use crate::net::connect_tcp;
+use crate::net::pinning::check_certificate_pin;
 use crate::net::session::SessionStream;
 use crate::net::tls::wrap_tls;
+use crate::net::traffic::{CountingStream, TrafficProtocol};
 use crate::socks::Socks5Config;
 
 /// IMAP write and read timeout.
@@ -53,11 +55,22 @@ async fn determine_capabilities(
     } else {
         None
     };
+    // QRESYNC has to be enabled explicitly, see
+    // <https://tools.ietf.org/html/rfc7162#section-3.2.3>.
+    // If enabling fails, we continue without it and use CONDSTORE only.
+    let can_qresync = caps.has_str("QRESYNC")
+        && session
+            .run_command_and_check_ok("ENABLE QRESYNC")
+            .await
+            .is_ok();
     let capabilities = Capabilities {
         can_idle: caps.has_str("IDLE"),
         can_move: caps.has_str("MOVE"),
         can_check_quota: caps.has_str("QUOTA"),
         can_condstore: caps.has_str("CONDSTORE"),
+        can_qresync,
+        can_metadata: caps.has_str("METADATA"),
+        can_push: caps.has_str("XDELTAPUSH"),
         server_id,
     };
     Ok(capabilities)
@@ -96,7 +109,12 @@ pub async fn connect_secure(
     ) -> Result<Self> {
         let tcp_stream = connect_tcp(context, hostname, port, IMAP_TIMEOUT, strict_tls).await?;
         let tls_stream = wrap_tls(strict_tls, hostname, tcp_stream).await?;
-        let buffered_stream = BufWriter::new(tls_stream);
+        check_certificate_pin(context, hostname, &tls_stream).await?;
+        let buffered_stream = BufWriter::new(CountingStream::new(
+            context,
+            TrafficProtocol::Imap,
+            tls_stream,
+        ));
         let session_stream: Box<dyn SessionStream> = Box::new(buffered_stream);
         let mut client = ImapClient::new(session_stream);
 
@@ -110,7 +128,11 @@ pub async fn connect_secure(
 
     pub async fn connect_insecure(context: &Context, hostname: &str, port: u16) -> Result<Self> {
         let tcp_stream = connect_tcp(context, hostname, port, IMAP_TIMEOUT, false).await?;
-        let buffered_stream = BufWriter::new(tcp_stream);
+        let buffered_stream = BufWriter::new(CountingStream::new(
+            context,
+            TrafficProtocol::Imap,
+            tcp_stream,
+        ));
         let session_stream: Box<dyn SessionStream> = Box::new(buffered_stream);
         let mut client = ImapClient::new(session_stream);
         let _greeting = client
@@ -144,8 +166,13 @@ pub async fn connect_starttls(
         let tls_stream = wrap_tls(strict_tls, hostname, tcp_stream)
             .await
             .context("STARTTLS upgrade failed")?;
+        check_certificate_pin(context, hostname, &tls_stream).await?;
 
-        let buffered_stream = BufWriter::new(tls_stream);
+        let buffered_stream = BufWriter::new(CountingStream::new(
+            context,
+            TrafficProtocol::Imap,
+            tls_stream,
+        ));
         let session_stream: Box<dyn SessionStream> = Box::new(buffered_stream);
         let client = ImapClient::new(session_stream);
 
@@ -163,7 +190,12 @@ pub async fn connect_secure_socks5(
             .connect(context, domain, port, IMAP_TIMEOUT, strict_tls)
             .await?;
         let tls_stream = wrap_tls(strict_tls, domain, socks5_stream).await?;
-        let buffered_stream = BufWriter::new(tls_stream);
+        check_certificate_pin(context, domain, &tls_stream).await?;
+        let buffered_stream = BufWriter::new(CountingStream::new(
+            context,
+            TrafficProtocol::Imap,
+            tls_stream,
+        ));
         let session_stream: Box<dyn SessionStream> = Box::new(buffered_stream);
         let mut client = ImapClient::new(session_stream);
         let _greeting = client
@@ -183,7 +215,11 @@ pub async fn connect_insecure_socks5(
         let socks5_stream = socks5_config
             .connect(context, domain, port, IMAP_TIMEOUT, false)
             .await?;
-        let buffered_stream = BufWriter::new(socks5_stream);
+        let buffered_stream = BufWriter::new(CountingStream::new(
+            context,
+            TrafficProtocol::Imap,
+            socks5_stream,
+        ));
         let session_stream: Box<dyn SessionStream> = Box::new(buffered_stream);
         let mut client = ImapClient::new(session_stream);
         let _greeting = client
@@ -220,7 +256,12 @@ pub async fn connect_starttls_socks5(
         let tls_stream = wrap_tls(strict_tls, hostname, socks5_stream)
             .await
             .context("STARTTLS upgrade failed")?;
-        let buffered_stream = BufWriter::new(tls_stream);
+        check_certificate_pin(context, hostname, &tls_stream).await?;
+        let buffered_stream = BufWriter::new(CountingStream::new(
+            context,
+            TrafficProtocol::Imap,
+            tls_stream,
+        ));
         let session_stream: Box<dyn SessionStream> = Box::new(buffered_stream);
         let client = ImapClient::new(session_stream);
 
@@ -3,6 +3,7 @@
 use anyhow::{bail, Context as _, Result};
 use async_channel::Receiver;
 use async_imap::extensions::idle::IdleResponse;
+use async_imap::imap_proto::Response;
 use futures_lite::FutureExt;
 
 use super::session::Session;
@@ -29,7 +30,7 @@ pub async fn idle(
 
         self.select_folder(context, watch_folder.as_deref()).await?;
 
-        if self.server_sent_unsolicited_exists(context)? {
+        if self.server_sent_unsolicited_exists(context).await? {
             return Ok((self, info));
         }
 
@@ -72,6 +73,11 @@ enum Event {
         match fut.await {
             Ok(Event::IdleResponse(IdleResponse::NewData(x))) => {
                 info!(context, "{}: Idle has NewData {:?}", folder_name, x);
+                if let Response::Vanished { uids, .. } = x.parsed() {
+                    for range in uids {
+                        self.vanished_uids.extend(range.clone());
+                    }
+                }
             }
             Ok(Event::IdleResponse(IdleResponse::Timeout)) => {
                 info!(
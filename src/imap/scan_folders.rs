@@ -1,6 +1,9 @@
-use std::{collections::BTreeMap, time::Instant};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
 
 use anyhow::{Context as _, Result};
+use futures::future::BoxFuture;
 use futures::stream::StreamExt;
 
 use super::{get_folder_meaning_by_attrs, get_folder_meaning_by_name};
@@ -11,6 +14,7 @@
 
 impl Imap {
     /// Returns true if folders were scanned, false if scanning was postponed.
+    #[tracing::instrument(skip_all, fields(account_id = context.id))]
     pub(crate) async fn scan_folders(&mut self, context: &Context) -> Result<bool> {
         // First of all, debounce to once per minute:
         let mut last_scan = context.last_full_folder_scan.lock().await;
@@ -24,6 +28,13 @@ pub(crate) async fn scan_folders(&mut self, context: &Context) -> Result<bool> {
                 return Ok(false);
             }
         }
+        if !context.folder_scan_queue.is_idle() {
+            // Folders queued by the previous scan are still queued or being scanned
+            // over other connections. Do not queue them again,
+            // scanning the same folder over two connections could lead to duplicate messages,
+            // but help emptying the queue, other loops may not take part in scanning.
+            return self.scan_queued_folders(context).await;
+        }
         info!(context, "Starting full folder scan");
 
         self.prepare(context).await?;
@@ -31,6 +42,7 @@ pub(crate) async fn scan_folders(&mut self, context: &Context) -> Result<bool> {
         let watched_folders = get_watched_folders(context).await?;
 
         let mut folder_configs = BTreeMap::new();
+        let mut folders_to_scan = VecDeque::new();
 
         for folder in folders {
             let folder_meaning = get_folder_meaning_by_attrs(folder.attributes());
@@ -63,26 +75,27 @@ pub(crate) async fn scan_folders(&mut self, context: &Context) -> Result<bool> {
                 && folder_meaning != FolderMeaning::Drafts
                 && folder_meaning != FolderMeaning::Trash
             {
-                let session = self.session.as_mut().context("no session")?;
-                // Drain leftover unsolicited EXISTS messages
-                session.server_sent_unsolicited_exists(context)?;
-
-                loop {
-                    self.fetch_move_delete(context, folder.name(), folder_meaning)
-                        .await
-                        .context("Can't fetch new msgs in scanned folder")
-                        .log_err(context)
-                        .ok();
-
-                    let session = self.session.as_mut().context("no session")?;
-                    // If the server sent an unsocicited EXISTS during the fetch, we need to fetch again
-                    if !session.server_sent_unsolicited_exists(context)? {
-                        break;
-                    }
-                }
+                folders_to_scan.push_back((folder.name().to_string(), folder_meaning));
             }
         }
 
+        // Other IMAP loops help scanning the queued folders
+        // over the connections they have open anyway.
+        let connections = get_scan_folders_connections(context).await?;
+        let folders_count = folders_to_scan.len();
+        context.folder_scan_queue.extend(folders_to_scan);
+        if connections > 1 && folders_count > 1 {
+            info!(
+                context,
+                "Scanning {} folders over up to {} connections.", folders_count, connections
+            );
+            context
+                .scheduler
+                .interrupt_folder_scan(connections.min(folders_count))
+                .await;
+        }
+        self.scan_queued_folders(context).await?;
+
         // Set configs for necessary folders. Or reset if the folder was deleted.
         for conf in [
             Config::ConfiguredSentboxFolder,
@@ -97,6 +110,44 @@ pub(crate) async fn scan_folders(&mut self, context: &Context) -> Result<bool> {
         Ok(true)
     }
 
+    /// Scans folders queued by [`Imap::scan_folders`] until the queue is empty.
+    ///
+    /// Returns true if at least one folder was scanned.
+    pub(crate) async fn scan_queued_folders(&mut self, context: &Context) -> Result<bool> {
+        let scanned = scan_queue(context, self, |imap, context, folder, meaning| {
+            Box::pin(imap.scan_folder(context, folder, meaning))
+        })
+        .await;
+        Ok(scanned)
+    }
+
+    /// Fetches new messages from a folder that is not watched.
+    async fn scan_folder(
+        &mut self,
+        context: &Context,
+        folder: &str,
+        folder_meaning: FolderMeaning,
+    ) -> Result<()> {
+        let session = self.session.as_mut().context("no session")?;
+        // Drain leftover unsolicited EXISTS messages
+        session.server_sent_unsolicited_exists(context).await?;
+
+        loop {
+            self.fetch_move_delete(context, folder, folder_meaning)
+                .await
+                .context("Can't fetch new msgs in scanned folder")
+                .log_err(context)
+                .ok();
+
+            let session = self.session.as_mut().context("no session")?;
+            // If the server sent an unsocicited EXISTS during the fetch, we need to fetch again
+            if !session.server_sent_unsolicited_exists(context).await? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     /// Returns the names of all folders on the IMAP server.
     pub async fn list_folders(
         self: &mut Imap,
@@ -128,6 +179,12 @@ pub(crate) async fn get_watched_folder_configs(context: &Context) -> Result<Vec<
 }
 
 pub(crate) async fn get_watched_folders(context: &Context) -> Result<Vec<String>> {
+    let mut res = get_standard_watched_folders(context).await?;
+    res.extend(get_extra_watched_folders(context).await?);
+    Ok(res)
+}
+
+async fn get_standard_watched_folders(context: &Context) -> Result<Vec<String>> {
     let mut res = Vec::new();
     for folder_config in get_watched_folder_configs(context).await? {
         if let Some(folder) = context.get_config(folder_config).await? {
@@ -136,3 +193,172 @@ pub(crate) async fn get_watched_folders(context: &Context) -> Result<Vec<String>
     }
     Ok(res)
 }
+
+/// Returns the folders configured in `Config::WatchFolders`
+/// that are not watched anyway as inbox, mvbox or sentbox.
+pub(crate) async fn get_extra_watched_folders(context: &Context) -> Result<Vec<String>> {
+    let standard_folders = get_standard_watched_folders(context).await?;
+    let mut res: Vec<String> = Vec::new();
+    let watch_folders = context
+        .get_config(Config::WatchFolders)
+        .await?
+        .unwrap_or_default();
+    for folder in watch_folders.lines().map(str::trim) {
+        if !folder.is_empty()
+            && !standard_folders.iter().any(|f| f == folder)
+            && !res.iter().any(|f| f == folder)
+        {
+            res.push(folder.to_string());
+        }
+    }
+    Ok(res)
+}
+
+/// Returns the maximum number of IMAP loops taking part in scanning folders that are not watched,
+/// see [`Config::ScanFoldersConnections`].
+pub(crate) async fn get_scan_folders_connections(context: &Context) -> Result<usize> {
+    let connections = context
+        .get_config_int(Config::ScanFoldersConnections)
+        .await?
+        .max(1);
+    Ok(usize::try_from(connections)?)
+}
+
+/// Folders that are not watched and are queued to be scanned for new messages.
+///
+/// The queue is filled by the inbox loop and emptied by the IMAP loops taking part in scanning,
+/// so every folder is scanned over exactly one connection.
+#[derive(Debug, Default)]
+pub(crate) struct FolderScanQueue {
+    inner: Mutex<FolderScanQueueInner>,
+}
+
+#[derive(Debug, Default)]
+struct FolderScanQueueInner {
+    folders: VecDeque<(String, FolderMeaning)>,
+
+    /// Number of folders taken from the queue which are still being scanned.
+    in_progress: usize,
+}
+
+impl FolderScanQueue {
+    /// Returns true if no folder is queued or being scanned.
+    pub(crate) fn is_idle(&self) -> bool {
+        let inner = self.inner.lock().expect("Mutex is poisoned");
+        inner.folders.is_empty() && inner.in_progress == 0
+    }
+
+    fn extend(&self, folders: impl IntoIterator<Item = (String, FolderMeaning)>) {
+        let mut inner = self.inner.lock().expect("Mutex is poisoned");
+        inner.folders.extend(folders);
+    }
+
+    /// Takes the next folder from the queue.
+    ///
+    /// The folder counts as being scanned until the returned guard is dropped.
+    fn pop(&self) -> Option<QueuedFolder<'_>> {
+        let mut inner = self.inner.lock().expect("Mutex is poisoned");
+        let (name, meaning) = inner.folders.pop_front()?;
+        inner.in_progress += 1;
+        Some(QueuedFolder {
+            queue: self,
+            name,
+            meaning,
+        })
+    }
+}
+
+/// Takes folders from [`Context::folder_scan_queue`] and scans them with `scan`
+/// until the queue is empty.
+///
+/// Errors are logged and the next folder is scanned,
+/// so a single failing folder does not leave the rest of the queue unscanned.
+/// Returns true if at least one folder was taken from the queue.
+async fn scan_queue<T, F>(context: &Context, target: &mut T, mut scan: F) -> bool
+where
+    F: for<'a> FnMut(&'a mut T, &'a Context, &'a str, FolderMeaning) -> BoxFuture<'a, Result<()>>,
+{
+    let mut scanned = false;
+    while let Some(folder) = context.folder_scan_queue.pop() {
+        scanned = true;
+        scan(target, context, &folder.name, folder.meaning)
+            .await
+            .with_context(|| format!("Can't scan folder {}", folder.name))
+            .log_err(context)
+            .ok();
+    }
+    scanned
+}
+
+/// Folder taken from [`FolderScanQueue`].
+struct QueuedFolder<'a> {
+    queue: &'a FolderScanQueue,
+    name: String,
+    meaning: FolderMeaning,
+}
+
+impl Drop for QueuedFolder<'_> {
+    fn drop(&mut self) {
+        let mut inner = self.queue.inner.lock().expect("Mutex is poisoned");
+        inner.in_progress -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::ensure;
+
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[test]
+    fn test_folder_scan_queue() {
+        let queue = FolderScanQueue::default();
+        assert!(queue.is_idle());
+
+        queue.extend([
+            ("Archive".to_string(), FolderMeaning::Unknown),
+            ("Spam".to_string(), FolderMeaning::Spam),
+        ]);
+        assert!(!queue.is_idle());
+
+        let first = queue.pop().unwrap();
+        assert_eq!(first.name, "Archive");
+        let second = queue.pop().unwrap();
+        assert_eq!(second.name, "Spam");
+        assert_eq!(second.meaning, FolderMeaning::Spam);
+        assert!(queue.pop().is_none());
+
+        // Folders which are still being scanned keep the queue busy.
+        assert!(!queue.is_idle());
+        drop(first);
+        assert!(!queue.is_idle());
+        drop(second);
+        assert!(queue.is_idle());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_scan_queue_failing_folder() {
+        let t = TestContext::new().await;
+        t.folder_scan_queue.extend([
+            ("Broken".to_string(), FolderMeaning::Unknown),
+            ("Archive".to_string(), FolderMeaning::Unknown),
+        ]);
+
+        let mut scanned = Vec::new();
+        let res = scan_queue(&t, &mut scanned, |scanned, _context, folder, _meaning| {
+            Box::pin(async move {
+                scanned.push(folder.to_string());
+                ensure!(folder != "Broken", "cannot select {folder}");
+                Ok(())
+            })
+        })
+        .await;
+
+        // The folder after the failing one is scanned and the queue is empty again,
+        // so the next full scan is not skipped.
+        assert!(res);
+        assert_eq!(scanned, ["Broken", "Archive"]);
+        assert!(t.folder_scan_queue.is_idle());
+    }
+}
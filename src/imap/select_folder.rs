@@ -2,7 +2,10 @@
 
 use anyhow::Context as _;
 
+use async_imap::types::Mailbox;
+
 use super::session::Session as ImapSession;
+use super::{get_modseq, get_uidvalidity};
 use crate::context::Context;
 
 type Result<T> = std::result::Result<T, Error>;
@@ -66,12 +69,25 @@ pub(super) async fn select_folder(
             }
         }
 
+        // VANISHED responses refer to the currently selected folder,
+        // so they have to be processed before another folder is selected.
+        self.delete_vanished(context).await?;
+
         // deselect existing folder, if needed (it's also done implicitly by SELECT, however, without EXPUNGE then)
         self.maybe_close_folder(context).await?;
 
         // select new folder
         if let Some(folder) = folder {
-            let res = if self.can_condstore() {
+            let res = if self.can_qresync() {
+                let uid_validity = get_uidvalidity(context, folder).await?;
+                let modseq = get_modseq(context, folder).await?;
+                if uid_validity > 0 && modseq > 0 {
+                    self.select_qresync(context, folder, uid_validity, modseq)
+                        .await
+                } else {
+                    self.select_condstore(folder).await
+                }
+            } else if self.can_condstore() {
                 self.select_condstore(folder).await
             } else {
                 self.select(folder).await
@@ -101,6 +117,73 @@ pub(super) async fn select_folder(
         }
     }
 
+    /// Selects a folder passing the known UIDVALIDITY and MODSEQ as QRESYNC parameters,
+    /// see <https://www.rfc-editor.org/rfc/rfc7162#section-3.2.5>.
+    ///
+    /// The server then reports messages expunged since the last synchronization
+    /// with a `VANISHED (EARLIER)` response, so messages deleted while Delta Chat
+    /// was offline are removed from the database without rescanning the folder.
+    async fn select_qresync(
+        &mut self,
+        context: &Context,
+        folder: &str,
+        uid_validity: u32,
+        modseq: u64,
+    ) -> async_imap::error::Result<Mailbox> {
+        use async_imap::imap_proto::{Response, ResponseCode};
+        use async_imap::types::UnsolicitedResponse;
+
+        // Responses left from the previously selected folder must not be mixed
+        // with the responses to SELECT.
+        // Its vanished UIDs were already removed by `select_folder()`.
+        self.drain_unsolicited_responses(context);
+        self.vanished_uids.clear();
+
+        if let Some(c) = folder.chars().find(|c| *c == '\r' || *c == '\n') {
+            return Err(async_imap::error::Error::Validate(
+                async_imap::error::ValidateError(c),
+            ));
+        }
+        let quoted = folder.replace('\\', "\\\\").replace('"', "\\\"");
+        self.run_command_and_check_ok(&format!(
+            "SELECT \"{quoted}\" (QRESYNC ({uid_validity} {modseq}))"
+        ))
+        .await?;
+
+        // Untagged responses to SELECT are delivered as unsolicited responses.
+        let mut mailbox = Mailbox::default();
+        while let Ok(response) = self.unsolicited_responses.try_recv() {
+            match response {
+                UnsolicitedResponse::Exists(exists) => mailbox.exists = exists,
+                UnsolicitedResponse::Recent(recent) => mailbox.recent = recent,
+                UnsolicitedResponse::Other(response_data) => match response_data.parsed() {
+                    Response::Data {
+                        code: Some(code), ..
+                    } => match code {
+                        ResponseCode::UidValidity(uid_validity) => {
+                            mailbox.uid_validity = Some(*uid_validity)
+                        }
+                        ResponseCode::UidNext(uid_next) => mailbox.uid_next = Some(*uid_next),
+                        ResponseCode::HighestModSeq(modseq) => {
+                            mailbox.highest_modseq = Some(*modseq)
+                        }
+                        ResponseCode::Unseen(unseen) => mailbox.unseen = Some(*unseen),
+                        _ => {}
+                    },
+                    Response::Vanished { uids, .. } => {
+                        for range in uids {
+                            self.vanished_uids.extend(range.clone());
+                        }
+                    }
+                    // Flag changes are synchronized by `sync_seen_flags()`.
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        Ok(mailbox)
+    }
+
     /// Selects a folder. Tries to create it once and select again if the folder does not exist.
     pub(super) async fn select_or_create_folder(
         &mut self,
@@ -19,6 +19,14 @@ pub(crate) struct Session {
     pub selected_mailbox: Option<Mailbox>,
 
     pub selected_folder_needs_expunge: bool,
+
+    /// True if an unsolicited EXISTS response was received
+    /// but not yet reported by `server_sent_unsolicited_exists()`.
+    pub(super) new_mail: bool,
+
+    /// UIDs in the selected folder reported as expunged by `VANISHED` responses
+    /// but not yet removed by `delete_vanished()`.
+    pub(super) vanished_uids: Vec<u32>,
 }
 
 impl Deref for Session {
@@ -46,6 +54,8 @@ pub(crate) fn new(
             selected_folder: None,
             selected_mailbox: None,
             selected_folder_needs_expunge: false,
+            new_mail: false,
+            vanished_uids: Vec::new(),
         }
     }
 
@@ -64,4 +74,13 @@ pub fn can_check_quota(&self) -> bool {
     pub fn can_condstore(&self) -> bool {
         self.capabilities.can_condstore
     }
+
+    pub fn can_qresync(&self) -> bool {
+        self.capabilities.can_qresync
+    }
+
+    /// Returns true if the server can send push notifications.
+    pub fn can_push(&self) -> bool {
+        self.capabilities.can_push && self.capabilities.can_metadata
+    }
 }
@@ -93,6 +93,7 @@ impl BackupProvider {
     ///
     /// [`Accounts::stop_io`]: crate::accounts::Accounts::stop_io
     pub async fn prepare(context: &Context) -> Result<Self> {
+        context.ensure_network_allowed()?;
         e2ee::ensure_secret_key_exists(context)
             .await
             .context("Private key not available, aborting backup export")?;
@@ -389,6 +390,7 @@ pub async fn get_backup(context: &Context, qr: Qr) -> Result<()> {
         matches!(qr, Qr::Backup { .. }),
         "QR code for backup must be of type DCBACKUP"
     );
+    context.ensure_network_allowed()?;
     ensure!(
         !context.is_configured().await?,
         "Cannot import backups to accounts in use."
@@ -0,0 +1,1003 @@
+//! # JMAP transport.
+//!
+//! Alternative to IMAP and SMTP for servers speaking JMAP,
+//! see <https://www.rfc-editor.org/rfc/rfc8620> and <https://www.rfc-editor.org/rfc/rfc8621>.
+//!
+//! Whether JMAP is used is decided once when configuring the account, see [`discover`]:
+//! the session resource is looked up at the `jmap_url` config option if it is set
+//! and at `/.well-known/jmap` of the email domain otherwise.
+//! If found, its URL is stored as `configured_jmap_url`
+//! and the scheduler uses JMAP instead of IMAP and SMTP.
+//! New emails are found with `Email/changes` since the last known state,
+//! downloaded as raw RFC 5322 blobs and passed to `receive_imf()`.
+//! Outgoing messages are uploaded as blobs, imported to the drafts mailbox
+//! and submitted with `EmailSubmission/set`, which destroys the draft on success.
+//! Instead of IMAP IDLE, the EventSource push endpoint is used to wait for changes.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use anyhow::{bail, ensure, format_err, Context as _, Result};
+use async_channel::Receiver;
+use async_imap::types::{QuotaResource, QuotaResourceName};
+use futures_lite::FutureExt;
+use mailparse::SingleInfo;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::header;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::constants::DC_FETCH_EXISTING_MSGS_COUNT;
+use crate::context::Context;
+use crate::imap::{add_recipients_as_contacts, prefetch_get_or_create_message_id};
+use crate::log::LogExt;
+use crate::login_param::LoginParam;
+use crate::message::rfc724_mid_exists;
+use crate::net::http::get_client;
+use crate::receive_imf::receive_imf_inner;
+use crate::scheduler::InterruptInfo;
+
+const CAPABILITY_CORE: &str = "urn:ietf:params:jmap:core";
+const CAPABILITY_MAIL: &str = "urn:ietf:params:jmap:mail";
+const CAPABILITY_SUBMISSION: &str = "urn:ietf:params:jmap:submission";
+const CAPABILITY_QUOTA: &str = "urn:ietf:params:jmap:quota";
+
+/// Raw config key storing the `Email` state of the last fetch.
+const EMAIL_STATE_KEY: &str = "jmap_email_state";
+
+/// Maximum number of changes fetched with a single `Email/changes` call.
+const MAX_CHANGES: u32 = 100;
+
+/// Maximum number of the most recent emails checked when resynchronizing.
+const RESYNC_LIMIT: i64 = 1000;
+
+/// How long to wait for a push notification before polling anyway.
+const PUSH_TIMEOUT: Duration = Duration::from_secs(23 * 60);
+
+/// Types of `SetError` returned by `Email/import` and `EmailSubmission/set`
+/// for which retrying to send the same message will not help,
+/// see <https://www.rfc-editor.org/rfc/rfc8621#section-7.5>.
+const PERMANENT_SET_ERRORS: &[&str] = &[
+    "invalidProperties",
+    "invalidEmail",
+    "tooLarge",
+    "tooManyRecipients",
+    "noRecipients",
+    "invalidRecipients",
+    "forbiddenMailFrom",
+    "forbiddenFrom",
+    "forbiddenToSend",
+];
+
+/// JMAP session resource, see <https://www.rfc-editor.org/rfc/rfc8620#section-2>.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Session {
+    capabilities: HashMap<String, Value>,
+    api_url: String,
+    download_url: String,
+    upload_url: String,
+    event_source_url: String,
+    primary_accounts: HashMap<String, String>,
+}
+
+/// Error returned by [`Jmap::send`].
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum SendError {
+    /// The server rejected the message, sending it again will fail again.
+    #[error("JMAP server rejected the message: {0}")]
+    Permanent(String),
+    #[error("{0:#}")]
+    Other(#[from] anyhow::Error),
+}
+
+/// Method-level error, see <https://www.rfc-editor.org/rfc/rfc8620#section-3.6.2>.
+#[derive(Debug, thiserror::Error)]
+#[error("JMAP method call failed: {0}")]
+struct MethodError(Value);
+
+impl MethodError {
+    fn error_type(&self) -> Option<&str> {
+        self.0.get("type").and_then(Value::as_str)
+    }
+}
+
+/// JMAP connection.
+#[derive(Debug, Clone)]
+pub(crate) struct Jmap {
+    client: reqwest::Client,
+
+    /// URL of the session resource.
+    url: String,
+
+    user: String,
+    password: String,
+
+    /// Session resource, fetched on first use.
+    session: Option<Session>,
+}
+
+/// Looks up the JMAP session resource while configuring the account.
+///
+/// Uses the `jmap_url` config option if set and fails if there is no usable session resource.
+/// Otherwise tries autodiscovery at `/.well-known/jmap` of the email domain,
+/// see <https://www.rfc-editor.org/rfc/rfc8620#section-2.2>,
+/// and returns `None` if the server does not speak JMAP.
+pub(crate) async fn discover(
+    context: &Context,
+    param: &LoginParam,
+    domain: &str,
+) -> Result<Option<String>> {
+    let user_url = context
+        .get_config(Config::JmapUrl)
+        .await?
+        .filter(|url| !url.is_empty());
+    if user_url.is_none() && param.socks5_config.is_some() {
+        // HTTP requests are not done through SOCKS5 yet, do not leak the IP address.
+        return Ok(None);
+    }
+    let url = user_url
+        .clone()
+        .unwrap_or_else(|| format!("https://{domain}/.well-known/jmap"));
+
+    let mut jmap = Jmap {
+        client: get_client(context, param.socks5_config.clone())?,
+        url: url.clone(),
+        user: param.imap.user.clone(),
+        password: param.imap.password.clone(),
+        session: None,
+    };
+    let res = async {
+        jmap.connect().await?;
+        let capabilities = &jmap.session()?.capabilities;
+        for capability in [CAPABILITY_MAIL, CAPABILITY_SUBMISSION] {
+            if !capabilities.contains_key(capability) {
+                bail!("JMAP server does not support {capability}");
+            }
+        }
+        jmap.account_id()
+    }
+    .await;
+    match res {
+        Ok(_) => {
+            info!(context, "Using JMAP session resource at {url}.");
+            Ok(Some(url))
+        }
+        Err(err) if user_url.is_some() => {
+            Err(err.context(format!("Cannot use JMAP session resource at {url}")))
+        }
+        Err(err) => {
+            info!(context, "No JMAP autodiscovery for {domain}: {err:#}.");
+            Ok(None)
+        }
+    }
+}
+
+impl Jmap {
+    /// Creates a JMAP connection using the configured credentials.
+    /// Returns `None` if JMAP was not found when configuring the account.
+    pub(crate) async fn new_configured(context: &Context) -> Result<Option<Self>> {
+        let url = match context.get_config(Config::ConfiguredJmapUrl).await? {
+            Some(url) if !url.is_empty() => url,
+            _ => return Ok(None),
+        };
+        if !context.is_configured().await? {
+            bail!("JMAP connect without configured params");
+        }
+        let param = LoginParam::load_configured_params(context).await?;
+        let client = get_client(context, param.socks5_config)?;
+        Ok(Some(Self {
+            client,
+            url,
+            user: param.imap.user,
+            password: param.imap.password,
+            session: None,
+        }))
+    }
+
+    /// Fetches the session resource if not done yet.
+    async fn connect(&mut self) -> Result<()> {
+        if self.session.is_some() {
+            return Ok(());
+        }
+        let session: Session = self
+            .client
+            .get(&self.url)
+            .basic_auth(&self.user, Some(&self.password))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Cannot parse JMAP session resource")?;
+        self.session = Some(session);
+        Ok(())
+    }
+
+    /// Drops the session so that it is fetched again on next use.
+    pub(crate) fn trigger_reconnect(&mut self) {
+        self.session = None;
+    }
+
+    fn session(&self) -> Result<&Session> {
+        self.session.as_ref().context("No JMAP session")
+    }
+
+    /// Returns the ID of the primary mail account.
+    fn account_id(&self) -> Result<String> {
+        self.session()?
+            .primary_accounts
+            .get(CAPABILITY_MAIL)
+            .cloned()
+            .context("No primary JMAP mail account")
+    }
+
+    /// Sends a JMAP API request and returns the names and arguments of the method responses.
+    ///
+    /// Fails if any of the method calls returned an error.
+    /// Use [`get_response`] to access the arguments of a particular method response.
+    async fn call(&self, using: &[&str], method_calls: Value) -> Result<Vec<(String, Value)>> {
+        let api_url = &self.session()?.api_url;
+        let response: Value = self
+            .client
+            .post(api_url)
+            .basic_auth(&self.user, Some(&self.password))
+            .json(&json!({ "using": using, "methodCalls": method_calls }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Cannot parse JMAP response")?;
+        let method_responses = response
+            .get("methodResponses")
+            .and_then(Value::as_array)
+            .context("JMAP response has no methodResponses")?;
+
+        let mut res = Vec::with_capacity(method_responses.len());
+        for method_response in method_responses {
+            let name = method_response.get(0).and_then(Value::as_str);
+            let arguments = method_response.get(1).cloned().unwrap_or_default();
+            if name == Some("error") {
+                return Err(MethodError(arguments).into());
+            }
+            res.push((name.unwrap_or_default().to_string(), arguments));
+        }
+        Ok(res)
+    }
+
+    /// Downloads the raw content of a blob.
+    async fn download_blob(&self, account_id: &str, blob_id: &str) -> Result<Vec<u8>> {
+        let url = self
+            .session()?
+            .download_url
+            .replace("{accountId}", &urlencode(account_id))
+            .replace("{blobId}", &urlencode(blob_id))
+            .replace("{name}", "message.eml")
+            .replace("{type}", &urlencode("message/rfc822"));
+        let blob = self
+            .client
+            .get(url)
+            .basic_auth(&self.user, Some(&self.password))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        Ok(blob.into())
+    }
+
+    /// Uploads a blob and returns its ID.
+    async fn upload_blob(
+        &self,
+        account_id: &str,
+        content_type: &str,
+        data: &[u8],
+    ) -> Result<String> {
+        let url = self
+            .session()?
+            .upload_url
+            .replace("{accountId}", &urlencode(account_id));
+        let response: Value = self
+            .client
+            .post(url)
+            .basic_auth(&self.user, Some(&self.password))
+            .header(header::CONTENT_TYPE, content_type)
+            .body(data.to_vec())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let blob_id = response
+            .get("blobId")
+            .and_then(Value::as_str)
+            .context("JMAP upload response has no blobId")?;
+        Ok(blob_id.to_string())
+    }
+
+    /// Fetches emails created since the last fetch and passes them to `receive_imf()`.
+    ///
+    /// On the first call, existing emails are not fetched,
+    /// just as existing messages are not fetched when selecting an IMAP folder for the first time.
+    /// Returns true if at least one email was fetched.
+    pub(crate) async fn fetch_new_messages(&mut self, context: &Context) -> Result<bool> {
+        self.connect().await?;
+        let account_id = self.account_id()?;
+
+        let mut state = match context.sql.get_raw_config(EMAIL_STATE_KEY).await? {
+            Some(state) => state,
+            None => {
+                let res = self
+                    .call(
+                        &[CAPABILITY_CORE, CAPABILITY_MAIL],
+                        json!([["Email/get", { "accountId": account_id, "ids": [] }, "0"]]),
+                    )
+                    .await?;
+                let state = get_str(get_response(&res, 0, "Email/get")?, "state")?;
+                info!(context, "JMAP: Starting to fetch from state {state}.");
+                context
+                    .sql
+                    .set_raw_config(EMAIL_STATE_KEY, Some(&state))
+                    .await?;
+                return Ok(false);
+            }
+        };
+
+        let mut fetched = false;
+        loop {
+            let res = match self
+                .call(
+                    &[CAPABILITY_CORE, CAPABILITY_MAIL],
+                    json!([
+                        ["Email/changes", {
+                            "accountId": account_id,
+                            "sinceState": state,
+                            "maxChanges": MAX_CHANGES,
+                        }, "0"],
+                        ["Email/get", {
+                            "accountId": account_id,
+                            "#ids": { "resultOf": "0", "name": "Email/changes", "path": "/created" },
+                            "properties": ["blobId", "keywords"],
+                        }, "1"],
+                    ]),
+                )
+                .await
+            {
+                Ok(res) => res,
+                Err(err)
+                    if err
+                        .downcast_ref::<MethodError>()
+                        .and_then(MethodError::error_type)
+                        == Some("cannotCalculateChanges") =>
+                {
+                    // The state is too old, the server does not know the changes anymore.
+                    info!(context, "JMAP: Cannot get changes since {state}, resyncing.");
+                    return Ok(self.resync(context).await? || fetched);
+                }
+                Err(err) => return Err(err),
+            };
+            let changes = get_response(&res, 0, "Email/changes")?;
+            let emails = get_response(&res, 1, "Email/get")?
+                .get("list")
+                .and_then(Value::as_array)
+                .context("JMAP Email/get response has no list")?;
+
+            for email in emails {
+                if self
+                    .receive_email(context, &account_id, email, false)
+                    .await?
+                {
+                    fetched = true;
+                }
+            }
+
+            state = get_str(changes, "newState")?;
+            context
+                .sql
+                .set_raw_config(EMAIL_STATE_KEY, Some(&state))
+                .await?;
+            if !changes
+                .get("hasMoreChanges")
+                .and_then(Value::as_bool)
+                .unwrap_or_default()
+            {
+                break;
+            }
+        }
+        Ok(fetched)
+    }
+
+    /// Downloads an email returned by `Email/get` and passes it to `receive_imf()`.
+    ///
+    /// Returns true if the email was received.
+    /// Errors of `receive_imf()` are logged, only download errors are returned.
+    async fn receive_email(
+        &self,
+        context: &Context,
+        account_id: &str,
+        email: &Value,
+        fetching_existing_messages: bool,
+    ) -> Result<bool> {
+        let blob_id = get_str(email, "blobId")?;
+        let seen = email
+            .pointer("/keywords/$seen")
+            .and_then(Value::as_bool)
+            .unwrap_or_default();
+        let raw = self.download_blob(account_id, &blob_id).await?;
+        let res = async {
+            let mail = mailparse::parse_mail(&raw).context("can't parse mail")?;
+            let rfc724_mid = prefetch_get_or_create_message_id(&mail.headers);
+            receive_imf_inner(
+                context,
+                &rfc724_mid,
+                &raw,
+                seen,
+                None,
+                fetching_existing_messages,
+            )
+            .await
+        }
+        .await
+        .with_context(|| format!("Failed to receive JMAP blob {blob_id}"))
+        .log_err(context);
+        Ok(res.is_ok())
+    }
+
+    /// Queries the most recent emails matching `filter`
+    /// and returns them sorted from the oldest to the newest
+    /// together with the `Email` state at the time of the query.
+    async fn query_emails(
+        &self,
+        account_id: &str,
+        filter: Value,
+        limit: i64,
+        properties: &[&str],
+    ) -> Result<(String, Vec<Value>)> {
+        let res = self
+            .call(
+                &[CAPABILITY_CORE, CAPABILITY_MAIL],
+                json!([
+                    ["Email/query", {
+                        "accountId": account_id,
+                        "filter": filter,
+                        "sort": [{ "property": "receivedAt", "isAscending": false }],
+                        "limit": limit,
+                    }, "0"],
+                    ["Email/get", {
+                        "accountId": account_id,
+                        "#ids": { "resultOf": "0", "name": "Email/query", "path": "/ids" },
+                        "properties": properties,
+                    }, "1"],
+                ]),
+            )
+            .await?;
+        let email_get = get_response(&res, 1, "Email/get")?;
+        let state = get_str(email_get, "state")?;
+        let mut emails = email_get
+            .get("list")
+            .and_then(Value::as_array)
+            .cloned()
+            .context("JMAP Email/get response has no list")?;
+        emails.reverse();
+        Ok((state, emails))
+    }
+
+    /// Downloads the emails which are not in the database yet.
+    ///
+    /// Returns true if at least one email was received.
+    async fn receive_unknown_emails(
+        &self,
+        context: &Context,
+        account_id: &str,
+        emails: &[Value],
+        fetching_existing_messages: bool,
+    ) -> Result<bool> {
+        let mut fetched = false;
+        for email in emails {
+            let message_id = email.pointer("/messageId/0").and_then(Value::as_str);
+            if let Some(message_id) = message_id {
+                if rfc724_mid_exists(context, message_id).await?.is_some() {
+                    continue;
+                }
+            }
+            if self
+                .receive_email(context, account_id, email, fetching_existing_messages)
+                .await?
+            {
+                fetched = true;
+            }
+        }
+        Ok(fetched)
+    }
+
+    /// Fetches the most recent emails which are not in the database yet
+    /// and continues fetching new emails from the current state.
+    ///
+    /// This is the JMAP equivalent of resynchronizing IMAP UIDs.
+    /// It is done on request, e.g. after switching to another account,
+    /// and if the server cannot calculate the changes since the stored state.
+    /// Returns true if at least one email was fetched.
+    pub(crate) async fn resync(&mut self, context: &Context) -> Result<bool> {
+        self.connect().await?;
+        let account_id = self.account_id()?;
+        let (state, emails) = self
+            .query_emails(
+                &account_id,
+                json!({}),
+                RESYNC_LIMIT,
+                &["messageId", "blobId", "keywords"],
+            )
+            .await?;
+        let fetched = self
+            .receive_unknown_emails(context, &account_id, &emails, false)
+            .await?;
+        info!(context, "JMAP: Resynced, continuing from state {state}.");
+        context
+            .sql
+            .set_raw_config(EMAIL_STATE_KEY, Some(&state))
+            .await?;
+        Ok(fetched)
+    }
+
+    /// Adds the recipients of sent emails as contacts and, if `fetch_existing_msgs` is enabled,
+    /// fetches the most recent emails of the inbox and the sent mailbox.
+    ///
+    /// This is the JMAP equivalent of `Imap::fetch_existing_msgs()`.
+    pub(crate) async fn fetch_existing_msgs(&mut self, context: &Context) -> Result<()> {
+        if context.get_config_bool(Config::Bot).await? {
+            return Ok(()); // Bots don't want those messages
+        }
+        self.connect().await?;
+        let account_id = self.account_id()?;
+
+        let res = self
+            .call(
+                &[CAPABILITY_CORE, CAPABILITY_MAIL],
+                json!([
+                    ["Mailbox/query", { "accountId": account_id, "filter": { "role": "sent" } }, "0"],
+                    ["Mailbox/query", { "accountId": account_id, "filter": { "role": "inbox" } }, "1"],
+                ]),
+            )
+            .await?;
+        let sent_mailbox_id = get_response(&res, 0, "Mailbox/query")?
+            .pointer("/ids/0")
+            .and_then(Value::as_str);
+        let inbox_mailbox_id = get_response(&res, 1, "Mailbox/query")?
+            .pointer("/ids/0")
+            .and_then(Value::as_str);
+
+        if let Some(sent_mailbox_id) = sent_mailbox_id {
+            let (_, emails) = self
+                .query_emails(
+                    &account_id,
+                    json!({ "inMailbox": sent_mailbox_id }),
+                    RESYNC_LIMIT,
+                    &["to", "cc", "bcc"],
+                )
+                .await
+                .context("failed to get recipients from the sent mailbox")?;
+            let recipients = emails
+                .iter()
+                .flat_map(|email| {
+                    ["to", "cc", "bcc"]
+                        .iter()
+                        .filter_map(|key| email.get(key).and_then(Value::as_array))
+                        .flatten()
+                })
+                .filter_map(|address| {
+                    Some(SingleInfo {
+                        display_name: address
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .map(|s| s.to_string()),
+                        addr: address.get("email")?.as_str()?.to_string(),
+                    })
+                })
+                .collect();
+            add_recipients_as_contacts(context, recipients).await?;
+        }
+
+        if context.get_config_bool(Config::FetchExistingMsgs).await? {
+            for mailbox_id in [inbox_mailbox_id, sent_mailbox_id].into_iter().flatten() {
+                let (_, emails) = self
+                    .query_emails(
+                        &account_id,
+                        json!({ "inMailbox": mailbox_id }),
+                        DC_FETCH_EXISTING_MSGS_COUNT,
+                        &["messageId", "blobId", "keywords"],
+                    )
+                    .await?;
+                self.receive_unknown_emails(context, &account_id, &emails, true)
+                    .await
+                    .context("could not fetch existing messages")?;
+            }
+        }
+
+        info!(context, "Done fetching existing messages.");
+        Ok(())
+    }
+
+    /// Returns the usage of all quotas,
+    /// see <https://www.rfc-editor.org/rfc/rfc9425>.
+    ///
+    /// The quotas are returned in the form of IMAP QUOTA resources,
+    /// sizes are converted to units of 1024 bytes as used by the IMAP `STORAGE` resource.
+    /// Returns an empty map if the server does not support quotas.
+    pub(crate) async fn get_quota(&mut self) -> Result<BTreeMap<String, Vec<QuotaResource>>> {
+        self.connect().await?;
+        if !self.session()?.capabilities.contains_key(CAPABILITY_QUOTA) {
+            return Ok(BTreeMap::new());
+        }
+        let account_id = self.account_id()?;
+        let res = self
+            .call(
+                &[CAPABILITY_CORE, CAPABILITY_QUOTA],
+                json!([["Quota/get", { "accountId": account_id, "ids": null }, "0"]]),
+            )
+            .await?;
+        let quotas = get_response(&res, 0, "Quota/get")?
+            .get("list")
+            .and_then(Value::as_array)
+            .context("JMAP Quota/get response has no list")?;
+        Ok(parse_quotas(quotas))
+    }
+
+    /// Sends a message to the given recipients.
+    pub(crate) async fn send(
+        &mut self,
+        context: &Context,
+        recipients: &[&str],
+        message: &[u8],
+    ) -> std::result::Result<(), SendError> {
+        self.connect().await?;
+        let account_id = self.account_id()?;
+        let from = context.get_primary_self_addr().await?;
+        let using = [CAPABILITY_CORE, CAPABILITY_MAIL, CAPABILITY_SUBMISSION];
+
+        let res = self
+            .call(
+                &using,
+                json!([
+                    ["Mailbox/query", { "accountId": account_id, "filter": { "role": "drafts" } }, "0"],
+                    ["Identity/get", { "accountId": account_id }, "1"],
+                ]),
+            )
+            .await?;
+        let drafts_mailbox_id = get_response(&res, 0, "Mailbox/query")?
+            .pointer("/ids/0")
+            .and_then(Value::as_str)
+            .context("No JMAP drafts mailbox")?;
+        let identities = get_response(&res, 1, "Identity/get")?
+            .get("list")
+            .and_then(Value::as_array)
+            .context("JMAP Identity/get response has no list")?;
+        let identity = identities
+            .iter()
+            .find(|identity| {
+                identity
+                    .get("email")
+                    .and_then(Value::as_str)
+                    .map_or(false, |email| email.eq_ignore_ascii_case(&from))
+            })
+            .or_else(|| identities.first())
+            .context("No JMAP identity")?;
+        let identity_id = get_str(identity, "id")?;
+
+        let blob_id = self
+            .upload_blob(&account_id, "message/rfc822", message)
+            .await?;
+        let rcpt_to: Vec<Value> = recipients
+            .iter()
+            .map(|addr| json!({ "email": addr }))
+            .collect();
+        let res = self
+            .call(
+                &using,
+                json!([
+                    ["Email/import", {
+                        "accountId": account_id,
+                        "emails": { "msg": {
+                            "blobId": blob_id,
+                            "mailboxIds": { drafts_mailbox_id: true },
+                            "keywords": { "$seen": true, "$draft": true },
+                        } },
+                    }, "0"],
+                    ["EmailSubmission/set", {
+                        "accountId": account_id,
+                        "create": { "submission": {
+                            "emailId": "#msg",
+                            "identityId": identity_id,
+                            "envelope": {
+                                "mailFrom": { "email": from },
+                                "rcptTo": rcpt_to,
+                            },
+                        } },
+                        "onSuccessDestroyEmail": ["#submission"],
+                    }, "1"],
+                ]),
+            )
+            .await?;
+        for (_, response) in &res {
+            if let Some(not_created) = response.get("notCreated").and_then(Value::as_object) {
+                for set_error in not_created.values() {
+                    let error_type = set_error.get("type").and_then(Value::as_str);
+                    if error_type.map_or(false, |t| PERMANENT_SET_ERRORS.contains(&t)) {
+                        return Err(SendError::Permanent(set_error.to_string()));
+                    }
+                }
+                if !not_created.is_empty() {
+                    return Err(format_err!("JMAP submission failed: {not_created:?}").into());
+                }
+            }
+        }
+        info!(
+            context,
+            "Message len={} was sent over JMAP to {}",
+            message.len(),
+            recipients.join(",")
+        );
+        Ok(())
+    }
+
+    /// Waits for changed emails using the EventSource push endpoint.
+    ///
+    /// Returns when the server reports a new `Email` state, on interrupt,
+    /// or after a timeout so that emails are fetched anyway from time to time.
+    pub(crate) async fn idle(
+        &mut self,
+        context: &Context,
+        idle_interrupt_receiver: Receiver<InterruptInfo>,
+    ) -> Result<InterruptInfo> {
+        self.connect().await?;
+        let account_id = self.account_id()?;
+        let known_state = context.sql.get_raw_config(EMAIL_STATE_KEY).await?;
+        let url = self
+            .session()?
+            .event_source_url
+            .replace("{types}", "Email")
+            .replace("{closeafter}", "no")
+            .replace("{ping}", "60");
+
+        let wait_for_change = async {
+            let mut response = self
+                .client
+                .get(url)
+                .basic_auth(&self.user, Some(&self.password))
+                .header(header::ACCEPT, "text/event-stream")
+                .timeout(PUSH_TIMEOUT)
+                .send()
+                .await?
+                .error_for_status()?;
+            info!(context, "JMAP: Waiting for push notifications.");
+
+            let mut buf = String::new();
+            while let Some(chunk) = response.chunk().await? {
+                buf.push_str(&String::from_utf8_lossy(&chunk));
+                // Events are separated by an empty line,
+                // see <https://html.spec.whatwg.org/multipage/server-sent-events.html>.
+                while let Some(len) = event_len(&buf) {
+                    let event: String = buf.drain(..len).collect();
+                    if let Some(new_state) = parse_email_state_change(&event, &account_id) {
+                        if known_state.as_deref() != Some(&new_state) {
+                            info!(context, "JMAP: Email state changed to {new_state}.");
+                            return Ok(InterruptInfo::new(false));
+                        }
+                    }
+                }
+            }
+            Ok::<_, anyhow::Error>(InterruptInfo::new(false))
+        };
+        let interrupt = async {
+            Ok::<_, anyhow::Error>(idle_interrupt_receiver.recv().await.unwrap_or_default())
+        };
+
+        match wait_for_change.race(interrupt).await {
+            Err(err) if is_timeout(&err) => Ok(InterruptInfo::new(false)),
+            res => res,
+        }
+    }
+}
+
+fn urlencode(s: &str) -> String {
+    utf8_percent_encode(s, NON_ALPHANUMERIC).to_string()
+}
+
+fn get_str(value: &Value, key: &str) -> Result<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(|s| s.to_string())
+        .with_context(|| format!("JMAP response has no {key}"))
+}
+
+/// Converts JMAP `Quota` objects to IMAP QUOTA resources grouped by quota name.
+fn parse_quotas(quotas: &[Value]) -> BTreeMap<String, Vec<QuotaResource>> {
+    let mut res: BTreeMap<String, Vec<QuotaResource>> = BTreeMap::new();
+    for quota in quotas {
+        let used = quota.get("used").and_then(Value::as_u64);
+        let limit = quota.get("hardLimit").and_then(Value::as_u64);
+        let (used, limit) = match (used, limit) {
+            (Some(used), Some(limit)) => (used, limit),
+            _ => continue,
+        };
+        let resource = match quota.get("resourceType").and_then(Value::as_str) {
+            Some("octets") => QuotaResource {
+                name: QuotaResourceName::Storage,
+                usage: used / 1024,
+                limit: limit / 1024,
+            },
+            Some("count") => QuotaResource {
+                name: QuotaResourceName::Message,
+                usage: used,
+                limit,
+            },
+            _ => continue,
+        };
+        let name = quota
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        res.entry(name).or_default().push(resource);
+    }
+    res
+}
+
+/// Returns the arguments of the `index`-th method response returned by [`Jmap::call`]
+/// after checking that it is a response to the method `name`.
+fn get_response<'a>(res: &'a [(String, Value)], index: usize, name: &str) -> Result<&'a Value> {
+    let (response_name, arguments) = res.get(index).context("missing method response")?;
+    ensure!(
+        response_name == name,
+        "JMAP method response {index} is {response_name:?} instead of {name:?}"
+    );
+    Ok(arguments)
+}
+
+/// Returns the length of the first complete server-sent event in `buf`
+/// including the empty line terminating it.
+///
+/// Lines may end with either LF or CRLF.
+fn event_len(buf: &str) -> Option<usize> {
+    ["\n\n", "\r\n\r\n"]
+        .iter()
+        .filter_map(|separator| buf.find(separator).map(|pos| pos + separator.len()))
+        .min()
+}
+
+fn is_timeout(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<reqwest::Error>()
+        .map_or(false, |err| err.is_timeout())
+}
+
+/// Parses a server-sent `state` event and returns the new `Email` state of the account, if any.
+fn parse_email_state_change(event: &str, account_id: &str) -> Option<String> {
+    let mut event_type = "message";
+    let mut data = String::new();
+    for line in event.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            event_type = value.trim();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push_str(value.trim());
+        }
+    }
+    if event_type != "state" {
+        return None;
+    }
+    let data: Value = serde_json::from_str(&data).ok()?;
+    data.get("changed")?
+        .get(account_id)?
+        .get("Email")?
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_email_state_change() {
+        let event = "event: state\ndata: {\"@type\":\"StateChange\",\"changed\":{\"a1\":{\"Email\":\"s42\",\"Mailbox\":\"m1\"}}}\n\n";
+        assert_eq!(
+            parse_email_state_change(event, "a1"),
+            Some("s42".to_string())
+        );
+        assert_eq!(parse_email_state_change(event, "a2"), None);
+
+        let event = "event: ping\ndata: {\"interval\":60}\n\n";
+        assert_eq!(parse_email_state_change(event, "a1"), None);
+
+        let event = "event: state\ndata: {\"@type\":\"StateChange\",\"changed\":{\"a1\":{\"Mailbox\":\"m2\"}}}\n\n";
+        assert_eq!(parse_email_state_change(event, "a1"), None);
+    }
+
+    #[test]
+    fn test_event_len() {
+        assert_eq!(event_len("event: ping\n"), None);
+        assert_eq!(event_len("event: ping\n\nevent: state\n"), Some(13));
+        assert_eq!(event_len("event: ping\r\n\r\nevent: state\r\n"), Some(15));
+        assert_eq!(event_len("event: ping\r\n"), None);
+
+        let event = "event: state\r\ndata: {\"changed\":{\"a1\":{\"Email\":\"s42\"}}}\r\n\r\n";
+        assert_eq!(event_len(event), Some(event.len()));
+        assert_eq!(
+            parse_email_state_change(event, "a1"),
+            Some("s42".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_response() {
+        let res = vec![
+            ("Mailbox/query".to_string(), json!({ "ids": ["m1"] })),
+            ("Identity/get".to_string(), json!({ "list": [] })),
+        ];
+        assert_eq!(
+            get_response(&res, 0, "Mailbox/query").unwrap(),
+            &json!({ "ids": ["m1"] })
+        );
+        assert!(get_response(&res, 1, "Mailbox/query").is_err());
+        assert!(get_response(&res, 2, "Identity/get").is_err());
+    }
+
+    #[test]
+    fn test_parse_quotas() {
+        let quotas: Vec<Value> = serde_json::from_str(
+            r#"[
+                {"id": "q1", "name": "mail", "resourceType": "octets", "scope": "account",
+                 "used": 2097152, "hardLimit": 10485760, "types": ["Mail"]},
+                {"id": "q2", "name": "mail", "resourceType": "count", "scope": "account",
+                 "used": 42, "hardLimit": 1000, "types": ["Mail"]},
+                {"id": "q3", "name": "files", "resourceType": "octets", "scope": "account",
+                 "used": 1024, "types": ["File"]}
+            ]"#,
+        )
+        .unwrap();
+        let quotas = parse_quotas(&quotas);
+        assert_eq!(quotas.len(), 1);
+        let resources = quotas.get("mail").unwrap();
+        assert_eq!(resources.len(), 2);
+        assert_eq!(resources[0].name, QuotaResourceName::Storage);
+        assert_eq!(resources[0].usage, 2048);
+        assert_eq!(resources[0].limit, 10240);
+        assert_eq!(resources[0].get_usage_percentage(), 20);
+        assert_eq!(resources[1].name, QuotaResourceName::Message);
+        assert_eq!(resources[1].usage, 42);
+    }
+
+    #[test]
+    fn test_method_error_type() {
+        let err: anyhow::Error = MethodError(json!({ "type": "cannotCalculateChanges" })).into();
+        assert_eq!(
+            err.downcast_ref::<MethodError>()
+                .and_then(MethodError::error_type),
+            Some("cannotCalculateChanges")
+        );
+    }
+
+    #[test]
+    fn test_parse_session() {
+        let session: Session = serde_json::from_str(
+            r#"{
+                "capabilities": {},
+                "accounts": {},
+                "primaryAccounts": {"urn:ietf:params:jmap:mail": "u1"},
+                "username": "alice@example.org",
+                "apiUrl": "https://jmap.example.org/api/",
+                "downloadUrl": "https://jmap.example.org/download/{accountId}/{blobId}/{name}?accept={type}",
+                "uploadUrl": "https://jmap.example.org/upload/{accountId}/",
+                "eventSourceUrl": "https://jmap.example.org/eventsource/?types={types}&closeafter={closeafter}&ping={ping}",
+                "state": "75128aab4b1b"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(session.api_url, "https://jmap.example.org/api/");
+        assert_eq!(
+            session.primary_accounts.get(CAPABILITY_MAIL),
+            Some(&"u1".to_string())
+        );
+    }
+}
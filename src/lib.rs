@@ -49,6 +49,11 @@
 
 pub(crate) mod events;
 pub use events::*;
+pub use scheduler::connectivity::{ConnectionInfo, ConnectionProtocol, ConnectionState};
+pub use sql::{
+    BlobGcReport, ColdStoredMsg, DbCheckReport, HousekeepingStats, InMemoryStorage, JournalMode,
+    MigrationPlan, OrphanedBlob, SqlConfig, SqlStorage, SqliteFileStorage,
+};
 
 mod aheader;
 mod blob;
@@ -65,6 +70,7 @@
 pub mod ephemeral;
 mod imap;
 pub mod imex;
+mod jmap;
 pub mod release;
 mod scheduler;
 #[macro_use]
@@ -74,6 +80,7 @@
 pub mod location;
 mod login_param;
 pub mod message;
+pub mod metrics;
 mod mimefactory;
 pub mod mimeparser;
 pub mod oauth2;
@@ -81,18 +88,26 @@
 pub mod peerstate;
 pub mod pgp;
 pub mod provider;
+pub mod push;
 pub mod qr;
 pub mod qr_code_generator;
 pub mod quota;
+pub mod search;
 pub mod securejoin;
 mod simplify;
 mod smtp;
-mod socks;
+pub mod socks;
 pub mod stock_str;
+pub mod storage;
 mod sync;
 mod timesmearing;
 mod token;
+pub mod transport;
 mod update_helper;
+#[cfg(feature = "webxdc")]
+pub mod webxdc;
+#[cfg(not(feature = "webxdc"))]
+#[path = "webxdc_disabled.rs"]
 pub mod webxdc;
 #[macro_use]
 mod dehtml;
@@ -100,7 +115,9 @@
 mod color;
 pub mod html;
 pub mod net;
+pub mod notifications;
 pub mod plaintext;
+pub mod profiling;
 pub mod summary;
 
 mod debug_logging;
@@ -110,6 +127,9 @@
 pub mod accounts;
 pub mod reaction;
 
+#[cfg(any(test, feature = "test-server"))]
+pub mod test_server;
+
 /// If set IMAP/incoming and SMTP/outgoing MIME messages will be printed.
 pub const DCC_MIME_DEBUG: &str = "DCC_MIME_DEBUG";
 
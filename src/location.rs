@@ -1,21 +1,39 @@
 //! Location handling.
+//!
+//! Parsing of KML attachments is always available
+//! so that received location messages can be recognized.
+//! Location streaming and storing received locations
+//! require the `location` feature.
 
+#[cfg(feature = "location")]
 use std::convert::TryFrom;
+#[cfg(feature = "location")]
 use std::time::Duration;
 
 use anyhow::{ensure, Context as _, Result};
+#[cfg(feature = "location")]
 use async_channel::Receiver;
 use quick_xml::events::{BytesEnd, BytesStart, BytesText};
+#[cfg(feature = "location")]
 use tokio::time::timeout;
 
-use crate::chat::{self, ChatId};
+#[cfg(feature = "location")]
+use crate::chat;
+use crate::chat::ChatId;
 use crate::contact::ContactId;
+#[cfg(feature = "location")]
 use crate::context::Context;
+#[cfg(feature = "location")]
 use crate::events::EventType;
+#[cfg(feature = "location")]
 use crate::message::{Message, MsgId, Viewtype};
+#[cfg(feature = "location")]
 use crate::mimeparser::SystemMessage;
+#[cfg(feature = "location")]
 use crate::stock_str;
-use crate::tools::{duration_to_str, time};
+#[cfg(feature = "location")]
+use crate::tools::duration_to_str;
+use crate::tools::time;
 
 /// Location record.
 #[derive(Debug, Clone, Default)]
@@ -255,6 +273,7 @@ fn starttag_cb<B: std::io::BufRead>(
 }
 
 /// Enables location streaming in chat identified by `chat_id` for `seconds` seconds.
+#[cfg(feature = "location")]
 pub async fn send_locations_to_chat(
     context: &Context,
     chat_id: ChatId,
@@ -300,6 +319,7 @@ pub async fn send_locations_to_chat(
 ///
 /// If `chat_id` is `Some` only that chat is checked, otherwise returns `true` if any chat
 /// is sending locations.
+#[cfg(feature = "location")]
 pub async fn is_sending_locations_to_chat(
     context: &Context,
     chat_id: Option<ChatId>,
@@ -328,6 +348,7 @@ pub async fn is_sending_locations_to_chat(
 }
 
 /// Sets current location of the user device.
+#[cfg(feature = "location")]
 pub async fn set(context: &Context, latitude: f64, longitude: f64, accuracy: f64) -> bool {
     if latitude == 0.0 && longitude == 0.0 {
         return true;
@@ -376,6 +397,7 @@ pub async fn set(context: &Context, latitude: f64, longitude: f64, accuracy: f64
 }
 
 /// Searches for locations in the given time range, optionally filtering by chat and contact IDs.
+#[cfg(feature = "location")]
 pub async fn get_range(
     context: &Context,
     chat_id: Option<ChatId>,
@@ -447,6 +469,7 @@ pub async fn get_range(
     Ok(list)
 }
 
+#[cfg(feature = "location")]
 fn is_marker(txt: &str) -> bool {
     let mut chars = txt.chars();
     if let Some(c) = chars.next() {
@@ -457,6 +480,7 @@ fn is_marker(txt: &str) -> bool {
 }
 
 /// Deletes all locations from the database.
+#[cfg(feature = "location")]
 pub async fn delete_all(context: &Context) -> Result<()> {
     context.sql.execute("DELETE FROM locations;", ()).await?;
     context.emit_event(EventType::LocationChanged(None));
@@ -464,6 +488,7 @@ pub async fn delete_all(context: &Context) -> Result<()> {
 }
 
 /// Returns `location.kml` contents.
+#[cfg(feature = "location")]
 pub async fn get_kml(context: &Context, chat_id: ChatId) -> Result<(String, u32)> {
     let mut last_added_location_id = 0;
 
@@ -539,6 +564,7 @@ pub async fn get_kml(context: &Context, chat_id: ChatId) -> Result<(String, u32)
     Ok((ret, last_added_location_id))
 }
 
+#[cfg(feature = "location")]
 fn get_kml_timestamp(utc: i64) -> String {
     // Returns a string formatted as YYYY-MM-DDTHH:MM:SSZ. The trailing `Z` indicates UTC.
     chrono::NaiveDateTime::from_timestamp_opt(utc, 0)
@@ -548,6 +574,7 @@ fn get_kml_timestamp(utc: i64) -> String {
 }
 
 /// Returns a KML document containing a single location with the given timestamp and coordinates.
+#[cfg(feature = "location")]
 pub fn get_message_kml(timestamp: i64, latitude: f64, longitude: f64) -> String {
     format!(
         "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
@@ -566,6 +593,7 @@ pub fn get_message_kml(timestamp: i64, latitude: f64, longitude: f64) -> String
 }
 
 /// Sets the timestamp of the last time location was sent in the chat.
+#[cfg(feature = "location")]
 pub async fn set_kml_sent_timestamp(
     context: &Context,
     chat_id: ChatId,
@@ -582,6 +610,7 @@ pub async fn set_kml_sent_timestamp(
 }
 
 /// Sets the location of the message.
+#[cfg(feature = "location")]
 pub async fn set_msg_location_id(context: &Context, msg_id: MsgId, location_id: u32) -> Result<()> {
     context
         .sql
@@ -597,6 +626,7 @@ pub async fn set_msg_location_id(context: &Context, msg_id: MsgId, location_id:
 /// Saves given locations to the database.
 ///
 /// Returns the database row ID of the location with the highest timestamp.
+#[cfg(feature = "location")]
 pub(crate) async fn save(
     context: &Context,
     chat_id: ChatId,
@@ -659,6 +689,7 @@ pub(crate) async fn save(
     Ok(newest_location_id)
 }
 
+#[cfg(feature = "location")]
 pub(crate) async fn location_loop(context: &Context, interrupt_receiver: Receiver<()>) {
     loop {
         let next_event = match maybe_send_locations(context).await {
@@ -686,6 +717,7 @@ pub(crate) async fn location_loop(context: &Context, interrupt_receiver: Receive
 
 /// Returns number of seconds until the next time location streaming for some chat ends
 /// automatically.
+#[cfg(feature = "location")]
 async fn maybe_send_locations(context: &Context) -> Result<Option<u64>> {
     let mut next_event: Option<u64> = None;
 
@@ -800,7 +832,9 @@ mod tests {
     #![allow(clippy::indexing_slicing)]
 
     use super::*;
+    #[cfg(feature = "location")]
     use crate::receive_imf::receive_imf;
+    #[cfg(feature = "location")]
     use crate::test_utils::TestContext;
 
     #[test]
@@ -839,6 +873,7 @@ fn test_kml_parse_error() {
         assert!(Kml::parse(xml).is_err());
     }
 
+    #[cfg(feature = "location")]
     #[test]
     fn test_get_message_kml() {
         let timestamp = 1598490000;
@@ -856,6 +891,7 @@ fn test_get_message_kml() {
         assert_eq!(locations_ref[0].timestamp, timestamp);
     }
 
+    #[cfg(feature = "location")]
     #[test]
     fn test_is_marker() {
         assert!(is_marker("f"));
@@ -866,6 +902,7 @@ fn test_is_marker() {
     }
 
     /// Tests that location.kml is hidden.
+    #[cfg(feature = "location")]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn receive_location_kml() -> Result<()> {
         let alice = TestContext::new_alice().await;
@@ -2,7 +2,14 @@
 
 #![allow(missing_docs)]
 
+use std::collections::VecDeque;
+
 use crate::context::Context;
+use crate::events::EventType;
+use crate::tools::time_ms;
+
+/// Maximum number of entries kept in the captured log of a context.
+pub const LOG_BUFFER_SIZE: usize = 10_000;
 
 #[macro_export]
 macro_rules! info {
@@ -61,6 +68,132 @@ pub fn get_last_error(&self) -> String {
     }
 }
 
+/// Severity of a log entry.
+#[derive(
+    Debug, Default, Display, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, FromPrimitive, ToPrimitive,
+)]
+#[repr(u8)]
+pub enum LogLevel {
+    /// Informational message, emitted as `EventType::Info`.
+    #[default]
+    Info = 0,
+
+    /// Warning, emitted as `EventType::Warning`.
+    Warning = 1,
+
+    /// Error, emitted as `EventType::Error`.
+    Error = 2,
+}
+
+/// Entry of the captured log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// Time the entry was logged, in milliseconds since the Unix epoch.
+    pub timestamp_ms: i64,
+
+    /// Severity.
+    pub level: LogLevel,
+
+    /// Source file which logged the entry, if known.
+    pub file: Option<String>,
+
+    /// Line in the source file, if known.
+    pub line: Option<u32>,
+
+    /// Log message without the location.
+    pub message: String,
+}
+
+impl LogEntry {
+    /// Creates a log entry from an `Info`, `Warning` or `Error` event.
+    fn from_event(event: &EventType) -> Option<Self> {
+        let (level, msg) = match event {
+            EventType::Info(msg) => (LogLevel::Info, msg),
+            EventType::Warning(msg) => (LogLevel::Warning, msg),
+            EventType::Error(msg) => (LogLevel::Error, msg),
+            _ => return None,
+        };
+
+        // info!() and warn!() prefix the message with "file:line: ".
+        let location = msg.split_once(": ").and_then(|(location, message)| {
+            let (file, line) = location.rsplit_once(':')?;
+            if !file.ends_with(".rs") {
+                return None;
+            }
+            Some((file, line.parse::<u32>().ok()?, message))
+        });
+        let (file, line, message) = match location {
+            Some((file, line, message)) => (Some(file.to_string()), Some(line), message),
+            None => (None, None, msg.as_str()),
+        };
+        Some(LogEntry {
+            timestamp_ms: time_ms(),
+            level,
+            file,
+            line,
+            message: message.to_string(),
+        })
+    }
+}
+
+/// Ring buffer of the last log entries.
+#[derive(Debug, Default)]
+pub(crate) struct LogBuffer {
+    /// Minimum level of captured entries.
+    level: LogLevel,
+
+    entries: VecDeque<LogEntry>,
+}
+
+impl LogBuffer {
+    /// Captures the event if it is a log event of at least the configured level.
+    pub(crate) fn capture(&mut self, event: &EventType) {
+        if let Some(entry) = LogEntry::from_event(event) {
+            if entry.level < self.level {
+                return;
+            }
+            if self.entries.len() >= LOG_BUFFER_SIZE {
+                self.entries.pop_front();
+            }
+            self.entries.push_back(entry);
+        }
+    }
+}
+
+impl Context {
+    /// Sets the minimum level of entries captured into the log returned by
+    /// [`Context::get_log_entries`].
+    ///
+    /// Does not affect emitted events.
+    /// The level is not persisted and is reset to [`LogLevel::Info`] when the context is opened.
+    pub fn set_log_level(&self, level: LogLevel) {
+        self.log_buffer.lock().expect("Mutex is poisoned").level = level;
+    }
+
+    /// Returns the minimum level of captured log entries.
+    pub fn get_log_level(&self) -> LogLevel {
+        self.log_buffer.lock().expect("Mutex is poisoned").level
+    }
+
+    /// Returns the last captured log entries of at least `min_level`, oldest first.
+    ///
+    /// If `limit` is set, at most `limit` newest entries are returned.
+    /// At most [`LOG_BUFFER_SIZE`] entries are kept in memory.
+    pub fn get_log_entries(&self, min_level: LogLevel, limit: Option<usize>) -> Vec<LogEntry> {
+        let log_buffer = self.log_buffer.lock().expect("Mutex is poisoned");
+        let mut entries: Vec<LogEntry> = log_buffer
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.level >= min_level)
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect();
+        entries.reverse();
+        entries
+    }
+}
+
 pub trait LogExt<T, E>
 where
     Self: std::marker::Sized,
@@ -102,8 +235,11 @@ fn log_err(self, context: &Context) -> Result<T, E> {
 
 #[cfg(test)]
 mod tests {
+    #![allow(clippy::indexing_slicing)]
+
     use anyhow::Result;
 
+    use super::*;
     use crate::test_utils::TestContext;
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
@@ -127,4 +263,35 @@ async fn test_get_last_error() -> Result<()> {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_log_capture() -> Result<()> {
+        let t = TestContext::new().await;
+
+        info!(t, "foo-info");
+        warn!(t, "foo-warning");
+        error!(t, "foo-error");
+
+        let entries = t.get_log_entries(LogLevel::Warning, None);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].level, LogLevel::Warning);
+        assert_eq!(entries[0].file.as_deref(), Some("src/log.rs"));
+        assert!(entries[0].line.is_some());
+        assert_eq!(entries[0].message, "foo-warning");
+        assert_eq!(entries[1].level, LogLevel::Error);
+        assert_eq!(entries[1].file, None);
+        assert_eq!(entries[1].message, "foo-error");
+
+        let entries = t.get_log_entries(LogLevel::Info, Some(2));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].message, "foo-warning");
+
+        t.set_log_level(LogLevel::Error);
+        assert_eq!(t.get_log_level(), LogLevel::Error);
+        warn!(t, "bar-warning");
+        let entries = t.get_log_entries(LogLevel::Info, Some(1));
+        assert_eq!(entries[0].message, "foo-error");
+
+        Ok(())
+    }
 }
@@ -7,6 +7,7 @@
 use deltachat_derive::{FromSql, ToSql};
 use serde::{Deserialize, Serialize};
 
+use crate::blob::remove_unreferenced_blobs;
 use crate::chat::{self, Chat, ChatId};
 use crate::config::Config;
 use crate::constants::{
@@ -18,7 +19,7 @@
 use crate::download::DownloadState;
 use crate::ephemeral::{start_ephemeral_timers_msgids, Timer as EphemeralTimer};
 use crate::events::EventType;
-use crate::imap::markseen_on_imap_table;
+use crate::log::LogExt;
 use crate::mimeparser::{parse_message_id, DeliveryReport, SystemMessage};
 use crate::param::{Param, Params};
 use crate::pgp::split_armored_data;
@@ -83,6 +84,15 @@ pub async fn get_state(self, context: &Context) -> Result<MessageState> {
         Ok(result)
     }
 
+    /// Returns true if the message is starred, see [`star_msgs`].
+    pub async fn is_starred(self, context: &Context) -> Result<bool> {
+        let starred: Option<bool> = context
+            .sql
+            .query_get_value("SELECT starred FROM msgs WHERE id=?", (self,))
+            .await?;
+        Ok(starred.unwrap_or_default())
+    }
+
     /// Put message into trash chat and delete message text.
     ///
     /// It means the message is deleted locally, but not on the server.
@@ -526,11 +536,16 @@ pub fn get_subject(&self) -> &str {
     /// Returns base file name without the path.
     /// The base file name includes the extension.
     ///
+    /// This is the original name of the file,
+    /// which may differ from the name of the file in the blob directory.
     /// To get the full path, use [`Self::get_file()`].
     pub fn get_filename(&self) -> Option<String> {
-        self.param
-            .get(Param::File)
-            .and_then(|file| Path::new(file).file_name())
+        let file = self.param.get(Param::File)?;
+        if let Some(name) = self.param.get(Param::Filename) {
+            return Some(name.to_string());
+        }
+        Path::new(file)
+            .file_name()
             .map(|name| name.to_string_lossy().to_string())
     }
 
@@ -711,6 +726,7 @@ pub async fn get_setupcodebegin(&self, context: &Context) -> Option<String> {
 
     // add room to a webrtc_instance as defined by the corresponding config-value;
     // the result may still be prefixed by the type
+    #[cfg(feature = "videochat")]
     pub(crate) fn create_webrtc_instance(instance: &str, room: &str) -> String {
         let (videochat_type, mut url) = Message::parse_webrtc_instance(instance);
 
@@ -806,6 +822,7 @@ pub fn set_subject(&mut self, subject: String) {
     /// for sending.
     pub fn set_file(&mut self, file: impl ToString, filemime: Option<&str>) {
         self.param.set(Param::File, file);
+        self.param.remove(Param::Filename);
         if let Some(filemime) = filemime {
             self.param.set(Param::MimeType, filemime);
         }
@@ -1438,6 +1455,10 @@ pub async fn delete_msgs(context: &Context, msg_ids: &[MsgId]) -> Result<()> {
 
     if !msg_ids.is_empty() {
         context.emit_msgs_changed_without_ids();
+        remove_unreferenced_blobs(context)
+            .await
+            .log_err(context)
+            .ok();
 
         // Run housekeeping to delete unused blobs.
         context.set_config(Config::LastHousekeeping, None).await?;
@@ -1462,6 +1483,26 @@ async fn delete_poi_location(context: &Context, location_id: u32) -> Result<()>
     Ok(())
 }
 
+/// Stars or unstars messages.
+///
+/// Starred messages are kept when media is deleted automatically,
+/// see [`MediaCleanupPolicy`](crate::storage::MediaCleanupPolicy).
+pub async fn star_msgs(context: &Context, msg_ids: &[MsgId], star: bool) -> Result<()> {
+    let msg_ids = msg_ids.to_vec();
+    context
+        .sql
+        .transaction(move |transaction| {
+            let mut stmt = transaction.prepare("UPDATE msgs SET starred=? WHERE id=?")?;
+            for msg_id in msg_ids {
+                stmt.execute((star, msg_id))?;
+            }
+            Ok(())
+        })
+        .await?;
+    context.emit_msgs_changed_without_ids();
+    Ok(())
+}
+
 /// Marks requested messages as seen.
 pub async fn markseen_msgs(context: &Context, msg_ids: Vec<MsgId>) -> Result<()> {
     if msg_ids.is_empty() {
@@ -1526,7 +1567,9 @@ pub async fn markseen_msgs(context: &Context, msg_ids: Vec<MsgId>) -> Result<()>
             .context("failed to start ephemeral timers")?;
     }
 
+    let mdns_enabled = context.get_config_bool(Config::MdnsEnabled).await?;
     let mut updated_chat_ids = BTreeSet::new();
+    let mut seen = Vec::new();
     for (
         id,
         curr_chat_id,
@@ -1541,40 +1584,61 @@ pub async fn markseen_msgs(context: &Context, msg_ids: Vec<MsgId>) -> Result<()>
         if curr_blocked == Blocked::Not
             && (curr_state == MessageState::InFresh || curr_state == MessageState::InNoticed)
         {
-            update_msg_state(context, id, MessageState::InSeen).await?;
-            info!(context, "Seen message {}.", id);
-
-            markseen_on_imap_table(context, &curr_rfc724_mid).await?;
-
             // Read receipts for system messages are never sent. These messages have no place to
             // display received read receipt anyway.  And since their text is locally generated,
             // quoting them is dangerous as it may contain contact names. E.g., for original message
             // "Group left by me", a read receipt will quote "Group left by <name>", and the name can
             // be a display name stored in address book rather than the name sent in the From field by
             // the user.
-            if curr_param.get_bool(Param::WantsMdn).unwrap_or_default()
-                && curr_param.get_cmd() == SystemMessage::Unknown
-            {
-                let mdns_enabled = context.get_config_bool(Config::MdnsEnabled).await?;
-                if mdns_enabled {
-                    context
-                        .sql
-                        .execute(
-                            "INSERT INTO smtp_mdns (msg_id, from_id, rfc724_mid) VALUES(?, ?, ?)",
-                            (id, curr_from_id, curr_rfc724_mid),
-                        )
-                        .await
-                        .context("failed to insert into smtp_mdns")?;
-                    context
-                        .scheduler
-                        .interrupt_smtp(InterruptInfo::new(false))
-                        .await;
-                }
-            }
+            let send_mdn = mdns_enabled
+                && curr_param.get_bool(Param::WantsMdn).unwrap_or_default()
+                && curr_param.get_cmd() == SystemMessage::Unknown;
+            seen.push((id, curr_from_id, curr_rfc724_mid, send_mdn));
             updated_chat_ids.insert(curr_chat_id);
         }
     }
+    if seen.is_empty() {
+        return Ok(());
+    }
+
+    // Update the states, schedule marking the messages as seen on IMAP
+    // and sending read receipts in a single transaction.
+    let seen_count = seen.len();
+    let any_mdn = seen.iter().any(|(_, _, _, send_mdn)| *send_mdn);
+    context
+        .sql
+        .transaction(move |transaction| {
+            let mut update_state = transaction.prepare("UPDATE msgs SET state=? WHERE id=?")?;
+            let mut markseen_on_imap = transaction.prepare(
+                "INSERT OR IGNORE INTO imap_markseen (id)
+                 SELECT id FROM imap WHERE rfc724_mid=?",
+            )?;
+            let mut insert_mdn = transaction
+                .prepare("INSERT INTO smtp_mdns (msg_id, from_id, rfc724_mid) VALUES(?, ?, ?)")?;
+            for (id, from_id, rfc724_mid, send_mdn) in seen {
+                update_state.execute((MessageState::InSeen, id))?;
+                markseen_on_imap.execute((&rfc724_mid,))?;
+                if send_mdn {
+                    insert_mdn
+                        .execute((id, from_id, rfc724_mid))
+                        .context("failed to insert into smtp_mdns")?;
+                }
+            }
+            Ok(())
+        })
+        .await?;
+    info!(context, "Marked {} messages as seen.", seen_count);
 
+    context
+        .scheduler
+        .interrupt_inbox(InterruptInfo::new(false))
+        .await;
+    if any_mdn {
+        context
+            .scheduler
+            .interrupt_smtp(InterruptInfo::new(false))
+            .await;
+    }
     for updated_chat_id in updated_chat_ids {
         context.emit_event(EventType::MsgsNoticed(updated_chat_id));
     }
@@ -2117,6 +2181,7 @@ async fn test_parse_webrtc_instance() {
         assert_eq!(url, "https://j.si/foo");
     }
 
+    #[cfg(feature = "videochat")]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_create_webrtc_instance() {
         // webrtc_instance may come from an input field of the ui, be pretty tolerant on input
@@ -2154,6 +2219,7 @@ async fn test_create_webrtc_instance() {
         assert_eq!(instance, "basicwebrtc:https://basic.stuff/12345ab");
     }
 
+    #[cfg(feature = "videochat")]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_create_webrtc_instance_noroom() {
         // webrtc_instance may come from an input field of the ui, be pretty tolerant on input
@@ -2385,6 +2451,48 @@ async fn test_markseen_msgs() -> Result<()> {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_markseen_msgs_bulk() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let alice_chat_id = alice.create_chat(&bob).await.id;
+        let bob_chat_id = bob.create_chat(&alice).await.id;
+
+        let mut msg_ids = Vec::new();
+        for i in 0..20 {
+            let sent = bob.send_text(bob_chat_id, &format!("Message {i}")).await;
+            let msg = alice.recv_msg(&sent).await;
+            assert_eq!(msg.chat_id, alice_chat_id);
+            msg_ids.push(msg.id);
+        }
+        assert_eq!(alice_chat_id.get_fresh_msg_cnt(&alice).await?, 20);
+
+        markseen_msgs(&alice, msg_ids.clone()).await?;
+        assert_eq!(alice_chat_id.get_fresh_msg_cnt(&alice).await?, 0);
+        for msg_id in &msg_ids {
+            assert_eq!(msg_id.get_state(&alice).await?, MessageState::InSeen);
+        }
+        assert_eq!(
+            alice
+                .sql
+                .count("SELECT COUNT(*) FROM smtp_mdns", ())
+                .await?,
+            20
+        );
+
+        // Messages already seen are skipped.
+        markseen_msgs(&alice, msg_ids).await?;
+        assert_eq!(
+            alice
+                .sql
+                .count("SELECT COUNT(*) FROM smtp_mdns", ())
+                .await?,
+            20
+        );
+
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_get_state() -> Result<()> {
         let alice = TestContext::new_alice().await;
@@ -0,0 +1,200 @@
+//! # Metrics for monitoring.
+//!
+//! Counters and gauges of an account, meant to be scraped by monitoring tools
+//! such as Prometheus in bot deployments.
+//! Counters are kept in memory and start at zero when the process starts.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Result;
+use tokio::fs;
+
+use crate::accounts::Accounts;
+use crate::context::Context;
+
+/// In-memory metric counters of an account.
+#[derive(Debug, Default)]
+pub(crate) struct MetricsCounters {
+    msgs_sent: AtomicU64,
+    msgs_received: AtomicU64,
+    send_failures: AtomicU64,
+}
+
+impl MetricsCounters {
+    /// Counts a message sent over SMTP.
+    pub(crate) fn count_msg_sent(&self) {
+        self.msgs_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts an incoming message.
+    pub(crate) fn count_msg_received(&self) {
+        self.msgs_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a message which could not be sent and will not be retried.
+    pub(crate) fn count_send_failure(&self) {
+        self.send_failures.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Metrics of an account.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+    /// Messages sent over SMTP since the process started.
+    pub msgs_sent: u64,
+
+    /// Incoming messages received since the process started.
+    pub msgs_received: u64,
+
+    /// Messages given up sending since the process started.
+    pub send_failures: u64,
+
+    /// Connectivity as returned by [`Context::get_connectivity`], 1000 to 4000.
+    pub connectivity: u32,
+
+    /// Number of messages waiting to be sent over SMTP.
+    pub smtp_queue_len: usize,
+
+    /// Size of the database file in bytes.
+    pub db_size: u64,
+}
+
+impl Context {
+    /// Returns the metrics of the account.
+    pub async fn get_metrics(&self) -> Result<Metrics> {
+        let db_size = match fs::metadata(self.get_dbfile()).await {
+            Ok(metadata) => metadata.len(),
+            Err(err) => {
+                warn!(self, "Failed to get database size: {err:#}.");
+                0
+            }
+        };
+        Ok(Metrics {
+            msgs_sent: self.metrics.msgs_sent.load(Ordering::Relaxed),
+            msgs_received: self.metrics.msgs_received.load(Ordering::Relaxed),
+            send_failures: self.metrics.send_failures.load(Ordering::Relaxed),
+            connectivity: self.get_connectivity().await as u32,
+            smtp_queue_len: self.sql.count("SELECT COUNT(*) FROM smtp", ()).await?,
+            db_size,
+        })
+    }
+}
+
+/// Metric name, type, help text and value in the Prometheus text format.
+type PrometheusMetric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&Metrics) -> u64,
+);
+
+const PROMETHEUS_METRICS: [PrometheusMetric; 6] = [
+    (
+        "deltachat_messages_sent_total",
+        "counter",
+        "Messages sent over SMTP since the process started.",
+        |metrics| metrics.msgs_sent,
+    ),
+    (
+        "deltachat_messages_received_total",
+        "counter",
+        "Incoming messages received since the process started.",
+        |metrics| metrics.msgs_received,
+    ),
+    (
+        "deltachat_send_failures_total",
+        "counter",
+        "Messages given up sending since the process started.",
+        |metrics| metrics.send_failures,
+    ),
+    (
+        "deltachat_connectivity",
+        "gauge",
+        "Connectivity: 1000=not connected, 2000=connecting, 3000=working, 4000=connected.",
+        |metrics| metrics.connectivity.into(),
+    ),
+    (
+        "deltachat_smtp_queue_length",
+        "gauge",
+        "Messages waiting to be sent over SMTP.",
+        |metrics| metrics.smtp_queue_len.try_into().unwrap_or(u64::MAX),
+    ),
+    (
+        "deltachat_database_size_bytes",
+        "gauge",
+        "Size of the database file in bytes.",
+        |metrics| metrics.db_size,
+    ),
+];
+
+impl Accounts {
+    /// Returns the metrics of all open accounts in the Prometheus text exposition format.
+    ///
+    /// Each sample is labeled with the account ID.
+    pub async fn get_metrics_prometheus(&self) -> Result<String> {
+        let mut accounts_metrics = Vec::new();
+        for id in self.get_all() {
+            if let Some(account) = self.get_account(id) {
+                if account.is_open().await {
+                    accounts_metrics.push((id, account.get_metrics().await?));
+                }
+            }
+        }
+
+        let mut res = String::new();
+        for (name, typ, help, value) in PROMETHEUS_METRICS {
+            writeln!(res, "# HELP {name} {help}")?;
+            writeln!(res, "# TYPE {name} {typ}")?;
+            for (id, metrics) in &accounts_metrics {
+                writeln!(res, "{name}{{account=\"{id}\"}} {}", value(metrics))?;
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::chat;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_metrics() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let metrics = t.get_metrics().await?;
+        assert_eq!(metrics.msgs_sent, 0);
+        assert_eq!(metrics.smtp_queue_len, 0);
+        assert_eq!(metrics.connectivity, 1000);
+        assert!(metrics.db_size > 0);
+
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        chat::send_text_msg(&t, chat.id, "Hi".to_string()).await?;
+        assert_eq!(t.get_metrics().await?.smtp_queue_len, 1);
+
+        t.metrics.count_msg_received();
+        t.metrics.count_send_failure();
+        let metrics = t.get_metrics().await?;
+        assert_eq!(metrics.msgs_received, 1);
+        assert_eq!(metrics.send_failures, 1);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_metrics_prometheus() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let p: PathBuf = dir.path().join("accounts");
+        let mut accounts = Accounts::new(p).await?;
+        accounts.add_account().await?;
+        accounts.add_closed_account().await?;
+
+        let metrics = accounts.get_metrics_prometheus().await?;
+        assert!(metrics.contains("# TYPE deltachat_messages_sent_total counter\n"));
+        assert!(metrics.contains("deltachat_smtp_queue_length{account=\"1\"} 0\n"));
+        assert!(!metrics.contains("account=\"2\""));
+        Ok(())
+    }
+}
@@ -18,6 +18,7 @@
 use crate::e2ee::EncryptHelper;
 use crate::ephemeral::Timer as EphemeralTimer;
 use crate::html::new_html_mimepart;
+#[cfg(feature = "location")]
 use crate::location;
 use crate::message::{self, Message, MsgId, Viewtype};
 use crate::mimeparser::SystemMessage;
@@ -667,6 +668,11 @@ pub async fn render(mut self, context: &Context) -> Result<RenderedEmail> {
                     "Content-Type".to_string(),
                     "multipart/report; report-type=status-update".to_string(),
                 ))
+            } else if self.msg.param.get_cmd() == SystemMessage::WebxdcRealtimeData {
+                PartBuilder::new().header((
+                    "Content-Type".to_string(),
+                    "multipart/report; report-type=webxdc-realtime".to_string(),
+                ))
             } else {
                 PartBuilder::new().message_type(MimeMultipartType::Mixed)
             };
@@ -840,6 +846,7 @@ pub async fn render(mut self, context: &Context) -> Result<RenderedEmail> {
     }
 
     /// Returns MIME part with a `message.kml` attachment.
+    #[cfg(feature = "location")]
     fn get_message_kml_part(&self) -> Option<PartBuilder> {
         let latitude = self.msg.param.get_float(Param::SetLatitude)?;
         let longitude = self.msg.param.get_float(Param::SetLongitude)?;
@@ -860,6 +867,7 @@ fn get_message_kml_part(&self) -> Option<PartBuilder> {
     }
 
     /// Returns MIME part with a `location.kml` attachment.
+    #[cfg(feature = "location")]
     async fn get_location_kml_part(&mut self, context: &Context) -> Result<PartBuilder> {
         let (kml_content, last_added_location_id) =
             location::get_kml(context, self.msg.chat_id).await?;
@@ -996,7 +1004,8 @@ async fn render_message(
             }
             SystemMessage::LocationOnly
             | SystemMessage::MultiDeviceSync
-            | SystemMessage::WebxdcStatusUpdate => {
+            | SystemMessage::WebxdcStatusUpdate
+            | SystemMessage::WebxdcRealtimeData => {
                 // This should prevent automatic replies,
                 // such as non-delivery reports.
                 //
@@ -1216,15 +1225,18 @@ async fn render_message(
             parts.push(meta_part);
         }
 
-        if let Some(msg_kml_part) = self.get_message_kml_part() {
-            parts.push(msg_kml_part);
-        }
+        #[cfg(feature = "location")]
+        {
+            if let Some(msg_kml_part) = self.get_message_kml_part() {
+                parts.push(msg_kml_part);
+            }
 
-        if location::is_sending_locations_to_chat(context, Some(self.msg.chat_id)).await? {
-            match self.get_location_kml_part(context).await {
-                Ok(part) => parts.push(part),
-                Err(err) => {
-                    warn!(context, "mimefactory: could not send location: {}", err);
+            if location::is_sending_locations_to_chat(context, Some(self.msg.chat_id)).await? {
+                match self.get_location_kml_part(context).await {
+                    Ok(part) => parts.push(part),
+                    Err(err) => {
+                        warn!(context, "mimefactory: could not send location: {}", err);
+                    }
                 }
             }
         }
@@ -1239,6 +1251,9 @@ async fn render_message(
         } else if command == SystemMessage::WebxdcStatusUpdate {
             let json = self.msg.param.get(Param::Arg).unwrap_or_default();
             parts.push(context.build_status_update_part(json));
+        } else if command == SystemMessage::WebxdcRealtimeData {
+            let json = self.msg.param.get(Param::Arg).unwrap_or_default();
+            parts.push(context.build_realtime_data_part(json));
         } else if self.msg.viewtype == Viewtype::Webxdc {
             if let Some(json) = context
                 .render_webxdc_status_update_object(self.msg.id, None)
@@ -1422,7 +1437,11 @@ async fn build_body_file(
                 ),
             &suffix
         ),
-        _ => blob.as_file_name().to_string(),
+        _ => msg
+            .param
+            .get(Param::Filename)
+            .unwrap_or_else(|| blob.as_file_name())
+            .to_string(),
     };
 
     /* check mimetype */
@@ -10,6 +10,7 @@
 use deltachat_derive::{FromSql, ToSql};
 use format_flowed::unformat_flowed;
 use lettre_email::mime::{self, Mime};
+use mailparse::body::Body;
 use mailparse::{addrparse_header, DispositionType, MailHeader, MailHeaderMap, SingleInfo};
 use once_cell::sync::Lazy;
 
@@ -33,7 +34,11 @@
 use crate::simplify::{simplify, SimplifiedText};
 use crate::stock_str;
 use crate::sync::SyncItems;
-use crate::tools::{get_filemeta, parse_receive_headers, strip_rtlo_characters, truncate_by_lines};
+use crate::tools::{
+    get_filemeta, get_filemeta_from_file, parse_receive_headers, strip_rtlo_characters,
+    truncate_by_lines,
+};
+use crate::webxdc::WEBXDC_SUFFIX;
 use crate::{location, tools};
 
 /// A parsed MIME message.
@@ -84,6 +89,7 @@ pub(crate) struct MimeMessage {
     pub message_kml: Option<location::Kml>,
     pub(crate) sync_items: Option<SyncItems>,
     pub(crate) webxdc_status_update: Option<String>,
+    pub(crate) webxdc_realtime_data: Option<String>,
     pub(crate) user_avatar: Option<AvatarAction>,
     pub(crate) group_avatar: Option<AvatarAction>,
     pub(crate) mdn_reports: Vec<Report>,
@@ -185,10 +191,19 @@ pub enum SystemMessage {
 
     /// Webxdc info added with `info` set in `send_webxdc_status_update()`.
     WebxdcInfoMessage = 32,
+
+    /// Realtime data or peer address sent to the other peers
+    /// of the realtime channel of a webxdc instance.
+    /// These messages are not shown in the chat.
+    WebxdcRealtimeData = 33,
 }
 
 const MIME_AC_SETUP_FILE: &str = "application/autocrypt-setup";
 
+/// Minimum size of the encoded body of an attachment
+/// to decode it directly into the blob directory.
+const STREAMING_MIN_SIZE: usize = 1024 * 1024;
+
 impl MimeMessage {
     /// Parse a mime message.
     ///
@@ -402,6 +417,7 @@ pub(crate) async fn from_bytes(
             message_kml: None,
             sync_items: None,
             webxdc_status_update: None,
+            webxdc_realtime_data: None,
             user_avatar: None,
             group_avatar: None,
             delivery_report: None,
@@ -515,6 +531,7 @@ async fn parse_avatar_headers(&mut self, context: &Context) {
         }
     }
 
+    #[cfg(feature = "videochat")]
     fn parse_videochat_headers(&mut self) {
         if let Some(value) = self.get_header(HeaderDef::ChatContent).cloned() {
             if value == "videochat-invitation" {
@@ -607,6 +624,7 @@ fn parse_attachments(&mut self) {
     async fn parse_headers(&mut self, context: &Context) -> Result<()> {
         self.parse_system_message_headers(context);
         self.parse_avatar_headers(context).await;
+        #[cfg(feature = "videochat")]
         self.parse_videochat_headers();
         if self.delivery_report.is_none() {
             self.squash_attachment_parts();
@@ -961,7 +979,7 @@ async fn handle_multiple(
                                     .await?;
                             }
                         }
-                        Some("status-update") => {
+                        Some("status-update") | Some("webxdc-realtime") => {
                             if let Some(second) = mail.subparts.get(1) {
                                 self.add_single_part_if_known(context, second, is_related)
                                     .await?;
@@ -1010,6 +1028,12 @@ async fn add_single_part_if_known(
         let old_part_count = self.parts.len();
 
         match filename {
+            Some(filename) if is_streamable_file_part(mail, &mime_type, &filename) => {
+                self.do_add_streamed_file_part(
+                    context, msg_type, mime_type, &raw_mime, mail, &filename, is_related,
+                )
+                .await;
+            }
             Some(filename) => {
                 self.do_add_single_file_part(
                     context,
@@ -1219,6 +1243,12 @@ async fn do_add_single_file_part(
                 .unwrap_or_default();
             self.webxdc_status_update = Some(serialized);
             return Ok(());
+        } else if filename == "webxdc-realtime.json" {
+            let serialized = String::from_utf8_lossy(decoded_data)
+                .parse()
+                .unwrap_or_default();
+            self.webxdc_realtime_data = Some(serialized);
+            return Ok(());
         } else {
             msg_type
         };
@@ -1226,7 +1256,7 @@ async fn do_add_single_file_part(
         /* we have a regular file attachment,
         write decoded data to new blob object */
 
-        let blob = match BlobObject::create(context, filename, decoded_data).await {
+        let blob = match BlobObject::create_and_deduplicate(context, filename, decoded_data).await {
             Ok(blob) => blob,
             Err(err) => {
                 error!(
@@ -1238,25 +1268,96 @@ async fn do_add_single_file_part(
         };
         info!(context, "added blobfile: {:?}", blob.as_name());
 
-        /* create and register Mime part referencing the new Blob object */
+        let dimensions = if mime_type.type_() == mime::IMAGE {
+            get_filemeta(decoded_data).ok()
+        } else {
+            None
+        };
+        self.do_add_blob_part(
+            &blob,
+            msg_type,
+            mime_type,
+            raw_mime,
+            filename,
+            decoded_data.len(),
+            dimensions,
+            is_related,
+        );
+        Ok(())
+    }
+
+    /// Adds a large regular attachment,
+    /// decoding it directly into the blob directory
+    /// instead of keeping the decoded data in memory.
+    #[allow(clippy::too_many_arguments)]
+    async fn do_add_streamed_file_part(
+        &mut self,
+        context: &Context,
+        msg_type: Viewtype,
+        mime_type: Mime,
+        raw_mime: &str,
+        mail: &mailparse::ParsedMail<'_>,
+        filename: &str,
+        is_related: bool,
+    ) {
+        let (blob, bytes) =
+            match BlobObject::create_from_mime_body(context, filename, &mail.get_body_encoded())
+                .await
+            {
+                Ok(res) => res,
+                Err(err) => {
+                    error!(
+                        context,
+                        "Could not add blob for mime part {}, error {:#}", filename, err
+                    );
+                    return;
+                }
+            };
+        info!(context, "added streamed blobfile: {:?}", blob.as_name());
+        if bytes == 0 {
+            return;
+        }
+
+        let dimensions = if mime_type.type_() == mime::IMAGE {
+            get_filemeta_from_file(&blob.to_abs_path()).ok()
+        } else {
+            None
+        };
+        self.do_add_blob_part(
+            &blob, msg_type, mime_type, raw_mime, filename, bytes, dimensions, is_related,
+        );
+    }
+
+    /// Creates and registers a MIME part referencing the blob.
+    #[allow(clippy::too_many_arguments)]
+    fn do_add_blob_part(
+        &mut self,
+        blob: &BlobObject<'_>,
+        msg_type: Viewtype,
+        mime_type: Mime,
+        raw_mime: &str,
+        filename: &str,
+        bytes: usize,
+        dimensions: Option<(u32, u32)>,
+        is_related: bool,
+    ) {
         let mut part = Part::default();
-        if mime_type.type_() == mime::IMAGE {
-            if let Ok((width, height)) = get_filemeta(decoded_data) {
-                part.param.set_int(Param::Width, width as i32);
-                part.param.set_int(Param::Height, height as i32);
-            }
+        if let Some((width, height)) = dimensions {
+            part.param.set_int(Param::Width, width as i32);
+            part.param.set_int(Param::Height, height as i32);
         }
 
         part.typ = msg_type;
         part.org_filename = Some(filename.to_string());
         part.mimetype = Some(mime_type);
-        part.bytes = decoded_data.len();
+        part.bytes = bytes;
         part.param.set(Param::File, blob.as_name());
+        part.param
+            .set(Param::Filename, BlobObject::sanitise_filename(filename));
         part.param.set(Param::MimeType, raw_mime);
         part.is_related = is_related;
 
         self.do_add_single_part(part);
-        Ok(())
     }
 
     /// Returns whether a key from the attachment was set as peer's pubkey.
@@ -1853,6 +1954,28 @@ pub struct Part {
     pub(crate) is_reaction: bool,
 }
 
+/// Returns true if the attachment is large
+/// and its content is not needed to process the message,
+/// so it can be decoded directly into the blob directory.
+fn is_streamable_file_part(
+    mail: &mailparse::ParsedMail<'_>,
+    mime_type: &Mime,
+    filename: &str,
+) -> bool {
+    let encoded_size = match mail.get_body_encoded() {
+        Body::Base64(body) | Body::QuotedPrintable(body) => body.get_raw().len(),
+        Body::SevenBit(body) | Body::EightBit(body) => body.get_raw().len(),
+        Body::Binary(body) => body.get_raw().len(),
+    };
+    encoded_size >= STREAMING_MIN_SIZE
+        && !(mime_type.type_() == mime::APPLICATION && mime_type.subtype().as_str() == "pgp-keys")
+        && !filename.ends_with(WEBXDC_SUFFIX)
+        && !filename.ends_with(".kml")
+        && filename != "multi-device-sync.json"
+        && filename != "status-update.json"
+        && filename != "webxdc-realtime.json"
+}
+
 /// return mimetype and viewtype for a parsed mail
 fn get_mime_type(mail: &mailparse::ParsedMail<'_>) -> Result<(Mime, Viewtype)> {
     let mimetype = mail.ctype.mimetype.parse::<Mime>()?;
@@ -2460,6 +2583,7 @@ async fn test_mimeparser_with_avatars() {
         assert!(mimeparser.group_avatar.unwrap().is_change());
     }
 
+    #[cfg(feature = "videochat")]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_mimeparser_with_videochat() {
         let t = TestContext::new_alice().await;
@@ -3530,9 +3654,14 @@ async fn test_receive_eml() -> Result<()> {
             "this is a classic email – I attached the .EML file".to_string()
         );
         assert_eq!(
-            mime_message.parts[0].param.get(Param::File),
-            Some("$BLOBDIR/.eml")
+            mime_message.parts[0].param.get(Param::Filename),
+            Some(".eml")
         );
+        assert!(mime_message.parts[0]
+            .param
+            .get(Param::File)
+            .unwrap()
+            .ends_with(".eml"));
 
         assert_eq!(mime_message.parts[0].org_filename, Some(".eml".to_string()));
 
@@ -3574,4 +3703,61 @@ async fn test_parse_reaction() -> Result<()> {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_parse_large_attachment() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+
+        let data: Vec<u8> = (0..2_000_000u32).map(|i| (i % 251) as u8).collect();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&data);
+        let encoded = encoded
+            .as_bytes()
+            .chunks(76)
+            .map(|line| str::from_utf8(line).unwrap())
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        let raw = format!(
+            "To: alice@example.org\r\n\
+             From: bob@example.net\r\n\
+             Subject: Large file\r\n\
+             Message-ID: <large@example.net>\r\n\
+             Mime-Version: 1.0\r\n\
+             Content-Type: multipart/mixed; boundary=\"boundary\"\r\n\
+             \r\n\
+             --boundary\r\n\
+             Content-Type: text/plain; charset=utf-8\r\n\
+             \r\n\
+             Here is the file.\r\n\
+             --boundary\r\n\
+             Content-Type: application/octet-stream\r\n\
+             Content-Disposition: attachment; filename=\"data.bin\"\r\n\
+             Content-Transfer-Encoding: base64\r\n\
+             \r\n\
+             {encoded}\r\n\
+             --boundary--\r\n"
+        );
+        let mail = mailparse::parse_mail(raw.as_bytes())?;
+        assert!(is_streamable_file_part(
+            &mail.subparts[1],
+            &mime::APPLICATION_OCTET_STREAM,
+            "data.bin"
+        ));
+        assert!(!is_streamable_file_part(
+            &mail.subparts[1],
+            &mime::APPLICATION_OCTET_STREAM,
+            "status-update.json"
+        ));
+
+        let mime_message = MimeMessage::from_bytes(&alice, raw.as_bytes(), None).await?;
+        assert_eq!(mime_message.parts.len(), 1);
+        let part = &mime_message.parts[0];
+        assert_eq!(part.typ, Viewtype::File);
+        assert_eq!(part.msg, "Here is the file.");
+        assert_eq!(part.org_filename.as_deref(), Some("data.bin"));
+        assert_eq!(part.bytes, data.len());
+        let path = part.param.get_path(Param::File, &alice)?.unwrap();
+        assert_eq!(tokio::fs::read(path).await?, data);
+
+        Ok(())
+    }
 }
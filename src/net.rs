@@ -12,11 +12,18 @@
 use crate::context::Context;
 use crate::tools::time;
 
+pub(crate) mod diagnostics;
 pub(crate) mod http;
+pub(crate) mod pinning;
 pub(crate) mod session;
 pub(crate) mod tls;
+pub(crate) mod tor;
+pub(crate) mod traffic;
 
+pub use diagnostics::{DiagnosticsCheck, DiagnosticsStatus, DiagnosticsStep};
 pub use http::{read_url, read_url_blob, Response as HttpResponse};
+pub use pinning::{CertificatePin, PinKind};
+pub use traffic::{ProtocolTraffic, TrafficStats};
 
 async fn connect_tcp_inner(addr: SocketAddr, timeout_val: Duration) -> Result<TcpStream> {
     let tcp_stream = timeout(timeout_val, TcpStream::connect(addr))
@@ -136,6 +143,8 @@ async fn lookup_host_with_cache(
 /// for example using TLS.
 /// If TLS is not used or invalid TLS certificates are allowed,
 /// this option should be disabled.
+///
+/// Fails in maintenance mode, see [`Context::set_maintenance_mode`].
 pub(crate) async fn connect_tcp(
     context: &Context,
     host: &str,
@@ -143,6 +152,7 @@ pub(crate) async fn connect_tcp(
     timeout_val: Duration,
     load_cache: bool,
 ) -> Result<Pin<Box<TimeoutStream<TcpStream>>>> {
+    context.ensure_network_allowed()?;
     let mut tcp_stream = None;
     let mut last_error = None;
 
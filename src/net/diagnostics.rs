@@ -0,0 +1,355 @@
+//! # Network diagnostics.
+//!
+//! Checks connectivity to the configured servers step by step
+//! so it is easy to see where exactly the connection fails.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use anyhow::{ensure, Context as _, Result};
+use tokio::time::timeout;
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::imap::Imap;
+use crate::login_param::{CertificateChecks, LoginParam, ServerLoginParam};
+use crate::net::connect_tcp;
+use crate::net::pinning::check_certificate_pin;
+use crate::net::session::SessionStream;
+use crate::net::tls::wrap_tls;
+use crate::provider::Socket;
+use crate::smtp::Smtp;
+
+/// Timeout for each network operation.
+const DIAGNOSTICS_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Kind of the check done in a diagnostics step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticsCheck {
+    /// Resolving the server hostname.
+    Dns,
+
+    /// Establishing TCP connection, through the proxy if configured.
+    Tcp,
+
+    /// TLS handshake, including certificate pin check.
+    Tls,
+
+    /// Connecting and logging in to the IMAP server.
+    ImapLogin,
+
+    /// Opening the inbox folder.
+    FolderAccess,
+
+    /// Connecting and logging in to the SMTP server.
+    SmtpLogin,
+}
+
+/// Result of a diagnostics step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticsStatus {
+    /// The check succeeded.
+    Ok,
+
+    /// The check failed.
+    Failed,
+
+    /// The check was not done because a previous step failed.
+    Skipped,
+}
+
+/// Single step of the network diagnostics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticsStep {
+    /// What was checked.
+    pub check: DiagnosticsCheck,
+
+    /// Checked server as `host:port` or folder name.
+    pub target: String,
+
+    /// Result of the check.
+    pub status: DiagnosticsStatus,
+
+    /// Time the check took in milliseconds.
+    pub duration_ms: u64,
+
+    /// Additional information such as resolved addresses.
+    pub details: Option<String>,
+
+    /// Error if the check failed.
+    pub error: Option<String>,
+}
+
+/// Runs `check` unless `skip` is true and records the result into `steps`.
+///
+/// Returns the result of the check if it succeeded.
+async fn run_step<T>(
+    steps: &mut Vec<DiagnosticsStep>,
+    check: DiagnosticsCheck,
+    target: &str,
+    skip: bool,
+    fut: impl Future<Output = Result<(T, Option<String>)>>,
+) -> Option<T> {
+    let mut step = DiagnosticsStep {
+        check,
+        target: target.to_string(),
+        status: DiagnosticsStatus::Skipped,
+        duration_ms: 0,
+        details: None,
+        error: None,
+    };
+    let mut res = None;
+    if !skip {
+        let start = Instant::now();
+        match fut.await {
+            Ok((value, details)) => {
+                step.status = DiagnosticsStatus::Ok;
+                step.details = details;
+                res = Some(value);
+            }
+            Err(err) => {
+                step.status = DiagnosticsStatus::Failed;
+                step.error = Some(format!("{err:#}"));
+            }
+        }
+        step.duration_ms = start.elapsed().as_millis().try_into().unwrap_or(u64::MAX);
+    }
+    steps.push(step);
+    res
+}
+
+/// Checks DNS resolution, TCP connection and TLS handshake for a single server.
+///
+/// Returns true if all checks succeeded.
+async fn check_server(
+    context: &Context,
+    steps: &mut Vec<DiagnosticsStep>,
+    param: &LoginParam,
+    lp: &ServerLoginParam,
+    provider_strict_tls: bool,
+) -> bool {
+    let target = format!("{}:{}", lp.server, lp.port);
+    let strict_tls = match lp.certificate_checks {
+        CertificateChecks::Automatic => provider_strict_tls,
+        CertificateChecks::Strict => true,
+        CertificateChecks::AcceptInvalidCertificates
+        | CertificateChecks::AcceptInvalidCertificates2 => false,
+    };
+
+    // With a proxy, hostname is resolved by the proxy.
+    let resolved = if param.socks5_config.is_none() {
+        run_step(steps, DiagnosticsCheck::Dns, &target, false, async {
+            let addrs = timeout(
+                DIAGNOSTICS_TIMEOUT,
+                tokio::net::lookup_host((lp.server.as_str(), lp.port)),
+            )
+            .await
+            .context("DNS lookup timeout")?
+            .context("DNS lookup failure")?
+            .map(|addr| addr.ip().to_string())
+            .collect::<Vec<_>>();
+            ensure!(!addrs.is_empty(), "no DNS resolution results");
+            Ok(((), Some(addrs.join(", "))))
+        })
+        .await
+        .is_some()
+    } else {
+        true
+    };
+
+    let stream = run_step(steps, DiagnosticsCheck::Tcp, &target, !resolved, async {
+        let stream: Box<dyn SessionStream> = match &param.socks5_config {
+            Some(socks5_config) => {
+                socks5_config
+                    .connect(
+                        context,
+                        &lp.server,
+                        lp.port,
+                        DIAGNOSTICS_TIMEOUT,
+                        strict_tls,
+                    )
+                    .await?
+            }
+            None => Box::new(
+                connect_tcp(
+                    context,
+                    &lp.server,
+                    lp.port,
+                    DIAGNOSTICS_TIMEOUT,
+                    strict_tls,
+                )
+                .await?,
+            ),
+        };
+        let details = param
+            .socks5_config
+            .as_ref()
+            .map(|socks5_config| format!("via proxy {socks5_config}"));
+        Ok((stream, details))
+    })
+    .await;
+
+    // STARTTLS is checked as part of the login.
+    if lp.security != Socket::Ssl {
+        return stream.is_some();
+    }
+    run_step(
+        steps,
+        DiagnosticsCheck::Tls,
+        &target,
+        stream.is_none(),
+        async {
+            let stream = stream.context("no connection")?;
+            let tls_stream = wrap_tls(strict_tls, &lp.server, stream).await?;
+            check_certificate_pin(context, &lp.server, &tls_stream).await?;
+            Ok(((), Some(format!("strict_tls={strict_tls}"))))
+        },
+    )
+    .await
+    .is_some()
+}
+
+impl Context {
+    /// Runs network diagnostics for the configured account.
+    ///
+    /// DNS resolution, TCP connection and TLS handshake are checked
+    /// for the IMAP and SMTP server, followed by IMAP login, access to the inbox
+    /// and SMTP login.
+    /// Steps depending on a failed step are reported as skipped.
+    ///
+    /// The checks use separate connections, so IO does not need to be stopped.
+    pub async fn run_network_diagnostics(&self) -> Result<Vec<DiagnosticsStep>> {
+        ensure!(self.is_configured().await?, "Not configured");
+        self.ensure_network_allowed()?;
+        let param = LoginParam::load_configured_params(self).await?;
+        let provider_strict_tls = param
+            .provider
+            .map_or(param.socks5_config.is_some(), |provider| {
+                provider.opt.strict_tls
+            });
+        let mut steps = Vec::new();
+
+        // IMAP.
+        let imap_target = format!("{}:{}", param.imap.server, param.imap.port);
+        let imap_ok =
+            check_server(self, &mut steps, &param, &param.imap, provider_strict_tls).await;
+        let imap = run_step(
+            &mut steps,
+            DiagnosticsCheck::ImapLogin,
+            &imap_target,
+            !imap_ok,
+            async {
+                let (_sender, receiver) = async_channel::bounded(1);
+                let mut imap = Imap::new(
+                    &param.imap,
+                    param.socks5_config.clone(),
+                    &param.addr,
+                    provider_strict_tls,
+                    receiver,
+                )?;
+                imap.connect(self).await?;
+                Ok((imap, None))
+            },
+        )
+        .await;
+        let inbox = self
+            .get_config(Config::ConfiguredInboxFolder)
+            .await?
+            .unwrap_or_else(|| "INBOX".to_string());
+        run_step(
+            &mut steps,
+            DiagnosticsCheck::FolderAccess,
+            &inbox,
+            imap.is_none(),
+            async {
+                let mut imap = imap.context("not logged in")?;
+                let session = imap.session.as_mut().context("no IMAP session")?;
+                let mailbox = session.examine(&inbox).await?;
+                Ok(((), Some(format!("{} messages", mailbox.exists))))
+            },
+        )
+        .await;
+
+        // SMTP.
+        let smtp_target = format!("{}:{}", param.smtp.server, param.smtp.port);
+        let smtp_ok =
+            check_server(self, &mut steps, &param, &param.smtp, provider_strict_tls).await;
+        run_step(
+            &mut steps,
+            DiagnosticsCheck::SmtpLogin,
+            &smtp_target,
+            !smtp_ok,
+            async {
+                let mut smtp = Smtp::new();
+                smtp.connect(
+                    self,
+                    &param.smtp,
+                    &param.socks5_config,
+                    &param.addr,
+                    provider_strict_tls,
+                )
+                .await?;
+                smtp.disconnect().await;
+                Ok(((), None))
+            },
+        )
+        .await;
+
+        Ok(steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::indexing_slicing)]
+
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_run_step() {
+        let mut steps = Vec::new();
+        let res = run_step(
+            &mut steps,
+            DiagnosticsCheck::Dns,
+            "example.org:993",
+            false,
+            async { Ok((42, Some("127.0.0.1".to_string()))) },
+        )
+        .await;
+        assert_eq!(res, Some(42));
+
+        let res: Option<()> = run_step(
+            &mut steps,
+            DiagnosticsCheck::Tcp,
+            "example.org:993",
+            false,
+            async { anyhow::bail!("connection refused") },
+        )
+        .await;
+        assert_eq!(res, None);
+
+        let res: Option<()> = run_step(
+            &mut steps,
+            DiagnosticsCheck::Tls,
+            "example.org:993",
+            true,
+            async { unreachable!() },
+        )
+        .await;
+        assert_eq!(res, None);
+
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0].status, DiagnosticsStatus::Ok);
+        assert_eq!(steps[0].details.as_deref(), Some("127.0.0.1"));
+        assert_eq!(steps[1].status, DiagnosticsStatus::Failed);
+        assert_eq!(steps[1].error.as_deref(), Some("connection refused"));
+        assert_eq!(steps[2].status, DiagnosticsStatus::Skipped);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_run_network_diagnostics_unconfigured() {
+        let t = TestContext::new().await;
+        assert!(t.run_network_diagnostics().await.is_err());
+    }
+}
@@ -2,10 +2,11 @@
 
 use std::time::Duration;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use mime::Mime;
 
 use crate::context::Context;
+use crate::net::traffic::TrafficProtocol;
 use crate::socks::Socks5Config;
 
 const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
@@ -25,7 +26,11 @@ pub struct Response {
 
 /// Retrieves the text contents of URL using HTTP GET request.
 pub async fn read_url(context: &Context, url: &str) -> Result<String> {
-    Ok(read_url_inner(context, url).await?.text().await?)
+    let text = read_url_inner(context, url).await?.text().await?;
+    context
+        .traffic
+        .add_received(TrafficProtocol::Http, text.len() as u64);
+    Ok(text)
 }
 
 /// Retrieves the binary contents of URL using HTTP GET request.
@@ -44,6 +49,9 @@ pub async fn read_url_blob(context: &Context, url: &str) -> Result<Response> {
             .map(|charset| charset.as_str().to_string())
     });
     let blob: Vec<u8> = response.bytes().await?.into();
+    context
+        .traffic
+        .add_received(TrafficProtocol::Http, blob.len() as u64);
     Ok(Response {
         blob,
         mimetype,
@@ -53,7 +61,7 @@ pub async fn read_url_blob(context: &Context, url: &str) -> Result<Response> {
 
 async fn read_url_inner(context: &Context, url: &str) -> Result<reqwest::Response> {
     let socks5_config = Socks5Config::from_database(&context.sql).await?;
-    let client = get_client(socks5_config)?;
+    let client = get_client(context, socks5_config)?;
     let mut url = url.to_string();
 
     // Follow up to 10 http-redirects
@@ -78,10 +86,22 @@ async fn read_url_inner(context: &Context, url: &str) -> Result<reqwest::Respons
     Err(anyhow!("Followed 10 redirections"))
 }
 
-pub(crate) fn get_client(socks5_config: Option<Socks5Config>) -> Result<reqwest::Client> {
+/// Returns an HTTP client using the proxy, if any.
+///
+/// Fails in maintenance mode, see [`Context::set_maintenance_mode`].
+pub(crate) fn get_client(
+    context: &Context,
+    socks5_config: Option<Socks5Config>,
+) -> Result<reqwest::Client> {
+    context.ensure_network_allowed()?;
     let builder = reqwest::ClientBuilder::new().timeout(HTTP_TIMEOUT);
     let builder = if let Some(socks5_config) = socks5_config {
-        let proxy = reqwest::Proxy::all(socks5_config.to_url())?;
+        // Refuse to make the request rather than leaking it outside of Tor.
+        let url = match socks5_config.to_url() {
+            Some(url) => url,
+            None => bail!("HTTP requests are not supported with the built-in Tor client"),
+        };
+        let proxy = reqwest::Proxy::all(url)?;
         builder.proxy(proxy)
     } else {
         // Disable usage of "system" proxy configured via environment variables.
@@ -0,0 +1,291 @@
+//! # TLS certificate pinning.
+//!
+//! Pins protect against MITM attacks using certificates
+//! which are valid, but issued by another CA than the one used by the server.
+//! A pin is either supplied by the user
+//! or learned on the first successful connection if `learn_certificate_pins` is enabled.
+
+use anyhow::{bail, ensure, Context as _, Result};
+use async_native_tls::TlsStream;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::tools::time;
+
+/// What is pinned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinKind {
+    /// SHA-256 hash of the whole DER-encoded certificate.
+    ///
+    /// The pin breaks each time the certificate is renewed.
+    Certificate = 0,
+
+    /// SHA-256 hash of the DER-encoded SubjectPublicKeyInfo.
+    ///
+    /// The pin survives certificate renewals as long as the server key is reused.
+    Spki = 1,
+}
+
+impl PinKind {
+    fn from_i64(kind: i64) -> Result<Self> {
+        match kind {
+            0 => Ok(Self::Certificate),
+            1 => Ok(Self::Spki),
+            _ => bail!("Unknown pin kind {kind}"),
+        }
+    }
+}
+
+/// Certificate pin for a single server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificatePin {
+    /// Server hostname, lowercase.
+    pub hostname: String,
+
+    /// What is pinned.
+    pub kind: PinKind,
+
+    /// Hex-encoded SHA-256 hash.
+    pub sha256: String,
+
+    /// True if the pin was learned on the first connection rather than set explicitly.
+    pub learned: bool,
+
+    /// Timestamp of pin creation.
+    pub timestamp: i64,
+}
+
+/// Returns hex-encoded SHA-256 hashes of the DER-encoded certificate and its SPKI.
+fn certificate_hashes(der: &[u8]) -> Result<(String, String)> {
+    let (_rest, certificate) =
+        x509_parser::parse_x509_certificate(der).context("failed to parse certificate")?;
+    let certificate_hash = hex::encode(Sha256::digest(der));
+    let spki_hash = hex::encode(Sha256::digest(certificate.public_key().raw));
+    Ok((certificate_hash, spki_hash))
+}
+
+async fn get_certificate_pin(context: &Context, hostname: &str) -> Result<Option<CertificatePin>> {
+    context
+        .sql
+        .query_row_optional(
+            "SELECT hostname, kind, sha256, learned, timestamp
+             FROM certificate_pins WHERE hostname=?",
+            (hostname.to_lowercase(),),
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, bool>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            },
+        )
+        .await?
+        .map(|(hostname, kind, sha256, learned, timestamp)| {
+            Ok(CertificatePin {
+                hostname,
+                kind: PinKind::from_i64(kind)?,
+                sha256,
+                learned,
+                timestamp,
+            })
+        })
+        .transpose()
+}
+
+/// Checks the certificate presented by the server against the pin, if any.
+///
+/// If there is no pin and `learn_certificate_pins` is enabled,
+/// SPKI of the presented certificate is pinned.
+pub(crate) async fn check_certificate_pin<T: AsyncRead + AsyncWrite + Unpin>(
+    context: &Context,
+    hostname: &str,
+    tls_stream: &TlsStream<T>,
+) -> Result<()> {
+    let pin = get_certificate_pin(context, hostname).await?;
+    let learn = context
+        .get_config_bool(Config::LearnCertificatePins)
+        .await?;
+    if pin.is_none() && !learn {
+        return Ok(());
+    }
+
+    let der = tls_stream
+        .peer_certificate()?
+        .context("server did not present a certificate")?
+        .to_der()?;
+    let (certificate_hash, spki_hash) = certificate_hashes(&der)?;
+
+    match pin {
+        Some(pin) => {
+            let actual_hash = match pin.kind {
+                PinKind::Certificate => certificate_hash,
+                PinKind::Spki => spki_hash,
+            };
+            if actual_hash != pin.sha256 {
+                bail!(
+                    "Certificate of {} does not match the pinned one (expected {:?} hash {}, got {})",
+                    hostname,
+                    pin.kind,
+                    pin.sha256,
+                    actual_hash
+                );
+            }
+        }
+        None => {
+            info!(
+                context,
+                "Learned certificate pin for {}: SPKI hash {}.", hostname, spki_hash
+            );
+            context
+                .sql
+                .execute(
+                    "INSERT OR IGNORE INTO certificate_pins (hostname, kind, sha256, learned, timestamp)
+                     VALUES (?, ?, ?, 1, ?)",
+                    (
+                        hostname.to_lowercase(),
+                        PinKind::Spki as i64,
+                        spki_hash,
+                        time(),
+                    ),
+                )
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+impl Context {
+    /// Returns all certificate pins.
+    pub async fn get_certificate_pins(&self) -> Result<Vec<CertificatePin>> {
+        let pins = self
+            .sql
+            .query_map(
+                "SELECT hostname, kind, sha256, learned, timestamp
+                 FROM certificate_pins ORDER BY hostname",
+                (),
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, bool>(3)?,
+                        row.get::<_, i64>(4)?,
+                    ))
+                },
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .await?
+            .into_iter()
+            .map(|(hostname, kind, sha256, learned, timestamp)| {
+                Ok(CertificatePin {
+                    hostname,
+                    kind: PinKind::from_i64(kind)?,
+                    sha256,
+                    learned,
+                    timestamp,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(pins)
+    }
+
+    /// Pins the certificate or SPKI of the server with the given hostname,
+    /// replacing existing pin if any.
+    ///
+    /// `sha256` is the hex-encoded SHA-256 hash of the DER-encoded certificate or SPKI.
+    pub async fn set_certificate_pin(
+        &self,
+        hostname: &str,
+        kind: PinKind,
+        sha256: &str,
+    ) -> Result<()> {
+        let sha256 = sha256.trim().to_lowercase();
+        ensure!(
+            sha256.len() == 64 && sha256.chars().all(|c| c.is_ascii_hexdigit()),
+            "Invalid SHA-256 hash {sha256:?}"
+        );
+        self.sql
+            .execute(
+                "INSERT OR REPLACE INTO certificate_pins (hostname, kind, sha256, learned, timestamp)
+                 VALUES (?, ?, ?, 0, ?)",
+                (hostname.to_lowercase(), kind as i64, sha256, time()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Removes the certificate pin of the server with the given hostname.
+    ///
+    /// If `learn_certificate_pins` is enabled,
+    /// a new pin will be learned on the next connection.
+    pub async fn remove_certificate_pin(&self, hostname: &str) -> Result<()> {
+        self.sql
+            .execute(
+                "DELETE FROM certificate_pins WHERE hostname=?",
+                (hostname.to_lowercase(),),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::indexing_slicing)]
+
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[test]
+    fn test_certificate_hashes() -> Result<()> {
+        let der = include_bytes!("../../assets/root-certificates/letsencrypt/isrgrootx1.der");
+        let (certificate_hash, spki_hash) = certificate_hashes(der)?;
+        assert_eq!(
+            certificate_hash,
+            "96bcec06264976f37460779acf28c5a7cfe8a3c0aae11a8ffcee05c0bddf08c6"
+        );
+        assert_eq!(
+            spki_hash,
+            "0b9fa5a59eed715c26c1020c711b4f6ec42d58b0015e14337a39dad301c5afc3"
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_certificate_pins() -> Result<()> {
+        let t = TestContext::new().await;
+        assert!(t.get_certificate_pins().await?.is_empty());
+
+        assert!(t
+            .set_certificate_pin("imap.example.org", PinKind::Spki, "foobar")
+            .await
+            .is_err());
+
+        t.set_certificate_pin(
+            "IMAP.example.org",
+            PinKind::Spki,
+            "0B9FA5A59EED715C26C1020C711B4F6EC42D58B0015E14337A39DAD301C5AFC3",
+        )
+        .await?;
+        let pins = t.get_certificate_pins().await?;
+        assert_eq!(pins.len(), 1);
+        assert_eq!(pins[0].hostname, "imap.example.org");
+        assert_eq!(pins[0].kind, PinKind::Spki);
+        assert_eq!(
+            pins[0].sha256,
+            "0b9fa5a59eed715c26c1020c711b4f6ec42d58b0015e14337a39dad301c5afc3"
+        );
+        assert!(!pins[0].learned);
+
+        t.remove_certificate_pin("imap.example.org").await?;
+        assert!(t.get_certificate_pins().await?.is_empty());
+        Ok(())
+    }
+}
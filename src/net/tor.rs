@@ -0,0 +1,88 @@
+//! # Built-in Tor client.
+//!
+//! Connections are made using [arti](https://gitlab.torproject.org/tpo/core/arti)
+//! if Delta Chat is built with `tor` feature,
+//! so there is no need to run a separate Tor daemon exposing a SOCKS5 proxy.
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::context::Context;
+use crate::net::session::SessionStream;
+
+/// Returns true if the hostname is a Tor onion service address.
+pub(crate) fn is_onion(hostname: &str) -> bool {
+    hostname
+        .trim_end_matches('.')
+        .to_ascii_lowercase()
+        .ends_with(".onion")
+}
+
+/// Connects to the target through the built-in Tor client,
+/// bootstrapping the client first if needed.
+#[cfg(feature = "tor")]
+pub(crate) async fn connect(
+    context: &Context,
+    target_host: &str,
+    target_port: u16,
+    timeout_val: Duration,
+) -> Result<Box<dyn SessionStream>> {
+    use anyhow::Context as _;
+    use arti_client::config::TorClientConfigBuilder;
+    use arti_client::TorClient;
+    use tokio::time::timeout;
+    use tokio_io_timeout::TimeoutStream;
+
+    let tor_client = context
+        .tor_client
+        .get_or_try_init(|| async {
+            let tor_dir = context.get_dbfile().with_extension("tor");
+            let mut builder = TorClientConfigBuilder::from_directories(
+                tor_dir.join("state"),
+                tor_dir.join("cache"),
+            );
+            builder.address_filter().allow_onion_addrs(true);
+            let config = builder.build()?;
+            info!(context, "Bootstrapping built-in Tor client.");
+            let tor_client = TorClient::create_bootstrapped(config).await?;
+            anyhow::Ok(tor_client)
+        })
+        .await
+        .context("failed to bootstrap Tor client")?;
+
+    let stream = timeout(timeout_val, tor_client.connect((target_host, target_port)))
+        .await
+        .context("connection timeout")?
+        .context("connection failure")?;
+    let mut timeout_stream = TimeoutStream::new(stream);
+    timeout_stream.set_write_timeout(Some(timeout_val));
+    timeout_stream.set_read_timeout(Some(timeout_val));
+    Ok(Box::new(Box::pin(timeout_stream)))
+}
+
+/// Fails as Delta Chat is built without `tor` feature.
+#[cfg(not(feature = "tor"))]
+pub(crate) async fn connect(
+    _context: &Context,
+    _target_host: &str,
+    _target_port: u16,
+    _timeout_val: Duration,
+) -> Result<Box<dyn SessionStream>> {
+    anyhow::bail!("Delta Chat is built without Tor support")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_onion() {
+        assert!(is_onion(
+            "g77kjrad6bafzzyldqvffq6kxlsgphcygptxhnn4xlnktfgaqshilmyd.onion"
+        ));
+        assert!(is_onion("example.ONION."));
+        assert!(!is_onion("imap.example.org"));
+        assert!(!is_onion("onion"));
+    }
+}
@@ -0,0 +1,274 @@
+//! # Traffic statistics.
+//!
+//! Bytes sent and received over IMAP and SMTP connections are counted
+//! above the TLS layer, so TLS overhead is not included.
+//! For HTTP, only the size of downloaded response bodies is counted.
+
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::context::Context;
+use crate::net::session::SessionStream;
+use crate::tools::time;
+
+/// Protocol the traffic is counted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrafficProtocol {
+    Imap,
+    Smtp,
+    Http,
+}
+
+impl TrafficProtocol {
+    /// Raw config keys storing persisted byte counts sent and received.
+    fn config_keys(self) -> (&'static str, &'static str) {
+        match self {
+            TrafficProtocol::Imap => ("traffic_imap_sent", "traffic_imap_received"),
+            TrafficProtocol::Smtp => ("traffic_smtp_sent", "traffic_smtp_received"),
+            TrafficProtocol::Http => ("traffic_http_sent", "traffic_http_received"),
+        }
+    }
+}
+
+/// In-memory byte counters not yet persisted to the database.
+#[derive(Debug, Default)]
+pub(crate) struct TrafficCounters {
+    imap: (AtomicU64, AtomicU64),
+    smtp: (AtomicU64, AtomicU64),
+    http: (AtomicU64, AtomicU64),
+}
+
+impl TrafficCounters {
+    fn get(&self, protocol: TrafficProtocol) -> &(AtomicU64, AtomicU64) {
+        match protocol {
+            TrafficProtocol::Imap => &self.imap,
+            TrafficProtocol::Smtp => &self.smtp,
+            TrafficProtocol::Http => &self.http,
+        }
+    }
+
+    pub(crate) fn add_sent(&self, protocol: TrafficProtocol, bytes: u64) {
+        self.get(protocol).0.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_received(&self, protocol: TrafficProtocol, bytes: u64) {
+        self.get(protocol).1.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Traffic of a single protocol.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolTraffic {
+    /// Bytes sent.
+    pub sent: u64,
+
+    /// Bytes received.
+    pub received: u64,
+}
+
+/// Traffic statistics of an account.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrafficStats {
+    pub imap: ProtocolTraffic,
+    pub smtp: ProtocolTraffic,
+    pub http: ProtocolTraffic,
+
+    /// Timestamp of the last reset, 0 if the statistics were never reset.
+    pub since: i64,
+}
+
+/// Stream counting bytes sent and received.
+#[derive(Debug)]
+pub(crate) struct CountingStream<T> {
+    inner: T,
+    counters: Arc<TrafficCounters>,
+    protocol: TrafficProtocol,
+}
+
+impl<T> CountingStream<T> {
+    pub(crate) fn new(context: &Context, protocol: TrafficProtocol, inner: T) -> Self {
+        Self {
+            inner,
+            counters: context.traffic.clone(),
+            protocol,
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for CountingStream<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled_before = buf.filled().len();
+        let res = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let received = buf.filled().len().saturating_sub(filled_before);
+        if received > 0 {
+            self.counters
+                .add_received(self.protocol, received.try_into().unwrap_or(u64::MAX));
+        }
+        res
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for CountingStream<T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let res = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(sent)) = res {
+            self.counters
+                .add_sent(self.protocol, sent.try_into().unwrap_or(u64::MAX));
+        }
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+impl<T: SessionStream> SessionStream for CountingStream<T> {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.inner.set_read_timeout(timeout)
+    }
+}
+
+impl Context {
+    /// Returns the number of bytes sent and received by this account per protocol
+    /// since the last reset.
+    pub async fn get_traffic_stats(&self) -> Result<TrafficStats> {
+        let mut stats = TrafficStats {
+            since: self
+                .sql
+                .get_raw_config_int64("traffic_since")
+                .await?
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        for protocol in [
+            TrafficProtocol::Imap,
+            TrafficProtocol::Smtp,
+            TrafficProtocol::Http,
+        ] {
+            let (sent_key, received_key) = protocol.config_keys();
+            let (sent, received) = self.traffic.get(protocol);
+            let traffic = ProtocolTraffic {
+                sent: stored_bytes(self, sent_key).await? + sent.load(Ordering::Relaxed),
+                received: stored_bytes(self, received_key).await?
+                    + received.load(Ordering::Relaxed),
+            };
+            match protocol {
+                TrafficProtocol::Imap => stats.imap = traffic,
+                TrafficProtocol::Smtp => stats.smtp = traffic,
+                TrafficProtocol::Http => stats.http = traffic,
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Resets traffic statistics.
+    pub async fn reset_traffic_stats(&self) -> Result<()> {
+        for protocol in [
+            TrafficProtocol::Imap,
+            TrafficProtocol::Smtp,
+            TrafficProtocol::Http,
+        ] {
+            let (sent_key, received_key) = protocol.config_keys();
+            let (sent, received) = self.traffic.get(protocol);
+            sent.store(0, Ordering::Relaxed);
+            received.store(0, Ordering::Relaxed);
+            self.sql.set_raw_config(sent_key, None).await?;
+            self.sql.set_raw_config(received_key, None).await?;
+        }
+        self.sql
+            .set_raw_config_int64("traffic_since", time())
+            .await?;
+        Ok(())
+    }
+
+    /// Persists in-memory traffic counters to the database.
+    pub(crate) async fn flush_traffic_stats(&self) -> Result<()> {
+        for protocol in [
+            TrafficProtocol::Imap,
+            TrafficProtocol::Smtp,
+            TrafficProtocol::Http,
+        ] {
+            let (sent_key, received_key) = protocol.config_keys();
+            let (sent, received) = self.traffic.get(protocol);
+            for (key, counter) in [(sent_key, sent), (received_key, received)] {
+                let bytes = counter.swap(0, Ordering::Relaxed);
+                if bytes > 0 {
+                    let stored = stored_bytes(self, key).await?;
+                    self.sql
+                        .set_raw_config_int64(key, stored.saturating_add(bytes) as i64)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn stored_bytes(context: &Context, key: &str) -> Result<u64> {
+    let bytes = context
+        .sql
+        .get_raw_config_int64(key)
+        .await?
+        .unwrap_or_default();
+    Ok(u64::try_from(bytes).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_traffic_stats() -> Result<()> {
+        let t = TestContext::new().await;
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut stream = CountingStream::new(&t, TrafficProtocol::Imap, client);
+
+        stream.write_all(b"a1 NOOP\r\n").await?;
+        server.write_all(b"a1 OK\r\n").await?;
+        let mut buf = [0u8; 7];
+        stream.read_exact(&mut buf).await?;
+        t.traffic.add_received(TrafficProtocol::Http, 100);
+
+        let stats = t.get_traffic_stats().await?;
+        assert_eq!(stats.imap.sent, 9);
+        assert_eq!(stats.imap.received, 7);
+        assert_eq!(stats.http.received, 100);
+        assert_eq!(stats.smtp, ProtocolTraffic::default());
+
+        // Statistics survive flushing.
+        t.flush_traffic_stats().await?;
+        assert_eq!(t.get_traffic_stats().await?, stats);
+        t.traffic.add_sent(TrafficProtocol::Imap, 1);
+        assert_eq!(t.get_traffic_stats().await?.imap.sent, 10);
+
+        t.reset_traffic_stats().await?;
+        let stats = t.get_traffic_stats().await?;
+        assert_eq!(stats.imap, ProtocolTraffic::default());
+        assert_eq!(stats.http, ProtocolTraffic::default());
+        assert!(stats.since > 0);
+        Ok(())
+    }
+}
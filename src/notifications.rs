@@ -0,0 +1,654 @@
+//! # Notification helpers.
+//!
+//! Helpers for building stacked OS notifications from fresh messages
+//! and quiet hours during which notifications are suppressed.
+
+use std::path::PathBuf;
+
+use anyhow::{ensure, Result};
+use chrono::{Datelike, Local, Timelike};
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::chat::{Chat, ChatId};
+use crate::color::color_int_to_hex_string;
+use crate::config::Config;
+use crate::constants::{NotificationPrivacy, DC_CHAT_ID_LAST_SPECIAL};
+use crate::contact::{Contact, ContactId};
+use crate::context::Context;
+use crate::message::{Message, MessageState, MsgId, Viewtype};
+use crate::stock_str;
+use crate::sync::SyncData;
+use crate::tools::time;
+
+/// Raw config key storing quiet hours as JSON.
+const QUIET_HOURS_KEY: &str = "quiet_hours";
+
+/// Raw config key storing the timestamp of the last change of quiet hours.
+const QUIET_HOURS_TIMESTAMP_KEY: &str = "quiet_hours_timestamp";
+
+/// Time range during which notifications are suppressed.
+///
+/// Times are minutes since midnight in the local time of the device.
+/// If `end` is not after `start`, the range ends on the next day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    /// Day of the week the range starts on, 0 is Monday and 6 is Sunday.
+    pub weekday: u8,
+
+    /// Start of the range in minutes since midnight.
+    pub start: u16,
+
+    /// End of the range in minutes since midnight.
+    pub end: u16,
+}
+
+impl QuietHours {
+    fn validate(&self) -> Result<()> {
+        ensure!(self.weekday < 7, "Invalid weekday {}", self.weekday);
+        ensure!(self.start < 24 * 60, "Invalid start {}", self.start);
+        ensure!(self.end <= 24 * 60, "Invalid end {}", self.end);
+        Ok(())
+    }
+
+    /// Returns true if the given minute of the given weekday falls into the range.
+    fn contains(&self, weekday: u8, minute: u16) -> bool {
+        if self.start < self.end {
+            weekday == self.weekday && self.start <= minute && minute < self.end
+        } else {
+            (weekday == self.weekday && self.start <= minute)
+                || (weekday == (self.weekday + 1) % 7 && minute < self.end)
+        }
+    }
+}
+
+/// Action which can be offered in a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationAction {
+    /// Reply to the chat from the notification.
+    Reply,
+
+    /// Mark the message as seen.
+    MarkSeen,
+
+    /// Accept the contact request.
+    AcceptRequest,
+}
+
+/// Content of a notification for a single message,
+/// reduced according to the `notification_privacy` option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationContent {
+    /// Notification title, the chat name unless hidden.
+    pub title: String,
+
+    /// Avatar of the chat, unless hidden.
+    pub chat_profile_image: Option<PathBuf>,
+
+    /// Part displayed before ":", such as the sender name in groups.
+    pub summary_prefix: Option<String>,
+
+    /// Summary of the message, unless hidden.
+    pub summary_text: String,
+
+    /// Image, GIF or sticker attached to the message, unless hidden.
+    pub image: Option<PathBuf>,
+
+    /// Accent color of the chat, unless hidden.
+    pub chat_color: Option<u32>,
+
+    /// SVG avatar showing the first letter of the chat name on the chat color,
+    /// if the chat has no avatar and is not hidden.
+    pub chat_letter_avatar: Option<String>,
+
+    /// Actions applicable to the message.
+    pub actions: Vec<NotificationAction>,
+}
+
+impl NotificationContent {
+    /// Builds notification content for a message of the given chat.
+    pub async fn load(context: &Context, msg: &Message, chat: &Chat) -> Result<Self> {
+        let mut actions = Vec::new();
+        if chat.is_contact_request() {
+            actions.push(NotificationAction::AcceptRequest);
+        } else if chat.can_send(context).await? {
+            actions.push(NotificationAction::Reply);
+        }
+        actions.push(NotificationAction::MarkSeen);
+
+        let privacy = context.get_notification_privacy().await?;
+        if privacy == NotificationPrivacy::Generic {
+            let text = stock_str::new_msg(context).await;
+            return Ok(Self {
+                title: text.clone(),
+                chat_profile_image: None,
+                summary_prefix: None,
+                summary_text: text,
+                image: None,
+                chat_color: None,
+                chat_letter_avatar: None,
+                actions,
+            });
+        }
+
+        let summary = msg.get_summary(context, Some(chat)).await?;
+        let (summary_text, image) = if privacy == NotificationPrivacy::SenderOnly {
+            (stock_str::new_msg(context).await, None)
+        } else if matches!(
+            msg.get_viewtype(),
+            Viewtype::Image | Viewtype::Gif | Viewtype::Sticker
+        ) {
+            (summary.text, msg.get_file(context))
+        } else {
+            (summary.text, None)
+        };
+        let chat_color = chat.get_color(context).await?;
+        let chat_profile_image = chat.get_profile_image(context).await?;
+        let chat_letter_avatar = if chat_profile_image.is_none() {
+            Some(letter_avatar_svg(chat.get_name(), chat_color)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            title: chat.get_name().to_string(),
+            chat_profile_image,
+            summary_prefix: summary.prefix.map(|prefix| prefix.to_string()),
+            summary_text,
+            image,
+            chat_color: Some(chat_color),
+            chat_letter_avatar,
+            actions,
+        })
+    }
+
+    /// Returns the summary prefixed with the prefix if any, e.g. "Alice: Hello".
+    pub fn summary_line(&self) -> String {
+        match &self.summary_prefix {
+            Some(prefix) => format!("{}: {}", prefix, self.summary_text),
+            None => self.summary_text.clone(),
+        }
+    }
+}
+
+/// Renders an SVG avatar showing the first letter of `name` on a circle of the given color.
+fn letter_avatar_svg(name: &str, color: u32) -> Result<String> {
+    let letter = name
+        .chars()
+        .next()
+        .unwrap_or('#')
+        .to_uppercase()
+        .to_string();
+    let mut svg = String::with_capacity(400);
+    let mut w = tagger::new(&mut svg);
+    w.elem("svg", |d| {
+        d.attr("xmlns", "http://www.w3.org/2000/svg")?;
+        d.attr("viewBox", "0 0 100 100")?;
+        Ok(())
+    })?
+    .build(|w| {
+        w.single("circle", |d| {
+            d.attr("cx", 50)?;
+            d.attr("cy", 50)?;
+            d.attr("r", 50)?;
+            d.attr("style", format!("fill:{}", color_int_to_hex_string(color)))
+        })?;
+        w.elem("text", |d| {
+            d.attr("x", 50)?;
+            d.attr("y", 50)?;
+            d.attr("text-anchor", "middle")?;
+            d.attr("dominant-baseline", "central")?;
+            d.attr(
+                "style",
+                "font-family:sans-serif;font-weight:400;font-size:60px;fill:#ffffff;",
+            )
+        })?
+        .build(|w| w.put_raw(letter))
+    })?;
+    Ok(svg)
+}
+
+/// Fresh messages of a single chat, to be shown as one notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationGroup {
+    /// Chat the messages belong to.
+    pub chat_id: ChatId,
+
+    /// Chat name, to be used as notification title.
+    ///
+    /// Replaced with a generic text if `notification_privacy` hides the chat.
+    pub chat_name: String,
+
+    /// Messages of the group, newest first.
+    pub msg_ids: Vec<MsgId>,
+
+    /// Summary line such as "3 messages from Alice".
+    ///
+    /// For a group containing a single message, this is the same as `preview`.
+    pub summary: String,
+
+    /// Preview of the newest message, prefixed with the sender like the chatlist summary.
+    pub preview: String,
+
+    /// Sort timestamp of the newest message.
+    pub timestamp: i64,
+
+    /// True if the notification should be shown silently because of quiet hours.
+    pub suppress_notification: bool,
+}
+
+/// Groups messages by chat.
+///
+/// Groups are sorted by their newest message, newest first.
+/// Messages which are trashed or do not belong to a chat are skipped.
+/// Summaries and previews reveal only as much as allowed by `notification_privacy`.
+pub async fn get_notification_groups(
+    context: &Context,
+    msg_ids: &[MsgId],
+) -> Result<Vec<NotificationGroup>> {
+    let suppress_notification = context.is_quiet_time().await?;
+    let privacy = context.get_notification_privacy().await?;
+
+    // Messages of each chat, in the order chats appear.
+    let mut chats: Vec<(ChatId, Vec<Message>)> = Vec::new();
+    for msg_id in msg_ids {
+        let msg = Message::load_from_db(context, *msg_id).await?;
+        if msg.chat_id.is_special() {
+            continue;
+        }
+        match chats
+            .iter_mut()
+            .find(|(chat_id, _)| *chat_id == msg.chat_id)
+        {
+            Some((_, msgs)) => msgs.push(msg),
+            None => chats.push((msg.chat_id, vec![msg])),
+        }
+    }
+
+    let mut groups = Vec::with_capacity(chats.len());
+    for (chat_id, mut msgs) in chats {
+        msgs.sort_by_key(|msg| std::cmp::Reverse((msg.timestamp_sort, msg.id)));
+        msgs.dedup_by_key(|msg| msg.id);
+        let chat = Chat::load_from_db(context, chat_id).await?;
+        let newest = match msgs.first() {
+            Some(msg) => msg,
+            None => continue,
+        };
+
+        let content = NotificationContent::load(context, newest, &chat).await?;
+        let preview = content.summary_line();
+
+        let summary = if msgs.len() == 1 {
+            preview.clone()
+        } else if privacy == NotificationPrivacy::Generic {
+            stock_str::new_msgs(context, msgs.len()).await
+        } else if msgs.iter().all(|msg| msg.from_id == newest.from_id)
+            && newest.from_id != ContactId::SELF
+        {
+            let contact = Contact::get_by_id(context, newest.from_id).await?;
+            stock_str::notification_msgs_from(
+                context,
+                msgs.len(),
+                &newest.get_sender_name(&contact),
+            )
+            .await
+        } else {
+            stock_str::notification_msgs_in(context, msgs.len(), chat.get_name()).await
+        };
+
+        groups.push(NotificationGroup {
+            chat_id,
+            chat_name: content.title,
+            timestamp: newest.timestamp_sort,
+            msg_ids: msgs.iter().map(|msg| msg.id).collect(),
+            summary,
+            preview,
+            suppress_notification,
+        });
+    }
+    groups.sort_by_key(|group| std::cmp::Reverse(group.timestamp));
+    Ok(groups)
+}
+
+/// Adds messages to the queue of messages the user was not notified about yet.
+pub(crate) async fn add_pending_notifications(context: &Context, msg_ids: &[MsgId]) -> Result<()> {
+    let timestamp = time();
+    context
+        .sql
+        .transaction(|transaction| {
+            let mut stmt = transaction.prepare(
+                "INSERT OR IGNORE INTO pending_notifications (msg_id, timestamp) VALUES (?, ?)",
+            )?;
+            for msg_id in msg_ids {
+                stmt.execute((msg_id, timestamp))?;
+            }
+            Ok(())
+        })
+        .await
+}
+
+impl Context {
+    /// Returns messages the user was not notified about yet, oldest first,
+    /// e.g. to post notifications missed while the UI was not running.
+    ///
+    /// Messages are added when `DC_EVENT_INCOMING_MSG` is emitted for them
+    /// and are returned until they are removed with [`Context::clear_pending_notifications`]
+    /// or are not fresh anymore.
+    pub async fn get_pending_notifications(&self) -> Result<Vec<MsgId>> {
+        self.sql
+            .query_map(
+                "SELECT p.msg_id
+                 FROM pending_notifications p
+                 INNER JOIN msgs m ON p.msg_id=m.id
+                 WHERE m.state=? AND m.chat_id>?
+                 ORDER BY p.msg_id",
+                (MessageState::InFresh, DC_CHAT_ID_LAST_SPECIAL),
+                |row| row.get::<_, MsgId>(0),
+                |rows| {
+                    rows.collect::<std::result::Result<Vec<_>, _>>()
+                        .map_err(Into::into)
+                },
+            )
+            .await
+    }
+
+    /// Removes the given messages from the queue of pending notifications,
+    /// all messages if `msg_ids` is `None`.
+    pub async fn clear_pending_notifications(&self, msg_ids: Option<&[MsgId]>) -> Result<()> {
+        match msg_ids {
+            Some(msg_ids) => {
+                self.sql
+                    .transaction(|transaction| {
+                        let mut stmt = transaction
+                            .prepare("DELETE FROM pending_notifications WHERE msg_id=?")?;
+                        for msg_id in msg_ids {
+                            stmt.execute((msg_id,))?;
+                        }
+                        Ok(())
+                    })
+                    .await?;
+            }
+            None => {
+                self.sql
+                    .execute("DELETE FROM pending_notifications", ())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns how much content notifications may reveal,
+    /// as set in the `notification_privacy` option.
+    pub async fn get_notification_privacy(&self) -> Result<NotificationPrivacy> {
+        Ok(
+            NotificationPrivacy::from_i32(self.get_config_int(Config::NotificationPrivacy).await?)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Sets the quiet hours during which notifications are suppressed,
+    /// replacing the previous ones.
+    ///
+    /// Quiet hours are synchronized to other devices if `send_sync_msgs` is enabled.
+    pub async fn set_quiet_hours(&self, quiet_hours: &[QuietHours]) -> Result<()> {
+        for range in quiet_hours {
+            range.validate()?;
+        }
+        let timestamp = time();
+        self.set_quiet_hours_ex(quiet_hours, timestamp).await?;
+        self.add_sync_item_with_timestamp(SyncData::SetQuietHours(quiet_hours.to_vec()), timestamp)
+            .await?;
+        self.send_sync_msg().await?;
+        Ok(())
+    }
+
+    async fn set_quiet_hours_ex(&self, quiet_hours: &[QuietHours], timestamp: i64) -> Result<()> {
+        let value = if quiet_hours.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(quiet_hours)?)
+        };
+        self.sql
+            .set_raw_config(QUIET_HOURS_KEY, value.as_deref())
+            .await?;
+        self.sql
+            .set_raw_config_int64(QUIET_HOURS_TIMESTAMP_KEY, timestamp)
+            .await?;
+        Ok(())
+    }
+
+    /// Applies quiet hours received from another device
+    /// unless they were changed later on this device.
+    pub(crate) async fn sync_quiet_hours(
+        &self,
+        quiet_hours: &[QuietHours],
+        timestamp: i64,
+    ) -> Result<()> {
+        let last_timestamp = self
+            .sql
+            .get_raw_config_int64(QUIET_HOURS_TIMESTAMP_KEY)
+            .await?
+            .unwrap_or_default();
+        if timestamp < last_timestamp {
+            info!(self, "Ignoring outdated quiet hours.");
+            return Ok(());
+        }
+        for range in quiet_hours {
+            if let Err(err) = range.validate() {
+                warn!(self, "Ignoring invalid quiet hours: {err:#}.");
+                return Ok(());
+            }
+        }
+        self.set_quiet_hours_ex(quiet_hours, timestamp).await
+    }
+
+    /// Returns the quiet hours during which notifications are suppressed.
+    pub async fn get_quiet_hours(&self) -> Result<Vec<QuietHours>> {
+        match self.sql.get_raw_config(QUIET_HOURS_KEY).await? {
+            Some(value) => Ok(serde_json::from_str(&value)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns true if the current local time falls into the quiet hours.
+    ///
+    /// Notification APIs mark messages as to be shown silently during quiet hours.
+    pub async fn is_quiet_time(&self) -> Result<bool> {
+        let quiet_hours = self.get_quiet_hours().await?;
+        if quiet_hours.is_empty() {
+            return Ok(false);
+        }
+        let now = Local::now();
+        let weekday = now.weekday().num_days_from_monday() as u8;
+        let minute = (now.hour() * 60 + now.minute()) as u16;
+        Ok(quiet_hours
+            .iter()
+            .any(|range| range.contains(weekday, minute)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::indexing_slicing)]
+
+    use super::*;
+    use crate::chat;
+    use crate::message;
+    use crate::receive_imf::receive_imf;
+    use crate::test_utils::{TestContext, TestContextManager};
+
+    #[test]
+    fn test_quiet_hours_contains() {
+        let range = QuietHours {
+            weekday: 0,
+            start: 8 * 60,
+            end: 12 * 60,
+        };
+        assert!(range.contains(0, 8 * 60));
+        assert!(range.contains(0, 11 * 60 + 59));
+        assert!(!range.contains(0, 12 * 60));
+        assert!(!range.contains(1, 9 * 60));
+
+        // Sunday 22:00 to Monday 07:00.
+        let range = QuietHours {
+            weekday: 6,
+            start: 22 * 60,
+            end: 7 * 60,
+        };
+        assert!(range.contains(6, 23 * 60));
+        assert!(range.contains(0, 6 * 60));
+        assert!(!range.contains(0, 7 * 60));
+        assert!(!range.contains(6, 6 * 60));
+        assert!(!range.contains(5, 23 * 60));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_pending_notifications() -> Result<()> {
+        let mut tcm = TestContextManager::new();
+        let alice = tcm.alice().await;
+        let bob = tcm.bob().await;
+        assert!(alice.get_pending_notifications().await?.is_empty());
+
+        let chat_id = bob.create_chat(&alice).await.id;
+        let mut msg_ids = Vec::new();
+        for text in ["one", "two", "three"] {
+            let sent = bob.send_text(chat_id, text).await;
+            msg_ids.push(alice.recv_msg(&sent).await.id);
+        }
+        assert_eq!(alice.get_pending_notifications().await?, msg_ids);
+
+        alice
+            .clear_pending_notifications(Some(&msg_ids[..1]))
+            .await?;
+        assert_eq!(alice.get_pending_notifications().await?, msg_ids[1..]);
+
+        // Seen messages are not pending anymore.
+        message::markseen_msgs(&alice, vec![msg_ids[1]]).await?;
+        assert_eq!(alice.get_pending_notifications().await?, msg_ids[2..]);
+
+        alice.clear_pending_notifications(None).await?;
+        assert!(alice.get_pending_notifications().await?.is_empty());
+
+        // Outgoing messages are not added.
+        let chat_id = alice.create_chat(&bob).await.id;
+        alice.send_text(chat_id, "hi").await;
+        assert!(alice.get_pending_notifications().await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_quiet_hours() -> Result<()> {
+        let alice0 = TestContext::new_alice().await;
+        let alice1 = TestContext::new_alice().await;
+        for a in [&alice0, &alice1] {
+            a.set_config_bool(Config::SendSyncMsgs, true).await?;
+        }
+        assert!(alice0.get_quiet_hours().await?.is_empty());
+        assert!(!alice0.is_quiet_time().await?);
+
+        assert!(alice0
+            .set_quiet_hours(&[QuietHours {
+                weekday: 7,
+                start: 0,
+                end: 60
+            }])
+            .await
+            .is_err());
+
+        // Quiet all the time.
+        let quiet_hours: Vec<QuietHours> = (0..7)
+            .map(|weekday| QuietHours {
+                weekday,
+                start: 0,
+                end: 0,
+            })
+            .collect();
+        alice0.set_quiet_hours(&quiet_hours).await?;
+        assert_eq!(alice0.get_quiet_hours().await?, quiet_hours);
+        assert!(alice0.is_quiet_time().await?);
+
+        let sent_msg = alice0.pop_sent_msg().await;
+        alice1.recv_msg_opt(&sent_msg).await;
+        assert_eq!(alice1.get_quiet_hours().await?, quiet_hours);
+        assert!(alice1.is_quiet_time().await?);
+
+        // Older changes are ignored.
+        alice1.sync_quiet_hours(&[], 1631781316).await?;
+        assert_eq!(alice1.get_quiet_hours().await?, quiet_hours);
+
+        alice1.set_quiet_hours(&[]).await?;
+        let sent_msg = alice1.pop_sent_msg().await;
+        alice0.recv_msg_opt(&sent_msg).await;
+        assert!(alice0.get_quiet_hours().await?.is_empty());
+        assert!(!alice0.is_quiet_time().await?);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_notification_groups() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        for (i, text) in ["hello", "how are you?"].iter().enumerate() {
+            let msg = format!(
+                "From: Bob <bob@example.net>\n\
+                 To: alice@example.org\n\
+                 Message-ID: <{i}@example.net>\n\
+                 Chat-Version: 1.0\n\
+                 Date: Sun, 22 Mar 2020 22:37:5{i} +0000\n\
+                 \n\
+                 {text}\n"
+            );
+            receive_imf(&t, msg.as_bytes(), false).await?;
+        }
+
+        let fresh = t.get_fresh_msgs().await?;
+        assert_eq!(fresh.len(), 2);
+        let groups = get_notification_groups(&t, &fresh).await?;
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].chat_id, chat.id);
+        assert_eq!(groups[0].chat_name, "Bob");
+        assert_eq!(groups[0].msg_ids, fresh);
+        assert_eq!(groups[0].summary, "2 messages from Bob");
+        assert_eq!(groups[0].preview, "how are you?");
+
+        // A single message is summarized by its preview.
+        let groups = get_notification_groups(&t, &fresh[1..]).await?;
+        assert_eq!(groups[0].summary, "hello");
+        assert_eq!(groups[0].preview, "hello");
+
+        // Messages from several senders are summarized by the chat name.
+        let sent_id = chat::send_text_msg(&t, chat.id, "fine".to_string()).await?;
+        let mut msg_ids = fresh.clone();
+        msg_ids.push(sent_id);
+        let groups = get_notification_groups(&t, &msg_ids).await?;
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].msg_ids[0], sent_id);
+        assert_eq!(groups[0].summary, "3 messages in Bob");
+        assert_eq!(groups[0].preview, "Me: fine");
+
+        assert!(get_notification_groups(&t, &[]).await?.is_empty());
+
+        let msg = Message::load_from_db(&t, fresh[0]).await?;
+        let content = NotificationContent::load(&t, &msg, &chat).await?;
+        assert_eq!(content.title, "Bob");
+        assert_eq!(content.summary_text, "how are you?");
+        assert_eq!(
+            content.actions,
+            vec![NotificationAction::Reply, NotificationAction::MarkSeen]
+        );
+        assert_eq!(content.chat_color, Some(chat.get_color(&t).await?));
+        assert!(content.chat_letter_avatar.unwrap().contains(">B</text>"));
+
+        t.set_config(Config::NotificationPrivacy, Some("1")).await?;
+        let groups = get_notification_groups(&t, &fresh).await?;
+        assert_eq!(groups[0].chat_name, "Bob");
+        assert_eq!(groups[0].summary, "2 messages from Bob");
+        assert_eq!(groups[0].preview, "New message");
+
+        t.set_config(Config::NotificationPrivacy, Some("2")).await?;
+        let groups = get_notification_groups(&t, &fresh).await?;
+        assert_eq!(groups[0].chat_name, "New message");
+        assert_eq!(groups[0].summary, "2 new messages");
+        assert_eq!(groups[0].preview, "New message");
+        Ok(())
+    }
+}
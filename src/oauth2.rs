@@ -1,18 +1,26 @@
 //! OAuth 2 module.
 
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
+use futures::future::BoxFuture;
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde::Deserialize;
 
 use crate::config::Config;
 use crate::context::Context;
+use crate::events::EventType;
 use crate::provider;
 use crate::provider::Oauth2Authorizer;
 use crate::socks::Socks5Config;
 use crate::tools::time;
 
+/// How long [`Oauth2TokenRequester`] waits for the UI to answer a token request.
+const OAUTH2_TOKEN_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
 const OAUTH2_GMAIL: Oauth2 = Oauth2 {
     // see <https://developers.google.com/identity/protocols/OAuth2InstalledApp>
     client_id: "959970109878-4mvtgf6feshskf7695nfln6002mom908.apps.googleusercontent.com",
@@ -31,6 +39,123 @@
     get_userinfo: None,
 };
 
+const OAUTH2_MICROSOFT: Oauth2 = Oauth2 {
+    // see <https://learn.microsoft.com/en-us/exchange/client-developer/legacy-protocols/how-to-authenticate-an-imap-pop-smtp-application-by-using-oauth>
+    // There is no built-in client ID, it has to be set using `Config::Oauth2ClientId`.
+    client_id: "",
+    get_code: "https://login.microsoftonline.com/common/oauth2/v2.0/authorize?client_id=$CLIENT_ID&redirect_uri=$REDIRECT_URI&response_type=code&scope=https%3A%2F%2Foutlook.office.com%2FIMAP.AccessAsUser.All%20https%3A%2F%2Foutlook.office.com%2FSMTP.Send%20offline_access",
+    init_token: "https://login.microsoftonline.com/common/oauth2/v2.0/token?client_id=$CLIENT_ID&redirect_uri=$REDIRECT_URI&code=$CODE&grant_type=authorization_code",
+    refresh_token: "https://login.microsoftonline.com/common/oauth2/v2.0/token?client_id=$CLIENT_ID&redirect_uri=$REDIRECT_URI&refresh_token=$REFRESH_TOKEN&grant_type=refresh_token",
+    get_userinfo: None,
+};
+
+/// Source of OAuth 2 access tokens obtained by the embedding application,
+/// e.g. using the sign-in SDK of the platform.
+///
+/// If set using [`Context::set_oauth2_token_provider`],
+/// it is used instead of the built-in authorization code flow,
+/// also for addresses of providers not known to support OAuth 2.
+pub trait Oauth2TokenProvider: fmt::Debug + Send + Sync {
+    /// Returns an access token for `addr`, `None` if there is none.
+    ///
+    /// `regenerate` is true if a fresh token should be obtained,
+    /// e.g. because the previous one was rejected by the server.
+    fn get_access_token<'a>(
+        &'a self,
+        context: &'a Context,
+        addr: &'a str,
+        regenerate: bool,
+    ) -> BoxFuture<'a, Result<Option<String>>>;
+}
+
+/// Token provider asking the UI for access tokens
+/// by emitting [`EventType::Oauth2TokenRequested`].
+///
+/// The UI answers using [`Context::set_oauth2_access_token`].
+/// This is the token provider used by the FFI and JSON-RPC bindings,
+/// see [`Context::set_oauth2_token_requests`].
+#[derive(Debug, Default)]
+pub struct Oauth2TokenRequester;
+
+impl Oauth2TokenProvider for Oauth2TokenRequester {
+    fn get_access_token<'a>(
+        &'a self,
+        context: &'a Context,
+        addr: &'a str,
+        regenerate: bool,
+    ) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move {
+            if !regenerate {
+                let access_token = context.sql.get_raw_config("oauth2_access_token").await?;
+                if access_token.is_some() {
+                    return Ok(access_token);
+                }
+            }
+            context
+                .sql
+                .set_raw_config("oauth2_access_token", None)
+                .await?;
+
+            // Create the future before emitting the event
+            // so the answer is not missed even if the UI responds immediately.
+            let notified = context.oauth2_token_notify.notified();
+            context.emit_event(EventType::Oauth2TokenRequested {
+                addr: addr.to_string(),
+                regenerate,
+            });
+            if tokio::time::timeout(OAUTH2_TOKEN_REQUEST_TIMEOUT, notified)
+                .await
+                .is_err()
+            {
+                warn!(context, "No OAuth2 access token received for {addr}.");
+                return Ok(None);
+            }
+            context.sql.get_raw_config("oauth2_access_token").await
+        })
+    }
+}
+
+impl Context {
+    /// Sets the source of OAuth 2 access tokens, see [`Oauth2TokenProvider`].
+    /// `None` restores the built-in authorization code flow.
+    pub fn set_oauth2_token_provider(&self, provider: Option<Arc<dyn Oauth2TokenProvider>>) {
+        *self
+            .oauth2_token_provider
+            .write()
+            .expect("RwLock is poisoned") = provider;
+    }
+
+    /// Enables or disables requesting OAuth 2 access tokens from the UI
+    /// using [`EventType::Oauth2TokenRequested`] events, see [`Oauth2TokenRequester`].
+    pub fn set_oauth2_token_requests(&self, enabled: bool) {
+        let provider: Option<Arc<dyn Oauth2TokenProvider>> = if enabled {
+            Some(Arc::new(Oauth2TokenRequester))
+        } else {
+            None
+        };
+        self.set_oauth2_token_provider(provider);
+    }
+
+    /// Answers an [`EventType::Oauth2TokenRequested`] event.
+    ///
+    /// `None` or an empty token means that no token could be obtained.
+    pub async fn set_oauth2_access_token(&self, token: Option<&str>) -> Result<()> {
+        let token = token.filter(|token| !token.is_empty());
+        self.sql
+            .set_raw_config("oauth2_access_token", token)
+            .await?;
+        self.oauth2_token_notify.notify_waiters();
+        Ok(())
+    }
+
+    fn get_oauth2_token_provider(&self) -> Option<Arc<dyn Oauth2TokenProvider>> {
+        self.oauth2_token_provider
+            .read()
+            .expect("RwLock is poisoned")
+            .clone()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Oauth2 {
     client_id: &'static str,
@@ -63,11 +188,15 @@ pub async fn get_oauth2_url(
 ) -> Result<Option<String>> {
     let socks5_enabled = context.get_config_bool(Config::Socks5Enabled).await?;
     if let Some(oauth2) = Oauth2::from_address(context, addr, socks5_enabled).await {
+        let client_id = oauth2.get_client_id(context).await?;
+        if client_id.is_empty() {
+            return Ok(None);
+        }
         context
             .sql
             .set_raw_config("oauth2_pending_redirect_uri", Some(redirect_uri))
             .await?;
-        let oauth2_url = replace_in_uri(oauth2.get_code, "$CLIENT_ID", oauth2.client_id);
+        let oauth2_url = replace_in_uri(oauth2.get_code, "$CLIENT_ID", &client_id);
         let oauth2_url = replace_in_uri(&oauth2_url, "$REDIRECT_URI", redirect_uri);
 
         Ok(Some(oauth2_url))
@@ -82,9 +211,21 @@ pub(crate) async fn get_oauth2_access_token(
     code: &str,
     regenerate: bool,
 ) -> Result<Option<String>> {
+    if let Some(token_provider) = context.get_oauth2_token_provider() {
+        let _lock = context.oauth2_mutex.lock().await;
+        return token_provider
+            .get_access_token(context, addr, regenerate)
+            .await;
+    }
+
     let socks5_enabled = context.get_config_bool(Config::Socks5Enabled).await?;
     if let Some(oauth2) = Oauth2::from_address(context, addr, socks5_enabled).await {
         let lock = context.oauth2_mutex.lock().await;
+        let client_id = oauth2.get_client_id(context).await?;
+        if client_id.is_empty() {
+            warn!(context, "No OAuth2 client ID configured for {}.", addr);
+            return Ok(None);
+        }
 
         // read generated token
         if !regenerate && !is_expired(context).await? {
@@ -144,7 +285,7 @@ pub(crate) async fn get_oauth2_access_token(
             let mut value = parts.next().unwrap_or_default();
 
             if value == "$CLIENT_ID" {
-                value = oauth2.client_id;
+                value = &client_id;
             } else if value == "$REDIRECT_URI" {
                 value = &redirect_uri;
             } else if value == "$CODE" {
@@ -160,7 +301,7 @@ pub(crate) async fn get_oauth2_access_token(
 
         // ... and POST
         let socks5_config = Socks5Config::from_database(&context.sql).await?;
-        let client = crate::net::http::get_client(socks5_config)?;
+        let client = crate::net::http::get_client(context, socks5_config)?;
 
         let response: Response = match client.post(post_url).form(&post_param).send().await {
             Ok(resp) => match resp.json().await {
@@ -266,19 +407,28 @@ async fn from_address(context: &Context, addr: &str, skip_mx: bool) -> Option<Se
             .find('@')
             .map(|index| addr_normalized.split_at(index + 1).1)
         {
-            if let Some(oauth2_authorizer) = provider::get_provider_info(context, domain, skip_mx)
-                .await
-                .and_then(|provider| provider.oauth2_authorizer.as_ref())
-            {
+            let provider = provider::get_provider_info(context, domain, skip_mx).await?;
+            if let Some(oauth2_authorizer) = provider.oauth2_authorizer.as_ref() {
                 return Some(match oauth2_authorizer {
                     Oauth2Authorizer::Gmail => OAUTH2_GMAIL,
                     Oauth2Authorizer::Yandex => OAUTH2_YANDEX,
+                    Oauth2Authorizer::Microsoft => OAUTH2_MICROSOFT,
                 });
             }
         }
         None
     }
 
+    /// Returns the client ID set by `Config::Oauth2ClientId` or the built-in one.
+    /// An empty string means that there is no client ID and OAuth 2 cannot be used.
+    async fn get_client_id(&self, context: &Context) -> Result<String> {
+        Ok(context
+            .get_config(Config::Oauth2ClientId)
+            .await?
+            .filter(|client_id| !client_id.is_empty())
+            .unwrap_or_else(|| self.client_id.to_string()))
+    }
+
     async fn get_addr(&self, context: &Context, access_token: &str) -> Option<String> {
         let userinfo_url = self.get_userinfo.unwrap_or("");
         let userinfo_url = replace_in_uri(userinfo_url, "$ACCESS_TOKEN", access_token);
@@ -291,7 +441,7 @@ async fn get_addr(&self, context: &Context, access_token: &str) -> Option<String
         //   "picture": "https://lh4.googleusercontent.com/-Gj5jh_9R0BY/AAAAAAAAAAI/AAAAAAAAAAA/IAjtjfjtjNA/photo.jpg"
         // }
         let socks5_config = Socks5Config::from_database(&context.sql).await.ok()?;
-        let client = match crate::net::http::get_client(socks5_config) {
+        let client = match crate::net::http::get_client(context, socks5_config) {
             Ok(cl) => cl,
             Err(err) => {
                 warn!(context, "failed to get HTTP client: {}", err);
@@ -443,4 +593,89 @@ async fn test_get_oauth2_token() {
         // this should fail as it is an invalid password
         assert_eq!(res, None);
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_oauth_microsoft_client_id() -> Result<()> {
+        let t = TestContext::new().await;
+        let addr = "hello@outlook.com";
+        let redirect_uri = "chat.delta:/com.b44t.messenger";
+        assert_eq!(
+            Oauth2::from_address(&t, addr, false).await,
+            Some(OAUTH2_MICROSOFT)
+        );
+        // There is no built-in client ID for Microsoft.
+        assert_eq!(get_oauth2_url(&t, addr, redirect_uri).await?, None);
+
+        t.set_config(Config::Oauth2ClientId, Some("my-client-id"))
+            .await?;
+        let url = get_oauth2_url(&t, addr, redirect_uri).await?.unwrap();
+        assert!(url.starts_with(
+            "https://login.microsoftonline.com/common/oauth2/v2.0/authorize?client_id=my%2Dclient%2Did&"
+        ));
+        Ok(())
+    }
+
+    #[derive(Debug)]
+    struct TestTokenProvider;
+
+    impl Oauth2TokenProvider for TestTokenProvider {
+        fn get_access_token<'a>(
+            &'a self,
+            _context: &'a Context,
+            addr: &'a str,
+            regenerate: bool,
+        ) -> BoxFuture<'a, Result<Option<String>>> {
+            Box::pin(async move { Ok(Some(format!("token-{addr}-{regenerate}"))) })
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_oauth2_token_provider() -> Result<()> {
+        let t = TestContext::new().await;
+        t.set_oauth2_token_provider(Some(Arc::new(TestTokenProvider)));
+        assert_eq!(
+            get_oauth2_access_token(&t, "hello@example.org", "", true).await?,
+            Some("token-hello@example.org-true".to_string())
+        );
+
+        t.set_oauth2_token_provider(None);
+        assert_eq!(
+            get_oauth2_access_token(&t, "hello@example.org", "", true).await?,
+            None
+        );
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_oauth2_token_requests() -> Result<()> {
+        let t = TestContext::new().await;
+        t.set_oauth2_token_requests(true);
+
+        let requester = {
+            let t = t.ctx.clone();
+            tokio::spawn(async move {
+                get_oauth2_access_token(&t, "hello@example.org", "", false).await
+            })
+        };
+        let event = t
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::Oauth2TokenRequested { .. }))
+            .await;
+        assert_eq!(
+            event,
+            EventType::Oauth2TokenRequested {
+                addr: "hello@example.org".to_string(),
+                regenerate: false
+            }
+        );
+        t.set_oauth2_access_token(Some("my-token")).await?;
+        assert_eq!(requester.await??, Some("my-token".to_string()));
+
+        // The token is reused until it is rejected.
+        assert_eq!(
+            get_oauth2_access_token(&t, "hello@example.org", "", false).await?,
+            Some("my-token".to_string())
+        );
+        Ok(())
+    }
 }
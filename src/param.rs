@@ -21,6 +21,10 @@ pub enum Param {
     /// For messages and jobs
     File = b'f',
 
+    /// For messages: original name of the file,
+    /// if it differs from the name of the blob in [`Param::File`].
+    Filename = b'y',
+
     /// For messages: This name should be shown instead of contact.get_display_name()
     /// (used if this is a mailinglist
     /// or explicitly set using set_override_sender_name(), eg. by bots)
@@ -173,6 +177,12 @@ pub enum Param {
     /// For Chats: timestamp of protection settings update.
     ProtectionSettingsTimestamp = b'L',
 
+    /// For Chats: timestamp of visibility (archived/pinned) update.
+    VisibilityTimestamp = b'v',
+
+    /// For Chats: timestamp of mute setting update.
+    MuteTimestamp = b'z',
+
     /// For Webxdc Message Instances: Current document name
     WebxdcDocument = b'R',
 
@@ -0,0 +1,285 @@
+//! # Profiling with tracing spans.
+//!
+//! IMAP, SMTP and message receiving functions are instrumented with [`tracing`] spans.
+//! While span recording is enabled, closed spans are kept in memory
+//! and can be exported in the Chrome trace event format,
+//! which can be opened in `chrome://tracing` or <https://ui.perfetto.dev>.
+//!
+//! Recording is process-wide because `tracing` subscribers are global.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Metadata, Subscriber};
+use tracing_subscriber::layer::{Context as LayerContext, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Maximum number of recorded spans kept in memory.
+pub const SPAN_BUFFER_SIZE: usize = 10_000;
+
+/// True if spans are recorded.
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// True if a [`SpanRecorder`] was added to a subscriber.
+static RECORDER_ATTACHED: AtomicBool = AtomicBool::new(false);
+
+static RECORDED_SPANS: Lazy<Mutex<VecDeque<RecordedSpan>>> =
+    Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// Closed span recorded by [`SpanRecorder`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecordedSpan {
+    /// Span name, usually the name of the instrumented function.
+    pub name: String,
+
+    /// Module path of the span.
+    pub target: String,
+
+    /// Time the span was created, in microseconds since the Unix epoch.
+    pub start_us: i64,
+
+    /// Time between creating and closing the span, in microseconds.
+    pub duration_us: i64,
+
+    /// ID of the outermost span containing this span.
+    ///
+    /// Spans with the same root belong to the same operation, such as fetching a folder.
+    pub root_id: u64,
+
+    /// Span fields, such as the account ID or folder name.
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Timing and fields of an open span, stored in the span extensions.
+struct OpenSpan {
+    start: Instant,
+    start_us: i64,
+    fields: BTreeMap<String, String>,
+}
+
+struct FieldRecorder<'a>(&'a mut BTreeMap<String, String>);
+
+impl Visit for FieldRecorder<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+/// [`Layer`] recording closed spans of this crate while recording is enabled.
+///
+/// Installed automatically by [`start_span_recording`] unless
+/// the application has installed its own global subscriber.
+/// In this case the application can add the recorder to its subscriber.
+#[derive(Debug, Default)]
+pub struct SpanRecorder;
+
+impl<S> Layer<S> for SpanRecorder
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_layer(&mut self, _subscriber: &mut S) {
+        RECORDER_ATTACHED.store(true, Ordering::Relaxed);
+    }
+
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        if metadata.is_span() && metadata.target().starts_with("deltachat") {
+            // Recording can be enabled and disabled at any time.
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: LayerContext<'_, S>) -> bool {
+        metadata.is_span() && RECORDING.load(Ordering::Relaxed)
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: LayerContext<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            let mut fields = BTreeMap::new();
+            attrs.record(&mut FieldRecorder(&mut fields));
+            span.extensions_mut().insert(OpenSpan {
+                start: Instant::now(),
+                start_us: SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_micros()
+                    .try_into()
+                    .unwrap_or(i64::MAX),
+                fields,
+            });
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: LayerContext<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(open_span) = span.extensions_mut().get_mut::<OpenSpan>() {
+                values.record(&mut FieldRecorder(&mut open_span.fields));
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: LayerContext<'_, S>) {
+        let span = match ctx.span(&id) {
+            Some(span) => span,
+            None => return,
+        };
+        let open_span = match span.extensions_mut().remove::<OpenSpan>() {
+            Some(open_span) => open_span,
+            None => return,
+        };
+        let root_id = span
+            .scope()
+            .from_root()
+            .next()
+            .map_or(id.into_u64(), |root| root.id().into_u64());
+        let recorded_span = RecordedSpan {
+            name: span.name().to_string(),
+            target: span.metadata().target().to_string(),
+            start_us: open_span.start_us,
+            duration_us: open_span
+                .start
+                .elapsed()
+                .as_micros()
+                .try_into()
+                .unwrap_or(i64::MAX),
+            root_id,
+            fields: open_span.fields,
+        };
+
+        let mut recorded_spans = RECORDED_SPANS.lock().expect("Mutex is poisoned");
+        if recorded_spans.len() >= SPAN_BUFFER_SIZE {
+            recorded_spans.pop_front();
+        }
+        recorded_spans.push_back(recorded_span);
+    }
+}
+
+/// Clears previously recorded spans and starts recording spans.
+///
+/// Installs a global `tracing` subscriber with a [`SpanRecorder`] on first use.
+/// Fails if the application installed another global subscriber
+/// without adding a [`SpanRecorder`] to it.
+pub fn start_span_recording() -> Result<()> {
+    static INSTALLED: Lazy<Result<(), String>> = Lazy::new(|| {
+        if RECORDER_ATTACHED.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        tracing::subscriber::set_global_default(tracing_subscriber::registry().with(SpanRecorder))
+            .map_err(|err| {
+                RECORDER_ATTACHED.store(false, Ordering::Relaxed);
+                err.to_string()
+            })
+    });
+
+    if let Err(err) = &*INSTALLED {
+        bail!("Failed to install span recorder: {err}");
+    }
+    RECORDED_SPANS.lock().expect("Mutex is poisoned").clear();
+    RECORDING.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stops recording spans.
+///
+/// Already recorded spans are kept until the recording is started again.
+pub fn stop_span_recording() {
+    RECORDING.store(false, Ordering::Relaxed);
+}
+
+/// Returns the recorded spans in the order they were closed.
+pub fn get_recorded_spans() -> Vec<RecordedSpan> {
+    RECORDED_SPANS
+        .lock()
+        .expect("Mutex is poisoned")
+        .iter()
+        .cloned()
+        .collect()
+}
+
+/// Returns the recorded spans in the Chrome trace event JSON format.
+///
+/// Spans of the same operation are shown in the same row.
+pub fn get_chrome_trace() -> Result<String> {
+    let trace_events: Vec<serde_json::Value> = get_recorded_spans()
+        .into_iter()
+        .map(|span| {
+            serde_json::json!({
+                "name": span.name,
+                "cat": span.target,
+                "ph": "X",
+                "ts": span.start_us,
+                "dur": span.duration_us,
+                "pid": 1,
+                "tid": span.root_id,
+                "args": span.fields,
+            })
+        })
+        .collect();
+    Ok(serde_json::to_string(
+        &serde_json::json!({ "traceEvents": trace_events }),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context as _;
+
+    use super::*;
+
+    #[test]
+    fn test_span_recording() -> Result<()> {
+        start_span_recording()?;
+        {
+            let _outer = tracing::info_span!("outer", account_id = 1).entered();
+            let _inner = tracing::info_span!("inner", folder = "INBOX").entered();
+        }
+        stop_span_recording();
+        {
+            let _span = tracing::info_span!("not_recorded").entered();
+        }
+
+        let spans = get_recorded_spans();
+        let inner = spans
+            .iter()
+            .find(|span| span.name == "inner")
+            .context("inner span not recorded")?;
+        let outer = spans
+            .iter()
+            .find(|span| span.name == "outer")
+            .context("outer span not recorded")?;
+        assert_eq!(
+            inner.fields.get("folder").map(|s| s.as_str()),
+            Some("INBOX")
+        );
+        assert_eq!(
+            outer.fields.get("account_id").map(|s| s.as_str()),
+            Some("1")
+        );
+        assert_eq!(inner.root_id, outer.root_id);
+        assert!(inner.duration_us <= outer.duration_us);
+        assert!(!spans.iter().any(|span| span.name == "not_recorded"));
+
+        let trace: serde_json::Value = serde_json::from_str(&get_chrome_trace()?)?;
+        let trace_events = trace
+            .get("traceEvents")
+            .and_then(|trace_events| trace_events.as_array())
+            .context("no trace events")?;
+        assert!(trace_events.len() >= 2);
+        Ok(())
+    }
+}
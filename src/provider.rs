@@ -1,7 +1,24 @@
 //! [Provider database](https://providers.delta.chat/) module.
 
+#[cfg(feature = "provider-db")]
 mod data;
 
+/// Empty provider database, used if the `provider-db` feature is disabled.
+#[cfg(not(feature = "provider-db"))]
+mod data {
+    use std::collections::HashMap;
+
+    use once_cell::sync::Lazy;
+
+    use super::Provider;
+
+    pub(crate) static PROVIDER_DATA: Lazy<HashMap<&'static str, &'static Provider>> =
+        Lazy::new(HashMap::new);
+
+    pub(crate) static PROVIDER_IDS: Lazy<HashMap<&'static str, &'static Provider>> =
+        Lazy::new(HashMap::new);
+}
+
 use anyhow::Result;
 use trust_dns_resolver::{config, AsyncResolver, TokioAsyncResolver};
 
@@ -73,6 +90,9 @@ pub enum Oauth2Authorizer {
 
     /// Gmail.
     Gmail = 2,
+
+    /// Microsoft identity platform, used by Outlook.com and Microsoft 365.
+    Microsoft = 3,
 }
 
 /// Email server endpoint.
@@ -216,6 +236,9 @@ pub fn get_provider_by_domain(domain: &str) -> Option<&'static Provider> {
 ///
 /// For security reasons, only Gmail can be configured this way.
 pub async fn get_provider_by_mx(context: &Context, domain: &str) -> Option<&'static Provider> {
+    if context.is_maintenance_mode() {
+        return None;
+    }
     if let Ok(resolver) = get_resolver() {
         let mut fqdn: String = domain.to_string();
         if !fqdn.ends_with('.') {
@@ -257,7 +280,7 @@ pub fn get_provider_by_id(id: &str) -> Option<&'static Provider> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "provider-db"))]
 mod tests {
     #![allow(clippy::indexing_slicing)]
 
@@ -934,7 +934,7 @@
     ],
     opt: Default::default(),
     config_defaults: None,
-    oauth2_authorizer: None,
+    oauth2_authorizer: Some(Oauth2Authorizer::Microsoft),
 });
 
 // ouvaton.coop.md: ouvaton.org
@@ -0,0 +1,252 @@
+//! # Push notifications.
+//!
+//! Servers with `XDELTAPUSH` and `METADATA` capabilities, such as chatmail servers,
+//! run a notification proxy which wakes up the device when a new message arrives.
+//! The device token is stored on the server as `/private/devicetoken` IMAP metadata.
+//!
+//! The device token is either an APNs or FCM token set by the app,
+//! which is passed to the notification service unchanged,
+//! or, with UnifiedPush, the endpoint URL provided by the distributor
+//! prefixed with `unifiedpush:`, the notification proxy pokes the device
+//! by sending a POST request to the endpoint.
+//! While a token is registered, the inbox loop does not keep the IMAP connection open,
+//! but waits until it is woken up by a push notification.
+
+use std::time::Duration;
+
+use anyhow::{ensure, Context as _, Result};
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::imap::session::Session;
+use crate::scheduler::InterruptInfo;
+
+/// Time after which new messages are fetched even without a push notification,
+/// in case the notification proxy fails to deliver notifications.
+pub(crate) const PUSH_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Raw config key storing the device token currently registered on the server.
+const REGISTERED_TOKEN_KEY: &str = "push_registered_token";
+
+/// Raw config key storing whether the server supported push notifications
+/// on the last connection.
+const SUPPORTED_KEY: &str = "push_supported";
+
+/// State of push notifications, see [`Context::get_push_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushState {
+    /// Neither a device token nor a UnifiedPush endpoint is set.
+    NoToken,
+
+    /// The server does not support push notifications.
+    NotSupported,
+
+    /// The token is not registered on the server yet,
+    /// it will be registered on the next connection.
+    Pending,
+
+    /// The token is registered on the server.
+    Registered,
+}
+
+/// Returns the device token which should be registered on the server, if any.
+///
+/// The device token set by the app takes precedence over the UnifiedPush endpoint.
+async fn wanted_token(context: &Context) -> Result<Option<String>> {
+    if let Some(token) = context
+        .get_config(Config::PushDeviceToken)
+        .await?
+        .filter(|token| !token.is_empty())
+    {
+        return Ok(Some(token));
+    }
+    Ok(context
+        .get_config(Config::UnifiedpushEndpoint)
+        .await?
+        .filter(|endpoint| !endpoint.is_empty())
+        .map(|endpoint| format!("unifiedpush:{endpoint}")))
+}
+
+/// Forgets about the token registered on the server,
+/// so it is registered again on the next connection.
+///
+/// Called after configuration as the account may have moved to another server.
+pub(crate) async fn reset_push_registration(context: &Context) -> Result<()> {
+    context
+        .sql
+        .set_raw_config(REGISTERED_TOKEN_KEY, None)
+        .await?;
+    context.sql.set_raw_config(SUPPORTED_KEY, None).await?;
+    Ok(())
+}
+
+/// Quotes a string for use in an IMAP command.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Session {
+    /// Registers the device token on the server
+    /// or removes it if the token was unset since the last registration.
+    ///
+    /// Returns true if a token is registered and the server sends push notifications.
+    pub(crate) async fn update_push_token(&mut self, context: &Context) -> Result<bool> {
+        context
+            .sql
+            .set_raw_config_int(SUPPORTED_KEY, self.can_push().into())
+            .await?;
+        if !self.can_push() {
+            return Ok(false);
+        }
+
+        let token = wanted_token(context).await?;
+        let registered_token = context.sql.get_raw_config(REGISTERED_TOKEN_KEY).await?;
+        if token != registered_token {
+            let value = token.as_deref().map_or_else(|| "NIL".to_string(), quote);
+            self.run_command_and_check_ok(format!(
+                "SETMETADATA \"\" (/private/devicetoken {value})"
+            ))
+            .await
+            .context("SETMETADATA failed")?;
+            context
+                .sql
+                .set_raw_config(REGISTERED_TOKEN_KEY, token.as_deref())
+                .await?;
+            if token.is_some() {
+                info!(context, "Registered device token for push notifications.");
+            } else {
+                info!(context, "Removed device token for push notifications.");
+            }
+        }
+        Ok(token.is_some())
+    }
+}
+
+impl Context {
+    /// Sets the UnifiedPush endpoint provided by the distributor,
+    /// `None` to stop receiving push notifications.
+    ///
+    /// The endpoint is registered on the next connection
+    /// if the server supports push notifications.
+    pub async fn set_unifiedpush_endpoint(&self, endpoint: Option<&str>) -> Result<()> {
+        if let Some(endpoint) = endpoint {
+            ensure!(
+                endpoint.starts_with("https://"),
+                "UnifiedPush endpoint {endpoint:?} is not an HTTPS URL"
+            );
+        }
+        self.set_config(Config::UnifiedpushEndpoint, endpoint)
+            .await?;
+        self.scheduler
+            .interrupt_inbox(InterruptInfo::new(false))
+            .await;
+        Ok(())
+    }
+
+    /// Sets the APNs or FCM device token, `None` to stop receiving push notifications.
+    ///
+    /// The token is passed to the notification service of the server unchanged.
+    /// It is registered on the next connection if the server supports push notifications,
+    /// and registered again automatically after the account is reconfigured.
+    pub async fn set_push_device_token(&self, token: Option<&str>) -> Result<()> {
+        self.set_config(Config::PushDeviceToken, token.map(str::trim))
+            .await?;
+        self.scheduler
+            .interrupt_inbox(InterruptInfo::new(false))
+            .await;
+        Ok(())
+    }
+
+    /// Returns the state of push notifications for the settings screen.
+    pub async fn get_push_state(&self) -> Result<PushState> {
+        let token = match wanted_token(self).await? {
+            Some(token) => token,
+            None => return Ok(PushState::NoToken),
+        };
+        let registered_token = self.sql.get_raw_config(REGISTERED_TOKEN_KEY).await?;
+        let state = match self.sql.get_raw_config_int(SUPPORTED_KEY).await? {
+            Some(0) => PushState::NotSupported,
+            Some(_) if registered_token.as_deref() == Some(&token) => PushState::Registered,
+            _ => PushState::Pending,
+        };
+        Ok(state)
+    }
+
+    /// Fetches new messages after a push notification was received.
+    ///
+    /// The content of the notification is not used.
+    /// IO must be started with `start_io()` before, otherwise this is a no-op.
+    pub async fn handle_push_notification(&self) {
+        info!(self, "Received push notification.");
+        self.scheduler
+            .interrupt_inbox(InterruptInfo::new(false))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("foo"), "\"foo\"");
+        assert_eq!(quote("a\"b\\c"), "\"a\\\"b\\\\c\"");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_set_unifiedpush_endpoint() -> Result<()> {
+        let t = TestContext::new().await;
+        assert_eq!(wanted_token(&t).await?, None);
+
+        assert!(t
+            .set_unifiedpush_endpoint(Some("http://push.example.org/up/123"))
+            .await
+            .is_err());
+        t.set_unifiedpush_endpoint(Some("https://push.example.org/up/123"))
+            .await?;
+        assert_eq!(
+            wanted_token(&t).await?.as_deref(),
+            Some("unifiedpush:https://push.example.org/up/123")
+        );
+
+        t.set_unifiedpush_endpoint(None).await?;
+        assert_eq!(wanted_token(&t).await?, None);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_push_state() -> Result<()> {
+        let t = TestContext::new().await;
+        assert_eq!(t.get_push_state().await?, PushState::NoToken);
+
+        t.set_unifiedpush_endpoint(Some("https://push.example.org/up/123"))
+            .await?;
+        t.set_push_device_token(Some("0123abcd")).await?;
+        assert_eq!(wanted_token(&t).await?.as_deref(), Some("0123abcd"));
+        assert_eq!(t.get_push_state().await?, PushState::Pending);
+
+        // Simulate successful registration.
+        t.sql.set_raw_config_int(SUPPORTED_KEY, 1).await?;
+        t.sql
+            .set_raw_config(REGISTERED_TOKEN_KEY, Some("0123abcd"))
+            .await?;
+        assert_eq!(t.get_push_state().await?, PushState::Registered);
+
+        // Changed token needs to be registered again.
+        t.set_push_device_token(Some("4567cdef")).await?;
+        assert_eq!(t.get_push_state().await?, PushState::Pending);
+
+        reset_push_registration(&t).await?;
+        assert_eq!(t.get_push_state().await?, PushState::Pending);
+
+        t.sql.set_raw_config_int(SUPPORTED_KEY, 0).await?;
+        assert_eq!(t.get_push_state().await?, PushState::NotSupported);
+
+        t.set_push_device_token(None).await?;
+        t.set_unifiedpush_endpoint(None).await?;
+        assert_eq!(t.get_push_state().await?, PushState::NoToken);
+        Ok(())
+    }
+}
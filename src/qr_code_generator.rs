@@ -1,7 +1,9 @@
 //! # QR code generation module.
 
 use anyhow::Result;
+#[cfg(feature = "qr-svg")]
 use base64::Engine as _;
+#[cfg(feature = "qr-svg")]
 use qrcodegen::{QrCode, QrCodeEcc};
 
 use crate::{
@@ -96,6 +98,18 @@ async fn self_info(context: &Context) -> Result<(Option<Vec<u8>>, String, String
     Ok((avatar, displayname, addr, color))
 }
 
+#[cfg(not(feature = "qr-svg"))]
+fn inner_generate_secure_join_qr_code(
+    _qrcode_description: &str,
+    _qrcode_content: &str,
+    _color: &str,
+    _avatar: Option<Vec<u8>>,
+    _avatar_letter: char,
+) -> Result<String> {
+    anyhow::bail!("QR code SVG generation is disabled in this build, enable the qr-svg feature")
+}
+
+#[cfg(feature = "qr-svg")]
 fn inner_generate_secure_join_qr_code(
     qrcode_description: &str,
     qrcode_content: &str,
@@ -295,7 +309,7 @@ fn inner_generate_secure_join_qr_code(
     Ok(svg)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "qr-svg"))]
 mod tests {
     use testdir::testdir;
 
@@ -4,7 +4,7 @@
 use std::sync::atomic::Ordering;
 
 use anyhow::{anyhow, Context as _, Result};
-use async_imap::types::{Quota, QuotaResource};
+use async_imap::types::{Quota, QuotaResource, QuotaResourceName};
 
 use crate::chat::add_device_msg_with_importance;
 use crate::config::Config;
@@ -12,6 +12,7 @@
 use crate::imap::scan_folders::get_watched_folders;
 use crate::imap::session::Session as ImapSession;
 use crate::imap::Imap;
+use crate::jmap::Jmap;
 use crate::message::{Message, Viewtype};
 use crate::scheduler::InterruptInfo;
 use crate::tools::time;
@@ -101,15 +102,122 @@ fn get_highest_usage<'t>(
     highest.context("no quota_resource found, this is unexpected")
 }
 
+/// Usage of a single quota resource, see [`Context::get_quota_usage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaResourceUsage {
+    /// Name of the quota root, empty eg. for Gmail.
+    pub root_name: String,
+
+    /// Name of the resource as defined in RFC 2087,
+    /// eg. "STORAGE" for the mailbox size in units of 1024 bytes
+    /// or "MESSAGE" for the number of messages.
+    pub resource_name: String,
+
+    /// Current usage of the resource.
+    pub usage: u64,
+
+    /// Limit of the resource.
+    pub limit: u64,
+
+    /// Usage in percent of the limit.
+    pub usage_percentage: u64,
+}
+
+/// Quota usage percentages at which the user is warned,
+/// see `Config::QuotaWarnThreshold` and `Config::QuotaErrorThreshold`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct QuotaThresholds {
+    pub warn: u64,
+    pub error: u64,
+}
+
+impl Default for QuotaThresholds {
+    fn default() -> Self {
+        Self {
+            warn: QUOTA_WARN_THRESHOLD_PERCENTAGE,
+            error: QUOTA_ERROR_THRESHOLD_PERCENTAGE,
+        }
+    }
+}
+
+impl QuotaThresholds {
+    /// Checks if a quota warning is needed.
+    fn needs_warning(&self, curr_percentage: u64, warned_at_percentage: u64) -> bool {
+        (curr_percentage >= self.warn && warned_at_percentage < self.warn)
+            || (curr_percentage >= self.error && warned_at_percentage < self.error)
+    }
+
+    /// Percentage below which `QuotaExceeding` is cleared,
+    /// keeps the same distance to the warning threshold as the default values.
+    fn allclear(&self) -> u64 {
+        self.warn
+            .saturating_sub(QUOTA_WARN_THRESHOLD_PERCENTAGE - QUOTA_ALLCLEAR_PERCENTAGE)
+    }
+}
+
 /// Checks if a quota warning is needed.
 pub fn needs_quota_warning(curr_percentage: u64, warned_at_percentage: u64) -> bool {
-    (curr_percentage >= QUOTA_WARN_THRESHOLD_PERCENTAGE
-        && warned_at_percentage < QUOTA_WARN_THRESHOLD_PERCENTAGE)
-        || (curr_percentage >= QUOTA_ERROR_THRESHOLD_PERCENTAGE
-            && warned_at_percentage < QUOTA_ERROR_THRESHOLD_PERCENTAGE)
+    QuotaThresholds::default().needs_warning(curr_percentage, warned_at_percentage)
 }
 
 impl Context {
+    /// Returns the configured quota warning thresholds.
+    pub(crate) async fn get_quota_thresholds(&self) -> Result<QuotaThresholds> {
+        let warn = self
+            .get_config_int(Config::QuotaWarnThreshold)
+            .await?
+            .clamp(1, 100) as u64;
+        let error = self
+            .get_config_int(Config::QuotaErrorThreshold)
+            .await?
+            .clamp(1, 100) as u64;
+        Ok(QuotaThresholds {
+            warn,
+            error: error.max(warn),
+        })
+    }
+
+    /// Returns the most recently fetched quota usage
+    /// or `None` if the quota was not fetched yet or is not supported by the server.
+    ///
+    /// If the information is outdated, an update is scheduled
+    /// and `EventType::ConnectivityChanged` is emitted once it is done.
+    pub async fn get_quota_usage(&self) -> Result<Option<Vec<QuotaResourceUsage>>> {
+        let quota_lock = self.quota.read().await;
+        let quota = match &*quota_lock {
+            Some(quota) => quota,
+            None => {
+                drop(quota_lock);
+                self.schedule_quota_update().await?;
+                return Ok(None);
+            }
+        };
+        let res = quota.recent.as_ref().ok().map(|roots| {
+            roots
+                .iter()
+                .flat_map(|(root_name, resources)| {
+                    resources.iter().map(|resource| QuotaResourceUsage {
+                        root_name: root_name.clone(),
+                        resource_name: match &resource.name {
+                            QuotaResourceName::Storage => "STORAGE".to_string(),
+                            QuotaResourceName::Message => "MESSAGE".to_string(),
+                            QuotaResourceName::Atom(name) => name.to_string(),
+                        },
+                        usage: resource.usage,
+                        limit: resource.limit,
+                        usage_percentage: resource.get_usage_percentage(),
+                    })
+                })
+                .collect()
+        });
+        let outdated = quota.modified + QUOTA_MAX_AGE_SECONDS < time();
+        drop(quota_lock);
+        if outdated {
+            self.schedule_quota_update().await?;
+        }
+        Ok(res)
+    }
+
     // Adds a job to update `quota.recent`
     pub(crate) async fn schedule_quota_update(&self) -> Result<()> {
         let requested = self.quota_update_request.swap(true, Ordering::Relaxed);
@@ -145,11 +253,31 @@ pub(crate) async fn update_recent_quota(&self, imap: &mut Imap) -> Result<()> {
         } else {
             Err(anyhow!(stock_str::not_supported_by_provider(self).await))
         };
+        self.set_recent_quota(quota).await
+    }
 
+    /// Like [`Context::update_recent_quota`], but gets the quota using JMAP.
+    pub(crate) async fn update_recent_quota_jmap(&self, jmap: &mut Jmap) -> Result<()> {
+        let quota = match jmap.get_quota().await {
+            Ok(quota) if quota.is_empty() => {
+                Err(anyhow!(stock_str::not_supported_by_provider(self).await))
+            }
+            res => res,
+        };
+        self.set_recent_quota(quota).await
+    }
+
+    /// Stores the quota got from the server, warns the user if needed
+    /// and notifies the UI about the change.
+    async fn set_recent_quota(
+        &self,
+        quota: Result<BTreeMap<String, Vec<QuotaResource>>>,
+    ) -> Result<()> {
         if let Ok(quota) = &quota {
             match get_highest_usage(quota) {
                 Ok((highest, _, _)) => {
-                    if needs_quota_warning(
+                    let thresholds = self.get_quota_thresholds().await?;
+                    if thresholds.needs_warning(
                         highest,
                         self.get_config_int(Config::QuotaExceeding).await? as u64,
                     ) {
@@ -158,7 +286,10 @@ pub(crate) async fn update_recent_quota(&self, imap: &mut Imap) -> Result<()> {
                         let mut msg = Message::new(Viewtype::Text);
                         msg.text = Some(stock_str::quota_exceeding(self, highest).await);
                         add_device_msg_with_importance(self, None, Some(&mut msg), true).await?;
-                    } else if highest <= QUOTA_ALLCLEAR_PERCENTAGE {
+                        self.emit_event(EventType::QuotaWarning {
+                            usage_percentage: highest,
+                        });
+                    } else if highest <= thresholds.allclear() {
                         self.set_config(Config::QuotaExceeding, None).await?;
                     }
                 }
@@ -186,6 +317,7 @@ mod tests {
         QUOTA_ALLCLEAR_PERCENTAGE, QUOTA_ERROR_THRESHOLD_PERCENTAGE,
         QUOTA_WARN_THRESHOLD_PERCENTAGE,
     };
+    use crate::test_utils::TestContext;
 
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_needs_quota_warning() -> Result<()> {
@@ -205,6 +337,33 @@ async fn test_needs_quota_warning() -> Result<()> {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_configured_quota_thresholds() -> Result<()> {
+        let t = TestContext::new().await;
+        let thresholds = t.get_quota_thresholds().await?;
+        assert_eq!(thresholds, QuotaThresholds::default());
+        assert_eq!(thresholds.allclear(), QUOTA_ALLCLEAR_PERCENTAGE);
+
+        t.set_config(Config::QuotaWarnThreshold, Some("50")).await?;
+        t.set_config(Config::QuotaErrorThreshold, Some("70"))
+            .await?;
+        let thresholds = t.get_quota_thresholds().await?;
+        assert!(!thresholds.needs_warning(49, 0));
+        assert!(thresholds.needs_warning(50, 0));
+        assert!(!thresholds.needs_warning(60, 50));
+        assert!(thresholds.needs_warning(70, 50));
+        assert_eq!(thresholds.allclear(), 45);
+
+        // Error threshold below warning threshold is corrected.
+        t.set_config(Config::QuotaErrorThreshold, Some("10"))
+            .await?;
+        assert_eq!(t.get_quota_thresholds().await?.error, 50);
+
+        // Quota is not fetched yet.
+        assert_eq!(t.get_quota_usage().await?, None);
+        Ok(())
+    }
+
     #[allow(clippy::assertions_on_constants)]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_quota_thresholds() -> anyhow::Result<()> {
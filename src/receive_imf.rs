@@ -23,6 +23,7 @@
 use crate::events::EventType;
 use crate::headerdef::{HeaderDef, HeaderDefMap};
 use crate::imap::{markseen_on_imap_table, GENERATED_PREFIX};
+#[cfg(feature = "location")]
 use crate::location;
 use crate::log::LogExt;
 use crate::message::{
@@ -31,6 +32,7 @@
 use crate::mimeparser::{
     parse_message_ids, AvatarAction, MailinglistType, MimeMessage, SystemMessage,
 };
+use crate::notifications::add_pending_notifications;
 use crate::param::{Param, Params};
 use crate::peerstate::{Peerstate, PeerstateKeyType, PeerstateVerifiedStatus};
 use crate::reaction::{set_msg_reaction, Reaction};
@@ -90,6 +92,7 @@ pub async fn receive_imf(
 ///
 /// If `is_partial_download` is set, it contains the full message size in bytes.
 /// Do not confuse that with `replace_partial_download` that will be set when the full message is loaded later.
+#[tracing::instrument(skip_all, fields(account_id = context.id, rfc724_mid = rfc724_mid))]
 pub(crate) async fn receive_imf_inner(
     context: &Context,
     rfc724_mid: &str,
@@ -163,6 +166,13 @@ pub(crate) async fn receive_imf_inner(
                 info!(context, "Message already in DB, doing nothing.");
                 return Ok(None);
             }
+        } else if context
+            .sql
+            .cold_stored_rfc724_mid_exists(rfc724_mid)
+            .await?
+        {
+            info!(context, "Message already in cold storage, doing nothing.");
+            return Ok(None);
         } else {
             None
         };
@@ -265,6 +275,7 @@ pub(crate) async fn receive_imf_inner(
         MsgId::new_unset()
     };
 
+    #[cfg(feature = "location")]
     save_locations(context, &mime_parser, chat_id, from_id, insert_msg_id).await?;
 
     if let Some(ref sync_items) = mime_parser.sync_items {
@@ -290,6 +301,18 @@ pub(crate) async fn receive_imf_inner(
         }
     }
 
+    if let Some(ref realtime_data) = mime_parser.webxdc_realtime_data {
+        if let Err(err) = context
+            .receive_realtime_envelope(from_id, insert_msg_id, realtime_data)
+            .await
+        {
+            warn!(
+                context,
+                "receive_imf cannot receive realtime data: {err:#}."
+            );
+        }
+    }
+
     if let Some(avatar_action) = &mime_parser.user_avatar {
         if from_id != ContactId::UNDEFINED
             && context
@@ -331,8 +354,8 @@ pub(crate) async fn receive_imf_inner(
         }
     }
 
-    // Get user-configured server deletion
-    let delete_server_after = context.get_config_delete_server_after().await?;
+    // Get user-configured server deletion, possibly overridden for the chat
+    let delete_server_after = chat_id.get_effective_delete_server_after(context).await?;
 
     if !received_msg.msg_ids.is_empty() {
         if received_msg.needs_delete_job
@@ -356,6 +379,12 @@ pub(crate) async fn receive_imf_inner(
         context.emit_msgs_changed(chat_id, MsgId::new(0));
     } else if !chat_id.is_trash() {
         let fresh = received_msg.state == MessageState::InFresh;
+        if incoming {
+            context.metrics.count_msg_received();
+        }
+        if incoming && fresh {
+            add_pending_notifications(context, &received_msg.msg_ids).await?;
+        }
         for msg_id in &received_msg.msg_ids {
             chat_id.emit_msg_event(context, *msg_id, incoming && fresh);
         }
@@ -878,6 +907,15 @@ async fn add_parts(
         }
     }
 
+    if mime_parser.webxdc_realtime_data.is_some() {
+        chat_id = Some(DC_CHAT_ID_TRASH);
+        info!(
+            context,
+            "Message contains webxdc realtime data only (TRASH)."
+        );
+        markseen_on_imap_table(context, rfc724_mid).await.ok();
+    }
+
     let orig_chat_id = chat_id;
     let chat_id = if is_mdn || is_reaction {
         DC_CHAT_ID_TRASH
@@ -1300,6 +1338,7 @@ async fn add_parts(
 /// Saves attached locations to the database.
 ///
 /// Emits an event if at least one new location was added.
+#[cfg(feature = "location")]
 async fn save_locations(
     context: &Context,
     mime_parser: &MimeMessage,
@@ -1,6 +1,7 @@
 use std::iter::{self, once};
 use std::num::NonZeroUsize;
 use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 use anyhow::{bail, Context as _, Error, Result};
 use async_channel::{self as channel, Receiver, Sender};
@@ -15,13 +16,17 @@
 use crate::context::Context;
 use crate::ephemeral::{self, delete_expired_imap_messages};
 use crate::events::EventType;
+use crate::imap::scan_folders::{get_extra_watched_folders, get_scan_folders_connections};
 use crate::imap::{FolderMeaning, Imap};
+use crate::jmap::Jmap;
 use crate::job;
+#[cfg(feature = "location")]
 use crate::location;
 use crate::log::LogExt;
-use crate::smtp::{send_smtp_messages, Smtp};
+use crate::push::PUSH_IDLE_TIMEOUT;
+use crate::search;
+use crate::smtp::{next_retry_delay, send_smtp_messages, Smtp};
 use crate::sql;
-use crate::tools::time;
 use crate::tools::{duration_to_str, maybe_add_time_based_warnings};
 
 pub(crate) mod connectivity;
@@ -254,6 +259,14 @@ pub(crate) async fn interrupt_ephemeral_task(&self) {
         }
     }
 
+    pub(crate) async fn interrupt_folder_scan(&self, connections: usize) {
+        let inner = self.inner.read().await;
+        if let InnerSchedulerState::Started(ref scheduler) = *inner {
+            scheduler.interrupt_folder_scan(connections);
+        }
+    }
+
+    #[cfg(feature = "location")]
     pub(crate) async fn interrupt_location(&self) {
         let inner = self.inner.read().await;
         if let InnerSchedulerState::Started(ref scheduler) = *inner {
@@ -301,6 +314,9 @@ fn drop(&mut self) {
 #[derive(Debug)]
 struct SchedBox {
     meaning: FolderMeaning,
+
+    /// Name of the folder if `meaning` is `FolderMeaning::Extra`.
+    extra_folder: Option<String>,
     conn_state: ImapConnectionState,
 
     /// IMAP loop task handle.
@@ -311,22 +327,30 @@ struct SchedBox {
 #[derive(Debug)]
 pub(crate) struct Scheduler {
     inbox: SchedBox,
-    /// Optional boxes -- mvbox, sentbox.
+    /// Optional boxes -- mvbox, sentbox and extra watched folders.
     oboxes: Vec<SchedBox>,
     smtp: SmtpConnectionState,
     smtp_handle: task::JoinHandle<()>,
     ephemeral_handle: task::JoinHandle<()>,
     ephemeral_interrupt_send: Sender<()>,
+    #[cfg(feature = "location")]
     location_handle: task::JoinHandle<()>,
+    #[cfg(feature = "location")]
     location_interrupt_send: Sender<()>,
+    search_index_handle: task::JoinHandle<()>,
 
     recently_seen_loop: RecentlySeenLoop,
 }
 
+/// Runs the inbox loop.
+///
+/// If `jmap` is set, messages are fetched using JMAP and the IMAP connection is not used
+/// for anything but the connectivity and interrupts.
 async fn inbox_loop(
     ctx: Context,
     started: oneshot::Sender<()>,
     inbox_handlers: ImapConnectionHandlers,
+    mut jmap: Option<Jmap>,
 ) {
     use futures::future::FutureExt;
 
@@ -362,14 +386,22 @@ async fn inbox_loop(
                 None => {
                     let quota_requested = ctx.quota_update_request.swap(false, Ordering::Relaxed);
                     if quota_requested {
-                        if let Err(err) = ctx.update_recent_quota(&mut connection).await {
+                        let res = match jmap.as_mut() {
+                            Some(jmap) => ctx.update_recent_quota_jmap(jmap).await,
+                            None => ctx.update_recent_quota(&mut connection).await,
+                        };
+                        if let Err(err) = res {
                             warn!(ctx, "Failed to update quota: {:#}.", err);
                         }
                     }
 
                     let resync_requested = ctx.resync_request.swap(false, Ordering::Relaxed);
                     if resync_requested {
-                        if let Err(err) = connection.resync_folders(&ctx).await {
+                        let res = match jmap.as_mut() {
+                            Some(jmap) => jmap.resync(&ctx).await.map(|_| ()),
+                            None => connection.resync_folders(&ctx).await,
+                        };
+                        if let Err(err) = res {
                             warn!(ctx, "Failed to resync folders: {:#}.", err);
                             ctx.resync_request.store(true, Ordering::Relaxed);
                         }
@@ -377,18 +409,9 @@ async fn inbox_loop(
 
                     maybe_add_time_based_warnings(&ctx).await;
 
-                    match ctx.get_config_i64(Config::LastHousekeeping).await {
-                        Ok(last_housekeeping_time) => {
-                            let next_housekeeping_time =
-                                last_housekeeping_time.saturating_add(60 * 60 * 24);
-                            if next_housekeeping_time <= time() {
-                                sql::housekeeping(&ctx).await.log_err(&ctx).ok();
-                            }
-                        }
-                        Err(err) => {
-                            warn!(ctx, "Failed to get last housekeeping time: {}", err);
-                        }
-                    };
+                    if let Err(err) = sql::maybe_run_housekeeping(&ctx).await {
+                        warn!(ctx, "Failed to run housekeeping: {:#}.", err);
+                    }
 
                     match ctx.get_config_bool(Config::FetchedExistingMsgs).await {
                         Ok(fetched_existing_msgs) => {
@@ -403,7 +426,13 @@ async fn inbox_loop(
                                     warn!(ctx, "Can't set Config::FetchedExistingMsgs: {:#}", err);
                                 }
 
-                                if let Err(err) = connection.fetch_existing_msgs(&ctx).await {
+                                if let Some(jmap) = jmap.as_mut() {
+                                    if let Err(err) = jmap.fetch_existing_msgs(&ctx).await {
+                                        warn!(ctx, "Failed to fetch existing messages: {:#}", err);
+                                        jmap.trigger_reconnect();
+                                    }
+                                } else if let Err(err) = connection.fetch_existing_msgs(&ctx).await
+                                {
                                     warn!(ctx, "Failed to fetch existing messages: {:#}", err);
                                     connection.trigger_reconnect(&ctx);
                                 }
@@ -414,7 +443,17 @@ async fn inbox_loop(
                         }
                     }
 
-                    info = fetch_idle(&ctx, &mut connection, FolderMeaning::Inbox).await;
+                    if ctx.get_transport().is_some() {
+                        if let Err(err) = ctx.receive_transport_messages().await {
+                            warn!(ctx, "Failed to receive messages from transport: {:#}", err);
+                        }
+                    }
+
+                    info = if let Some(jmap) = jmap.as_mut() {
+                        jmap_fetch_idle(&ctx, jmap, &mut connection).await
+                    } else {
+                        fetch_idle(&ctx, &mut connection, FolderMeaning::Inbox, 0).await
+                    };
                 }
             }
         }
@@ -439,6 +478,7 @@ async fn fetch_idle(
     ctx: &Context,
     connection: &mut Imap,
     folder_meaning: FolderMeaning,
+    scan_slot: usize,
 ) -> InterruptInfo {
     let folder_config = match folder_meaning.to_config() {
         Some(c) => c,
@@ -472,6 +512,22 @@ async fn fetch_idle(
             .await;
     };
 
+    fetch_idle_folder(ctx, connection, watch_folder, folder_meaning, scan_slot).await
+}
+
+/// Like `fetch_idle`, but for a folder given by name
+/// rather than by the config key of its meaning, e.g. one of `Config::WatchFolders`.
+///
+/// `scan_slot` is the position of the loop in the scheduler, the inbox loop has slot 0.
+/// Loops with a slot below `Config::ScanFoldersConnections` help scanning folders
+/// that are not watched.
+async fn fetch_idle_folder(
+    ctx: &Context,
+    connection: &mut Imap,
+    watch_folder: String,
+    folder_meaning: FolderMeaning,
+    scan_slot: usize,
+) -> InterruptInfo {
     // connect and fake idle if unable to connect
     if let Err(err) = connection
         .prepare(ctx)
@@ -485,7 +541,7 @@ async fn fetch_idle(
             .await;
     }
 
-    if folder_config == Config::ConfiguredInboxFolder {
+    if folder_meaning == FolderMeaning::Inbox {
         if let Some(session) = connection.session.as_mut() {
             session
                 .store_seen_flags_on_imap(ctx)
@@ -519,37 +575,60 @@ async fn fetch_idle(
         .log_err(ctx)
         .ok();
 
+    if folder_meaning == FolderMeaning::Inbox {
+        if let Some(session) = connection.session.as_mut() {
+            session
+                .cleanup_trash(ctx)
+                .await
+                .context("cleanup_trash")
+                .log_err(ctx)
+                .ok();
+        }
+    }
+
     // Scan additional folders only after finishing fetching the watched folder.
     //
     // On iOS the application has strictly limited time to work in background, so we may not
     // be able to scan all folders before time is up if there are many of them.
-    if folder_config == Config::ConfiguredInboxFolder {
-        // Only scan on the Inbox thread in order to prevent parallel scans, which might lead to duplicate messages
-        match connection.scan_folders(ctx).await.context("scan_folders") {
-            Err(err) => {
-                // Don't reconnect, if there is a problem with the connection we will realize this when IDLEing
-                // but maybe just one folder can't be selected or something
+    let scanned = if folder_meaning == FolderMeaning::Inbox {
+        // Only list folders on the Inbox thread in order to prevent parallel scans of the same
+        // folder, which might lead to duplicate messages. The folders are then queued
+        // and other loops take part in scanning them.
+        connection.scan_folders(ctx).await.context("scan_folders")
+    } else {
+        match get_scan_folders_connections(ctx).await {
+            Ok(connections) if scan_slot < connections => connection
+                .scan_queued_folders(ctx)
+                .await
+                .context("scan_queued_folders"),
+            Ok(_) => Ok(false),
+            Err(err) => Err(err),
+        }
+    };
+    match scanned {
+        Err(err) => {
+            // Don't reconnect, if there is a problem with the connection we will realize this when IDLEing
+            // but maybe just one folder can't be selected or something
+            warn!(ctx, "{:#}", err);
+        }
+        Ok(true) => {
+            // Fetch the watched folder again in case scanning other folder moved messages
+            // there.
+            //
+            // In most cases this will select the watched folder and return because there are
+            // no new messages. We want to select the watched folder anyway before going IDLE
+            // there, so this does not take additional protocol round-trip.
+            if let Err(err) = connection
+                .fetch_move_delete(ctx, &watch_folder, folder_meaning)
+                .await
+                .context("fetch_move_delete after scan_folders")
+            {
+                connection.trigger_reconnect(ctx);
                 warn!(ctx, "{:#}", err);
+                return InterruptInfo::new(false);
             }
-            Ok(true) => {
-                // Fetch the watched folder again in case scanning other folder moved messages
-                // there.
-                //
-                // In most cases this will select the watched folder and return because there are
-                // no new messages. We want to select the watched folder anyway before going IDLE
-                // there, so this does not take additional protocol round-trip.
-                if let Err(err) = connection
-                    .fetch_move_delete(ctx, &watch_folder, folder_meaning)
-                    .await
-                    .context("fetch_move_delete after scan_folders")
-                {
-                    connection.trigger_reconnect(ctx);
-                    warn!(ctx, "{:#}", err);
-                    return InterruptInfo::new(false);
-                }
-            }
-            Ok(false) => {}
         }
+        Ok(false) => {}
     }
 
     // Synchronize Seen flags.
@@ -563,6 +642,37 @@ async fn fetch_idle(
     connection.connectivity.set_connected(ctx).await;
 
     ctx.emit_event(EventType::ImapInboxIdle);
+
+    // If the server sends push notifications, disconnect and wait to be woken up.
+    if folder_meaning == FolderMeaning::Inbox {
+        let push_active = match connection.session.as_mut() {
+            Some(session) => session
+                .update_push_token(ctx)
+                .await
+                .context("update_push_token")
+                .log_err(ctx)
+                .unwrap_or_default(),
+            None => false,
+        };
+        if push_active {
+            if let Some(mut session) = connection.session.take() {
+                session.logout().await.log_err(ctx).ok();
+            }
+            info!(
+                ctx,
+                "Push notifications are active, waiting to be woken up."
+            );
+            return tokio::time::timeout(
+                PUSH_IDLE_TIMEOUT,
+                connection.idle_interrupt_receiver.recv(),
+            )
+            .await
+            .ok()
+            .and_then(|info| info.ok())
+            .unwrap_or_default();
+        }
+    }
+
     if let Some(session) = connection.session.take() {
         if !session.can_idle() {
             info!(
@@ -602,11 +712,62 @@ async fn fetch_idle(
     }
 }
 
+/// Implements a single iteration of the inbox loop if JMAP is used instead of IMAP.
+///
+/// The IMAP `connection` is not used for fetching,
+/// but provides the connectivity store and the interrupt receiver.
+async fn jmap_fetch_idle(ctx: &Context, jmap: &mut Jmap, connection: &mut Imap) -> InterruptInfo {
+    if let Err(err) = jmap
+        .fetch_new_messages(ctx)
+        .await
+        .context("JMAP fetch_new_messages")
+    {
+        warn!(ctx, "{:#}", err);
+        jmap.trigger_reconnect();
+        connection
+            .connectivity
+            .set_err(ctx, format!("{err:#}"))
+            .await;
+        // Retry after a minute unless interrupted earlier.
+        return tokio::time::timeout(
+            Duration::from_secs(60),
+            connection.idle_interrupt_receiver.recv(),
+        )
+        .await
+        .ok()
+        .and_then(|info| info.ok())
+        .unwrap_or_default();
+    }
+
+    connection.connectivity.set_connected(ctx).await;
+    ctx.emit_event(EventType::ImapInboxIdle);
+
+    match jmap
+        .idle(ctx, connection.idle_interrupt_receiver.clone())
+        .await
+        .context("JMAP idle")
+    {
+        Ok(info) => info,
+        Err(err) => {
+            warn!(ctx, "{:#}", err);
+            jmap.trigger_reconnect();
+            InterruptInfo::new(false)
+        }
+    }
+}
+
+/// Watches a single folder.
+///
+/// `extra_folder` is the name of the folder if `folder_meaning` is `FolderMeaning::Extra`,
+/// otherwise the folder is determined by the config key of `folder_meaning`.
+/// `scan_slot` is the position of the loop in the scheduler, see `fetch_idle_folder`.
 async fn simple_imap_loop(
     ctx: Context,
     started: oneshot::Sender<()>,
     inbox_handlers: ImapConnectionHandlers,
     folder_meaning: FolderMeaning,
+    extra_folder: Option<String>,
+    scan_slot: usize,
 ) {
     use futures::future::FutureExt;
 
@@ -626,7 +787,19 @@ async fn simple_imap_loop(
         }
 
         loop {
-            fetch_idle(&ctx, &mut connection, folder_meaning).await;
+            match &extra_folder {
+                Some(folder) => {
+                    fetch_idle_folder(
+                        &ctx,
+                        &mut connection,
+                        folder.clone(),
+                        folder_meaning,
+                        scan_slot,
+                    )
+                    .await
+                }
+                None => fetch_idle(&ctx, &mut connection, folder_meaning, scan_slot).await,
+            };
         }
     };
 
@@ -665,7 +838,12 @@ async fn smtp_loop(
         loop {
             if let Err(err) = send_smtp_messages(&ctx, &mut connection).await {
                 warn!(ctx, "send_smtp_messages failed: {:#}", err);
-                timeout = Some(timeout.map_or(30, |timeout: u64| timeout.saturating_mul(3)))
+                timeout = Some(
+                    next_retry_delay(&ctx, timeout)
+                        .await
+                        .log_err(&ctx)
+                        .unwrap_or(30),
+                );
             } else {
                 let duration_until_can_send = ctx.ratelimit.read().await.until_can_send();
                 if !duration_until_can_send.is_zero() {
@@ -727,10 +905,20 @@ async fn smtp_loop(
 impl Scheduler {
     /// Start the scheduler.
     pub async fn start(ctx: Context) -> Result<Self> {
-        let (smtp, smtp_handlers) = SmtpConnectionState::new();
+        // JMAP is used instead of IMAP and SMTP if it was found when configuring.
+        let jmap = Jmap::new_configured(&ctx)
+            .await
+            .context("Failed to create JMAP connection")
+            .log_err(&ctx)
+            .ok()
+            .flatten();
+        let use_jmap = jmap.is_some();
+
+        let (smtp, smtp_handlers) = SmtpConnectionState::new(jmap.clone());
 
         let (smtp_start_send, smtp_start_recv) = oneshot::channel();
         let (ephemeral_interrupt_send, ephemeral_interrupt_recv) = channel::bounded(1);
+        #[cfg(feature = "location")]
         let (location_interrupt_send, location_interrupt_recv) = channel::bounded(1);
 
         let mut oboxes = Vec::new();
@@ -740,15 +928,17 @@ pub async fn start(ctx: Context) -> Result<Self> {
         let (inbox_start_send, inbox_start_recv) = oneshot::channel();
         let handle = {
             let ctx = ctx.clone();
-            task::spawn(inbox_loop(ctx, inbox_start_send, inbox_handlers))
+            task::spawn(inbox_loop(ctx, inbox_start_send, inbox_handlers, jmap))
         };
         let inbox = SchedBox {
             meaning: FolderMeaning::Inbox,
+            extra_folder: None,
             conn_state,
             handle,
         };
         start_recvs.push(inbox_start_recv);
 
+        // With JMAP, all mailboxes are synchronized by the inbox loop.
         for (meaning, should_watch) in [
             (FolderMeaning::Mvbox, ctx.should_watch_mvbox().await),
             (
@@ -756,13 +946,38 @@ pub async fn start(ctx: Context) -> Result<Self> {
                 ctx.get_config_bool(Config::SentboxWatch).await,
             ),
         ] {
-            if should_watch? {
+            if should_watch? && !use_jmap {
                 let (conn_state, handlers) = ImapConnectionState::new(&ctx).await?;
                 let (start_send, start_recv) = oneshot::channel();
                 let ctx = ctx.clone();
-                let handle = task::spawn(simple_imap_loop(ctx, start_send, handlers, meaning));
+                let scan_slot = oboxes.len() + 1;
+                let handle = task::spawn(simple_imap_loop(
+                    ctx, start_send, handlers, meaning, None, scan_slot,
+                ));
                 oboxes.push(SchedBox {
                     meaning,
+                    extra_folder: None,
+                    conn_state,
+                    handle,
+                });
+                start_recvs.push(start_recv);
+            }
+        }
+        if !use_jmap {
+            for folder in get_extra_watched_folders(&ctx).await? {
+                let (conn_state, handlers) = ImapConnectionState::new(&ctx).await?;
+                let (start_send, start_recv) = oneshot::channel();
+                let handle = task::spawn(simple_imap_loop(
+                    ctx.clone(),
+                    start_send,
+                    handlers,
+                    FolderMeaning::Extra,
+                    Some(folder.clone()),
+                    oboxes.len() + 1,
+                ));
+                oboxes.push(SchedBox {
+                    meaning: FolderMeaning::Extra,
+                    extra_folder: Some(folder),
                     conn_state,
                     handle,
                 });
@@ -783,6 +998,7 @@ pub async fn start(ctx: Context) -> Result<Self> {
             })
         };
 
+        #[cfg(feature = "location")]
         let location_handle = {
             let ctx = ctx.clone();
             task::spawn(async move {
@@ -790,6 +1006,13 @@ pub async fn start(ctx: Context) -> Result<Self> {
             })
         };
 
+        let search_index_handle = {
+            let ctx = ctx.clone();
+            task::spawn(async move {
+                search::backfill_loop(&ctx).await;
+            })
+        };
+
         let recently_seen_loop = RecentlySeenLoop::new(ctx.clone());
 
         let res = Self {
@@ -799,8 +1022,11 @@ pub async fn start(ctx: Context) -> Result<Self> {
             smtp_handle,
             ephemeral_handle,
             ephemeral_interrupt_send,
+            #[cfg(feature = "location")]
             location_handle,
+            #[cfg(feature = "location")]
             location_interrupt_send,
+            search_index_handle,
             recently_seen_loop,
         };
 
@@ -843,6 +1069,17 @@ fn interrupt_ephemeral_task(&self) {
         self.ephemeral_interrupt_send.try_send(()).ok();
     }
 
+    /// Interrupts the IMAP loops other than the inbox loop which take part in scanning folders,
+    /// so that they start scanning queued folders.
+    ///
+    /// `connections` is the number of loops taking part in scanning, including the inbox loop.
+    fn interrupt_folder_scan(&self, connections: usize) {
+        for b in self.oboxes.iter().take(connections.saturating_sub(1)) {
+            b.conn_state.interrupt(InterruptInfo::new(false));
+        }
+    }
+
+    #[cfg(feature = "location")]
     fn interrupt_location(&self) {
         self.location_interrupt_send.try_send(()).ok();
     }
@@ -875,7 +1112,9 @@ pub(crate) async fn stop(self, context: &Context) {
             .log_err(context)
             .ok();
         self.ephemeral_handle.abort();
+        #[cfg(feature = "location")]
         self.location_handle.abort();
+        self.search_index_handle.abort();
         self.recently_seen_loop.abort();
     }
 }
@@ -914,12 +1153,14 @@ pub(crate) struct SmtpConnectionState {
 }
 
 impl SmtpConnectionState {
-    fn new() -> (Self, SmtpConnectionHandlers) {
+    /// Creates the SMTP connection state,
+    /// messages are sent using `jmap` instead of SMTP if it is set.
+    fn new(jmap: Option<Jmap>) -> (Self, SmtpConnectionHandlers) {
         let (stop_sender, stop_receiver) = channel::bounded(1);
         let (idle_interrupt_sender, idle_interrupt_receiver) = channel::bounded(1);
 
         let handlers = SmtpConnectionHandlers {
-            connection: Smtp::new(),
+            connection: Smtp::with_jmap(jmap),
             stop_receiver,
             idle_interrupt_receiver,
         };
@@ -7,9 +7,7 @@
 
 use crate::events::EventType;
 use crate::imap::{scan_folders::get_watched_folder_configs, FolderMeaning};
-use crate::quota::{
-    QUOTA_ERROR_THRESHOLD_PERCENTAGE, QUOTA_MAX_AGE_SECONDS, QUOTA_WARN_THRESHOLD_PERCENTAGE,
-};
+use crate::quota::QUOTA_MAX_AGE_SECONDS;
 use crate::tools::time;
 use crate::{context::Context, log::LogExt};
 use crate::{stock_str, tools};
@@ -116,13 +114,39 @@ fn all_work_done(&self) -> bool {
     }
 }
 
+/// Past results of a connection, kept in addition to the current state.
+#[derive(Debug, Default, Clone)]
+struct ConnectivityHistory {
+    /// Last error and its timestamp.
+    last_error: Option<(String, i64)>,
+
+    /// Timestamp of the last time the connection became connected,
+    /// i.e. finished fetching or sending messages.
+    last_success: Option<i64>,
+}
+
 #[derive(Clone, Default)]
-pub(crate) struct ConnectivityStore(Arc<Mutex<DetailedConnectivity>>);
+pub(crate) struct ConnectivityStore {
+    state: Arc<Mutex<DetailedConnectivity>>,
+    history: Arc<Mutex<ConnectivityHistory>>,
+}
 
 impl ConnectivityStore {
     async fn set(&self, context: &Context, v: DetailedConnectivity) {
         {
-            *self.0.lock().await = v;
+            let mut history = self.history.lock().await;
+            match &v {
+                DetailedConnectivity::Error(err) => {
+                    history.last_error = Some((err.clone(), time()));
+                }
+                DetailedConnectivity::Connected => {
+                    history.last_success = Some(time());
+                }
+                _ => {}
+            }
+        }
+        {
+            *self.state.lock().await = v;
         }
         context.emit_event(EventType::ConnectivityChanged);
     }
@@ -145,13 +169,16 @@ pub(crate) async fn set_not_configured(&self, context: &Context) {
     }
 
     async fn get_detailed(&self) -> DetailedConnectivity {
-        self.0.lock().await.deref().clone()
+        self.state.lock().await.deref().clone()
+    }
+    async fn get_history(&self) -> ConnectivityHistory {
+        self.history.lock().await.clone()
     }
     async fn get_basic(&self) -> Option<Connectivity> {
-        self.0.lock().await.to_basic()
+        self.state.lock().await.to_basic()
     }
     async fn get_all_work_done(&self) -> bool {
-        self.0.lock().await.all_work_done()
+        self.state.lock().await.all_work_done()
     }
 }
 
@@ -159,7 +186,7 @@ async fn get_all_work_done(&self) -> bool {
 /// Called during `dc_maybe_network()` to make sure that `dc_accounts_all_work_done()`
 /// returns false immediately after `dc_maybe_network()`.
 pub(crate) async fn idle_interrupted(inbox: ConnectivityStore, oboxes: Vec<ConnectivityStore>) {
-    let mut connectivity_lock = inbox.0.lock().await;
+    let mut connectivity_lock = inbox.state.lock().await;
     // For the inbox, we also have to set the connectivity to InterruptingIdle if it was
     // NotConfigured before: If all folders are NotConfigured, dc_get_connectivity()
     // returns Connected. But after dc_maybe_network(), dc_get_connectivity() must not
@@ -173,7 +200,7 @@ pub(crate) async fn idle_interrupted(inbox: ConnectivityStore, oboxes: Vec<Conne
     drop(connectivity_lock);
 
     for state in oboxes {
-        let mut connectivity_lock = state.0.lock().await;
+        let mut connectivity_lock = state.state.lock().await;
         if *connectivity_lock == DetailedConnectivity::Connected {
             *connectivity_lock = DetailedConnectivity::InterruptingIdle;
         }
@@ -187,7 +214,7 @@ pub(crate) async fn idle_interrupted(inbox: ConnectivityStore, oboxes: Vec<Conne
 /// after `maybe_network_lost()` was called.
 pub(crate) async fn maybe_network_lost(context: &Context, stores: Vec<ConnectivityStore>) {
     for store in &stores {
-        let mut connectivity_lock = store.0.lock().await;
+        let mut connectivity_lock = store.state.lock().await;
         if !matches!(
             *connectivity_lock,
             DetailedConnectivity::Uninitialized
@@ -203,7 +230,7 @@ pub(crate) async fn maybe_network_lost(context: &Context, stores: Vec<Connectivi
 
 impl fmt::Debug for ConnectivityStore {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Ok(guard) = self.0.try_lock() {
+        if let Ok(guard) = self.state.try_lock() {
             write!(f, "ConnectivityStore {:?}", &*guard)
         } else {
             write!(f, "ConnectivityStore [LOCKED]")
@@ -211,6 +238,82 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     }
 }
 
+/// State of a single connection, see [`Context::get_connectivity_info`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The connection was not started yet.
+    NotStarted,
+
+    /// Connecting to the server.
+    Connecting,
+
+    /// Fetching or sending messages.
+    Working,
+
+    /// Connected and idle.
+    Connected,
+
+    /// The last connection attempt or operation failed.
+    Error,
+
+    /// The folder is not configured or configured not to be watched.
+    NotConfigured,
+}
+
+impl From<&DetailedConnectivity> for ConnectionState {
+    fn from(detailed: &DetailedConnectivity) -> Self {
+        match detailed {
+            DetailedConnectivity::Error(_) => ConnectionState::Error,
+            DetailedConnectivity::Uninitialized => ConnectionState::NotStarted,
+            DetailedConnectivity::Connecting => ConnectionState::Connecting,
+            DetailedConnectivity::Working => ConnectionState::Working,
+            DetailedConnectivity::InterruptingIdle | DetailedConnectivity::Connected => {
+                ConnectionState::Connected
+            }
+            DetailedConnectivity::NotConfigured => ConnectionState::NotConfigured,
+        }
+    }
+}
+
+/// Protocol of a connection, see [`Context::get_connectivity_info`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionProtocol {
+    Imap,
+    Smtp,
+}
+
+/// Structured connectivity information about a single connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// Protocol of the connection.
+    pub protocol: ConnectionProtocol,
+
+    /// Watched folder for IMAP connections.
+    ///
+    /// `None` for SMTP and for IMAP connections without configured folder.
+    pub folder: Option<String>,
+
+    /// Current state.
+    pub state: ConnectionState,
+
+    /// Translated description of the current state
+    /// as shown by [`Context::get_connectivity_html`].
+    pub state_text: String,
+
+    /// Last error, even if the connection has recovered since then.
+    pub last_error: Option<String>,
+
+    /// Timestamp of the last error.
+    pub last_error_timestamp: Option<i64>,
+
+    /// Timestamp of the last time the connection finished fetching or sending messages.
+    pub last_success_timestamp: Option<i64>,
+
+    /// Number of messages waiting to be sent for SMTP
+    /// or to be moved or deleted on the server for IMAP folders.
+    pub pending_messages: usize,
+}
+
 impl Context {
     /// Get the current connectivity, i.e. whether the device is connected to the IMAP server.
     /// One of:
@@ -315,7 +418,13 @@ pub async fn get_connectivity_html(&self) -> Result<String> {
             InnerSchedulerState::Started(ref sched) => (
                 sched
                     .boxes()
-                    .map(|b| (b.meaning, b.conn_state.state.connectivity.clone()))
+                    .map(|b| {
+                        (
+                            b.meaning,
+                            b.extra_folder.clone(),
+                            b.conn_state.state.connectivity.clone(),
+                        )
+                    })
                     .collect::<Vec<_>>(),
                 sched.smtp.state.connectivity.clone(),
             ),
@@ -335,24 +444,26 @@ pub async fn get_connectivity_html(&self) -> Result<String> {
         let watched_folders = get_watched_folder_configs(self).await?;
         let incoming_messages = stock_str::incoming_messages(self).await;
         ret += &format!("<h3>{incoming_messages}</h3><ul>");
-        for (folder, state) in &folders_states {
+        for (folder, extra_folder, state) in &folders_states {
             let mut folder_added = false;
 
-            if let Some(config) = folder.to_config().filter(|c| watched_folders.contains(c)) {
-                let f = self.get_config(config).await.log_err(self).ok().flatten();
-
-                if let Some(foldername) = f {
-                    let detailed = &state.get_detailed().await;
-                    ret += "<li>";
-                    ret += &*detailed.to_icon();
-                    ret += " <b>";
-                    ret += &*escaper::encode_minimal(&foldername);
-                    ret += ":</b> ";
-                    ret += &*escaper::encode_minimal(&detailed.to_string_imap(self).await);
-                    ret += "</li>";
-
-                    folder_added = true;
-                }
+            let f = if let Some(config) = folder.to_config().filter(|c| watched_folders.contains(c))
+            {
+                self.get_config(config).await.log_err(self).ok().flatten()
+            } else {
+                extra_folder.clone()
+            };
+            if let Some(foldername) = f {
+                let detailed = &state.get_detailed().await;
+                ret += "<li>";
+                ret += &*detailed.to_icon();
+                ret += " <b>";
+                ret += &*escaper::encode_minimal(&foldername);
+                ret += ":</b> ";
+                ret += &*escaper::encode_minimal(&detailed.to_string_imap(self).await);
+                ret += "</li>";
+
+                folder_added = true;
             }
 
             if !folder_added && folder == &FolderMeaning::Inbox {
@@ -394,6 +505,7 @@ pub async fn get_connectivity_html(&self) -> Result<String> {
         let domain = &tools::EmailAddress::new(&self.get_primary_self_addr().await?)?.domain;
         let storage_on_domain = stock_str::storage_on_domain(self, domain).await;
         ret += &format!("<h3>{storage_on_domain}</h3><ul>");
+        let thresholds = self.get_quota_thresholds().await?;
         let quota = self.quota.read().await;
         if let Some(quota) = &*quota {
             match &quota.recent {
@@ -450,9 +562,9 @@ pub async fn get_connectivity_html(&self) -> Result<String> {
                                 };
 
                                 let percent = resource.get_usage_percentage();
-                                let color = if percent >= QUOTA_ERROR_THRESHOLD_PERCENTAGE {
+                                let color = if percent >= thresholds.error {
                                     "red"
-                                } else if percent >= QUOTA_WARN_THRESHOLD_PERCENTAGE {
+                                } else if percent >= thresholds.warn {
                                     "yellow"
                                 } else {
                                     "green"
@@ -487,6 +599,84 @@ pub async fn get_connectivity_html(&self) -> Result<String> {
         Ok(ret)
     }
 
+    /// Returns structured connectivity information for each IMAP and SMTP connection.
+    ///
+    /// This is the same information as shown by [`Context::get_connectivity_html`]
+    /// without the quota, meant for UIs building their own connectivity screens.
+    ///
+    /// If the information changes, a DC_EVENT_CONNECTIVITY_CHANGED will be emitted.
+    pub async fn get_connectivity_info(&self) -> Result<Vec<ConnectionInfo>> {
+        let lock = self.scheduler.inner.read().await;
+        let (folders_states, smtp) = match *lock {
+            InnerSchedulerState::Started(ref sched) => (
+                sched
+                    .boxes()
+                    .map(|b| {
+                        (
+                            b.meaning,
+                            b.extra_folder.clone(),
+                            b.conn_state.state.connectivity.clone(),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+                sched.smtp.state.connectivity.clone(),
+            ),
+            _ => {
+                return Err(anyhow!("Not started"));
+            }
+        };
+        drop(lock);
+
+        let watched_folders = get_watched_folder_configs(self).await?;
+        let mut res = Vec::new();
+        for (folder, extra_folder, store) in &folders_states {
+            let foldername =
+                if let Some(config) = folder.to_config().filter(|c| watched_folders.contains(c)) {
+                    self.get_config(config).await.log_err(self).ok().flatten()
+                } else {
+                    extra_folder.clone()
+                };
+            let pending_messages = match &foldername {
+                Some(foldername) => {
+                    self.sql
+                        .count(
+                            "SELECT COUNT(*) FROM imap WHERE folder=? AND target!=folder",
+                            (foldername,),
+                        )
+                        .await?
+                }
+                None => 0,
+            };
+            let detailed = store.get_detailed().await;
+            let history = store.get_history().await;
+            res.push(ConnectionInfo {
+                protocol: ConnectionProtocol::Imap,
+                folder: foldername,
+                state: ConnectionState::from(&detailed),
+                state_text: detailed.to_string_imap(self).await,
+                last_error: history.last_error.as_ref().map(|(err, _)| err.clone()),
+                last_error_timestamp: history.last_error.map(|(_, timestamp)| timestamp),
+                last_success_timestamp: history.last_success,
+                pending_messages,
+            });
+        }
+
+        let detailed = smtp.get_detailed().await;
+        let history = smtp.get_history().await;
+        res.push(ConnectionInfo {
+            protocol: ConnectionProtocol::Smtp,
+            folder: None,
+            state: ConnectionState::from(&detailed),
+            state_text: detailed.to_string_smtp(self).await,
+            last_error: history.last_error.as_ref().map(|(err, _)| err.clone()),
+            last_error_timestamp: history.last_error.map(|(_, timestamp)| timestamp),
+            last_success_timestamp: history.last_success,
+            pending_messages: self.sql.count("SELECT COUNT(*) FROM smtp", ()).await?,
+        });
+
+        Ok(res)
+    }
+
     /// Returns true if all background work is done.
     pub async fn all_work_done(&self) -> bool {
         let lock = self.scheduler.inner.read().await;
@@ -509,3 +699,38 @@ pub async fn all_work_done(&self) -> bool {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_connectivity_store_history() {
+        let t = TestContext::new().await;
+        let store = ConnectivityStore::default();
+        assert_eq!(
+            ConnectionState::from(&store.get_detailed().await),
+            ConnectionState::NotStarted
+        );
+
+        store.set_err(&t, "Connection refused").await;
+        store.set_connecting(&t).await;
+        store.set_connected(&t).await;
+        assert_eq!(
+            ConnectionState::from(&store.get_detailed().await),
+            ConnectionState::Connected
+        );
+        let history = store.get_history().await;
+        let (last_error, last_error_timestamp) = history.last_error.unwrap();
+        assert_eq!(last_error, "Connection refused");
+        assert!(last_error_timestamp > 0);
+        assert!(history.last_success.unwrap() >= last_error_timestamp);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_connectivity_info_not_started() {
+        let t = TestContext::new_alice().await;
+        assert!(t.get_connectivity_info().await.is_err());
+    }
+}
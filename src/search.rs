@@ -0,0 +1,253 @@
+//! # Full-text search index.
+//!
+//! Message texts are indexed in the `msgs_search` FTS5 table
+//! using the trigram tokenizer, so substrings of at least 3 characters can be searched
+//! without scanning all messages, see [`Context::search_msgs`].
+//!
+//! Triggers on the `msgs` table keep the index up to date
+//! when messages are added, edited or deleted.
+//! Messages existing before the index was created
+//! are added in batches by [`backfill_loop`] while IO is running.
+//! Until this is finished, search falls back to scanning the `msgs` table.
+//!
+//! [`Context::search_chat_msgs`] additionally returns the positions of the matches,
+//! so UIs can highlight them.
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::chat::ChatId;
+use crate::constants::DC_MSG_ID_LAST_SPECIAL;
+use crate::context::Context;
+use crate::log::LogExt;
+use crate::message::MsgId;
+
+/// Raw config key storing the highest message ID not added to the index yet,
+/// 0 if all messages are indexed.
+const BACKFILL_KEY: &str = "search_index_backfill_id";
+
+/// Number of messages added to the index in a single transaction.
+const BACKFILL_BATCH: i64 = 1000;
+
+/// Pause between batches to let other database users proceed.
+const BACKFILL_PAUSE: Duration = Duration::from_millis(100);
+
+/// Minimum number of characters in a query which can be looked up in the index,
+/// shorter queries have no trigrams.
+const MIN_QUERY_CHARS: usize = 3;
+
+/// Message found by [`Context::search_chat_msgs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MsgSearchMatch {
+    /// ID of the message.
+    pub msg_id: MsgId,
+
+    /// Byte offsets and lengths of the matches in the message text,
+    /// as returned by [`Message::get_text`](crate::message::Message::get_text).
+    pub ranges: Vec<(usize, usize)>,
+}
+
+impl Context {
+    /// Searches for messages containing the query string in the chat
+    /// and returns them together with the positions of the matches in their text.
+    ///
+    /// Messages are found as with [`Context::search_msgs`] and ordered as in the chat.
+    /// Matches do not overlap and are case-insensitive.
+    pub async fn search_chat_msgs(
+        &self,
+        chat_id: ChatId,
+        query: &str,
+    ) -> Result<Vec<MsgSearchMatch>> {
+        let query = query.trim();
+        let mut res = Vec::new();
+        for msg_id in self.search_msgs(Some(chat_id), query).await? {
+            let text: String = self
+                .sql
+                .query_get_value("SELECT txt FROM msgs WHERE id=?", (msg_id,))
+                .await?
+                .unwrap_or_default();
+            res.push(MsgSearchMatch {
+                msg_id,
+                ranges: find_matches(&text, query),
+            });
+        }
+        Ok(res)
+    }
+}
+
+/// Returns byte offsets and lengths of non-overlapping case-insensitive matches of `query` in `text`.
+fn find_matches(text: &str, query: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    if query.is_empty() {
+        return ranges;
+    }
+    let mut next = 0;
+    for (start, _) in text.char_indices() {
+        if start < next {
+            continue;
+        }
+        if let Some(len) = match_len(&text[start..], query) {
+            ranges.push((start, len));
+            next = start + len;
+        }
+    }
+    ranges
+}
+
+/// Returns the length in bytes of the prefix of `text` matching `query` case-insensitively.
+fn match_len(text: &str, query: &str) -> Option<usize> {
+    let mut text_chars = text.char_indices();
+    for q in query.chars() {
+        let (_, c) = text_chars.next()?;
+        if !c.to_lowercase().eq(q.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(text_chars.next().map_or(text.len(), |(i, _)| i))
+}
+
+/// Returns the FTS5 query matching messages containing `query`,
+/// or `None` if the index cannot be used for it.
+pub(crate) async fn index_query(context: &Context, query: &str) -> Result<Option<String>> {
+    if query.chars().count() < MIN_QUERY_CHARS || !is_index_complete(context).await? {
+        return Ok(None);
+    }
+    Ok(Some(format!("\"{}\"", query.replace('"', "\"\""))))
+}
+
+/// Returns true if all messages are indexed.
+async fn is_index_complete(context: &Context) -> Result<bool> {
+    Ok(context
+        .sql
+        .get_raw_config_int64(BACKFILL_KEY)
+        .await?
+        .unwrap_or_default()
+        <= 0)
+}
+
+/// Adds the next batch of messages to the index.
+///
+/// Returns false if there are no more messages to index.
+async fn backfill_batch(context: &Context) -> Result<bool> {
+    let last_id = context
+        .sql
+        .get_raw_config_int64(BACKFILL_KEY)
+        .await?
+        .unwrap_or_default();
+    if last_id <= 0 {
+        return Ok(false);
+    }
+    // Special message IDs are placeholders without text.
+    let min_id = i64::from(DC_MSG_ID_LAST_SPECIAL) + 1;
+    let first_id = last_id.saturating_sub(BACKFILL_BATCH - 1).max(min_id);
+
+    context
+        .sql
+        .transaction(move |transaction| {
+            // Messages edited since the index was created are indexed already.
+            transaction.execute(
+                "DELETE FROM msgs_search WHERE rowid BETWEEN ? AND ?",
+                (first_id, last_id),
+            )?;
+            transaction.execute(
+                "INSERT INTO msgs_search (rowid, txt)
+                 SELECT id, txt FROM msgs WHERE id BETWEEN ? AND ?",
+                (first_id, last_id),
+            )?;
+            Ok(())
+        })
+        .await?;
+    let next_id = if first_id > min_id { first_id - 1 } else { 0 };
+    context
+        .sql
+        .set_raw_config_int64(BACKFILL_KEY, next_id)
+        .await?;
+    Ok(next_id > 0)
+}
+
+/// Adds messages existing before the index was created to the index.
+pub(crate) async fn backfill_loop(context: &Context) {
+    loop {
+        match backfill_batch(context).await.log_err(context) {
+            Ok(true) => tokio::time::sleep(BACKFILL_PAUSE).await,
+            Ok(false) | Err(_) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::send_text_msg;
+    use crate::test_utils::TestContext;
+
+    #[test]
+    fn test_find_matches() {
+        assert_eq!(find_matches("foobar", "o"), vec![(1, 1), (2, 1)]);
+        assert_eq!(find_matches("Hello hello", "HELLO"), vec![(0, 5), (6, 5)]);
+        assert_eq!(find_matches("aaaa", "aa"), vec![(0, 2), (2, 2)]);
+        assert_eq!(find_matches("Grüße, GRÜSSE", "grü"), vec![(0, 4), (9, 4)]);
+        assert_eq!(find_matches("ÄÖÜ äöü", "öü"), vec![(2, 4), (9, 4)]);
+        assert!(find_matches("foo", "bar").is_empty());
+        assert!(find_matches("foo", "").is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_backfill() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let msg_id = send_text_msg(&t, chat.id, "Hello world".to_string()).await?;
+        assert!(is_index_complete(&t).await?);
+        assert_eq!(t.search_msgs(None, "o wor").await?, vec![msg_id]);
+
+        // Simulate a database created before the index.
+        t.sql.execute("DELETE FROM msgs_search", ()).await?;
+        t.sql
+            .set_raw_config_int64(BACKFILL_KEY, msg_id.to_u32().into())
+            .await?;
+        assert_eq!(index_query(&t, "world").await?, None);
+        assert_eq!(t.search_msgs(None, "world").await?, vec![msg_id]);
+
+        backfill_loop(&t).await;
+        assert!(is_index_complete(&t).await?);
+        assert_eq!(
+            index_query(&t, "\"world\"").await?,
+            Some("\"\"\"world\"\"\"".to_string())
+        );
+        assert_eq!(t.search_msgs(None, "world").await?, vec![msg_id]);
+
+        // The index is updated when the message is deleted.
+        t.sql
+            .execute("UPDATE msgs SET txt='' WHERE id=?", (msg_id,))
+            .await?;
+        assert!(t.search_msgs(None, "world").await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_search_chat_msgs() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let msg1 = send_text_msg(&t, chat.id, "Grüße aus Berlin".to_string()).await?;
+        send_text_msg(&t, chat.id, "Hallo".to_string()).await?;
+        let msg3 = send_text_msg(&t, chat.id, "berlin, BERLIN".to_string()).await?;
+
+        let res = t.search_chat_msgs(chat.id, " Berlin ").await?;
+        assert_eq!(
+            res,
+            vec![
+                MsgSearchMatch {
+                    msg_id: msg1,
+                    ranges: vec![(12, 6)],
+                },
+                MsgSearchMatch {
+                    msg_id: msg3,
+                    ranges: vec![(0, 6), (8, 6)],
+                },
+            ]
+        );
+        assert!(t.search_chat_msgs(chat.id, "Paris").await?.is_empty());
+        Ok(())
+    }
+}
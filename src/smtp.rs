@@ -11,19 +11,24 @@
 use tokio::task;
 
 use crate::config::Config;
+use crate::constants::NetworkClass;
 use crate::contact::{Contact, ContactId};
 use crate::events::EventType;
+use crate::jmap::{self, Jmap};
 use crate::login_param::{CertificateChecks, LoginParam, ServerLoginParam};
 use crate::message::Message;
 use crate::message::{self, MsgId};
 use crate::mimefactory::MimeFactory;
 use crate::net::connect_tcp;
+use crate::net::pinning::check_certificate_pin;
 use crate::net::session::SessionBufStream;
 use crate::net::tls::wrap_tls;
+use crate::net::traffic::{CountingStream, TrafficProtocol};
 use crate::oauth2::get_oauth2_access_token;
 use crate::provider::Socket;
 use crate::socks::Socks5Config;
 use crate::sql;
+use crate::transport::Transport;
 use crate::{context::Context, scheduler::connectivity::ConnectivityStore};
 
 /// SMTP write and read timeout.
@@ -46,6 +51,9 @@ pub(crate) struct Smtp {
 
     /// If sending the last message failed, contains the error message.
     pub(crate) last_send_error: Option<String>,
+
+    /// JMAP connection used instead of SMTP if JMAP is enabled.
+    jmap: Option<Jmap>,
 }
 
 impl Smtp {
@@ -54,6 +62,14 @@ pub fn new() -> Self {
         Default::default()
     }
 
+    /// Creates a new Smtp instance sending messages using JMAP instead of SMTP if `jmap` is set.
+    pub(crate) fn with_jmap(jmap: Option<Jmap>) -> Self {
+        Self {
+            jmap,
+            ..Default::default()
+        }
+    }
+
     /// Disconnect the SMTP transport and drop it entirely.
     pub async fn disconnect(&mut self) {
         if let Some(mut transport) = self.transport.take() {
@@ -85,6 +101,7 @@ pub fn is_connected(&self) -> bool {
     }
 
     /// Connect using configured parameters.
+    #[tracing::instrument(skip_all, fields(account_id = context.id))]
     pub async fn connect_configured(&mut self, context: &Context) -> Result<()> {
         if self.has_maybe_stale_connection() {
             info!(context, "Closing stale connection");
@@ -121,7 +138,12 @@ async fn connect_secure_socks5(
             .connect(context, hostname, port, SMTP_TIMEOUT, strict_tls)
             .await?;
         let tls_stream = wrap_tls(strict_tls, hostname, socks5_stream).await?;
-        let buffered_stream = BufStream::new(tls_stream);
+        check_certificate_pin(context, hostname, &tls_stream).await?;
+        let buffered_stream = BufStream::new(CountingStream::new(
+            context,
+            TrafficProtocol::Smtp,
+            tls_stream,
+        ));
         let session_stream: Box<dyn SessionBufStream> = Box::new(buffered_stream);
         let client = smtp::SmtpClient::new().smtp_utf8(true);
         let transport = SmtpTransport::new(client, session_stream).await?;
@@ -147,7 +169,12 @@ async fn connect_starttls_socks5(
         let tls_stream = wrap_tls(strict_tls, hostname, tcp_stream)
             .await
             .context("STARTTLS upgrade failed")?;
-        let buffered_stream = BufStream::new(tls_stream);
+        check_certificate_pin(context, hostname, &tls_stream).await?;
+        let buffered_stream = BufStream::new(CountingStream::new(
+            context,
+            TrafficProtocol::Smtp,
+            tls_stream,
+        ));
         let session_stream: Box<dyn SessionBufStream> = Box::new(buffered_stream);
         let client = smtp::SmtpClient::new().smtp_utf8(true).without_greeting();
         let transport = SmtpTransport::new(client, session_stream).await?;
@@ -164,7 +191,11 @@ async fn connect_insecure_socks5(
         let socks5_stream = socks5_config
             .connect(context, hostname, port, SMTP_TIMEOUT, false)
             .await?;
-        let buffered_stream = BufStream::new(socks5_stream);
+        let buffered_stream = BufStream::new(CountingStream::new(
+            context,
+            TrafficProtocol::Smtp,
+            socks5_stream,
+        ));
         let session_stream: Box<dyn SessionBufStream> = Box::new(buffered_stream);
         let client = smtp::SmtpClient::new().smtp_utf8(true);
         let transport = SmtpTransport::new(client, session_stream).await?;
@@ -180,7 +211,12 @@ async fn connect_secure(
     ) -> Result<SmtpTransport<Box<dyn SessionBufStream>>> {
         let tcp_stream = connect_tcp(context, hostname, port, SMTP_TIMEOUT, false).await?;
         let tls_stream = wrap_tls(strict_tls, hostname, tcp_stream).await?;
-        let buffered_stream = BufStream::new(tls_stream);
+        check_certificate_pin(context, hostname, &tls_stream).await?;
+        let buffered_stream = BufStream::new(CountingStream::new(
+            context,
+            TrafficProtocol::Smtp,
+            tls_stream,
+        ));
         let session_stream: Box<dyn SessionBufStream> = Box::new(buffered_stream);
         let client = smtp::SmtpClient::new().smtp_utf8(true);
         let transport = SmtpTransport::new(client, session_stream).await?;
@@ -203,7 +239,12 @@ async fn connect_starttls(
         let tls_stream = wrap_tls(strict_tls, hostname, tcp_stream)
             .await
             .context("STARTTLS upgrade failed")?;
-        let buffered_stream = BufStream::new(tls_stream);
+        check_certificate_pin(context, hostname, &tls_stream).await?;
+        let buffered_stream = BufStream::new(CountingStream::new(
+            context,
+            TrafficProtocol::Smtp,
+            tls_stream,
+        ));
         let session_stream: Box<dyn SessionBufStream> = Box::new(buffered_stream);
         let client = smtp::SmtpClient::new().smtp_utf8(true).without_greeting();
         let transport = SmtpTransport::new(client, session_stream).await?;
@@ -217,7 +258,11 @@ async fn connect_insecure(
         port: u16,
     ) -> Result<SmtpTransport<Box<dyn SessionBufStream>>> {
         let tcp_stream = connect_tcp(context, hostname, port, SMTP_TIMEOUT, false).await?;
-        let buffered_stream = BufStream::new(tcp_stream);
+        let buffered_stream = BufStream::new(CountingStream::new(
+            context,
+            TrafficProtocol::Smtp,
+            tcp_stream,
+        ));
         let session_stream: Box<dyn SessionBufStream> = Box::new(buffered_stream);
         let client = smtp::SmtpClient::new().smtp_utf8(true);
         let transport = SmtpTransport::new(client, session_stream).await?;
@@ -370,6 +415,32 @@ pub(crate) async fn smtp_send(
 
     smtp.connectivity.set_working(context).await;
 
+    if let Some(transport) = context.get_transport() {
+        return transport_send(context, recipients, message, smtp, transport.as_ref()).await;
+    }
+
+    if let Some(jmap) = smtp.jmap.as_mut() {
+        let status = jmap_send(context, recipients, message, jmap).await;
+        smtp.last_send_error = match &status {
+            Ok(()) => None,
+            Err(err) => Some(format!("{err:#}")),
+        };
+        return match status {
+            Ok(()) => SendResult::Success,
+            Err(jmap::SendError::Permanent(err)) => {
+                info!(context, "Permanent error, message sending failed");
+                let err = format_err!("Permanent JMAP error: {}", err);
+                message::set_msg_failed(context, msg_id, &err.to_string()).await;
+                SendResult::Failure(err)
+            }
+            Err(jmap::SendError::Other(err)) => {
+                warn!(context, "Failed to send message over JMAP: {:#}", err);
+                jmap.trigger_reconnect();
+                SendResult::Retry
+            }
+        };
+    }
+
     if let Err(err) = smtp
         .connect_configured(context)
         .await
@@ -379,6 +450,10 @@ pub(crate) async fn smtp_send(
         return SendResult::Retry;
     }
 
+    let fail_fast = context
+        .get_config_bool(Config::SmtpFailFast)
+        .await
+        .unwrap_or_default();
     let send_result = smtp.send(context, recipients, message.as_bytes()).await;
     smtp.last_send_error = send_result.as_ref().err().map(|e| e.to_string());
 
@@ -411,7 +486,7 @@ pub(crate) async fn smtp_send(
                         _ => false,
                     };
 
-                    if maybe_transient {
+                    if maybe_transient && !fail_fast {
                         info!(context, "Permanent error that is likely to actually be transient, postponing retry for later");
                         SendResult::Retry
                     } else {
@@ -427,7 +502,13 @@ pub(crate) async fn smtp_send(
                     // We got a transient 4xx response from SMTP server.
                     // Give some time until the server-side error maybe goes away.
 
-                    if let Some(first_word) = response.first_word() {
+                    if fail_fast {
+                        info!(
+                            context,
+                            "Transient error, but smtp_fail_fast is set, failing immediately"
+                        );
+                        SendResult::Failure(format_err!("SMTP error: {}", err))
+                    } else if let Some(first_word) = response.first_word() {
                         if first_word.ends_with(".1.1")
                             || first_word.ends_with(".1.2")
                             || first_word.ends_with(".1.3")
@@ -497,21 +578,79 @@ pub(crate) async fn smtp_send(
     status
 }
 
+/// Tries to send a message over JMAP instead of SMTP.
+async fn jmap_send(
+    context: &Context,
+    recipients: &[async_smtp::EmailAddress],
+    message: &str,
+    jmap: &mut Jmap,
+) -> std::result::Result<(), jmap::SendError> {
+    if !context
+        .get_config_bool(Config::Bot)
+        .await
+        .unwrap_or_default()
+    {
+        context.ratelimit.write().await.send();
+    }
+
+    let recipients: Vec<&str> = recipients.iter().map(|addr| addr.as_ref()).collect();
+    jmap.send(context, &recipients, message.as_bytes()).await
+}
+
+/// Tries to send a message using the transport set with [`Context::set_transport`].
+async fn transport_send(
+    context: &Context,
+    recipients: &[async_smtp::EmailAddress],
+    message: &str,
+    smtp: &mut Smtp,
+    transport: &dyn Transport,
+) -> SendResult {
+    if !context
+        .get_config_bool(Config::Bot)
+        .await
+        .unwrap_or_default()
+    {
+        context.ratelimit.write().await.send();
+    }
+
+    let recipients: Vec<String> = recipients
+        .iter()
+        .map(|addr| addr.as_ref().to_string())
+        .collect();
+    match transport
+        .send(context, &recipients, message.as_bytes())
+        .await
+    {
+        Ok(()) => {
+            smtp.last_send_error = None;
+            SendResult::Success
+        }
+        Err(err) => {
+            warn!(context, "Failed to send message using transport: {:#}", err);
+            smtp.last_send_error = Some(format!("{err:#}"));
+            SendResult::Retry
+        }
+    }
+}
+
 /// Sends message identified by `smtp` table rowid over SMTP connection.
 ///
 /// Removes row if the message should not be retried, otherwise increments retry count.
+#[tracing::instrument(skip_all, fields(account_id = context.id, rowid = rowid))]
 pub(crate) async fn send_msg_to_smtp(
     context: &Context,
     smtp: &mut Smtp,
     rowid: i64,
 ) -> anyhow::Result<()> {
-    if let Err(err) = smtp
-        .connect_configured(context)
-        .await
-        .context("SMTP connection failure")
-    {
-        smtp.last_send_error = Some(format!("{err:#}"));
-        return Err(err);
+    if context.get_transport().is_none() {
+        if let Err(err) = smtp
+            .connect_configured(context)
+            .await
+            .context("SMTP connection failure")
+        {
+            smtp.last_send_error = Some(format!("{err:#}"));
+            return Err(err);
+        }
     }
 
     // Increase retry count as soon as we have an SMTP connection. This ensures that the message is
@@ -538,13 +677,16 @@ pub(crate) async fn send_msg_to_smtp(
             },
         )
         .await?;
-    if retries > 6 {
+    let max_attempts = context.get_config_int(Config::SmtpMaxAttempts).await?;
+    if retries > i64::from(max_attempts.max(1)) {
         message::set_msg_failed(context, msg_id, "Number of retries exceeded the limit.").await;
+        context.metrics.count_send_failure();
         context
             .sql
             .execute("DELETE FROM smtp WHERE id=?", (rowid,))
             .await
             .context("failed to remove message with exceeded retry limit from smtp table")?;
+        emit_msg_given_up(context, msg_id).await?;
         bail!("Number of retries exceeded the limit");
     }
     info!(
@@ -593,13 +735,47 @@ pub(crate) async fn send_msg_to_smtp(
     match status {
         SendResult::Retry => Err(format_err!("Retry")),
         SendResult::Success => {
+            context.metrics.count_msg_sent();
             msg_id.set_delivered(context).await?;
             Ok(())
         }
-        SendResult::Failure(err) => Err(format_err!("{}", err)),
+        SendResult::Failure(err) => {
+            context.metrics.count_send_failure();
+            emit_msg_given_up(context, msg_id).await?;
+            Err(format_err!("{}", err))
+        }
     }
 }
 
+/// Emits `MsgGivenUp` for a message removed from the queue without being sent.
+async fn emit_msg_given_up(context: &Context, msg_id: MsgId) -> Result<()> {
+    let msg = Message::load_from_db(context, msg_id).await?;
+    context.emit_event(EventType::MsgGivenUp {
+        chat_id: msg.chat_id,
+        msg_id,
+    });
+    Ok(())
+}
+
+/// Returns the number of seconds to wait before retrying to send messages
+/// after sending failed, given the `previous` delay if sending failed before.
+///
+/// The delay starts at `smtp_retry_delay` and is multiplied by `smtp_retry_backoff`
+/// after each further failure.
+pub(crate) async fn next_retry_delay(context: &Context, previous: Option<u64>) -> Result<u64> {
+    let delay = match previous {
+        None => {
+            let delay = context.get_config_int(Config::SmtpRetryDelay).await?;
+            u64::try_from(delay).unwrap_or_default()
+        }
+        Some(previous) => {
+            let backoff = context.get_config_int(Config::SmtpRetryBackoff).await?;
+            previous.saturating_mul(u64::try_from(backoff).unwrap_or_default().max(1))
+        }
+    };
+    Ok(delay.max(1))
+}
+
 /// Attempts to send queued MDNs.
 async fn send_mdns(context: &Context, connection: &mut Smtp) -> Result<()> {
     loop {
@@ -620,6 +796,7 @@ async fn send_mdns(context: &Context, connection: &mut Smtp) -> Result<()> {
 ///
 /// Logs and ignores SMTP errors to ensure that a single SMTP message constantly failing to be sent
 /// does not block other messages in the queue from being sent.
+#[tracing::instrument(skip_all, fields(account_id = context.id))]
 pub(crate) async fn send_smtp_messages(context: &Context, connection: &mut Smtp) -> Result<()> {
     let ratelimited = if context.ratelimit.read().await.can_send() {
         // add status updates and sync messages to end of sending queue
@@ -630,11 +807,36 @@ pub(crate) async fn send_smtp_messages(context: &Context, connection: &mut Smtp)
         true
     };
 
+    // On metered networks, large messages wait in the queue for an unmetered network.
+    let send_limit = match context.get_network_class().await? {
+        NetworkClass::Unmetered => 0,
+        NetworkClass::Metered | NetworkClass::Roaming => {
+            context.get_config_int(Config::SendLimitMetered).await?
+        }
+    };
+    if send_limit > 0 {
+        let deferred = context
+            .sql
+            .count(
+                "SELECT COUNT(*) FROM smtp WHERE LENGTH(mime)>?",
+                (send_limit,),
+            )
+            .await?;
+        if deferred > 0 {
+            info!(
+                context,
+                "Deferring {} messages larger than {} bytes until the network is unmetered.",
+                deferred,
+                send_limit
+            );
+        }
+    }
+
     let rowids = context
         .sql
         .query_map(
-            "SELECT id FROM smtp ORDER BY id ASC",
-            (),
+            "SELECT id FROM smtp WHERE ?1<=0 OR LENGTH(mime)<=?1 ORDER BY id ASC",
+            (send_limit,),
             |row| {
                 let rowid: i64 = row.get(0)?;
                 Ok(rowid)
@@ -793,3 +995,28 @@ async fn send_mdn(context: &Context, smtp: &mut Smtp) -> Result<bool> {
         Ok(true)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_next_retry_delay() -> Result<()> {
+        let t = TestContext::new().await;
+        assert_eq!(next_retry_delay(&t, None).await?, 30);
+        assert_eq!(next_retry_delay(&t, Some(30)).await?, 90);
+
+        t.set_config(Config::SmtpRetryDelay, Some("5")).await?;
+        t.set_config(Config::SmtpRetryBackoff, Some("1")).await?;
+        assert_eq!(next_retry_delay(&t, None).await?, 5);
+        assert_eq!(next_retry_delay(&t, Some(5)).await?, 5);
+
+        // Invalid values do not result in retrying without delay.
+        t.set_config(Config::SmtpRetryDelay, Some("0")).await?;
+        t.set_config(Config::SmtpRetryBackoff, Some("-1")).await?;
+        assert_eq!(next_retry_delay(&t, None).await?, 1);
+        assert_eq!(next_retry_delay(&t, Some(1)).await?, 1);
+        Ok(())
+    }
+}
@@ -3,24 +3,31 @@
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{bail, Context as _, Result};
+use once_cell::sync::Lazy;
 use rusqlite::{self, config::DbConfig, types::ValueRef, Connection, OpenFlags, Row};
 use tokio::sync::{Mutex, MutexGuard, RwLock};
 
-use crate::blob::BlobObject;
+use crate::blob::{deduplicate_blobs, remove_unreferenced_blobs, BlobObject};
 use crate::chat::{add_device_msg, update_device_icon, update_saved_messages_icon};
 use crate::config::Config;
 use crate::constants::DC_CHAT_ID_TRASH;
 use crate::context::Context;
 use crate::debug_logging::set_debug_logging_xdc;
 use crate::ephemeral::start_ephemeral_timers;
+use crate::events::EventType;
 use crate::imex::BLOBS_BACKUP_NAME;
 use crate::log::LogExt;
-use crate::message::{Message, MsgId, Viewtype};
+use crate::message::{Message, MessageState, MsgId, Viewtype};
 use crate::param::{Param, Params};
 use crate::peerstate::{deduplicate_peerstates, Peerstate};
+use crate::scheduler::InterruptInfo;
 use crate::stock_str;
+use crate::storage::cleanup_media_by_config;
 use crate::tools::{delete_file, time};
 
 /// Extension to [`rusqlite::ToSql`] trait
@@ -47,17 +54,125 @@ pub(crate) fn params_iter(
     iter.iter().map(|item| item as &dyn crate::sql::ToSql)
 }
 
+mod blob_gc;
+mod cold_storage;
+mod diagnostic_export;
+mod maintenance;
 mod migrations;
 mod pool;
+mod storage;
+
+pub use self::blob_gc::{BlobGcReport, OrphanedBlob};
+pub use self::cold_storage::ColdStoredMsg;
+pub use self::maintenance::DbCheckReport;
+pub use self::migrations::MigrationPlan;
+pub use self::storage::{InMemoryStorage, SqlStorage, SqliteFileStorage};
 
 use pool::Pool;
 
+/// SQLite journal mode, see <https://www.sqlite.org/pragma.html#pragma_journal_mode>.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum JournalMode {
+    /// Rollback journal deleted at the end of each transaction.
+    Delete,
+
+    /// Rollback journal truncated at the end of each transaction.
+    Truncate,
+
+    /// Rollback journal kept and invalidated at the end of each transaction.
+    Persist,
+
+    /// Rollback journal kept in memory.
+    Memory,
+
+    /// Write-ahead log, allowing readers to proceed while a transaction is written.
+    #[default]
+    Wal,
+
+    /// No rollback journal, transactions cannot be rolled back.
+    Off,
+}
+
+impl JournalMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            JournalMode::Delete => "DELETE",
+            JournalMode::Truncate => "TRUNCATE",
+            JournalMode::Persist => "PERSIST",
+            JournalMode::Memory => "MEMORY",
+            JournalMode::Wal => "WAL",
+            JournalMode::Off => "OFF",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        [
+            JournalMode::Delete,
+            JournalMode::Truncate,
+            JournalMode::Persist,
+            JournalMode::Memory,
+            JournalMode::Wal,
+            JournalMode::Off,
+        ]
+        .into_iter()
+        .find(|mode| mode.as_str().eq_ignore_ascii_case(s))
+    }
+}
+
+/// Configuration of the connection pool and the connections to the database.
+///
+/// The defaults suit apps with a single user.
+/// Deployments with many concurrent readers, e.g. bots,
+/// may increase the pool size and enable memory-mapped I/O.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SqlConfig {
+    /// Number of connections in the pool, at least 1.
+    ///
+    /// In-memory databases always use a single connection.
+    pub pool_size: usize,
+
+    /// Time to wait for locks held by other processes before failing with `SQLITE_BUSY`.
+    ///
+    /// Locks held by other connections of the pool are waited for anyway.
+    pub busy_timeout: Duration,
+
+    /// Journal mode of the database.
+    pub journal_mode: JournalMode,
+
+    /// Maximum number of bytes of the database file mapped into memory,
+    /// 0 disables memory-mapped I/O.
+    pub mmap_size: u64,
+
+    /// Whether to copy the database file before running schema migrations
+    /// and to restore the copy if a migration fails,
+    /// so the database is not left partially migrated.
+    ///
+    /// The copy is removed once the migrations succeed.
+    /// Requires free space for a copy of the database.
+    pub backup_before_migrate: bool,
+}
+
+impl Default for SqlConfig {
+    fn default() -> Self {
+        Self {
+            pool_size: 3,
+            busy_timeout: Duration::ZERO,
+            journal_mode: JournalMode::Wal,
+            mmap_size: 0,
+            backup_before_migrate: false,
+        }
+    }
+}
+
 /// A wrapper around the underlying Sqlite3 object.
 #[derive(Debug)]
 pub struct Sql {
-    /// Database file path
+    /// Database file path, `:memory:` if the storage has no database file.
     pub(crate) dbfile: PathBuf,
 
+    /// Storage opening the connections.
+    storage: Arc<dyn SqlStorage>,
+
     /// Write transactions mutex.
     ///
     /// See [`Self::write_lock`].
@@ -72,17 +187,38 @@ pub struct Sql {
 
     /// Cache of `config` table.
     pub(crate) config_cache: RwLock<HashMap<String, Option<String>>>,
+
+    /// Configuration used when the database is opened.
+    config: std::sync::RwLock<SqlConfig>,
 }
 
 impl Sql {
     /// Creates new SQL database.
     pub fn new(dbfile: PathBuf) -> Sql {
+        Self::with_storage(Arc::new(SqliteFileStorage::new(dbfile)))
+    }
+
+    /// Creates new SQL database kept in memory.
+    ///
+    /// The data is lost once the database is closed.
+    pub fn new_in_memory() -> Sql {
+        Self::with_storage(Arc::new(InMemoryStorage))
+    }
+
+    /// Creates new SQL database kept in the given storage.
+    pub fn with_storage(storage: Arc<dyn SqlStorage>) -> Sql {
+        let dbfile = storage
+            .path()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from(":memory:"));
         Self {
             dbfile,
+            storage,
             write_mtx: Mutex::new(()),
             pool: Default::default(),
             is_encrypted: Default::default(),
             config_cache: Default::default(),
+            config: Default::default(),
         }
     }
 
@@ -102,7 +238,7 @@ pub async fn check_passphrase(&self, passphrase: String) -> Result<bool> {
         let _lock = self.pool.write().await;
 
         // Test that the key is correct using a single connection.
-        let connection = Connection::open(&self.dbfile)?;
+        let connection = self.storage.open(OpenFlags::default())?;
         connection
             .pragma_update(None, "key", &passphrase)
             .context("failed to set PRAGMA key")?;
@@ -125,8 +261,48 @@ pub(crate) async fn is_encrypted(&self) -> Option<bool> {
         *self.is_encrypted.read().await
     }
 
+    /// Sets the configuration used when the database is opened.
+    ///
+    /// Changes take effect the next time the database is opened.
+    pub fn set_config(&self, config: SqlConfig) {
+        *self.config.write().expect("RwLock is poisoned") = config;
+    }
+
+    /// Returns the configuration used when the database is opened.
+    pub fn get_config(&self) -> SqlConfig {
+        *self.config.read().expect("RwLock is poisoned")
+    }
+
+    /// Returns the configuration actually used by the open database,
+    /// reading the pragmas from a connection of the pool.
+    pub async fn get_current_config(&self) -> Result<SqlConfig> {
+        let pool_size = self
+            .pool
+            .read()
+            .await
+            .as_ref()
+            .context("no SQL connection")?
+            .size();
+        let backup_before_migrate = self.get_config().backup_before_migrate;
+        self.call(move |conn| {
+            let busy_timeout: u64 = conn.query_row("PRAGMA busy_timeout", [], |row| row.get(0))?;
+            let journal_mode: String =
+                conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?;
+            let mmap_size: u64 = conn.query_row("PRAGMA mmap_size", [], |row| row.get(0))?;
+            Ok(SqlConfig {
+                pool_size,
+                busy_timeout: Duration::from_millis(busy_timeout),
+                journal_mode: JournalMode::from_str(&journal_mode)
+                    .with_context(|| format!("unknown journal mode {journal_mode:?}"))?,
+                mmap_size,
+                backup_before_migrate,
+            })
+        })
+        .await
+    }
+
     /// Closes all underlying Sqlite connections.
-    async fn close(&self) {
+    pub(crate) async fn close(&self) {
         let _ = self.pool.write().await.take();
         // drop closes the connection
     }
@@ -180,10 +356,16 @@ pub(crate) async fn import(&self, path: &Path, passphrase: String) -> Result<()>
     }
 
     /// Creates a new connection pool.
-    fn new_pool(dbfile: &Path, passphrase: String) -> Result<Pool> {
+    ///
+    /// The pool size is limited by [`SqlStorage::max_connections`].
+    fn new_pool(storage: &dyn SqlStorage, passphrase: String, config: &SqlConfig) -> Result<Pool> {
+        let mut num_connections = config.pool_size.max(1);
+        if let Some(max_connections) = storage.max_connections() {
+            num_connections = num_connections.min(max_connections.max(1));
+        }
         let mut connections = Vec::new();
-        for _ in 0..3 {
-            let connection = new_connection(dbfile, &passphrase)?;
+        for _ in 0..num_connections {
+            let connection = new_connection(storage, &passphrase, config)?;
             connections.push(connection);
         }
 
@@ -191,11 +373,95 @@ fn new_pool(dbfile: &Path, passphrase: String) -> Result<Pool> {
         Ok(pool)
     }
 
-    async fn try_open(&self, context: &Context, dbfile: &Path, passphrase: String) -> Result<()> {
-        *self.pool.write().await = Some(Self::new_pool(dbfile, passphrase.to_string())?);
+    async fn try_open(&self, context: &Context, passphrase: String) -> Result<()> {
+        let config = self.get_config();
+        *self.pool.write().await = Some(Self::new_pool(
+            self.storage.as_ref(),
+            passphrase.to_string(),
+            &config,
+        )?);
+
+        let backup = match self.storage.path() {
+            Some(dbfile) if config.backup_before_migrate => {
+                self.backup_before_migrate(context, dbfile).await?
+            }
+            _ => None,
+        };
+        if let Err(err) = self.run_migrations(context).await {
+            if let (Some(dbfile), Some(backup)) = (self.storage.path(), backup) {
+                self.restore_migration_backup(dbfile, &backup)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "{err:#}, failed to restore the database from {}",
+                            backup.display()
+                        )
+                    })?;
+                warn!(
+                    context,
+                    "Migrations failed, restored the database from the backup: {err:#}."
+                );
+            }
+            return Err(err);
+        }
+        if let Some(backup) = backup {
+            tokio::fs::remove_file(&backup)
+                .await
+                .context("failed to remove the database backup")
+                .log_err(context)
+                .ok();
+        }
+
+        Ok(())
+    }
+
+    /// Copies the database file if migrations are needed
+    /// and returns the path of the copy.
+    async fn backup_before_migrate(
+        &self,
+        context: &Context,
+        dbfile: &Path,
+    ) -> Result<Option<PathBuf>> {
+        let plan = self.get_migration_plan(String::new()).await?;
+        if !plan.is_needed() {
+            return Ok(None);
+        }
+
+        // Move the content of the write-ahead log into the database file before copying it.
+        self.call_write(|conn| {
+            conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", (), |_row| Ok(()))?;
+            Ok(())
+        })
+        .await?;
+        let backup = path_with_suffix(dbfile, "-migration-backup");
+        tokio::fs::copy(dbfile, &backup)
+            .await
+            .with_context(|| format!("failed to copy the database to {}", backup.display()))?;
+        info!(
+            context,
+            "Copied the database to {} before migrating from version {} to {}.",
+            backup.display(),
+            plan.db_version.unwrap_or_default(),
+            plan.latest_version
+        );
+        Ok(Some(backup))
+    }
 
-        self.run_migrations(context).await?;
+    /// Closes the database and replaces it with the copy made before running migrations.
+    async fn restore_migration_backup(&self, dbfile: &Path, backup: &Path) -> Result<()> {
+        self.close().await;
+        // The cache contains values written by the migrations.
+        self.config_cache.write().await.clear();
 
+        // Closing the last connection normally removes these files,
+        // leftovers must not be applied to the restored database.
+        for suffix in ["-wal", "-shm"] {
+            match tokio::fs::remove_file(path_with_suffix(dbfile, suffix)).await {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+                _ => {}
+            }
+        }
+        tokio::fs::rename(backup, dbfile).await?;
         Ok(())
     }
 
@@ -285,7 +551,7 @@ pub async fn open(&self, context: &Context, passphrase: String) -> Result<()> {
         }
 
         let passphrase_nonempty = !passphrase.is_empty();
-        if let Err(err) = self.try_open(context, &self.dbfile, passphrase).await {
+        if let Err(err) = self.try_open(context, passphrase).await {
             self.close().await;
             Err(err)
         } else {
@@ -650,22 +916,46 @@ pub fn config_cache(&self) -> &RwLock<HashMap<String, Option<String>>> {
     }
 }
 
+/// Returns `path` with `suffix` appended to the file name.
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Returns true if the linked SQLite library is SQLCipher,
+/// so databases and backups can be encrypted.
+pub(crate) fn is_sqlcipher() -> bool {
+    static IS_SQLCIPHER: Lazy<bool> = Lazy::new(|| {
+        // Plain SQLite ignores unknown pragmas and returns no row.
+        Connection::open_in_memory()
+            .and_then(|conn| {
+                conn.query_row("PRAGMA cipher_version", [], |row| row.get::<_, String>(0))
+            })
+            .is_ok()
+    });
+    *IS_SQLCIPHER
+}
+
 /// Creates a new SQLite connection.
 ///
-/// `path` is the database path.
+/// `storage` opens the connection.
 ///
 /// `passphrase` is the SQLCipher database passphrase.
 /// Empty string if database is not encrypted.
-fn new_connection(path: &Path, passphrase: &str) -> Result<Connection> {
+fn new_connection(
+    storage: &dyn SqlStorage,
+    passphrase: &str,
+    config: &SqlConfig,
+) -> Result<Connection> {
     let mut flags = OpenFlags::SQLITE_OPEN_NO_MUTEX;
     flags.insert(OpenFlags::SQLITE_OPEN_READ_WRITE);
     flags.insert(OpenFlags::SQLITE_OPEN_CREATE);
 
-    let conn = Connection::open_with_flags(path, flags)?;
+    let conn = storage.open(flags)?;
     conn.execute_batch(
         "PRAGMA cipher_memory_security = OFF; -- Too slow on Android
          PRAGMA secure_delete=on;
-         PRAGMA busy_timeout = 0; -- fail immediately
          PRAGMA temp_store=memory; -- Avoid SQLITE_IOERR_GETTEMPPATH errors on Android
          PRAGMA foreign_keys=on;
          ",
@@ -679,15 +969,86 @@ fn new_connection(path: &Path, passphrase: &str) -> Result<Connection> {
     // database pages to the filesystem.
     conn.pragma_update(None, "auto_vacuum", "INCREMENTAL".to_string())?;
 
-    conn.pragma_update(None, "journal_mode", "WAL".to_string())?;
+    // Fails immediately by default.
+    conn.busy_timeout(config.busy_timeout)?;
+    conn.pragma_update(None, "journal_mode", config.journal_mode.as_str())?;
     // Default synchronous=FULL is much slower. NORMAL is sufficient for WAL mode.
     conn.pragma_update(None, "synchronous", "NORMAL".to_string())?;
+    conn.pragma_update(None, "mmap_size", config.mmap_size)?;
 
     Ok(conn)
 }
 
+/// Statistics about what was cleaned up by housekeeping.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HousekeepingStats {
+    /// Number of messages whose media was deleted according to the
+    /// [`MediaCleanupPolicy`](crate::storage::MediaCleanupPolicy).
+    pub deleted_msgs: usize,
+
+    /// Number of deleted messages removed from the database completely.
+    pub pruned_tombstones: usize,
+
+    /// Number of unused files removed from the blob directory.
+    pub removed_files: usize,
+
+    /// Number of bytes of the removed files.
+    pub freed_bytes: u64,
+}
+
+impl Context {
+    /// Runs housekeeping now and returns what was cleaned up.
+    ///
+    /// Housekeeping runs automatically while IO is running
+    /// every [`Config::HousekeepingInterval`] seconds unless deferred,
+    /// see [`Context::set_housekeeping_deferred`].
+    /// Running it explicitly also works while it is deferred.
+    pub async fn run_housekeeping(&self) -> Result<HousekeepingStats> {
+        housekeeping(self).await
+    }
+
+    /// Defers automatic housekeeping, e.g. while the device is on battery.
+    ///
+    /// When housekeeping is not deferred anymore, it runs if it is overdue.
+    /// The setting is not persisted and is disabled for new contexts.
+    pub async fn set_housekeeping_deferred(&self, deferred: bool) {
+        self.housekeeping_deferred
+            .store(deferred, Ordering::Relaxed);
+        if !deferred {
+            self.scheduler
+                .interrupt_inbox(InterruptInfo::new(false))
+                .await;
+        }
+    }
+
+    /// Returns true if automatic housekeeping is deferred,
+    /// see [`Context::set_housekeeping_deferred`].
+    pub fn is_housekeeping_deferred(&self) -> bool {
+        self.housekeeping_deferred.load(Ordering::Relaxed)
+    }
+}
+
+/// Runs housekeeping if it is due and not deferred, called by the IO scheduler.
+pub(crate) async fn maybe_run_housekeeping(context: &Context) -> Result<()> {
+    let interval = context.get_config_i64(Config::HousekeepingInterval).await?;
+    if interval <= 0 || context.is_housekeeping_deferred() {
+        return Ok(());
+    }
+    let last_housekeeping = context.get_config_i64(Config::LastHousekeeping).await?;
+    if last_housekeeping.saturating_add(interval) <= time() {
+        housekeeping(context).await?;
+    }
+    Ok(())
+}
+
 /// Cleanup the account to restore some storage and optimize the database.
-pub async fn housekeeping(context: &Context) -> Result<()> {
+///
+/// Emits [`EventType::HousekeepingDone`] with the statistics when done.
+pub async fn housekeeping(context: &Context) -> Result<HousekeepingStats> {
+    // Explicit runs must not overlap with the ones of the IO scheduler.
+    let _guard = context.housekeeping_mutex.lock().await;
+    let mut stats = HousekeepingStats::default();
+
     // Setting `Config::LastHousekeeping` at the beginning avoids endless loops when things do not
     // work out for whatever reason or are interrupted by the OS.
     if let Err(e) = context
@@ -697,13 +1058,34 @@ pub async fn housekeeping(context: &Context) -> Result<()> {
         warn!(context, "Can't set config: {e:#}.");
     }
 
-    if let Err(err) = remove_unused_files(context).await {
+    if let Err(err) = deduplicate_blobs(context).await {
+        warn!(context, "Housekeeping: cannot deduplicate blobs: {err:#}.");
+    }
+
+    // Files which are not deduplicated are removed by `remove_unused_files()` below.
+    match cleanup_media_by_config(context).await {
+        Ok(deleted_msgs) => stats.deleted_msgs = deleted_msgs,
+        Err(err) => warn!(context, "Housekeeping: cannot clean up media: {err:#}."),
+    }
+
+    if let Err(err) = remove_unreferenced_blobs(context).await {
         warn!(
             context,
-            "Housekeeping: cannot remove unused files: {:#}.", err
+            "Housekeeping: cannot remove unreferenced blobs: {err:#}."
         );
     }
 
+    match remove_unused_files(context).await {
+        Ok((removed_files, freed_bytes)) => {
+            stats.removed_files = removed_files;
+            stats.freed_bytes = freed_bytes;
+        }
+        Err(err) => warn!(
+            context,
+            "Housekeeping: cannot remove unused files: {:#}.", err
+        ),
+    }
+
     if let Err(err) = start_ephemeral_timers(context).await {
         warn!(
             context,
@@ -711,10 +1093,18 @@ pub async fn housekeeping(context: &Context) -> Result<()> {
         );
     }
 
-    if let Err(err) = prune_tombstones(&context.sql).await {
-        warn!(
+    match prune_tombstones(&context.sql).await {
+        Ok(pruned_tombstones) => stats.pruned_tombstones = pruned_tombstones,
+        Err(err) => warn!(
             context,
             "Housekeeping: Cannot prune message tombstones: {:#}.", err
+        ),
+    }
+
+    if let Err(err) = context.flush_traffic_stats().await {
+        warn!(
+            context,
+            "Housekeeping: Cannot persist traffic statistics: {:#}.", err
         );
     }
 
@@ -755,8 +1145,25 @@ pub async fn housekeeping(context: &Context) -> Result<()> {
         .log_err(context)
         .ok();
 
-    info!(context, "Housekeeping done.");
-    Ok(())
+    context
+        .sql
+        .execute(
+            "DELETE FROM pending_notifications
+             WHERE msg_id NOT IN (SELECT id FROM msgs WHERE state=?)",
+            (MessageState::InFresh,),
+        )
+        .await
+        .context("failed to remove outdated pending notifications")
+        .log_err(context)
+        .ok();
+
+    info!(context, "Housekeeping done: {stats:?}.");
+    context.emit_event(EventType::HousekeepingDone {
+        deleted_msgs: stats.deleted_msgs,
+        removed_files: stats.removed_files,
+        freed_bytes: stats.freed_bytes,
+    });
+    Ok(stats)
 }
 
 /// Get the value of a column `idx` of the `row` as `Vec<u8>`.
@@ -770,55 +1177,15 @@ pub fn row_get_vec(row: &Row, idx: usize) -> rusqlite::Result<Vec<u8>> {
 }
 
 /// Enumerates used files in the blobdir and removes unused ones.
-pub async fn remove_unused_files(context: &Context) -> Result<()> {
-    let mut files_in_use = HashSet::new();
+///
+/// Returns the number of removed files and their total size in bytes.
+pub async fn remove_unused_files(context: &Context) -> Result<(usize, u64)> {
     let mut unreferenced_count = 0;
+    let mut removed_files = 0;
+    let mut freed_bytes = 0;
 
     info!(context, "Start housekeeping...");
-    maybe_add_from_param(
-        &context.sql,
-        &mut files_in_use,
-        "SELECT param FROM msgs  WHERE chat_id!=3   AND type!=10;",
-        Param::File,
-    )
-    .await?;
-    maybe_add_from_param(
-        &context.sql,
-        &mut files_in_use,
-        "SELECT param FROM jobs;",
-        Param::File,
-    )
-    .await?;
-    maybe_add_from_param(
-        &context.sql,
-        &mut files_in_use,
-        "SELECT param FROM chats;",
-        Param::ProfileImage,
-    )
-    .await?;
-    maybe_add_from_param(
-        &context.sql,
-        &mut files_in_use,
-        "SELECT param FROM contacts;",
-        Param::ProfileImage,
-    )
-    .await?;
-
-    context
-        .sql
-        .query_map(
-            "SELECT value FROM config;",
-            (),
-            |row| row.get::<_, String>(0),
-            |rows| {
-                for row in rows {
-                    maybe_add_file(&mut files_in_use, &row?);
-                }
-                Ok(())
-            },
-        )
-        .await
-        .context("housekeeping: failed to SELECT value FROM config")?;
+    let files_in_use = get_files_in_use(context).await?;
 
     info!(context, "{} files in use.", files_in_use.len());
     /* go through directories and delete unused files */
@@ -826,22 +1193,12 @@ pub async fn remove_unused_files(context: &Context) -> Result<()> {
     for p in [&blobdir.join(BLOBS_BACKUP_NAME), blobdir] {
         match tokio::fs::read_dir(p).await {
             Ok(mut dir_handle) => {
-                /* avoid deletion of files that are just created to build a message object */
-                let diff = std::time::Duration::from_secs(60 * 60);
-                let keep_files_newer_than = std::time::SystemTime::now()
-                    .checked_sub(diff)
-                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
-
                 while let Ok(Some(entry)) = dir_handle.next_entry().await {
+                    let mut size = 0;
                     let name_f = entry.file_name();
                     let name_s = name_f.to_string_lossy();
 
-                    if p == blobdir
-                        && (is_file_in_use(&files_in_use, None, &name_s)
-                            || is_file_in_use(&files_in_use, Some(".increation"), &name_s)
-                            || is_file_in_use(&files_in_use, Some(".waveform"), &name_s)
-                            || is_file_in_use(&files_in_use, Some("-preview.jpg"), &name_s))
-                    {
+                    if p == blobdir && is_blob_in_use(&files_in_use, &name_s) {
                         continue;
                     }
 
@@ -860,18 +1217,7 @@ pub async fn remove_unused_files(context: &Context) -> Result<()> {
                             continue;
                         }
                         unreferenced_count += 1;
-                        let recently_created =
-                            stats.created().map_or(false, |t| t > keep_files_newer_than);
-                        let recently_modified = stats
-                            .modified()
-                            .map_or(false, |t| t > keep_files_newer_than);
-                        let recently_accessed = stats
-                            .accessed()
-                            .map_or(false, |t| t > keep_files_newer_than);
-
-                        if p == blobdir
-                            && (recently_created || recently_modified || recently_accessed)
-                        {
+                        if p == blobdir && is_recent_file(&stats) {
                             info!(
                                 context,
                                 "Housekeeping: Keeping new unreferenced file #{}: {:?}.",
@@ -880,6 +1226,7 @@ pub async fn remove_unused_files(context: &Context) -> Result<()> {
                             );
                             continue;
                         }
+                        size = stats.len();
                     } else {
                         unreferenced_count += 1;
                     }
@@ -890,13 +1237,17 @@ pub async fn remove_unused_files(context: &Context) -> Result<()> {
                         entry.file_name()
                     );
                     let path = entry.path();
-                    if let Err(err) = delete_file(context, &path).await {
-                        error!(
+                    match delete_file(context, &path).await {
+                        Ok(()) => {
+                            removed_files += 1;
+                            freed_bytes += size;
+                        }
+                        Err(err) => error!(
                             context,
                             "Failed to delete unused file {}: {:#}.",
                             path.display(),
                             err
-                        );
+                        ),
                     }
                 }
             }
@@ -913,7 +1264,92 @@ pub async fn remove_unused_files(context: &Context) -> Result<()> {
         }
     }
 
-    Ok(())
+    Ok((removed_files, freed_bytes))
+}
+
+/// Returns the names of the files in the blob directory
+/// referenced by messages, jobs, chats, contacts or the configuration.
+async fn get_files_in_use(context: &Context) -> Result<HashSet<String>> {
+    let mut files_in_use = HashSet::new();
+    maybe_add_from_param(
+        &context.sql,
+        &mut files_in_use,
+        "SELECT param FROM msgs  WHERE chat_id!=3   AND type!=10;",
+        Param::File,
+    )
+    .await?;
+    maybe_add_from_param(
+        &context.sql,
+        &mut files_in_use,
+        "SELECT param FROM jobs;",
+        Param::File,
+    )
+    .await?;
+    for param in context.sql.get_cold_stored_params().await? {
+        let param: Params = param.parse().unwrap_or_default();
+        if let Some(file) = param.get(Param::File) {
+            maybe_add_file(&mut files_in_use, file);
+        }
+    }
+    maybe_add_from_param(
+        &context.sql,
+        &mut files_in_use,
+        "SELECT param FROM chats;",
+        Param::ProfileImage,
+    )
+    .await?;
+    maybe_add_from_param(
+        &context.sql,
+        &mut files_in_use,
+        "SELECT param FROM contacts;",
+        Param::ProfileImage,
+    )
+    .await?;
+
+    context
+        .sql
+        .query_map(
+            "SELECT value FROM config;",
+            (),
+            |row| row.get::<_, String>(0),
+            |rows| {
+                for row in rows {
+                    maybe_add_file(&mut files_in_use, &row?);
+                }
+                Ok(())
+            },
+        )
+        .await
+        .context("housekeeping: failed to SELECT value FROM config")?;
+    Ok(files_in_use)
+}
+
+/// Returns true if the file in the blob directory is in use,
+/// including temporary and derived files of blobs in use.
+fn is_blob_in_use(files_in_use: &HashSet<String>, name: &str) -> bool {
+    is_file_in_use(files_in_use, None, name)
+        || is_file_in_use(files_in_use, Some(".increation"), name)
+        || is_file_in_use(files_in_use, Some(".waveform"), name)
+        || is_file_in_use(files_in_use, Some("-preview.jpg"), name)
+}
+
+/// Returns true if the file was created, modified or accessed during the last hour.
+///
+/// Such files may have just been created to build a message object,
+/// so they are not removed even if they are not referenced.
+fn is_recent_file(stats: &std::fs::Metadata) -> bool {
+    let diff = std::time::Duration::from_secs(60 * 60);
+    let keep_files_newer_than = std::time::SystemTime::now()
+        .checked_sub(diff)
+        .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+    let recently_created = stats.created().map_or(false, |t| t > keep_files_newer_than);
+    let recently_modified = stats
+        .modified()
+        .map_or(false, |t| t > keep_files_newer_than);
+    let recently_accessed = stats
+        .accessed()
+        .map_or(false, |t| t > keep_files_newer_than);
+    recently_created || recently_modified || recently_accessed
 }
 
 #[allow(clippy::indexing_slicing)]
@@ -965,7 +1401,9 @@ async fn maybe_add_from_param(
 
 /// Removes from the database locally deleted messages that also don't
 /// have a server UID.
-async fn prune_tombstones(sql: &Sql) -> Result<()> {
+/// Removes deleted messages which do not need to be deleted on the server anymore
+/// and returns their number.
+async fn prune_tombstones(sql: &Sql) -> Result<usize> {
     sql.execute(
         "DELETE FROM msgs
          WHERE chat_id=?
@@ -974,8 +1412,7 @@ async fn prune_tombstones(sql: &Sql) -> Result<()> {
          )",
         (DC_CHAT_ID_TRASH,),
     )
-    .await?;
-    Ok(())
+    .await
 }
 
 /// Helper function to return comma-separated sequence of `?` chars.
@@ -1123,6 +1560,62 @@ async fn test_housekeeping_delete_blobs_backup_dir() {
         tokio::fs::create_dir(&dir).await.unwrap();
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_housekeeping_scheduling() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let dir = t.get_blobdir().join(BLOBS_BACKUP_NAME);
+        tokio::fs::create_dir(&dir).await?;
+        tokio::fs::write(dir.join("f"), "hello").await?;
+
+        let stats = t.run_housekeeping().await?;
+        assert_eq!(stats.removed_files, 1);
+        assert_eq!(stats.freed_bytes, 5);
+        t.evtracker
+            .get_matching(|evt| {
+                matches!(
+                    evt,
+                    EventType::HousekeepingDone {
+                        removed_files: 1,
+                        freed_bytes: 5,
+                        ..
+                    }
+                )
+            })
+            .await;
+        assert!(t.get_config_i64(Config::LastHousekeeping).await? > 0);
+
+        // Deferred housekeeping does not run automatically.
+        t.set_config(Config::LastHousekeeping, None).await?;
+        t.set_housekeeping_deferred(true).await;
+        assert!(t.is_housekeeping_deferred());
+        maybe_run_housekeeping(&t).await?;
+        assert_eq!(t.get_config_i64(Config::LastHousekeeping).await?, 0);
+
+        t.set_housekeeping_deferred(false).await;
+        t.set_config(Config::HousekeepingInterval, Some("0"))
+            .await?;
+        maybe_run_housekeeping(&t).await?;
+        assert_eq!(t.get_config_i64(Config::LastHousekeeping).await?, 0);
+
+        t.set_config(Config::HousekeepingInterval, None).await?;
+        maybe_run_housekeeping(&t).await?;
+        let last_housekeeping = t.get_config_i64(Config::LastHousekeeping).await?;
+        assert!(last_housekeeping > 0);
+
+        // Housekeeping is not due yet.
+        t.set_config(
+            Config::LastHousekeeping,
+            Some(&(last_housekeeping - 10).to_string()),
+        )
+        .await?;
+        maybe_run_housekeeping(&t).await?;
+        assert_eq!(
+            t.get_config_i64(Config::LastHousekeeping).await?,
+            last_housekeeping - 10
+        );
+        Ok(())
+    }
+
     /// Regression test.
     ///
     /// Previously the code checking for existence of `config` table
@@ -1167,6 +1660,91 @@ async fn test_db_reopen() -> Result<()> {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_sql_config() -> Result<()> {
+        use tempfile::tempdir;
+
+        // The context is used only for logging.
+        let t = TestContext::new().await;
+
+        let dir = tempdir()?;
+        let sql = Sql::new(dir.path().join("testdb.sqlite"));
+        sql.open(&t, "".to_string()).await?;
+        assert_eq!(sql.get_current_config().await?, SqlConfig::default());
+        sql.close().await;
+
+        let config = SqlConfig {
+            pool_size: 5,
+            busy_timeout: Duration::from_secs(2),
+            journal_mode: JournalMode::Truncate,
+            mmap_size: 1 << 20,
+            backup_before_migrate: true,
+        };
+        sql.set_config(config);
+        assert!(sql.get_current_config().await.is_err());
+        sql.open(&t, "".to_string()).await?;
+        assert_eq!(sql.get_current_config().await?, config);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_backup_before_migrate() -> Result<()> {
+        use tempfile::tempdir;
+
+        // The context is used only for logging.
+        let t = TestContext::new().await;
+
+        let dir = tempdir()?;
+        let dbfile = dir.path().join("testdb.sqlite");
+        let backup = path_with_suffix(&dbfile, "-migration-backup");
+        let sql = Sql::new(dbfile);
+        let plan = sql.get_migration_plan("".to_string()).await?;
+        assert_eq!(plan.db_version, None);
+        assert!(!plan.is_needed());
+
+        sql.open(&t, "".to_string()).await?;
+        let latest_version = sql.get_migration_plan("".to_string()).await?.latest_version;
+        assert_eq!(
+            sql.get_raw_config_int("dbversion").await?,
+            Some(latest_version)
+        );
+
+        // Successful migrations remove the backup.
+        sql.set_raw_config_int("dbversion", latest_version - 1)
+            .await?;
+        sql.close().await;
+        let plan = sql.get_migration_plan("".to_string()).await?;
+        assert_eq!(plan.db_version, Some(latest_version - 1));
+        assert!(plan.is_needed());
+        sql.set_config(SqlConfig {
+            backup_before_migrate: true,
+            ..Default::default()
+        });
+        sql.open(&t, "".to_string()).await?;
+        assert!(!backup.exists());
+        assert!(!sql.get_migration_plan("".to_string()).await?.is_needed());
+
+        // Simulate a database where the migration to version 102 succeeds,
+        // but the migration to version 103 fails.
+        sql.execute("DROP TABLE certificate_pins", ()).await?;
+        sql.set_raw_config_int("dbversion", 101).await?;
+        sql.close().await;
+
+        assert!(sql.open(&t, "".to_string()).await.is_err());
+        assert!(!sql.is_open().await);
+        assert!(!backup.exists());
+        let plan = sql.get_migration_plan("".to_string()).await?;
+        assert_eq!(plan.db_version, Some(101));
+
+        // Without the backup, the database is left partially migrated.
+        sql.set_config(SqlConfig::default());
+        assert!(sql.open(&t, "".to_string()).await.is_err());
+        let plan = sql.get_migration_plan("".to_string()).await?;
+        assert_eq!(plan.db_version, Some(102));
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_migration_flags() -> Result<()> {
         let t = TestContext::new().await;
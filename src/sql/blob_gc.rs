@@ -0,0 +1,212 @@
+//! # Orphaned blob garbage collection.
+//!
+//! Housekeeping removes files from the blob directory which are not referenced anymore.
+//! [`Context::collect_blob_garbage`] reports these files
+//! together with references to files which do not exist,
+//! to diagnose why the storage does not shrink,
+//! and optionally removes the orphaned files right away.
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use tokio::fs;
+
+use super::{get_files_in_use, is_blob_in_use, is_recent_file};
+use crate::constants::DC_CHAT_ID_TRASH;
+use crate::context::Context;
+use crate::message::MsgId;
+use crate::param::{Param, Params};
+use crate::tools::delete_file;
+
+/// File in the blob directory not referenced anywhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanedBlob {
+    /// Name of the file in the blob directory.
+    pub name: String,
+
+    /// Size of the file in bytes.
+    pub size: u64,
+
+    /// Whether the file was used during the last hour.
+    ///
+    /// Such files may belong to messages being created
+    /// and are not removed.
+    pub recent: bool,
+}
+
+/// Result of [`Context::collect_blob_garbage`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BlobGcReport {
+    /// Files in the blob directory not referenced by messages, chats, contacts or the configuration.
+    pub orphaned_blobs: Vec<OrphanedBlob>,
+
+    /// Names of files referenced by messages, chats, contacts or the configuration
+    /// which do not exist in the blob directory.
+    pub missing_blobs: Vec<String>,
+
+    /// Messages whose file does not exist in the blob directory.
+    pub msgs_with_missing_blobs: Vec<MsgId>,
+
+    /// Number of orphaned files removed.
+    pub removed_blobs: usize,
+
+    /// Number of bytes of the removed files.
+    pub freed_bytes: u64,
+}
+
+impl BlobGcReport {
+    /// Returns the total size of the orphaned files in bytes.
+    pub fn orphaned_bytes(&self) -> u64 {
+        self.orphaned_blobs.iter().map(|blob| blob.size).sum()
+    }
+}
+
+impl Context {
+    /// Looks for files in the blob directory which are not referenced anywhere
+    /// and for references to files which do not exist.
+    ///
+    /// If `remove` is true, orphaned files not used during the last hour are removed,
+    /// as housekeeping does.
+    /// Subdirectories of the blob directory are not looked at.
+    pub async fn collect_blob_garbage(&self, remove: bool) -> Result<BlobGcReport> {
+        let mut report = BlobGcReport::default();
+        let files_in_use = get_files_in_use(self).await?;
+
+        let blobdir = self.get_blobdir();
+        let mut existing = HashSet::new();
+        let mut entries = fs::read_dir(blobdir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !is_blob_in_use(&files_in_use, &name) {
+                report.orphaned_blobs.push(OrphanedBlob {
+                    name: name.clone(),
+                    size: metadata.len(),
+                    recent: is_recent_file(&metadata),
+                });
+            }
+            existing.insert(name);
+        }
+        report.orphaned_blobs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        report.missing_blobs = files_in_use
+            .iter()
+            .filter(|name| !existing.contains(*name))
+            .cloned()
+            .collect();
+        report.missing_blobs.sort();
+
+        if !report.missing_blobs.is_empty() {
+            let msgs = self
+                .sql
+                .query_map(
+                    "SELECT id, param FROM msgs WHERE chat_id!=? AND param LIKE '%f=%' ORDER BY id",
+                    (DC_CHAT_ID_TRASH,),
+                    |row| {
+                        let id: MsgId = row.get(0)?;
+                        let param: String = row.get(1)?;
+                        Ok((id, param))
+                    },
+                    |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+                )
+                .await?;
+            for (msg_id, param) in msgs {
+                let param: Params = param.parse().unwrap_or_default();
+                if let Some(name) = param
+                    .get(Param::File)
+                    .and_then(|file| file.strip_prefix("$BLOBDIR/"))
+                {
+                    if !existing.contains(name) {
+                        report.msgs_with_missing_blobs.push(msg_id);
+                    }
+                }
+            }
+        }
+
+        if remove {
+            for blob in report.orphaned_blobs.iter().filter(|blob| !blob.recent) {
+                match delete_file(self, blobdir.join(&blob.name)).await {
+                    Ok(()) => {
+                        report.removed_blobs += 1;
+                        report.freed_bytes += blob.size;
+                    }
+                    Err(err) => warn!(self, "Cannot remove orphaned blob {}: {err:#}.", blob.name),
+                }
+            }
+        }
+
+        info!(
+            self,
+            "Found {} orphaned blobs ({} bytes) and {} missing blobs, removed {} blobs.",
+            report.orphaned_blobs.len(),
+            report.orphaned_bytes(),
+            report.missing_blobs.len(),
+            report.removed_blobs
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat;
+    use crate::message::{Message, Viewtype};
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_collect_blob_garbage() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let report = t.collect_blob_garbage(false).await?;
+        assert!(report.missing_blobs.is_empty());
+        assert!(report.msgs_with_missing_blobs.is_empty());
+
+        let file = t.get_blobdir().join("used.txt");
+        fs::write(&file, b"used").await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), None);
+        chat::send_msg(&t, chat.id, &mut msg).await?;
+        let used_name = msg
+            .get_file(&t)
+            .unwrap()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        let missing = t.get_blobdir().join("missing.txt");
+        fs::write(&missing, b"missing").await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(missing.to_str().unwrap(), None);
+        let missing_msg_id = chat::send_msg(&t, chat.id, &mut msg).await?;
+        fs::remove_file(msg.get_file(&t).unwrap()).await?;
+
+        let orphan = t.get_blobdir().join("orphan.txt");
+        fs::write(&orphan, b"orphan").await?;
+
+        let report = t.collect_blob_garbage(false).await?;
+        let orphaned: Vec<&str> = report
+            .orphaned_blobs
+            .iter()
+            .map(|blob| blob.name.as_str())
+            .collect();
+        assert!(orphaned.contains(&"orphan.txt"));
+        assert!(!orphaned.contains(&used_name.as_str()));
+        assert!(report.orphaned_blobs.iter().all(|blob| blob.recent));
+        assert!(report.orphaned_bytes() >= 6);
+        assert_eq!(report.msgs_with_missing_blobs, vec![missing_msg_id]);
+        assert_eq!(report.missing_blobs.len(), 1);
+        assert_eq!(report.removed_blobs, 0);
+
+        // Recently used files are kept.
+        let report = t.collect_blob_garbage(true).await?;
+        assert_eq!(report.removed_blobs, 0);
+        assert!(orphan.exists());
+        assert!(t.get_blobdir().join(used_name).exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,739 @@
+//! # Cold storage for old messages.
+//!
+//! Accounts with millions of messages can move old messages
+//! into a secondary database next to the main one with
+//! [`Context::move_msgs_to_cold_storage`], keeping the main database small
+//! and queries such as loading the chatlist fast.
+//!
+//! The cold storage database is attached to a pooled connection
+//! the first time it is accessed on this connection and stays attached,
+//! e.g. to browse the history of a chat with [`Context::get_cold_stored_msgs`]
+//! or to search it with [`Context::search_cold_stored_msgs`].
+//! [`Context::restore_msgs_from_cold_storage`] moves the messages of a chat back,
+//! so they can be used as any other message.
+//!
+//! The cold storage database is encrypted with the passphrase of the main database.
+//! It is not included in backups.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context as _, Result};
+use rusqlite::{Connection, Transaction};
+
+use super::{path_with_suffix, Sql};
+use crate::blob::deduplicated_blob_sql;
+use crate::chat::ChatId;
+use crate::constants::{DC_CHAT_ID_LAST_SPECIAL, DC_MSG_ID_LAST_SPECIAL};
+use crate::contact::ContactId;
+use crate::context::Context;
+use crate::message::{MessageState, MsgId, Viewtype};
+
+/// Tables with rows referencing messages in the `msg_id` column
+/// which are moved to cold storage together with the messages.
+const REFERENCING_TABLES: &[&str] = &["reactions", "msgs_mdns"];
+
+/// Conditions selecting the messages which can be moved to cold storage
+/// without affecting the chatlist, fresh message counters or pending jobs.
+///
+/// The last message of each chat is kept, so the chatlist summary does not change.
+/// Starred, fresh, ephemeral and partially downloaded messages, webxdc instances
+/// and messages referenced by pending jobs are kept as well.
+const MOVABLE_MSGS: &str = "id>? AND chat_id>? AND timestamp<?
+    AND starred=0 AND state!=? AND type!=? AND ephemeral_timestamp=0 AND download_state=0
+    AND timestamp<(SELECT MAX(last.timestamp) FROM msgs last
+                   WHERE last.chat_id=msgs.chat_id AND last.hidden=0)
+    AND id NOT IN (SELECT msg_id FROM smtp)
+    AND id NOT IN (SELECT msg_id FROM smtp_mdns)
+    AND id NOT IN (SELECT msg_id FROM msgs_status_updates)
+    AND id NOT IN (SELECT msg_id FROM pending_notifications)";
+
+/// Maximum number of messages returned by a search in all chats.
+const MAX_SEARCH_RESULTS: u32 = 1000;
+
+/// Message in cold storage, see [`Context::get_cold_stored_msgs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColdStoredMsg {
+    /// ID of the message, kept when it is restored.
+    pub id: MsgId,
+
+    /// ID of the chat the message belongs to.
+    pub chat_id: ChatId,
+
+    /// ID of the sender.
+    pub from_id: ContactId,
+
+    /// Time of the message as shown in the chat.
+    pub timestamp: i64,
+
+    /// Type of the message.
+    pub viewtype: Viewtype,
+
+    /// Text of the message.
+    pub text: String,
+}
+
+impl Context {
+    /// Moves messages older than `cutoff` to the cold storage database
+    /// and returns the number of moved messages.
+    ///
+    /// Messages which are still needed, e.g. the last message of each chat,
+    /// starred or fresh messages, stay in the main database.
+    /// Files of the moved messages are kept.
+    /// The main database does not shrink on disk until [`Context::run_db_maintenance`] runs.
+    pub async fn move_msgs_to_cold_storage(&self, cutoff: i64) -> Result<usize> {
+        let count = self
+            .sql
+            .call_write_with_cold_storage(move |conn| {
+                let transaction = conn.transaction()?;
+                create_cold_table(&transaction, "msgs", "id")?;
+                for table in REFERENCING_TABLES {
+                    create_cold_table(&transaction, table, "msg_id")?;
+                }
+                transaction.execute(
+                    "CREATE TEMP TABLE moved_msg_ids (id INTEGER PRIMARY KEY)",
+                    (),
+                )?;
+                transaction.execute(
+                    &format!(
+                        "INSERT INTO temp.moved_msg_ids
+                         SELECT id FROM main.msgs WHERE {MOVABLE_MSGS}"
+                    ),
+                    (
+                        DC_MSG_ID_LAST_SPECIAL,
+                        DC_CHAT_ID_LAST_SPECIAL,
+                        cutoff,
+                        MessageState::InFresh,
+                        Viewtype::Webxdc,
+                    ),
+                )?;
+                for table in REFERENCING_TABLES {
+                    move_rows(&transaction, "main", "cold", table, "msg_id")?;
+                }
+                let count = move_rows(&transaction, "main", "cold", "msgs", "id")?;
+                count_cold_blob_refs(&transaction, 1)?;
+                transaction.execute("DROP TABLE temp.moved_msg_ids", ())?;
+                transaction.commit()?;
+                Ok(count)
+            })
+            .await?;
+
+        info!(
+            self,
+            "Moved {count} messages older than {cutoff} to cold storage."
+        );
+        if count > 0 {
+            self.emit_msgs_changed_without_ids();
+        }
+        Ok(count)
+    }
+
+    /// Moves the messages of the chat from the cold storage database back to the main database
+    /// and returns the number of restored messages.
+    pub async fn restore_msgs_from_cold_storage(&self, chat_id: ChatId) -> Result<usize> {
+        if !self.sql.has_cold_storage() {
+            return Ok(0);
+        }
+        ensure!(
+            self.sql
+                .exists("SELECT COUNT(*) FROM chats WHERE id=?", (chat_id,))
+                .await?,
+            "chat {chat_id} does not exist"
+        );
+        let count = self
+            .sql
+            .call_write_with_cold_storage(move |conn| {
+                let transaction = conn.transaction()?;
+                create_cold_table(&transaction, "msgs", "id")?;
+                for table in REFERENCING_TABLES {
+                    create_cold_table(&transaction, table, "msg_id")?;
+                }
+                transaction.execute(
+                    "CREATE TEMP TABLE moved_msg_ids (id INTEGER PRIMARY KEY)",
+                    (),
+                )?;
+                transaction.execute(
+                    "INSERT INTO temp.moved_msg_ids SELECT id FROM cold.msgs WHERE chat_id=?",
+                    (chat_id,),
+                )?;
+                // Reactions of contacts deleted meanwhile cannot be restored.
+                transaction.execute(
+                    "DELETE FROM cold.reactions
+                     WHERE msg_id IN (SELECT id FROM temp.moved_msg_ids)
+                       AND contact_id NOT IN (SELECT id FROM main.contacts)",
+                    (),
+                )?;
+                count_cold_blob_refs(&transaction, -1)?;
+                let count = move_rows(&transaction, "cold", "main", "msgs", "id")?;
+                for table in REFERENCING_TABLES {
+                    move_rows(&transaction, "cold", "main", table, "msg_id")?;
+                }
+                transaction.execute("DROP TABLE temp.moved_msg_ids", ())?;
+                transaction.commit()?;
+                Ok(count)
+            })
+            .await?;
+
+        info!(
+            self,
+            "Restored {count} messages of {chat_id} from cold storage."
+        );
+        if count > 0 {
+            self.emit_msgs_changed(chat_id, MsgId::new(0));
+        }
+        Ok(count)
+    }
+
+    /// Returns the messages of the chat in cold storage, ordered as in the chat.
+    pub async fn get_cold_stored_msgs(&self, chat_id: ChatId) -> Result<Vec<ColdStoredMsg>> {
+        self.sql.query_cold_stored_msgs(Some(chat_id), None).await
+    }
+
+    /// Searches for messages in cold storage containing the query string,
+    /// in the given chat or in all chats.
+    ///
+    /// Results are ordered as in the chat,
+    /// at most 1000 messages are returned when searching all chats.
+    pub async fn search_cold_stored_msgs(
+        &self,
+        chat_id: Option<ChatId>,
+        query: &str,
+    ) -> Result<Vec<ColdStoredMsg>> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.sql
+            .query_cold_stored_msgs(chat_id, Some(format!("%{query}%")))
+            .await
+    }
+}
+
+impl Sql {
+    /// Returns the path of the cold storage database,
+    /// `None` if the storage has no database file.
+    fn cold_storage_path(&self) -> Option<PathBuf> {
+        let dbfile = self.storage.path()?;
+        Some(path_with_suffix(dbfile, "-cold"))
+    }
+
+    /// Returns true if messages were moved to cold storage.
+    pub(crate) fn has_cold_storage(&self) -> bool {
+        self.cold_storage_path().map_or(false, |path| path.exists())
+    }
+
+    /// Returns the path of the cold storage database as a string which can be passed to `ATTACH`.
+    fn cold_storage_path_str(&self) -> Result<String> {
+        let path = self
+            .cold_storage_path()
+            .context("databases without a database file have no cold storage")?;
+        let path = path
+            .to_str()
+            .with_context(|| format!("path {} is not valid unicode", path.display()))?
+            .to_string();
+        Ok(path)
+    }
+
+    /// Runs `function` on a connection with the cold storage database attached as `cold`,
+    /// assuming it only does read queries.
+    async fn call_with_cold_storage<'a, F, R>(&'a self, function: F) -> Result<R>
+    where
+        F: 'a + FnOnce(&mut Connection) -> Result<R> + Send,
+        R: Send + 'static,
+    {
+        let path = self.cold_storage_path_str()?;
+        self.call(move |conn| {
+            attach_cold_storage(conn, &path)?;
+            function(conn)
+        })
+        .await
+    }
+
+    /// Runs `function` on a connection with the cold storage database attached as `cold`,
+    /// assuming it does write queries.
+    ///
+    /// The cold storage database is created if it does not exist.
+    async fn call_write_with_cold_storage<'a, F, R>(&'a self, function: F) -> Result<R>
+    where
+        F: 'a + FnOnce(&mut Connection) -> Result<R> + Send,
+        R: Send + 'static,
+    {
+        let path = self.cold_storage_path_str()?;
+        self.call_write(move |conn| {
+            attach_cold_storage(conn, &path)?;
+            function(conn)
+        })
+        .await
+    }
+
+    /// Returns the parameters of the messages in cold storage,
+    /// so housekeeping and media cleanup keep their files.
+    pub(crate) async fn get_cold_stored_params(&self) -> Result<Vec<String>> {
+        if !self.has_cold_storage() {
+            return Ok(Vec::new());
+        }
+        self.call_with_cold_storage(|conn| {
+            if !cold_table_exists(conn, "msgs")? {
+                return Ok(Vec::new());
+            }
+            let params = conn
+                .prepare("SELECT param FROM cold.msgs")?
+                .query_map((), |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(params)
+        })
+        .await
+    }
+
+    /// Returns true if a message with the Message-ID `rfc724_mid` is in cold storage,
+    /// so it is not received again, see [`rfc724_mid_exists`](crate::message::rfc724_mid_exists).
+    pub(crate) async fn cold_stored_rfc724_mid_exists(&self, rfc724_mid: &str) -> Result<bool> {
+        if !self.has_cold_storage() {
+            return Ok(false);
+        }
+        let rfc724_mid = rfc724_mid
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .to_string();
+        self.call_with_cold_storage(move |conn| {
+            if !cold_table_exists(conn, "msgs")? {
+                return Ok(false);
+            }
+            let exists = conn.query_row(
+                "SELECT COUNT(*) > 0 FROM cold.msgs WHERE rfc724_mid=?",
+                (rfc724_mid,),
+                |row| row.get(0),
+            )?;
+            Ok(exists)
+        })
+        .await
+    }
+
+    /// Returns true if messages from or to the contact are in cold storage,
+    /// so the contact is kept when it is deleted, see [`Contact::delete`](crate::contact::Contact::delete).
+    pub(crate) async fn has_cold_stored_msgs_of_contact(
+        &self,
+        contact_id: ContactId,
+    ) -> Result<bool> {
+        if !self.has_cold_storage() {
+            return Ok(false);
+        }
+        self.call_with_cold_storage(move |conn| {
+            if !cold_table_exists(conn, "msgs")? {
+                return Ok(false);
+            }
+            let exists = conn.query_row(
+                "SELECT COUNT(*) > 0 FROM cold.msgs WHERE from_id=?1 OR to_id=?1",
+                (contact_id,),
+                |row| row.get(0),
+            )?;
+            Ok(exists)
+        })
+        .await
+    }
+
+    /// Deletes the messages of the chat from cold storage, called when the chat is deleted.
+    ///
+    /// Returns the number of deleted messages.
+    pub(crate) async fn delete_cold_stored_chat_msgs(&self, chat_id: ChatId) -> Result<usize> {
+        self.delete_cold_stored_msgs("chat_id=?", chat_id.to_u32())
+            .await
+    }
+
+    /// Deletes the device messages from cold storage,
+    /// called when all device messages are deleted.
+    ///
+    /// Returns the number of deleted messages.
+    pub(crate) async fn delete_cold_stored_device_msgs(&self) -> Result<usize> {
+        self.delete_cold_stored_msgs("from_id=?", ContactId::DEVICE.to_u32())
+            .await
+    }
+
+    /// Deletes the messages matching `condition` with the parameter `id` from cold storage
+    /// together with the rows referencing them.
+    ///
+    /// References to deduplicated blobs are removed from the `blobs` table,
+    /// the blobs are removed by [`remove_unreferenced_blobs`](crate::blob::remove_unreferenced_blobs).
+    async fn delete_cold_stored_msgs(&self, condition: &'static str, id: u32) -> Result<usize> {
+        if !self.has_cold_storage() {
+            return Ok(0);
+        }
+        self.call_write_with_cold_storage(move |conn| {
+            if !cold_table_exists(conn, "msgs")? {
+                return Ok(0);
+            }
+            let transaction = conn.transaction()?;
+            transaction.execute(
+                "CREATE TEMP TABLE moved_msg_ids (id INTEGER PRIMARY KEY)",
+                (),
+            )?;
+            transaction.execute(
+                &format!("INSERT INTO temp.moved_msg_ids SELECT id FROM cold.msgs WHERE {condition}"),
+                (id,),
+            )?;
+            count_cold_blob_refs(&transaction, -1)?;
+            for table in REFERENCING_TABLES {
+                if cold_table_exists(&transaction, table)? {
+                    transaction.execute(
+                        &format!(
+                            "DELETE FROM cold.{table} WHERE msg_id IN (SELECT id FROM temp.moved_msg_ids)"
+                        ),
+                        (),
+                    )?;
+                }
+            }
+            let count = transaction.execute(
+                "DELETE FROM cold.msgs WHERE id IN (SELECT id FROM temp.moved_msg_ids)",
+                (),
+            )?;
+            transaction.execute("DROP TABLE temp.moved_msg_ids", ())?;
+            transaction.commit()?;
+            Ok(count)
+        })
+        .await
+    }
+
+    async fn query_cold_stored_msgs(
+        &self,
+        chat_id: Option<ChatId>,
+        pattern: Option<String>,
+    ) -> Result<Vec<ColdStoredMsg>> {
+        if !self.has_cold_storage() {
+            return Ok(Vec::new());
+        }
+        let limit = if chat_id.is_none() {
+            MAX_SEARCH_RESULTS
+        } else {
+            u32::MAX
+        };
+        self.call_with_cold_storage(move |conn| {
+            if !cold_table_exists(conn, "msgs")? {
+                return Ok(Vec::new());
+            }
+            let msgs = conn
+                .prepare(
+                    "SELECT id, chat_id, from_id, timestamp, type, txt FROM cold.msgs
+                     WHERE (?1 IS NULL OR chat_id=?1)
+                       AND (?2 IS NULL OR txt LIKE ?2)
+                       AND chat_id IN (SELECT id FROM main.chats)
+                     ORDER BY timestamp, id
+                     LIMIT ?3",
+                )?
+                .query_map((chat_id, pattern, limit), |row| {
+                    Ok(ColdStoredMsg {
+                        id: row.get(0)?,
+                        chat_id: row.get(1)?,
+                        from_id: row.get(2)?,
+                        timestamp: row.get(3)?,
+                        viewtype: row.get(4)?,
+                        text: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(msgs)
+        })
+        .await
+    }
+}
+
+/// Returns true if the table exists in cold storage.
+/// Attaches the cold storage database at `path` as `cold` unless it is attached already.
+///
+/// The database is never detached, so looking up received messages in cold storage
+/// does not attach and detach it each time.
+fn attach_cold_storage(conn: &Connection, path: &str) -> Result<()> {
+    let attached: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_database_list WHERE name='cold'",
+        (),
+        |row| row.get(0),
+    )?;
+    if !attached {
+        // Without a key, the attached database uses the key of the main database.
+        conn.execute("ATTACH DATABASE ? AS cold", (path,))
+            .context("failed to attach cold storage database")?;
+    }
+    Ok(())
+}
+
+fn cold_table_exists(conn: &Connection, table: &str) -> Result<bool> {
+    let exists = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM cold.sqlite_master WHERE type='table' AND name=?",
+        (table,),
+        |row| row.get(0),
+    )?;
+    Ok(exists)
+}
+
+/// Returns the names of the columns of the table.
+fn get_columns(conn: &Connection, schema: &str, table: &str) -> Result<BTreeSet<String>> {
+    let columns = conn
+        .prepare("SELECT name FROM pragma_table_info(?, ?)")?
+        .query_map((table, schema), |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<BTreeSet<_>>>()?;
+    Ok(columns)
+}
+
+/// Creates the table in cold storage with the columns of the table in the main database.
+///
+/// Columns added to the main database by migrations are added to existing tables.
+fn create_cold_table(transaction: &Transaction, table: &str, id_column: &str) -> Result<()> {
+    transaction.execute(
+        &format!("CREATE TABLE IF NOT EXISTS cold.{table} AS SELECT * FROM main.{table} WHERE 0"),
+        (),
+    )?;
+    transaction.execute(
+        &format!("CREATE INDEX IF NOT EXISTS cold.{table}_index1 ON {table} ({id_column})"),
+        (),
+    )?;
+    if table == "msgs" {
+        transaction.execute(
+            "CREATE INDEX IF NOT EXISTS cold.msgs_index2 ON msgs (chat_id, timestamp)",
+            (),
+        )?;
+        transaction.execute(
+            "CREATE INDEX IF NOT EXISTS cold.msgs_index3 ON msgs (rfc724_mid)",
+            (),
+        )?;
+    }
+
+    let cold_columns = get_columns(transaction, "cold", table)?;
+    for column in get_columns(transaction, "main", table)?.difference(&cold_columns) {
+        transaction.execute(
+            &format!("ALTER TABLE cold.{table} ADD COLUMN \"{column}\""),
+            (),
+        )?;
+    }
+    Ok(())
+}
+
+/// Moves the rows of the table referencing the messages in `temp.moved_msg_ids`
+/// from the `from` database to the `to` database.
+///
+/// Returns the number of moved rows.
+fn move_rows(
+    transaction: &Transaction,
+    from: &str,
+    to: &str,
+    table: &str,
+    id_column: &str,
+) -> Result<usize> {
+    // Columns may have been removed from the main database by migrations.
+    let columns = get_columns(transaction, from, table)?
+        .intersection(&get_columns(transaction, to, table)?)
+        .map(|column| format!("\"{column}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let condition = format!("{id_column} IN (SELECT id FROM temp.moved_msg_ids)");
+    transaction.execute(
+        &format!(
+            "INSERT INTO {to}.{table} ({columns})
+             SELECT {columns} FROM {from}.{table} WHERE {condition}"
+        ),
+        (),
+    )?;
+    let count =
+        transaction.execute(&format!("DELETE FROM {from}.{table} WHERE {condition}"), ())?;
+    Ok(count)
+}
+
+/// Adds `sign` times the number of messages in `temp.moved_msg_ids` stored in cold storage
+/// to the reference counts of their deduplicated blobs.
+///
+/// The triggers maintaining the `blobs` table only see the main database,
+/// so messages in cold storage are counted here to keep their blobs.
+fn count_cold_blob_refs(transaction: &Transaction, sign: i64) -> Result<()> {
+    let blob = deduplicated_blob_sql("param");
+    transaction.execute(
+        &format!(
+            "INSERT INTO main.blobs (name, refcount)
+             SELECT name, ? * COUNT(*) FROM
+               (SELECT {blob} AS name FROM cold.msgs
+                WHERE id IN (SELECT id FROM temp.moved_msg_ids))
+             WHERE name IS NOT NULL GROUP BY name
+             ON CONFLICT (name) DO UPDATE SET refcount=refcount+excluded.refcount"
+        ),
+        (sign,),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat;
+    use crate::contact::Contact;
+    use crate::message::{star_msgs, Message};
+    use crate::param::Param;
+    use crate::reaction::send_reaction;
+    use crate::receive_imf::receive_imf;
+    use crate::test_utils::TestContext;
+    use crate::tools::time;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_cold_storage() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let old_msg_id = chat::send_text_msg(&t, chat.id, "Old news".to_string()).await?;
+        let starred_msg_id = chat::send_text_msg(&t, chat.id, "Starred".to_string()).await?;
+        let last_msg_id = chat::send_text_msg(&t, chat.id, "Latest news".to_string()).await?;
+        t.sql
+            .execute(
+                "UPDATE msgs SET timestamp=? WHERE id=?",
+                (time() - 2000, old_msg_id),
+            )
+            .await?;
+        t.sql
+            .execute(
+                "UPDATE msgs SET timestamp=? WHERE id=?",
+                (time() - 1000, starred_msg_id),
+            )
+            .await?;
+        t.sql.execute("DELETE FROM smtp", ()).await?;
+        send_reaction(&t, old_msg_id, "👍").await?;
+        t.sql.execute("DELETE FROM smtp", ()).await?;
+        star_msgs(&t, &[starred_msg_id], true).await?;
+        assert!(!t.sql.has_cold_storage());
+        assert!(t.get_cold_stored_msgs(chat.id).await?.is_empty());
+
+        // The last message of the chat and starred messages are kept.
+        assert_eq!(t.move_msgs_to_cold_storage(time() + 10).await?, 1);
+        assert!(t.sql.has_cold_storage());
+        assert!(Message::load_from_db(&t, old_msg_id).await.is_err());
+        assert!(Message::load_from_db(&t, starred_msg_id).await.is_ok());
+        assert!(Message::load_from_db(&t, last_msg_id).await.is_ok());
+        assert_eq!(
+            t.sql
+                .count(
+                    "SELECT COUNT(*) FROM reactions WHERE msg_id=?",
+                    (old_msg_id,)
+                )
+                .await?,
+            0
+        );
+        assert_eq!(t.search_msgs(Some(chat.id), "news").await?.len(), 1);
+
+        let cold_msgs = t.get_cold_stored_msgs(chat.id).await?;
+        assert_eq!(cold_msgs.len(), 1);
+        assert_eq!(cold_msgs[0].id, old_msg_id);
+        assert_eq!(cold_msgs[0].text, "Old news");
+        assert_eq!(cold_msgs[0].from_id, ContactId::SELF);
+        assert_eq!(t.search_cold_stored_msgs(None, "NEWS").await?, cold_msgs);
+        assert!(t.search_cold_stored_msgs(None, "Latest").await?.is_empty());
+
+        // Nothing else can be moved.
+        assert_eq!(t.move_msgs_to_cold_storage(time() + 10).await?, 0);
+
+        assert_eq!(t.restore_msgs_from_cold_storage(chat.id).await?, 1);
+        let msg = Message::load_from_db(&t, old_msg_id).await?;
+        assert_eq!(msg.get_text().as_deref(), Some("Old news"));
+        assert_eq!(
+            t.sql
+                .count(
+                    "SELECT COUNT(*) FROM reactions WHERE msg_id=?",
+                    (old_msg_id,)
+                )
+                .await?,
+            1
+        );
+        assert_eq!(t.search_msgs(Some(chat.id), "news").await?.len(), 2);
+        assert!(t.get_cold_stored_msgs(chat.id).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_cold_storage_delete() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let bob_id = chat::get_chat_contacts(&t, chat.id).await?[0];
+        let file = t.get_blobdir().join("report.pdf");
+        tokio::fs::write(&file, b"%PDF- content").await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), None);
+        let old_msg_id = chat::send_msg(&t, chat.id, &mut msg).await?;
+        chat::send_text_msg(&t, chat.id, "Latest news".to_string()).await?;
+        t.sql
+            .execute(
+                "UPDATE msgs SET timestamp=? WHERE id=?",
+                (time() - 1000, old_msg_id),
+            )
+            .await?;
+        t.sql.execute("DELETE FROM smtp", ()).await?;
+        let old_msg = Message::load_from_db(&t, old_msg_id).await?;
+        let blob = old_msg
+            .param
+            .get_blob(Param::File, &t, false)
+            .await?
+            .unwrap();
+        let get_refcount = || {
+            t.sql.query_get_value::<i64>(
+                "SELECT refcount FROM blobs WHERE name=?",
+                (blob.as_file_name().to_string(),),
+            )
+        };
+
+        assert_eq!(t.move_msgs_to_cold_storage(time() - 10).await?, 1);
+        assert_eq!(get_refcount().await?, Some(1));
+
+        // Messages in cold storage are not received again.
+        let rfc724_mid = old_msg.rfc724_mid.clone();
+        assert!(t.sql.cold_stored_rfc724_mid_exists(&rfc724_mid).await?);
+        assert!(
+            t.sql
+                .cold_stored_rfc724_mid_exists(&format!("<{rfc724_mid}>"))
+                .await?
+        );
+        let imf_raw = format!(
+            "From: alice@example.org\n\
+             To: bob@example.net\n\
+             Message-ID: <{rfc724_mid}>\n\
+             Date: Sun, 22 Mar 2020 22:37:57 +0000\n\
+             \n\
+             Resent\n"
+        );
+        assert!(receive_imf(&t, imf_raw.as_bytes(), false).await?.is_none());
+
+        // The contact is kept as messages in cold storage may be restored.
+        t.sql
+            .execute("DELETE FROM chats_contacts WHERE chat_id=?", (chat.id,))
+            .await?;
+        Contact::delete(&t, bob_id).await?;
+        assert!(Contact::get_by_id(&t, bob_id).await.is_ok());
+
+        chat.id.delete(&t).await?;
+        assert!(!t.sql.cold_stored_rfc724_mid_exists(&rfc724_mid).await?);
+        assert_eq!(get_refcount().await?, Some(0));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_attach_cold_storage() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let path = t.sql.cold_storage_path_str()?;
+        let attached = t
+            .sql
+            .call(move |conn| {
+                // Attaching again does not fail as the database stays attached.
+                attach_cold_storage(conn, &path)?;
+                attach_cold_storage(conn, &path)?;
+                let attached: i64 = conn.query_row(
+                    "SELECT COUNT(*) FROM pragma_database_list WHERE name='cold'",
+                    (),
+                    |row| row.get(0),
+                )?;
+                Ok(attached)
+            })
+            .await?;
+        assert_eq!(attached, 1);
+
+        // Lookups work on connections which have the database attached already.
+        for _ in 0..10 {
+            assert!(
+                !t.sql
+                    .cold_stored_rfc724_mid_exists("abc@example.org")
+                    .await?
+            );
+            assert_eq!(t.move_msgs_to_cold_storage(0).await?, 0);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+//! # Sanitized database export.
+//!
+//! [`Context::export_sanitized_database`] writes a copy of the database
+//! which users can attach to bug reports about performance or synchronization problems
+//! without disclosing their messages, keys or contacts.
+//!
+//! The copy keeps the schema, all rows, numbers such as IDs, states, sizes and timestamps,
+//! and the length of every value. Texts are replaced with pseudonyms,
+//! binary data such as keys and raw messages with zero bytes
+//! and floating point numbers such as locations with zero.
+//! Pseudonyms are derived from a random salt chosen for each export,
+//! so equal texts, e.g. addresses or Message-IDs referenced from several tables,
+//! get equal pseudonyms within one export, but cannot be looked up.
+
+use std::path::Path;
+
+use anyhow::{ensure, Context as _, Result};
+use rusqlite::{Connection, Transaction};
+use sha2::{Digest, Sha256};
+
+use crate::context::Context;
+
+/// Prefixes of tables which are not sanitized column by column.
+///
+/// SQLite's internal tables contain no user data.
+/// The full-text index is rebuilt from the sanitized message texts instead.
+const SKIPPED_TABLE_PREFIXES: &[&str] = &["sqlite_", "msgs_search"];
+
+impl Context {
+    /// Exports the database to the new file `dest` with message texts, keys, addresses
+    /// and all other texts and binary data replaced, see the [module documentation](self).
+    ///
+    /// The exported database is not encrypted,
+    /// it is meant to be attached to bug reports about performance or synchronization problems.
+    pub async fn export_sanitized_database(&self, dest: &Path) -> Result<()> {
+        ensure!(!dest.exists(), "{} exists already", dest.display());
+        let dest_str = dest
+            .to_str()
+            .with_context(|| format!("path {} is not valid unicode", dest.display()))?
+            .to_string();
+        self.sql
+            .call_write(move |conn| {
+                conn.execute("ATTACH DATABASE ? AS diagnostic KEY ''", (dest_str,))
+                    .context("failed to attach diagnostic database")?;
+                let res = conn
+                    .query_row("SELECT sqlcipher_export('diagnostic')", [], |_row| Ok(()))
+                    .context("failed to export to attached diagnostic database");
+                conn.execute("DETACH DATABASE diagnostic", [])
+                    .context("failed to detach diagnostic database")?;
+                res?;
+                Ok(())
+            })
+            .await?;
+
+        let salt: [u8; 32] = rand::random();
+        let path = dest.to_path_buf();
+        let res = tokio::task::spawn_blocking(move || sanitize_database(&path, &salt))
+            .await
+            .context("sanitizing task failed")
+            .and_then(|res| res);
+        if let Err(err) = res {
+            // Never leave a partially sanitized database behind.
+            tokio::fs::remove_file(dest).await.ok();
+            return Err(err.context("failed to sanitize the exported database"));
+        }
+        info!(self, "Exported sanitized database to {}.", dest.display());
+        Ok(())
+    }
+}
+
+/// Sanitizes all tables of the database at `path`
+/// and removes the replaced data from the file.
+fn sanitize_database(path: &Path, salt: &[u8]) -> Result<()> {
+    let mut conn = Connection::open(path)?;
+    let tables = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type='table'")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let transaction = conn.transaction()?;
+    for table in tables
+        .iter()
+        .filter(|table| !SKIPPED_TABLE_PREFIXES.iter().any(|p| table.starts_with(p)))
+    {
+        let columns = transaction
+            .prepare("SELECT name FROM pragma_table_info(?)")?
+            .query_map((table,), |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for column in &columns {
+            sanitize_column(&transaction, table, column, salt)
+                .with_context(|| format!("failed to sanitize {table}.{column}"))?;
+        }
+    }
+    if tables.iter().any(|table| table == "msgs_search") {
+        transaction.execute(
+            "INSERT INTO msgs_search (msgs_search) VALUES ('rebuild')",
+            (),
+        )?;
+    }
+    transaction.commit()?;
+
+    // Replaced data may remain in free pages otherwise.
+    conn.execute("VACUUM", ())?;
+    Ok(())
+}
+
+fn sanitize_column(
+    transaction: &Transaction,
+    table: &str,
+    column: &str,
+    salt: &[u8],
+) -> Result<()> {
+    // Config keys are needed to make sense of the values.
+    if table == "config" && column == "keyname" {
+        return Ok(());
+    }
+
+    transaction.execute(
+        &format!(
+            "UPDATE \"{table}\" SET \"{column}\"=zeroblob(length(\"{column}\"))
+             WHERE typeof(\"{column}\")='blob'"
+        ),
+        (),
+    )?;
+    transaction.execute(
+        &format!(
+            "UPDATE \"{table}\" SET \"{column}\"=0.0
+             WHERE typeof(\"{column}\")='real'"
+        ),
+        (),
+    )?;
+    if table == "config" && column == "value" {
+        sanitize_config_values(transaction, salt)
+    } else {
+        sanitize_texts(transaction, table, column, salt)
+    }
+}
+
+/// Replaces all texts in the column with pseudonyms.
+fn sanitize_texts(transaction: &Transaction, table: &str, column: &str, salt: &[u8]) -> Result<()> {
+    let rows = transaction
+        .prepare(&format!(
+            "SELECT rowid, CAST(\"{column}\" AS BLOB) FROM \"{table}\"
+             WHERE typeof(\"{column}\")='text' AND \"{column}\"!=''"
+        ))?
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut stmt = transaction.prepare(&format!(
+        "UPDATE \"{table}\" SET \"{column}\"=? WHERE rowid=?"
+    ))?;
+    for (rowid, value) in rows {
+        stmt.execute((pseudonym(salt, &value), rowid))?;
+    }
+    Ok(())
+}
+
+/// Replaces the values of the `config` table with pseudonyms,
+/// except for numbers such as versions, flags and timestamps.
+/// Passwords are always replaced.
+fn sanitize_config_values(transaction: &Transaction, salt: &[u8]) -> Result<()> {
+    let rows = transaction
+        .prepare(
+            "SELECT id, keyname, value FROM config
+             WHERE typeof(value)='text' AND value!=''",
+        )?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut stmt = transaction.prepare("UPDATE config SET value=? WHERE id=?")?;
+    for (id, keyname, value) in rows {
+        if value.parse::<i64>().is_ok() && !keyname.contains("pw") {
+            continue;
+        }
+        stmt.execute((pseudonym(salt, value.as_bytes()), id))?;
+    }
+    Ok(())
+}
+
+/// Returns a pseudonym of `value` with the same length in bytes.
+///
+/// Values are taken as bytes because texts in the database are not always valid UTF-8.
+/// Lowercase letters are used, so pseudonyms are not converted to numbers
+/// in columns with numeric affinity.
+fn pseudonym(salt: &[u8], value: &[u8]) -> String {
+    let len = value.len();
+    let mut res = String::with_capacity(len);
+    let mut block = Sha256::new()
+        .chain_update(salt)
+        .chain_update(value)
+        .finalize();
+    while res.len() < len {
+        res.extend(
+            block
+                .iter()
+                .flat_map(|byte| [byte >> 4, byte & 0x0f])
+                .map(|nibble| char::from(b'a' + nibble))
+                .take(len - res.len()),
+        );
+        block = Sha256::digest(block);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat;
+    use crate::test_utils::TestContext;
+
+    #[test]
+    fn test_pseudonym() {
+        let salt = b"salt";
+        assert_eq!(pseudonym(salt, b""), "");
+        let addr = pseudonym(salt, b"bob@example.net");
+        assert_eq!(addr.len(), "bob@example.net".len());
+        assert!(addr.chars().all(|c| c.is_ascii_lowercase()));
+        assert_eq!(addr, pseudonym(salt, b"bob@example.net"));
+        assert_ne!(addr, pseudonym(b"pepper", b"bob@example.net"));
+        assert_ne!(addr, pseudonym(salt, b"bob@example.org"));
+        assert_eq!(pseudonym(salt, "Grüße".as_bytes()).len(), "Grüße".len());
+        assert_eq!(pseudonym(salt, "x".repeat(1000).as_bytes()).len(), 1000);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_export_sanitized_database() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let text = "Please sanitize this secret message";
+        let msg_id = chat::send_text_msg(&t, chat.id, text.to_string()).await?;
+
+        let dir = tempfile::tempdir()?;
+        let dest = dir.path().join("diagnostic.sqlite");
+        t.export_sanitized_database(&dest).await?;
+        assert!(t.export_sanitized_database(&dest).await.is_err());
+
+        let data = tokio::fs::read(&dest).await?;
+        for needle in [text, "secret", "bob@example.net", "alice@example.org"] {
+            assert!(
+                !data
+                    .windows(needle.len())
+                    .any(|window| window == needle.as_bytes()),
+                "{needle} found in the sanitized database"
+            );
+        }
+
+        let conn = Connection::open(&dest)?;
+        let (txt, bytes): (String, i64) = conn.query_row(
+            "SELECT txt, LENGTH(txt) FROM msgs WHERE id=?",
+            (msg_id,),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        assert_ne!(txt, text);
+        assert_eq!(bytes, text.len() as i64);
+        let msg_count: i64 = conn.query_row("SELECT COUNT(*) FROM msgs", [], |row| row.get(0))?;
+        assert_eq!(
+            usize::try_from(msg_count)?,
+            t.sql.count("SELECT COUNT(*) FROM msgs", ()).await?
+        );
+        let dbversion: String = conn.query_row(
+            "SELECT value FROM config WHERE keyname='dbversion'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(
+            dbversion.parse::<i32>().ok(),
+            t.sql.get_raw_config_int("dbversion").await?
+        );
+        let private_key: Vec<u8> =
+            conn.query_row("SELECT private_key FROM keypairs", [], |row| row.get(0))?;
+        assert!(!private_key.is_empty());
+        assert!(private_key.iter().all(|byte| *byte == 0));
+        Ok(())
+    }
+}
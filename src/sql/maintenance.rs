@@ -0,0 +1,313 @@
+//! # Database maintenance.
+//!
+//! Housekeeping keeps the database in a usable state automatically,
+//! but avoids expensive operations such as `VACUUM` rewriting the whole database.
+//! [`Context::run_db_maintenance`] runs them explicitly,
+//! e.g. when the user chooses to optimize the storage.
+//!
+//! [`Context::check_database`] checks the database for corruption,
+//! e.g. after crashes or on bad storage, and optionally repairs what can be repaired.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::constants::{DC_CHAT_ID_LAST_SPECIAL, DC_CHAT_ID_TRASH};
+use crate::context::Context;
+use crate::events::EventType;
+use crate::message::MsgId;
+use crate::param::{Param, Params};
+use crate::tools::time;
+
+/// Maximum number of errors reported by `PRAGMA integrity_check`.
+const MAX_INTEGRITY_ERRORS: u32 = 100;
+
+/// Tables with rows referencing rows which may not exist anymore,
+/// and the conditions selecting such orphaned rows.
+const ORPHANED_ROWS: &[(&str, &str)] = &[
+    (
+        "chats_contacts",
+        "chat_id NOT IN (SELECT id FROM chats) OR contact_id NOT IN (SELECT id FROM contacts)",
+    ),
+    ("smtp", "msg_id NOT IN (SELECT id FROM msgs)"),
+    ("smtp_status_updates", "msg_id NOT IN (SELECT id FROM msgs)"),
+    ("msgs_status_updates", "msg_id NOT IN (SELECT id FROM msgs)"),
+    (
+        "reactions",
+        "msg_id NOT IN (SELECT id FROM msgs) OR contact_id NOT IN (SELECT id FROM contacts)",
+    ),
+];
+
+/// Result of [`Context::check_database`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DbCheckReport {
+    /// Problems reported by `PRAGMA integrity_check`, empty if there are none.
+    pub integrity_errors: Vec<String>,
+
+    /// Number of orphaned rows referencing chats, contacts or messages which do not exist,
+    /// by table.
+    /// Messages in chats which do not exist are counted for the `msgs` table.
+    pub orphaned_rows: BTreeMap<String, usize>,
+
+    /// Messages whose file does not exist in the blob directory.
+    pub missing_files: Vec<MsgId>,
+
+    /// Whether the database was repaired.
+    pub repaired: bool,
+}
+
+impl DbCheckReport {
+    /// Returns true if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.integrity_errors.is_empty()
+            && self.orphaned_rows.is_empty()
+            && self.missing_files.is_empty()
+    }
+}
+
+impl Context {
+    /// Optimizes the database and returns unused space to the file system.
+    ///
+    /// The full-text search index is optimized, statistics used by the query planner are updated,
+    /// the database is rebuilt with `VACUUM` and the write-ahead log is truncated.
+    /// This may take a while for large databases, other database users are blocked meanwhile.
+    ///
+    /// Progress is reported with [`EventType::DbMaintenanceProgress`] events.
+    /// On success, the time is stored in [`Config::LastDbMaintenance`].
+    /// Only one maintenance, import or export can run at the same time.
+    pub async fn run_db_maintenance(&self) -> Result<()> {
+        self.alloc_ongoing().await?;
+        let res = run_db_maintenance_inner(self).await;
+        self.free_ongoing().await;
+
+        if let Err(err) = res.as_ref() {
+            error!(self, "Database maintenance failed: {err:#}.");
+            self.emit_event(EventType::DbMaintenanceProgress(0));
+        } else {
+            info!(self, "Database maintenance completed.");
+            self.emit_event(EventType::DbMaintenanceProgress(1000));
+        }
+        res
+    }
+
+    /// Checks the database for corruption and inconsistencies.
+    ///
+    /// Runs `PRAGMA integrity_check`, looks for orphaned rows referencing chats, contacts or messages
+    /// which do not exist and for messages whose file is missing in the blob directory.
+    ///
+    /// If `repair` is set, the indices are rebuilt with `REINDEX`,
+    /// orphaned rows are deleted and messages in chats which do not exist are moved to the trash.
+    /// This is best effort: the report describes the problems found before repairing,
+    /// integrity errors in tables cannot be repaired this way
+    /// and missing files cannot be restored.
+    /// Check again to find out whether problems remain.
+    pub async fn check_database(&self, repair: bool) -> Result<DbCheckReport> {
+        let mut report = DbCheckReport {
+            integrity_errors: self
+                .sql
+                .query_map(
+                    &format!("PRAGMA integrity_check({MAX_INTEGRITY_ERRORS})"),
+                    (),
+                    |row| row.get::<_, String>(0),
+                    |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+                )
+                .await?
+                .into_iter()
+                .filter(|line| line != "ok")
+                .collect(),
+            ..Default::default()
+        };
+
+        let orphaned_msgs = "chat_id>? AND chat_id NOT IN (SELECT id FROM chats)";
+        let count = self
+            .sql
+            .count(
+                &format!("SELECT COUNT(*) FROM msgs WHERE {orphaned_msgs}"),
+                (DC_CHAT_ID_LAST_SPECIAL,),
+            )
+            .await?;
+        if count > 0 {
+            report.orphaned_rows.insert("msgs".to_string(), count);
+        }
+        for (table, condition) in ORPHANED_ROWS {
+            let count = self
+                .sql
+                .count(
+                    &format!("SELECT COUNT(*) FROM {table} WHERE {condition}"),
+                    (),
+                )
+                .await?;
+            if count > 0 {
+                report.orphaned_rows.insert(table.to_string(), count);
+            }
+        }
+
+        let files = self
+            .sql
+            .query_map(
+                "SELECT id, param FROM msgs WHERE chat_id!=? AND param LIKE '%f=%'",
+                (DC_CHAT_ID_TRASH,),
+                |row| {
+                    let id: MsgId = row.get(0)?;
+                    let param: String = row.get(1)?;
+                    Ok((id, param))
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        for (msg_id, param) in files {
+            let param: Params = param.parse().unwrap_or_default();
+            if let Ok(Some(path)) = param.get_path(Param::File, self) {
+                if !path.exists() {
+                    report.missing_files.push(msg_id);
+                }
+            }
+        }
+
+        if repair {
+            self.sql
+                .transaction(move |transaction| {
+                    transaction.execute("REINDEX", ())?;
+                    transaction.execute(
+                        &format!("UPDATE msgs SET chat_id=? WHERE {orphaned_msgs}"),
+                        (DC_CHAT_ID_TRASH, DC_CHAT_ID_LAST_SPECIAL),
+                    )?;
+                    for (table, condition) in ORPHANED_ROWS {
+                        transaction
+                            .execute(&format!("DELETE FROM {table} WHERE {condition}"), ())?;
+                    }
+                    Ok(())
+                })
+                .await?;
+            report.repaired = true;
+        }
+
+        if report.is_ok() {
+            info!(self, "Database check found no problems.");
+        } else {
+            warn!(self, "Database check found problems: {report:?}.");
+        }
+        Ok(report)
+    }
+
+    /// Returns the time of the last successful [`Context::run_db_maintenance`],
+    /// `None` if it never ran.
+    pub async fn get_last_db_maintenance(&self) -> Result<Option<i64>> {
+        let timestamp = self.get_config_i64(Config::LastDbMaintenance).await?;
+        Ok(Some(timestamp).filter(|timestamp| *timestamp > 0))
+    }
+}
+
+async fn run_db_maintenance_inner(context: &Context) -> Result<()> {
+    context.emit_event(EventType::DbMaintenanceProgress(10));
+
+    context
+        .sql
+        .execute(
+            "INSERT INTO msgs_search (msgs_search) VALUES ('optimize')",
+            (),
+        )
+        .await?;
+    context.emit_event(EventType::DbMaintenanceProgress(250));
+
+    context.sql.execute("ANALYZE", ()).await?;
+    context.emit_event(EventType::DbMaintenanceProgress(500));
+
+    context
+        .sql
+        .call_write(|conn| {
+            conn.execute("VACUUM", ())?;
+            Ok(())
+        })
+        .await?;
+    context.emit_event(EventType::DbMaintenanceProgress(750));
+
+    // VACUUM writes the whole database to the write-ahead log.
+    context
+        .sql
+        .call_write(|conn| {
+            conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", (), |_row| Ok(()))?;
+            Ok(())
+        })
+        .await?;
+
+    context
+        .set_config(Config::LastDbMaintenance, Some(&time().to_string()))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat;
+    use crate::message::{Message, Viewtype};
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_run_db_maintenance() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert_eq!(t.get_last_db_maintenance().await?, None);
+
+        t.run_db_maintenance().await?;
+        t.evtracker
+            .get_matching(|evt| matches!(evt, EventType::DbMaintenanceProgress(500)))
+            .await;
+        t.evtracker
+            .get_matching(|evt| matches!(evt, EventType::DbMaintenanceProgress(1000)))
+            .await;
+        let timestamp = t.get_last_db_maintenance().await?.unwrap();
+        assert!(timestamp <= time());
+
+        // Maintenance cannot run during another ongoing process.
+        let _receiver = t.alloc_ongoing().await?;
+        assert!(t.run_db_maintenance().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_check_database() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let report = t.check_database(false).await?;
+        assert!(report.is_ok(), "{report:?}");
+
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let file = t.get_blobdir().join("foo.txt");
+        tokio::fs::write(&file, b"hello").await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), None);
+        let file_msg_id = chat::send_msg(&t, chat.id, &mut msg).await?;
+        tokio::fs::remove_file(&file).await?;
+
+        let orphan_msg_id = chat::send_text_msg(&t, chat.id, "Hi".to_string()).await?;
+        t.sql
+            .execute("UPDATE msgs SET chat_id=1000 WHERE id=?", (orphan_msg_id,))
+            .await?;
+        t.sql
+            .execute(
+                "INSERT INTO chats_contacts (chat_id, contact_id) VALUES (1000, 1)",
+                (),
+            )
+            .await?;
+
+        let report = t.check_database(false).await?;
+        assert!(report.integrity_errors.is_empty());
+        assert_eq!(report.orphaned_rows.get("msgs"), Some(&1));
+        assert_eq!(report.orphaned_rows.get("chats_contacts"), Some(&1));
+        assert_eq!(report.missing_files, vec![file_msg_id]);
+        assert!(!report.repaired);
+
+        let report = t.check_database(true).await?;
+        assert_eq!(report.orphaned_rows.len(), 2);
+        assert!(report.repaired);
+        let msg = Message::load_from_db(&t, orphan_msg_id).await?;
+        assert_eq!(msg.chat_id, DC_CHAT_ID_TRASH);
+
+        // Missing files cannot be repaired.
+        let report = t.check_database(false).await?;
+        assert!(report.orphaned_rows.is_empty());
+        assert_eq!(report.missing_files, vec![file_msg_id]);
+        Ok(())
+    }
+}
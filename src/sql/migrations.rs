@@ -1,7 +1,9 @@
 //! Migrations module.
 
 use anyhow::{Context as _, Result};
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
 
+use crate::blob::deduplicated_blob_sql;
 use crate::config::Config;
 use crate::constants::ShowEmails;
 use crate::context::Context;
@@ -11,9 +13,57 @@
 use crate::tools::EmailAddress;
 
 const DBVERSION: i32 = 68;
+/// Version of the last migration, to be updated when adding a migration.
+const LATEST_VERSION: i32 = 107;
 const VERSION_CFG: &str = "dbversion";
 const TABLES: &str = include_str!("./tables.sql");
 
+/// Schema migrations needed before a database can be used,
+/// see [`Sql::get_migration_plan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationPlan {
+    /// Schema version of the database, `None` if the database does not exist yet.
+    pub db_version: Option<i32>,
+
+    /// Schema version the database is migrated to when it is opened.
+    ///
+    /// Migrations to the versions after `db_version` up to this one run in order.
+    pub latest_version: i32,
+}
+
+impl MigrationPlan {
+    /// Returns true if migrations run when the database is opened.
+    ///
+    /// Databases which do not exist yet are created from scratch,
+    /// there is no data to migrate.
+    pub fn is_needed(&self) -> bool {
+        matches!(self.db_version, Some(version) if version < self.latest_version)
+    }
+}
+
+/// Reads the schema version using a single connection,
+/// `None` if the database has no tables yet.
+fn read_db_version(conn: &Connection) -> Result<Option<i32>> {
+    let config_exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='config'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !config_exists {
+        return Ok(None);
+    }
+    let version: Option<String> = conn
+        .query_row(
+            "SELECT value FROM config WHERE keyname=?",
+            (VERSION_CFG,),
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(Some(
+        version.and_then(|v| v.parse().ok()).unwrap_or_default(),
+    ))
+}
+
 pub async fn run(context: &Context, sql: &Sql) -> Result<(bool, bool, bool, bool)> {
     let mut recalc_fingerprints = false;
     let mut exists_before_update = false;
@@ -711,6 +761,125 @@ pub async fn run(context: &Context, sql: &Sql) -> Result<(bool, bool, bool, bool
         )
         .await?;
     }
+    if dbversion < 101 {
+        // NULL means that the global `delete_server_after` setting is used.
+        sql.execute_migration(
+            "ALTER TABLE chats ADD COLUMN delete_server_after INTEGER DEFAULT NULL",
+            101,
+        )
+        .await?;
+    }
+    if dbversion < 102 {
+        sql.execute_migration(
+            "CREATE TABLE certificate_pins (
+               hostname TEXT PRIMARY KEY,
+               kind INTEGER NOT NULL, -- 0 = certificate, 1 = SPKI
+               sha256 TEXT NOT NULL, -- hex-encoded hash
+               learned INTEGER NOT NULL DEFAULT 0,
+               timestamp INTEGER NOT NULL DEFAULT 0
+             )",
+            102,
+        )
+        .await?;
+    }
+    if dbversion < 103 {
+        sql.execute_migration(
+            "CREATE TABLE pending_notifications (
+               msg_id INTEGER PRIMARY KEY,
+               timestamp INTEGER NOT NULL DEFAULT 0
+             )",
+            103,
+        )
+        .await?;
+    }
+    if dbversion < 104 {
+        // Full-text index of message texts, see the `search` module.
+        // Migrations recreating the `msgs` table have to recreate the triggers.
+        // Existing messages are indexed in the background starting from the highest ID.
+        sql.execute_migration(
+            "CREATE VIRTUAL TABLE msgs_search USING fts5(txt, tokenize='trigram');
+             CREATE TRIGGER msgs_search_insert AFTER INSERT ON msgs BEGIN
+               INSERT INTO msgs_search (rowid, txt) VALUES (new.id, new.txt);
+             END;
+             CREATE TRIGGER msgs_search_update AFTER UPDATE OF txt ON msgs BEGIN
+               DELETE FROM msgs_search WHERE rowid=old.id;
+               INSERT INTO msgs_search (rowid, txt) VALUES (new.id, new.txt);
+             END;
+             CREATE TRIGGER msgs_search_delete AFTER DELETE ON msgs BEGIN
+               DELETE FROM msgs_search WHERE rowid=old.id;
+             END;
+             INSERT INTO config (keyname, value)
+               SELECT 'search_index_backfill_id', IFNULL(MAX(id), 0) FROM msgs WHERE id>9;",
+            104,
+        )
+        .await?;
+    }
+    if dbversion < 105 {
+        // Blobs of existing messages are deduplicated during the next housekeeping,
+        // see `blob::deduplicate_blobs()`.
+        sql.execute_migration(
+            "INSERT INTO config (keyname, value)
+               SELECT 'deduplicate_blobs', 1 WHERE EXISTS (SELECT 1 FROM msgs WHERE id>9);",
+            105,
+        )
+        .await?;
+    }
+    if dbversion < 106 {
+        // Number of messages referencing each deduplicated blob,
+        // blobs are removed once they are not referenced anymore,
+        // see `blob::remove_unreferenced_blobs()`.
+        // Migrations recreating the `msgs` table have to recreate the triggers.
+        // Blobs deduplicated before are named after their hash without a prefix
+        // and are deduplicated again.
+        let old_blob = deduplicated_blob_sql("old.param");
+        let new_blob = deduplicated_blob_sql("new.param");
+        let increment = |blob: &str| {
+            format!(
+                "INSERT INTO blobs (name, refcount) SELECT {blob}, 1 WHERE {blob} IS NOT NULL
+                   ON CONFLICT (name) DO UPDATE SET refcount=refcount+1;"
+            )
+        };
+        let decrement =
+            |blob: &str| format!("UPDATE blobs SET refcount=refcount-1 WHERE name={blob};");
+        sql.execute_migration(
+            &format!(
+                "CREATE TABLE blobs (
+                   name TEXT PRIMARY KEY,
+                   refcount INTEGER NOT NULL DEFAULT 0,
+                   timestamp INTEGER NOT NULL DEFAULT 0);
+                 CREATE TRIGGER blobs_insert AFTER INSERT ON msgs BEGIN
+                   {}
+                 END;
+                 CREATE TRIGGER blobs_update AFTER UPDATE OF param ON msgs BEGIN
+                   {}
+                   {}
+                 END;
+                 CREATE TRIGGER blobs_delete AFTER DELETE ON msgs BEGIN
+                   {}
+                 END;
+                 DELETE FROM config WHERE keyname='deduplicate_blobs';
+                 INSERT INTO config (keyname, value)
+                   SELECT 'deduplicate_blobs', 1 WHERE EXISTS (SELECT 1 FROM msgs WHERE id>9);",
+                increment(&new_blob),
+                decrement(&old_blob),
+                increment(&new_blob),
+                decrement(&old_blob),
+            ),
+            106,
+        )
+        .await?;
+    }
+    if dbversion < 107 {
+        // Messages moved to the Trash folder by Delta Chat and when they were moved there,
+        // see `Imap::cleanup_trash()`.
+        sql.execute_migration(
+            "CREATE TABLE imap_trash (
+               rfc724_mid TEXT PRIMARY KEY,
+               timestamp INTEGER NOT NULL DEFAULT 0);",
+            107,
+        )
+        .await?;
+    }
 
     let new_version = sql
         .get_raw_config_int(VERSION_CFG)
@@ -737,6 +906,37 @@ pub async fn run(context: &Context, sql: &Sql) -> Result<(bool, bool, bool, bool
 }
 
 impl Sql {
+    /// Returns the schema migrations which run when the database is opened,
+    /// without running them.
+    ///
+    /// If the database is not open, `passphrase` is used to read it
+    /// with a separate connection.
+    pub async fn get_migration_plan(&self, passphrase: String) -> Result<MigrationPlan> {
+        let db_version = if self.is_open().await {
+            Some(
+                self.get_raw_config_int(VERSION_CFG)
+                    .await?
+                    .unwrap_or_default(),
+            )
+        } else {
+            // Hold the lock to prevent other thread from opening the database.
+            let _lock = self.pool.write().await;
+            if !self.storage.exists() {
+                None
+            } else {
+                let connection = self.storage.open(OpenFlags::default())?;
+                connection
+                    .pragma_update(None, "key", &passphrase)
+                    .context("failed to set PRAGMA key")?;
+                read_db_version(&connection).context("failed to read database version")?
+            }
+        };
+        Ok(MigrationPlan {
+            db_version,
+            latest_version: LATEST_VERSION,
+        })
+    }
+
     async fn set_db_version(&self, version: i32) -> Result<()> {
         self.set_raw_config_int(VERSION_CFG, version).await?;
         Ok(())
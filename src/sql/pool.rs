@@ -24,6 +24,9 @@ struct InnerPool {
 
     /// Counts the number of available connections.
     semaphore: Arc<Semaphore>,
+
+    /// Total number of connections.
+    size: usize,
 }
 
 impl InnerPool {
@@ -84,10 +87,12 @@ pub struct Pool {
 impl Pool {
     /// Creates a new connection pool.
     pub fn new(connections: Vec<Connection>) -> Self {
-        let semaphore = Arc::new(Semaphore::new(connections.len()));
+        let size = connections.len();
+        let semaphore = Arc::new(Semaphore::new(size));
         let inner = Arc::new(InnerPool {
             connections: Mutex::new(connections),
             semaphore,
+            size,
         });
         Pool { inner }
     }
@@ -106,4 +111,9 @@ pub async fn get(&self) -> Result<PooledConnection> {
         };
         Ok(conn)
     }
+
+    /// Returns the total number of connections, including allocated ones.
+    pub fn size(&self) -> usize {
+        self.inner.size
+    }
 }
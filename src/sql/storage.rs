@@ -0,0 +1,92 @@
+//! Storage backends of the SQL database.
+//!
+//! [`Sql`](super::Sql) runs all queries itself,
+//! a [`SqlStorage`] only decides where the database lives
+//! by opening the connections for the pool.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use rusqlite::{Connection, OpenFlags};
+
+/// Storage of the SQLite database of a context.
+///
+/// Besides [`SqliteFileStorage`], used by default, and [`InMemoryStorage`],
+/// applications can implement this trait to keep the database elsewhere,
+/// e.g. by opening connections with a custom SQLite VFS
+/// using [`Connection::open_with_flags_and_vfs`].
+/// Use [`ContextBuilder::with_storage`](crate::context::ContextBuilder::with_storage)
+/// to create a context with a custom storage.
+pub trait SqlStorage: fmt::Debug + Send + Sync {
+    /// Opens a new connection to the database.
+    ///
+    /// All connections opened by a storage must access the same database
+    /// unless [`SqlStorage::max_connections`] is 1.
+    fn open(&self, flags: OpenFlags) -> Result<Connection>;
+
+    /// Returns true if the database already exists.
+    fn exists(&self) -> bool;
+
+    /// Returns the path of the database file if the database is stored in a regular file.
+    ///
+    /// Backups before migrations and cold storage of old messages
+    /// are only supported if there is a database file.
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Returns the maximum number of connections in the pool, `None` if there is no limit.
+    fn max_connections(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Database stored in a file, the default storage.
+#[derive(Debug, Clone)]
+pub struct SqliteFileStorage {
+    dbfile: PathBuf,
+}
+
+impl SqliteFileStorage {
+    /// Creates a storage using the database file `dbfile`.
+    pub fn new(dbfile: PathBuf) -> Self {
+        Self { dbfile }
+    }
+}
+
+impl SqlStorage for SqliteFileStorage {
+    fn open(&self, flags: OpenFlags) -> Result<Connection> {
+        Ok(Connection::open_with_flags(&self.dbfile, flags)?)
+    }
+
+    fn exists(&self) -> bool {
+        self.dbfile.exists()
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.dbfile)
+    }
+}
+
+/// Database kept in memory.
+///
+/// The data is lost once the database is closed.
+/// Each connection to an in-memory database opens a separate database,
+/// so the pool has a single connection.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryStorage;
+
+impl SqlStorage for InMemoryStorage {
+    fn open(&self, flags: OpenFlags) -> Result<Connection> {
+        Ok(Connection::open_in_memory_with_flags(flags)?)
+    }
+
+    fn exists(&self) -> bool {
+        false
+    }
+
+    fn max_connections(&self) -> Option<usize> {
+        Some(1)
+    }
+}
@@ -410,6 +410,18 @@ pub enum StockMessage {
 
     #[strum(props(fallback = "ℹ️ Account transferred to your second device."))]
     BackupTransferMsgBody = 163,
+
+    #[strum(props(fallback = "%1$s messages from %2$s"))]
+    NotificationMsgsFrom = 164,
+
+    #[strum(props(fallback = "%1$s messages in %2$s"))]
+    NotificationMsgsIn = 165,
+
+    #[strum(props(fallback = "New message"))]
+    NewMsg = 166,
+
+    #[strum(props(fallback = "%1$s new messages"))]
+    NewMsgs = 167,
 }
 
 impl StockMessage {
@@ -972,6 +984,7 @@ pub(crate) async fn videochat_invitation(context: &Context) -> String {
 }
 
 /// Stock string: `You are invited to a video chat, click %1$s to join.`.
+#[cfg(feature = "videochat")]
 pub(crate) async fn videochat_invite_msg_body(context: &Context, url: &str) -> String {
     translated(context, StockMessage::VideochatInviteMsgBody)
         .await
@@ -1268,6 +1281,38 @@ pub(crate) async fn backup_transfer_msg_body(context: &Context) -> String {
     translated(context, StockMessage::BackupTransferMsgBody).await
 }
 
+/// Stock string: `%1$s messages from %2$s`.
+pub(crate) async fn notification_msgs_from(context: &Context, count: usize, name: &str) -> String {
+    translated(context, StockMessage::NotificationMsgsFrom)
+        .await
+        .replace1(&count.to_string())
+        .replace2(name)
+}
+
+/// Stock string: `%1$s messages in %2$s`.
+pub(crate) async fn notification_msgs_in(
+    context: &Context,
+    count: usize,
+    chat_name: &str,
+) -> String {
+    translated(context, StockMessage::NotificationMsgsIn)
+        .await
+        .replace1(&count.to_string())
+        .replace2(chat_name)
+}
+
+/// Stock string: `New message`.
+pub(crate) async fn new_msg(context: &Context) -> String {
+    translated(context, StockMessage::NewMsg).await
+}
+
+/// Stock string: `%1$s new messages`.
+pub(crate) async fn new_msgs(context: &Context, count: usize) -> String {
+    translated(context, StockMessage::NewMsgs)
+        .await
+        .replace1(&count.to_string())
+}
+
 impl Context {
     /// Set the stock string for the [StockMessage].
     ///
@@ -1275,6 +1320,7 @@ pub async fn set_stock_translation(&self, id: StockMessage, stockstring: String)
         self.translated_stockstrings
             .set_stock_translation(id, stockstring)
             .await?;
+        self.summary_cache.clear();
         Ok(())
     }
 
@@ -1326,6 +1372,9 @@ pub async fn set_stock_translation(&self, id: StockMessage, stockstring: String)
         self.stockstrings
             .set_stock_translation(id, stockstring)
             .await?;
+        for context in self.contexts() {
+            context.summary_cache.clear();
+        }
         Ok(())
     }
 }
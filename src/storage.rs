@@ -0,0 +1,691 @@
+//! # Storage usage.
+//!
+//! [`Context::get_storage_usage`] reports how much space the account uses
+//! and how much of it is attributable to each chat,
+//! so users can find out what is eating space
+//! and target deleting old messages at the right chats.
+//!
+//! [`MediaCleanupPolicy`] describes which media is deleted automatically by housekeeping.
+//! [`Context::cleanup_media`] applies a policy, in dry-run mode it only reports
+//! what would be deleted, so the user can confirm before the policy is configured.
+//! The messages stay in their chats, only their files are removed.
+//! Only media of messages which are still on the server is deleted,
+//! so it can be downloaded again.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tokio::fs;
+
+use crate::blob::remove_unreferenced_blobs;
+use crate::chat::ChatId;
+use crate::config::Config;
+use crate::constants::DC_CHAT_ID_LAST_SPECIAL;
+use crate::contact::ContactId;
+use crate::context::Context;
+use crate::download::DownloadState;
+use crate::message::{MsgId, Viewtype};
+use crate::param::{Param, Params};
+use crate::stock_str;
+use crate::tools::time;
+
+/// View types of messages whose files are deleted by [`Context::cleanup_media`].
+///
+/// Webxdc apps are not deleted as they store state of the users.
+const MEDIA_VIEWTYPES: &[Viewtype] = &[
+    Viewtype::Image,
+    Viewtype::Gif,
+    Viewtype::Sticker,
+    Viewtype::Audio,
+    Viewtype::Voice,
+    Viewtype::Video,
+    Viewtype::File,
+];
+
+/// SQL expression estimating the number of bytes a row of the `msgs` table takes in the database,
+/// including webxdc status updates.
+const MSG_DB_BYTES: &str = "IFNULL(LENGTH(CAST(txt AS BLOB)), 0)
+    + IFNULL(LENGTH(CAST(txt_raw AS BLOB)), 0)
+    + IFNULL(LENGTH(CAST(subject AS BLOB)), 0)
+    + IFNULL(LENGTH(CAST(param AS BLOB)), 0)
+    + IFNULL(LENGTH(CAST(mime_headers AS BLOB)), 0)
+    + IFNULL((SELECT SUM(LENGTH(CAST(update_item AS BLOB)))
+              FROM msgs_status_updates WHERE msg_id=msgs.id), 0)";
+
+/// Storage used by the account, see [`Context::get_storage_usage`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Size of the database file including the write-ahead log in bytes.
+    pub db_size: u64,
+
+    /// Size of all files in the blob directory in bytes.
+    pub blobdir_size: u64,
+
+    /// Storage used by the messages of each chat,
+    /// chats using the most storage first.
+    /// Chats without messages are omitted.
+    pub chats: Vec<ChatStorageUsage>,
+}
+
+/// Storage used by the messages of a chat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatStorageUsage {
+    /// ID of the chat.
+    pub chat_id: ChatId,
+
+    /// Number of messages in the chat.
+    pub msg_count: usize,
+
+    /// Estimated number of bytes the messages take in the database.
+    pub db_bytes: u64,
+
+    /// Number of bytes of the files of the messages in the blob directory.
+    ///
+    /// Files referenced by several messages of the chat are counted once,
+    /// files shared with other chats are counted for each of them.
+    pub blob_bytes: u64,
+
+    /// Storage used by the messages of each view type,
+    /// view types using the most storage first.
+    pub viewtypes: Vec<ViewtypeStorageUsage>,
+}
+
+impl ChatStorageUsage {
+    /// Returns the number of bytes attributable to the chat.
+    pub fn total_bytes(&self) -> u64 {
+        self.db_bytes + self.blob_bytes
+    }
+}
+
+/// Storage used by the messages of a chat having a view type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewtypeStorageUsage {
+    /// View type of the messages.
+    pub viewtype: Viewtype,
+
+    /// Number of messages.
+    pub msg_count: usize,
+
+    /// Estimated number of bytes the messages take in the database.
+    pub db_bytes: u64,
+
+    /// Number of bytes of the files of the messages in the blob directory.
+    pub blob_bytes: u64,
+}
+
+impl ViewtypeStorageUsage {
+    /// Returns the number of bytes attributable to the messages.
+    pub fn total_bytes(&self) -> u64 {
+        self.db_bytes + self.blob_bytes
+    }
+}
+
+/// Policy for deleting old media from the device.
+///
+/// Images, videos, audio and other files of messages older than `max_age` are deleted
+/// from chats using more than `min_chat_size` bytes.
+/// Media in "Saved messages" and in the device chat is never deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MediaCleanupPolicy {
+    /// Age in seconds after which media of messages is deleted.
+    pub max_age: i64,
+
+    /// Only chats using more than this number of bytes are cleaned up,
+    /// as reported by [`Context::get_storage_usage`].
+    /// 0 for all chats.
+    pub min_chat_size: u64,
+
+    /// Whether starred messages are kept, see [`star_msgs`](crate::message::star_msgs).
+    pub keep_starred: bool,
+}
+
+impl MediaCleanupPolicy {
+    /// Returns the policy configured with [`Config::MediaCleanupAfter`],
+    /// [`Config::MediaCleanupMinChatSize`] and [`Config::MediaCleanupKeepStarred`],
+    /// `None` if media is not deleted automatically.
+    pub async fn from_config(context: &Context) -> Result<Option<Self>> {
+        let max_age = context.get_config_i64(Config::MediaCleanupAfter).await?;
+        if max_age <= 0 {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            max_age,
+            min_chat_size: context
+                .get_config_u64(Config::MediaCleanupMinChatSize)
+                .await?,
+            keep_starred: context
+                .get_config_bool(Config::MediaCleanupKeepStarred)
+                .await?,
+        }))
+    }
+}
+
+/// Result of [`Context::cleanup_media`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MediaCleanupReport {
+    /// Messages whose media was deleted, or would be deleted in dry-run mode.
+    pub msg_ids: Vec<MsgId>,
+
+    /// Number of bytes of the files which are freed, or would be freed in dry-run mode.
+    ///
+    /// Files shared with messages which are kept are not counted.
+    pub blob_bytes: u64,
+
+    /// Whether this is the result of a dry run and nothing was deleted.
+    pub dry_run: bool,
+}
+
+impl Context {
+    /// Returns the storage used by the account and by each chat.
+    ///
+    /// The sizes of the messages in the database are estimated from the lengths of their contents,
+    /// space used by indices and free pages is not attributed to chats.
+    /// Messages in the trash and other special chats are not reported.
+    ///
+    /// This looks at all messages and files, so it may take a while for large accounts.
+    pub async fn get_storage_usage(&self) -> Result<StorageUsage> {
+        let rows = self
+            .sql
+            .query_map(
+                &format!(
+                    "SELECT chat_id, type, COUNT(*), SUM({MSG_DB_BYTES})
+                     FROM msgs WHERE chat_id>?
+                     GROUP BY chat_id, type"
+                ),
+                (DC_CHAT_ID_LAST_SPECIAL,),
+                |row| {
+                    let chat_id: ChatId = row.get(0)?;
+                    let viewtype: Viewtype = row.get(1)?;
+                    let msg_count: usize = row.get(2)?;
+                    let db_bytes: i64 = row.get(3)?;
+                    Ok((chat_id, viewtype, msg_count, db_bytes))
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        let mut chats: BTreeMap<ChatId, Vec<ViewtypeStorageUsage>> = BTreeMap::new();
+        for (chat_id, viewtype, msg_count, db_bytes) in rows {
+            chats
+                .entry(chat_id)
+                .or_default()
+                .push(ViewtypeStorageUsage {
+                    viewtype,
+                    msg_count,
+                    db_bytes: u64::try_from(db_bytes).unwrap_or_default(),
+                    blob_bytes: 0,
+                });
+        }
+
+        let files = self
+            .sql
+            .query_map(
+                "SELECT chat_id, type, param FROM msgs WHERE chat_id>? AND param LIKE '%f=%'",
+                (DC_CHAT_ID_LAST_SPECIAL,),
+                |row| {
+                    let chat_id: ChatId = row.get(0)?;
+                    let viewtype: Viewtype = row.get(1)?;
+                    let param: String = row.get(2)?;
+                    Ok((chat_id, viewtype, param))
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+        let mut seen = HashSet::new();
+        for (chat_id, viewtype, param) in files {
+            let param: Params = param.parse().unwrap_or_default();
+            let path = match param.get_path(Param::File, self) {
+                Ok(Some(path)) => path,
+                _ => continue,
+            };
+            let size = match fs::metadata(&path).await {
+                Ok(metadata) => metadata.len(),
+                Err(_) => continue,
+            };
+            if !seen.insert((chat_id, path)) {
+                continue;
+            }
+            if let Some(usage) = chats
+                .get_mut(&chat_id)
+                .and_then(|viewtypes| viewtypes.iter_mut().find(|u| u.viewtype == viewtype))
+            {
+                usage.blob_bytes += size;
+            }
+        }
+
+        let mut chats: Vec<ChatStorageUsage> = chats
+            .into_iter()
+            .map(|(chat_id, mut viewtypes)| {
+                viewtypes.sort_by_key(|usage| std::cmp::Reverse(usage.total_bytes()));
+                ChatStorageUsage {
+                    chat_id,
+                    msg_count: viewtypes.iter().map(|usage| usage.msg_count).sum(),
+                    db_bytes: viewtypes.iter().map(|usage| usage.db_bytes).sum(),
+                    blob_bytes: viewtypes.iter().map(|usage| usage.blob_bytes).sum(),
+                    viewtypes,
+                }
+            })
+            .collect();
+        chats.sort_by_key(|usage| std::cmp::Reverse(usage.total_bytes()));
+
+        let dbfile = self.get_dbfile();
+        Ok(StorageUsage {
+            db_size: file_size(dbfile).await + file_size(&Context::derive_walfile(dbfile)).await,
+            blobdir_size: dir_size(self.get_blobdir()).await?,
+            chats,
+        })
+    }
+}
+
+impl Context {
+    /// Deletes media of messages matching `policy` from the device.
+    ///
+    /// The messages stay in their chats, only their files are deleted locally.
+    /// As partially downloaded messages, they become text messages
+    /// with [`DownloadState::Available`] mentioning the size of the deleted file,
+    /// so the media can be downloaded again with [`MsgId::download_full`].
+    /// Media of messages which are not on the server anymore is never deleted
+    /// as it could not be downloaded again.
+    /// Files shared with other messages are kept for them.
+    ///
+    /// If `dry_run` is true, nothing is deleted
+    /// and the report lists the messages whose media would be deleted.
+    pub async fn cleanup_media(
+        &self,
+        policy: &MediaCleanupPolicy,
+        dry_run: bool,
+    ) -> Result<MediaCleanupReport> {
+        let chats: Option<HashSet<ChatId>> = if policy.min_chat_size > 0 {
+            Some(
+                self.get_storage_usage()
+                    .await?
+                    .chats
+                    .into_iter()
+                    .filter(|usage| usage.total_bytes() > policy.min_chat_size)
+                    .map(|usage| usage.chat_id)
+                    .collect(),
+            )
+        } else {
+            None
+        };
+        let self_chat_id = ChatId::lookup_by_contact(self, ContactId::SELF)
+            .await?
+            .unwrap_or_default();
+        let device_chat_id = ChatId::lookup_by_contact(self, ContactId::DEVICE)
+            .await?
+            .unwrap_or_default();
+
+        let rows = self
+            .sql
+            .query_map(
+                "SELECT id, chat_id, type, txt, param FROM msgs
+                 WHERE chat_id>? AND chat_id!=? AND chat_id!=?
+                 AND timestamp<? AND (starred=0 OR ?=0) AND type!=? AND download_state=?
+                 AND EXISTS (SELECT 1 FROM imap
+                             WHERE imap.rfc724_mid=msgs.rfc724_mid AND imap.target=imap.folder)
+                 ORDER BY id",
+                (
+                    DC_CHAT_ID_LAST_SPECIAL,
+                    self_chat_id,
+                    device_chat_id,
+                    time().saturating_sub(policy.max_age),
+                    policy.keep_starred,
+                    Viewtype::Text,
+                    DownloadState::Done,
+                ),
+                |row| {
+                    let msg_id: MsgId = row.get(0)?;
+                    let chat_id: ChatId = row.get(1)?;
+                    let viewtype: Viewtype = row.get(2)?;
+                    let text: String = row.get::<_, Option<String>>(3)?.unwrap_or_default();
+                    let param: String = row.get(4)?;
+                    Ok((msg_id, chat_id, viewtype, text, param))
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?;
+
+        let mut report = MediaCleanupReport {
+            dry_run,
+            ..Default::default()
+        };
+        let mut cleaned = Vec::new();
+        let mut files = HashMap::new();
+        for (msg_id, chat_id, viewtype, text, param) in rows {
+            if !MEDIA_VIEWTYPES.contains(&viewtype)
+                || matches!(&chats, Some(chats) if !chats.contains(&chat_id))
+            {
+                continue;
+            }
+            let mut param: Params = param.parse().unwrap_or_default();
+            let path = match param.get_path(Param::File, self) {
+                Ok(Some(path)) => path,
+                _ => continue,
+            };
+            let size = file_size(&path).await;
+            files.insert(path, size);
+            report.msg_ids.push(msg_id);
+            param.remove(Param::File);
+            cleaned.push((chat_id, msg_id, text, param, size));
+        }
+
+        if !files.is_empty() {
+            let kept_files = self.get_files_of_msgs_except(&report.msg_ids).await?;
+            report.blob_bytes = files
+                .iter()
+                .filter(|(path, _)| !kept_files.contains(*path))
+                .map(|(_, size)| size)
+                .sum();
+        }
+
+        if !dry_run && !cleaned.is_empty() {
+            info!(
+                self,
+                "Deleting media of {} messages to free {} bytes.",
+                cleaned.len(),
+                report.blob_bytes
+            );
+            let mut updates = Vec::with_capacity(cleaned.len());
+            for (_chat_id, msg_id, text, param, size) in &cleaned {
+                // Same text as for partially downloaded messages.
+                let stub = format!(
+                    "[{}]",
+                    stock_str::partial_download_msg_body(
+                        self,
+                        u32::try_from(*size).unwrap_or(u32::MAX)
+                    )
+                    .await
+                );
+                let text = if text.is_empty() {
+                    stub
+                } else {
+                    format!("{text} {stub}")
+                };
+                updates.push((*msg_id, text, param.to_string()));
+            }
+            self.sql
+                .transaction(move |transaction| {
+                    let mut stmt = transaction.prepare(
+                        "UPDATE msgs SET type=?, txt=?, param=?, download_state=? WHERE id=?",
+                    )?;
+                    for (msg_id, text, param) in updates {
+                        stmt.execute((
+                            Viewtype::Text,
+                            text,
+                            param,
+                            DownloadState::Available,
+                            msg_id,
+                        ))?;
+                    }
+                    Ok(())
+                })
+                .await?;
+            for (chat_id, msg_id, ..) in cleaned {
+                self.emit_msgs_changed(chat_id, msg_id);
+            }
+            // Files which are not deduplicated are removed by housekeeping.
+            remove_unreferenced_blobs(self).await?;
+        }
+        Ok(report)
+    }
+
+    /// Returns the paths of the files used by messages other than `msg_ids`,
+    /// including messages in cold storage.
+    async fn get_files_of_msgs_except(&self, msg_ids: &[MsgId]) -> Result<HashSet<PathBuf>> {
+        let msg_ids: HashSet<MsgId> = msg_ids.iter().copied().collect();
+        let mut params: Vec<String> = self
+            .sql
+            .query_map(
+                "SELECT id, param FROM msgs WHERE param LIKE '%f=%'",
+                (),
+                |row| {
+                    let msg_id: MsgId = row.get(0)?;
+                    let param: String = row.get(1)?;
+                    Ok((msg_id, param))
+                },
+                |rows| rows.collect::<Result<Vec<_>, _>>().map_err(Into::into),
+            )
+            .await?
+            .into_iter()
+            .filter(|(msg_id, _)| !msg_ids.contains(msg_id))
+            .map(|(_, param)| param)
+            .collect();
+        params.extend(self.sql.get_cold_stored_params().await?);
+        Ok(params
+            .iter()
+            .filter_map(|param| {
+                let param: Params = param.parse().ok()?;
+                param.get_path(Param::File, self).ok().flatten()
+            })
+            .collect())
+    }
+}
+
+/// Deletes media according to the configured [`MediaCleanupPolicy`], called by housekeeping.
+///
+/// Returns the number of messages whose media was deleted.
+pub(crate) async fn cleanup_media_by_config(context: &Context) -> Result<usize> {
+    match MediaCleanupPolicy::from_config(context).await? {
+        Some(policy) => Ok(context.cleanup_media(&policy, false).await?.msg_ids.len()),
+        None => Ok(0),
+    }
+}
+
+/// Returns the size of the file, 0 if it does not exist.
+async fn file_size(path: &Path) -> u64 {
+    fs::metadata(path)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or_default()
+}
+
+/// Returns the total size of the files in the directory, not looking into subdirectories.
+async fn dir_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let metadata = entry.metadata().await?;
+        if metadata.is_file() {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat;
+    use crate::message::{star_msgs, Message};
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_get_storage_usage() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let usage = t.get_storage_usage().await?;
+        assert!(usage.db_size > 0);
+        assert!(usage.chats.is_empty());
+
+        let bob_chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let claire_chat = t
+            .create_chat_with_contact("Claire", "claire@example.org")
+            .await;
+        chat::send_text_msg(&t, bob_chat.id, "Hi Bob!".to_string()).await?;
+        chat::send_text_msg(&t, claire_chat.id, "Hi Claire!".to_string()).await?;
+
+        let file = t.get_blobdir().join("report.pdf");
+        fs::write(&file, vec![0; 10_000]).await?;
+        for _ in 0..2 {
+            let mut msg = Message::new(Viewtype::File);
+            msg.set_file(file.to_str().unwrap(), None);
+            chat::send_msg(&t, claire_chat.id, &mut msg).await?;
+        }
+
+        let usage = t.get_storage_usage().await?;
+        assert!(usage.blobdir_size >= 10_000);
+        assert_eq!(usage.chats.len(), 2);
+
+        let claire = &usage.chats[0];
+        assert_eq!(claire.chat_id, claire_chat.id);
+        assert_eq!(claire.msg_count, 3);
+        // The file is counted once.
+        assert_eq!(claire.blob_bytes, 10_000);
+        assert_eq!(claire.viewtypes.len(), 2);
+        assert_eq!(claire.viewtypes[0].viewtype, Viewtype::File);
+        assert_eq!(claire.viewtypes[0].msg_count, 2);
+        assert_eq!(claire.viewtypes[0].blob_bytes, 10_000);
+        assert_eq!(claire.viewtypes[1].viewtype, Viewtype::Text);
+        assert_eq!(claire.viewtypes[1].blob_bytes, 0);
+        assert!(claire.viewtypes[1].db_bytes >= "Hi Claire!".len() as u64);
+
+        let bob = &usage.chats[1];
+        assert_eq!(bob.chat_id, bob_chat.id);
+        assert_eq!(bob.msg_count, 1);
+        assert_eq!(bob.blob_bytes, 0);
+        assert!(bob.db_bytes > 0);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_cleanup_media() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        assert_eq!(MediaCleanupPolicy::from_config(&t).await?, None);
+
+        let small_chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let big_chat = t
+            .create_chat_with_contact("Claire", "claire@example.org")
+            .await;
+        let mut msg_ids = Vec::new();
+        for (chat_id, size) in [(small_chat.id, 10), (big_chat.id, 100_000)] {
+            for name in ["a.pdf", "b.pdf"] {
+                let file = t.get_blobdir().join(format!("{size}-{name}"));
+                fs::write(&file, vec![0; size]).await?;
+                let mut msg = Message::new(Viewtype::File);
+                msg.set_file(file.to_str().unwrap(), None);
+                msg_ids.push(chat::send_msg(&t, chat_id, &mut msg).await?);
+            }
+        }
+        let text_msg_id = chat::send_text_msg(&t, big_chat.id, "Hi".to_string()).await?;
+        t.sql
+            .execute("UPDATE msgs SET timestamp=timestamp-100000", ())
+            .await?;
+        t.sql
+            .execute(
+                "INSERT INTO imap (rfc724_mid, folder, uid, target)
+                 SELECT rfc724_mid, 'INBOX', id, 'INBOX' FROM msgs",
+                (),
+            )
+            .await?;
+        star_msgs(&t, &[msg_ids[3]], true).await?;
+        assert!(msg_ids[3].is_starred(&t).await?);
+
+        let policy = MediaCleanupPolicy {
+            max_age: 1000,
+            min_chat_size: 1000,
+            keep_starred: true,
+        };
+        let report = t.cleanup_media(&policy, true).await?;
+        assert_eq!(report.msg_ids, vec![msg_ids[2]]);
+        // The file is deduplicated and still used by the starred message.
+        assert_eq!(report.blob_bytes, 0);
+        assert!(report.dry_run);
+        let msg = Message::load_from_db(&t, msg_ids[2]).await?;
+        assert_eq!(msg.chat_id, big_chat.id);
+
+        t.set_config(Config::MediaCleanupAfter, Some("1000"))
+            .await?;
+        t.set_config(Config::MediaCleanupMinChatSize, Some("1000"))
+            .await?;
+        assert_eq!(MediaCleanupPolicy::from_config(&t).await?, Some(policy));
+        let small_file = Message::load_from_db(&t, msg_ids[0])
+            .await?
+            .get_file(&t)
+            .unwrap();
+        let big_file = Message::load_from_db(&t, msg_ids[2])
+            .await?
+            .get_file(&t)
+            .unwrap();
+        t.sql.execute("UPDATE blobs SET timestamp=0", ()).await?;
+        cleanup_media_by_config(&t).await?;
+
+        // The message is kept, only the media is deleted.
+        let msg = Message::load_from_db(&t, msg_ids[2]).await?;
+        assert_eq!(msg.chat_id, big_chat.id);
+        assert_eq!(msg.get_viewtype(), Viewtype::Text);
+        assert_eq!(msg.download_state(), DownloadState::Available);
+        assert!(msg.get_file(&t).is_none());
+        assert!(msg
+            .get_text()
+            .unwrap()
+            .contains(&stock_str::partial_download_msg_body(&t, 100_000).await));
+        for msg_id in [msg_ids[0], msg_ids[1], msg_ids[3]] {
+            let msg = Message::load_from_db(&t, msg_id).await?;
+            assert_eq!(msg.get_viewtype(), Viewtype::File);
+            assert!(msg.get_file(&t).is_some());
+        }
+        let msg = Message::load_from_db(&t, text_msg_id).await?;
+        assert_eq!(msg.get_text().unwrap(), "Hi");
+        // The starred message has the same file.
+        assert!(big_file.exists());
+
+        // Without size limit and starred messages, all media is deleted.
+        let policy = MediaCleanupPolicy {
+            max_age: 1000,
+            min_chat_size: 0,
+            keep_starred: false,
+        };
+        let report = t.cleanup_media(&policy, false).await?;
+        assert_eq!(report.msg_ids, vec![msg_ids[0], msg_ids[1], msg_ids[3]]);
+        assert_eq!(report.blob_bytes, 100_010);
+        assert!(!report.dry_run);
+        assert!(!small_file.exists());
+        assert!(!big_file.exists());
+
+        // Media is not deleted twice.
+        let report = t.cleanup_media(&policy, false).await?;
+        assert!(report.msg_ids.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_cleanup_media_not_on_server() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat = t.create_chat_with_contact("Bob", "bob@example.net").await;
+        let file = t.get_blobdir().join("report.pdf");
+        fs::write(&file, vec![0; 1000]).await?;
+        let mut msg = Message::new(Viewtype::File);
+        msg.set_file(file.to_str().unwrap(), None);
+        let msg_id = chat::send_msg(&t, chat.id, &mut msg).await?;
+        t.sql
+            .execute("UPDATE msgs SET timestamp=timestamp-100000", ())
+            .await?;
+        let policy = MediaCleanupPolicy {
+            max_age: 1000,
+            min_chat_size: 0,
+            keep_starred: true,
+        };
+
+        // The message is not on the server, so its media could not be downloaded again.
+        let report = t.cleanup_media(&policy, false).await?;
+        assert!(report.msg_ids.is_empty());
+        assert_eq!(report.blob_bytes, 0);
+
+        // Messages planned for deletion from the server are not cleaned either.
+        let rfc724_mid = Message::load_from_db(&t, msg_id).await?.rfc724_mid;
+        t.sql
+            .execute(
+                "INSERT INTO imap (rfc724_mid, folder, uid, target) VALUES (?, 'INBOX', 1, '')",
+                (&rfc724_mid,),
+            )
+            .await?;
+        assert!(t.cleanup_media(&policy, true).await?.msg_ids.is_empty());
+
+        t.sql.execute("UPDATE imap SET target=folder", ()).await?;
+        let report = t.cleanup_media(&policy, false).await?;
+        assert_eq!(report.msg_ids, vec![msg_id]);
+        assert_eq!(report.blob_bytes, 1000);
+        let msg = Message::load_from_db(&t, msg_id).await?;
+        assert_eq!(msg.download_state(), DownloadState::Available);
+        Ok(())
+    }
+}
@@ -14,7 +14,7 @@
 use crate::tools::truncate;
 
 /// Prefix displayed before message and separated by ":" in the chatlist.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SummaryPrefix {
     /// Username.
     Username(String),
@@ -37,7 +37,7 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 }
 
 /// Message summary.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Summary {
     /// Part displayed before ":", such as an username or a string "Draft".
     pub prefix: Option<SummaryPrefix>,
@@ -140,6 +140,7 @@ async fn get_summary_text(&self, context: &Context) -> String {
                 append_text = false;
                 stock_str::videochat_invitation(context).await
             }
+            #[cfg(feature = "webxdc")]
             Viewtype::Webxdc => {
                 append_text = true;
                 self.get_webxdc_info(context)
@@ -147,6 +148,12 @@ async fn get_summary_text(&self, context: &Context) -> String {
                     .map(|info| info.name)
                     .unwrap_or_else(|_| "ErrWebxdcName".to_string())
             }
+            #[cfg(not(feature = "webxdc"))]
+            Viewtype::Webxdc => {
+                append_text = true;
+                self.get_filename()
+                    .unwrap_or_else(|| "ErrWebxdcName".to_string())
+            }
             Viewtype::Text | Viewtype::Unknown => {
                 if self.param.get_cmd() != SystemMessage::LocationOnly {
                     "".to_string()
@@ -5,15 +5,16 @@
 use lettre_email::PartBuilder;
 use serde::{Deserialize, Serialize};
 
-use crate::chat::{Chat, ChatId};
+use crate::chat::{Chat, ChatId, ChatVisibility, MuteDuration};
 use crate::config::Config;
 use crate::constants::Blocked;
-use crate::contact::ContactId;
+use crate::contact::{Contact, ContactId, Origin};
 use crate::context::Context;
 use crate::message::{Message, MsgId, Viewtype};
 use crate::mimeparser::SystemMessage;
+use crate::notifications::QuietHours;
 use crate::param::Param;
-use crate::sync::SyncData::{AddQrToken, DeleteQrToken};
+use crate::sync::SyncData::{AddQrToken, AlterChat, DeleteQrToken};
 use crate::token::Namespace;
 use crate::tools::time;
 use crate::{chat, stock_str, token};
@@ -25,10 +26,29 @@ pub(crate) struct QrTokenData {
     pub(crate) grpid: Option<String>,
 }
 
+/// Identifies a chat on other devices.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum ChatSyncId {
+    /// 1:1 chat, identified by the address of the contact.
+    ContactAddr(String),
+
+    /// Group, mailing list or broadcast list, identified by the group id.
+    Grpid(String),
+}
+
+/// Change of a chat setting to be applied on other devices.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum ChatAction {
+    SetVisibility(ChatVisibility),
+    SetMuted(MuteDuration),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) enum SyncData {
     AddQrToken(QrTokenData),
     DeleteQrToken(QrTokenData),
+    AlterChat { id: ChatSyncId, action: ChatAction },
+    SetQuietHours(Vec<QuietHours>),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -57,7 +77,11 @@ pub(crate) async fn add_sync_item(&self, data: SyncData) -> Result<()> {
 
     /// Adds item and timestamp to the list of items that should be synchronized to other devices.
     /// If device synchronization is disabled, the function does nothing.
-    async fn add_sync_item_with_timestamp(&self, data: SyncData, timestamp: i64) -> Result<()> {
+    pub(crate) async fn add_sync_item_with_timestamp(
+        &self,
+        data: SyncData,
+        timestamp: i64,
+    ) -> Result<()> {
         if !self.is_sync_sending_enabled().await? {
             return Ok(());
         }
@@ -250,6 +274,71 @@ pub(crate) async fn execute_sync_items(&self, items: &SyncItems) -> Result<()> {
                     token::delete(self, Namespace::InviteNumber, &token.invitenumber).await?;
                     token::delete(self, Namespace::Auth, &token.auth).await?;
                 }
+                AlterChat { id, action } => {
+                    self.sync_alter_chat(id, action, item.timestamp).await?
+                }
+                SyncData::SetQuietHours(quiet_hours) => {
+                    self.sync_quiet_hours(quiet_hours, item.timestamp).await?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a chat action received from another device.
+    ///
+    /// The action is ignored if the chat does not exist here
+    /// or if the setting was changed later on this device;
+    /// the newest change wins.
+    async fn sync_alter_chat(
+        &self,
+        id: &ChatSyncId,
+        action: &ChatAction,
+        timestamp: i64,
+    ) -> Result<()> {
+        let chat_id = match id {
+            ChatSyncId::ContactAddr(addr) => {
+                let contact_id =
+                    match Contact::lookup_id_by_addr(self, addr, Origin::Unknown).await? {
+                        Some(contact_id) => contact_id,
+                        None => {
+                            warn!(self, "sync_alter_chat: No contact for addr '{addr}'.");
+                            return Ok(());
+                        }
+                    };
+                match ChatId::lookup_by_contact(self, contact_id).await? {
+                    Some(chat_id) => chat_id,
+                    None => {
+                        warn!(self, "sync_alter_chat: No chat for addr '{addr}'.");
+                        return Ok(());
+                    }
+                }
+            }
+            ChatSyncId::Grpid(grpid) => match chat::get_chat_id_by_grpid(self, grpid).await? {
+                Some((chat_id, _, _)) => chat_id,
+                None => {
+                    warn!(self, "sync_alter_chat: No chat for grpid '{grpid}'.");
+                    return Ok(());
+                }
+            },
+        };
+
+        match action {
+            ChatAction::SetVisibility(visibility) => {
+                if chat_id
+                    .update_timestamp(self, Param::VisibilityTimestamp, timestamp)
+                    .await?
+                {
+                    chat_id.set_visibility_ex(self, false, *visibility).await?;
+                }
+            }
+            ChatAction::SetMuted(duration) => {
+                if chat_id
+                    .update_timestamp(self, Param::MuteTimestamp, timestamp)
+                    .await?
+                {
+                    chat::set_muted_ex(self, false, chat_id, duration.clone()).await?;
+                }
             }
         }
         Ok(())
@@ -450,6 +539,56 @@ async fn test_execute_sync_items() -> Result<()> {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_sync_alter_chat() -> Result<()> {
+        let alice0 = TestContext::new_alice().await;
+        let alice1 = TestContext::new_alice().await;
+        for a in [&alice0, &alice1] {
+            a.set_config_bool(Config::SendSyncMsgs, true).await?;
+        }
+        let bob = TestContext::new_bob().await;
+
+        let a0b_chat_id = alice0.create_chat(&bob).await.id;
+        let a1b_chat_id = alice1.create_chat(&bob).await.id;
+
+        a0b_chat_id
+            .set_visibility(&alice0, ChatVisibility::Archived)
+            .await?;
+        let sent_msg = alice0.pop_sent_msg().await;
+        alice1.recv_msg_opt(&sent_msg).await;
+        let a1b_chat = Chat::load_from_db(&alice1, a1b_chat_id).await?;
+        assert_eq!(a1b_chat.get_visibility(), ChatVisibility::Archived);
+
+        chat::set_muted(&alice0, a0b_chat_id, MuteDuration::Forever).await?;
+        let sent_msg = alice0.pop_sent_msg().await;
+        alice1.recv_msg_opt(&sent_msg).await;
+        assert!(Chat::load_from_db(&alice1, a1b_chat_id).await?.is_muted());
+
+        // Changes older than the last change are ignored.
+        let sync_items = alice1.parse_sync_items(
+            r#"{"items":[
+{"timestamp":1631781316,"data":{"AlterChat":{"id":{"ContactAddr":"bob@example.net"},"action":{"SetVisibility":"Pinned"}}}},
+{"timestamp":1631781316,"data":{"AlterChat":{"id":{"ContactAddr":"bob@example.net"},"action":{"SetMuted":"NotMuted"}}}}
+]}"#
+            .to_string(),
+        )?;
+        alice1.execute_sync_items(&sync_items).await?;
+        let a1b_chat = Chat::load_from_db(&alice1, a1b_chat_id).await?;
+        assert_eq!(a1b_chat.get_visibility(), ChatVisibility::Archived);
+        assert!(a1b_chat.is_muted());
+
+        // Unknown chats are skipped.
+        let sync_items = alice1.parse_sync_items(
+            r#"{"items":[
+{"timestamp":1631781316,"data":{"AlterChat":{"id":{"Grpid":"non-existent"},"action":{"SetVisibility":"Pinned"}}}}
+]}"#
+            .to_string(),
+        )?;
+        alice1.execute_sync_items(&sync_items).await?;
+
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_send_sync_msg() -> Result<()> {
         let alice = TestContext::new_alice().await;
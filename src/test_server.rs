@@ -0,0 +1,148 @@
+//! # In-process test mail server.
+//!
+//! [`TestServer`] keeps a mailbox per address in memory,
+//! so bots and bindings can be tested end to end
+//! without a mail server, Docker or network access.
+//!
+//! Contexts are connected with [`TestServer::connect`],
+//! which configures them without probing any server
+//! and sets the server as their [`Transport`].
+//! Messages sent are stored in the mailboxes of the recipients
+//! and delivered to a context by [`TestServer::fetch`],
+//! which receives them as [`Transport::receive`].
+//!
+//! The server does not speak IMAP or SMTP,
+//! so IO does not have to be started;
+//! outgoing messages are sent with [`Context::send_queued_messages`].
+//!
+//! Available with the `test-server` feature.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::transport::Transport;
+
+/// In-memory mail server shared by contexts of the same process.
+#[derive(Debug, Default)]
+pub struct TestServer {
+    /// Messages not fetched yet, by lowercased address.
+    mailboxes: Mutex<HashMap<String, Vec<Vec<u8>>>>,
+}
+
+impl TestServer {
+    /// Creates a server with empty mailboxes.
+    pub fn new() -> Arc<Self> {
+        Default::default()
+    }
+
+    /// Configures `context` to use `addr` on this server.
+    ///
+    /// The secret key is generated when the first message is sent,
+    /// as for accounts configured on real servers.
+    pub async fn connect(self: &Arc<Self>, context: &Context, addr: &str) -> Result<()> {
+        context.set_config(Config::Addr, Some(addr)).await?;
+        context
+            .set_config(Config::ConfiguredAddr, Some(addr))
+            .await?;
+        context.set_config(Config::Configured, Some("1")).await?;
+        context.set_transport(Some(self.clone()));
+        Ok(())
+    }
+
+    /// Sends queued messages of `context` and delivers them to the mailboxes of the recipients.
+    pub async fn send(&self, context: &Context) -> Result<()> {
+        context.send_queued_messages().await
+    }
+
+    /// Passes all messages in the mailbox of `context` to it
+    /// and returns the number of messages fetched.
+    pub async fn fetch(&self, context: &Context) -> Result<usize> {
+        context.receive_transport_messages().await
+    }
+
+    /// Returns the number of messages waiting in the mailbox of `addr`.
+    pub fn pending(&self, addr: &str) -> usize {
+        self.mailboxes
+            .lock()
+            .expect("Mutex is poisoned")
+            .get(&addr.to_lowercase())
+            .map_or(0, Vec::len)
+    }
+}
+
+impl Transport for TestServer {
+    fn send<'a>(
+        &'a self,
+        _context: &'a Context,
+        recipients: &'a [String],
+        message: &'a [u8],
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut mailboxes = self.mailboxes.lock().expect("Mutex is poisoned");
+            for recipient in recipients {
+                mailboxes
+                    .entry(recipient.to_lowercase())
+                    .or_default()
+                    .push(message.to_vec());
+            }
+            Ok(())
+        })
+    }
+
+    fn receive<'a>(&'a self, context: &'a Context) -> BoxFuture<'a, Result<Vec<Vec<u8>>>> {
+        Box::pin(async move {
+            let addr = context.get_primary_self_addr().await?.to_lowercase();
+            Ok(self
+                .mailboxes
+                .lock()
+                .expect("Mutex is poisoned")
+                .remove(&addr)
+                .unwrap_or_default())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::{self, ChatId};
+    use crate::contact::Contact;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_test_server() -> Result<()> {
+        let server = TestServer::new();
+        let alice = TestContext::new().await;
+        let bob = TestContext::new().await;
+        server.connect(&alice, "alice@example.org").await?;
+        server.connect(&bob, "bob@example.net").await?;
+        assert!(alice.is_configured().await?);
+
+        let contact_id = Contact::create(&alice, "", "Bob@example.net").await?;
+        let chat_id = ChatId::create_for_contact(&alice, contact_id).await?;
+        chat::send_text_msg(&alice, chat_id, "Hello!".to_string()).await?;
+        server.send(&alice).await?;
+        assert_eq!(server.pending("bob@example.net"), 1);
+
+        assert_eq!(server.fetch(&bob).await?, 1);
+        assert_eq!(server.pending("bob@example.net"), 0);
+        let msg = bob.get_last_msg().await;
+        assert_eq!(msg.get_text().as_deref(), Some("Hello!"));
+        assert_eq!(server.fetch(&bob).await?, 0);
+
+        // Bob's reply is encrypted, Bob got Alice's key from the Autocrypt header.
+        msg.chat_id.accept(&bob).await?;
+        chat::send_text_msg(&bob, msg.chat_id, "Hi!".to_string()).await?;
+        server.send(&bob).await?;
+        server.fetch(&alice).await?;
+        let msg = alice.get_last_msg().await;
+        assert_eq!(msg.get_text().as_deref(), Some("Hi!"));
+        assert!(msg.get_showpadlock());
+        Ok(())
+    }
+}
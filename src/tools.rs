@@ -321,6 +321,14 @@ pub fn get_filemeta(buf: &[u8]) -> Result<(u32, u32)> {
     Ok(dimensions)
 }
 
+/// Returns the width and height of the image file at `path`,
+/// reading only as much of the file as needed.
+pub fn get_filemeta_from_file(path: &Path) -> Result<(u32, u32)> {
+    let image = image::io::Reader::open(path)?.with_guessed_format()?;
+    let dimensions = image.into_dimensions()?;
+    Ok(dimensions)
+}
+
 /// Expand paths relative to $BLOBDIR into absolute paths.
 ///
 /// If `path` starts with "$BLOBDIR", replaces it with the blobdir path.
@@ -488,6 +496,16 @@ pub(crate) fn time() -> i64 {
         .as_secs() as i64
 }
 
+/// Returns the current time in milliseconds since the Unix epoch.
+pub(crate) fn time_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+        .try_into()
+        .unwrap_or(i64::MAX)
+}
+
 /// Very simple email address wrapper.
 ///
 /// Represents an email address, right now just the `name@domain` portion.
@@ -0,0 +1,214 @@
+//! # Pluggable transports.
+//!
+//! By default messages are sent over SMTP (or JMAP) and received over IMAP.
+//! A [`Transport`] set with [`Context::set_transport`] replaces SMTP for sending
+//! and is polled for incoming messages in addition to IMAP,
+//! e.g. to experiment with other relay protocols
+//! or to run integration tests without a mail server.
+//!
+//! The outgoing queue and incoming messages are processed as usual
+//! by the IO scheduler if IO is started,
+//! otherwise by calling [`Context::send_queued_messages`]
+//! and [`Context::receive_transport_messages`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, ensure, Result};
+use futures::future::BoxFuture;
+
+use crate::contact::addr_cmp;
+use crate::context::Context;
+use crate::receive_imf::receive_imf;
+use crate::scheduler::InterruptInfo;
+use crate::smtp::{send_smtp_messages, Smtp};
+
+/// Transport used instead of SMTP to send messages and in addition to IMAP to receive them.
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// Sends the rendered MIME `message` to `recipients`.
+    ///
+    /// On error the message stays in the queue and is retried later,
+    /// until the `smtp_max_attempts` limit is reached.
+    fn send<'a>(
+        &'a self,
+        context: &'a Context,
+        recipients: &'a [String],
+        message: &'a [u8],
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// Returns the raw MIME messages received for `context` since the last call.
+    ///
+    /// Returned messages are removed from the transport,
+    /// they are passed to [`receive_imf`] by the caller.
+    /// Transports that have new messages should interrupt the inbox loop
+    /// with [`Context::interrupt_transport_receive`] so they are received without delay.
+    fn receive<'a>(&'a self, context: &'a Context) -> BoxFuture<'a, Result<Vec<Vec<u8>>>>;
+}
+
+impl Context {
+    /// Sets the transport used instead of SMTP, see [`Transport`].
+    /// `None` restores sending over SMTP.
+    pub fn set_transport(&self, transport: Option<Arc<dyn Transport>>) {
+        *self.transport.write().expect("RwLock is poisoned") = transport;
+    }
+
+    pub(crate) fn get_transport(&self) -> Option<Arc<dyn Transport>> {
+        self.transport.read().expect("RwLock is poisoned").clone()
+    }
+
+    /// Sends all queued messages using the transport set with [`Context::set_transport`].
+    ///
+    /// This does not require IO to be started,
+    /// so it can be used to deliver messages in tests step by step.
+    pub async fn send_queued_messages(&self) -> Result<()> {
+        ensure!(self.get_transport().is_some(), "No transport set");
+        send_smtp_messages(self, &mut Smtp::new()).await
+    }
+
+    /// Receives all messages pending in the transport set with [`Context::set_transport`].
+    ///
+    /// Like [`Context::send_queued_messages`], this does not require IO to be started.
+    /// Returns the number of received messages.
+    pub async fn receive_transport_messages(&self) -> Result<usize> {
+        let transport = match self.get_transport() {
+            Some(transport) => transport,
+            None => bail!("No transport set"),
+        };
+        let messages = transport.receive(self).await?;
+        for message in &messages {
+            receive_imf(self, message, false).await?;
+        }
+        Ok(messages.len())
+    }
+
+    /// Wakes up the inbox loop to receive new messages from the transport.
+    pub async fn interrupt_transport_receive(&self) {
+        self.scheduler
+            .interrupt_inbox(InterruptInfo::new(false))
+            .await;
+    }
+}
+
+/// Transport delivering messages to other contexts of the same process.
+///
+/// Sent messages are queued for every added context
+/// whose address is one of the recipients
+/// and returned by [`Transport::receive`] of that context.
+/// Note that contexts using the transport keep each other alive
+/// until they are removed with [`LoopbackTransport::clear`].
+#[derive(Debug, Default)]
+pub struct LoopbackTransport {
+    contexts: Mutex<Vec<Context>>,
+
+    /// Queued messages by context ID.
+    mailboxes: Mutex<HashMap<u32, Vec<Vec<u8>>>>,
+}
+
+impl LoopbackTransport {
+    /// Creates a transport without contexts.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a context receiving messages sent to its address.
+    pub fn add(&self, context: Context) {
+        self.contexts
+            .lock()
+            .expect("Mutex is poisoned")
+            .push(context);
+    }
+
+    /// Removes all contexts and their queued messages.
+    pub fn clear(&self) {
+        self.contexts.lock().expect("Mutex is poisoned").clear();
+        self.mailboxes.lock().expect("Mutex is poisoned").clear();
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn send<'a>(
+        &'a self,
+        context: &'a Context,
+        recipients: &'a [String],
+        message: &'a [u8],
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let contexts = self.contexts.lock().expect("Mutex is poisoned").clone();
+            for receiver in contexts {
+                if receiver.get_id() == context.get_id() {
+                    continue;
+                }
+                let addr = receiver.get_primary_self_addr().await?;
+                if recipients
+                    .iter()
+                    .any(|recipient| addr_cmp(recipient, &addr))
+                {
+                    self.mailboxes
+                        .lock()
+                        .expect("Mutex is poisoned")
+                        .entry(receiver.get_id())
+                        .or_default()
+                        .push(message.to_vec());
+                    receiver.interrupt_transport_receive().await;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn receive<'a>(&'a self, context: &'a Context) -> BoxFuture<'a, Result<Vec<Vec<u8>>>> {
+        Box::pin(async move {
+            Ok(self
+                .mailboxes
+                .lock()
+                .expect("Mutex is poisoned")
+                .remove(&context.get_id())
+                .unwrap_or_default())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat;
+    use crate::message::MessageState;
+    use crate::test_utils::TestContext;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_loopback_transport() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        assert!(alice.send_queued_messages().await.is_err());
+        assert!(alice.receive_transport_messages().await.is_err());
+
+        let transport = Arc::new(LoopbackTransport::new());
+        transport.add(alice.ctx.clone());
+        transport.add(bob.ctx.clone());
+        alice.set_transport(Some(transport.clone()));
+        bob.set_transport(Some(transport.clone()));
+
+        let chat_id = alice.create_chat(&bob).await.id;
+        let msg_id = chat::send_text_msg(&alice, chat_id, "Hello!".to_string()).await?;
+        alice.send_queued_messages().await?;
+        assert_eq!(alice.receive_transport_messages().await?, 0);
+        assert_eq!(bob.receive_transport_messages().await?, 1);
+        assert_eq!(bob.receive_transport_messages().await?, 0);
+
+        let sent = alice.get_last_msg().await;
+        assert_eq!(sent.id, msg_id);
+        assert_eq!(sent.state, MessageState::OutDelivered);
+        let received = bob.get_last_msg().await;
+        assert_eq!(received.text.as_deref(), Some("Hello!"));
+
+        let chat_id = bob.create_chat(&alice).await.id;
+        chat::send_text_msg(&bob, chat_id, "Hi!".to_string()).await?;
+        bob.send_queued_messages().await?;
+        assert_eq!(alice.receive_transport_messages().await?, 1);
+        assert_eq!(alice.get_last_msg().await.text.as_deref(), Some("Hi!"));
+
+        transport.clear();
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@
 use lettre_email::PartBuilder;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use strum_macros::{Display, EnumString};
 use tokio::io::AsyncReadExt;
 
 use crate::chat::Chat;
@@ -24,6 +25,11 @@
 use crate::tools::{create_smeared_timestamp, get_abs_path};
 use crate::{chat, EventType};
 
+mod realtime;
+
+pub(crate) use realtime::RealtimeChannels;
+pub use realtime::{RealtimeTransport, REALTIME_DATA_MAX_BYTES};
+
 /// The current API version.
 /// If `min_api` in manifest.toml is set to a larger value,
 /// the Webxdc's index.html is replaced by an error message.
@@ -66,6 +72,44 @@ struct WebxdcManifest {
 
     /// If the webxdc requests network access.
     request_internet_access: Option<bool>,
+
+    /// Capabilities requested by the webxdc, see [`WebxdcCapability`].
+    /// Unknown capabilities are ignored.
+    request_capabilities: Option<Vec<String>>,
+}
+
+/// Capability a webxdc has to request in the manifest to use it,
+/// e.g. `request_capabilities = ["realtime", "send_to_chat"]`.
+///
+/// Capabilities which are not requested are denied.
+/// Core denies joining realtime channels,
+/// UIs have to deny the capabilities implemented by them.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Display,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WebxdcCapability {
+    /// Exchanging data over realtime channels,
+    /// see [`Context::join_webxdc_realtime_channel`].
+    Realtime,
+
+    /// Passing messages to the UI to be sent to a chat with `sendToChat()`.
+    SendToChat,
+
+    /// Storing more data in the web storage of the webxdc than UIs allow by default.
+    LargeStorage,
 }
 
 /// Parsed information from WebxdcManifest and fallbacks.
@@ -94,6 +138,10 @@ pub struct WebxdcInfo {
     /// It should request access, be encrypted
     /// and sent to self for this.
     pub internet_access: bool,
+
+    /// Capabilities requested in the manifest, sorted.
+    /// UIs may show them before launching the webxdc.
+    pub capabilities: Vec<WebxdcCapability>,
 }
 
 /// Status Update ID.
@@ -740,6 +788,7 @@ pub async fn get_webxdc_info(&self, context: &Context) -> Result<WebxdcInfo> {
                     min_api: None,
                     source_code_url: None,
                     request_internet_access: None,
+                    request_capabilities: None,
                 }
             }
         } else {
@@ -748,6 +797,7 @@ pub async fn get_webxdc_info(&self, context: &Context) -> Result<WebxdcInfo> {
                 min_api: None,
                 source_code_url: None,
                 request_internet_access: None,
+                request_capabilities: None,
             }
         };
 
@@ -763,6 +813,16 @@ pub async fn get_webxdc_info(&self, context: &Context) -> Result<WebxdcInfo> {
             && self.chat_id.is_self_talk(context).await.unwrap_or_default()
             && self.get_showpadlock();
 
+        let mut capabilities = Vec::new();
+        for name in manifest.request_capabilities.unwrap_or_default() {
+            match name.parse::<WebxdcCapability>() {
+                Ok(capability) => capabilities.push(capability),
+                Err(_) => info!(context, "Ignoring unknown webxdc capability {name:?}."),
+            }
+        }
+        capabilities.sort();
+        capabilities.dedup();
+
         Ok(WebxdcInfo {
             name: if let Some(name) = manifest.name {
                 name
@@ -795,8 +855,22 @@ pub async fn get_webxdc_info(&self, context: &Context) -> Result<WebxdcInfo> {
                 "".to_string()
             },
             internet_access,
+            capabilities,
         })
     }
+
+    /// Returns true if the webxdc requested `capability` in the manifest.
+    pub async fn has_webxdc_capability(
+        &self,
+        context: &Context,
+        capability: WebxdcCapability,
+    ) -> Result<bool> {
+        Ok(self
+            .get_webxdc_info(context)
+            .await?
+            .capabilities
+            .contains(&capability))
+    }
 }
 
 #[cfg(test)]
@@ -1868,6 +1942,67 @@ async fn test_parse_webxdc_manifest_request_internet_access() -> Result<()> {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_parse_webxdc_manifest_request_capabilities() -> Result<()> {
+        let result = parse_webxdc_manifest(r#"request_capabilities = "realtime""#.as_bytes());
+        assert!(result.is_err());
+        let manifest = parse_webxdc_manifest(r#"name = "foo""#.as_bytes())?;
+        assert_eq!(manifest.request_capabilities, None);
+        let manifest =
+            parse_webxdc_manifest(r#"request_capabilities = ["realtime", "foo"]"#.as_bytes())?;
+        assert_eq!(
+            manifest.request_capabilities,
+            Some(vec!["realtime".to_string(), "foo".to_string()])
+        );
+        assert_eq!(
+            "send_to_chat".parse::<WebxdcCapability>()?,
+            WebxdcCapability::SendToChat
+        );
+        assert_eq!(WebxdcCapability::LargeStorage.to_string(), "large_storage");
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_webxdc_capabilities() -> Result<()> {
+        let t = TestContext::new_alice().await;
+        let chat_id = create_group_chat(&t, ProtectionStatus::Unprotected, "chat").await?;
+
+        let instance = send_webxdc_instance(&t, chat_id).await?;
+        assert!(instance.get_webxdc_info(&t).await?.capabilities.is_empty());
+        assert!(
+            !instance
+                .has_webxdc_capability(&t, WebxdcCapability::Realtime)
+                .await?
+        );
+        assert!(t.join_webxdc_realtime_channel(instance.id).await.is_err());
+
+        let mut instance = create_webxdc_instance(
+            &t,
+            "with-capabilities.xdc",
+            include_bytes!("../test-data/webxdc/with-capabilities.xdc"),
+        )
+        .await?;
+        send_msg(&t, chat_id, &mut instance).await?;
+        let instance = t.get_last_msg().await;
+        let info = instance.get_webxdc_info(&t).await?;
+        assert_eq!(info.name, "capable app");
+        assert_eq!(
+            info.capabilities,
+            vec![WebxdcCapability::Realtime, WebxdcCapability::SendToChat]
+        );
+        assert!(
+            !instance
+                .has_webxdc_capability(&t, WebxdcCapability::LargeStorage)
+                .await?
+        );
+        t.join_webxdc_realtime_channel(instance.id).await?;
+        assert_eq!(
+            serde_json::to_value(&info)?["capabilities"],
+            serde_json::json!(["realtime", "send_to_chat"])
+        );
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn test_webxdc_min_api_too_large() -> Result<()> {
         let t = TestContext::new_alice().await;
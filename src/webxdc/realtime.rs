@@ -0,0 +1,639 @@
+//! # Realtime channels of webxdc apps.
+//!
+//! Games and collaborative apps exchange data which is only useful
+//! to the peers running the app at the same time, e.g. positions of players or cursors.
+//! Sending such data as status updates would store it forever
+//! and send an email for each update.
+//!
+//! Instead, peers join the realtime channel of a webxdc instance
+//! with [`Context::join_webxdc_realtime_channel`]
+//! and send data with [`Context::send_webxdc_realtime_data`].
+//! Received data is emitted as [`EventType::WebxdcRealtimeData`] and not stored.
+//!
+//! Data is sent over the peer-to-peer [`RealtimeTransport`]
+//! set with [`Context::set_webxdc_realtime_transport`].
+//! Peers advertise their transport addresses with hidden messages in the chat of the instance
+//! when joining the channel.
+//! Without transport, data is sent by email as a fallback:
+//! data sent within 10 seconds is collected into a single hidden message,
+//! so apps sending frequent updates do not flood the chat members with emails.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, ensure, Context as _, Result};
+use base64::Engine as _;
+use futures::future::BoxFuture;
+use lettre_email::mime;
+use lettre_email::PartBuilder;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::task;
+
+use crate::chat;
+use crate::contact::ContactId;
+use crate::context::Context;
+use crate::events::EventType;
+use crate::message::{Message, MsgId, Viewtype};
+use crate::mimeparser::SystemMessage;
+use crate::param::Param;
+use crate::webxdc::WebxdcCapability;
+
+/// Maximum number of bytes sent with one call to [`Context::send_webxdc_realtime_data`].
+pub const REALTIME_DATA_MAX_BYTES: usize = 128 * 1024;
+
+/// Minimum interval between two emails with realtime data of the same channel.
+const EMAIL_FALLBACK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum number of bytes waiting to be sent by email per channel.
+const EMAIL_FALLBACK_MAX_BYTES: usize = 4 * REALTIME_DATA_MAX_BYTES;
+
+/// Maximum number of advertised peers remembered per channel.
+const MAX_PEERS: usize = 32;
+
+/// Peer-to-peer transport for realtime channels.
+///
+/// Channels are identified by topics derived from the webxdc instance,
+/// so all peers of an instance use the same topic.
+/// Data received from peers is passed to [`Context::receive_webxdc_realtime_data`].
+pub trait RealtimeTransport: fmt::Debug + Send + Sync {
+    /// Joins the channel `topic` and connects to the `peers` advertised so far.
+    ///
+    /// Returns the address of this peer,
+    /// which is advertised to the other members of the chat.
+    fn join<'a>(
+        &'a self,
+        context: &'a Context,
+        topic: &'a str,
+        peers: &'a [String],
+    ) -> BoxFuture<'a, Result<String>>;
+
+    /// Connects to `peer` advertised after joining the channel `topic`.
+    fn add_peer<'a>(
+        &'a self,
+        context: &'a Context,
+        topic: &'a str,
+        peer: &'a str,
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// Sends `data` to the peers of the channel `topic`.
+    fn send<'a>(
+        &'a self,
+        context: &'a Context,
+        topic: &'a str,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// Leaves the channel `topic`.
+    fn leave<'a>(&'a self, context: &'a Context, topic: &'a str) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Realtime channels joined by the context.
+#[derive(Debug, Default)]
+pub(crate) struct RealtimeChannels {
+    transport: RwLock<Option<Arc<dyn RealtimeTransport>>>,
+    inner: Mutex<RealtimeChannelsInner>,
+}
+
+#[derive(Debug, Default)]
+struct RealtimeChannelsInner {
+    /// Joined channels by webxdc instance.
+    joined: HashMap<MsgId, Channel>,
+
+    /// Peers advertised in the chat of each webxdc instance,
+    /// also for channels which are not joined yet.
+    peers: HashMap<MsgId, Vec<String>>,
+}
+
+#[derive(Debug)]
+struct Channel {
+    topic: String,
+
+    /// Data waiting to be sent by email.
+    pending: Vec<Vec<u8>>,
+
+    /// Whether a task sending the pending data by email is scheduled.
+    flush_scheduled: bool,
+
+    /// When realtime data was last sent by email.
+    last_email: Option<Instant>,
+}
+
+impl RealtimeChannels {
+    fn inner(&self) -> MutexGuard<'_, RealtimeChannelsInner> {
+        self.inner.lock().expect("Mutex is poisoned")
+    }
+
+    fn topic(&self, instance_msg_id: MsgId) -> Option<String> {
+        self.inner()
+            .joined
+            .get(&instance_msg_id)
+            .map(|channel| channel.topic.clone())
+    }
+
+    /// Queues `data` to be sent by email.
+    ///
+    /// Returns the delay after which the data should be sent
+    /// if no task sending the data is scheduled yet.
+    fn queue(&self, instance_msg_id: MsgId, data: Vec<u8>) -> Result<Option<Duration>> {
+        let mut inner = self.inner();
+        let channel = inner
+            .joined
+            .get_mut(&instance_msg_id)
+            .with_context(|| format!("Realtime channel of {instance_msg_id} is not joined"))?;
+        let pending_bytes: usize = channel.pending.iter().map(Vec::len).sum();
+        ensure!(
+            pending_bytes + data.len() <= EMAIL_FALLBACK_MAX_BYTES,
+            "Too much realtime data waiting to be sent by email"
+        );
+        channel.pending.push(data);
+        if channel.flush_scheduled {
+            return Ok(None);
+        }
+        channel.flush_scheduled = true;
+        Ok(Some(channel.last_email.map_or(Duration::ZERO, |last| {
+            EMAIL_FALLBACK_INTERVAL.saturating_sub(last.elapsed())
+        })))
+    }
+
+    fn take_pending(&self, instance_msg_id: MsgId) -> Vec<Vec<u8>> {
+        match self.inner().joined.get_mut(&instance_msg_id) {
+            Some(channel) => {
+                channel.flush_scheduled = false;
+                channel.last_email = Some(Instant::now());
+                std::mem::take(&mut channel.pending)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Remembers `peer` advertised for the instance.
+    ///
+    /// Returns the topic of the channel if it is joined.
+    fn add_peer(&self, instance_msg_id: MsgId, peer: String) -> Option<String> {
+        let mut inner = self.inner();
+        let peers = inner.peers.entry(instance_msg_id).or_default();
+        if !peers.contains(&peer) {
+            if peers.len() >= MAX_PEERS {
+                peers.remove(0);
+            }
+            peers.push(peer);
+        }
+        inner
+            .joined
+            .get(&instance_msg_id)
+            .map(|channel| channel.topic.clone())
+    }
+}
+
+/// Realtime data and peer addresses as sent by email.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RealtimeEnvelope {
+    /// Transport address of the sender.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    peer: Option<String>,
+
+    /// Base64-encoded data.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    data: Vec<String>,
+}
+
+/// Returns the topic of the realtime channel of the webxdc instance.
+///
+/// The topic is derived from the Message-ID,
+/// which is the same for all members of the chat.
+fn realtime_topic(instance: &Message) -> String {
+    hex::encode(
+        Sha256::new()
+            .chain_update(b"webxdc-realtime:")
+            .chain_update(instance.rfc724_mid.as_bytes())
+            .finalize(),
+    )
+}
+
+impl Context {
+    /// Sets the peer-to-peer transport used for realtime channels, see [`RealtimeTransport`].
+    /// `None` sends realtime data by email.
+    ///
+    /// Channels joined before are not moved to the new transport.
+    pub fn set_webxdc_realtime_transport(&self, transport: Option<Arc<dyn RealtimeTransport>>) {
+        *self
+            .webxdc_realtime
+            .transport
+            .write()
+            .expect("RwLock is poisoned") = transport;
+    }
+
+    fn get_webxdc_realtime_transport(&self) -> Option<Arc<dyn RealtimeTransport>> {
+        self.webxdc_realtime
+            .transport
+            .read()
+            .expect("RwLock is poisoned")
+            .clone()
+    }
+
+    /// Joins the realtime channel of a webxdc instance.
+    /// The webxdc has to request [`WebxdcCapability::Realtime`] in its manifest.
+    ///
+    /// Afterwards, data sent by other peers of the channel
+    /// is emitted as [`EventType::WebxdcRealtimeData`].
+    /// If a [`RealtimeTransport`] is set, the address of this peer
+    /// is advertised to the other members of the chat.
+    /// Joining a channel again does nothing.
+    pub async fn join_webxdc_realtime_channel(&self, instance_msg_id: MsgId) -> Result<()> {
+        let instance = Message::load_from_db(self, instance_msg_id).await?;
+        ensure!(
+            instance.viewtype == Viewtype::Webxdc,
+            "Message {instance_msg_id} is not a webxdc instance"
+        );
+        ensure!(
+            instance
+                .has_webxdc_capability(self, WebxdcCapability::Realtime)
+                .await?,
+            "Webxdc {instance_msg_id} did not request the realtime capability"
+        );
+        if self.webxdc_realtime.topic(instance_msg_id).is_some() {
+            return Ok(());
+        }
+
+        let topic = realtime_topic(&instance);
+        if let Some(transport) = self.get_webxdc_realtime_transport() {
+            let peers = self
+                .webxdc_realtime
+                .inner()
+                .peers
+                .get(&instance_msg_id)
+                .cloned()
+                .unwrap_or_default();
+            let peer = transport.join(self, &topic, &peers).await?;
+            self.send_realtime_envelope(
+                &instance,
+                &RealtimeEnvelope {
+                    peer: Some(peer),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+        self.webxdc_realtime.inner().joined.insert(
+            instance_msg_id,
+            Channel {
+                topic,
+                pending: Vec::new(),
+                flush_scheduled: false,
+                last_email: None,
+            },
+        );
+        info!(self, "Joined realtime channel of {instance_msg_id}.");
+        Ok(())
+    }
+
+    /// Leaves the realtime channel of a webxdc instance.
+    ///
+    /// Data not sent yet is dropped.
+    pub async fn leave_webxdc_realtime_channel(&self, instance_msg_id: MsgId) -> Result<()> {
+        let channel = self.webxdc_realtime.inner().joined.remove(&instance_msg_id);
+        if let Some(channel) = channel {
+            if let Some(transport) = self.get_webxdc_realtime_transport() {
+                transport.leave(self, &channel.topic).await?;
+            }
+            info!(self, "Left realtime channel of {instance_msg_id}.");
+        }
+        Ok(())
+    }
+
+    /// Sends `data` to the peers of the joined realtime channel of a webxdc instance.
+    ///
+    /// Realtime data is not stored and may be lost,
+    /// e.g. if peers are not connected or join the channel later.
+    /// Use [`Context::send_webxdc_status_update`] for data which has to reach all members.
+    /// At most [`REALTIME_DATA_MAX_BYTES`] can be sent at once.
+    pub async fn send_webxdc_realtime_data(
+        &self,
+        instance_msg_id: MsgId,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        ensure!(
+            data.len() <= REALTIME_DATA_MAX_BYTES,
+            "Realtime data exceeds {REALTIME_DATA_MAX_BYTES} bytes"
+        );
+        if let Some(transport) = self.get_webxdc_realtime_transport() {
+            let topic = self
+                .webxdc_realtime
+                .topic(instance_msg_id)
+                .with_context(|| format!("Realtime channel of {instance_msg_id} is not joined"))?;
+            return transport.send(self, &topic, &data).await;
+        }
+
+        if let Some(delay) = self.webxdc_realtime.queue(instance_msg_id, data)? {
+            let context = self.clone();
+            task::spawn(async move {
+                tokio::time::sleep(delay).await;
+                if let Err(err) = context.flush_webxdc_realtime_data(instance_msg_id).await {
+                    warn!(context, "Failed to send realtime data by email: {err:#}.");
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Emits data received by the [`RealtimeTransport`] for the channel `topic`.
+    ///
+    /// Data for channels which are not joined is ignored.
+    pub fn receive_webxdc_realtime_data(&self, topic: &str, data: Vec<u8>) {
+        let instance_msg_id = self
+            .webxdc_realtime
+            .inner()
+            .joined
+            .iter()
+            .find(|(_, channel)| channel.topic == topic)
+            .map(|(instance_msg_id, _)| *instance_msg_id);
+        match instance_msg_id {
+            Some(msg_id) => self.emit_event(EventType::WebxdcRealtimeData { msg_id, data }),
+            None => info!(self, "Ignoring realtime data for channel not joined."),
+        }
+    }
+
+    /// Sends the realtime data waiting to be sent by email.
+    async fn flush_webxdc_realtime_data(&self, instance_msg_id: MsgId) -> Result<()> {
+        let data = self.webxdc_realtime.take_pending(instance_msg_id);
+        if data.is_empty() {
+            return Ok(());
+        }
+        let instance = Message::load_from_db(self, instance_msg_id).await?;
+        let envelope = RealtimeEnvelope {
+            data: data
+                .iter()
+                .map(|data| base64::engine::general_purpose::STANDARD.encode(data))
+                .collect(),
+            ..Default::default()
+        };
+        self.send_realtime_envelope(&instance, &envelope).await
+    }
+
+    async fn send_realtime_envelope(
+        &self,
+        instance: &Message,
+        envelope: &RealtimeEnvelope,
+    ) -> Result<()> {
+        let mut msg = Message {
+            chat_id: instance.chat_id,
+            viewtype: Viewtype::Text,
+            hidden: true,
+            ..Default::default()
+        };
+        msg.param.set_cmd(SystemMessage::WebxdcRealtimeData);
+        msg.param.set(Param::Arg, serde_json::to_string(envelope)?);
+        msg.set_quote(self, Some(instance)).await?;
+        msg.param.remove(Param::GuaranteeE2ee);
+        chat::send_msg(self, instance.chat_id, &mut msg).await?;
+        Ok(())
+    }
+
+    pub(crate) fn build_realtime_data_part(&self, json: &str) -> PartBuilder {
+        PartBuilder::new()
+            .content_type(&"application/json".parse::<mime::Mime>().unwrap())
+            .header((
+                "Content-Disposition",
+                "attachment; filename=\"webxdc-realtime.json\"",
+            ))
+            .body(json)
+    }
+
+    /// Receives realtime data and peer addresses sent by email from receive_imf.
+    ///
+    /// `msg_id` is the received message replying to the webxdc instance.
+    pub(crate) async fn receive_realtime_envelope(
+        &self,
+        from_id: ContactId,
+        msg_id: MsgId,
+        json: &str,
+    ) -> Result<()> {
+        let msg = Message::load_from_db(self, msg_id).await?;
+        let instance = match msg.parent(self).await? {
+            Some(parent) if parent.viewtype == Viewtype::Webxdc => parent,
+            _ => bail!("Realtime data is not sent to a webxdc instance"),
+        };
+        if from_id != ContactId::SELF
+            && !chat::is_contact_in_chat(self, instance.chat_id, from_id).await?
+        {
+            bail!("Realtime data sender is not a chat member");
+        }
+
+        let envelope: RealtimeEnvelope = serde_json::from_str(json)?;
+        if let Some(peer) = envelope.peer {
+            let topic = self.webxdc_realtime.add_peer(instance.id, peer.clone());
+            if let (Some(topic), Some(transport)) = (topic, self.get_webxdc_realtime_transport()) {
+                transport.add_peer(self, &topic, &peer).await?;
+            }
+        }
+        if self.webxdc_realtime.topic(instance.id).is_none() {
+            return Ok(());
+        }
+        for data in envelope.data {
+            match base64::engine::general_purpose::STANDARD.decode(data) {
+                Ok(data) => self.emit_event(EventType::WebxdcRealtimeData {
+                    msg_id: instance.id,
+                    data,
+                }),
+                Err(err) => warn!(self, "Cannot decode realtime data: {err:#}."),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestContext;
+
+    /// Transport recording the calls.
+    #[derive(Debug, Default)]
+    struct RecordingTransport {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl RecordingTransport {
+        fn record(&self, call: String) {
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+
+    impl RealtimeTransport for RecordingTransport {
+        fn join<'a>(
+            &'a self,
+            _context: &'a Context,
+            _topic: &'a str,
+            peers: &'a [String],
+        ) -> BoxFuture<'a, Result<String>> {
+            self.record(format!("join {}", peers.join(",")));
+            Box::pin(async { Ok("peer-address".to_string()) })
+        }
+
+        fn add_peer<'a>(
+            &'a self,
+            _context: &'a Context,
+            _topic: &'a str,
+            peer: &'a str,
+        ) -> BoxFuture<'a, Result<()>> {
+            self.record(format!("add_peer {peer}"));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn send<'a>(
+            &'a self,
+            _context: &'a Context,
+            _topic: &'a str,
+            data: &'a [u8],
+        ) -> BoxFuture<'a, Result<()>> {
+            self.record(format!("send {}", String::from_utf8_lossy(data)));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn leave<'a>(
+            &'a self,
+            _context: &'a Context,
+            _topic: &'a str,
+        ) -> BoxFuture<'a, Result<()>> {
+            self.record("leave".to_string());
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    async fn send_instance(alice: &TestContext, bob: &TestContext) -> Result<(MsgId, MsgId)> {
+        let chat_id = alice.create_chat(bob).await.id;
+        let file = alice.get_blobdir().join("with-capabilities.xdc");
+        tokio::fs::write(
+            &file,
+            include_bytes!("../../test-data/webxdc/with-capabilities.xdc"),
+        )
+        .await?;
+        let mut instance = Message::new(Viewtype::File);
+        instance.set_file(file.to_str().unwrap(), None);
+        let alice_instance_id = chat::send_msg(alice, chat_id, &mut instance).await?;
+        let bob_instance = bob.recv_msg(&alice.pop_sent_msg().await).await;
+        assert_eq!(bob_instance.viewtype, Viewtype::Webxdc);
+        Ok((alice_instance_id, bob_instance.id))
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_realtime_email_fallback() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let (alice_instance_id, bob_instance_id) = send_instance(&alice, &bob).await?;
+        bob.create_chat(&alice).await;
+
+        assert!(alice
+            .send_webxdc_realtime_data(alice_instance_id, b"foo".to_vec())
+            .await
+            .is_err());
+        alice
+            .join_webxdc_realtime_channel(alice_instance_id)
+            .await?;
+        bob.join_webxdc_realtime_channel(bob_instance_id).await?;
+        assert!(alice
+            .send_webxdc_realtime_data(alice_instance_id, vec![0; REALTIME_DATA_MAX_BYTES + 1])
+            .await
+            .is_err());
+
+        alice
+            .send_webxdc_realtime_data(alice_instance_id, b"foo".to_vec())
+            .await?;
+        alice
+            .send_webxdc_realtime_data(alice_instance_id, b"bar".to_vec())
+            .await?;
+        while let Some(sent) = alice.pop_sent_msg_opt(Duration::from_secs(1)).await {
+            bob.recv_msg_opt(&sent).await;
+        }
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            if let EventType::WebxdcRealtimeData { msg_id, data } = bob
+                .evtracker
+                .get_matching(|evt| matches!(evt, EventType::WebxdcRealtimeData { .. }))
+                .await
+            {
+                assert_eq!(msg_id, bob_instance_id);
+                received.push(data);
+            }
+        }
+        received.sort();
+        assert_eq!(received, vec![b"bar".to_vec(), b"foo".to_vec()]);
+
+        // Realtime data is not shown in the chat.
+        let bob_chat_id = Message::load_from_db(&bob, bob_instance_id).await?.chat_id;
+        assert_eq!(
+            bob.sql
+                .count("SELECT COUNT(*) FROM msgs WHERE chat_id=?", (bob_chat_id,))
+                .await?,
+            1
+        );
+
+        alice
+            .leave_webxdc_realtime_channel(alice_instance_id)
+            .await?;
+        assert!(alice
+            .send_webxdc_realtime_data(alice_instance_id, b"foo".to_vec())
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_realtime_transport_signaling() -> Result<()> {
+        let alice = TestContext::new_alice().await;
+        let bob = TestContext::new_bob().await;
+        let (alice_instance_id, bob_instance_id) = send_instance(&alice, &bob).await?;
+        bob.create_chat(&alice).await;
+
+        let alice_transport = Arc::new(RecordingTransport::default());
+        alice.set_webxdc_realtime_transport(Some(alice_transport.clone()));
+        let bob_transport = Arc::new(RecordingTransport::default());
+        bob.set_webxdc_realtime_transport(Some(bob_transport.clone()));
+
+        // Alice advertises her address when joining.
+        alice
+            .join_webxdc_realtime_channel(alice_instance_id)
+            .await?;
+        let sent = alice.pop_sent_msg().await;
+        assert!(sent.payload().contains("webxdc-realtime.json"));
+        bob.recv_msg_opt(&sent).await;
+        assert_eq!(bob.get_last_msg().await.id, bob_instance_id);
+
+        // Bob connects to the advertised address when joining later.
+        bob.join_webxdc_realtime_channel(bob_instance_id).await?;
+        bob.join_webxdc_realtime_channel(bob_instance_id).await?;
+        alice.recv_msg_opt(&bob.pop_sent_msg().await).await;
+        bob.send_webxdc_realtime_data(bob_instance_id, b"hi".to_vec())
+            .await?;
+        assert_eq!(
+            *bob_transport.calls.lock().unwrap(),
+            vec!["join peer-address", "send hi"]
+        );
+        assert_eq!(
+            *alice_transport.calls.lock().unwrap(),
+            vec!["join ", "add_peer peer-address"]
+        );
+
+        let topic = realtime_topic(&Message::load_from_db(&bob, bob_instance_id).await?);
+        assert_eq!(
+            topic,
+            realtime_topic(&Message::load_from_db(&alice, alice_instance_id).await?)
+        );
+        alice.receive_webxdc_realtime_data(&topic, b"hi".to_vec());
+        let evt = alice
+            .evtracker
+            .get_matching(|evt| matches!(evt, EventType::WebxdcRealtimeData { .. }))
+            .await;
+        assert!(matches!(
+            evt,
+            EventType::WebxdcRealtimeData { msg_id, data } if msg_id == alice_instance_id && data == b"hi"
+        ));
+
+        bob.leave_webxdc_realtime_channel(bob_instance_id).await?;
+        assert_eq!(bob_transport.calls.lock().unwrap().last().unwrap(), "leave");
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+//! Replacement for the webxdc module if the `webxdc` feature is disabled.
+//!
+//! Received `.xdc` files are treated as regular files,
+//! received status updates and realtime data are ignored
+//! and webxdc instances cannot be sent.
+
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use deltachat_derive::FromSql;
+use lettre_email::mime;
+use lettre_email::PartBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::contact::ContactId;
+use crate::context::Context;
+use crate::message::MsgId;
+
+/// Suffix used to recognize webxdc files.
+pub const WEBXDC_SUFFIX: &str = "xdc";
+
+/// Status Update ID.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    FromSql,
+    FromPrimitive,
+)]
+pub struct StatusUpdateSerial(u32);
+
+impl StatusUpdateSerial {
+    /// Create a new [StatusUpdateSerial].
+    pub fn new(id: u32) -> StatusUpdateSerial {
+        StatusUpdateSerial(id)
+    }
+
+    /// Gets StatusUpdateSerial as untyped integer.
+    /// Avoid using this outside ffi.
+    pub fn to_u32(self) -> u32 {
+        self.0
+    }
+}
+
+/// Update items as sent on the wire and as stored in the database.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct StatusUpdateItem {
+    pub(crate) payload: Value,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) info: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) document: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) summary: Option<String>,
+}
+
+/// Realtime channels are not available without webxdc support.
+#[derive(Debug, Default)]
+pub(crate) struct RealtimeChannels;
+
+impl Context {
+    pub(crate) async fn is_webxdc_file(&self, _filename: &str, _file: &[u8]) -> Result<bool> {
+        Ok(false)
+    }
+
+    pub(crate) async fn ensure_sendable_webxdc_file(&self, _path: &Path) -> Result<()> {
+        bail!("Webxdc support is disabled in this build, enable the webxdc feature")
+    }
+
+    pub(crate) async fn write_status_update_inner(
+        &self,
+        _instance_id: &MsgId,
+        _status_update_item: StatusUpdateItem,
+    ) -> Result<StatusUpdateSerial> {
+        bail!("Webxdc support is disabled in this build, enable the webxdc feature")
+    }
+
+    pub(crate) async fn flush_status_updates(&self) -> Result<()> {
+        Ok(())
+    }
+
+    pub(crate) fn build_status_update_part(&self, json: &str) -> PartBuilder {
+        PartBuilder::new()
+            .content_type(&"application/json".parse::<mime::Mime>().unwrap())
+            .header((
+                "Content-Disposition",
+                "attachment; filename=\"status-update.json\"",
+            ))
+            .body(json)
+    }
+
+    pub(crate) fn build_realtime_data_part(&self, json: &str) -> PartBuilder {
+        PartBuilder::new()
+            .content_type(&"application/json".parse::<mime::Mime>().unwrap())
+            .header((
+                "Content-Disposition",
+                "attachment; filename=\"webxdc-realtime.json\"",
+            ))
+            .body(json)
+    }
+
+    pub(crate) async fn receive_status_update(
+        &self,
+        _from_id: ContactId,
+        _msg_id: MsgId,
+        _json: &str,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    pub(crate) async fn receive_realtime_envelope(
+        &self,
+        _from_id: ContactId,
+        _msg_id: MsgId,
+        _json: &str,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    pub(crate) async fn render_webxdc_status_update_object(
+        &self,
+        _instance_msg_id: MsgId,
+        _range: Option<(StatusUpdateSerial, StatusUpdateSerial)>,
+    ) -> Result<Option<String>> {
+        Ok(None)
+    }
+}